@@ -0,0 +1,223 @@
+//! Audit log of changes made to installed assets.
+//!
+//! Every sync, upgrade and removal that changes something appends one JSON
+//! record per line to `.aps/audit.log` next to the manifest, capturing who
+//! ran what and how each affected entry's version changed. `aps log` reads
+//! it back.
+
+use crate::error::{ApsError, Result};
+use crate::lockfile::{LockedEntry, Lockfile};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Directory for aps state files
+pub const APS_DIR: &str = ".aps";
+
+/// Audit log filename within the aps state directory
+pub const AUDIT_LOG_FILENAME: &str = "audit.log";
+
+/// A single audit record
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// RFC 3339 timestamp in UTC
+    pub timestamp: String,
+
+    /// User that ran the command
+    pub user: String,
+
+    /// Command that produced this record (sync, upgrade, remove)
+    pub command: String,
+
+    /// Entries whose installed version changed
+    #[serde(default)]
+    pub entries: Vec<AuditChange>,
+}
+
+/// Version change for one entry. `before` is None for newly installed
+/// entries and `after` is None for removed entries.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AuditChange {
+    pub id: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+impl AuditRecord {
+    /// Create a record for `command` describing the changes between two lockfiles
+    pub fn new(command: impl Into<String>, before: &Lockfile, after: &Lockfile) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            user: current_user(),
+            command: command.into(),
+            entries: diff_lockfiles(before, after),
+        }
+    }
+}
+
+/// Get the audit log path relative to the manifest
+pub fn audit_log_path(manifest_path: &Path) -> PathBuf {
+    manifest_path
+        .parent()
+        .map(|p| p.join(APS_DIR))
+        .unwrap_or_else(|| PathBuf::from(APS_DIR))
+        .join(AUDIT_LOG_FILENAME)
+}
+
+/// Append a record to the audit log, creating it if needed
+pub fn append_record(path: &Path, record: &AuditRecord) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| ApsError::io(e, format!("Failed to create directory {:?}", parent)))?;
+    }
+
+    let line = serde_json::to_string(record).map_err(|e| ApsError::AuditLogError {
+        message: format!("Failed to serialize audit record: {}", e),
+    })?;
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| ApsError::io(e, format!("Failed to open audit log at {:?}", path)))?;
+    writeln!(file, "{}", line)
+        .map_err(|e| ApsError::io(e, format!("Failed to write audit log at {:?}", path)))?;
+
+    debug!("Appended audit record to {:?}", path);
+    Ok(())
+}
+
+/// Append a record, logging rather than failing if the write does not succeed.
+/// An audit write failure should not undo a sync that already completed.
+pub fn record(manifest_path: &Path, command: &str, before: &Lockfile, after: &Lockfile) {
+    let record = AuditRecord::new(command, before, after);
+    if let Err(e) = append_record(&audit_log_path(manifest_path), &record) {
+        warn!("Failed to write audit log: {}", e);
+    }
+}
+
+/// Read all records from the audit log (oldest first)
+pub fn read_records(path: &Path) -> Result<Vec<AuditRecord>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| ApsError::io(e, format!("Failed to read audit log at {:?}", path)))?;
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| ApsError::AuditLogError {
+                message: format!("line {}: {}", i + 1, e),
+            })
+        })
+        .collect()
+}
+
+/// Compute per-entry version changes between two lockfiles
+pub fn diff_lockfiles(before: &Lockfile, after: &Lockfile) -> Vec<AuditChange> {
+    let ids: BTreeSet<&String> = before.entries.keys().chain(after.entries.keys()).collect();
+
+    ids.into_iter()
+        .filter_map(|id| {
+            let old = before.entries.get(id).map(entry_version);
+            let new = after.entries.get(id).map(entry_version);
+            (old != new).then(|| AuditChange {
+                id: id.clone(),
+                before: old,
+                after: new,
+            })
+        })
+        .collect()
+}
+
+/// Version identifier for a locked entry: the commit for git sources,
+/// otherwise the content checksum
+fn entry_version(entry: &LockedEntry) -> String {
+    entry
+        .commit
+        .clone()
+        .unwrap_or_else(|| entry.checksum.clone())
+}
+
+/// Best-effort name of the user running aps
+fn current_user() -> String {
+    ["USER", "USERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn lockfile_with(entries: &[(&str, &str)]) -> Lockfile {
+        let mut lockfile = Lockfile::new();
+        for (id, checksum) in entries {
+            lockfile.upsert(
+                id.to_string(),
+                LockedEntry::new_composite(vec![], "./dest", checksum.to_string()),
+            );
+        }
+        lockfile
+    }
+
+    #[test]
+    fn test_diff_lockfiles() {
+        let before = lockfile_with(&[("a", "sha256:1"), ("b", "sha256:2"), ("c", "sha256:3")]);
+        let after = lockfile_with(&[("a", "sha256:1"), ("b", "sha256:9"), ("d", "sha256:4")]);
+
+        let changes = diff_lockfiles(&before, &after);
+        assert_eq!(
+            changes,
+            vec![
+                AuditChange {
+                    id: "b".into(),
+                    before: Some("sha256:2".into()),
+                    after: Some("sha256:9".into()),
+                },
+                AuditChange {
+                    id: "c".into(),
+                    before: Some("sha256:3".into()),
+                    after: None,
+                },
+                AuditChange {
+                    id: "d".into(),
+                    before: None,
+                    after: Some("sha256:4".into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_append_and_read_records() {
+        let temp = TempDir::new().unwrap();
+        let path = audit_log_path(&temp.path().join("aps.yaml"));
+        assert!(read_records(&path).unwrap().is_empty());
+
+        let before = Lockfile::new();
+        let after = lockfile_with(&[("a", "sha256:1")]);
+        append_record(&path, &AuditRecord::new("sync", &before, &after)).unwrap();
+        append_record(&path, &AuditRecord::new("remove", &after, &before)).unwrap();
+
+        let records = read_records(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].command, "sync");
+        assert_eq!(records[0].entries[0].after.as_deref(), Some("sha256:1"));
+        assert_eq!(records[1].command, "remove");
+        assert_eq!(records[1].entries[0].after, None);
+    }
+}
@@ -1,13 +1,74 @@
 use crate::error::{ApsError, Result};
-use chrono::Local;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use tracing::{debug, info};
+use std::process::Command;
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
 
 /// Directory for storing backups
 pub const BACKUP_DIR: &str = ".aps-backups";
 
-/// Create a backup of an existing file or directory
-pub fn create_backup(base_dir: &Path, dest_path: &Path) -> Result<PathBuf> {
+/// Namespace of the refs holding git backups
+pub const BACKUP_REF_PREFIX: &str = "refs/aps/backups/";
+
+/// Where backups are kept
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupMode {
+    /// Copies under `.aps-backups/`
+    #[default]
+    Dir,
+    /// Commits under `refs/aps/backups/` in the project's git repository:
+    /// deduplicated, and kept by `git clean`
+    Git,
+}
+
+/// Index of the backups in the backup directory, one JSON record per line
+pub const BACKUP_INDEX_FILENAME: &str = "index.jsonl";
+
+/// Where a backup came from, as recorded in the backup index
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BackupRecord {
+    /// Name of the backup within the backup directory
+    pub name: String,
+
+    /// Path that was backed up, relative to the manifest directory
+    pub dest: String,
+
+    /// Entry whose sync made the backup
+    pub entry: String,
+
+    /// RFC 3339 timestamp in UTC
+    pub created: String,
+}
+
+/// A backup found in the backup directory or among the backup refs
+#[derive(Debug, Clone)]
+pub struct Backup {
+    /// Path of the backup, or its ref for git backups
+    pub path: PathBuf,
+    pub name: String,
+    /// The index record, absent for backups made before it existed
+    pub record: Option<BackupRecord>,
+    /// When the backup was made (from the index, else the file time)
+    pub created: String,
+    /// Total size in bytes
+    pub size: u64,
+}
+
+/// Create a backup of an existing file or directory, recording which entry
+/// it was made for. Returns the backup path, or the ref of a git backup.
+pub fn create_backup(
+    base_dir: &Path,
+    dest_path: &Path,
+    entry_id: &str,
+    mode: BackupMode,
+) -> Result<PathBuf> {
+    if mode == BackupMode::Git {
+        return create_git_backup(base_dir, dest_path, entry_id);
+    }
     let backup_root = base_dir.join(BACKUP_DIR);
 
     // Create backup directory if it doesn't exist
@@ -21,17 +82,7 @@ pub fn create_backup(base_dir: &Path, dest_path: &Path) -> Result<PathBuf> {
         debug!("Created backup directory at {:?}", backup_root);
     }
 
-    // Generate timestamp-based backup name
-    let timestamp = Local::now().format("%Y-%m-%d-%H%M").to_string();
-
-    // Include parent path components to avoid collisions
-    let relative_path = dest_path
-        .strip_prefix(base_dir)
-        .unwrap_or(dest_path)
-        .to_string_lossy()
-        .replace(['/', '\\'], "-");
-
-    let backup_name = format!("{}-{}", relative_path, timestamp);
+    let backup_name = backup_name(base_dir, dest_path);
     let backup_path = backup_root.join(&backup_name);
 
     // Copy the content to backup location
@@ -44,9 +95,255 @@ pub fn create_backup(base_dir: &Path, dest_path: &Path) -> Result<PathBuf> {
         info!("Backed up directory to {:?}", backup_path);
     }
 
+    let record = BackupRecord {
+        name: backup_name,
+        dest: relative_dest(base_dir, dest_path),
+        entry: entry_id.to_string(),
+        created: Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    };
+    // The backup itself exists either way; a missing record only loses provenance
+    if let Err(e) = append_record(&backup_root, &record) {
+        warn!("Failed to record backup in index: {}", e);
+    }
+
     Ok(backup_path)
 }
 
+/// Timestamped backup name for `dest_path`, including its parent path
+/// components to avoid collisions
+fn backup_name(base_dir: &Path, dest_path: &Path) -> String {
+    let timestamp = Local::now().format("%Y-%m-%d-%H%M").to_string();
+    let relative_path = relative_dest(base_dir, dest_path).replace('/', "-");
+    format!("{}-{}", relative_path, timestamp)
+}
+
+/// `dest_path` relative to the manifest directory, with `/` separators
+fn relative_dest(base_dir: &Path, dest_path: &Path) -> String {
+    dest_path
+        .strip_prefix(base_dir)
+        .unwrap_or(dest_path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Run git in `dir` and return its trimmed output
+fn git(dir: &Path, args: &[&str], index: Option<&Path>) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C").arg(dir).args(args);
+    if let Some(index) = index {
+        cmd.env("GIT_INDEX_FILE", index);
+    }
+    let output = cmd.output().map_err(|e| ApsError::GitError {
+        message: format!("Failed to execute git {}: {}", args[0], e),
+    })?;
+    if !output.status.success() {
+        return Err(ApsError::GitError {
+            message: format!(
+                "git {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Back up `dest_path` as a commit under `refs/aps/backups/`. The content is
+/// staged in a temporary index, so the project's own index is untouched.
+fn create_git_backup(base_dir: &Path, dest_path: &Path, entry_id: &str) -> Result<PathBuf> {
+    let staging =
+        tempfile::tempdir().map_err(|e| ApsError::io(e, "Failed to create temporary git index"))?;
+    let index = staging.path().join("index");
+    let dest = relative_dest(base_dir, dest_path);
+    let name = backup_name(base_dir, dest_path);
+
+    git(base_dir, &["add", "--force", "--", &dest], Some(&index))?;
+    let tree = git(base_dir, &["write-tree"], Some(&index))?;
+    let message = format!(
+        "aps backup of {}\n\nEntry: {}\nDest: {}\n",
+        dest, entry_id, dest
+    );
+    // Backups are made by aps, not by whoever happens to run it, and never
+    // wait on a signing prompt
+    let commit = git(
+        base_dir,
+        &[
+            "-c",
+            "user.name=aps",
+            "-c",
+            "user.email=aps@localhost",
+            "-c",
+            "commit.gpgsign=false",
+            "commit-tree",
+            &tree,
+            "-m",
+            &message,
+        ],
+        None,
+    )?;
+    let reference = format!("{}{}", BACKUP_REF_PREFIX, name);
+    git(base_dir, &["update-ref", &reference, &commit], None)?;
+    info!("Backed up {:?} to {}", dest_path, reference);
+
+    Ok(PathBuf::from(reference))
+}
+
+/// Backups stored as git refs. Empty outside a git repository.
+fn list_git_backups(base_dir: &Path) -> Vec<Backup> {
+    let Ok(refs) = git(
+        base_dir,
+        &[
+            "for-each-ref",
+            "--format=%(refname)%00%(creatordate:iso-strict)%00%(contents:body)%00",
+            BACKUP_REF_PREFIX,
+        ],
+        None,
+    ) else {
+        return Vec::new();
+    };
+
+    let fields: Vec<&str> = refs.split('\0').map(|f| f.trim()).collect();
+    fields
+        .chunks_exact(3)
+        .map(|fields| {
+            let (reference, created, body) = (fields[0], fields[1], fields[2]);
+            let trailer = |key: &str| {
+                body.lines()
+                    .find_map(|line| line.strip_prefix(key))
+                    .map(|value| value.trim().to_string())
+            };
+            let name = reference
+                .strip_prefix(BACKUP_REF_PREFIX)
+                .unwrap_or(reference)
+                .to_string();
+            let record = match (trailer("Entry:"), trailer("Dest:")) {
+                (Some(entry), Some(dest)) => Some(BackupRecord {
+                    name: name.clone(),
+                    dest,
+                    entry,
+                    created: created.to_string(),
+                }),
+                _ => None,
+            };
+            let size = git(base_dir, &["ls-tree", "-r", "-l", reference], None)
+                .map(|tree| {
+                    tree.lines()
+                        .filter_map(|line| line.split_whitespace().nth(3)?.parse::<u64>().ok())
+                        .sum()
+                })
+                .unwrap_or(0);
+            Backup {
+                path: PathBuf::from(reference),
+                name,
+                record,
+                created: created.to_string(),
+                size,
+            }
+        })
+        .collect()
+}
+
+fn append_record(backup_root: &Path, record: &BackupRecord) -> std::io::Result<()> {
+    let line = serde_json::to_string(record)?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(backup_root.join(BACKUP_INDEX_FILENAME))?;
+    writeln!(file, "{}", line)
+}
+
+/// Records of the backup index, oldest first. Unreadable lines are skipped.
+fn read_index(backup_root: &Path) -> Vec<BackupRecord> {
+    std::fs::read_to_string(backup_root.join(BACKUP_INDEX_FILENAME))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// List the backups under the manifest directory and in its git
+/// repository, newest first
+pub fn list_backups(base_dir: &Path) -> Result<Vec<Backup>> {
+    let mut backups = list_git_backups(base_dir);
+    let backup_root = base_dir.join(BACKUP_DIR);
+    let index = read_index(&backup_root);
+
+    let dir_items: Vec<std::fs::DirEntry> = if backup_root.is_dir() {
+        std::fs::read_dir(&backup_root)
+            .map_err(|e| ApsError::io(e, format!("Failed to read directory {:?}", backup_root)))?
+            .collect::<std::io::Result<_>>()
+            .map_err(|e| ApsError::io(e, "Failed to read directory entry"))?
+    } else {
+        Vec::new()
+    };
+    for item in dir_items {
+        let name = item.file_name().to_string_lossy().into_owned();
+        if name == BACKUP_INDEX_FILENAME {
+            continue;
+        }
+        let path = item.path();
+        // A name reused within the same minute keeps the latest record
+        let record = index.iter().rev().find(|r| r.name == name).cloned();
+        let created = match record {
+            Some(ref record) => record.created.clone(),
+            None => item
+                .metadata()
+                .and_then(|m| m.modified())
+                .map(|time| {
+                    DateTime::<Utc>::from(time).to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+                })
+                .unwrap_or_default(),
+        };
+        backups.push(Backup {
+            size: backup_size(&path),
+            path,
+            name,
+            record,
+            created,
+        });
+    }
+    backups.sort_by(|a, b| b.created.cmp(&a.created).then(a.name.cmp(&b.name)));
+    Ok(backups)
+}
+
+/// Total size of the files in a backup
+fn backup_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.metadata().ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+impl Backup {
+    /// Whether the backup is stored as a git ref
+    pub fn is_git(&self) -> bool {
+        self.path.starts_with(BACKUP_REF_PREFIX)
+    }
+
+    /// Whether this is a backup of `path` (relative to the manifest
+    /// directory) or of something under it
+    pub fn is_of(&self, path: &str) -> bool {
+        let path = path
+            .strip_prefix("./")
+            .unwrap_or(path)
+            .trim_end_matches('/');
+        match self.record {
+            Some(ref record) => {
+                record.dest == path
+                    || record
+                        .dest
+                        .strip_prefix(path)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            }
+            // Older backups only have the flattened path in their name
+            None => self.name.starts_with(&path.replace('/', "-")),
+        }
+    }
+}
+
 /// Recursively copy a directory
 fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
     std::fs::create_dir_all(dst)
@@ -59,6 +356,11 @@ fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
         let src_path = entry.path();
         let dst_path = dst.join(entry.file_name());
 
+        // A link that doesn't resolve has no content to back up
+        if !src_path.exists() {
+            debug!("Skipping dangling symlink {:?} in backup", src_path);
+            continue;
+        }
         if src_path.is_dir() {
             copy_dir_recursive(&src_path, &dst_path)?;
         } else {
@@ -138,6 +440,34 @@ mod tests {
     use std::fs;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_list_backups_reads_provenance() {
+        let temp = tempdir().unwrap();
+        let dest = temp.path().join(".cursor/rules");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("a.md"), "12345").unwrap();
+        create_backup(temp.path(), &dest, "rules", BackupMode::Dir).unwrap();
+        // A backup made before the index existed
+        fs::write(
+            temp.path()
+                .join(BACKUP_DIR)
+                .join("AGENTS.md-2024-01-01-0000"),
+            "old",
+        )
+        .unwrap();
+
+        let backups = list_backups(temp.path()).unwrap();
+        assert_eq!(backups.len(), 2);
+        let rules = backups.iter().find(|b| b.record.is_some()).unwrap();
+        assert_eq!(rules.record.as_ref().unwrap().entry, "rules");
+        assert_eq!(rules.size, 5);
+        assert!(rules.is_of(".cursor"));
+        assert!(rules.is_of("./.cursor/rules/"));
+        assert!(!rules.is_of(".cursor/rul"));
+        let old = backups.iter().find(|b| b.record.is_none()).unwrap();
+        assert!(old.is_of("AGENTS.md"));
+    }
+
     #[test]
     fn test_is_aps_managed_dir_with_only_symlinks() {
         let temp = tempdir().unwrap();
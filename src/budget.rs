@@ -0,0 +1,103 @@
+//! Size budgets for composed outputs (`max_tokens` / `max_bytes`).
+//!
+//! Oversized instruction files quietly degrade agent performance, so
+//! composite entries can cap their output. Token counts are an estimate
+//! (about four bytes per token for English prose and code), not a tokenizer.
+
+use crate::compose::ComposedSource;
+use crate::manifest::Entry;
+
+/// Approximate bytes per token used for estimates
+const BYTES_PER_TOKEN: usize = 4;
+
+/// How many of the largest fragments to list when over budget
+const LARGEST_FRAGMENTS_SHOWN: usize = 3;
+
+/// Estimate the number of tokens in `text`
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(BYTES_PER_TOKEN)
+}
+
+/// Describe how a composed output exceeds the entry's budget, listing the
+/// largest fragments. Returns None when within budget.
+pub fn check_budget(entry: &Entry, content: &str, sources: &[ComposedSource]) -> Option<String> {
+    let mut exceeded = Vec::new();
+
+    if let Some(max_tokens) = entry.max_tokens {
+        let tokens = estimate_tokens(content);
+        if tokens > max_tokens {
+            exceeded.push(format!("~{} tokens (max_tokens: {})", tokens, max_tokens));
+        }
+    }
+    if let Some(max_bytes) = entry.max_bytes {
+        if content.len() > max_bytes {
+            exceeded.push(format!(
+                "{} bytes (max_bytes: {})",
+                content.len(),
+                max_bytes
+            ));
+        }
+    }
+    if exceeded.is_empty() {
+        return None;
+    }
+
+    let mut largest: Vec<&ComposedSource> = sources.iter().collect();
+    largest.sort_by_key(|s| std::cmp::Reverse(s.content.len()));
+    let fragments = largest
+        .iter()
+        .take(LARGEST_FRAGMENTS_SHOWN)
+        .map(|s| {
+            format!(
+                "{} (~{} tokens)",
+                s.path.display(),
+                estimate_tokens(&s.content)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Some(format!(
+        "output is {}; largest fragments: {}",
+        exceeded.join(" and "),
+        fragments
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn test_check_budget() {
+        let sources = vec![
+            ComposedSource::fragment("small.md", &"x".repeat(100)),
+            ComposedSource::fragment("huge.md", &"x".repeat(3000)),
+            ComposedSource::fragment("medium.md", &"x".repeat(900)),
+            ComposedSource::fragment("tiny.md", &"x".repeat(10)),
+        ];
+        let content = "x".repeat(4010);
+
+        let mut entry = Entry {
+            max_tokens: Some(2000),
+            ..Default::default()
+        };
+        assert!(check_budget(&entry, &content, &sources).is_none());
+
+        entry.max_tokens = Some(1000);
+        entry.max_bytes = Some(4000);
+        let message = check_budget(&entry, &content, &sources).unwrap();
+        assert_eq!(
+            message,
+            "output is ~1003 tokens (max_tokens: 1000) and 4010 bytes (max_bytes: 4000); \
+             largest fragments: huge.md (~750 tokens), medium.md (~225 tokens), small.md (~25 tokens)"
+        );
+    }
+}
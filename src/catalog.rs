@@ -7,16 +7,29 @@
 //! - cursor_hooks: One entry per hook script
 //! - cursor_skills_root: One entry per skill folder
 //! - agent_skill: One entry per skill folder
+//! - file: One entry for the file
+//! - dir: One entry per file (recursive)
+//!
+//! A catalog can also be generated by scanning a repository of assets with
+//! no manifest: skill folders, Cursor rules and AGENTS.md files or partials.
 
 use crate::error::{ApsError, Result};
-use crate::manifest::{AssetKind, Entry, Manifest};
+use crate::frontmatter;
+use crate::manifest::{AssetKind, Entry, Manifest, Source};
+use crate::mdc::mdc_file_name;
+use crate::prompts::{is_prompt_file, read_prompt_info};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use tracing::{debug, info, warn};
+use walkdir::{DirEntry, WalkDir};
 
 /// Default catalog filename
 pub const CATALOG_FILENAME: &str = "aps.catalog.yaml";
 
+/// Directories never scanned for assets
+const SKIPPED_DIRS: &[&str] = &[".git", "node_modules", "target"];
+
 /// The catalog structure containing all enumerated assets
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Catalog {
@@ -24,11 +37,35 @@ pub struct Catalog {
     #[serde(default = "default_version")]
     pub version: u32,
 
+    /// Semver release of the catalog's contents, bumped on publish
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<String>,
+
+    /// Where `aps catalog publish` sends the catalog
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publish: Option<PublishTarget>,
+
     /// List of catalog entries
     #[serde(default)]
     pub entries: Vec<CatalogEntry>,
 }
 
+/// Where a catalog is published
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PublishTarget {
+    /// Git repository, HTTP(S) URL to upload to with PUT (e.g. a presigned
+    /// S3 URL), or `s3://bucket/key`
+    pub to: String,
+
+    /// Branch to push to, for git repositories (default: main)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+
+    /// Path of the catalog in the git repository (default: aps.catalog.yaml)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
 fn default_version() -> u32 {
     1
 }
@@ -37,6 +74,8 @@ impl Default for Catalog {
     fn default() -> Self {
         Self {
             version: default_version(),
+            release: None,
+            publish: None,
             entries: Vec::new(),
         }
     }
@@ -60,6 +99,37 @@ pub struct CatalogEntry {
     /// Short description extracted from the asset file (if available)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub short_description: Option<String>,
+
+    /// Keywords for search, e.g. `tag:rust` in a query
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// Released versions of the asset, each pinned to a source ref
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<CatalogVersion>,
+
+    /// Where the asset comes from, so it can be added to a manifest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Source>,
+
+    /// Catalog the entry was read from, when several catalogs are merged
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub catalog: Option<String>,
+}
+
+/// A released version of a catalog entry
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CatalogVersion {
+    /// Semantic version, e.g. `1.2.0`
+    pub version: String,
+
+    /// Git ref (tag, branch or commit) of the source at this version
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+
+    /// What changed in this version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changelog: Option<String>,
 }
 
 impl Catalog {
@@ -77,7 +147,6 @@ impl Catalog {
     }
 
     /// Load a catalog from disk
-    #[allow(dead_code)] // Public API for future catalog commands
     pub fn load(path: &Path) -> Result<Self> {
         if !path.exists() {
             return Err(ApsError::CatalogNotFound);
@@ -108,11 +177,30 @@ impl Catalog {
         Ok(())
     }
 
+    /// Merge catalogs listed in order of precedence. An asset id taken by an
+    /// earlier catalog hides the same id in later ones, and each entry
+    /// records which catalog it came from.
+    pub fn merge(catalogs: Vec<(String, Catalog)>) -> Self {
+        let mut merged = Catalog::new();
+        let mut seen = HashSet::new();
+        for (label, catalog) in catalogs {
+            for mut entry in catalog.entries {
+                if !seen.insert(entry.id.clone()) {
+                    debug!("Catalog {} entry '{}' is shadowed", label, entry.id);
+                    continue;
+                }
+                entry.catalog = Some(label.clone());
+                merged.entries.push(entry);
+            }
+        }
+        merged
+    }
+
     /// Generate a catalog from a manifest by enumerating all individual assets
     pub fn generate_from_manifest(manifest: &Manifest, manifest_dir: &Path) -> Result<Self> {
         let mut catalog = Catalog::new();
 
-        for entry in &manifest.entries {
+        for entry in &manifest.expanded_entries() {
             let entries = enumerate_entry_assets(entry, manifest_dir)?;
             catalog.entries.extend(entries);
         }
@@ -125,6 +213,412 @@ impl Catalog {
 
         Ok(catalog)
     }
+
+    /// Generate a catalog by scanning a directory of assets: folders with a
+    /// SKILL.md, Cursor rules (`.mdc` files, or markdown under a `rules`
+    /// directory) and AGENTS.md files or partials (markdown under an
+    /// `agents` or `partials` directory). Entry ids are paths in `root`,
+    /// and entry sources are `source` (which points at `root`) narrowed to
+    /// the asset.
+    pub fn generate_from_dir(root: &Path, source: &Source) -> Result<Self> {
+        if !root.is_dir() {
+            return Err(ApsError::SourcePathNotFound {
+                path: root.to_path_buf(),
+            });
+        }
+
+        let mut catalog = Catalog::new();
+        let mut walker = WalkDir::new(root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !is_skipped_dir(e));
+        while let Some(entry) = walker.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let path = entry.path();
+            let relative = path
+                .strip_prefix(root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/");
+
+            if entry.file_type().is_dir() {
+                if path.join("SKILL.md").is_file() {
+                    let mut skill = scanned_skill(path, &relative);
+                    skill.source = source_at(source, &relative);
+                    catalog.entries.push(skill);
+                    // Everything in a skill folder belongs to the skill
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+            if let Some(kind) = scanned_file_kind(&relative) {
+                let mut file = scanned_file(path, &relative, kind);
+                file.source = source_at(source, &relative);
+                catalog.entries.push(file);
+            }
+        }
+
+        info!(
+            "Generated catalog with {} entries from {:?}",
+            catalog.entries.len(),
+            root
+        );
+        Ok(catalog)
+    }
+}
+
+/// `source` narrowed to `relative` inside it (the whole source when empty)
+fn source_at(source: &Source, relative: &str) -> Option<Source> {
+    let join = |path: &Option<String>| match path.as_deref() {
+        _ if relative.is_empty() => path.clone(),
+        Some(path) if !path.is_empty() => {
+            Some(format!("{}/{}", path.trim_end_matches('/'), relative))
+        }
+        _ => Some(relative.to_string()),
+    };
+    match source {
+        Source::Git {
+            repo,
+            r#ref,
+            shallow,
+            path,
+        } => Some(Source::Git {
+            repo: repo.clone(),
+            r#ref: r#ref.clone(),
+            shallow: *shallow,
+            path: join(path),
+        }),
+        Source::Filesystem {
+            root,
+            symlink,
+            path,
+        } => Some(Source::Filesystem {
+            root: root.clone(),
+            symlink: *symlink,
+            path: join(path),
+        }),
+        Source::Unsupported { .. } => None,
+    }
+}
+
+fn is_skipped_dir(entry: &DirEntry) -> bool {
+    entry.file_type().is_dir()
+        && SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref())
+}
+
+/// The kind of a scanned file, judged by its name and the directories it
+/// sits in
+fn scanned_file_kind(relative: &str) -> Option<AssetKind> {
+    let (dirs, name) = relative.rsplit_once('/').unwrap_or(("", relative));
+    let in_dir = |names: &[&str]| dirs.split('/').any(|dir| names.contains(&dir));
+    let markdown = name.ends_with(".md");
+
+    if name.ends_with(".mdc") || (markdown && in_dir(&["rules"])) {
+        Some(AssetKind::CursorRules)
+    } else if name.eq_ignore_ascii_case("AGENTS.md")
+        || (markdown && in_dir(&["agents", "partials"]))
+    {
+        Some(AssetKind::AgentsMd)
+    } else {
+        None
+    }
+}
+
+/// Catalog entry for a scanned skill folder, named by its SKILL.md
+fn scanned_skill(folder_path: &Path, relative: &str) -> CatalogEntry {
+    let folder_name = folder_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = std::fs::read_to_string(folder_path.join("SKILL.md"))
+        .ok()
+        .and_then(|content| frontmatter::parse(&content))
+        .and_then(|fields| frontmatter::get_str(&fields, "name").map(str::to_string))
+        .unwrap_or_else(|| folder_name.clone());
+
+    CatalogEntry {
+        id: if relative.is_empty() {
+            folder_name.clone()
+        } else {
+            relative.to_string()
+        },
+        name,
+        kind: AssetKind::AgentSkill,
+        destination: format!(
+            "./{}",
+            AssetKind::AgentSkill
+                .default_dest()
+                .join(&folder_name)
+                .display()
+        ),
+        short_description: extract_agent_skill_description(folder_path),
+        tags: Vec::new(),
+        versions: Vec::new(),
+        source: None,
+        catalog: None,
+    }
+}
+
+/// Catalog entry for a scanned rule or AGENTS.md file, named by its
+/// frontmatter `name` or first heading
+fn scanned_file(path: &Path, relative: &str, kind: AssetKind) -> CatalogEntry {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let content = std::fs::read_to_string(path).unwrap_or_default();
+    let name = frontmatter::parse(&content)
+        .and_then(|fields| frontmatter::get_str(&fields, "name").map(str::to_string))
+        .or_else(|| first_heading(&content))
+        .unwrap_or_else(|| file_name.clone());
+    let destination = match kind {
+        AssetKind::CursorRules => kind.default_dest().join(&file_name),
+        _ => kind.default_dest(),
+    };
+
+    CatalogEntry {
+        id: relative.to_string(),
+        name,
+        kind,
+        destination: format!("./{}", destination.display()),
+        short_description: extract_cursor_rule_description(path),
+        tags: Vec::new(),
+        versions: Vec::new(),
+        source: None,
+        catalog: None,
+    }
+}
+
+/// Text of the first markdown heading after any frontmatter
+fn first_heading(content: &str) -> Option<String> {
+    strip_frontmatter(content)
+        .lines()
+        .find(|line| line.starts_with('#'))
+        .map(|line| line.trim_start_matches('#').trim().to_string())
+        .filter(|heading| !heading.is_empty())
+}
+
+impl CatalogEntry {
+    /// Versions with valid semver, newest first
+    pub fn sorted_versions(&self) -> Vec<(semver::Version, &CatalogVersion)> {
+        let mut versions: Vec<_> = self
+            .versions
+            .iter()
+            .filter_map(|v| Some((semver::Version::parse(&v.version).ok()?, v)))
+            .collect();
+        versions.sort_by(|a, b| b.0.cmp(&a.0));
+        versions
+    }
+
+    /// The newest version matching `requirement`, an exact version (`1.2.0`)
+    /// or a semver requirement (`^1.2`), or the newest of all without one.
+    /// `None` when the entry lists no versions and none was asked for.
+    pub fn find_version(&self, requirement: Option<&str>) -> Result<Option<&CatalogVersion>> {
+        let versions = self.sorted_versions();
+        let Some(requirement) = requirement else {
+            return Ok(versions.first().map(|(_, v)| *v));
+        };
+        let matches: Box<dyn Fn(&semver::Version) -> bool> =
+            match semver::Version::parse(requirement) {
+                Ok(exact) => Box::new(move |version| *version == exact),
+                Err(_) => {
+                    let req = semver::VersionReq::parse(requirement).map_err(|_| {
+                        ApsError::InvalidInput {
+                            message: format!(
+                                "'{}' is not a version or version requirement (e.g. 1.2.0 or ^1.2)",
+                                requirement
+                            ),
+                        }
+                    })?;
+                    Box::new(move |version| req.matches(version))
+                }
+            };
+        versions
+            .iter()
+            .find(|(version, _)| matches(version))
+            .map(|(_, v)| Some(*v))
+            .ok_or_else(|| ApsError::CatalogVersionNotFound {
+                id: self.id.clone(),
+                requirement: requirement.to_string(),
+                available: if versions.is_empty() {
+                    "none".to_string()
+                } else {
+                    versions
+                        .iter()
+                        .map(|(v, _)| v.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                },
+            })
+    }
+
+    /// A manifest entry installing just this asset, at `version` if given,
+    /// or `None` when the asset has no source or its kind can't be installed
+    /// on its own. A version's ref replaces the ref of a git source, and the
+    /// version is recorded on the entry.
+    pub fn to_manifest_entry(&self, version: Option<&CatalogVersion>) -> Option<Entry> {
+        let mut source = self.source.clone()?;
+        if let (Some(git_ref), Source::Git { r#ref, .. }) =
+            (version.and_then(|v| v.git_ref.as_ref()), &mut source)
+        {
+            *r#ref = git_ref.clone();
+        }
+        let id = self.manifest_id();
+        let destination = self.destination.trim_start_matches("./");
+        let entry = match self.kind {
+            AssetKind::AgentSkill => Entry {
+                id,
+                kind: AssetKind::AgentSkill,
+                source: Some(source),
+                dest: Some(format!("{}/", destination.trim_end_matches('/')).into()),
+                ..Default::default()
+            },
+            // Skill roots and rule directories are narrowed to the one asset
+            AssetKind::CursorSkillsRoot => {
+                let (parent, name) = split_source(source)?;
+                Entry {
+                    id,
+                    kind: AssetKind::CursorSkillsRoot,
+                    source: Some(parent),
+                    skills: vec![name],
+                    ..Default::default()
+                }
+            }
+            AssetKind::CursorRules => {
+                let (parent, name) = split_source(source)?;
+                Entry {
+                    id,
+                    kind: AssetKind::CursorRules,
+                    source: Some(parent),
+                    include: vec![name],
+                    ..Default::default()
+                }
+            }
+            AssetKind::AgentsMd | AssetKind::File => Entry {
+                id,
+                kind: self.kind.clone(),
+                source: Some(source),
+                dest: Some(destination.to_string().into()),
+                ..Default::default()
+            },
+            _ => return None,
+        };
+        Some(Entry {
+            version: version.map(|v| v.version.clone()),
+            ..entry
+        })
+    }
+
+    /// Manifest entry id for the asset: its file or folder name without
+    /// extension, e.g. `fastapi-auth` for `rules:fastapi-auth.mdc`
+    fn manifest_id(&self) -> String {
+        let last = self.id.rsplit([':', '/']).next().unwrap_or(&self.id);
+        let stem = last
+            .split('.')
+            .next()
+            .filter(|s| !s.is_empty())
+            .unwrap_or(last);
+        stem.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect()
+    }
+}
+
+/// Split a source pointing at a file or folder into its parent directory
+/// and the item's name
+fn split_source(source: Source) -> Option<(Source, String)> {
+    let split = |path: Option<String>| {
+        let path = path?;
+        let (parent, name) = match path.trim_end_matches('/').rsplit_once('/') {
+            Some((parent, name)) => (Some(parent.to_string()), name.to_string()),
+            None => (None, path.trim_end_matches('/').to_string()),
+        };
+        Some((parent, name))
+    };
+    match source {
+        Source::Git {
+            repo,
+            r#ref,
+            shallow,
+            path,
+        } => {
+            let (path, name) = split(path)?;
+            Some((
+                Source::Git {
+                    repo,
+                    r#ref,
+                    shallow,
+                    path,
+                },
+                name,
+            ))
+        }
+        Source::Filesystem {
+            root,
+            symlink,
+            path,
+        } => {
+            let (path, name) = split(path)?;
+            Some((
+                Source::Filesystem {
+                    root,
+                    symlink,
+                    path,
+                },
+                name,
+            ))
+        }
+        Source::Unsupported { .. } => None,
+    }
+}
+
+/// A window over a larger result set, carrying the total count so callers
+/// can paginate without loading everything at once.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    /// Total number of results before pagination
+    pub total: usize,
+
+    /// Number of results skipped
+    pub offset: usize,
+
+    /// Maximum number of results requested (None means unbounded)
+    pub limit: Option<usize>,
+
+    /// Results in this page
+    pub entries: Vec<T>,
+}
+
+impl<T> Page<T> {
+    /// Slice `items` into a page starting at `offset` with at most `limit` items
+    pub fn paginate(items: Vec<T>, offset: usize, limit: Option<usize>) -> Self {
+        let total = items.len();
+        let entries = items
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+
+        Self {
+            total,
+            offset,
+            limit,
+            entries,
+        }
+    }
+
+    /// Whether more results exist after this page
+    pub fn has_more(&self) -> bool {
+        self.offset + self.entries.len() < self.total
+    }
 }
 
 /// Enumerate all individual assets from a manifest entry
@@ -135,12 +629,21 @@ fn enumerate_entry_assets(entry: &Entry, manifest_dir: &Path) -> Result<Vec<Cata
     // Handle composite entries (no single source to resolve)
     if entry.is_composite() {
         // For composite entries, we create a single catalog entry
+        let name = match entry.kind {
+            AssetKind::McpConfig => ".mcp.json (composite)",
+            AssetKind::ClaudeSettings => ".claude/settings.json (composite)",
+            _ => "AGENTS.md (composite)",
+        };
         catalog_entries.push(CatalogEntry {
             id: format!("{}:composite", entry.id),
-            name: "AGENTS.md (composite)".to_string(),
-            kind: AssetKind::CompositeAgentsMd,
+            name: name.to_string(),
+            kind: entry.kind.clone(),
             destination: format!("./{}", base_dest.display()),
             short_description: Some(format!("Composed from {} sources", entry.sources.len())),
+            tags: Vec::new(),
+            versions: Vec::new(),
+            source: None,
+            catalog: None,
         });
         return Ok(catalog_entries);
     }
@@ -179,16 +682,24 @@ fn enumerate_entry_assets(entry: &Entry, manifest_dir: &Path) -> Result<Vec<Cata
                 kind: AssetKind::AgentsMd,
                 destination: format!("./{}", base_dest.display()),
                 short_description,
+                tags: Vec::new(),
+                versions: Vec::new(),
+                source: source_at(source, ""),
+                catalog: None,
             });
         }
-        AssetKind::CompositeAgentsMd => {
+        AssetKind::CompositeAgentsMd | AssetKind::McpConfig | AssetKind::ClaudeSettings => {
             // This case is handled above, but include for completeness
             catalog_entries.push(CatalogEntry {
                 id: format!("{}:composite", entry.id),
-                name: "AGENTS.md (composite)".to_string(),
-                kind: AssetKind::CompositeAgentsMd,
+                name: format!("{} (composite)", base_dest.display()),
+                kind: entry.kind.clone(),
                 destination: format!("./{}", base_dest.display()),
                 short_description: None,
+                tags: Vec::new(),
+                versions: Vec::new(),
+                source: None,
+                catalog: None,
             });
         }
         AssetKind::CursorRules => {
@@ -205,18 +716,46 @@ fn enumerate_entry_assets(entry: &Entry, manifest_dir: &Path) -> Result<Vec<Cata
                 }
 
                 let short_description = extract_cursor_rule_description(&file_path);
-                let dest_path = base_dest.join(&name);
+                let dest_path = if entry.mdc.is_some() {
+                    base_dest.join(mdc_file_name(&name))
+                } else {
+                    base_dest.join(&name)
+                };
 
+                let asset_source = source_at(source, &name);
                 catalog_entries.push(CatalogEntry {
                     id: format!("{}:{}", entry.id, name),
                     name,
                     kind: AssetKind::CursorRules,
                     destination: format!("./{}", dest_path.display()),
                     short_description,
+                    tags: Vec::new(),
+                    versions: Vec::new(),
+                    source: asset_source,
+                    catalog: None,
                 });
             }
         }
-        AssetKind::CursorHooks => {
+        AssetKind::File => {
+            let name = resolved
+                .source_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            catalog_entries.push(CatalogEntry {
+                id: format!("{}:{}", entry.id, name),
+                name,
+                kind: entry.kind.clone(),
+                destination: format!("./{}", base_dest.display()),
+                short_description: None,
+                tags: Vec::new(),
+                versions: Vec::new(),
+                source: source_at(source, ""),
+                catalog: None,
+            });
+        }
+        AssetKind::CursorHooks | AssetKind::Dir => {
             let files = enumerate_files_recursive(&resolved.source_path, &entry.include)?;
             for file_path in files {
                 let relative_path = file_path
@@ -233,18 +772,42 @@ fn enumerate_entry_assets(entry: &Entry, manifest_dir: &Path) -> Result<Vec<Cata
 
                 let dest_path = base_dest.join(&relative_path);
 
+                let asset_source = source_at(source, &name);
                 catalog_entries.push(CatalogEntry {
                     id: format!("{}:{}", entry.id, name),
                     name,
                     kind: entry.kind.clone(),
                     destination: format!("./{}", dest_path.display()),
                     short_description: None,
+                    tags: Vec::new(),
+                    versions: Vec::new(),
+                    source: asset_source,
+                    catalog: None,
+                });
+            }
+        }
+        AssetKind::PromptLibrary => {
+            let files = enumerate_files_recursive(&resolved.source_path, &entry.include)?;
+            for file_path in files.iter().filter(|f| is_prompt_file(f)) {
+                let prompt = read_prompt_info(&resolved.source_path, file_path)?;
+                let dest_path = base_dest.join(&prompt.path);
+
+                catalog_entries.push(CatalogEntry {
+                    id: format!("{}:{}", entry.id, prompt.path),
+                    name: prompt.title,
+                    kind: AssetKind::PromptLibrary,
+                    destination: format!("./{}", dest_path.display()),
+                    short_description: prompt.description,
+                    tags: Vec::new(),
+                    versions: Vec::new(),
+                    source: source_at(source, &prompt.path),
+                    catalog: None,
                 });
             }
         }
         AssetKind::CursorSkillsRoot => {
             // Enumerate each skill folder in the directory
-            let folders = enumerate_folders(&resolved.source_path, &entry.include)?;
+            let folders = enumerate_folders(&resolved.source_path, &entry.include, &entry.skills)?;
             for folder_path in folders {
                 let name = folder_path
                     .file_name()
@@ -258,18 +821,23 @@ fn enumerate_entry_assets(entry: &Entry, manifest_dir: &Path) -> Result<Vec<Cata
                 let short_description = extract_cursor_skill_description(&folder_path);
                 let dest_path = base_dest.join(&name);
 
+                let asset_source = source_at(source, &name);
                 catalog_entries.push(CatalogEntry {
                     id: format!("{}:{}", entry.id, name),
                     name,
                     kind: AssetKind::CursorSkillsRoot,
                     destination: format!("./{}", dest_path.display()),
                     short_description,
+                    tags: Vec::new(),
+                    versions: Vec::new(),
+                    source: asset_source,
+                    catalog: None,
                 });
             }
         }
         AssetKind::AgentSkill => {
             // Enumerate each skill folder in the directory
-            let folders = enumerate_folders(&resolved.source_path, &entry.include)?;
+            let folders = enumerate_folders(&resolved.source_path, &entry.include, &entry.skills)?;
             for folder_path in folders {
                 let name = folder_path
                     .file_name()
@@ -283,12 +851,17 @@ fn enumerate_entry_assets(entry: &Entry, manifest_dir: &Path) -> Result<Vec<Cata
                 let short_description = extract_agent_skill_description(&folder_path);
                 let dest_path = base_dest.join(&name);
 
+                let asset_source = source_at(source, &name);
                 catalog_entries.push(CatalogEntry {
                     id: format!("{}:{}", entry.id, name),
                     name,
                     kind: AssetKind::AgentSkill,
                     destination: format!("./{}", dest_path.display()),
                     short_description,
+                    tags: Vec::new(),
+                    versions: Vec::new(),
+                    source: asset_source,
+                    catalog: None,
                 });
             }
         }
@@ -303,7 +876,8 @@ fn extract_agents_md_description(path: &Path) -> Option<String> {
     extract_first_paragraph(&content)
 }
 
-/// Extract a short description from a cursor rule file (.mdc)
+/// Extract a short description from a cursor rule file (.mdc) or another
+/// markdown file
 ///
 /// Cursor rules may have YAML frontmatter with a `description` field,
 /// or we fall back to extracting the first meaningful line.
@@ -567,8 +1141,9 @@ fn enumerate_files_recursive_inner(
     Ok(())
 }
 
-/// Enumerate all folders in a directory, optionally filtering by include prefixes
-fn enumerate_folders(dir: &Path, include: &[String]) -> Result<Vec<PathBuf>> {
+/// Enumerate all folders in a directory, optionally filtering by skill names
+/// and include prefixes
+fn enumerate_folders(dir: &Path, include: &[String], skills: &[String]) -> Result<Vec<PathBuf>> {
     let mut folders = Vec::new();
 
     for entry in std::fs::read_dir(dir)
@@ -584,6 +1159,11 @@ fn enumerate_folders(dir: &Path, include: &[String]) -> Result<Vec<PathBuf>> {
 
         let name = entry.file_name().to_string_lossy().to_string();
 
+        // Apply skills selection if specified
+        if !skills.is_empty() && !skills.contains(&name) {
+            continue;
+        }
+
         // Apply include filter if specified
         if !include.is_empty() {
             let matches = include.iter().any(|prefix| name.starts_with(prefix));
@@ -612,6 +1192,371 @@ mod tests {
         assert!(catalog.entries.is_empty());
     }
 
+    #[test]
+    fn test_merge_by_precedence() {
+        let catalog = |ids: &[&str], description: &str| Catalog {
+            version: 1,
+            release: None,
+            publish: None,
+            entries: ids
+                .iter()
+                .map(|id| CatalogEntry {
+                    id: id.to_string(),
+                    name: id.to_string(),
+                    kind: AssetKind::AgentSkill,
+                    destination: format!("./.claude/skills/{}", id),
+                    short_description: Some(description.to_string()),
+                    tags: Vec::new(),
+                    versions: Vec::new(),
+                    source: None,
+                    catalog: None,
+                })
+                .collect(),
+        };
+
+        let merged = Catalog::merge(vec![
+            ("personal".to_string(), catalog(&["pdf"], "My PDF skill")),
+            ("org".to_string(), catalog(&["pdf", "docx"], "Org skill")),
+        ]);
+        let found: Vec<_> = merged
+            .entries
+            .iter()
+            .map(|e| {
+                (
+                    e.id.as_str(),
+                    e.short_description.as_deref().unwrap(),
+                    e.catalog.as_deref().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("pdf", "My PDF skill", "personal"),
+                ("docx", "Org skill", "org"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_from_dir() {
+        let dir = TempDir::new().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "skills/pdf/SKILL.md",
+            "---\nname: pdf-tools\ndescription: Extract text from PDFs\n---\n",
+        );
+        write("skills/pdf/reference/rules/ignored.md", "# Not a rule\n");
+        write(
+            "cursor/fastapi.mdc",
+            "---\ndescription: FastAPI patterns\n---\nBody\n",
+        );
+        write("rules/python.md", "# Python style\n\nUse type hints.\n");
+        write("partials/testing.md", "# Testing\n\nRun the suite first.\n");
+        write("README.md", "# Assets\n");
+        write("node_modules/pkg/AGENTS.md", "# Vendored\n");
+
+        let source = Source::Git {
+            repo: "https://github.com/acme/assets.git".to_string(),
+            r#ref: "main".to_string(),
+            shallow: true,
+            path: None,
+        };
+        let catalog = Catalog::generate_from_dir(dir.path(), &source).unwrap();
+        assert!(matches!(
+            catalog.entries[0].source,
+            Some(Source::Git { ref path, .. }) if path.as_deref() == Some("cursor/fastapi.mdc")
+        ));
+        let found: Vec<_> = catalog
+            .entries
+            .iter()
+            .map(|e| {
+                (
+                    e.id.as_str(),
+                    e.name.as_str(),
+                    e.destination.as_str(),
+                    e.short_description.as_deref().unwrap_or_default(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "cursor/fastapi.mdc",
+                    "fastapi.mdc",
+                    "./.cursor/rules/fastapi.mdc",
+                    "FastAPI patterns"
+                ),
+                (
+                    "partials/testing.md",
+                    "Testing",
+                    "./AGENTS.md",
+                    "Run the suite first."
+                ),
+                (
+                    "rules/python.md",
+                    "Python style",
+                    "./.cursor/rules/python.md",
+                    "Use type hints."
+                ),
+                (
+                    "skills/pdf",
+                    "pdf-tools",
+                    "./.claude/skills/pdf",
+                    "Extract text from PDFs"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_manifest_entry() {
+        let catalog_entry =
+            |id: &str, kind: AssetKind, destination: &str, path: &str| CatalogEntry {
+                id: id.to_string(),
+                name: id.to_string(),
+                kind,
+                destination: destination.to_string(),
+                short_description: None,
+                tags: Vec::new(),
+                versions: Vec::new(),
+                source: Some(Source::Git {
+                    repo: "https://github.com/acme/assets.git".to_string(),
+                    r#ref: "main".to_string(),
+                    shallow: true,
+                    path: Some(path.to_string()),
+                }),
+                catalog: None,
+            };
+        let source_path = |entry: &Entry| match &entry.source {
+            Some(Source::Git { path, .. }) => path.clone(),
+            _ => None,
+        };
+
+        let rule = catalog_entry(
+            "rules:fastapi-auth.mdc",
+            AssetKind::CursorRules,
+            "./.cursor/rules/fastapi-auth.mdc",
+            "rules/fastapi-auth.mdc",
+        )
+        .to_manifest_entry(None)
+        .unwrap();
+        assert_eq!(rule.id, "fastapi-auth");
+        assert_eq!(source_path(&rule).as_deref(), Some("rules"));
+        assert_eq!(rule.include, vec!["fastapi-auth.mdc"]);
+
+        let skill = catalog_entry(
+            "skills/pdf",
+            AssetKind::AgentSkill,
+            "./.claude/skills/pdf",
+            "skills/pdf",
+        )
+        .to_manifest_entry(None)
+        .unwrap();
+        assert_eq!(skill.id, "pdf");
+        assert_eq!(source_path(&skill).as_deref(), Some("skills/pdf"));
+        assert_eq!(skill.destination(), PathBuf::from(".claude/skills/pdf/"));
+
+        let skill_root = catalog_entry(
+            "cursor-skills:deploy",
+            AssetKind::CursorSkillsRoot,
+            "./.cursor/skills/deploy",
+            "cursor/skills/deploy/",
+        )
+        .to_manifest_entry(None)
+        .unwrap();
+        assert_eq!(skill_root.id, "deploy");
+        assert_eq!(source_path(&skill_root).as_deref(), Some("cursor/skills"));
+        assert_eq!(skill_root.skills, vec!["deploy"]);
+        assert!(skill_root.dest.is_none());
+
+        let agents = catalog_entry(
+            "agents:AGENTS.md",
+            AssetKind::AgentsMd,
+            "./AGENTS.md",
+            "AGENTS.md",
+        )
+        .to_manifest_entry(None)
+        .unwrap();
+        assert_eq!(agents.id, "agents");
+        assert_eq!(source_path(&agents).as_deref(), Some("AGENTS.md"));
+        assert_eq!(agents.destination(), PathBuf::from("AGENTS.md"));
+
+        let file = catalog_entry(
+            "files:ci.yml",
+            AssetKind::File,
+            "./.github/workflows/ci.yml",
+            "files/ci.yml",
+        )
+        .to_manifest_entry(None)
+        .unwrap();
+        assert_eq!(file.kind, AssetKind::File);
+        assert_eq!(source_path(&file).as_deref(), Some("files/ci.yml"));
+        assert_eq!(
+            file.destination(),
+            PathBuf::from(".github/workflows/ci.yml")
+        );
+
+        // Kinds aps can't narrow to one asset aren't added
+        let hooks = catalog_entry("hooks", AssetKind::CursorHooks, "./.cursor/hooks", "hooks");
+        assert!(hooks.to_manifest_entry(None).is_none());
+
+        let mut no_source = catalog_entry("x", AssetKind::AgentSkill, "./x", "x");
+        no_source.source = None;
+        assert!(no_source.to_manifest_entry(None).is_none());
+    }
+
+    fn path_of(source: &Source) -> Option<&str> {
+        match source {
+            Source::Git { path, .. } | Source::Filesystem { path, .. } => path.as_deref(),
+            Source::Unsupported { .. } => None,
+        }
+    }
+
+    #[test]
+    fn test_source_at() {
+        let git = Source::Git {
+            repo: "https://github.com/acme/assets.git".to_string(),
+            r#ref: "main".to_string(),
+            shallow: true,
+            path: Some("rules/".to_string()),
+        };
+        let at = source_at(&git, "auth.mdc").unwrap();
+        assert_eq!(path_of(&at), Some("rules/auth.mdc"));
+        assert!(matches!(at, Source::Git { ref r#ref, shallow: true, .. } if r#ref == "main"));
+        assert_eq!(path_of(&source_at(&git, "").unwrap()), Some("rules/"));
+
+        let filesystem = Source::Filesystem {
+            root: "../shared".to_string(),
+            symlink: false,
+            path: None,
+        };
+        let at = source_at(&filesystem, "skills/pdf").unwrap();
+        assert_eq!(path_of(&at), Some("skills/pdf"));
+        assert!(matches!(at, Source::Filesystem { symlink: false, .. }));
+        assert_eq!(path_of(&source_at(&filesystem, "").unwrap()), None);
+
+        let unsupported = Source::Unsupported {
+            source_type: "s3".to_string(),
+            raw: serde_yaml::Value::Null,
+        };
+        assert!(source_at(&unsupported, "x").is_none());
+    }
+
+    #[test]
+    fn test_split_source() {
+        let git = |path: Option<&str>| Source::Git {
+            repo: "https://github.com/acme/assets.git".to_string(),
+            r#ref: "v1".to_string(),
+            shallow: false,
+            path: path.map(str::to_string),
+        };
+
+        let (parent, name) = split_source(git(Some("rules/auth.mdc"))).unwrap();
+        assert_eq!(path_of(&parent), Some("rules"));
+        assert_eq!(name, "auth.mdc");
+        assert!(matches!(parent, Source::Git { ref r#ref, .. } if r#ref == "v1"));
+
+        let (parent, name) = split_source(git(Some("skills/pdf/"))).unwrap();
+        assert_eq!(path_of(&parent), Some("skills"));
+        assert_eq!(name, "pdf");
+
+        // An item at the repository root has no parent path
+        let (parent, name) = split_source(git(Some("pdf"))).unwrap();
+        assert_eq!(path_of(&parent), None);
+        assert_eq!(name, "pdf");
+
+        assert!(split_source(git(None)).is_none());
+
+        let (parent, name) = split_source(Source::Filesystem {
+            root: "$HOME/assets".to_string(),
+            symlink: true,
+            path: Some("rules/style.md".to_string()),
+        })
+        .unwrap();
+        assert_eq!(path_of(&parent), Some("rules"));
+        assert_eq!(name, "style.md");
+        assert!(matches!(parent, Source::Filesystem { ref root, .. } if root == "$HOME/assets"));
+    }
+
+    #[test]
+    fn test_find_version() {
+        let version = |version: &str, git_ref: &str| CatalogVersion {
+            version: version.to_string(),
+            git_ref: Some(git_ref.to_string()),
+            changelog: None,
+        };
+        let entry = CatalogEntry {
+            id: "skills/pdf".to_string(),
+            name: "pdf".to_string(),
+            kind: AssetKind::AgentSkill,
+            destination: "./.claude/skills/pdf".to_string(),
+            short_description: None,
+            tags: Vec::new(),
+            versions: vec![
+                version("1.2.0", "v1.2.0"),
+                version("2.0.0", "v2.0.0"),
+                version("1.10.1", "v1.10.1"),
+                version("not-semver", "x"),
+            ],
+            source: Some(Source::Git {
+                repo: "https://github.com/acme/assets.git".to_string(),
+                r#ref: "main".to_string(),
+                shallow: true,
+                path: Some("skills/pdf".to_string()),
+            }),
+            catalog: None,
+        };
+        let found = |requirement| {
+            entry
+                .find_version(requirement)
+                .unwrap()
+                .map(|v| v.version.as_str())
+        };
+
+        assert_eq!(found(None), Some("2.0.0"));
+        assert_eq!(found(Some("^1")), Some("1.10.1"));
+        assert_eq!(found(Some("1.2.0")), Some("1.2.0"));
+        assert!(matches!(
+            entry.find_version(Some("3")),
+            Err(ApsError::CatalogVersionNotFound { available, .. })
+                if available == "2.0.0, 1.10.1, 1.2.0"
+        ));
+        assert!(entry.find_version(Some("latest!")).is_err());
+
+        // The version's ref pins the source, and the entry records it
+        let manifest_entry = entry
+            .to_manifest_entry(entry.find_version(Some("~1.2")).unwrap())
+            .unwrap();
+        assert_eq!(manifest_entry.version.as_deref(), Some("1.2.0"));
+        assert!(matches!(
+            manifest_entry.source,
+            Some(Source::Git { r#ref, .. }) if r#ref == "v1.2.0"
+        ));
+    }
+
+    #[test]
+    fn test_page_paginate() {
+        let page = Page::paginate((0..10).collect::<Vec<_>>(), 3, Some(4));
+        assert_eq!(page.total, 10);
+        assert_eq!(page.entries, vec![3, 4, 5, 6]);
+        assert!(page.has_more());
+
+        let page = Page::paginate((0..10).collect::<Vec<_>>(), 8, Some(4));
+        assert_eq!(page.entries, vec![8, 9]);
+        assert!(!page.has_more());
+
+        let page = Page::paginate((0..3).collect::<Vec<_>>(), 5, None);
+        assert_eq!(page.total, 3);
+        assert!(page.entries.is_empty());
+        assert!(!page.has_more());
+    }
+
     #[test]
     fn test_catalog_path_for_manifest() {
         let manifest_path = PathBuf::from("/home/user/project/aps.yaml");
@@ -687,13 +1632,17 @@ mod tests {
         std::fs::write(dir.join("file.txt"), "content").unwrap();
 
         // Test without filter
-        let folders = enumerate_folders(dir, &[])?;
+        let folders = enumerate_folders(dir, &[], &[])?;
         assert_eq!(folders.len(), 3);
 
         // Test with filter
-        let folders = enumerate_folders(dir, &["skill".to_string()])?;
+        let folders = enumerate_folders(dir, &["skill".to_string()], &[])?;
         assert_eq!(folders.len(), 2);
 
+        // Test with skills selection
+        let folders = enumerate_folders(dir, &[], &["skill2".to_string(), "other".to_string()])?;
+        assert_eq!(folders, vec![dir.join("other"), dir.join("skill2")]);
+
         Ok(())
     }
 
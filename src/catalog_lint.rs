@@ -0,0 +1,273 @@
+//! Checks for `aps catalog lint`, to keep shared catalogs healthy.
+//!
+//! Every entry needs a description, an id no earlier catalog in the merge
+//! uses, semver versions, and a source that exists. Local sources are
+//! checked on disk; git sources with `git ls-remote`, which must reach the
+//! repository and find the source's ref and each version's ref. As in
+//! `aps validate`, paths inside git repositories are only checked with
+//! `--deep`, which clones each repository once per ref.
+
+use crate::catalog::{Catalog, CatalogEntry};
+use crate::manifest::Source;
+use crate::registry::is_remote;
+use crate::sources::{check_remote_ref, clone_and_resolve, expand_path, ResolvedGitSource};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// What a lint problem is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintKind {
+    MissingDescription,
+    DuplicateId,
+    InvalidVersion,
+    DeadSource,
+    MissingPath,
+}
+
+impl LintKind {
+    /// Problems that only warrant a warning, unless `--strict`
+    pub fn is_warning(self) -> bool {
+        self == LintKind::MissingDescription
+    }
+}
+
+/// A problem found in a catalog entry
+#[derive(Debug, Serialize)]
+pub struct LintIssue {
+    /// Catalog the entry was read from
+    pub catalog: String,
+    pub id: String,
+    pub kind: LintKind,
+    pub message: String,
+}
+
+/// Lint `catalogs`, given in merge order, returning problems in catalog and
+/// entry order
+pub fn lint_catalogs(catalogs: &[(String, Catalog)], deep: bool) -> Vec<LintIssue> {
+    let mut linter = Linter {
+        deep,
+        remote_refs: HashMap::new(),
+        clones: HashMap::new(),
+    };
+    let mut first_seen: HashMap<&str, &str> = HashMap::new();
+    let mut issues = Vec::new();
+
+    for (location, catalog) in catalogs {
+        let base_dir = if is_remote(location) {
+            PathBuf::from(".")
+        } else {
+            Path::new(location)
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default()
+        };
+        for entry in &catalog.entries {
+            let mut problems = Vec::new();
+            if entry
+                .short_description
+                .as_deref()
+                .is_none_or(|d| d.trim().is_empty())
+            {
+                problems.push((LintKind::MissingDescription, "No description".to_string()));
+            }
+            match first_seen.get(entry.id.as_str()) {
+                Some(first) if *first == location.as_str() => problems.push((
+                    LintKind::DuplicateId,
+                    "Id is used by an earlier entry in this catalog".to_string(),
+                )),
+                Some(first) => problems.push((
+                    LintKind::DuplicateId,
+                    format!("Id is also in {}, which takes precedence", first),
+                )),
+                None => {
+                    first_seen.insert(&entry.id, location);
+                }
+            }
+            for version in &entry.versions {
+                if let Err(e) = semver::Version::parse(&version.version) {
+                    problems.push((
+                        LintKind::InvalidVersion,
+                        format!("Version '{}' is not semver: {}", version.version, e),
+                    ));
+                }
+            }
+            if let Some(ref source) = entry.source {
+                problems.extend(linter.check_source(entry, source, &base_dir));
+            }
+
+            issues.extend(problems.into_iter().map(|(kind, message)| LintIssue {
+                catalog: location.clone(),
+                id: entry.id.clone(),
+                kind,
+                message,
+            }));
+        }
+    }
+    issues
+}
+
+/// Source checks, caching remote lookups and clones across entries
+struct Linter {
+    deep: bool,
+    /// `git ls-remote` outcome per (repository, ref): the error, if any
+    remote_refs: HashMap<(String, String), Option<String>>,
+    /// Clone per (repository, ref), kept until linting ends, or why it failed
+    clones: HashMap<(String, String), std::result::Result<ResolvedGitSource, String>>,
+}
+
+impl Linter {
+    fn check_source(
+        &mut self,
+        entry: &CatalogEntry,
+        source: &Source,
+        base_dir: &Path,
+    ) -> Vec<(LintKind, String)> {
+        match source {
+            Source::Filesystem { root, path, .. } => {
+                let root = base_dir.join(expand_path(root));
+                if !root.is_dir() {
+                    return vec![(
+                        LintKind::DeadSource,
+                        format!("Source directory {} does not exist", root.display()),
+                    )];
+                }
+                match path {
+                    Some(path) if !root.join(path).exists() => vec![(
+                        LintKind::MissingPath,
+                        format!("Path '{}' does not exist in {}", path, root.display()),
+                    )],
+                    _ => Vec::new(),
+                }
+            }
+            Source::Git {
+                repo, r#ref, path, ..
+            } => {
+                let mut refs = vec![r#ref.clone()];
+                for git_ref in entry.versions.iter().filter_map(|v| v.git_ref.clone()) {
+                    if !refs.contains(&git_ref) {
+                        refs.push(git_ref);
+                    }
+                }
+                let mut problems = Vec::new();
+                for git_ref in refs {
+                    if let Some(error) = self.remote_error(repo, &git_ref) {
+                        problems.push((LintKind::DeadSource, error));
+                        continue;
+                    }
+                    let Some(path) = path.as_deref().filter(|_| self.deep) else {
+                        continue;
+                    };
+                    match self.clone_path(repo, &git_ref) {
+                        Ok(repo_path) if !repo_path.join(path).exists() => problems.push((
+                            LintKind::MissingPath,
+                            format!("Path '{}' does not exist in {} at {}", path, repo, git_ref),
+                        )),
+                        Ok(_) => {}
+                        Err(error) => problems.push((LintKind::DeadSource, error)),
+                    }
+                }
+                problems
+            }
+            Source::Unsupported { .. } => Vec::new(),
+        }
+    }
+
+    fn remote_error(&mut self, repo: &str, git_ref: &str) -> Option<String> {
+        self.remote_refs
+            .entry((repo.to_string(), git_ref.to_string()))
+            .or_insert_with(|| {
+                check_remote_ref(repo, git_ref)
+                    .err()
+                    .map(|e| format!("{} (ref '{}'): {}", repo, git_ref, e))
+            })
+            .clone()
+    }
+
+    fn clone_path(&mut self, repo: &str, git_ref: &str) -> std::result::Result<PathBuf, String> {
+        self.clones
+            .entry((repo.to_string(), git_ref.to_string()))
+            .or_insert_with(|| {
+                clone_and_resolve(repo, git_ref, true)
+                    .map_err(|e| format!("Failed to clone {} at {}: {}", repo, git_ref, e))
+            })
+            .as_ref()
+            .map(|resolved| resolved.repo_path.clone())
+            .map_err(Clone::clone)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::CatalogVersion;
+    use crate::manifest::AssetKind;
+
+    fn entry(id: &str, description: Option<&str>, path: &str) -> CatalogEntry {
+        CatalogEntry {
+            id: id.to_string(),
+            name: id.to_string(),
+            kind: AssetKind::CursorRules,
+            destination: format!("./.cursor/rules/{}", id),
+            short_description: description.map(str::to_string),
+            tags: Vec::new(),
+            versions: Vec::new(),
+            source: Some(Source::Filesystem {
+                root: ".".to_string(),
+                symlink: true,
+                path: Some(path.to_string()),
+            }),
+            catalog: None,
+        }
+    }
+
+    #[test]
+    fn test_lint_catalogs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.mdc"), "# A\n").unwrap();
+        let location = |name: &str| dir.path().join(name).display().to_string();
+
+        let mut versioned = entry("a.mdc", Some("A rule"), "a.mdc");
+        versioned.versions = vec![CatalogVersion {
+            version: "v1".to_string(),
+            git_ref: None,
+            changelog: None,
+        }];
+        let catalogs = vec![
+            (
+                location("first.yaml"),
+                Catalog {
+                    version: 1,
+                    release: None,
+                    publish: None,
+                    entries: vec![versioned, entry("b.mdc", None, "b.mdc")],
+                },
+            ),
+            (
+                location("second.yaml"),
+                Catalog {
+                    version: 1,
+                    release: None,
+                    publish: None,
+                    entries: vec![entry("a.mdc", Some("Another"), "a.mdc")],
+                },
+            ),
+        ];
+
+        let issues = lint_catalogs(&catalogs, false);
+        let found: Vec<(&str, LintKind)> = issues
+            .iter()
+            .map(|issue| (issue.id.as_str(), issue.kind))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("a.mdc", LintKind::InvalidVersion),
+                ("b.mdc", LintKind::MissingDescription),
+                ("b.mdc", LintKind::MissingPath),
+                ("a.mdc", LintKind::DuplicateId),
+            ]
+        );
+    }
+}
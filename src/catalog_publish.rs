@@ -0,0 +1,152 @@
+//! Publishing catalogs for `aps catalog publish`.
+//!
+//! The catalog file is sent to its publish target: committed and pushed to
+//! a branch of a git repository, uploaded with an HTTP PUT (which also
+//! covers presigned S3 URLs), or copied to an `s3://` URL with the AWS CLI.
+//! Git and the AWS CLI run with the user's own configuration and
+//! credentials, as when cloning sources.
+
+use crate::catalog::{PublishTarget, CATALOG_FILENAME};
+use crate::cli::ReleaseBump;
+use crate::error::{ApsError, Result};
+use crate::http;
+use crate::registry::is_git_url;
+use crate::sources::clone_and_resolve;
+use std::path::Path;
+use std::process::Command;
+use tracing::info;
+
+/// Branch pushed to when the target doesn't name one
+pub const DEFAULT_PUBLISH_BRANCH: &str = "main";
+
+/// The release after `current`; the first release is 1.0.0
+pub fn bump_release(current: Option<&str>, bump: ReleaseBump) -> Result<String> {
+    let Some(current) = current else {
+        return Ok("1.0.0".to_string());
+    };
+    let mut version = semver::Version::parse(current).map_err(|e| ApsError::InvalidInput {
+        message: format!("Catalog release '{}' is not semver: {}", current, e),
+    })?;
+    match bump {
+        ReleaseBump::Major => {
+            version.major += 1;
+            version.minor = 0;
+            version.patch = 0;
+        }
+        ReleaseBump::Minor => {
+            version.minor += 1;
+            version.patch = 0;
+        }
+        ReleaseBump::Patch => version.patch += 1,
+    }
+    version.pre = semver::Prerelease::EMPTY;
+    version.build = semver::BuildMetadata::EMPTY;
+    Ok(version.to_string())
+}
+
+/// Send the catalog file at `catalog_path`, at `release`, to `target`
+pub fn publish_catalog(catalog_path: &Path, target: &PublishTarget, release: &str) -> Result<()> {
+    let to = target.to.as_str();
+    if to.starts_with("s3://") {
+        info!("Uploading catalog to {} with the AWS CLI", to);
+        run(
+            Command::new("aws")
+                .args(["s3", "cp"])
+                .arg(catalog_path)
+                .arg(to),
+            to,
+        )
+    } else if to.starts_with("http://") || to.starts_with("https://") {
+        if is_git_url(to) {
+            return publish_to_git(catalog_path, target, release);
+        }
+        info!("Uploading catalog to {}", to);
+        let body = std::fs::read(catalog_path).map_err(|e| publish_error(to, e))?;
+        http::upload(to, &body, "application/yaml").map_err(|message| publish_error(to, message))
+    } else {
+        // SSH URLs and local paths are git repositories
+        publish_to_git(catalog_path, target, release)
+    }
+}
+
+/// Commit the catalog to the target branch and push it
+fn publish_to_git(catalog_path: &Path, target: &PublishTarget, release: &str) -> Result<()> {
+    let to = target.to.as_str();
+    let branch = target.branch.as_deref().unwrap_or(DEFAULT_PUBLISH_BRANCH);
+    let path = target.path.as_deref().unwrap_or(CATALOG_FILENAME);
+    info!("Publishing catalog to {} ({} on {})", to, path, branch);
+
+    let cloned = clone_and_resolve(to, branch, false).map_err(|e| publish_error(to, e))?;
+    let dest = cloned.repo_path.join(path);
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| publish_error(to, e))?;
+    }
+    std::fs::copy(catalog_path, &dest).map_err(|e| publish_error(to, e))?;
+
+    let git = |args: &[&str]| {
+        let mut command = Command::new("git");
+        command.current_dir(&cloned.repo_path).args(args);
+        command
+    };
+    run(&mut git(&["add", "--", path]), to)?;
+    run(
+        &mut git(&["commit", "-m", &format!("Publish catalog {}", release)]),
+        to,
+    )?;
+    run(
+        &mut git(&["push", "origin", &format!("HEAD:refs/heads/{}", branch)]),
+        to,
+    )
+}
+
+/// Run a publishing command, failing with its stderr
+fn run(command: &mut Command, to: &str) -> Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .map_err(|e| publish_error(to, format!("failed to run {}: {}", program, e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let message = if stderr.trim().is_empty() {
+            stdout.trim()
+        } else {
+            stderr.trim()
+        };
+        return Err(publish_error(
+            to,
+            format!("{} failed: {}", program, message),
+        ));
+    }
+    Ok(())
+}
+
+fn publish_error(to: &str, message: impl std::fmt::Display) -> ApsError {
+    ApsError::CatalogPublishError {
+        to: to.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump_release() {
+        assert_eq!(bump_release(None, ReleaseBump::Patch).unwrap(), "1.0.0");
+        assert_eq!(
+            bump_release(Some("1.2.3"), ReleaseBump::Patch).unwrap(),
+            "1.2.4"
+        );
+        assert_eq!(
+            bump_release(Some("1.2.3"), ReleaseBump::Minor).unwrap(),
+            "1.3.0"
+        );
+        assert_eq!(
+            bump_release(Some("1.2.3-rc.1"), ReleaseBump::Major).unwrap(),
+            "2.0.0"
+        );
+        assert!(bump_release(Some("one"), ReleaseBump::Patch).is_err());
+    }
+}
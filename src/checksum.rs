@@ -22,7 +22,7 @@ pub fn compute_checksum(path: &Path) -> Result<String> {
                 // Exclude .git directories
                 !e.path().components().any(|c| c.as_os_str() == ".git")
             })
-            .filter(|e| e.file_type().is_file())
+            .filter(|e| e.file_type().is_file() || e.file_type().is_symlink())
             .map(|e| e.path().to_path_buf())
             .collect();
 
@@ -37,6 +37,21 @@ pub fn compute_checksum(path: &Path) -> Result<String> {
             hasher.update(relative.as_bytes());
             hasher.update(b"\0"); // separator
 
+            // Links are hashed by their target, plus its content when it
+            // is a file, so changing either where a link points or what it
+            // points to changes the checksum however the link is installed
+            if file_path.is_symlink() {
+                let target = std::fs::read_link(&file_path).map_err(|e| {
+                    ApsError::io(e, format!("Failed to read symlink {:?}", file_path))
+                })?;
+                hasher.update(b"symlink:");
+                hasher.update(target.to_string_lossy().as_bytes());
+                hasher.update(b"\0");
+                if !file_path.is_file() {
+                    continue;
+                }
+            }
+
             // Hash the file content
             let content = std::fs::read(&file_path).map_err(|e| {
                 ApsError::io(
@@ -0,0 +1,1098 @@
+//! Claude settings composition for the `claude_settings` kind.
+//!
+//! Each source is a YAML, JSON or JSONC fragment shaped like
+//! `.claude/settings.json`.
+//! Permission lists may be written at the top level (`allow: [...]`) or under
+//! `permissions:`. Fragments are merged into a single settings file:
+//!
+//! - `permissions.allow`/`deny`/`ask`/`additionalDirectories`: union of all
+//!   fragments, in order of first appearance
+//! - other `permissions` keys (e.g. `defaultMode`): the last fragment wins
+//! - `env`: merged per variable; the last fragment wins for each variable
+//! - `hooks`: matcher groups are appended per event; groups with the same
+//!   matcher are combined and identical hook commands are kept once
+//! - any other top-level key (`model`, `includeCoAuthoredBy`, ...): the last
+//!   fragment wins
+
+use crate::compose::ComposedSource;
+use crate::error::{ApsError, Result};
+use crate::hooks::referenced_hook_scripts;
+use crate::manifest::MergeStrategy;
+use crate::merge::StrategyMerge;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Key holding the permission rules
+pub const PERMISSIONS_KEY: &str = "permissions";
+
+/// Key holding environment variables
+pub const ENV_KEY: &str = "env";
+
+/// Key holding hook definitions
+pub const HOOKS_KEY: &str = "hooks";
+
+/// Permission lists that are unioned across fragments, in output order
+pub const PERMISSION_LISTS: &[&str] = &["allow", "deny", "ask", "additionalDirectories"];
+
+/// OS-specific location of the managed (enterprise) settings file
+pub fn managed_settings_path() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/ClaudeCode/managed-settings.json")
+    } else if cfg!(windows) {
+        PathBuf::from(r"C:\ProgramData\ClaudeCode\managed-settings.json")
+    } else {
+        PathBuf::from("/etc/claude-code/managed-settings.json")
+    }
+}
+
+/// Whether `path` is the managed settings file, which aps writes into but
+/// never deletes
+pub fn is_managed_settings_path(path: &Path) -> bool {
+    path == managed_settings_path()
+}
+
+/// Parse a fragment by extension: `.json` as JSON, `.jsonc`/`.json5` as JSON
+/// with comments and trailing commas, anything else as YAML
+fn parse_fragment(source: &ComposedSource) -> std::result::Result<Value, String> {
+    let extension = source
+        .path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("json") => serde_json::from_str(&source.content).map_err(|e| e.to_string()),
+        Some("jsonc") | Some("json5") => {
+            json5::from_str(&source.content).map_err(|e| e.to_string())
+        }
+        _ => {
+            let yaml: serde_yaml::Value =
+                serde_yaml::from_str(&source.content).map_err(|e| e.to_string())?;
+            serde_json::to_value(yaml).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Parse a fragment into settings.json shape, moving top-level permission
+/// lists under `permissions`
+pub fn read_permission_fragment(source: &ComposedSource) -> Result<Map<String, Value>> {
+    let invalid = |message: String| ApsError::InvalidClaudeSettings {
+        path: source.path.clone(),
+        message,
+    };
+
+    let value = parse_fragment(source).map_err(invalid)?;
+    let mut settings = match value {
+        Value::Object(map) => map,
+        Value::Null => Map::new(),
+        _ => return Err(invalid("expected a mapping of settings".to_string())),
+    };
+
+    let mut permissions = match settings.remove(PERMISSIONS_KEY) {
+        Some(Value::Object(map)) => map,
+        None | Some(Value::Null) => Map::new(),
+        Some(_) => return Err(invalid(format!("'{}' must be a mapping", PERMISSIONS_KEY))),
+    };
+    for list in PERMISSION_LISTS {
+        if let Some(value) = settings.remove(*list) {
+            permissions.insert(list.to_string(), value);
+        }
+    }
+
+    for list in PERMISSION_LISTS {
+        match permissions.get(*list) {
+            None => {}
+            Some(Value::Array(items)) if items.iter().all(Value::is_string) => {}
+            Some(_) => return Err(invalid(format!("'{}' must be a list of strings", list))),
+        }
+    }
+    if let Some(env) = settings.get(ENV_KEY) {
+        if !env.is_object() {
+            return Err(invalid(format!("'{}' must be a mapping", ENV_KEY)));
+        }
+    }
+
+    if let Some(hooks) = settings.get(HOOKS_KEY) {
+        let valid = hooks.as_object().is_some_and(|events| {
+            events.values().all(|groups| {
+                groups.as_array().is_some_and(|groups| {
+                    groups
+                        .iter()
+                        .all(|group| group.get(HOOKS_KEY).is_some_and(Value::is_array))
+                })
+            })
+        });
+        if !valid {
+            return Err(invalid(format!(
+                "'{}' must map each event to a list of {{matcher, hooks: [...]}} groups",
+                HOOKS_KEY
+            )));
+        }
+    }
+
+    if !permissions.is_empty() {
+        settings.insert(PERMISSIONS_KEY.to_string(), Value::Object(permissions));
+    }
+    Ok(settings)
+}
+
+/// Merge fragments into a single pretty-printed settings document. Keys listed
+/// in `strategies` are merged with their strategy instead of the defaults.
+pub fn compose_claude_settings(
+    sources: &[ComposedSource],
+    strategies: &BTreeMap<String, MergeStrategy>,
+) -> Result<String> {
+    if sources.is_empty() {
+        return Err(ApsError::ComposeError {
+            message: "No sources provided for composition".to_string(),
+        });
+    }
+
+    let mut settings = Map::new();
+    let mut permissions = Map::new();
+    let mut env = Map::new();
+    let mut hooks = Map::new();
+    let mut lists: Vec<(&str, Vec<Value>)> =
+        PERMISSION_LISTS.iter().map(|l| (*l, Vec::new())).collect();
+
+    let mut strategy_merge = StrategyMerge::new(strategies);
+
+    for source in sources {
+        let mut fragment = read_permission_fragment(source)?;
+        strategy_merge.take(&mut fragment, &source.path)?;
+        for (key, value) in fragment {
+            match (key.as_str(), value) {
+                (PERMISSIONS_KEY, Value::Object(fragment_permissions)) => {
+                    for (key, value) in fragment_permissions {
+                        match lists.iter_mut().find(|(list, _)| *list == key) {
+                            Some((_, merged)) => {
+                                for item in value.as_array().into_iter().flatten() {
+                                    if !merged.contains(item) {
+                                        merged.push(item.clone());
+                                    }
+                                }
+                            }
+                            None => {
+                                permissions.insert(key, value);
+                            }
+                        }
+                    }
+                }
+                (ENV_KEY, Value::Object(vars)) => env.extend(vars),
+                (HOOKS_KEY, Value::Object(events)) => merge_hooks(&mut hooks, events),
+                (_, value) => {
+                    settings.insert(key, value);
+                }
+            }
+        }
+    }
+
+    for (list, items) in lists {
+        if !items.is_empty() {
+            permissions.insert(list.to_string(), Value::Array(items));
+        }
+    }
+    if !permissions.is_empty() {
+        settings.insert(PERMISSIONS_KEY.to_string(), Value::Object(permissions));
+    }
+    if !env.is_empty() {
+        settings.insert(ENV_KEY.to_string(), Value::Object(env));
+    }
+    if !hooks.is_empty() {
+        settings.insert(HOOKS_KEY.to_string(), Value::Object(hooks));
+    }
+    strategy_merge.apply(&mut settings);
+
+    let mut content = serde_json::to_string_pretty(&Value::Object(settings)).map_err(|e| {
+        ApsError::ComposeError {
+            message: format!("Failed to serialize Claude settings: {}", e),
+        }
+    })?;
+    content.push('\n');
+    Ok(content)
+}
+
+/// Built-in Claude Code tools that permission rules can name
+const KNOWN_TOOLS: &[&str] = &[
+    "Bash",
+    "BashOutput",
+    "Edit",
+    "ExitPlanMode",
+    "Glob",
+    "Grep",
+    "KillShell",
+    "LS",
+    "MultiEdit",
+    "NotebookEdit",
+    "NotebookRead",
+    "Read",
+    "SlashCommand",
+    "Skill",
+    "Task",
+    "TodoWrite",
+    "WebFetch",
+    "WebSearch",
+    "Write",
+];
+
+/// Check a permission rule against the forms Claude Code understands:
+/// bare tool names, `Tool(specifier)`, and `mcp__server[__tool]`
+pub fn check_permission_pattern(pattern: &str) -> std::result::Result<(), String> {
+    if let Some(rest) = pattern.strip_prefix("mcp__") {
+        let mut parts = rest.split("__");
+        let server = parts.next().unwrap_or_default();
+        let tool = parts.next();
+        let valid_name = |name: &str| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "-_*".contains(c))
+        };
+        return if !valid_name(server)
+            || tool.is_some_and(|t| !valid_name(t))
+            || parts.next().is_some()
+        {
+            Err("expected mcp__<server> or mcp__<server>__<tool>".to_string())
+        } else {
+            Ok(())
+        };
+    }
+
+    let (tool, spec) = match pattern.split_once('(') {
+        Some((tool, rest)) => match rest.strip_suffix(')') {
+            Some(spec) => (tool, Some(spec)),
+            None => return Err("missing closing ')'".to_string()),
+        },
+        None if pattern.contains(')') => return Err("unexpected ')'".to_string()),
+        None => (pattern, None),
+    };
+
+    if !KNOWN_TOOLS.contains(&tool) {
+        let suggestion = KNOWN_TOOLS
+            .iter()
+            .find(|known| known.eq_ignore_ascii_case(tool.trim()))
+            .map(|known| format!(" (did you mean '{}'?)", known))
+            .unwrap_or_default();
+        return Err(format!("unknown tool '{}'{}", tool, suggestion));
+    }
+
+    let Some(spec) = spec else {
+        return Ok(());
+    };
+    if spec.trim().is_empty() {
+        return Err("empty specifier; use the bare tool name to match every call".to_string());
+    }
+    if spec.contains('(') || spec.contains(')') {
+        return Err("unbalanced parentheses".to_string());
+    }
+    match tool {
+        "Bash" if spec.find(":*").is_some_and(|i| i + 2 != spec.len()) => {
+            Err("':*' prefix matching is only allowed at the end of a Bash rule".to_string())
+        }
+        "WebFetch" if !spec.starts_with("domain:") => {
+            Err("WebFetch rules must use 'WebFetch(domain:<host>)'".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Messages for every malformed allow/deny/ask rule in a fragment
+pub fn invalid_permission_patterns(source: &ComposedSource) -> Result<Vec<String>> {
+    let fragment = read_permission_fragment(source)?;
+    let mut invalid = Vec::new();
+    let Some(permissions) = fragment.get(PERMISSIONS_KEY) else {
+        return Ok(invalid);
+    };
+
+    for list in ["allow", "deny", "ask"] {
+        for pattern in permissions
+            .get(list)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if let Err(reason) = check_permission_pattern(pattern) {
+                invalid.push(format!(
+                    "invalid permission '{}' in {} of {}: {}",
+                    pattern,
+                    list,
+                    fragment_name(source),
+                    reason
+                ));
+            }
+        }
+    }
+    Ok(invalid)
+}
+
+/// A permission rule and the fragments that contributed it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionRule {
+    /// `allow`, `deny` or `ask`
+    pub list: &'static str,
+    pub pattern: String,
+    pub fragments: Vec<String>,
+}
+
+/// Fragment name for reports
+fn fragment_name(source: &ComposedSource) -> String {
+    source
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| source.path.display().to_string())
+}
+
+/// Every allow/deny/ask rule with the fragments that declare it
+pub fn permission_provenance(sources: &[ComposedSource]) -> Result<Vec<PermissionRule>> {
+    let mut rules: Vec<PermissionRule> = Vec::new();
+
+    for source in sources {
+        let fragment = read_permission_fragment(source)?;
+        let Some(permissions) = fragment.get(PERMISSIONS_KEY) else {
+            continue;
+        };
+        for list in ["allow", "deny", "ask"] {
+            for pattern in permissions
+                .get(list)
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                let name = fragment_name(source);
+                match rules
+                    .iter_mut()
+                    .find(|r| r.list == list && r.pattern == pattern)
+                {
+                    Some(rule) if !rule.fragments.contains(&name) => rule.fragments.push(name),
+                    Some(_) => {}
+                    None => rules.push(PermissionRule {
+                        list,
+                        pattern: pattern.to_string(),
+                        fragments: vec![name],
+                    }),
+                }
+            }
+        }
+    }
+
+    rules.sort_by_key(|r| PERMISSION_LISTS.iter().position(|l| *l == r.list));
+    Ok(rules)
+}
+
+/// Whether a deny pattern blocks everything an allow/ask pattern grants.
+///
+/// `Tool` covers every `Tool(...)` rule, and a specifier ending in `*`
+/// (`Read(./secrets/**)`) covers specifiers with that prefix. A `:*`
+/// command prefix (`Bash(rm:*)`) only covers the whole command: `rm` or
+/// `rm -rf build`, not `rmdir`.
+pub fn pattern_covers(deny: &str, pattern: &str) -> bool {
+    if deny == pattern {
+        return true;
+    }
+    let split = |p: &str| match p.split_once('(') {
+        Some((tool, spec)) => (tool.to_string(), spec.strip_suffix(')').map(str::to_string)),
+        None => (p.to_string(), None),
+    };
+    let (deny_tool, deny_spec) = split(deny);
+    let (tool, spec) = split(pattern);
+    if deny_tool != tool {
+        return false;
+    }
+    match (deny_spec, spec) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(deny_spec), Some(spec)) => {
+            if let Some(command) = deny_spec.strip_suffix(":*") {
+                spec.strip_prefix(command)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with([':', ' ']))
+            } else {
+                deny_spec.ends_with('*') && spec.starts_with(deny_spec.trim_end_matches('*'))
+            }
+        }
+    }
+}
+
+/// Allow/ask rules that a deny rule blocks, one message per conflict
+pub fn permission_conflicts(rules: &[PermissionRule]) -> Vec<String> {
+    let mut conflicts = Vec::new();
+    for rule in rules.iter().filter(|r| r.list != "deny") {
+        for deny in rules
+            .iter()
+            .filter(|r| r.list == "deny" && pattern_covers(&r.pattern, &rule.pattern))
+        {
+            let blocked_by = if deny.pattern == rule.pattern {
+                String::new()
+            } else {
+                format!(" '{}'", deny.pattern)
+            };
+            conflicts.push(format!(
+                "'{}' in {} ({}) is blocked by deny{} ({})",
+                rule.pattern,
+                rule.list,
+                rule.fragments.join(", "),
+                blocked_by,
+                deny.fragments.join(", ")
+            ));
+        }
+    }
+    conflicts
+}
+
+/// Human-readable report of where each permission rule came from
+pub fn explain_permissions(rules: &[PermissionRule]) -> String {
+    let width = rules.iter().map(|r| r.pattern.len()).max().unwrap_or(0);
+    let mut report = String::new();
+    let mut current = "";
+
+    for rule in rules {
+        if rule.list != current {
+            report.push_str(&format!("  {}\n", rule.list));
+            current = rule.list;
+        }
+        report.push_str(&format!(
+            "    {:width$}  {}\n",
+            rule.pattern,
+            rule.fragments.join(", "),
+            width = width
+        ));
+    }
+
+    let conflicts = permission_conflicts(rules);
+    if !conflicts.is_empty() {
+        report.push_str("  conflicts\n");
+        for conflict in conflicts {
+            report.push_str(&format!("    {}\n", conflict));
+        }
+    }
+    report
+}
+
+/// Append a fragment's hook groups to the merged hooks, combining groups that
+/// share a matcher
+fn merge_hooks(merged: &mut Map<String, Value>, events: Map<String, Value>) {
+    for (event, groups) in events {
+        let Value::Array(merged_groups) = merged
+            .entry(event)
+            .or_insert_with(|| Value::Array(Vec::new()))
+        else {
+            continue;
+        };
+        for group in groups.as_array().into_iter().flatten() {
+            let existing = merged_groups
+                .iter_mut()
+                .find(|g| g.get("matcher") == group.get("matcher"));
+            match existing.and_then(|g| g.get_mut(HOOKS_KEY)?.as_array_mut()) {
+                Some(existing_hooks) => {
+                    for hook in group[HOOKS_KEY].as_array().into_iter().flatten() {
+                        if !existing_hooks.contains(hook) {
+                            existing_hooks.push(hook.clone());
+                        }
+                    }
+                }
+                None => merged_groups.push(group.clone()),
+            }
+        }
+    }
+}
+
+/// A script referenced by a hook command, installed next to the settings file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookScript {
+    /// Script in the fragment's source directory
+    pub source: PathBuf,
+    /// Path relative to the settings file's directory (e.g. `hooks/check.sh`)
+    pub path: PathBuf,
+}
+
+/// Find the scripts referenced by each fragment's hook commands. Scripts are
+/// looked up relative to the fragment's directory; missing scripts are
+/// reported as warnings (errors with `strict`), since they may already exist
+/// in the project.
+pub fn collect_hook_scripts(
+    sources: &[ComposedSource],
+    strict: bool,
+) -> Result<(Vec<HookScript>, Vec<String>)> {
+    let mut scripts = Vec::new();
+    let mut warnings = Vec::new();
+
+    for source in sources {
+        let fragment = read_permission_fragment(source)?;
+        let Some(hooks) = fragment.get(HOOKS_KEY) else {
+            continue;
+        };
+        let hooks = serde_yaml::to_value(hooks).map_err(|e| ApsError::InvalidClaudeSettings {
+            path: source.path.clone(),
+            message: e.to_string(),
+        })?;
+        let root = source.path.parent().unwrap_or(Path::new("."));
+
+        for path in referenced_hook_scripts(&hooks) {
+            let script = root.join(&path);
+            if script.is_file() {
+                scripts.push(HookScript {
+                    source: script,
+                    path,
+                });
+            } else if strict {
+                return Err(ApsError::HookScriptNotFound { path: script });
+            } else {
+                warnings.push(ApsError::HookScriptNotFound { path: script }.to_string());
+            }
+        }
+    }
+
+    Ok((scripts, warnings))
+}
+
+/// Merge `managed` into `existing`. Objects are merged key by key and
+/// arrays are unioned, keeping existing items first; any other managed
+/// value replaces the existing one.
+pub fn merge_settings(existing: &mut Value, managed: Value) {
+    match (existing, managed) {
+        (Value::Object(existing), Value::Object(managed)) => {
+            for (key, value) in managed {
+                match existing.get_mut(&key) {
+                    Some(current) => merge_settings(current, value),
+                    None => {
+                        existing.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(existing), Value::Array(managed)) => {
+            for item in managed {
+                if !existing.contains(&item) {
+                    existing.push(item);
+                }
+            }
+        }
+        (existing, managed) => *existing = managed,
+    }
+}
+
+/// A settings key or array item written by a `claude_settings` entry, as
+/// recorded in the lockfile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManagedKey {
+    /// Object keys leading to the value, from the top of the document
+    pub path: Vec<String>,
+
+    /// The item, when the value at `path` is an array
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item: Option<Value>,
+}
+
+/// The keys and array items a composed settings document sets: one for each
+/// value that isn't an object, and one for each array item
+pub fn managed_keys(content: &str) -> Vec<ManagedKey> {
+    fn collect(value: &Value, path: &mut Vec<String>, keys: &mut Vec<ManagedKey>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    path.push(key.clone());
+                    collect(value, path, keys);
+                    path.pop();
+                }
+            }
+            Value::Array(items) => keys.extend(items.iter().map(|item| ManagedKey {
+                path: path.clone(),
+                item: Some(item.clone()),
+            })),
+            _ => keys.push(ManagedKey {
+                path: path.clone(),
+                item: None,
+            }),
+        }
+    }
+
+    let mut keys = Vec::new();
+    if let Ok(value) = serde_json::from_str::<Value>(content) {
+        collect(&value, &mut Vec::new(), &mut keys);
+    }
+    keys
+}
+
+/// Remove a key or array item recorded by [`managed_keys`] from `settings`,
+/// along with any arrays and objects it leaves empty
+fn remove_managed_key(settings: &mut Value, key: &ManagedKey) {
+    fn remove(value: &mut Value, path: &[String], item: Option<&Value>) {
+        let Some((first, rest)) = path.split_first() else {
+            return;
+        };
+        let Some(map) = value.as_object_mut() else {
+            return;
+        };
+        if !rest.is_empty() {
+            if let Some(child) = map.get_mut(first) {
+                remove(child, rest, item);
+                if child.as_object().is_some_and(|m| m.is_empty()) {
+                    map.remove(first);
+                }
+            }
+            return;
+        }
+        match item {
+            Some(item) => {
+                if let Some(Value::Array(items)) = map.get_mut(first) {
+                    items.retain(|i| i != item);
+                    if items.is_empty() {
+                        map.remove(first);
+                    }
+                }
+            }
+            None => {
+                map.remove(first);
+            }
+        }
+    }
+
+    remove(settings, &key.path, key.item.as_ref());
+}
+
+/// Settings already at `path`, if it is a regular file holding a JSON object
+pub fn read_existing_settings(path: &Path) -> Option<Value> {
+    let is_file = path
+        .symlink_metadata()
+        .map(|m| m.file_type().is_file())
+        .unwrap_or(false);
+    if !is_file {
+        return None;
+    }
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+        .filter(Value::is_object)
+}
+
+/// Write composed settings to the destination file, deep-merging them into
+/// existing settings so keys aps doesn't manage are kept. Keys and array
+/// items in `previous_keys` (written by the last sync) that the composed
+/// settings no longer set are removed first.
+pub fn write_settings_file(dest: &Path, content: &str, previous_keys: &[ManagedKey]) -> Result<()> {
+    let managed: Value = serde_json::from_str(content).map_err(|e| ApsError::ComposeError {
+        message: format!("Composed Claude settings are not valid JSON: {}", e),
+    })?;
+
+    let content = match read_existing_settings(dest) {
+        Some(mut existing) => {
+            debug!("Merging managed settings into {:?}", dest);
+            let current = managed_keys(content);
+            for key in previous_keys.iter().filter(|k| !current.contains(k)) {
+                debug!(
+                    "Removing setting {} no longer in any fragment",
+                    key.path.join(".")
+                );
+                remove_managed_key(&mut existing, key);
+            }
+            merge_settings(&mut existing, managed);
+            let mut merged =
+                serde_json::to_string_pretty(&existing).map_err(|e| ApsError::ComposeError {
+                    message: format!("Failed to serialize Claude settings: {}", e),
+                })?;
+            merged.push('\n');
+            merged
+        }
+        None => content.to_string(),
+    };
+
+    // Never write through a symlink left by a previous install
+    if dest
+        .symlink_metadata()
+        .is_ok_and(|m| m.file_type().is_symlink())
+    {
+        std::fs::remove_file(dest)
+            .map_err(|e| ApsError::io(e, format!("Failed to remove symlink {:?}", dest)))?;
+    }
+
+    if let Some(parent) = dest.parent() {
+        if !parent.exists() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                ApsError::io(e, format!("Failed to create directory: {:?}", parent))
+            })?;
+        }
+    }
+
+    std::fs::write(dest, content)
+        .map_err(|e| ApsError::io(e, format!("Failed to write settings file: {:?}", dest)))?;
+    info!("Wrote Claude settings to {:?}", dest);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compose(sources: &[ComposedSource]) -> Value {
+        serde_json::from_str(&compose_claude_settings(sources, &BTreeMap::new()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_compose_unions_permission_lists() {
+        let result = compose(&[
+            ComposedSource::fragment(
+                "base.yaml",
+                "allow: [\"Bash(git status)\", Read]\ndeny: [\"Bash(rm:*)\"]\n",
+            ),
+            ComposedSource::fragment(
+                "team.yaml",
+                "permissions:\n  allow: [Read, \"Bash(npm test)\"]\n  ask: [\"Bash(git push:*)\"]\n  additionalDirectories: [../shared]\n",
+            ),
+        ]);
+
+        assert_eq!(
+            result["permissions"]["allow"],
+            serde_json::json!(["Bash(git status)", "Read", "Bash(npm test)"])
+        );
+        assert_eq!(
+            result["permissions"]["deny"],
+            serde_json::json!(["Bash(rm:*)"])
+        );
+        assert_eq!(
+            result["permissions"]["ask"],
+            serde_json::json!(["Bash(git push:*)"])
+        );
+        assert_eq!(
+            result["permissions"]["additionalDirectories"],
+            serde_json::json!(["../shared"])
+        );
+    }
+
+    #[test]
+    fn test_compose_settings_keys_last_wins() {
+        let result = compose(&[
+            ComposedSource::fragment(
+                "base.yaml",
+                "model: sonnet\nincludeCoAuthoredBy: true\nenv:\n  CI: \"1\"\n  LOG: info\npermissions:\n  defaultMode: plan\n",
+            ),
+            ComposedSource::fragment(
+                "team.yaml",
+                "model: opus\nenv:\n  LOG: debug\npermissions:\n  defaultMode: acceptEdits\n",
+            ),
+        ]);
+
+        assert_eq!(result["model"], "opus");
+        assert_eq!(result["includeCoAuthoredBy"], true);
+        assert_eq!(
+            result["env"],
+            serde_json::json!({"CI": "1", "LOG": "debug"})
+        );
+        assert_eq!(result["permissions"]["defaultMode"], "acceptEdits");
+    }
+
+    #[test]
+    fn test_rejects_invalid_fragments() {
+        for content in [
+            "- allow",
+            "allow: Bash",
+            "permissions: [Read]",
+            "deny: [1, 2]",
+            "env: [CI]",
+            "hooks:\n  PreToolUse: Bash",
+        ] {
+            let err = compose_claude_settings(
+                &[ComposedSource::fragment("bad.yaml", content)],
+                &BTreeMap::new(),
+            )
+            .unwrap_err();
+            assert!(
+                matches!(err, ApsError::InvalidClaudeSettings { .. }),
+                "{}",
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_settings_keeps_unmanaged_keys_and_unions_arrays() {
+        let mut existing = serde_json::json!({
+            "statusLine": {"type": "command", "command": "status.sh"},
+            "model": "haiku",
+            "permissions": {"allow": ["Local"], "defaultMode": "plan"},
+            "hooks": {"Stop": []}
+        });
+        merge_settings(
+            &mut existing,
+            serde_json::json!({
+                "model": "opus",
+                "permissions": {"allow": ["Read", "Local"], "deny": ["Bash(rm:*)"]}
+            }),
+        );
+
+        assert_eq!(
+            existing,
+            serde_json::json!({
+                "statusLine": {"type": "command", "command": "status.sh"},
+                "model": "opus",
+                "permissions": {
+                    "allow": ["Local", "Read"],
+                    "deny": ["Bash(rm:*)"],
+                    "defaultMode": "plan"
+                },
+                "hooks": {"Stop": []}
+            })
+        );
+    }
+
+    #[test]
+    fn test_write_settings_file_merges_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join(".claude/settings.json");
+        std::fs::create_dir_all(dest.parent().unwrap()).unwrap();
+        std::fs::write(
+            &dest,
+            r#"{"statusLine": {"type": "command"}, "model": "haiku"}"#,
+        )
+        .unwrap();
+
+        write_settings_file(&dest, "{\n  \"model\": \"opus\"\n}\n", &[]).unwrap();
+
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&dest).unwrap()).unwrap();
+        assert_eq!(
+            written,
+            serde_json::json!({"statusLine": {"type": "command"}, "model": "opus"})
+        );
+    }
+
+    #[test]
+    fn test_write_settings_file_removes_keys_dropped_from_fragments() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("settings.json");
+        let first = r#"{"model": "opus", "env": {"CI": "1"}, "permissions": {"allow": ["Read", "Bash(ls:*)"]}}"#;
+        let keys = managed_keys(first);
+        assert_eq!(
+            serde_json::to_value(&keys).unwrap(),
+            serde_json::json!([
+                {"path": ["env", "CI"]},
+                {"path": ["model"]},
+                {"path": ["permissions", "allow"], "item": "Read"},
+                {"path": ["permissions", "allow"], "item": "Bash(ls:*)"},
+            ])
+        );
+        std::fs::write(
+            &dest,
+            r#"{"model": "opus", "env": {"CI": "1"}, "theme": "dark", "permissions": {"allow": ["Local", "Read", "Bash(ls:*)"]}}"#,
+        )
+        .unwrap();
+
+        // The fragments dropped `model`, `env.CI` and one permission
+        write_settings_file(&dest, r#"{"permissions": {"allow": ["Read"]}}"#, &keys).unwrap();
+
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&dest).unwrap()).unwrap();
+        assert_eq!(
+            written,
+            serde_json::json!({"theme": "dark", "permissions": {"allow": ["Local", "Read"]}})
+        );
+    }
+
+    #[test]
+    fn test_write_settings_file_removes_dotted_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("settings.json");
+        let first = r#"{"env": {"otel.endpoint": "http://collector"}}"#;
+        std::fs::write(
+            &dest,
+            r#"{"env": {"otel.endpoint": "http://collector", "otel": {"endpoint": "local"}}}"#,
+        )
+        .unwrap();
+
+        write_settings_file(&dest, "{}", &managed_keys(first)).unwrap();
+
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&dest).unwrap()).unwrap();
+        assert_eq!(
+            written,
+            serde_json::json!({"env": {"otel": {"endpoint": "local"}}})
+        );
+    }
+
+    #[test]
+    fn test_compose_merges_hooks_by_matcher() {
+        let result = compose(&[
+            ComposedSource::fragment(
+                "base.yaml",
+                "hooks:\n  PreToolUse:\n    - matcher: Bash\n      hooks:\n        - type: command\n          command: .claude/hooks/check-bash.sh\n",
+            ),
+            ComposedSource::fragment(
+                "team.yaml",
+                "hooks:\n  PreToolUse:\n    - matcher: Bash\n      hooks:\n        - type: command\n          command: .claude/hooks/check-bash.sh\n        - type: command\n          command: .claude/hooks/audit.sh\n    - matcher: Edit\n      hooks:\n        - type: command\n          command: cargo fmt\n",
+            ),
+        ]);
+
+        let groups = result["hooks"]["PreToolUse"].as_array().unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0]["matcher"], "Bash");
+        assert_eq!(groups[0]["hooks"].as_array().unwrap().len(), 2);
+        assert_eq!(groups[1]["matcher"], "Edit");
+    }
+
+    #[test]
+    fn test_collect_hook_scripts() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("hooks")).unwrap();
+        std::fs::write(dir.path().join("hooks/check-bash.sh"), "#!/bin/sh\n").unwrap();
+        let source = ComposedSource::fragment(
+            dir.path().join("base.yaml").to_str().unwrap(),
+            "hooks:\n  PreToolUse:\n    - matcher: Bash\n      hooks:\n        - type: command\n          command: '\"$CLAUDE_PROJECT_DIR\"/.claude/hooks/check-bash.sh'\n        - type: command\n          command: .claude/hooks/missing.sh\n",
+        );
+
+        let (scripts, warnings) =
+            collect_hook_scripts(std::slice::from_ref(&source), false).unwrap();
+        assert_eq!(
+            scripts,
+            vec![HookScript {
+                source: dir.path().join("hooks/check-bash.sh"),
+                path: PathBuf::from("hooks/check-bash.sh"),
+            }]
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("missing.sh"));
+
+        assert!(matches!(
+            collect_hook_scripts(&[source], true),
+            Err(ApsError::HookScriptNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_pattern_covers() {
+        assert!(pattern_covers("Bash(rm:*)", "Bash(rm -rf build)"));
+        assert!(pattern_covers("Bash", "Bash(git status)"));
+        assert!(pattern_covers(
+            "Read(./secrets/**)",
+            "Read(./secrets/prod.env)"
+        ));
+        assert!(pattern_covers("WebFetch", "WebFetch"));
+        assert!(!pattern_covers("Bash(rm:*)", "Bash(git status)"));
+        assert!(pattern_covers("Bash(rm:*)", "Bash(rm)"));
+        assert!(pattern_covers("Bash(rm:*)", "Bash(rm:*)"));
+        assert!(pattern_covers("Bash(rm:*)", "Bash(rm:-rf)"));
+        assert!(!pattern_covers("Bash(rm:*)", "Bash(rmdir build)"));
+        assert!(!pattern_covers("Bash(rm:*)", "Bash(rmdir:*)"));
+        assert!(!pattern_covers("Bash(rm -rf build)", "Bash(rm -rf dist)"));
+        assert!(!pattern_covers("Bash(git push)", "Bash"));
+        assert!(!pattern_covers("Read", "Edit(src/**)"));
+    }
+
+    #[test]
+    fn test_permission_provenance_and_conflicts() {
+        let rules = permission_provenance(&[
+            ComposedSource::fragment(
+                "dev/base.yaml",
+                "allow: [Read, \"Bash(rm -rf build)\"]\nask: [\"Bash(git push:*)\"]\n",
+            ),
+            ComposedSource::fragment(
+                "sec/team.yaml",
+                "allow: [Read]\ndeny: [\"Bash(rm:*)\", \"Bash(git push:*)\"]\n",
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(rules[0].pattern, "Read");
+        assert_eq!(rules[0].fragments, vec!["base.yaml", "team.yaml"]);
+        assert_eq!(
+            permission_conflicts(&rules),
+            vec![
+                "'Bash(rm -rf build)' in allow (base.yaml) is blocked by deny 'Bash(rm:*)' (team.yaml)",
+                "'Bash(git push:*)' in ask (base.yaml) is blocked by deny (team.yaml)",
+            ]
+        );
+
+        let report = explain_permissions(&rules);
+        assert!(report.contains("  allow\n    Read                base.yaml, team.yaml\n"));
+        assert!(report.contains("  conflicts\n"));
+    }
+
+    #[test]
+    fn test_check_permission_pattern() {
+        for valid in [
+            "Bash",
+            "Bash(git status)",
+            "Bash(npm run test:*)",
+            "Read(./src/**)",
+            "WebFetch(domain:docs.rs)",
+            "mcp__github",
+            "mcp__github__create_issue",
+        ] {
+            assert!(check_permission_pattern(valid).is_ok(), "{}", valid);
+        }
+
+        for (invalid, reason) in [
+            ("Bash(git push*", "missing closing ')'"),
+            ("bash(ls)", "did you mean 'Bash'"),
+            ("Shell", "unknown tool 'Shell'"),
+            ("Bash()", "empty specifier"),
+            ("Bash(git:* push)", "only allowed at the end"),
+            ("WebFetch(docs.rs)", "domain:"),
+            ("mcp__", "mcp__<server>"),
+            ("Read)", "unexpected ')'"),
+        ] {
+            let err = check_permission_pattern(invalid).unwrap_err();
+            assert!(err.contains(reason), "{}: {}", invalid, err);
+        }
+    }
+
+    #[test]
+    fn test_invalid_permission_patterns() {
+        let invalid = invalid_permission_patterns(&ComposedSource::fragment(
+            "base.yaml",
+            "allow: [Read, \"Bash(git push*\"]\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            invalid,
+            vec!["invalid permission 'Bash(git push*' in allow of base.yaml: missing closing ')'"]
+        );
+    }
+
+    #[test]
+    fn test_reads_json_and_jsonc_fragments() {
+        let result = compose(&[
+            ComposedSource::fragment(
+                "upstream/settings.json",
+                r#"{"permissions": {"allow": ["Read"]}, "model": "sonnet"}"#,
+            ),
+            ComposedSource::fragment(
+                "team.jsonc",
+                "{\n  // Team defaults\n  \"permissions\": {\"deny\": [\"Bash(rm:*)\",]},\n}\n",
+            ),
+        ]);
+        assert_eq!(result["permissions"]["allow"], serde_json::json!(["Read"]));
+        assert_eq!(
+            result["permissions"]["deny"],
+            serde_json::json!(["Bash(rm:*)"])
+        );
+        assert_eq!(result["model"], "sonnet");
+
+        // Comments are only accepted in .jsonc fragments
+        let sources = [ComposedSource::fragment(
+            "settings.json",
+            "{\n  // comment\n  \"model\": \"opus\"\n}",
+        )];
+        let err = compose_claude_settings(&sources, &BTreeMap::new()).unwrap_err();
+        assert!(matches!(err, ApsError::InvalidClaudeSettings { .. }));
+    }
+
+    #[test]
+    fn test_compose_with_merge_strategies() {
+        let sources = [
+            ComposedSource::fragment("base.yaml", "allow: [Read, Edit]\nenv:\n  LOG: info\n"),
+            ComposedSource::fragment("team.yaml", "allow: [Read]\nenv:\n  CI: \"1\"\n"),
+        ];
+        let strategies = BTreeMap::from([
+            ("permissions.allow".to_string(), MergeStrategy::Replace),
+            ("env".to_string(), MergeStrategy::Replace),
+        ]);
+
+        let result: Value =
+            serde_json::from_str(&compose_claude_settings(&sources, &strategies).unwrap()).unwrap();
+        assert_eq!(result["permissions"]["allow"], serde_json::json!(["Read"]));
+        assert_eq!(result["env"], serde_json::json!({"CI": "1"}));
+    }
+}
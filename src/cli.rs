@@ -16,6 +16,16 @@ pub struct Cli {
     /// Enable verbose logging output
     #[arg(short, long, global = true)]
     pub verbose: bool,
+
+    /// Also write logs to this file, at debug level regardless of --verbose
+    /// (appended to if it exists)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Format of log lines: in the log file if --log-file is given, on the
+    /// console otherwise
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub log_format: OutputFormat,
 }
 
 #[derive(Subcommand, Debug)]
@@ -29,17 +39,54 @@ pub enum Commands {
     /// Sync and install assets from manifest sources
     Sync(SyncArgs),
 
+    /// Compute what sync would change without writing anything
+    Plan(PlanArgs),
+
+    /// Apply a plan written by `aps plan --out`
+    Apply(ApplyArgs),
+
+    /// Take over existing files at an entry's destination without
+    /// overwriting them
+    Adopt(AdoptArgs),
+
     /// Validate manifest and sources
     Validate(ValidateArgs),
 
+    /// Print the JSON Schema of an aps file, for editor integration
+    Schema(SchemaArgs),
+
     /// Display status from lockfile
     Status(StatusArgs),
 
+    /// Check installed files against the lockfile for local modifications
+    Verify(VerifyArgs),
+
+    /// Show which entry installed a file
+    Why(WhyArgs),
+
+    /// Show how an entry resolves without installing it
+    Explain(ExplainArgs),
+
+    /// Show the files aps manages, grouped by entry
+    Tree(TreeArgs),
+
+    /// Lockfile operations
+    Lock(LockArgs),
+
+    /// Backup operations
+    Backup(BackupArgs),
+
     /// List manifest entries and their resources
     List(ListArgs),
 
     /// Catalog operations for asset discovery
     Catalog(CatalogArgs),
+
+    /// Suggest catalog entries for this project
+    Suggest(SuggestArgs),
+
+    /// Show the audit log of syncs, upgrades and removals
+    Log(LogArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -59,6 +106,8 @@ pub struct AddArgs {
     /// Supports: GitHub URLs (https://github.com/owner/repo/...) and local
     /// paths ($HOME/skills, ~/skills, ./skills). For repo-level URLs or
     /// directories without SKILL.md, discovers skills and prompts for selection.
+    /// Anything else is looked up as a catalog entry ID, with `ID@VERSION`
+    /// picking a version.
     #[arg(value_name = "URL_OR_PATH")]
     pub url: String,
 
@@ -85,6 +134,14 @@ pub struct AddArgs {
     /// Skip confirmation prompts
     #[arg(long, short = 'y')]
     pub yes: bool,
+
+    /// Answer no to confirmation prompts
+    #[arg(long, conflicts_with_all = ["yes", "fail_on_prompt"])]
+    pub no: bool,
+
+    /// Fail instead of showing a prompt, even on a terminal
+    #[arg(long, conflicts_with = "yes")]
+    pub fail_on_prompt: bool,
 }
 
 #[derive(ValueEnum, Clone, Debug, Default)]
@@ -121,6 +178,14 @@ pub struct SyncArgs {
     #[arg(long, short = 'y')]
     pub yes: bool,
 
+    /// Answer no to confirmation prompts: never overwrite or delete anything
+    #[arg(long, conflicts_with_all = ["yes", "fail_on_prompt"])]
+    pub no: bool,
+
+    /// Fail instead of showing a prompt, even on a terminal
+    #[arg(long, conflicts_with = "yes")]
+    pub fail_on_prompt: bool,
+
     /// Ignore manifest (v0: not implemented)
     #[arg(long, hide = true)]
     pub ignore_manifest: bool,
@@ -139,6 +204,62 @@ pub struct SyncArgs {
     /// Use --upgrade to fetch the latest versions and update the lockfile.
     #[arg(long, short = 'u')]
     pub upgrade: bool,
+
+    /// Install exactly what the lockfile records, without resolving remote
+    /// refs or writing the lockfile. Fails if the manifest and lockfile differ.
+    #[arg(long, alias = "locked", conflicts_with = "upgrade")]
+    pub frozen: bool,
+
+    /// Delete the installed files of entries removed from the manifest
+    /// (backed up to .aps-backups first), and links to files removed from
+    /// symlinked sources
+    #[arg(long)]
+    pub prune: bool,
+
+    /// Show a unified diff of changes to existing files before overwriting
+    /// them, and for every change with --dry-run (paged when long)
+    #[arg(long)]
+    pub diff: bool,
+
+    /// Reinstall entries even when the lockfile says they are up to date,
+    /// rewriting destinations that were changed by hand
+    #[arg(long)]
+    pub force: bool,
+
+    /// Run the `post_install` commands of entries that were installed
+    #[arg(long)]
+    pub allow_hooks: bool,
+
+    /// Three-way merge local edits to the managed region of instruction
+    /// files with upstream changes, instead of overwriting them
+    #[arg(long)]
+    pub merge: bool,
+
+    /// Remove sections of composite fragments that duplicate an earlier fragment
+    #[arg(long)]
+    pub dedupe: bool,
+
+    /// Print which fragment each Claude permission rule came from
+    #[arg(long)]
+    pub explain_permissions: bool,
+
+    /// Print how long each entry took to sync, slowest first
+    #[arg(long)]
+    pub timings: bool,
+
+    /// Review entries one at a time, choosing to install, skip or diff each
+    #[arg(long, conflicts_with_all = ["yes", "no", "fail_on_prompt", "dry_run"])]
+    pub interactive: bool,
+
+    /// Output format for the sync result. With `json`, other messages go to
+    /// stderr.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        conflicts_with_all = ["interactive", "diff"]
+    )]
+    pub format: OutputFormat,
 }
 
 #[derive(Parser, Debug)]
@@ -150,6 +271,73 @@ pub struct ValidateArgs {
     /// Treat warnings as errors
     #[arg(long)]
     pub strict: bool,
+
+    /// Clone git sources and check the paths, skills and hooks inside them,
+    /// instead of only checking that the remote has the ref
+    #[arg(long)]
+    pub deep: bool,
+
+    /// Check that relative links in synced markdown point at files that
+    /// will exist next to the installed copy (clones git sources)
+    #[arg(long)]
+    pub check_links: bool,
+
+    /// Only validate the entry with this ID (can be repeated)
+    #[arg(long = "only", value_name = "ID")]
+    pub only: Vec<String>,
+
+    /// Only validate entries whose manifest definition changed since the
+    /// last sync
+    #[arg(long)]
+    pub changed: bool,
+
+    /// Compose this composite entry and print the result instead of
+    /// validating, without installing anything
+    #[arg(long, value_name = "ID", conflicts_with_all = ["only", "changed", "report"])]
+    pub render: Option<String>,
+
+    /// Number of entries to validate at once
+    #[arg(long, short = 'j', default_value_t = crate::validate::DEFAULT_JOBS)]
+    pub jobs: usize,
+
+    /// Write a report of each entry's result to this file, for CI test and
+    /// code scanning UIs
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+
+    /// Format of the --report file (default: sarif for `.sarif` files, junit
+    /// otherwise)
+    #[arg(long, value_enum, requires = "report")]
+    pub report_format: Option<ReportFormat>,
+}
+
+/// Format of `aps validate --report`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// JUnit XML
+    Junit,
+    /// SARIF 2.1.0
+    Sarif,
+}
+
+#[derive(Parser, Debug)]
+pub struct SchemaArgs {
+    /// File to print the schema of
+    #[arg(value_enum)]
+    pub kind: SchemaKind,
+}
+
+/// Files aps has a JSON Schema for
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchemaKind {
+    /// The manifest (aps.yaml)
+    Manifest,
+    /// The lockfile (aps.lock.yaml or aps.lock.json)
+    Lockfile,
+    /// The catalog (aps.catalog.yaml)
+    Catalog,
+    /// Claude settings, as composed by claude_settings entries
+    ClaudeSettings,
 }
 
 #[derive(Parser, Debug)]
@@ -157,6 +345,111 @@ pub struct StatusArgs {
     /// Path to the manifest file
     #[arg(long)]
     pub manifest: Option<PathBuf>,
+
+    /// Exit nonzero if anything diverged: 2 for local drift, 3 for available
+    /// upgrades, 4 for a lockfile out of sync with the manifest, 1 for entries
+    /// past their max_age policy
+    #[arg(long)]
+    pub check: bool,
+
+    /// Don't contact git remotes to check for upgrades
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = StatusFormat::Text)]
+    pub format: StatusFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StatusFormat {
+    #[default]
+    Text,
+    Json,
+    Yaml,
+}
+
+#[derive(Parser, Debug)]
+pub struct VerifyArgs {
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct WhyArgs {
+    /// Installed file or directory to look up
+    pub path: PathBuf,
+
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct ExplainArgs {
+    /// Entry ID to explain
+    pub id: String,
+
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct TreeArgs {
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct LockArgs {
+    #[command(subcommand)]
+    pub command: LockCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LockCommands {
+    /// Show which entries changed between a committed lockfile and the current one
+    Diff(LockDiffArgs),
+
+    /// Rewrite a lockfile with merge conflict markers, keeping the newer side of each entry
+    Resolve(LockResolveArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct LockDiffArgs {
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Git revision to compare against
+    #[arg(long, default_value = "HEAD")]
+    pub rev: String,
+
+    /// Compare against this lockfile instead of a git revision
+    #[arg(long, value_name = "FILE", conflicts_with = "rev")]
+    pub against: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: OutputFormat,
+}
+
+#[derive(Parser, Debug)]
+pub struct LockResolveArgs {
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Keep our side of every conflicting entry
+    #[arg(long, conflicts_with = "theirs")]
+    pub ours: bool,
+
+    /// Keep their side of every conflicting entry
+    #[arg(long)]
+    pub theirs: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -170,6 +463,97 @@ pub struct ListArgs {
     pub assets: bool,
 }
 
+#[derive(Parser, Debug)]
+pub struct PlanArgs {
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Only plan specific entry IDs (can be repeated)
+    #[arg(long = "only")]
+    pub only: Vec<String>,
+
+    /// Plan an upgrade to the latest versions instead of the locked ones
+    #[arg(long, short = 'u')]
+    pub upgrade: bool,
+
+    /// Write the plan as JSON for `aps apply`
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct ApplyArgs {
+    /// Plan file written by `aps plan --out`
+    #[arg(value_name = "PLAN")]
+    pub plan: PathBuf,
+
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Run the post_install commands of entries that were installed
+    #[arg(long)]
+    pub allow_hooks: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct AdoptArgs {
+    /// Entry IDs whose destinations to adopt
+    #[arg(value_name = "ENTRY_ID", required = true)]
+    pub ids: Vec<String>,
+
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub struct LogArgs {
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Only show records affecting this entry ID
+    #[arg(long)]
+    pub entry: Option<String>,
+
+    /// Show only the most recent N records
+    #[arg(long, short = 'n')]
+    pub limit: Option<usize>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: OutputFormat,
+}
+
+#[derive(Parser, Debug)]
+pub struct BackupArgs {
+    #[command(subcommand)]
+    pub command: BackupCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BackupCommands {
+    /// List backups made before overwriting or deleting content
+    List(BackupListArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct BackupListArgs {
+    /// Only list backups of this path (relative to the manifest directory)
+    #[arg(value_name = "PATH")]
+    pub path: Option<String>,
+
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: OutputFormat,
+}
+
 #[derive(Parser, Debug)]
 pub struct CatalogArgs {
     #[command(subcommand)]
@@ -180,6 +564,29 @@ pub struct CatalogArgs {
 pub enum CatalogCommands {
     /// Generate a catalog from the manifest
     Generate(CatalogGenerateArgs),
+
+    /// List catalog entries
+    List(CatalogListArgs),
+
+    /// Search catalog entries by keyword
+    Search(CatalogSearchArgs),
+
+    /// Fetch remote catalogs again and refresh their cached copies
+    Update(CatalogUpdateArgs),
+
+    /// Show a catalog entry's details and versions
+    Info(CatalogInfoArgs),
+
+    /// Check catalog entries for missing descriptions, duplicate ids,
+    /// invalid versions and sources that don't exist
+    Lint(CatalogLintArgs),
+
+    /// Lint the catalog, bump its release and publish it
+    Publish(CatalogPublishArgs),
+
+    /// Show how often catalog entries were added and kept (opt in with
+    /// APS_TRACK_USAGE=1)
+    Stats(CatalogStatsArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -188,7 +595,260 @@ pub struct CatalogGenerateArgs {
     #[arg(long)]
     pub manifest: Option<PathBuf>,
 
-    /// Output path for the catalog file (default: aps.catalog.yaml next to manifest)
+    /// Scan a directory or git repository of skills, rules and AGENTS.md
+    /// partials instead of the manifest's entries
+    #[arg(long, value_name = "PATH_OR_REPO", conflicts_with = "manifest")]
+    pub from: Option<String>,
+
+    /// Output path for the catalog file (default: aps.catalog.yaml next to
+    /// manifest, or in the current directory with --from)
     #[arg(long, short)]
     pub output: Option<PathBuf>,
 }
+
+#[derive(Parser, Debug)]
+pub struct CatalogInfoArgs {
+    /// Catalog entry ID
+    #[arg(value_name = "ID")]
+    pub id: String,
+
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Path or git/HTTP(S) URL of the catalog (default: the manifest's
+    /// `catalogs`, else aps.catalog.yaml next to manifest)
+    #[arg(long, value_name = "PATH_OR_URL")]
+    pub catalog: Option<String>,
+
+    #[command(flatten)]
+    pub registry: RegistryArgs,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: OutputFormat,
+}
+
+#[derive(Parser, Debug)]
+pub struct CatalogLintArgs {
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Path or git/HTTP(S) URL of the catalog (default: the manifest's
+    /// `catalogs`, else aps.catalog.yaml next to manifest)
+    #[arg(long, value_name = "PATH_OR_URL")]
+    pub catalog: Option<String>,
+
+    #[command(flatten)]
+    pub registry: RegistryArgs,
+
+    /// Clone git sources to check that entry paths exist in them
+    #[arg(long)]
+    pub deep: bool,
+
+    /// Fail on warnings (missing descriptions) too
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: OutputFormat,
+}
+
+#[derive(Parser, Debug)]
+pub struct CatalogStatsArgs {
+    /// Show at most this many entries
+    #[arg(long)]
+    pub limit: Option<usize>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: OutputFormat,
+}
+
+#[derive(Parser, Debug)]
+pub struct CatalogPublishArgs {
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Path of the catalog file to publish (default: aps.catalog.yaml next
+    /// to manifest)
+    #[arg(long, value_name = "PATH")]
+    pub catalog: Option<PathBuf>,
+
+    /// Git repository, HTTP(S) URL or s3:// URL to publish to (default: the
+    /// catalog's `publish.to`)
+    #[arg(long, value_name = "URL")]
+    pub to: Option<String>,
+
+    /// Branch to push to, for git repositories
+    #[arg(long)]
+    pub branch: Option<String>,
+
+    /// Which part of the release to bump
+    #[arg(long, value_enum, default_value = "patch")]
+    pub bump: ReleaseBump,
+
+    /// Clone git sources to check entry paths while linting
+    #[arg(long)]
+    pub deep: bool,
+
+    /// Lint and show the release that would be published, without
+    /// publishing it
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReleaseBump {
+    Major,
+    Minor,
+    #[default]
+    Patch,
+}
+
+#[derive(Parser, Debug)]
+pub struct CatalogUpdateArgs {
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Git or HTTP(S) URL of a catalog to update (repeatable; default: the
+    /// remote catalogs in the manifest's `catalogs`)
+    #[arg(long, value_name = "URL")]
+    pub catalog: Vec<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Parser, Debug)]
+pub struct CatalogListArgs {
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Path or git/HTTP(S) URL of the catalog (default: the manifest's
+    /// `catalogs`, else aps.catalog.yaml next to manifest)
+    #[arg(long, value_name = "PATH_OR_URL")]
+    pub catalog: Option<String>,
+
+    #[command(flatten)]
+    pub registry: RegistryArgs,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: OutputFormat,
+
+    /// Maximum number of entries to return
+    #[arg(long)]
+    pub limit: Option<usize>,
+
+    /// Number of entries to skip before returning results
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+}
+
+#[derive(Parser, Debug)]
+pub struct CatalogSearchArgs {
+    /// Search query: keywords matched against name, id, tags, kind and
+    /// description, "quoted phrases", and tag:, kind:, id: or name: filters
+    #[arg(value_name = "QUERY")]
+    pub query: String,
+
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Path or git/HTTP(S) URL of the catalog (default: the manifest's
+    /// `catalogs`, else aps.catalog.yaml next to manifest)
+    #[arg(long, value_name = "PATH_OR_URL")]
+    pub catalog: Option<String>,
+
+    #[command(flatten)]
+    pub registry: RegistryArgs,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: OutputFormat,
+
+    /// Maximum number of results to return
+    #[arg(long)]
+    pub limit: Option<usize>,
+
+    /// Number of results to skip before returning results
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+}
+
+#[derive(Parser, Debug)]
+pub struct SuggestArgs {
+    /// What you are looking for (without one, suggests a starter set for the
+    /// project)
+    #[arg(value_name = "QUERY")]
+    pub query: Option<String>,
+
+    /// Path to the manifest file
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Path or git/HTTP(S) URL of the catalog (default: the manifest's
+    /// `catalogs`, else aps.catalog.yaml next to manifest)
+    #[arg(long, value_name = "PATH_OR_URL")]
+    pub catalog: Option<String>,
+
+    #[command(flatten)]
+    pub registry: RegistryArgs,
+
+    /// Don't look at the project's languages, frameworks and tooling
+    #[arg(long)]
+    pub no_detect: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: OutputFormat,
+
+    /// Maximum number of suggestions to return
+    #[arg(long, default_value_t = 10)]
+    pub limit: usize,
+
+    /// Pick suggestions to add to the manifest from a filterable list
+    #[arg(long, conflicts_with_all = ["format", "add", "add_top"])]
+    pub pick: bool,
+
+    /// Add these catalog entries to the manifest (comma-separated IDs, each
+    /// optionally `ID@VERSION`)
+    #[arg(
+        long,
+        value_name = "IDS",
+        value_delimiter = ',',
+        conflicts_with_all = ["format", "add_top"]
+    )]
+    pub add: Vec<String>,
+
+    /// Add the top N suggestions to the manifest
+    #[arg(long, value_name = "N", conflicts_with = "format")]
+    pub add_top: Option<usize>,
+}
+
+/// Options for reading a catalog from a remote registry
+#[derive(Parser, Debug, Default)]
+pub struct RegistryArgs {
+    /// URL of a remote catalog registry (cached locally for offline use)
+    #[arg(long, value_name = "URL", conflicts_with = "catalog")]
+    pub registry: Option<String>,
+
+    /// Refresh cached remote catalogs even if they are recent
+    #[arg(long, conflicts_with = "offline")]
+    pub refresh: bool,
+
+    /// Use cached remote catalogs without contacting them
+    #[arg(long)]
+    pub offline: bool,
+}
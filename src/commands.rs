@@ -1,26 +1,70 @@
-use crate::catalog::Catalog;
+use crate::audit::{self, audit_log_path, read_records};
+use crate::backup::{list_backups, Backup, BACKUP_DIR};
+use crate::catalog::{
+    Catalog, CatalogEntry, CatalogVersion, Page, PublishTarget, CATALOG_FILENAME,
+};
+use crate::catalog_lint::{lint_catalogs, LintIssue};
+use crate::catalog_publish::{bump_release, publish_catalog};
 use crate::cli::{
-    AddArgs, AddAssetKind, CatalogGenerateArgs, InitArgs, ListArgs, ManifestFormat, StatusArgs,
-    SyncArgs, ValidateArgs,
+    AddArgs, AddAssetKind, AdoptArgs, ApplyArgs, BackupListArgs, CatalogGenerateArgs,
+    CatalogInfoArgs, CatalogLintArgs, CatalogListArgs, CatalogPublishArgs, CatalogSearchArgs,
+    CatalogStatsArgs, CatalogUpdateArgs, ExplainArgs, InitArgs, ListArgs, LockDiffArgs,
+    LockResolveArgs, LogArgs, ManifestFormat, OutputFormat, PlanArgs, RegistryArgs, SchemaArgs,
+    SchemaKind, StatusArgs, StatusFormat, SuggestArgs, SyncArgs, TreeArgs, ValidateArgs,
+    VerifyArgs, WhyArgs,
 };
+use crate::confirm::{confirm, PromptMode};
+use crate::detect::detect_project;
+use crate::diff::show_diff;
 use crate::discover::{
     discover_skills_in_local_dir, discover_skills_in_repo, prompt_skill_selection,
 };
 use crate::error::{ApsError, Result};
+use crate::freshness::{find_stale_entries, is_refresh, now_timestamp};
 use crate::github_url::parse_github_url;
-use crate::hooks::validate_cursor_hooks;
-use crate::install::{install_composite_entry, install_entry, InstallOptions, InstallResult};
-use crate::lockfile::{display_status, Lockfile};
+use crate::install::{
+    copy_reason, install_composite_entry, install_entry, InstallOptions, InstallResult,
+};
+use crate::lock_resolve::{resolve_conflict, split_conflict, Side};
+use crate::lockfile::{
+    diff_lockfiles, display_status, LockChange, LockChangeKind, LockedEntry, Lockfile, Ownership,
+    LOCKFILE_NAME,
+};
 use crate::manifest::{
-    detect_overlapping_destinations, discover_manifest, load_manifest, manifest_dir,
-    validate_manifest, AssetKind, Entry, Manifest, Source, DEFAULT_MANIFEST_NAME,
+    detect_overlapping_destinations, discover_manifest, entry_hash, load_manifest, manifest_dir,
+    manifest_hash, validate_manifest, AssetKind, Entry, GitignoreMode, Manifest, Source,
+    SymlinkMode, DEFAULT_MANIFEST_NAME,
+};
+use crate::orphan::{
+    detect_orphaned_paths, detect_stale_destinations, prompt_and_cleanup_orphans,
+    remove_stale_destinations,
 };
-use crate::orphan::{detect_orphaned_paths, prompt_and_cleanup_orphans};
-use crate::sync_output::{print_sync_results, print_sync_summary, SyncDisplayItem, SyncStatus};
+use crate::output::{self, message};
+use crate::plan::{
+    lockfile_checksum as plan_lockfile_checksum, Plan, PlannedAction, PlannedEntry, PLAN_VERSION,
+};
+use crate::post_install::run_post_install;
+use crate::progress;
+use crate::registry::{is_remote, load_index, update_index, RefreshMode};
+use crate::report;
+use crate::schema;
+use crate::search::{CatalogSearch, SearchHit};
+use crate::sources::{clone_and_resolve, expand_path, show_file_at_revision};
+use crate::status::{StatusReport, EXIT_MISMATCH};
+use crate::suggest::{suggest, Suggestion};
+use crate::sync_output::{
+    print_sync_results, print_sync_summary, print_sync_timings, SyncDisplayItem, SyncReport,
+    SyncStatus, SyncUpgrade,
+};
+use crate::tree::render_tree;
+use crate::usage::{load_adoption, track, tracking_enabled, UsageStats, TRACK_USAGE_ENV};
+use crate::validate::{validate_entries, ValidateOptions};
+use crate::verify::{installed_files, lock_key, verify_entry, DriftKind};
 use console::{style, Style};
 use std::fs;
-use std::io::Write;
-use std::path::Path;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tracing::info;
 
 /// Parsed add target — the adapter pattern for distinguishing GitHub vs. filesystem sources.
@@ -196,53 +240,126 @@ pub fn cmd_init(args: InitArgs) -> Result<()> {
     info!("Created manifest at {:?}", manifest_path);
 
     // Update .gitignore
-    update_gitignore(&manifest_path)?;
+    update_gitignore(&manifest_path, &[".aps-backups/".to_string()], &[])?;
 
     Ok(())
 }
 
-/// Update .gitignore to include the backup directory
-fn update_gitignore(manifest_path: &Path) -> Result<()> {
-    let manifest_dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+/// Header of the block aps adds its `.gitignore` lines under
+const GITIGNORE_HEADER: &str = "# APS (Agentic Prompt Sync)";
 
+/// Update .gitignore so it ignores each pattern in `add` and none in
+/// `remove`. Patterns are compared without leading `/`, `./` or trailing
+/// `/`, so running this again changes nothing.
+fn update_gitignore(manifest_path: &Path, add: &[String], remove: &[String]) -> Result<()> {
+    let manifest_dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
     let gitignore_path = manifest_dir.join(".gitignore");
-    let backup_entry = ".aps-backups/";
+    let normalize = |line: &str| {
+        let line = line.trim();
+        let line = line.strip_prefix("./").unwrap_or(line);
+        line.trim_start_matches('/')
+            .trim_end_matches('/')
+            .to_string()
+    };
 
     // Read existing .gitignore or start with empty
     let existing = fs::read_to_string(&gitignore_path).unwrap_or_default();
 
-    let needs_backup = !existing.lines().any(|line| line.trim() == backup_entry);
+    let removed: Vec<&str> = existing
+        .lines()
+        .filter(|line| remove.iter().any(|r| normalize(r) == normalize(line)))
+        .collect();
+    let mut lines: Vec<&str> = existing
+        .lines()
+        .filter(|line| !removed.contains(line))
+        .collect();
+    let added: Vec<&String> = add
+        .iter()
+        .filter(|a| !lines.iter().any(|line| normalize(line) == normalize(a)))
+        .collect();
 
-    if !needs_backup {
+    if removed.is_empty() && added.is_empty() {
         info!(".gitignore already contains required entries");
         return Ok(());
     }
 
-    // Append entries
-    let mut file = fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&gitignore_path)
-        .map_err(|e| ApsError::io(e, "Failed to open .gitignore"))?;
-
-    // Add newline if file doesn't end with one
-    if !existing.is_empty() && !existing.ends_with('\n') {
-        writeln!(file).map_err(|e| ApsError::io(e, "Failed to write to .gitignore"))?;
+    // New lines go at the end of the APS block, which is created if needed
+    if !added.is_empty() {
+        let block_end = lines
+            .iter()
+            .position(|line| line.trim() == GITIGNORE_HEADER)
+            .map(|header| {
+                header
+                    + 1
+                    + lines[header + 1..]
+                        .iter()
+                        .take_while(|line| !line.trim().is_empty())
+                        .count()
+            });
+        let at = match block_end {
+            Some(at) => at,
+            None => {
+                if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                    lines.push("");
+                }
+                lines.push(GITIGNORE_HEADER);
+                lines.len()
+            }
+        };
+        for (offset, pattern) in added.iter().enumerate() {
+            lines.insert(at + offset, pattern.as_str());
+        }
     }
 
-    // Add comment and entry
-    writeln!(file, "\n# APS (Agentic Prompt Sync)")
+    let mut content = lines.join("\n");
+    content.push('\n');
+    fs::write(&gitignore_path, content)
         .map_err(|e| ApsError::io(e, "Failed to write to .gitignore"))?;
 
-    writeln!(file, "{}", backup_entry)
-        .map_err(|e| ApsError::io(e, "Failed to write to .gitignore"))?;
-    println!("Added {} to .gitignore", backup_entry);
+    for pattern in &added {
+        message!("Added {} to .gitignore", pattern);
+    }
+    for line in &removed {
+        message!("Removed {} from .gitignore", line.trim());
+    }
 
     Ok(())
 }
 
+/// Apply each installed entry's `gitignore` mode
+fn sync_gitignore(manifest_path: &Path, entries: &[&Entry]) -> Result<()> {
+    let patterns = |mode: GitignoreMode| -> Vec<String> {
+        entries
+            .iter()
+            .filter(|e| e.gitignore == mode)
+            .map(|e| e.gitignore_pattern())
+            .collect()
+    };
+    let (add, remove) = (
+        patterns(GitignoreMode::Add),
+        patterns(GitignoreMode::Remove),
+    );
+    if add.is_empty() && remove.is_empty() {
+        return Ok(());
+    }
+    update_gitignore(manifest_path, &add, &remove)
+}
+
 /// Execute the `aps add` command
 pub fn cmd_add(args: AddArgs) -> Result<()> {
+    // Neither a URL nor an existing path: try the catalog
+    if !is_local_path(&args.url) && !args.url.contains("://") {
+        let (id, requirement) = split_version_spec(&args.url);
+        if let Ok((catalog, _)) =
+            load_catalog(None, args.manifest.as_deref(), &RegistryArgs::default())
+        {
+            if let Some(entry) = catalog.entries.iter().find(|e| e.id == id) {
+                let requirement = requirement.map(str::to_string);
+                return cmd_add_catalog_entry(args, entry, requirement.as_deref());
+            }
+        }
+    }
+
     let target = parse_add_target(&args.url, args.all)?;
 
     match target {
@@ -306,7 +423,16 @@ fn write_entries_to_manifest(
                 println!("Creating new manifest at {:?}", path);
 
                 let entry_ids: Vec<String> = entries.iter().map(|e| e.id.clone()).collect();
-                let manifest = Manifest { entries };
+                let manifest = Manifest {
+                    requires_aps: None,
+                    entries,
+                    max_age: None,
+                    lockfile_format: None,
+                    backup: None,
+                    max_warnings: None,
+                    catalogs: Vec::new(),
+                };
+                validate_manifest(&manifest)?;
 
                 let content =
                     serde_yaml::to_string(&manifest).map_err(|e| ApsError::ManifestParseError {
@@ -362,7 +488,8 @@ fn write_entries_to_manifest(
         return Ok((manifest_path, added_ids));
     }
 
-    // Write back
+    // Write back, refusing to save a manifest that no longer validates
+    validate_manifest(&manifest)?;
     let content = serde_yaml::to_string(&manifest).map_err(|e| ApsError::ManifestParseError {
         message: format!("Failed to serialize manifest: {}", e),
     })?;
@@ -377,10 +504,13 @@ fn write_entries_to_manifest(
     Ok((manifest_path, added_ids))
 }
 
-/// Optionally sync entries after adding them.
+/// Optionally sync entries after adding them. Adding an entry already
+/// confirms installing it, so overwrites are only refused with `--no` or
+/// `--fail-on-prompt`.
 fn maybe_sync(
     entry_ids: &[String],
     no_sync: bool,
+    prompts: PromptMode,
     manifest_override: Option<std::path::PathBuf>,
 ) -> Result<()> {
     if entry_ids.is_empty() {
@@ -392,11 +522,24 @@ fn maybe_sync(
         cmd_sync(SyncArgs {
             manifest: manifest_override,
             only: entry_ids.to_vec(),
-            yes: true,
+            yes: matches!(prompts, PromptMode::Ask | PromptMode::Yes),
+            no: prompts == PromptMode::No,
+            fail_on_prompt: prompts == PromptMode::Fail,
             ignore_manifest: false,
             dry_run: false,
             strict: false,
             upgrade: false,
+            frozen: false,
+            prune: false,
+            diff: false,
+            force: false,
+            allow_hooks: false,
+            merge: false,
+            dedupe: false,
+            explain_permissions: false,
+            timings: false,
+            interactive: false,
+            format: OutputFormat::Text,
         })?;
     } else {
         println!(
@@ -408,6 +551,64 @@ fn maybe_sync(
     Ok(())
 }
 
+// ============================================================================
+// Catalog add adapter
+// ============================================================================
+
+/// Add a catalog entry, at the version matching `requirement`. Without one,
+/// offers the entry's versions on a terminal, newest first, and otherwise
+/// takes the newest.
+fn cmd_add_catalog_entry(
+    args: AddArgs,
+    entry: &CatalogEntry,
+    requirement: Option<&str>,
+) -> Result<()> {
+    let prompts = PromptMode::from_flags(args.yes, args.no, args.fail_on_prompt);
+    let versions = entry.sorted_versions();
+    let version = if requirement.is_none() && versions.len() > 1 && prompts.attended() {
+        let items: Vec<String> = versions
+            .iter()
+            .map(|(version, v)| match &v.changelog {
+                Some(changelog) => format!("{}  {}", version, style(changelog).dim()),
+                None => version.to_string(),
+            })
+            .collect();
+        let selected = dialoguer::Select::new()
+            .with_prompt(format!("Version of {}", entry.id))
+            .items(&items)
+            .default(0)
+            .interact_on(&console::Term::stderr())
+            .map_err(|_| ApsError::Cancelled)?;
+        Some(versions[selected].1)
+    } else {
+        entry.find_version(requirement)?
+    };
+
+    let mut manifest_entry = catalog_manifest_entry(entry, version)?;
+    if let Some(id) = &args.id {
+        manifest_entry.id = id.clone();
+    }
+    let entry_id = manifest_entry.id.clone();
+
+    let (manifest_path, added_ids) =
+        write_entries_to_manifest(vec![manifest_entry], args.manifest.clone())?;
+
+    if !added_ids.is_empty() {
+        track(|stats| stats.record_added(&entry.id, &manifest_path, &entry_id));
+        info!("Added entry '{}' to {:?}", entry_id, manifest_path);
+        let at_version = version
+            .map(|v| format!(" at {}", v.version))
+            .unwrap_or_default();
+        println!(
+            "  {} {}\n",
+            style("✓").green(),
+            style(format!("Added entry '{}'{}", entry_id, at_version)).green()
+        );
+    }
+
+    maybe_sync(&added_ids, args.no_sync, prompts, args.manifest)
+}
+
 // ============================================================================
 // Git / GitHub add adapters
 // ============================================================================
@@ -438,9 +639,8 @@ fn cmd_add_single_git(
             shallow: true,
             path: Some(skill_path.to_string()),
         }),
-        sources: Vec::new(),
-        dest: Some(skill_dest(&asset_kind, &entry_id)),
-        include: Vec::new(),
+        dest: Some(skill_dest(&asset_kind, &entry_id).into()),
+        ..Default::default()
     };
 
     let (manifest_path, added_ids) = write_entries_to_manifest(vec![entry], args.manifest.clone())?;
@@ -454,7 +654,12 @@ fn cmd_add_single_git(
         );
     }
 
-    maybe_sync(&added_ids, args.no_sync, args.manifest)
+    maybe_sync(
+        &added_ids,
+        args.no_sync,
+        PromptMode::from_flags(args.yes, args.no, args.fail_on_prompt),
+        args.manifest,
+    )
 }
 
 /// Discover and add skills from a GitHub repository.
@@ -495,9 +700,8 @@ fn cmd_add_single_filesystem(args: AddArgs, original_path: &str, skill_name: &st
             symlink: true,
             path: None,
         }),
-        sources: Vec::new(),
-        dest: Some(skill_dest(&asset_kind, &entry_id)),
-        include: Vec::new(),
+        dest: Some(skill_dest(&asset_kind, &entry_id).into()),
+        ..Default::default()
     };
 
     let (manifest_path, added_ids) = write_entries_to_manifest(vec![entry], args.manifest.clone())?;
@@ -511,7 +715,12 @@ fn cmd_add_single_filesystem(args: AddArgs, original_path: &str, skill_name: &st
         );
     }
 
-    maybe_sync(&added_ids, args.no_sync, args.manifest)
+    maybe_sync(
+        &added_ids,
+        args.no_sync,
+        PromptMode::from_flags(args.yes, args.no, args.fail_on_prompt),
+        args.manifest,
+    )
 }
 
 /// Discover and add skills from a local filesystem directory.
@@ -564,7 +773,8 @@ fn cmd_add_discovered(
         style(format!("{} new", new_count)).cyan()
     );
 
-    let selected_indices = select_skills(&skills, &defaults, args.all)?;
+    let prompts = PromptMode::from_flags(args.yes, args.no, args.fail_on_prompt);
+    let selected_indices = select_skills(&skills, &defaults, args.all, prompts)?;
     let selected_names: std::collections::HashSet<&str> = selected_indices
         .iter()
         .map(|&i| skills[i].name.as_str())
@@ -633,20 +843,13 @@ fn cmd_add_discovered(
         return Ok(());
     }
 
-    // Prompt for confirmation unless --yes or --all
-    if !args.yes && !args.all {
-        println!();
-        let confirm = dialoguer::Confirm::new()
-            .with_prompt("Proceed?")
-            .default(true)
-            .interact()
-            .map_err(|e| {
-                ApsError::io(
-                    std::io::Error::other(e.to_string()),
-                    "Failed to display confirmation prompt",
-                )
-            })?;
-        if !confirm {
+    // Prompt for confirmation unless --all
+    if !args.all {
+        let prompts = PromptMode::from_flags(args.yes, args.no, args.fail_on_prompt);
+        if prompts.attended() {
+            println!();
+        }
+        if !confirm(prompts, "Proceed?", true)? {
             println!("Cancelled.");
             return Ok(());
         }
@@ -695,9 +898,8 @@ fn cmd_add_discovered(
                     id: id.clone(),
                     kind: asset_kind.clone(),
                     source: Some(source_builder(skill)),
-                    sources: Vec::new(),
-                    dest: Some(skill_dest(&asset_kind, &id)),
-                    include: Vec::new(),
+                    dest: Some(skill_dest(&asset_kind, &id).into()),
+                    ..Default::default()
                 }
             })
             .collect();
@@ -718,7 +920,12 @@ fn cmd_add_discovered(
             );
         }
 
-        maybe_sync(&added_ids, args.no_sync, args.manifest)?;
+        maybe_sync(
+            &added_ids,
+            args.no_sync,
+            PromptMode::from_flags(args.yes, args.no, args.fail_on_prompt),
+            args.manifest,
+        )?;
     }
 
     Ok(())
@@ -754,10 +961,15 @@ fn remove_entries_from_manifest(ids: &[String], manifest_override: Option<&Path>
         .entries
         .iter()
         .filter(|e| ids.contains(&e.id))
-        .map(|e| (e.id.clone(), e.dest.clone()))
+        .flat_map(Entry::expand_targets)
+        .map(|e| {
+            let dest = e.dest.map(|d| d.paths()[0].to_string());
+            (e.id, dest)
+        })
         .collect();
 
     // Remove entries from manifest
+    let hash_before = manifest_hash(&manifest);
     manifest.entries.retain(|e| !ids.contains(&e.id));
 
     let content = serde_yaml::to_string(&manifest).map_err(|e| ApsError::ManifestParseError {
@@ -771,11 +983,18 @@ fn remove_entries_from_manifest(ids: &[String], manifest_override: Option<&Path>
     })?;
 
     // Remove from lockfile
-    let lockfile_path = Lockfile::path_for_manifest(&manifest_path);
+    let lockfile_path = Lockfile::path_for_manifest(&manifest_path, manifest.lockfile_format);
     if let Ok(mut lockfile) = Lockfile::load(&lockfile_path) {
-        let keep_ids: Vec<&str> = manifest.entries.iter().map(|e| e.id.as_str()).collect();
+        let previous = lockfile.clone();
+        let expanded = manifest.expanded_entries();
+        let keep_ids: Vec<&str> = expanded.iter().map(|e| e.id.as_str()).collect();
         lockfile.retain_entries(&keep_ids);
+        // Removing entries keeps a lockfile that was current in sync
+        if lockfile.manifest_hash.as_deref() == Some(hash_before.as_str()) {
+            lockfile.manifest_hash = Some(manifest_hash(&manifest));
+        }
         lockfile.save(&lockfile_path)?;
+        audit::record(&manifest_path, "remove", &previous, &lockfile);
     }
 
     // Delete installed files/directories
@@ -816,9 +1035,19 @@ fn check_duplicate_id(entry_id: &str, manifest_override: Option<&Path>) -> Resul
 }
 
 /// Select skills (--all or interactive prompt). Returns selected indices.
-fn select_skills(skills: &[DiscoveredSkill], defaults: &[bool], all: bool) -> Result<Vec<usize>> {
+/// The picker is not a yes/no question, so `--yes` and `--no` don't answer it.
+fn select_skills(
+    skills: &[DiscoveredSkill],
+    defaults: &[bool],
+    all: bool,
+    prompts: PromptMode,
+) -> Result<Vec<usize>> {
     if all {
         Ok((0..skills.len()).collect())
+    } else if prompts == PromptMode::Fail || !std::io::stdin().is_terminal() {
+        Err(ApsError::PromptRequired {
+            prompt: "Select skills to add (pass --all to add every skill)".to_string(),
+        })
     } else {
         let indices = prompt_skill_selection(skills, defaults)?;
         if indices.is_empty() {
@@ -830,854 +1059,3083 @@ fn select_skills(skills: &[DiscoveredSkill], defaults: &[bool], all: bool) -> Re
 
 /// Execute the `aps sync` command
 pub fn cmd_sync(args: SyncArgs) -> Result<()> {
+    if args.format == OutputFormat::Json {
+        output::messages_to_stderr();
+    }
+
     // Discover and load manifest
     let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
     let base_dir = manifest_dir(&manifest_path);
 
     // Validate manifest
     validate_manifest(&manifest)?;
+    if !args.dry_run {
+        track(|stats| stats.record_manifest(&manifest_path, &manifest));
+    }
 
     // Detect overlapping destinations (printed after header in sync output)
     let overlap_warnings = detect_overlapping_destinations(&manifest);
 
     // Filter entries if --only is specified
-    let entries_to_install: Vec<_> = if args.only.is_empty() {
-        manifest.entries.iter().collect()
-    } else {
-        let filtered: Vec<_> = manifest
-            .entries
-            .iter()
-            .filter(|e| args.only.contains(&e.id))
-            .collect();
-
-        // Check for invalid IDs
-        for id in &args.only {
-            if !manifest.entries.iter().any(|e| &e.id == id) {
-                return Err(ApsError::EntryNotFound { id: id.clone() });
-            }
-        }
-
-        filtered
-    };
+    let selected = select_entries(&manifest, &args.only)?;
+    let entries_to_install: Vec<&Entry> = selected.iter().collect();
 
     // Load existing lockfile (or create new)
-    let lockfile_path = Lockfile::path_for_manifest(&manifest_path);
-    let mut lockfile = Lockfile::load(&lockfile_path).unwrap_or_else(|_| {
-        info!("No existing lockfile, creating new one");
-        Lockfile::new()
-    });
+    let lockfile_path = Lockfile::path_for_manifest(&manifest_path, manifest.lockfile_format);
+    let mut lockfile = if args.frozen {
+        Lockfile::load(&lockfile_path)?
+    } else {
+        Lockfile::load(&lockfile_path).unwrap_or_else(|_| {
+            info!("No existing lockfile, creating new one");
+            Lockfile::new()
+        })
+    };
+    if args.frozen {
+        let manifest_ids = args.only.is_empty().then(|| manifest.expanded_entries());
+        check_frozen(&entries_to_install, manifest_ids.as_deref(), &lockfile)?;
+    }
 
     // Set up install options
     let options = InstallOptions {
         dry_run: args.dry_run,
-        yes: args.yes,
+        prompts: PromptMode::from_flags(args.yes, args.no, args.fail_on_prompt),
+        backup: manifest.backup.unwrap_or_default(),
         strict: args.strict,
         upgrade: args.upgrade,
+        frozen: args.frozen,
+        diff: args.diff,
+        force: args.force,
+        prune: args.prune,
+        merge: args.merge,
+        dedupe: args.dedupe,
+        explain_permissions: args.explain_permissions,
+        plan: false,
+        render: false,
     };
 
+    // Entries using source types from a newer aps fail individually; the rest still sync
+    let (unsupported, entries_to_install): (Vec<_>, Vec<_>) = entries_to_install
+        .into_iter()
+        .partition(|e| e.unsupported_source_type().is_some());
+
+    if args.interactive && !std::io::stdin().is_terminal() {
+        return Err(ApsError::InteractiveRequiresTerminal);
+    }
+
     // Detect orphaned paths (destinations that changed)
-    let orphans = detect_orphaned_paths(&entries_to_install, &lockfile, &base_dir);
+    let mut orphans = detect_orphaned_paths(&entries_to_install, &lockfile, &base_dir);
 
-    // Install selected entries
+    // Install selected entries. Each install sees the files written by the
+    // ones before it, so entries sharing a destination keep each other's.
+    let before = lockfile.clone();
+    let mut installed = lockfile.clone();
     let mut results: Vec<InstallResult> = Vec::new();
-    for entry in &entries_to_install {
-        // Use composite install for composite entries, regular install otherwise
-        let result = if entry.is_composite() {
-            install_composite_entry(entry, &base_dir, &lockfile, &options)?
+    let mut timings: Vec<(String, Duration)> = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
+    for (index, entry) in entries_to_install.iter().enumerate() {
+        progress::start(index + 1, entries_to_install.len(), &entry.id);
+        let started = Instant::now();
+        let result = if args.interactive {
+            review_install(entry, &base_dir, &installed, &options)
         } else {
-            install_entry(entry, &base_dir, &lockfile, &options)?
+            install_one(entry, &base_dir, &installed, &options).map(Some)
         };
-        results.push(result);
+        progress::finish();
+        timings.push((entry.id.clone(), started.elapsed()));
+        match result? {
+            Some(result) => {
+                claim_installed(&mut installed, &result);
+                results.push(result);
+            }
+            None => skipped.push(entry.id.clone()),
+        }
     }
+    // A skipped entry keeps its old destination
+    orphans.retain(|orphan| !skipped.contains(&orphan.entry_id));
 
     // Cleanup orphaned paths after successful install
-    let orphan_count = if !orphans.is_empty() {
+    let mut orphan_count = if !orphans.is_empty() {
         prompt_and_cleanup_orphans(&orphans, &options, &base_dir)?
     } else {
         0
     };
+    if args.prune {
+        let stale = detect_stale_destinations(&manifest.expanded_entries(), &lockfile, &base_dir);
+        orphan_count += remove_stale_destinations(&stale, args.dry_run, &base_dir, options.backup)?;
+    }
 
-    // Update lockfile with results
-    if !args.dry_run {
-        for result in &results {
-            if let Some(ref locked_entry) = result.locked_entry {
-                lockfile.upsert(result.id.clone(), locked_entry.clone());
-            }
-        }
+    // Update lockfile with results (a frozen sync never changes it)
+    if !args.dry_run && !args.frozen {
+        let previous = lockfile.clone();
+        record_results(&mut lockfile, &results, &manifest, &base_dir, args.upgrade)?;
 
-        // Clean up stale entries (only during full sync, not with --only)
-        let removed_count = if args.only.is_empty() {
-            let manifest_ids: Vec<&str> = manifest.entries.iter().map(|e| e.id.as_str()).collect();
-            let removed = lockfile.retain_entries(&manifest_ids);
-            removed.len()
-        } else {
-            0
-        };
+        // Clean up records of entries no longer in the manifest
+        let expanded = manifest.expanded_entries();
+        let manifest_ids: Vec<&str> = expanded.iter().map(|e| e.id.as_str()).collect();
+        let removed_count = lockfile.retain_entries(&manifest_ids).len();
         if removed_count > 0 {
             info!("Removed {} stale entries from lockfile", removed_count);
         }
 
+        // Only a full sync brings every entry in line with the manifest
+        if args.only.is_empty() && unsupported.is_empty() && skipped.is_empty() {
+            lockfile.manifest_hash = Some(manifest_hash(&manifest));
+        }
+
         // Save lockfile
         lockfile.save(&lockfile_path)?;
-    }
-
-    // Convert results to display items
-    let display_items: Vec<SyncDisplayItem> = results
-        .iter()
-        .map(|r| {
-            let status = if !r.warnings.is_empty() {
-                SyncStatus::Warning
-            } else if r.skipped_no_change && r.upgrade_available.is_some() {
-                SyncStatus::Upgradable
-            } else if r.skipped_no_change {
-                SyncStatus::Current
-            } else if r.was_symlink {
-                SyncStatus::Synced
-            } else {
-                SyncStatus::Copied
-            };
-
-            let mut item = SyncDisplayItem::new(
-                r.id.clone(),
-                r.dest_path.to_string_lossy().to_string(),
-                status,
-            );
-
-            // Add warning message if present
-            if !r.warnings.is_empty() {
-                item = item.with_message(r.warnings.join(", "));
-            }
-
-            // Add upgrade info message if available
-            if let Some(ref upgrade_info) = r.upgrade_available {
-                let current_short =
-                    &upgrade_info.current_commit[..8.min(upgrade_info.current_commit.len())];
-                let available_short =
-                    &upgrade_info.available_commit[..8.min(upgrade_info.available_commit.len())];
-                item = item.with_message(format!("{} → {}", current_short, available_short));
-            }
 
-            item
-        })
-        .collect();
+        // A sync that left everything as it was is not worth a record
+        let changed = results.iter().any(|r| r.installed)
+            || removed_count > 0
+            || orphan_count > 0
+            || !skipped.is_empty()
+            || !audit::diff_lockfiles(&previous, &lockfile).is_empty();
+        if changed {
+            let command = if args.upgrade { "upgrade" } else { "sync" };
+            audit::record(&manifest_path, command, &previous, &lockfile);
+        }
+    }
 
-    // Print styled results
-    print_sync_results(
+    let display_items = sync_display_items(&results, &unsupported, &before, &timings);
+    report_results(
         &display_items,
         &manifest_path,
         args.dry_run,
         &overlap_warnings,
-    );
+        orphan_count,
+        args.format,
+    )?;
+    if args.timings && args.format == OutputFormat::Text {
+        print_sync_timings(&timings);
+    }
 
-    // Calculate counts for summary
-    let synced_count = display_items
-        .iter()
-        .filter(|i| i.status == SyncStatus::Synced)
-        .count();
-    let copied_count = display_items
-        .iter()
-        .filter(|i| i.status == SyncStatus::Copied)
-        .count();
-    let current_count = display_items
-        .iter()
-        .filter(|i| i.status == SyncStatus::Current)
-        .count();
-    let upgradable_count = display_items
-        .iter()
-        .filter(|i| i.status == SyncStatus::Upgradable)
-        .count();
-    let warning_count = display_items
-        .iter()
-        .filter(|i| i.status == SyncStatus::Warning)
-        .count();
+    // Post-install commands run once everything is installed and locked
+    if !args.dry_run {
+        sync_gitignore(&manifest_path, &entries_to_install)?;
+        run_post_install_commands(&results, &entries_to_install, &base_dir, args.allow_hooks)?;
+    }
 
-    // Print summary
-    print_sync_summary(
-        synced_count,
-        copied_count,
-        current_count,
-        upgradable_count,
-        warning_count,
-        orphan_count,
-        args.dry_run,
-    );
+    if !unsupported.is_empty() {
+        return Err(ApsError::SyncFailed {
+            count: unsupported.len(),
+        });
+    }
 
     Ok(())
 }
 
-/// Execute the `aps validate` command
-pub fn cmd_validate(args: ValidateArgs) -> Result<()> {
-    // Discover and load manifest
+/// Execute the `aps plan` command
+pub fn cmd_plan(args: PlanArgs) -> Result<()> {
     let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
-    println!("Validating manifest at {:?}", manifest_path);
-
-    // Validate schema
+    let base_dir = manifest_dir(&manifest_path);
     validate_manifest(&manifest)?;
-    println!("  Schema validation passed");
 
-    // Check for overlapping destinations
-    let overlap_warnings = detect_overlapping_destinations(&manifest);
-    for warning in &overlap_warnings {
-        println!(
-            "  {} {}",
-            console::style("[WARN]").yellow(),
-            console::style(warning).yellow()
-        );
+    let selected = select_entries(&manifest, &args.only)?;
+    if let Some(entry) = selected
+        .iter()
+        .find(|e| e.unsupported_source_type().is_some())
+    {
+        return Err(ApsError::UnsupportedSourceType {
+            source_type: entry
+                .unsupported_source_type()
+                .unwrap_or_default()
+                .to_string(),
+        });
     }
+    let lockfile_path = Lockfile::path_for_manifest(&manifest_path, manifest.lockfile_format);
+    let lockfile = Lockfile::load(&lockfile_path).unwrap_or_else(|_| Lockfile::new());
 
-    // Check sources are reachable
-    let base_dir = manifest_dir(&manifest_path);
-    let mut warnings = Vec::new();
-
-    println!("\nValidating entries:");
-    for entry in &manifest.entries {
-        // Handle composite entries differently
-        if entry.is_composite() {
-            print!(
-                "  [..] {} (composite) - checking {} sources...",
-                entry.id,
-                entry.sources.len()
-            );
-            std::io::stdout().flush().ok();
-
-            let mut all_valid = true;
-            for source in &entry.sources {
-                let adapter = source.to_adapter();
-                match adapter.resolve(&base_dir) {
-                    Ok(resolved) => {
-                        if !resolved.source_path.exists() {
-                            let warning =
-                                format!("Source path not found: {:?}", resolved.source_path);
-                            if args.strict {
-                                println!(" FAILED");
-                                return Err(ApsError::SourcePathNotFound {
-                                    path: resolved.source_path,
-                                });
-                            }
-                            warnings.push(warning);
-                            all_valid = false;
-                        }
-                    }
-                    Err(e) => {
-                        if args.strict {
-                            println!(" FAILED");
-                            return Err(e);
-                        }
-                        let warning = format!("Source validation failed: {}", e);
-                        warnings.push(warning);
-                        all_valid = false;
-                    }
-                }
-            }
+    // A dry run that records diffs instead of printing them
+    let options = InstallOptions {
+        dry_run: true,
+        upgrade: args.upgrade,
+        plan: true,
+        ..Default::default()
+    };
+    let mut entries = Vec::new();
+    for (index, entry) in selected.iter().enumerate() {
+        progress::start(index + 1, selected.len(), &entry.id);
+        let result = install_one(entry, &base_dir, &lockfile, &options);
+        progress::finish();
+        let result = result?;
+        let action = if result.skipped_no_change {
+            PlannedAction::Unchanged
+        } else {
+            PlannedAction::Install
+        };
+        entries.push(PlannedEntry {
+            id: result.id,
+            action,
+            dest: entry.destination().to_string_lossy().to_string(),
+            commit: result.locked_entry.as_ref().and_then(|l| l.commit.clone()),
+            conflict: result.conflict,
+            diff: result.diff.filter(|d| !d.is_empty()),
+            warnings: result.warnings,
+            locked: result.locked_entry,
+        });
+    }
 
-            if all_valid {
-                println!(
-                    "\r  [OK] {} (composite, {} sources)",
-                    entry.id,
-                    entry.sources.len()
-                );
-            } else {
-                println!(" WARN");
-            }
-            continue;
-        }
+    let plan = Plan {
+        version: PLAN_VERSION,
+        aps_version: env!("CARGO_PKG_VERSION").to_string(),
+        manifest_hash: manifest_hash(&manifest),
+        lockfile_checksum: plan_lockfile_checksum(&lockfile_path),
+        only: args.only.clone(),
+        upgrade: args.upgrade,
+        entries,
+    };
 
-        // Handle regular (single-source) entries
-        let source = match &entry.source {
-            Some(s) => s,
-            None => {
-                let warning = format!("Entry '{}' has no source configured", entry.id);
-                if args.strict {
-                    return Err(ApsError::EntryRequiresSource {
-                        id: entry.id.clone(),
-                    });
-                }
-                println!("  [WARN] {} - {}", entry.id, warning);
-                warnings.push(warning);
-                continue;
-            }
+    let dim = Style::new().dim();
+    println!(
+        "{} {}\n",
+        style("Plan for").bold(),
+        dim.apply_to(manifest_path.display())
+    );
+    for planned in &plan.entries {
+        let (marker, action) = match planned.action {
+            PlannedAction::Install => (style("+").green(), "install"),
+            PlannedAction::Unchanged => (style("=").dim(), "unchanged"),
         };
-
-        let adapter = source.to_adapter();
-        let source_type = adapter.source_type();
-        let display_name = adapter.display_name();
-
-        // For git sources, show progress indicator
-        if source_type == "git" {
-            print!("  [..] {} ({}) - checking...", entry.id, display_name);
-            std::io::stdout().flush().ok();
-        }
-
-        match adapter.resolve(&base_dir) {
-            Ok(resolved) => {
-                if !resolved.source_path.exists() {
-                    let warning = format!("Source path not found: {:?}", resolved.source_path);
-                    if args.strict {
-                        if source_type == "git" {
-                            println!(" FAILED");
-                        }
-                        return Err(ApsError::SourcePathNotFound {
-                            path: resolved.source_path,
-                        });
-                    }
-                    if source_type == "git" {
-                        println!(" WARN");
-                        println!("       Warning: {}", warning);
-                    } else {
-                        println!("  [WARN] {} - {}", entry.id, warning);
-                    }
-                    warnings.push(warning);
-                } else {
-                    // Validate skills if applicable
-                    if entry.kind == AssetKind::CursorSkillsRoot {
-                        let skill_warnings = validate_skills_for_validate(
-                            &resolved.source_path,
-                            &entry.id,
-                            args.strict,
-                        )?;
-                        warnings.extend(skill_warnings);
-                    }
-                    if entry.kind == AssetKind::CursorHooks {
-                        let hook_warnings =
-                            validate_cursor_hooks(&resolved.source_path, args.strict)?;
-                        for warning in &hook_warnings {
-                            println!("       Warning: {}", warning);
-                        }
-                        warnings.extend(hook_warnings);
-                    }
-                    // Format output based on source type
-                    if let Some(git_info) = &resolved.git_info {
-                        println!(
-                            "\r  [OK] {} ({} @ {})",
-                            entry.id, display_name, git_info.resolved_ref
-                        );
-                    } else {
-                        println!("  [OK] {} ({})", entry.id, display_name);
-                    }
-                }
-            }
-            Err(e) => {
-                if args.strict {
-                    if source_type == "git" {
-                        println!(" FAILED");
-                    }
-                    return Err(e);
-                }
-                if source_type == "git" {
-                    println!(" WARN");
-                }
-                let warning = format!("Source validation failed: {}", e);
-                println!("       Warning: {}", warning);
-                warnings.push(warning);
-            }
+        let mut line = format!("  {} {} → {} ({}", marker, planned.id, planned.dest, action);
+        if let (PlannedAction::Install, Some(commit)) = (planned.action, &planned.commit) {
+            line.push_str(&format!(" @ {}", &commit[..8.min(commit.len())]));
+        }
+        line.push(')');
+        if planned.conflict {
+            line.push_str(&format!(
+                " {}",
+                style("overwrites existing content").yellow()
+            ));
         }
+        println!("{}", line);
+    }
+    let diffs: String = plan
+        .entries
+        .iter()
+        .filter_map(|p| p.diff.as_deref())
+        .collect();
+    if !diffs.is_empty() {
+        println!();
+        show_diff(&diffs);
     }
 
-    // Print summary
-    println!();
-    if warnings.is_empty() {
+    let installs = plan
+        .entries
+        .iter()
+        .filter(|p| p.action == PlannedAction::Install)
+        .count();
+    println!(
+        "\n{} to install, {} unchanged",
+        installs,
+        plan.entries.len() - installs
+    );
+    if let Some(ref out) = args.out {
+        plan.save(out)?;
         println!(
-            "Manifest is valid. All {} entries validated successfully.",
-            manifest.entries.len()
+            "Saved plan to {}. Apply it with `aps apply {}`.",
+            out.display(),
+            out.display()
         );
-    } else {
-        println!("Manifest is valid with {} warning(s).", warnings.len());
-        if !args.strict {
-            println!("Run with --strict to treat warnings as errors.");
-        }
     }
-
     Ok(())
 }
 
-/// Validate skills directory for the validate command
-fn validate_skills_for_validate(
-    source: &Path,
-    entry_id: &str,
-    strict: bool,
-) -> Result<Vec<String>> {
-    let mut warnings = Vec::new();
-
-    for dir_entry in std::fs::read_dir(source)
-        .map_err(|e| ApsError::io(e, format!("Failed to read skills directory {:?}", source)))?
-    {
-        let dir_entry = dir_entry.map_err(|e| ApsError::io(e, "Failed to read directory entry"))?;
-        let skill_path = dir_entry.path();
+/// Execute the `aps apply` command
+pub fn cmd_apply(args: ApplyArgs) -> Result<()> {
+    let plan = Plan::load(&args.plan)?;
+    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+    let base_dir = manifest_dir(&manifest_path);
+    validate_manifest(&manifest)?;
 
-        if !skill_path.is_dir() {
-            continue;
+    let lockfile_path = Lockfile::path_for_manifest(&manifest_path, manifest.lockfile_format);
+    plan.check_current(
+        &manifest_hash(&manifest),
+        plan_lockfile_checksum(&lockfile_path).as_deref(),
+    )?;
+    let mut lockfile = Lockfile::load(&lockfile_path).unwrap_or_else(|_| Lockfile::new());
+
+    // Installing frozen against the planned lock records reproduces exactly
+    // the planned commits, and fails if any content differs from the plan
+    let mut pinned = lockfile.clone();
+    for planned in &plan.entries {
+        if let Some(ref locked) = planned.locked {
+            pinned.upsert(planned.id.clone(), locked.clone());
         }
+    }
+    let expanded = manifest.expanded_entries();
+    let mut entries: Vec<&Entry> = Vec::new();
+    for planned in &plan.entries {
+        let entry = expanded
+            .iter()
+            .find(|e| e.id == planned.id)
+            .ok_or_else(|| ApsError::PlanStale {
+                message: format!("'{}' is no longer in the manifest", planned.id),
+            })?;
+        entries.push(entry);
+    }
 
-        let skill_name = dir_entry.file_name().to_string_lossy().to_string();
-        let skill_md_path = skill_path.join("SKILL.md");
+    let mut results = Vec::new();
+    let mut timings = Vec::new();
+    for (index, (planned, entry)) in plan.entries.iter().zip(&entries).enumerate() {
+        let options = InstallOptions {
+            prompts: PromptMode::Yes,
+            backup: manifest.backup.unwrap_or_default(),
+            frozen: true,
+            force: planned.action == PlannedAction::Install,
+            ..Default::default()
+        };
+        progress::start(index + 1, entries.len(), &entry.id);
+        let started = Instant::now();
+        let result = install_one(entry, &base_dir, &pinned, &options);
+        progress::finish();
+        timings.push((entry.id.clone(), started.elapsed()));
+        let result = result.map_err(|e| match e {
+            ApsError::LockfileOutOfSync { message } => ApsError::PlanStale { message },
+            e => e,
+        })?;
+        claim_installed(&mut pinned, &result);
+        results.push(result);
+    }
 
-        if !skill_md_path.exists() {
-            let warning = format!(
-                "Skill '{}' in entry '{}' is missing SKILL.md",
-                skill_name, entry_id
-            );
-            if strict {
-                return Err(ApsError::MissingSkillMd { skill_name });
-            }
-            println!("       Warning: {}", warning);
-            warnings.push(warning);
-        }
+    let previous = lockfile.clone();
+    record_results(&mut lockfile, &results, &manifest, &base_dir, plan.upgrade)?;
+    let manifest_ids: Vec<&str> = expanded.iter().map(|e| e.id.as_str()).collect();
+    lockfile.retain_entries(&manifest_ids);
+    if plan.only.is_empty() {
+        lockfile.manifest_hash = Some(manifest_hash(&manifest));
     }
+    lockfile.save(&lockfile_path)?;
+    audit::record(&manifest_path, "apply", &previous, &lockfile);
 
-    Ok(warnings)
+    report_results(
+        &sync_display_items(&results, &[], &previous, &timings),
+        &manifest_path,
+        false,
+        &detect_overlapping_destinations(&manifest),
+        0,
+        OutputFormat::Text,
+    )?;
+    sync_gitignore(&manifest_path, &entries)?;
+    run_post_install_commands(&results, &entries, &base_dir, args.allow_hooks)
 }
 
-/// Execute the `aps status` command
-pub fn cmd_status(args: StatusArgs) -> Result<()> {
-    // Discover manifest to find lockfile location
-    let (_, manifest_path) = discover_manifest(args.manifest.as_deref())?;
-    let lockfile_path = Lockfile::path_for_manifest(&manifest_path);
+/// Execute the `aps adopt` command
+pub fn cmd_adopt(args: AdoptArgs) -> Result<()> {
+    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+    let base_dir = manifest_dir(&manifest_path);
+    validate_manifest(&manifest)?;
 
-    // Load lockfile
-    let lockfile = Lockfile::load(&lockfile_path)?;
+    let entries = select_entries(&manifest, &args.ids)?;
+    let lockfile_path = Lockfile::path_for_manifest(&manifest_path, manifest.lockfile_format);
+    let mut lockfile = Lockfile::load(&lockfile_path).unwrap_or_else(|_| Lockfile::new());
+    let previous = lockfile.clone();
 
-    // Display status
-    display_status(&lockfile);
+    // Resolve each source as a plan would, so nothing at the destination is
+    // written, then lock that version with the existing files as installed
+    let options = InstallOptions {
+        dry_run: true,
+        force: true,
+        plan: true,
+        ..Default::default()
+    };
+    for entry in &entries {
+        let dest = entry.destination();
+        let dest_path = base_dir.join(&dest);
+        if !dest_path.exists() {
+            return Err(ApsError::NothingToAdopt {
+                id: entry.id.clone(),
+                path: dest_path,
+            });
+        }
+
+        let result = install_one(entry, &base_dir, &lockfile, &options)?;
+        let Some(mut locked) = result.locked_entry else {
+            continue;
+        };
+        locked.is_symlink = false;
+        locked.symlink_fallback = false;
+        locked.readonly = false;
+        locked.target_path = None;
+        locked.symlinked_items.clear();
+        locked.files = installed_files(&base_dir, &dest)?;
+        locked.installed_at = Some(now_timestamp());
+        lockfile.upsert(entry.id.clone(), locked);
+
+        if result.diff.as_deref().is_some_and(|diff| !diff.is_empty()) {
+            println!(
+                "Adopted {} at {:?}; it differs from the source and is kept until the source \
+                 changes (`aps sync --force` replaces it now)",
+                entry.id, dest
+            );
+        } else {
+            println!("Adopted {} at {:?}", entry.id, dest);
+        }
+    }
 
+    lockfile.save(&lockfile_path)?;
+    audit::record(&manifest_path, "adopt", &previous, &lockfile);
     Ok(())
 }
 
-/// Execute the `aps list` command
-pub fn cmd_list(args: ListArgs) -> Result<()> {
-    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
-    let base_dir = manifest_dir(&manifest_path);
-
-    let manifest_display = manifest_path
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| manifest_path.to_string_lossy().to_string());
+/// Install an entry, using composite install for composite entries
+fn install_one(
+    entry: &Entry,
+    base_dir: &Path,
+    lockfile: &Lockfile,
+    options: &InstallOptions,
+) -> Result<InstallResult> {
+    if entry.is_composite() {
+        install_composite_entry(entry, base_dir, lockfile, options)
+    } else {
+        install_entry(entry, base_dir, lockfile, options)
+    }
+}
 
-    let dim = Style::new().dim();
-    let cyan = Style::new().cyan();
-    let green = Style::new().green();
-    let yellow = Style::new().yellow();
-    let white_bold = Style::new().white().bold();
+/// Show what installing an entry would change and ask whether to install
+/// it (`aps sync --interactive`). Returns `None` when the entry is skipped.
+fn review_install(
+    entry: &Entry,
+    base_dir: &Path,
+    lockfile: &Lockfile,
+    options: &InstallOptions,
+) -> Result<Option<InstallResult>> {
+    let preview_options = InstallOptions {
+        dry_run: true,
+        plan: true,
+        ..options.clone()
+    };
+    let preview = install_one(entry, base_dir, lockfile, &preview_options)?;
+    if preview.skipped_no_change {
+        return Ok(Some(preview));
+    }
 
+    progress::suspend();
+    let change = if lockfile.entries.contains_key(&entry.id) {
+        style("updated").yellow()
+    } else {
+        style("new").green()
+    };
+    let overwrites = if preview.conflict {
+        " (overwrites existing content)"
+    } else {
+        ""
+    };
     println!(
-        "{} {} {}",
-        style("Manifest:").dim(),
-        cyan.apply_to(&manifest_display),
-        dim.apply_to(format!("({} entries)", manifest.entries.len()))
+        "{} {:?}: {}{}",
+        entry.id,
+        entry.destination(),
+        change,
+        overwrites
     );
-    println!();
-
-    // Load lockfile once for status checks
-    let lockfile_path = Lockfile::path_for_manifest(&manifest_path);
-    let lockfile = Lockfile::load(&lockfile_path).ok();
-
-    for (i, entry) in manifest.entries.iter().enumerate() {
-        // Entry header: ID and kind
-        let kind_label = format_kind_label(&entry.kind);
-        println!(
-            "  {} {}",
-            white_bold.apply_to(&entry.id),
-            dim.apply_to(&kind_label),
-        );
 
-        // Source info
-        if entry.is_composite() {
-            println!(
-                "  {} composite ({} sources)",
-                dim.apply_to("Source:"),
-                entry.sources.len()
-            );
-            for (j, src) in entry.sources.iter().enumerate() {
-                let connector = if j == entry.sources.len() - 1 {
-                    "└──"
-                } else {
-                    "├──"
+    loop {
+        let choice = dialoguer::Select::new()
+            .with_prompt(format!("Install {}?", entry.id))
+            .items(&["install", "skip", "show diff"])
+            .default(0)
+            .interact()
+            .map_err(|_| ApsError::Cancelled)?;
+        match choice {
+            0 => {
+                // Choosing to install already confirms the overwrite
+                let options = InstallOptions {
+                    prompts: PromptMode::Yes,
+                    ..options.clone()
                 };
-                println!(
-                    "  {}  {} {}",
-                    dim.apply_to("       "),
-                    dim.apply_to(connector),
-                    dim.apply_to(format_source_short(src)),
-                );
+                return install_one(entry, base_dir, lockfile, &options).map(Some);
             }
-        } else if let Some(ref source) = entry.source {
-            println!(
-                "  {} {}",
-                dim.apply_to("Source:"),
-                dim.apply_to(format_source_short(source)),
-            );
+            1 => {
+                println!("Skipped {}", entry.id);
+                return Ok(None);
+            }
+            _ => match preview.diff.as_deref() {
+                Some(diff) if !diff.is_empty() => show_diff(diff),
+                _ => println!("No changes to existing content"),
+            },
         }
+    }
+}
 
-        // Destination
-        let dest = entry.destination();
-        let dest_display = {
-            let s = dest.to_string_lossy();
-            if s.starts_with("./") || s.starts_with('/') {
-                s.to_string()
-            } else {
-                format!("./{}", s)
-            }
-        };
-        println!(
-            "  {} {}",
-            dim.apply_to("Dest:  "),
-            cyan.apply_to(&dest_display),
-        );
+/// Entries a sync installs: all of them, or those named with `--only`, with
+/// fan-outs expanded
+fn select_entries(manifest: &Manifest, only: &[String]) -> Result<Vec<Entry>> {
+    if only.is_empty() {
+        return Ok(manifest.expanded_entries());
+    }
 
-        // Include filter
-        if !entry.include.is_empty() {
-            println!(
-                "  {} {}",
-                dim.apply_to("Filter:"),
-                yellow.apply_to(entry.include.join(", ")),
+    // Check for invalid IDs
+    for id in only {
+        if !manifest.entries.iter().any(|e| &e.id == id) {
+            return Err(ApsError::EntryNotFound { id: id.clone() });
+        }
+    }
+    Ok(manifest
+        .entries
+        .iter()
+        .filter(|e| only.contains(&e.id))
+        .flat_map(Entry::expand_targets)
+        .collect())
+}
+
+/// Run the `post_install` commands of installed entries, or say they were
+/// skipped without `--allow-hooks`
+fn run_post_install_commands(
+    results: &[InstallResult],
+    entries: &[&Entry],
+    base_dir: &Path,
+    allow_hooks: bool,
+) -> Result<()> {
+    for result in results.iter().filter(|r| r.installed) {
+        let Some(entry) = entries.iter().find(|e| e.id == result.id) else {
+            continue;
+        };
+        if entry.post_install.is_empty() {
+            continue;
+        }
+        if allow_hooks {
+            run_post_install(&entry.id, &entry.post_install, base_dir)?;
+        } else {
+            message!(
+                "{} Skipped {} post_install command(s) for {} (pass --allow-hooks to run them)",
+                style("Warning:").yellow(),
+                entry.post_install.len(),
+                entry.id
             );
         }
+    }
+    Ok(())
+}
 
-        // On-disk asset tree (when --assets is passed and destination exists)
-        if args.assets {
-            let abs_dest = if dest.is_relative() {
-                base_dir.join(&dest)
+/// Record install results in the lockfile
+fn record_results(
+    lockfile: &mut Lockfile,
+    results: &[InstallResult],
+    manifest: &Manifest,
+    base_dir: &Path,
+    upgrade: bool,
+) -> Result<()> {
+    let previous = lockfile.clone();
+    let expanded = manifest.expanded_entries();
+    let definition_hash = |id: &str| expanded.iter().find(|e| e.id == id).map(entry_hash);
+    for result in results {
+        let previous_entry = previous.entries.get(&result.id);
+        if let Some(ref locked_entry) = result.locked_entry {
+            let mut locked_entry = locked_entry.clone();
+            locked_entry.definition_hash = definition_hash(&result.id);
+            locked_entry.refreshed_at = if is_refresh(previous_entry, &locked_entry, upgrade) {
+                Some(now_timestamp())
             } else {
-                dest.clone()
+                previous_entry.and_then(|e| e.refreshed_at.clone())
             };
-
-            if abs_dest.is_dir() {
-                println!("  {}", dim.apply_to("Assets:"));
-                print_asset_tree(&abs_dest, &entry.kind, "  ");
-            } else if abs_dest.is_file() {
-                println!(
-                    "  {} {}",
-                    dim.apply_to("Assets:"),
-                    green.apply_to(
-                        abs_dest
-                            .file_name()
-                            .map(|n| n.to_string_lossy().to_string())
-                            .unwrap_or_default()
-                    ),
-                );
+            locked_entry.installed_at = if result.installed {
+                Some(now_timestamp())
             } else {
-                println!(
-                    "  {} {}",
-                    dim.apply_to("Assets:"),
-                    dim.apply_to("(not synced)"),
-                );
+                previous_entry.and_then(|e| e.installed_at.clone())
+            };
+            // The last backup stays on record until another overwrite replaces it
+            if locked_entry.backup.is_none() {
+                locked_entry.backup = previous_entry.and_then(|e| e.backup.clone());
             }
-        }
-
-        // Sync status indicator
-        if let Some(ref lf) = lockfile {
-            if lf.entries.contains_key(&entry.id) {
-                println!("  {} {}", green.apply_to("●"), green.apply_to("synced"));
+            lockfile.upsert(result.id.clone(), locked_entry);
+        } else if result.skipped_no_change {
+            if let Some(entry) = lockfile.entries.get_mut(&result.id) {
+                entry.definition_hash = definition_hash(&result.id);
+                // Unchanged content still counts as reviewed once re-resolved upstream
+                if upgrade {
+                    entry.refreshed_at = Some(now_timestamp());
+                }
+                // Entries locked before file lists were recorded pick one up now
+                if entry.files.is_empty() && !entry.is_symlink {
+                    entry.files = installed_files(base_dir, Path::new(&entry.dest))?;
+                }
             }
         }
-
-        // Separator between entries (but not after the last)
-        if i < manifest.entries.len() - 1 {
-            println!();
-        }
     }
-
-    println!();
-
-    // Summary
-    let synced_count = match lockfile {
-        Some(ref lf) => manifest
-            .entries
-            .iter()
-            .filter(|e| lf.entries.contains_key(&e.id))
-            .count(),
-        None => 0,
-    };
-    let total = manifest.entries.len();
-    if synced_count == total {
-        println!(
-            "{}",
-            green.apply_to(format!("All {} entries synced", total))
-        );
-    } else {
-        println!(
-            "{} synced, {} pending",
-            green.apply_to(synced_count),
-            yellow.apply_to(total - synced_count),
-        );
-    }
-
     Ok(())
 }
 
-/// Format the AssetKind as a human-readable label
-fn format_kind_label(kind: &AssetKind) -> String {
-    match kind {
-        AssetKind::AgentSkill => "agent_skill".to_string(),
-        AssetKind::AgentsMd => "agents_md".to_string(),
-        AssetKind::CompositeAgentsMd => "composite_agents_md".to_string(),
-        AssetKind::CursorRules => "cursor_rules".to_string(),
-        AssetKind::CursorHooks => "cursor_hooks".to_string(),
-        AssetKind::CursorSkillsRoot => "cursor_skills_root".to_string(),
+/// Note the files an install wrote, for the installs after it in the same run
+fn claim_installed(lockfile: &mut Lockfile, result: &InstallResult) {
+    if let Some(ref locked) = result.locked_entry {
+        lockfile.upsert(result.id.clone(), locked.clone());
     }
 }
 
-/// Format a source for compact display
-fn format_source_short(source: &Source) -> String {
-    match source {
-        Source::Git {
-            repo, r#ref, path, ..
-        } => {
-            // Shorten GitHub URLs: https://github.com/owner/repo.git -> owner/repo
-            let short_repo = repo
-                .trim_end_matches(".git")
-                .strip_prefix("https://github.com/")
-                .unwrap_or(repo);
+/// Display items for install results: what happened to each entry, its
+/// locked version before and after, and how long it took
+fn sync_display_items(
+    results: &[InstallResult],
+    unsupported: &[&Entry],
+    previous: &Lockfile,
+    timings: &[(String, Duration)],
+) -> Vec<SyncDisplayItem> {
+    let locked_version = |locked: &LockedEntry| {
+        locked
+            .commit
+            .clone()
+            .unwrap_or_else(|| locked.checksum.clone())
+    };
+    let duration = |id: &str| {
+        timings
+            .iter()
+            .find(|(timed, _)| timed == id)
+            .map(|(_, duration)| *duration)
+    };
 
-            let ref_part = if r#ref == "auto" {
-                String::new()
+    let mut display_items: Vec<SyncDisplayItem> = results
+        .iter()
+        .map(|r| {
+            let status = if r.skipped_no_change && !r.upgrades.is_empty() {
+                SyncStatus::Upgradable
+            } else if r.skipped_no_change {
+                SyncStatus::Current
+            } else if r.was_symlink {
+                SyncStatus::Synced
             } else {
-                format!(" @ {}", r#ref)
+                SyncStatus::Copied
             };
 
-            if let Some(p) = path {
-                format!("git: {}{} → {}", short_repo, ref_part, p)
-            } else {
-                format!("git: {}{}", short_repo, ref_part)
-            }
-        }
-        Source::Filesystem {
-            root,
-            path,
-            symlink,
-        } => {
-            let sym_tag = if *symlink { " (symlink)" } else { "" };
-            if let Some(p) = path {
-                format!("fs: {}/{}{}", root, p, sym_tag)
-            } else {
-                format!("fs: {}{}", root, sym_tag)
-            }
-        }
+            let before = previous.entries.get(&r.id).map(locked_version);
+            let after = r
+                .locked_entry
+                .as_ref()
+                .map(locked_version)
+                .or_else(|| before.clone());
+            let upgrades = r
+                .upgrades
+                .iter()
+                .map(|upgrade| SyncUpgrade {
+                    fragment: upgrade.fragment.clone(),
+                    current: upgrade.current_commit.clone(),
+                    available: upgrade.available_commit.clone(),
+                })
+                .collect();
+
+            // The lockfile's project-relative form, when the entry was locked
+            let dest = r
+                .locked_entry
+                .as_ref()
+                .or_else(|| previous.entries.get(&r.id))
+                .map_or_else(
+                    || r.dest_path.to_string_lossy().to_string(),
+                    |locked| locked.dest.clone(),
+                );
+            SyncDisplayItem::new(r.id.clone(), dest, status)
+                .with_versions(before, after)
+                .with_upgrades(upgrades)
+                .with_duration(duration(&r.id))
+                .with_warnings(r.warnings.clone())
+        })
+        .collect();
+
+    for entry in unsupported {
+        let error = ApsError::UnsupportedSourceType {
+            source_type: entry
+                .unsupported_source_type()
+                .unwrap_or_default()
+                .to_string(),
+        };
+        display_items.push(
+            SyncDisplayItem::new(
+                entry.id.clone(),
+                entry.destination().to_string_lossy().to_string(),
+                SyncStatus::Error,
+            )
+            .with_warnings(vec![format!("{} (upgrade aps to sync this entry)", error)]),
+        );
     }
+    display_items
 }
 
-/// Print a tree view of on-disk assets for a synced entry
-fn print_asset_tree(path: &Path, kind: &AssetKind, indent: &str) {
-    match kind {
-        AssetKind::AgentSkill => print_skill_tree(path, indent),
-        AssetKind::CursorSkillsRoot => print_skill_tree(path, indent),
-        _ => print_flat_tree(path, indent),
+/// Print the results table and summary of a sync, or the whole result as
+/// JSON
+fn report_results(
+    display_items: &[SyncDisplayItem],
+    manifest_path: &Path,
+    dry_run: bool,
+    overlap_warnings: &[String],
+    orphan_count: usize,
+    format: OutputFormat,
+) -> Result<()> {
+    if format == OutputFormat::Json {
+        let report = SyncReport {
+            dry_run,
+            entries: display_items,
+            orphans_removed: orphan_count,
+            warnings: overlap_warnings,
+        };
+        let json =
+            serde_json::to_string_pretty(&report).map_err(|e| ApsError::LockfileReadError {
+                message: format!("Failed to serialize sync result: {}", e),
+            })?;
+        println!("{}", json);
+        return Ok(());
     }
-}
 
-/// Print tree for agent_skill / cursor_skills_root entries.
-/// Groups contents into the well-known skill structure: SKILL.md, scripts/, references/, assets/
-fn print_skill_tree(path: &Path, indent: &str) {
-    let dim = Style::new().dim();
-    let green = Style::new().green();
-    let cyan = Style::new().cyan();
+    // Print styled results
+    print_sync_results(display_items, manifest_path, dry_run, overlap_warnings);
+
+    // Calculate counts for summary
+    let count = |status: SyncStatus| display_items.iter().filter(|i| i.status == status).count();
+    let warning_count = display_items
+        .iter()
+        .filter(|i| !i.warnings.is_empty() && i.status != SyncStatus::Error)
+        .count();
+
+    // Print summary
+    print_sync_summary(
+        count(SyncStatus::Synced),
+        count(SyncStatus::Copied),
+        count(SyncStatus::Current),
+        count(SyncStatus::Upgradable),
+        warning_count,
+        orphan_count,
+        dry_run,
+    );
+    Ok(())
+}
+
+/// Fail unless every entry being installed is locked as the manifest describes
+/// it. With `manifest_entries` (a full sync), locked entries missing from the
+/// manifest also count as out of sync.
+fn check_frozen(
+    entries: &[&Entry],
+    manifest_entries: Option<&[Entry]>,
+    lockfile: &Lockfile,
+) -> Result<()> {
+    let out_of_sync = |message: String| Err(ApsError::LockfileOutOfSync { message });
+
+    for entry in entries {
+        let Some(locked) = lockfile.entries.get(&entry.id) else {
+            return out_of_sync(format!("'{}' is not in the lockfile", entry.id));
+        };
+        let dest = entry.destination();
+        if locked.dest != dest.to_string_lossy() {
+            return out_of_sync(format!(
+                "'{}' is locked to {} but the manifest installs it to {}",
+                entry.id,
+                locked.dest,
+                dest.display()
+            ));
+        }
+        let is_git = entry
+            .source
+            .as_ref()
+            .is_some_and(|s| s.git_info().is_some());
+        if is_git && locked.commit.is_none() {
+            return out_of_sync(format!("'{}' has no locked commit", entry.id));
+        }
+    }
+
+    if let Some(manifest_entries) = manifest_entries {
+        let mut extra: Vec<&String> = lockfile
+            .entries
+            .keys()
+            .filter(|id| !manifest_entries.iter().any(|e| &e.id == *id))
+            .collect();
+        extra.sort();
+        if let Some(id) = extra.first() {
+            return out_of_sync(format!("'{}' is locked but not in the manifest", id));
+        }
+    }
+    Ok(())
+}
+
+/// Execute the `aps validate` command
+pub fn cmd_validate(args: ValidateArgs) -> Result<()> {
+    // Discover and load manifest
+    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+    if args.render.is_some() {
+        // Only the composed content goes to stdout
+        output::messages_to_stderr();
+    }
+    message!("Validating manifest at {:?}", manifest_path);
+
+    // Validate schema
+    validate_manifest(&manifest)?;
+    if let Some(ref id) = args.render {
+        return render_entry(&manifest, &manifest_path, id);
+    }
+
+    // Keys serde ignores (typos, mostly) only show up against the schemas
+    let schema_warnings = schema_warnings(&manifest, &manifest_path)?;
+    if schema_warnings.is_empty() {
+        println!("  Schema validation passed");
+    } else if args.strict {
+        return Err(ApsError::SchemaViolation {
+            problems: schema_warnings.join("\n"),
+        });
+    }
+
+    // Check for overlapping destinations
+    let overlap_warnings = detect_overlapping_destinations(&manifest);
+    let manifest_warnings: Vec<String> = schema_warnings
+        .iter()
+        .chain(&overlap_warnings)
+        .cloned()
+        .collect();
+    for warning in &manifest_warnings {
+        println!(
+            "  {} {}",
+            console::style("[WARN]").yellow(),
+            console::style(warning).yellow()
+        );
+    }
+
+    // Check sources are reachable
+    let base_dir = manifest_dir(&manifest_path);
+    let options = ValidateOptions {
+        strict: args.strict,
+        deep: args.deep,
+        check_links: args.check_links,
+    };
+    let entries = &selected_entries(&manifest, &manifest_path, &args.only, args.changed)?;
+    if entries.is_empty() {
+        println!("\nNo entries changed since the last sync.");
+        return Ok(());
+    }
+    println!("\nValidating entries:");
+    if let Some(first) = entries.first() {
+        progress::start(1, entries.len(), &first.id);
+    }
+    let mut results = validate_entries(entries, &base_dir, options, args.jobs, |index, result| {
+        progress::finish();
+        result.print();
+        if let Some(next) = entries.get(index + 1) {
+            progress::start(index + 2, entries.len(), &next.id);
+        }
+    });
+
+    if let Some(ref report_path) = args.report {
+        let format = args
+            .report_format
+            .unwrap_or_else(|| report::format_for_path(report_path));
+        report::write_report(
+            report_path,
+            format,
+            &manifest_path,
+            &results,
+            &manifest_warnings,
+        )?;
+        println!("\nWrote report to {:?}", report_path);
+    }
+
+    // Under --strict, fail with the first entry's error
+    if let Some(error) = results.iter_mut().find_map(|r| r.error.take()) {
+        return Err(error);
+    }
+
+    // Enforce max_age against the lockfile
+    check_freshness(&manifest, &manifest_path, true)?;
+
+    // Print summary
+    let warning_count: usize =
+        schema_warnings.len() + results.iter().map(|r| r.warnings.len()).sum::<usize>();
+    if let Some(max) = manifest.max_warnings.filter(|max| warning_count > *max) {
+        return Err(ApsError::TooManyWarnings {
+            count: warning_count,
+            max,
+        });
+    }
+    println!();
+    if warning_count == 0 {
+        println!(
+            "Manifest is valid. All {} entries validated successfully.",
+            entries.len()
+        );
+    } else {
+        println!("Manifest is valid with {} warning(s).", warning_count);
+        if !args.strict {
+            println!("Run with --strict to treat warnings as errors.");
+        }
+    }
+    if !args.deep && !args.check_links && results.iter().any(|r| r.source_type == "git") {
+        println!(
+            "{}",
+            style("Git sources were checked without cloning; run with --deep to check their contents.")
+                .dim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Problems found checking the manifest, and the lockfile and catalog next
+/// to it, against their JSON Schemas
+fn schema_warnings(manifest: &Manifest, manifest_path: &Path) -> Result<Vec<String>> {
+    let files = [
+        (SchemaKind::Manifest, manifest_path.to_path_buf()),
+        (
+            SchemaKind::Lockfile,
+            Lockfile::path_for_manifest(manifest_path, manifest.lockfile_format),
+        ),
+        (
+            SchemaKind::Catalog,
+            Catalog::path_for_manifest(manifest_path),
+        ),
+    ];
+    let mut warnings = Vec::new();
+    for (kind, path) in files {
+        if !path.exists() {
+            continue;
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        for problem in schema::file_problems(kind, &path)? {
+            warnings.push(format!("{}: {}", name, problem));
+        }
+    }
+    Ok(warnings)
+}
+
+/// Compose a composite entry and print it (`aps validate --render`), as the
+/// next sync would install it
+fn render_entry(manifest: &Manifest, manifest_path: &Path, id: &str) -> Result<()> {
+    let entries = select_entries(manifest, &[id.to_string()])?;
+    let Some(entry) = entries.first().filter(|e| e.is_composite()) else {
+        return Err(ApsError::ComposeError {
+            message: format!(
+                "'{}' is not a composite entry; only entries with `sources` can be rendered",
+                id
+            ),
+        });
+    };
+
+    let lockfile_path = Lockfile::path_for_manifest(manifest_path, manifest.lockfile_format);
+    let lockfile = Lockfile::load(&lockfile_path).unwrap_or_else(|_| Lockfile::new());
+    let options = InstallOptions {
+        dry_run: true,
+        render: true,
+        ..Default::default()
+    };
+    let result = install_composite_entry(entry, &manifest_dir(manifest_path), &lockfile, &options)?;
+    for warning in &result.warnings {
+        eprintln!(
+            "{} {}",
+            console::style("[WARN]").yellow(),
+            console::style(warning).yellow()
+        );
+    }
+    Ok(())
+}
+
+/// Entries `aps validate` should check: those named with `--only`, or with
+/// `--changed` those whose definition differs from what was last synced
+fn selected_entries(
+    manifest: &Manifest,
+    manifest_path: &Path,
+    only: &[String],
+    changed: bool,
+) -> Result<Vec<Entry>> {
+    for id in only {
+        if !manifest.entries.iter().any(|e| &e.id == id) {
+            return Err(ApsError::EntryNotFound { id: id.clone() });
+        }
+    }
+    let lockfile = if changed {
+        let lockfile_path = Lockfile::path_for_manifest(manifest_path, manifest.lockfile_format);
+        match Lockfile::load(&lockfile_path) {
+            Err(ApsError::LockfileNotFound) => None,
+            loaded => Some(loaded?),
+        }
+    } else {
+        None
+    };
+    let is_changed = |entry: &Entry| {
+        let Some(ref lockfile) = lockfile else {
+            return true;
+        };
+        entry.expand_targets().iter().any(|target| {
+            lockfile
+                .entries
+                .get(&target.id)
+                .and_then(|locked| locked.definition_hash.as_deref())
+                != Some(entry_hash(target).as_str())
+        })
+    };
+
+    Ok(manifest
+        .entries
+        .iter()
+        .filter(|e| only.is_empty() || only.contains(&e.id))
+        .filter(|e| !changed || is_changed(e))
+        .cloned()
+        .collect())
+}
+
+/// Execute the `aps schema` command
+pub fn cmd_schema(args: SchemaArgs) -> Result<()> {
+    print!("{}", schema::schema_text(args.kind));
+    Ok(())
+}
+
+/// Execute the `aps status` command
+pub fn cmd_status(args: StatusArgs) -> Result<()> {
+    // Discover manifest to find lockfile location
+    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+    let lockfile_path = Lockfile::path_for_manifest(&manifest_path, manifest.lockfile_format);
+
+    // Load lockfile
+    let lockfile = match Lockfile::load(&lockfile_path) {
+        Err(ApsError::LockfileNotFound) if args.check => {
+            return Err(ApsError::StatusCheckFailed {
+                exit_code: EXIT_MISMATCH,
+                message: "there is no lockfile; run `aps sync` to create it".to_string(),
+            });
+        }
+        loaded => loaded?,
+    };
+    let warnings = lockfile_warnings(&manifest, &lockfile);
+    let report = StatusReport::build(
+        &manifest,
+        &lockfile,
+        &manifest_dir(&manifest_path),
+        warnings,
+        args.offline,
+    )?;
+
+    if args.format != StatusFormat::Text {
+        let serialized = match args.format {
+            StatusFormat::Yaml => serde_yaml::to_string(&report).map_err(|e| e.to_string()),
+            _ => serde_json::to_string_pretty(&report)
+                .map(|json| json + "\n")
+                .map_err(|e| e.to_string()),
+        }
+        .map_err(|message| ApsError::LockfileReadError {
+            message: format!("Failed to serialize status: {}", message),
+        })?;
+        print!("{}", serialized);
+        if args.check {
+            report.check()?;
+            // Stale entries are named in the error; stdout stays parseable
+            check_freshness(&manifest, &manifest_path, false)?;
+        }
+        return Ok(());
+    }
+
+    // Display status
+    display_status(&lockfile);
+    if !lockfile.entries.is_empty() {
+        report.print_divergence();
+    }
+
+    if !report.warnings.is_empty() {
+        println!();
+        for warning in &report.warnings {
+            println!("{} {}", style("Warning:").yellow(), warning);
+        }
+    }
+
+    if args.check {
+        report.check()?;
+        check_freshness(&manifest, &manifest_path, true)?;
+    }
+
+    Ok(())
+}
+
+/// Reasons the lockfile may not reflect the manifest: written by a newer aps,
+/// or the manifest was edited after the last full sync
+fn lockfile_warnings(manifest: &Manifest, lockfile: &Lockfile) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    let current =
+        semver::Version::parse(env!("CARGO_PKG_VERSION")).expect("package version is valid semver");
+    if let Ok(written_by) = semver::Version::parse(&lockfile.aps_version) {
+        if written_by > current {
+            warnings.push(format!(
+                "the lockfile was written by aps {}, newer than this aps ({}); upgrade aps before syncing",
+                written_by, current
+            ));
+        }
+    }
+
+    if let Some(ref hash) = lockfile.manifest_hash {
+        if *hash != manifest_hash(manifest) {
+            warnings.push(
+                "the manifest has changed since the lockfile was written; run `aps sync` to update it"
+                    .to_string(),
+            );
+        }
+    }
+
+    warnings
+}
+
+/// Fail if any locked entry is older than its `max_age` policy
+fn check_freshness(manifest: &Manifest, manifest_path: &Path, list: bool) -> Result<()> {
+    let Ok(lockfile) = Lockfile::load(&Lockfile::path_for_manifest(
+        manifest_path,
+        manifest.lockfile_format,
+    )) else {
+        return Ok(());
+    };
+
+    let now = chrono::Utc::now();
+    let stale = find_stale_entries(manifest, &lockfile, now)?;
+    if stale.is_empty() {
+        return Ok(());
+    }
+
+    if list {
+        println!();
+        for entry in &stale {
+            println!("  {} {}", style("[STALE]").yellow(), entry.describe(now));
+        }
+    }
+
+    Err(ApsError::StaleEntries {
+        count: stale.len(),
+        ids: stale
+            .iter()
+            .map(|s| s.id.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+    })
+}
+
+/// Execute the `aps verify` command
+pub fn cmd_verify(args: VerifyArgs) -> Result<()> {
+    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+    let base_dir = manifest_dir(&manifest_path);
+    let lockfile = Lockfile::load(&Lockfile::path_for_manifest(
+        &manifest_path,
+        manifest.lockfile_format,
+    ))?;
+
+    let mut ids: Vec<&String> = lockfile.entries.keys().collect();
+    ids.sort();
+
+    let mut count = 0;
+    for id in ids {
+        match verify_entry(&lockfile, id, &base_dir)? {
+            None => println!(
+                "  {} {} {}",
+                style("?").yellow(),
+                id,
+                style("(no file list recorded; run `aps sync` to record one)").dim()
+            ),
+            Some(drift) if drift.is_empty() => println!("  {} {}", style("✓").green(), id),
+            Some(drift) => {
+                println!("  {} {}", style("✗").red(), style(id).red());
+                for file in &drift {
+                    println!("      {:<9} {}", file.kind.label(), file.path);
+                }
+                count += drift.len();
+            }
+        }
+    }
+
+    if count > 0 {
+        return Err(ApsError::VerifyFailed { count });
+    }
+    Ok(())
+}
+
+/// Execute the `aps why` command
+pub fn cmd_why(args: WhyArgs) -> Result<()> {
+    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+    let base_dir = manifest_dir(&manifest_path);
+    let lockfile = Lockfile::load(&Lockfile::path_for_manifest(
+        &manifest_path,
+        manifest.lockfile_format,
+    ))?;
+
+    let display = args.path.display().to_string();
+    let not_managed = || ApsError::NotManaged {
+        path: display.clone(),
+    };
+    let key = project_relative(&base_dir, &args.path).ok_or_else(not_managed)?;
+    let owners = lockfile.owners_of(&key);
+    if owners.is_empty() {
+        return Err(not_managed());
+    }
+
+    for (i, (id, locked, ownership)) in owners.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let how = match ownership {
+            Ownership::File => "is installed by",
+            Ownership::Dest if locked.is_symlink => "is symlinked by",
+            Ownership::Dest => "is the destination of",
+            Ownership::Unrecorded => "was not installed by aps, but is inside the destination of",
+        };
+        println!("{} {} {}", style(&key).cyan(), how, style(id).bold());
+        if let Some(entry) = manifest.entries.iter().find(|e| e.id == *id) {
+            println!("  Kind:         {}", entry.kind.as_str());
+        }
+        println!("  Source:       {}", locked.source);
+        println!("  Destination:  {}", locked.dest);
+        if let Some(ref resolved_ref) = locked.resolved_ref {
+            println!("  Ref:          {}", resolved_ref);
+        }
+        if let Some(ref commit) = locked.commit {
+            match locked.commit_info {
+                Some(ref info) => println!("  Commit:       {} ({})", commit, info),
+                None => println!("  Commit:       {}", commit),
+            }
+        }
+        for fragment in &locked.fragments {
+            match fragment.commit {
+                Some(ref sha) => println!("  Fragment:     {} @ {}", fragment.source, sha),
+                None => println!("  Fragment:     {}", fragment.source),
+            }
+        }
+        if let Some(checksum) = locked.files.get(&key) {
+            println!("  Checksum:     {}", checksum);
+        }
+        if let Some(ref installed_at) = locked.installed_at {
+            println!("  Installed:    {}", installed_at);
+        }
+    }
+    Ok(())
+}
+
+/// Execute the `aps tree` command
+pub fn cmd_tree(args: TreeArgs) -> Result<()> {
+    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+    let base_dir = manifest_dir(&manifest_path);
+    let lockfile = Lockfile::load(&Lockfile::path_for_manifest(
+        &manifest_path,
+        manifest.lockfile_format,
+    ))?;
+
+    if lockfile.entries.is_empty() {
+        println!("No entries in lockfile.");
+        return Ok(());
+    }
+
+    let mut file_count = 0;
+    let mut drift_count = 0;
+    for (i, (id, locked)) in lockfile.entries.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let dest_key = lock_key(Path::new(&locked.dest));
+        let dest_path = base_dir.join(&locked.dest);
+        let drift: std::collections::BTreeMap<String, DriftKind> =
+            verify_entry(&lockfile, id, &base_dir)?
+                .unwrap_or_default()
+                .into_iter()
+                .map(|d| (d.path, d.kind))
+                .collect();
+        drift_count += drift.len();
+
+        // Notes after the entry's destination
+        let mut notes = Vec::new();
+        if locked.source.is_composite() {
+            notes.push("[composite]".to_string());
+        }
+        if locked.is_symlink {
+            notes.push("[symlink]".to_string());
+        }
+        if locked.readonly {
+            notes.push("[read-only]".to_string());
+        }
+
+        // Files as (path relative to the destination, note)
+        let mut items: Vec<(String, String)> = Vec::new();
+        if locked.is_symlink {
+            for link in symlinks_under(&dest_path) {
+                let target = fs::read_link(&link)
+                    .map(|t| format!("→ {}", t.display()))
+                    .unwrap_or_default();
+                match link.strip_prefix(&dest_path) {
+                    Ok(relative) if !relative.as_os_str().is_empty() => {
+                        items.push((lock_key(relative), target))
+                    }
+                    _ => notes.push(target),
+                }
+            }
+        }
+        let keys: std::collections::BTreeSet<&String> =
+            locked.files.keys().chain(drift.keys()).collect();
+        for key in keys {
+            let note = drift.get(key).map(|kind| drift_note(*kind));
+            match key.strip_prefix(&format!("{}/", dest_key)) {
+                Some(relative) => items.push((relative.to_string(), note.unwrap_or_default())),
+                None => notes.extend(note),
+            }
+        }
+        if locked.files.is_empty() && !locked.is_symlink {
+            notes.push(style("(no file list recorded)").dim().to_string());
+        }
+        file_count += items.len().max(1);
+
+        notes.insert(0, style(&locked.dest).dim().to_string());
+        println!("{} {}", style(id).cyan().bold(), notes.join(" "));
+        for line in render_tree(&items) {
+            println!("{}", line);
+        }
+    }
+
+    println!();
+    let differ = if drift_count > 0 {
+        format!(", {} differ from the lockfile", drift_count)
+    } else {
+        String::new()
+    };
+    println!(
+        "{} entries, {} files{}",
+        lockfile.entries.len(),
+        file_count,
+        differ
+    );
+    Ok(())
+}
+
+fn drift_note(kind: DriftKind) -> String {
+    let label = format!("[{}]", kind.label());
+    match kind {
+        DriftKind::Modified => style(label).yellow().to_string(),
+        DriftKind::Missing => style(label).red().to_string(),
+        DriftKind::Extra => style(label).dim().to_string(),
+    }
+}
+
+/// Symlinks at or below `path`, without following them
+fn symlinks_under(path: &Path) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(path)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path_is_symlink())
+        .map(|e| e.into_path())
+        .collect()
+}
+
+/// Execute the `aps explain` command
+pub fn cmd_explain(args: ExplainArgs) -> Result<()> {
+    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+    let base_dir = manifest_dir(&manifest_path);
+    let lockfile = Lockfile::load(&Lockfile::path_for_manifest(
+        &manifest_path,
+        manifest.lockfile_format,
+    ))
+    .unwrap_or_default();
+
+    // An entry with `targets` or a `dest` list explains each destination
+    let entries: Vec<Entry> = manifest
+        .expanded_entries()
+        .into_iter()
+        .filter(|e| e.id == args.id || e.id.split(':').next() == Some(args.id.as_str()))
+        .collect();
+    if entries.is_empty() {
+        return Err(ApsError::EntryNotFound { id: args.id });
+    }
+
+    for (i, entry) in entries.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        explain_entry(entry, &base_dir, lockfile.entries.get(&entry.id));
+    }
+    Ok(())
+}
+
+/// Print how an entry resolves: its sources, destination, filters and
+/// whether it is symlinked or copied
+fn explain_entry(entry: &Entry, base_dir: &Path, locked: Option<&LockedEntry>) {
+    println!(
+        "{} {}",
+        style("Entry:").dim(),
+        style(&entry.id).cyan().bold()
+    );
+    println!("  Kind:         {}", entry.kind.as_str());
+
+    if let Some(ref source) = entry.source {
+        explain_source(source, base_dir, "  ");
+        match locked.and_then(|l| l.commit.as_deref()) {
+            Some(commit) => println!(
+                "  Locked at:    {} (`aps sync --upgrade` moves it to the ref's latest commit)",
+                commit
+            ),
+            None if source.git_info().is_some() => {
+                println!("  Locked at:    not locked yet; the next sync resolves the ref")
+            }
+            None => {}
+        }
+    }
+    if !entry.sources.is_empty() {
+        println!("  Sources:      composed in this order");
+        for (position, composite) in entry.ordered_sources().into_iter().enumerate() {
+            println!("    {}. {}", position + 1, composite.source.display_path());
+            explain_source(&composite.source, base_dir, "       ");
+            if let Some(ref condition) = composite.when {
+                if let Some(ref exists) = condition.exists {
+                    println!("       Only when:  {} exists", exists);
+                }
+                if let Some(ref lang) = condition.lang {
+                    println!("       Only when:  the project uses {}", lang);
+                }
+            }
+        }
+    }
+    if let Some(ref local) = entry.local {
+        println!("  Local:        {} (appended last)", local);
+    }
+
+    let default = if entry.dest.is_some() {
+        "from `dest`".to_string()
+    } else {
+        format!("default for {}", entry.kind.as_str())
+    };
+    println!(
+        "  Destination:  {} ({})",
+        entry.destination().display(),
+        default
+    );
+
+    if entry.include.is_empty() {
+        if entry.sources.is_empty() {
+            println!("  Include:      all files");
+        }
+    } else {
+        println!("  Include:      files under {}", entry.include.join(", "));
+    }
+    if !entry.skills.is_empty() {
+        println!("  Skills:       {}", entry.skills.join(", "));
+    }
+    for mapping in &entry.map {
+        println!("  Map:          {} -> {}", mapping.from, mapping.to);
+    }
+
+    let install = if !entry.sources.is_empty() {
+        "compose into a single file".to_string()
+    } else {
+        match entry.source {
+            Some(Source::Filesystem { symlink: true, .. }) => match copy_reason(entry) {
+                Some(reason) => format!("copy, because {}", reason),
+                None => "symlink to the source".to_string(),
+            },
+            Some(Source::Filesystem { .. }) => "copy (`symlink: false`)".to_string(),
+            Some(Source::Git { .. }) => "copy from a clone of the repository".to_string(),
+            _ => "unsupported source".to_string(),
+        }
+    };
+    println!("  Install:      {}", install);
+    if entry.symlinks != SymlinkMode::default() {
+        println!(
+            "  Symlinks:     {} (inside copied directories)",
+            entry.symlinks.as_str()
+        );
+    }
+}
+
+/// Print a source's type and where it is read from, with variables expanded
+fn explain_source(source: &Source, base_dir: &Path, indent: &str) {
+    match source {
+        Source::Git {
+            repo,
+            r#ref,
+            shallow,
+            path,
+        } => {
+            println!("{}Source type:  git", indent);
+            println!("{}Repo:         {}", indent, repo);
+            if r#ref == "auto" {
+                println!("{}Ref:          auto (main, then master)", indent);
+            } else {
+                println!("{}Ref:          {}", indent, r#ref);
+            }
+            println!(
+                "{}Clone:        {}",
+                indent,
+                if *shallow { "shallow" } else { "full" }
+            );
+            println!("{}Path:         {}", indent, path.as_deref().unwrap_or("."));
+        }
+        Source::Filesystem { root, path, .. } => {
+            let expanded = PathBuf::from(expand_path(root));
+            let root_path = if expanded.is_absolute() {
+                expanded
+            } else {
+                base_dir.join(expanded)
+            };
+            let resolved = match path {
+                Some(path) => root_path.join(expand_path(path)),
+                None => root_path.clone(),
+            };
+            println!("{}Source type:  filesystem", indent);
+            println!("{}Root:         {}", indent, root_path.display());
+            println!("{}Path:         {}", indent, path.as_deref().unwrap_or("."));
+            let missing = if resolved.exists() { "" } else { " (missing)" };
+            println!("{}Resolves to:  {}{}", indent, resolved.display(), missing);
+        }
+        Source::Unsupported { source_type, .. } => {
+            println!(
+                "{}Source type:  {} (not supported by this aps)",
+                indent, source_type
+            );
+        }
+    }
+}
+
+/// `path` (relative to the working directory, or absolute) as a lockfile key
+/// relative to the manifest directory, or `None` outside the project
+fn project_relative(base_dir: &Path, path: &Path) -> Option<String> {
+    let cwd = std::env::current_dir().ok()?;
+    let absolute = cwd.join(path);
+    // Resolve the parent only, so a symlinked destination is looked up as itself
+    let resolved = match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(parent).ok()?.join(name),
+        _ => fs::canonicalize(&absolute).ok()?,
+    };
+    let base = fs::canonicalize(cwd.join(base_dir)).ok()?;
+    let relative = resolved.strip_prefix(&base).ok()?;
+    Some(lock_key(relative))
+}
+
+/// Execute the `aps lock diff` command
+pub fn cmd_lock_diff(args: LockDiffArgs) -> Result<()> {
+    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+    let base_dir = manifest_dir(&manifest_path);
+    let lockfile_path = Lockfile::path_for_manifest(&manifest_path, manifest.lockfile_format);
+    let lockfile_name = lockfile_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| LOCKFILE_NAME.to_string());
+    let current = Lockfile::load(&lockfile_path)?;
+
+    let (previous, label) = match args.against {
+        Some(ref path) => (Lockfile::load(path)?, path.display().to_string()),
+        None => (
+            Lockfile::parse(&show_file_at_revision(
+                &base_dir,
+                &args.rev,
+                &lockfile_name,
+            )?)?,
+            args.rev.clone(),
+        ),
+    };
+
+    let changes = diff_lockfiles(&previous, &current);
+    if args.format == OutputFormat::Json {
+        let json =
+            serde_json::to_string_pretty(&changes).map_err(|e| ApsError::LockfileReadError {
+                message: format!("Failed to serialize lockfile diff: {}", e),
+            })?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    println!(
+        "{} {} {}",
+        style("Comparing").dim(),
+        style(&lockfile_name).cyan(),
+        style(format!("against {}", label)).dim()
+    );
+    println!();
+    if changes.is_empty() {
+        println!("No entries changed.");
+        return Ok(());
+    }
+    for change in &changes {
+        print_lock_change(change);
+    }
+
+    let count = |kind| changes.iter().filter(|c| c.kind == kind).count();
+    println!();
+    println!(
+        "{} added, {} removed, {} changed",
+        count(LockChangeKind::Added),
+        count(LockChangeKind::Removed),
+        count(LockChangeKind::Changed)
+    );
+    Ok(())
+}
+
+/// Print one entry of a lockfile diff
+fn print_lock_change(change: &LockChange) {
+    match change.kind {
+        LockChangeKind::Added => println!("  {} {}", style("+").green(), change.id),
+        LockChangeKind::Removed => println!("  {} {}", style("-").red(), change.id),
+        LockChangeKind::Changed => {
+            println!("  {} {}", style("~").yellow(), change.id);
+            for field in &change.fields {
+                let short = |value: &Option<String>| match (field.field, value) {
+                    (_, None) => "(none)".to_string(),
+                    ("commit", Some(sha)) => sha[..8.min(sha.len())].to_string(),
+                    ("checksum", Some(sum)) => sum[..19.min(sum.len())].to_string(),
+                    (_, Some(value)) => value.clone(),
+                };
+                println!(
+                    "      {:<9} {} {} {}",
+                    field.field,
+                    short(&field.old),
+                    style("→").dim(),
+                    short(&field.new)
+                );
+            }
+        }
+    }
+}
+
+/// Execute the `aps lock resolve` command
+pub fn cmd_lock_resolve(args: LockResolveArgs) -> Result<()> {
+    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+    let lockfile_path = Lockfile::path_for_manifest(&manifest_path, manifest.lockfile_format);
+    let content = fs::read_to_string(&lockfile_path)
+        .map_err(|e| ApsError::io(e, format!("Failed to read lockfile at {:?}", lockfile_path)))?;
+
+    let Some(sides) = split_conflict(&content)? else {
+        println!("No conflict markers in {}.", lockfile_path.display());
+        return Ok(());
+    };
+
+    let prefer = if args.ours {
+        Some(Side::Ours)
+    } else if args.theirs {
+        Some(Side::Theirs)
+    } else {
+        None
+    };
+    let interactive = std::io::stdin().is_terminal();
+    let mut unresolved = Vec::new();
+    let (mut merged, resolutions) = resolve_conflict(&sides, |id, newer| {
+        if let Some(side) = prefer.or(newer) {
+            return Ok(side);
+        }
+        if !interactive {
+            unresolved.push(id.to_string());
+            return Ok(Side::Ours);
+        }
+        let choice = dialoguer::Select::new()
+            .with_prompt(format!("Neither side of '{}' is newer. Keep", id))
+            .items(&[
+                format!("ours ({})", sides.ours_label),
+                format!("theirs ({})", sides.theirs_label),
+            ])
+            .default(0)
+            .interact()
+            .map_err(|_| ApsError::Cancelled)?;
+        Ok(if choice == 0 {
+            Side::Ours
+        } else {
+            Side::Theirs
+        })
+    })?;
+
+    if !unresolved.is_empty() {
+        return Err(ApsError::UnresolvedLockConflict {
+            ids: unresolved.join(", "),
+        });
+    }
+
+    merged.save(&lockfile_path)?;
+
+    println!(
+        "Resolved {} conflicting entr{} in {}",
+        resolutions.len(),
+        if resolutions.len() == 1 { "y" } else { "ies" },
+        lockfile_path.display()
+    );
+    for resolution in &resolutions {
+        let side = match resolution.side {
+            Side::Ours => "ours",
+            Side::Theirs => "theirs",
+        };
+        println!(
+            "  {} {} {}",
+            style("~").yellow(),
+            resolution.id,
+            style(format!(
+                "kept {} ({}){}",
+                side,
+                sides.label(resolution.side),
+                if resolution.newer { ", newer" } else { "" }
+            ))
+            .dim()
+        );
+    }
+    println!();
+    println!("Run `aps sync` to install the resolved versions.");
+    Ok(())
+}
+
+/// Execute the `aps list` command
+pub fn cmd_list(args: ListArgs) -> Result<()> {
+    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+    let base_dir = manifest_dir(&manifest_path);
+
+    let manifest_display = manifest_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| manifest_path.to_string_lossy().to_string());
+
+    let dim = Style::new().dim();
+    let cyan = Style::new().cyan();
+    let green = Style::new().green();
+    let yellow = Style::new().yellow();
+    let white_bold = Style::new().white().bold();
+
+    println!(
+        "{} {} {}",
+        style("Manifest:").dim(),
+        cyan.apply_to(&manifest_display),
+        dim.apply_to(format!("({} entries)", manifest.entries.len()))
+    );
+    println!();
+
+    // Load lockfile once for status checks
+    let lockfile_path = Lockfile::path_for_manifest(&manifest_path, manifest.lockfile_format);
+    let lockfile = Lockfile::load(&lockfile_path).ok();
+
+    for (i, entry) in manifest.entries.iter().enumerate() {
+        // Entry header: ID and kind
+        let kind_label = format_kind_label(&entry.kind);
+        println!(
+            "  {} {}",
+            white_bold.apply_to(&entry.id),
+            dim.apply_to(&kind_label),
+        );
+
+        // Source info
+        if entry.is_composite() {
+            println!(
+                "  {} composite ({} sources)",
+                dim.apply_to("Source:"),
+                entry.sources.len()
+            );
+            for (j, src) in entry.sources.iter().enumerate() {
+                let connector = if j == entry.sources.len() - 1 {
+                    "└──"
+                } else {
+                    "├──"
+                };
+                println!(
+                    "  {}  {} {}",
+                    dim.apply_to("       "),
+                    dim.apply_to(connector),
+                    dim.apply_to(format_source_short(&src.source)),
+                );
+            }
+        } else if let Some(ref source) = entry.source {
+            println!(
+                "  {} {}",
+                dim.apply_to("Source:"),
+                dim.apply_to(format_source_short(source)),
+            );
+        }
+
+        // Destination (one per target for fanned-out entries)
+        let dest = entry.destination();
+        let dest_display = entry
+            .expand_targets()
+            .iter()
+            .map(|e| {
+                let s = e.destination().to_string_lossy().to_string();
+                if s.starts_with("./") || s.starts_with('/') {
+                    s
+                } else {
+                    format!("./{}", s)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "  {} {}",
+            dim.apply_to("Dest:  "),
+            cyan.apply_to(&dest_display),
+        );
+
+        // Skills selection
+        if !entry.skills.is_empty() {
+            println!(
+                "  {} {}",
+                dim.apply_to("Skills:"),
+                yellow.apply_to(entry.skills.join(", ")),
+            );
+        }
+
+        // Include filter
+        if !entry.include.is_empty() {
+            println!(
+                "  {} {}",
+                dim.apply_to("Filter:"),
+                yellow.apply_to(entry.include.join(", ")),
+            );
+        }
+
+        // On-disk asset tree (when --assets is passed and destination exists)
+        if args.assets {
+            let abs_dest = if dest.is_relative() {
+                base_dir.join(&dest)
+            } else {
+                dest.clone()
+            };
+
+            if abs_dest.is_dir() {
+                println!("  {}", dim.apply_to("Assets:"));
+                print_asset_tree(&abs_dest, &entry.kind, "  ");
+            } else if abs_dest.is_file() {
+                println!(
+                    "  {} {}",
+                    dim.apply_to("Assets:"),
+                    green.apply_to(
+                        abs_dest
+                            .file_name()
+                            .map(|n| n.to_string_lossy().to_string())
+                            .unwrap_or_default()
+                    ),
+                );
+            } else {
+                println!(
+                    "  {} {}",
+                    dim.apply_to("Assets:"),
+                    dim.apply_to("(not synced)"),
+                );
+            }
+        }
+
+        // Sync status indicator
+        if let Some(ref lf) = lockfile {
+            if is_entry_synced(entry, lf) {
+                println!("  {} {}", green.apply_to("●"), green.apply_to("synced"));
+            }
+        }
+
+        // Separator between entries (but not after the last)
+        if i < manifest.entries.len() - 1 {
+            println!();
+        }
+    }
+
+    println!();
+
+    // Summary
+    let synced_count = match lockfile {
+        Some(ref lf) => manifest
+            .entries
+            .iter()
+            .filter(|e| is_entry_synced(e, lf))
+            .count(),
+        None => 0,
+    };
+    let total = manifest.entries.len();
+    if synced_count == total {
+        println!(
+            "{}",
+            green.apply_to(format!("All {} entries synced", total))
+        );
+    } else {
+        println!(
+            "{} synced, {} pending",
+            green.apply_to(synced_count),
+            yellow.apply_to(total - synced_count),
+        );
+    }
+
+    Ok(())
+}
+
+/// Format the AssetKind as a human-readable label
+fn format_kind_label(kind: &AssetKind) -> String {
+    kind.as_str().to_string()
+}
+
+/// Whether an entry (and every target it fans out to) is in the lockfile
+fn is_entry_synced(entry: &Entry, lockfile: &Lockfile) -> bool {
+    entry
+        .expand_targets()
+        .iter()
+        .all(|e| lockfile.entries.contains_key(&e.id))
+}
+
+/// Format a source for compact display
+fn format_source_short(source: &Source) -> String {
+    match source {
+        Source::Git {
+            repo, r#ref, path, ..
+        } => {
+            // Shorten GitHub URLs: https://github.com/owner/repo.git -> owner/repo
+            let short_repo = repo
+                .trim_end_matches(".git")
+                .strip_prefix("https://github.com/")
+                .unwrap_or(repo);
+
+            let ref_part = if r#ref == "auto" {
+                String::new()
+            } else {
+                format!(" @ {}", r#ref)
+            };
+
+            if let Some(p) = path {
+                format!("git: {}{} → {}", short_repo, ref_part, p)
+            } else {
+                format!("git: {}{}", short_repo, ref_part)
+            }
+        }
+        Source::Filesystem {
+            root,
+            path,
+            symlink,
+        } => {
+            let sym_tag = if *symlink { " (symlink)" } else { "" };
+            if let Some(p) = path {
+                format!("fs: {}/{}{}", root, p, sym_tag)
+            } else {
+                format!("fs: {}{}", root, sym_tag)
+            }
+        }
+        Source::Unsupported { source_type, .. } => format!("{}: (unsupported)", source_type),
+    }
+}
+
+/// Print a tree view of on-disk assets for a synced entry
+fn print_asset_tree(path: &Path, kind: &AssetKind, indent: &str) {
+    match kind {
+        AssetKind::AgentSkill => print_skill_tree(path, indent),
+        AssetKind::CursorSkillsRoot => print_skill_tree(path, indent),
+        _ => print_flat_tree(path, indent),
+    }
+}
+
+/// Print tree for agent_skill / cursor_skills_root entries.
+/// Groups contents into the well-known skill structure: SKILL.md, scripts/, references/, assets/
+fn print_skill_tree(path: &Path, indent: &str) {
+    let dim = Style::new().dim();
+    let green = Style::new().green();
+    let cyan = Style::new().cyan();
+
+    // If path is a directory containing skill subdirectories, enumerate each
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    let mut items: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name() != ".git")
+        .collect();
+    items.sort_by_key(|e| e.file_name());
+
+    // Check if this is a single skill directory (contains SKILL.md directly)
+    let has_skill_md = items.iter().any(|e| {
+        e.file_name()
+            .to_string_lossy()
+            .eq_ignore_ascii_case("skill.md")
+    });
+
+    if has_skill_md {
+        // This is a single skill folder - show its structure
+        print_single_skill_contents(&items, indent);
+    } else {
+        // This is a directory of skills - enumerate each
+        let total = items.len();
+        for (i, item) in items.iter().enumerate() {
+            let is_last = i == total - 1;
+            let connector = if is_last { "└── " } else { "├── " };
+            let name = item.file_name();
+            let name = name.to_string_lossy();
+
+            if item.path().is_dir() {
+                println!(
+                    "{}{}{}{}",
+                    indent,
+                    dim.apply_to(connector),
+                    cyan.apply_to(&*name),
+                    dim.apply_to("/"),
+                );
+
+                // Check if subdirectory is a skill (has SKILL.md)
+                let sub_indent = if is_last {
+                    format!("{}    ", indent)
+                } else {
+                    format!("{}│   ", indent)
+                };
+
+                let sub_entries = match std::fs::read_dir(item.path()) {
+                    Ok(entries) => {
+                        let mut items: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+                        items.sort_by_key(|e| e.file_name());
+                        items
+                    }
+                    Err(_) => continue,
+                };
+
+                print_single_skill_contents(&sub_entries, &sub_indent);
+            } else {
+                println!(
+                    "{}{}{}",
+                    indent,
+                    dim.apply_to(connector),
+                    green.apply_to(&*name),
+                );
+            }
+        }
+    }
+}
+
+/// Print the contents of a single skill directory, highlighting well-known structure
+fn print_single_skill_contents(items: &[std::fs::DirEntry], indent: &str) {
+    let dim = Style::new().dim();
+    let green = Style::new().green();
+    let cyan = Style::new().cyan();
+    let yellow = Style::new().yellow();
+
+    // Categorize items into well-known skill directories and other files
+    let well_known_dirs = ["scripts", "references", "assets"];
+
+    let total = items.len();
+    for (i, item) in items.iter().enumerate() {
+        let is_last = i == total - 1;
+        let connector = if is_last { "└── " } else { "├── " };
+        let name = item.file_name();
+        let name_str = name.to_string_lossy();
+
+        if item.path().is_dir() {
+            let dir_style = if well_known_dirs.contains(&name_str.as_ref()) {
+                &yellow
+            } else {
+                &cyan
+            };
+
+            // Count children
+            let child_count = std::fs::read_dir(item.path())
+                .map(|rd| rd.filter_map(|e| e.ok()).count())
+                .unwrap_or(0);
+
+            println!(
+                "{}{}{}{}  {}",
+                indent,
+                dim.apply_to(connector),
+                dir_style.apply_to(&*name_str),
+                dim.apply_to("/"),
+                dim.apply_to(format!("({} items)", child_count)),
+            );
+        } else {
+            // Highlight SKILL.md specially
+            let file_style = if name_str.eq_ignore_ascii_case("skill.md") {
+                &green
+            } else {
+                &dim
+            };
+            println!(
+                "{}{}{}",
+                indent,
+                dim.apply_to(connector),
+                file_style.apply_to(&*name_str),
+            );
+        }
+    }
+}
+
+/// Print a simple flat tree for non-skill asset types
+fn print_flat_tree(path: &Path, indent: &str) {
+    let dim = Style::new().dim();
+    let green = Style::new().green();
 
-    // If path is a directory containing skill subdirectories, enumerate each
     let entries = match std::fs::read_dir(path) {
         Ok(entries) => entries,
         Err(_) => return,
     };
 
-    let mut items: Vec<_> = entries
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_name() != ".git")
+    let mut items: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name() != ".git")
+        .collect();
+    items.sort_by_key(|e| e.file_name());
+
+    let total = items.len();
+    for (i, item) in items.iter().enumerate() {
+        let is_last = i == total - 1;
+        let connector = if is_last { "└── " } else { "├── " };
+        let name = item.file_name();
+        let name_str = name.to_string_lossy();
+
+        if item.path().is_dir() {
+            let child_count = std::fs::read_dir(item.path())
+                .map(|rd| rd.filter_map(|e| e.ok()).count())
+                .unwrap_or(0);
+            println!(
+                "{}{}{}{}  {}",
+                indent,
+                dim.apply_to(connector),
+                green.apply_to(&*name_str),
+                dim.apply_to("/"),
+                dim.apply_to(format!("({} items)", child_count)),
+            );
+        } else {
+            println!(
+                "{}{}{}",
+                indent,
+                dim.apply_to(connector),
+                green.apply_to(&*name_str),
+            );
+        }
+    }
+}
+
+/// Execute the `aps catalog generate` command
+pub fn cmd_catalog_generate(args: CatalogGenerateArgs) -> Result<()> {
+    let (catalog, output_path) = match args.from {
+        Some(ref from) => {
+            println!("Scanning {} for assets", from);
+            let catalog = if is_remote(from) {
+                let cloned = clone_and_resolve(from, "auto", true)?;
+                let source = Source::Git {
+                    repo: from.clone(),
+                    r#ref: cloned.resolved_ref.clone(),
+                    shallow: true,
+                    path: None,
+                };
+                Catalog::generate_from_dir(&cloned.repo_path, &source)?
+            } else {
+                let root = PathBuf::from(expand_path(from));
+                let root = root.canonicalize().unwrap_or(root);
+                let source = Source::Filesystem {
+                    root: root.display().to_string(),
+                    symlink: true,
+                    path: None,
+                };
+                Catalog::generate_from_dir(&root, &source)?
+            };
+            let output_path = args
+                .output
+                .unwrap_or_else(|| PathBuf::from(CATALOG_FILENAME));
+            (catalog, output_path)
+        }
+        None => {
+            // Discover and load manifest
+            let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+            let base_dir = manifest_dir(&manifest_path);
+
+            println!("Using manifest: {:?}", manifest_path);
+
+            // Validate manifest
+            validate_manifest(&manifest)?;
+
+            // Generate catalog
+            let catalog = Catalog::generate_from_manifest(&manifest, &base_dir)?;
+
+            // Determine output path
+            let output_path = args
+                .output
+                .unwrap_or_else(|| Catalog::path_for_manifest(&manifest_path));
+            (catalog, output_path)
+        }
+    };
+
+    // Keep the release and publish target of a catalog being regenerated
+    let mut catalog = catalog;
+    if let Ok(existing) = Catalog::load(&output_path) {
+        catalog.release = existing.release;
+        catalog.publish = existing.publish;
+    }
+
+    // Save catalog
+    catalog.save(&output_path)?;
+
+    println!(
+        "Generated catalog with {} entries at {:?}",
+        catalog.entries.len(),
+        output_path
+    );
+
+    // Count entries with descriptions
+    let with_desc = catalog
+        .entries
+        .iter()
+        .filter(|e| e.short_description.is_some())
+        .count();
+
+    if with_desc > 0 {
+        println!("  {} entries have descriptions", with_desc);
+    }
+
+    Ok(())
+}
+
+/// Load the catalog from a registry, an explicit path or URL, the
+/// manifest's `catalogs`, or next to the manifest
+fn load_catalog(
+    catalog: Option<String>,
+    manifest: Option<&Path>,
+    registry: &RegistryArgs,
+) -> Result<(Catalog, String)> {
+    let mut catalogs = load_catalogs(catalog, manifest, registry)?;
+    let label = catalogs
+        .iter()
+        .map(|(location, _)| location.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    if catalogs.len() == 1 {
+        return Ok((catalogs.remove(0).1, label));
+    }
+    Ok((Catalog::merge(catalogs), label))
+}
+
+/// Read each catalog to merge, labelled with its location, in precedence
+/// order
+fn load_catalogs(
+    catalog: Option<String>,
+    manifest: Option<&Path>,
+    registry: &RegistryArgs,
+) -> Result<Vec<(String, Catalog)>> {
+    let mode = if registry.offline {
+        RefreshMode::Offline
+    } else if registry.refresh {
+        RefreshMode::Force
+    } else {
+        RefreshMode::Auto
+    };
+
+    let locations = catalog_locations(catalog, manifest, registry)?;
+    let mut catalogs = Vec::new();
+    for location in &locations {
+        if !is_remote(location) {
+            catalogs.push((location.clone(), Catalog::load(Path::new(location))?));
+            continue;
+        }
+        let index = load_index(location, mode)?;
+        if let Some(notice) = index.staleness_notice(chrono::Utc::now()) {
+            eprintln!("{} {}", style("Warning:").yellow(), notice);
+        }
+        catalogs.push((location.clone(), index.catalog));
+    }
+    Ok(catalogs)
+}
+
+/// Paths and URLs of the catalogs to read
+fn catalog_locations(
+    catalog: Option<String>,
+    manifest: Option<&Path>,
+    registry: &RegistryArgs,
+) -> Result<Vec<String>> {
+    if let Some(ref url) = registry.registry {
+        return Ok(vec![url.clone()]);
+    }
+    if let Some(catalog) = catalog {
+        return Ok(vec![catalog]);
+    }
+
+    let (manifest, manifest_path) = discover_manifest(manifest)?;
+    if manifest.catalogs.is_empty() {
+        let path = Catalog::path_for_manifest(&manifest_path);
+        return Ok(vec![path.display().to_string()]);
+    }
+    // Local catalogs are relative to the manifest, with `~` and `$VAR` expanded
+    let base_dir = manifest_dir(&manifest_path);
+    Ok(manifest
+        .catalogs
+        .iter()
+        .map(|location| {
+            if is_remote(location) {
+                location.clone()
+            } else {
+                base_dir.join(expand_path(location)).display().to_string()
+            }
+        })
+        .collect())
+}
+
+/// Print a page of results as pretty JSON
+fn print_json_page<T: serde::Serialize>(page: &Page<T>) -> Result<()> {
+    let json = serde_json::to_string_pretty(page).map_err(|e| ApsError::CatalogReadError {
+        message: format!("Failed to serialize results: {}", e),
+    })?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// Print a single catalog entry in the text layout shared by list and search
+fn print_catalog_entry(entry: &CatalogEntry, score: Option<f64>) {
+    let dim = Style::new().dim();
+    let white_bold = Style::new().white().bold();
+
+    let score_label = score
+        .map(|s| format!(" (score {:.2})", s))
+        .unwrap_or_default();
+    println!(
+        "  {} {}{}",
+        white_bold.apply_to(&entry.id),
+        dim.apply_to(format_kind_label(&entry.kind)),
+        dim.apply_to(score_label),
+    );
+    if let Some(desc) = &entry.short_description {
+        println!("    {}", desc);
+    }
+    if let Some(catalog) = &entry.catalog {
+        println!("    {}", dim.apply_to(format!("from {}", catalog)));
+    }
+}
+
+/// Print the "showing X-Y of N" footer for paginated text output
+fn print_page_footer<T>(page: &Page<T>) {
+    if page.entries.is_empty() {
+        return;
+    }
+    if page.offset > 0 || page.has_more() {
+        println!();
+        println!(
+            "{}",
+            style(format!(
+                "Showing {}-{} of {}",
+                page.offset + 1,
+                page.offset + page.entries.len(),
+                page.total
+            ))
+            .dim()
+        );
+    }
+}
+
+/// Execute the `aps catalog list` command
+pub fn cmd_catalog_list(args: CatalogListArgs) -> Result<()> {
+    let (catalog, catalog_label) =
+        load_catalog(args.catalog, args.manifest.as_deref(), &args.registry)?;
+
+    let page = Page::paginate(catalog.entries, args.offset, args.limit);
+
+    if args.format == OutputFormat::Json {
+        return print_json_page(&page);
+    }
+
+    println!(
+        "{} {} {}",
+        style("Catalog:").dim(),
+        style(catalog_label).cyan(),
+        style(format!("({} entries)", page.total)).dim()
+    );
+    println!();
+
+    for entry in &page.entries {
+        print_catalog_entry(entry, None);
+    }
+    print_page_footer(&page);
+
+    Ok(())
+}
+
+/// Execute the `aps catalog info` command
+pub fn cmd_catalog_info(args: CatalogInfoArgs) -> Result<()> {
+    let (catalog, _) = load_catalog(args.catalog, args.manifest.as_deref(), &args.registry)?;
+    let entry = find_catalog_entry(&catalog, &args.id)?;
+
+    if args.format == OutputFormat::Json {
+        let json = serde_json::to_string_pretty(entry).map_err(|e| ApsError::CatalogReadError {
+            message: format!("Failed to serialize entry: {}", e),
+        })?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    let dim = Style::new().dim();
+    print_catalog_entry(entry, None);
+    println!();
+    println!("  {} {}", dim.apply_to("Destination:"), entry.destination);
+    if !entry.tags.is_empty() {
+        println!("  {} {}", dim.apply_to("Tags:"), entry.tags.join(", "));
+    }
+    match &entry.source {
+        Some(source) => println!(
+            "  {} {}",
+            dim.apply_to("Source:"),
+            format_source_short(source)
+        ),
+        None => println!(
+            "  {} {}",
+            dim.apply_to("Source:"),
+            dim.apply_to("none (can't be added to a manifest)")
+        ),
+    }
+
+    let versions = entry.sorted_versions();
+    if versions.is_empty() {
+        return Ok(());
+    }
+    println!("  {}", dim.apply_to("Versions:"));
+    for (version, v) in versions {
+        let git_ref = v
+            .git_ref
+            .as_ref()
+            .map(|r| format!(" @ {}", r))
+            .unwrap_or_default();
+        println!(
+            "    {}{}  {}",
+            style(version).cyan(),
+            dim.apply_to(git_ref),
+            v.changelog.as_deref().unwrap_or("")
+        );
+    }
+
+    Ok(())
+}
+
+/// Execute the `aps catalog lint` command
+pub fn cmd_catalog_lint(args: CatalogLintArgs) -> Result<()> {
+    let catalogs = load_catalogs(args.catalog, args.manifest.as_deref(), &args.registry)?;
+    let issues = lint_catalogs(&catalogs, args.deep);
+    let failures = issues
+        .iter()
+        .filter(|issue| args.strict || !issue.kind.is_warning())
+        .count();
+
+    if args.format == OutputFormat::Json {
+        let json =
+            serde_json::to_string_pretty(&issues).map_err(|e| ApsError::CatalogReadError {
+                message: format!("Failed to serialize results: {}", e),
+            })?;
+        println!("{}", json);
+    } else {
+        let entry_count: usize = catalogs.iter().map(|(_, c)| c.entries.len()).sum();
+        print_lint_issues(&catalogs, &issues, args.strict);
+        let warning_count = issues.len() - failures;
+        if issues.is_empty() {
+            println!("All {} catalog entries look healthy.", entry_count);
+        } else if failures == 0 {
+            println!(
+                "{} catalog entries checked, {} warning(s).",
+                entry_count, warning_count
+            );
+            println!("Run with --strict to treat warnings as errors.");
+        }
+        if !args.deep
+            && catalogs.iter().any(|(_, c)| {
+                c.entries
+                    .iter()
+                    .any(|e| matches!(e.source, Some(Source::Git { .. })))
+            })
+        {
+            println!(
+                "{}",
+                style("Paths in git sources were not checked; run with --deep to clone them.")
+                    .dim()
+            );
+        }
+    }
+
+    if failures > 0 {
+        return Err(ApsError::CatalogLintFailed { count: failures });
+    }
+    Ok(())
+}
+
+/// Print lint problems grouped by catalog
+fn print_lint_issues(catalogs: &[(String, Catalog)], issues: &[LintIssue], strict: bool) {
+    for (location, _) in catalogs {
+        let catalog_issues: Vec<_> = issues.iter().filter(|i| &i.catalog == location).collect();
+        if catalog_issues.is_empty() {
+            continue;
+        }
+        println!("{} {}", style("Catalog:").dim(), style(location).cyan());
+        for issue in catalog_issues {
+            let badge = if strict || !issue.kind.is_warning() {
+                style("[FAILED]").red()
+            } else {
+                style("[WARN]").yellow()
+            };
+            println!("  {} {}: {}", badge, issue.id, issue.message);
+        }
+        println!();
+    }
+}
+
+/// Execute the `aps catalog publish` command
+pub fn cmd_catalog_publish(args: CatalogPublishArgs) -> Result<()> {
+    let catalog_path = match args.catalog {
+        Some(path) => path,
+        None => {
+            let (_, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+            Catalog::path_for_manifest(&manifest_path)
+        }
+    };
+    if !catalog_path.exists() {
+        return Err(ApsError::CatalogNotFound);
+    }
+    let original = fs::read_to_string(&catalog_path)
+        .map_err(|e| ApsError::io(e, format!("Failed to read {:?}", catalog_path)))?;
+    let mut catalog = Catalog::load(&catalog_path)?;
+
+    let configured = catalog.publish.clone();
+    let target = match (args.to, configured) {
+        (Some(to), configured) => PublishTarget {
+            to,
+            branch: args.branch.or(configured.and_then(|c| c.branch)),
+            path: None,
+        },
+        (None, Some(configured)) => PublishTarget {
+            branch: args.branch.or(configured.branch),
+            ..configured
+        },
+        (None, None) => {
+            return Err(ApsError::InvalidInput {
+                message: "Nowhere to publish to: set `publish.to` in the catalog or pass --to"
+                    .to_string(),
+            })
+        }
+    };
+
+    let catalogs = vec![(catalog_path.display().to_string(), catalog.clone())];
+    let issues = lint_catalogs(&catalogs, args.deep);
+    print_lint_issues(&catalogs, &issues, false);
+    let failures = issues.iter().filter(|i| !i.kind.is_warning()).count();
+    if failures > 0 {
+        return Err(ApsError::CatalogLintFailed { count: failures });
+    }
+
+    let release = bump_release(catalog.release.as_deref(), args.bump)?;
+    if args.dry_run {
+        println!(
+            "Would publish {} entries as release {} to {}",
+            catalog.entries.len(),
+            release,
+            target.to
+        );
+        return Ok(());
+    }
+
+    catalog.release = Some(release.clone());
+    catalog.save(&catalog_path)?;
+    if let Err(e) = publish_catalog(&catalog_path, &target, &release) {
+        // Leave the release unbumped so the next attempt publishes it
+        fs::write(&catalog_path, original)
+            .map_err(|e| ApsError::io(e, format!("Failed to restore {:?}", catalog_path)))?;
+        return Err(e);
+    }
+
+    println!(
+        "  {} {}",
+        style("✓").green(),
+        style(format!(
+            "Published catalog release {} ({} entries) to {}",
+            release,
+            catalog.entries.len(),
+            target.to
+        ))
+        .green()
+    );
+    Ok(())
+}
+
+/// Execute the `aps suggest` command
+pub fn cmd_suggest(args: SuggestArgs) -> Result<()> {
+    let (catalog, _) = load_catalog(args.catalog, args.manifest.as_deref(), &args.registry)?;
+
+    let project_dir =
+        std::env::current_dir().map_err(|e| ApsError::io(e, "Failed to get current directory"))?;
+    let signals = if args.no_detect {
+        Vec::new()
+    } else {
+        detect_project(&project_dir)
+    };
+    if args.query.is_none() && signals.is_empty() {
+        return Err(ApsError::InvalidInput {
+            message: "Nothing to suggest from: give a query, or run aps suggest in a project \
+                      whose languages or tooling it can detect"
+                .to_string(),
+        });
+    }
+
+    let suggestions = suggest(&catalog, args.query.as_deref(), &signals, load_adoption());
+    if args.pick {
+        return pick_suggestions(suggestions, args.manifest);
+    }
+    if !args.add.is_empty() {
+        let entries = args
+            .add
+            .iter()
+            .map(|spec| {
+                let (id, requirement) = split_version_spec(spec);
+                let entry = find_catalog_entry(&catalog, id)?;
+                let manifest_entry =
+                    catalog_manifest_entry(entry, entry.find_version(requirement)?)?;
+                Ok((entry.id.clone(), manifest_entry))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        return write_catalog_entries(entries, args.manifest);
+    }
+    if let Some(n) = args.add_top {
+        let entries = suggestions
+            .iter()
+            .take(n)
+            .map(|s| {
+                let manifest_entry = catalog_manifest_entry(&s.entry, s.entry.find_version(None)?)?;
+                Ok((s.entry.id.clone(), manifest_entry))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if entries.is_empty() {
+            println!("No catalog entries to suggest");
+            return Ok(());
+        }
+        return write_catalog_entries(entries, args.manifest);
+    }
+    let page = Page::paginate(suggestions, 0, Some(args.limit));
+
+    if args.format == OutputFormat::Json {
+        return print_json_page(&page);
+    }
+
+    if !signals.is_empty() {
+        println!(
+            "{} {}",
+            style("Detected:").dim(),
+            style(signals.join(", ")).cyan()
+        );
+    }
+    if page.total == 0 {
+        println!("No catalog entries to suggest");
+        return Ok(());
+    }
+    let heading = match args.query {
+        Some(ref query) => format!("Suggestions for {:?}", query),
+        None => "Suggested for this project".to_string(),
+    };
+    println!(
+        "{} {}",
+        style(heading).dim(),
+        style(format!("({} matches)", page.total)).dim()
+    );
+    println!();
+
+    for suggestion in &page.entries {
+        print_catalog_entry(&suggestion.entry, Some(suggestion.score));
+        if !suggestion.signals.is_empty() {
+            println!(
+                "    {}",
+                style(format!("matches {}", suggestion.signals.join(", "))).dim()
+            );
+        }
+    }
+    print_page_footer(&page);
+
+    Ok(())
+}
+
+/// Filter suggestions by a fuzzy pattern, let the user toggle which to add,
+/// and append the chosen ones to the manifest
+fn pick_suggestions(suggestions: Vec<Suggestion>, manifest: Option<PathBuf>) -> Result<()> {
+    if !std::io::stdin().is_terminal() {
+        return Err(ApsError::PromptRequired {
+            prompt: "Pick suggestions to add".to_string(),
+        });
+    }
+
+    let (addable, unaddable): (Vec<_>, Vec<_>) = suggestions
+        .into_iter()
+        .map(|s| {
+            let version = s.entry.find_version(None).ok().flatten();
+            let entry = s.entry.to_manifest_entry(version);
+            (s, entry)
+        })
+        .partition(|(_, entry)| entry.is_some());
+    if !unaddable.is_empty() {
+        println!(
+            "{}",
+            style(format!(
+                "{} suggestion(s) can't be added (no source, or a kind aps can't add yet)",
+                unaddable.len()
+            ))
+            .dim()
+        );
+    }
+    let existing_ids: Vec<String> = match discover_manifest(manifest.as_deref()) {
+        Ok((manifest, _)) => manifest.entries.into_iter().map(|e| e.id).collect(),
+        Err(ApsError::ManifestNotFound) => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    let (present, candidates): (Vec<_>, Vec<_>) = addable
+        .into_iter()
+        .partition(|(_, entry)| entry.as_ref().is_some_and(|e| existing_ids.contains(&e.id)));
+    if !present.is_empty() {
+        println!(
+            "{}",
+            style(format!(
+                "{} suggestion(s) already in the manifest",
+                present.len()
+            ))
+            .dim()
+        );
+    }
+    if candidates.is_empty() {
+        println!("No catalog entries to pick from");
+        return Ok(());
+    }
+
+    let max_id_len = candidates
+        .iter()
+        .map(|(s, _)| s.entry.id.len())
+        .max()
+        .unwrap_or(0);
+    let labels: Vec<String> = candidates
+        .iter()
+        .map(|(s, _)| match s.entry.short_description.as_deref() {
+            Some(description) => format!("{:<max_id_len$}  {}", s.entry.id, description),
+            None => s.entry.id.clone(),
+        })
         .collect();
-    items.sort_by_key(|e| e.file_name());
 
-    // Check if this is a single skill directory (contains SKILL.md directly)
-    let has_skill_md = items.iter().any(|e| {
-        e.file_name()
-            .to_string_lossy()
-            .eq_ignore_ascii_case("skill.md")
-    });
+    // Each pick toggles one entry; the list filters as you type
+    let mut picked = vec![false; candidates.len()];
+    loop {
+        let items: Vec<String> = labels
+            .iter()
+            .zip(&picked)
+            .map(|(label, &on)| format!("{} {}", if on { "[x]" } else { "[ ]" }, label))
+            .collect();
+        let choice =
+            dialoguer::FuzzySelect::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Type to filter, enter to toggle, esc when done")
+                .items(&items)
+                .default(0)
+                .interact_on_opt(&console::Term::stderr())
+                .map_err(|e| {
+                    ApsError::io(
+                        std::io::Error::other(e.to_string()),
+                        "Failed to display selection prompt",
+                    )
+                })?;
+        match choice {
+            Some(i) => picked[i] = !picked[i],
+            None => break,
+        }
+    }
+
+    let entries: Vec<(String, Entry)> = candidates
+        .into_iter()
+        .zip(picked)
+        .filter(|(_, on)| *on)
+        .filter_map(|((suggestion, entry), _)| Some((suggestion.entry.id, entry?)))
+        .collect();
+    if entries.is_empty() {
+        println!("No entries added");
+        return Ok(());
+    }
 
-    if has_skill_md {
-        // This is a single skill folder - show its structure
-        print_single_skill_contents(&items, indent);
-    } else {
-        // This is a directory of skills - enumerate each
-        let total = items.len();
-        for (i, item) in items.iter().enumerate() {
-            let is_last = i == total - 1;
-            let connector = if is_last { "└── " } else { "├── " };
-            let name = item.file_name();
-            let name = name.to_string_lossy();
+    write_catalog_entries(entries, manifest)
+}
 
-            if item.path().is_dir() {
-                println!(
-                    "{}{}{}{}",
-                    indent,
-                    dim.apply_to(connector),
-                    cyan.apply_to(&*name),
-                    dim.apply_to("/"),
-                );
+/// Split `id@requirement` into a catalog entry id and version requirement
+fn split_version_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.rsplit_once('@') {
+        Some((id, requirement)) if !id.is_empty() && !requirement.is_empty() => {
+            (id, Some(requirement))
+        }
+        _ => (spec, None),
+    }
+}
 
-                // Check if subdirectory is a skill (has SKILL.md)
-                let sub_indent = if is_last {
-                    format!("{}    ", indent)
-                } else {
-                    format!("{}│   ", indent)
-                };
+fn find_catalog_entry<'a>(catalog: &'a Catalog, id: &str) -> Result<&'a CatalogEntry> {
+    catalog
+        .entries
+        .iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| ApsError::CatalogEntryNotFound { id: id.to_string() })
+}
 
-                let sub_entries = match std::fs::read_dir(item.path()) {
-                    Ok(entries) => {
-                        let mut items: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-                        items.sort_by_key(|e| e.file_name());
-                        items
-                    }
-                    Err(_) => continue,
-                };
+/// The manifest entry for a catalog entry at `version`, or an error naming
+/// why it can't be added
+fn catalog_manifest_entry(entry: &CatalogEntry, version: Option<&CatalogVersion>) -> Result<Entry> {
+    entry
+        .to_manifest_entry(version)
+        .ok_or_else(|| ApsError::InvalidInput {
+            message: format!(
+                "Catalog entry '{}' can't be added: its catalog lists no source, \
+                 or aps can't add entries of its kind yet",
+                entry.id
+            ),
+        })
+}
 
-                print_single_skill_contents(&sub_entries, &sub_indent);
-            } else {
-                println!(
-                    "{}{}{}",
-                    indent,
-                    dim.apply_to(connector),
-                    green.apply_to(&*name),
-                );
+/// Append entries converted from the catalog, paired with their catalog
+/// entry ids, to the manifest and report them
+fn write_catalog_entries(entries: Vec<(String, Entry)>, manifest: Option<PathBuf>) -> Result<()> {
+    let (catalog_ids, entries): (Vec<String>, Vec<Entry>) = entries.into_iter().unzip();
+    let entry_ids: Vec<String> = entries.iter().map(|e| e.id.clone()).collect();
+    let (manifest_path, added_ids) = write_entries_to_manifest(entries, manifest)?;
+    track(|stats| {
+        for (catalog_id, entry_id) in catalog_ids.iter().zip(&entry_ids) {
+            if added_ids.contains(entry_id) {
+                stats.record_added(catalog_id, &manifest_path, entry_id);
             }
         }
+    });
+    if !added_ids.is_empty() {
+        info!("Added {} entries to {:?}", added_ids.len(), manifest_path);
+        println!(
+            "  {} {}\n",
+            style("✓").green(),
+            style(format!(
+                "Added {} entries: {}",
+                added_ids.len(),
+                added_ids.join(", ")
+            ))
+            .green()
+        );
+        println!("Run `aps sync` to install them.");
     }
+    Ok(())
 }
 
-/// Print the contents of a single skill directory, highlighting well-known structure
-fn print_single_skill_contents(items: &[std::fs::DirEntry], indent: &str) {
-    let dim = Style::new().dim();
-    let green = Style::new().green();
-    let cyan = Style::new().cyan();
-    let yellow = Style::new().yellow();
+/// Execute the `aps catalog update` command
+pub fn cmd_catalog_update(args: CatalogUpdateArgs) -> Result<()> {
+    let urls: Vec<String> = if args.catalog.is_empty() {
+        let (manifest, _) = discover_manifest(args.manifest.as_deref())?;
+        manifest
+            .catalogs
+            .into_iter()
+            .filter(|location| is_remote(location))
+            .collect()
+    } else {
+        args.catalog
+    };
+    if urls.is_empty() {
+        println!("No remote catalogs to update. List their URLs in the manifest's `catalogs`.");
+        return Ok(());
+    }
 
-    // Categorize items into well-known skill directories and other files
-    let well_known_dirs = ["scripts", "references", "assets"];
+    for url in &urls {
+        if !is_remote(url) {
+            return Err(ApsError::RegistryError {
+                url: url.clone(),
+                message: "not a git or HTTP(S) URL; local catalogs are read as they are"
+                    .to_string(),
+            });
+        }
+        let index = update_index(url)?;
+        println!(
+            "{} {} {}",
+            style("Updated").green(),
+            url,
+            style(format!("({} entries)", index.catalog.entries.len())).dim()
+        );
+    }
+    Ok(())
+}
 
-    let total = items.len();
-    for (i, item) in items.iter().enumerate() {
-        let is_last = i == total - 1;
-        let connector = if is_last { "└── " } else { "├── " };
-        let name = item.file_name();
-        let name_str = name.to_string_lossy();
+/// Execute the `aps catalog search` command
+pub fn cmd_catalog_search(args: CatalogSearchArgs) -> Result<()> {
+    let (catalog, _) = load_catalog(args.catalog, args.manifest.as_deref(), &args.registry)?;
 
-        if item.path().is_dir() {
-            let dir_style = if well_known_dirs.contains(&name_str.as_ref()) {
-                &yellow
-            } else {
-                &cyan
-            };
+    let hits: Vec<SearchHit> = CatalogSearch::new(&catalog)
+        .with_adoption(load_adoption())
+        .search(&args.query);
+    let page = Page::paginate(hits, args.offset, args.limit);
 
-            // Count children
-            let child_count = std::fs::read_dir(item.path())
-                .map(|rd| rd.filter_map(|e| e.ok()).count())
-                .unwrap_or(0);
+    if args.format == OutputFormat::Json {
+        return print_json_page(&page);
+    }
 
-            println!(
-                "{}{}{}{}  {}",
-                indent,
-                dim.apply_to(connector),
-                dir_style.apply_to(&*name_str),
-                dim.apply_to("/"),
-                dim.apply_to(format!("({} items)", child_count)),
-            );
-        } else {
-            // Highlight SKILL.md specially
-            let file_style = if name_str.eq_ignore_ascii_case("skill.md") {
-                &green
-            } else {
-                &dim
-            };
-            println!(
-                "{}{}{}",
-                indent,
-                dim.apply_to(connector),
-                file_style.apply_to(&*name_str),
-            );
-        }
+    if page.total == 0 {
+        println!("No catalog entries match {:?}", args.query);
+        return Ok(());
+    }
+
+    println!(
+        "{} {} {}",
+        style("Results for").dim(),
+        style(format!("{:?}", args.query)).cyan(),
+        style(format!("({} matches)", page.total)).dim()
+    );
+    println!();
+
+    for hit in &page.entries {
+        print_catalog_entry(&hit.entry, Some(hit.score));
+    }
+    print_page_footer(&page);
+
+    Ok(())
+}
+
+/// Execute the `aps catalog stats` command
+pub fn cmd_catalog_stats(args: CatalogStatsArgs) -> Result<()> {
+    let stats = UsageStats::load(&UsageStats::path()?)?;
+    let mut entries: Vec<_> = stats.entries.into_iter().collect();
+    entries.sort_by(|(a_id, a), (b_id, b)| {
+        b.kept()
+            .cmp(&a.kept())
+            .then(b.added.cmp(&a.added))
+            .then_with(|| a_id.cmp(b_id))
+    });
+    if let Some(limit) = args.limit {
+        entries.truncate(limit);
+    }
+
+    if args.format == OutputFormat::Json {
+        let rows: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|(id, usage)| {
+                serde_json::json!({ "id": id, "kept": usage.kept(), "added": usage.added })
+            })
+            .collect();
+        let json = serde_json::to_string_pretty(&rows).map_err(|e| ApsError::InvalidInput {
+            message: format!("Failed to serialize usage stats: {}", e),
+        })?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if !tracking_enabled() {
+        println!(
+            "{}",
+            style(format!(
+                "Usage tracking is off. Set {}=1 to record the catalog entries you add.",
+                TRACK_USAGE_ENV
+            ))
+            .dim()
+        );
+    }
+    if entries.is_empty() {
+        println!("No catalog entries added yet");
+        return Ok(());
+    }
+
+    let width = entries.iter().map(|(id, _)| id.len()).max().unwrap_or(0);
+    println!(
+        "{}",
+        style(format!(
+            "{:<width$}  {:>5}  {:>5}",
+            "ENTRY", "KEPT", "ADDED"
+        ))
+        .dim()
+    );
+    for (id, usage) in &entries {
+        println!("{:<width$}  {:>5}  {:>5}", id, usage.kept(), usage.added);
     }
+    Ok(())
 }
 
-/// Print a simple flat tree for non-skill asset types
-fn print_flat_tree(path: &Path, indent: &str) {
-    let dim = Style::new().dim();
-    let green = Style::new().green();
+/// Execute the `aps log` command
+pub fn cmd_log(args: LogArgs) -> Result<()> {
+    let (_, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+    let log_path = audit_log_path(&manifest_path);
 
-    let entries = match std::fs::read_dir(path) {
-        Ok(entries) => entries,
-        Err(_) => return,
-    };
+    let mut records = read_records(&log_path)?;
+    if let Some(ref id) = args.entry {
+        records.retain(|r| r.entries.iter().any(|c| &c.id == id));
+    }
+    if let Some(limit) = args.limit {
+        let skip = records.len().saturating_sub(limit);
+        records.drain(..skip);
+    }
 
-    let mut items: Vec<_> = entries
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_name() != ".git")
-        .collect();
-    items.sort_by_key(|e| e.file_name());
+    if args.format == OutputFormat::Json {
+        let json = serde_json::to_string_pretty(&records).map_err(|e| ApsError::AuditLogError {
+            message: format!("Failed to serialize records: {}", e),
+        })?;
+        println!("{}", json);
+        return Ok(());
+    }
 
-    let total = items.len();
-    for (i, item) in items.iter().enumerate() {
-        let is_last = i == total - 1;
-        let connector = if is_last { "└── " } else { "├── " };
-        let name = item.file_name();
-        let name_str = name.to_string_lossy();
+    if records.is_empty() {
+        println!("No audit records found at {:?}", log_path);
+        return Ok(());
+    }
 
-        if item.path().is_dir() {
-            let child_count = std::fs::read_dir(item.path())
-                .map(|rd| rd.filter_map(|e| e.ok()).count())
-                .unwrap_or(0);
-            println!(
-                "{}{}{}{}  {}",
-                indent,
-                dim.apply_to(connector),
-                green.apply_to(&*name_str),
-                dim.apply_to("/"),
-                dim.apply_to(format!("({} items)", child_count)),
-            );
-        } else {
+    let dim = Style::new().dim();
+    let short = |v: &Option<String>| match v {
+        Some(v) => {
+            let v = v.strip_prefix("sha256:").unwrap_or(v);
+            v[..8.min(v.len())].to_string()
+        }
+        None => "-".to_string(),
+    };
+
+    // Newest first, like `git log`
+    for record in records.iter().rev() {
+        println!(
+            "{} {} {}",
+            style(&record.timestamp).yellow(),
+            style(&record.command).bold(),
+            dim.apply_to(format!("by {}", record.user)),
+        );
+        if record.entries.is_empty() {
+            println!("  {}", dim.apply_to("no changes"));
+        }
+        for change in &record.entries {
+            if args.entry.as_ref().is_some_and(|id| id != &change.id) {
+                continue;
+            }
             println!(
-                "{}{}{}",
-                indent,
-                dim.apply_to(connector),
-                green.apply_to(&*name_str),
+                "  {} {} → {}",
+                change.id,
+                dim.apply_to(short(&change.before)),
+                short(&change.after),
             );
         }
     }
+
+    Ok(())
 }
 
-/// Execute the `aps catalog generate` command
-pub fn cmd_catalog_generate(args: CatalogGenerateArgs) -> Result<()> {
-    // Discover and load manifest
-    let (manifest, manifest_path) = discover_manifest(args.manifest.as_deref())?;
+/// Execute the `aps backup list` command
+pub fn cmd_backup_list(args: BackupListArgs) -> Result<()> {
+    let (_, manifest_path) = discover_manifest(args.manifest.as_deref())?;
     let base_dir = manifest_dir(&manifest_path);
 
-    println!("Using manifest: {:?}", manifest_path);
-
-    // Validate manifest
-    validate_manifest(&manifest)?;
+    let mut backups = list_backups(&base_dir)?;
+    if let Some(ref path) = args.path {
+        backups.retain(|backup| backup.is_of(path));
+    }
 
-    // Generate catalog
-    let catalog = Catalog::generate_from_manifest(&manifest, &base_dir)?;
+    if args.format == OutputFormat::Json {
+        let items: Vec<serde_json::Value> = backups
+            .iter()
+            .map(|backup| {
+                serde_json::json!({
+                    "name": backup.name,
+                    "path": backup.path,
+                    "created": backup.created,
+                    "size": backup.size,
+                    "dest": backup.record.as_ref().map(|r| &r.dest),
+                    "entry": backup.record.as_ref().map(|r| &r.entry),
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&items).unwrap_or_else(|_| "[]".to_string())
+        );
+        return Ok(());
+    }
 
-    // Determine output path
-    let output_path = args
-        .output
-        .unwrap_or_else(|| Catalog::path_for_manifest(&manifest_path));
+    if backups.is_empty() {
+        println!("No backups found in {:?}", base_dir.join(BACKUP_DIR));
+        return Ok(());
+    }
 
-    // Save catalog
-    catalog.save(&output_path)?;
+    let dim = Style::new().dim();
+    let entry_width = backups.iter().map(backup_entry_label).map(str::len).max();
+    for backup in &backups {
+        // Git backups are shown by ref, ready for `git show`
+        let location = if backup.is_git() {
+            backup.path.to_string_lossy().into_owned()
+        } else {
+            backup.name.clone()
+        };
+        println!(
+            "{}  {:>9}  {:<width$}  {}",
+            style(&backup.created).yellow(),
+            format_size(backup.size),
+            backup_entry_label(backup),
+            dim.apply_to(location),
+            width = entry_width.unwrap_or(0),
+        );
+    }
 
-    println!(
-        "Generated catalog with {} entries at {:?}",
-        catalog.entries.len(),
-        output_path
-    );
+    Ok(())
+}
 
-    // Count entries with descriptions
-    let with_desc = catalog
-        .entries
-        .iter()
-        .filter(|e| e.short_description.is_some())
-        .count();
+/// The entry a backup was made for, or `?` for backups made before aps
+/// recorded it
+fn backup_entry_label(backup: &Backup) -> &str {
+    backup.record.as_ref().map_or("?", |r| r.entry.as_str())
+}
 
-    if with_desc > 0 {
-        println!("  {} entries have descriptions", with_desc);
+/// Format a size in bytes for display
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
     }
-
-    Ok(())
 }
@@ -17,6 +17,21 @@ pub struct ComposedSource {
     /// Optional label/name for this source (derived from filename)
     #[allow(dead_code)]
     pub label: String,
+    /// Heading inserted above the content when composing
+    pub title: Option<String>,
+}
+
+#[cfg(test)]
+impl ComposedSource {
+    /// A fragment with `content`, as if read from `path`
+    pub fn fragment(path: &str, content: &str) -> Self {
+        Self {
+            path: std::path::PathBuf::from(path),
+            content: content.to_string(),
+            label: String::new(),
+            title: None,
+        }
+    }
 }
 
 /// Options for composing markdown files
@@ -26,6 +41,8 @@ pub struct ComposeOptions {
     pub add_separators: bool,
     /// Include source file information as comments
     pub include_source_info: bool,
+    /// Start with a table of contents linking each fragment's heading
+    pub toc: bool,
 }
 
 impl Default for ComposedSource {
@@ -34,6 +51,7 @@ impl Default for ComposedSource {
             path: std::path::PathBuf::new(),
             content: String::new(),
             label: String::new(),
+            title: None,
         }
     }
 }
@@ -54,6 +72,7 @@ pub fn read_source_file(path: &Path) -> Result<ComposedSource> {
         path: path.to_path_buf(),
         content,
         label,
+        title: None,
     })
 }
 
@@ -74,6 +93,10 @@ pub fn compose_markdown(sources: &[ComposedSource], options: &ComposeOptions) ->
         "<!-- This file was auto-generated by aps (https://github.com/westonplatter/aps) -->\n\n",
     );
 
+    if options.toc {
+        result.push_str(&render_toc(sources));
+    }
+
     for (i, source) in sources.iter().enumerate() {
         if i > 0 {
             // Add separator between sections
@@ -87,6 +110,10 @@ pub fn compose_markdown(sources: &[ComposedSource], options: &ComposeOptions) ->
             result.push_str(&format!("<!-- Source: {} -->\n", source.path.display()));
         }
 
+        if let Some(ref title) = source.title {
+            result.push_str(&format!("## {}\n\n", title));
+        }
+
         // Add the content, trimming trailing whitespace but preserving structure
         let content = source.content.trim_end();
         result.push_str(content);
@@ -98,6 +125,78 @@ pub fn compose_markdown(sources: &[ComposedSource], options: &ComposeOptions) ->
     Ok(result)
 }
 
+/// Demote every ATX heading (`#`..`######`) by `levels`, capped at `######`.
+/// Lines inside fenced code blocks are left alone.
+pub fn shift_headings(content: &str, levels: u8) -> String {
+    let mut in_fence = false;
+    let mut result: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+
+        let depth = line.chars().take_while(|c| *c == '#').count();
+        let rest = &line[depth..];
+        let is_heading =
+            !in_fence && (1..=6).contains(&depth) && (rest.is_empty() || rest.starts_with(' '));
+
+        if is_heading {
+            let new_depth = (depth + levels as usize).min(6);
+            result.push(format!("{}{}", "#".repeat(new_depth), rest));
+        } else {
+            result.push(line.to_string());
+        }
+    }
+
+    let mut shifted = result.join("\n");
+    if content.ends_with('\n') {
+        shifted.push('\n');
+    }
+    shifted
+}
+
+/// Table of contents listing each fragment's title, or its first heading
+/// when it has no title
+fn render_toc(sources: &[ComposedSource]) -> String {
+    let mut toc = String::from("## Contents\n\n");
+    for source in sources {
+        let heading = source
+            .title
+            .clone()
+            .or_else(|| first_heading(&source.content));
+        if let Some(heading) = heading {
+            toc.push_str(&format!("- [{}](#{})\n", heading, heading_anchor(&heading)));
+        }
+    }
+    toc.push('\n');
+    toc
+}
+
+/// Text of the first markdown heading in `content`
+fn first_heading(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let text = line.trim_start_matches('#');
+        (text.len() < line.len() && text.starts_with(' ')).then(|| text.trim().to_string())
+    })
+}
+
+/// GitHub-style anchor for a heading: lowercase, punctuation dropped and
+/// spaces replaced with hyphens
+fn heading_anchor(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
 /// Write the composed markdown to a destination file
 pub fn write_composed_file(content: &str, dest: &Path) -> Result<()> {
     // Ensure parent directory exists
@@ -128,6 +227,7 @@ mod tests {
             path: std::path::PathBuf::from("test.md"),
             content: "# Test\n\nContent here".to_string(),
             label: "test".to_string(),
+            title: None,
         }];
 
         let result = compose_markdown(&sources, &ComposeOptions::default()).unwrap();
@@ -143,11 +243,13 @@ mod tests {
                 path: std::path::PathBuf::from("python.md"),
                 content: "# Python\n\nPython content".to_string(),
                 label: "python".to_string(),
+                title: None,
             },
             ComposedSource {
                 path: std::path::PathBuf::from("docker.md"),
                 content: "# Docker\n\nDocker content".to_string(),
                 label: "docker".to_string(),
+                title: None,
             },
         ];
 
@@ -165,17 +267,20 @@ mod tests {
                 path: std::path::PathBuf::from("a.md"),
                 content: "Section A".to_string(),
                 label: "a".to_string(),
+                title: None,
             },
             ComposedSource {
                 path: std::path::PathBuf::from("b.md"),
                 content: "Section B".to_string(),
                 label: "b".to_string(),
+                title: None,
             },
         ];
 
         let options = ComposeOptions {
             add_separators: true,
             include_source_info: false,
+            toc: false,
         };
 
         let result = compose_markdown(&sources, &options).unwrap();
@@ -188,17 +293,60 @@ mod tests {
             path: std::path::PathBuf::from("/path/to/test.md"),
             content: "Content".to_string(),
             label: "test".to_string(),
+            title: None,
         }];
 
         let options = ComposeOptions {
             add_separators: false,
             include_source_info: true,
+            toc: false,
         };
 
         let result = compose_markdown(&sources, &options).unwrap();
         assert!(result.contains("<!-- Source:"));
     }
 
+    #[test]
+    fn test_compose_with_titles_and_toc() {
+        let sources = vec![
+            ComposedSource {
+                path: std::path::PathBuf::from("python.md"),
+                content: "Use type hints.".to_string(),
+                label: "python".to_string(),
+                title: Some("Python & Typing".to_string()),
+            },
+            ComposedSource {
+                path: std::path::PathBuf::from("docker.md"),
+                content: "# Docker Builds\n\nPin base images.".to_string(),
+                label: "docker".to_string(),
+                title: None,
+            },
+        ];
+
+        let options = ComposeOptions {
+            toc: true,
+            ..Default::default()
+        };
+
+        let result = compose_markdown(&sources, &options).unwrap();
+        assert!(result.contains(
+            "## Contents\n\n- [Python & Typing](#python--typing)\n- [Docker Builds](#docker-builds)\n\n"
+        ));
+        assert!(result.contains("## Python & Typing\n\nUse type hints.\n"));
+        assert!(result.find("## Contents") < result.find("## Python"));
+    }
+
+    #[test]
+    fn test_shift_headings() {
+        let content =
+            "# Python\n\nIntro\n\n## Style\n\n```sh\n# not a heading\n```\n##### Deep\n#hashtag\n";
+        assert_eq!(
+            shift_headings(content, 2),
+            "### Python\n\nIntro\n\n#### Style\n\n```sh\n# not a heading\n```\n###### Deep\n#hashtag\n"
+        );
+        assert_eq!(shift_headings("# A", 0), "# A");
+    }
+
     #[test]
     fn test_compose_empty_sources_error() {
         let sources: Vec<ComposedSource> = vec![];
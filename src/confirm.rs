@@ -0,0 +1,82 @@
+//! Answering confirmation prompts.
+//!
+//! Every yes/no question aps asks goes through [`confirm`], so `--yes`,
+//! `--no` and `--fail-on-prompt` answer all of them the same way, and a run
+//! without a terminal fails with [`ApsError::PromptRequired`] instead of
+//! hanging or guessing.
+
+use crate::error::{ApsError, Result};
+use crate::progress;
+use dialoguer::Confirm;
+use std::io::IsTerminal;
+
+/// How confirmation prompts are answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PromptMode {
+    /// Ask on a terminal; fail without one
+    #[default]
+    Ask,
+    /// Answer yes to every prompt (`--yes`)
+    Yes,
+    /// Answer no to every prompt (`--no`)
+    No,
+    /// Fail instead of prompting, even on a terminal (`--fail-on-prompt`)
+    Fail,
+}
+
+impl PromptMode {
+    /// The mode selected by the `--yes`, `--no` and `--fail-on-prompt` flags
+    pub fn from_flags(yes: bool, no: bool, fail_on_prompt: bool) -> Self {
+        if yes {
+            PromptMode::Yes
+        } else if no {
+            PromptMode::No
+        } else if fail_on_prompt {
+            PromptMode::Fail
+        } else {
+            PromptMode::Ask
+        }
+    }
+
+    /// Whether prompts are shown to someone who can answer them
+    pub fn attended(&self) -> bool {
+        *self == PromptMode::Ask && std::io::stdin().is_terminal()
+    }
+}
+
+/// Ask a yes/no question, answered according to `mode`. `default` is the
+/// answer preselected on a terminal.
+pub fn confirm(mode: PromptMode, prompt: &str, default: bool) -> Result<bool> {
+    match mode {
+        PromptMode::Yes => Ok(true),
+        PromptMode::No => Ok(false),
+        PromptMode::Ask if mode.attended() => {
+            progress::suspend();
+            Confirm::new()
+                .with_prompt(prompt)
+                .default(default)
+                .interact()
+                .map_err(|_| ApsError::Cancelled)
+        }
+        PromptMode::Ask | PromptMode::Fail => Err(ApsError::PromptRequired {
+            prompt: prompt.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirm_without_a_terminal() {
+        assert!(confirm(PromptMode::Yes, "Overwrite?", false).unwrap());
+        assert!(!confirm(PromptMode::No, "Overwrite?", true).unwrap());
+        assert!(matches!(
+            confirm(PromptMode::Fail, "Overwrite?", true),
+            Err(ApsError::PromptRequired { prompt }) if prompt == "Overwrite?"
+        ));
+        assert_eq!(PromptMode::from_flags(false, true, false), PromptMode::No);
+        assert_eq!(PromptMode::from_flags(false, false, false), PromptMode::Ask);
+    }
+}
@@ -0,0 +1,143 @@
+//! Conversion of rule sets from other tools into a single instruction file.
+//!
+//! `agents_md` entries that set `convert` read their source in another format
+//! (for example a directory of Cursor `.mdc` rules), strip frontmatter and
+//! merge the files into markdown. Combined with `targets`, one upstream rule
+//! set can feed AGENTS.md, CLAUDE.md, copilot-instructions.md and GEMINI.md.
+
+use crate::compose::shift_headings;
+use crate::error::{ApsError, Result};
+use crate::frontmatter;
+use crate::manifest::{ConvertFormat, ConvertOptions};
+use crate::mdc::rule_files;
+use std::path::Path;
+use tracing::debug;
+
+/// Read `source` (a file or flat directory) and flatten it into markdown
+pub fn convert_to_markdown(
+    source: &Path,
+    include: &[String],
+    options: &ConvertOptions,
+) -> Result<String> {
+    let mut sections = Vec::new();
+
+    for file in rule_files(source, include)? {
+        let name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let is_rule = match options.from {
+            ConvertFormat::CursorRules => name.ends_with(".mdc") || name.ends_with(".md"),
+            ConvertFormat::Markdown => name.ends_with(".md"),
+        };
+        if !is_rule {
+            continue;
+        }
+
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| ApsError::io(e, format!("Failed to read rule {:?}", file)))?;
+        let section = match options.from {
+            ConvertFormat::CursorRules => convert_cursor_rule(&content),
+            ConvertFormat::Markdown => frontmatter::split(&content)
+                .map(|(_, body)| body.to_string())
+                .unwrap_or(content),
+        };
+        if !section.trim().is_empty() {
+            debug!("Converted {:?}", file);
+            sections.push(section.trim().to_string());
+        }
+    }
+
+    let mut markdown = sections.join("\n\n");
+    markdown.push('\n');
+    Ok(match options.shift_headings {
+        Some(levels) => shift_headings(&markdown, levels),
+        None => markdown,
+    })
+}
+
+/// Strip a Cursor rule's frontmatter, keeping its description as a heading
+/// (when the body has none) and its globs as a note
+fn convert_cursor_rule(content: &str) -> String {
+    let Some((_, body)) = frontmatter::split(content) else {
+        return content.to_string();
+    };
+    let meta = frontmatter::parse(content).unwrap_or_default();
+    let mut section = String::new();
+
+    let has_heading = body.trim_start().starts_with('#');
+    if let Some(description) = frontmatter::get_str(&meta, "description").filter(|_| !has_heading) {
+        section.push_str(&format!("## {}\n\n", description));
+    }
+
+    let always_apply = meta
+        .get("alwaysApply")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let globs = match meta.get("globs") {
+        Some(serde_yaml::Value::String(globs)) => globs
+            .split(',')
+            .map(|g| g.trim().to_string())
+            .filter(|g| !g.is_empty())
+            .collect(),
+        Some(serde_yaml::Value::Sequence(globs)) => globs
+            .iter()
+            .filter_map(|g| g.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    if !always_apply && !globs.is_empty() {
+        let globs: Vec<String> = globs.iter().map(|g| format!("`{}`", g)).collect();
+        section.push_str(&format!("_Applies to {}._\n\n", globs.join(", ")));
+    }
+
+    section.push_str(body);
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_convert_cursor_rules() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("python.mdc"),
+            "---\ndescription: Python style\nglobs: \"**/*.py\"\nalwaysApply: false\n---\n\nUse type hints.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("general.mdc"),
+            "---\ndescription: General\nalwaysApply: true\n---\n# General\n\nBe concise.\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let options = ConvertOptions {
+            from: ConvertFormat::CursorRules,
+            shift_headings: None,
+        };
+        assert_eq!(
+            convert_to_markdown(dir.path(), &[], &options).unwrap(),
+            "# General\n\nBe concise.\n\n## Python style\n\n_Applies to `**/*.py`._\n\nUse type hints.\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_markdown_shifts_headings() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("CLAUDE.md");
+        std::fs::write(&file, "# Project\n\nRun tests.\n").unwrap();
+
+        let options = ConvertOptions {
+            from: ConvertFormat::Markdown,
+            shift_headings: Some(1),
+        };
+        assert_eq!(
+            convert_to_markdown(&file, &[], &options).unwrap(),
+            "## Project\n\nRun tests.\n"
+        );
+    }
+}
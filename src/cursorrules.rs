@@ -0,0 +1,86 @@
+//! Legacy `.cursorrules` export for `cursor_rules` entries.
+//!
+//! Older Cursor versions read a single `.cursorrules` file at the project root
+//! instead of `.cursor/rules/`. Entries with `legacy_cursorrules: true` also
+//! write the concatenation of their installed rules there.
+
+use crate::checksum::compute_string_checksum;
+use crate::error::{ApsError, Result};
+use crate::frontmatter;
+use std::path::Path;
+use tracing::debug;
+
+/// Legacy rules filename, relative to the manifest directory
+pub const LEGACY_CURSORRULES_FILENAME: &str = ".cursorrules";
+
+/// Concatenate the rules installed in `rules_dir`, without their frontmatter
+pub fn render_legacy_cursorrules(rules_dir: &Path) -> Result<String> {
+    let mut files: Vec<_> = std::fs::read_dir(rules_dir)
+        .map_err(|e| ApsError::io(e, format!("Failed to read directory {:?}", rules_dir)))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "md" || ext == "mdc"))
+        .collect();
+    files.sort();
+
+    let mut sections = Vec::new();
+    for file in &files {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| ApsError::io(e, format!("Failed to read rule {:?}", file)))?;
+        let body = frontmatter::split(&content).map_or(content.as_str(), |(_, body)| body);
+        sections.push(body.trim().to_string());
+    }
+
+    let mut out = String::from("# Generated by aps from Cursor rules. Do not edit.\n\n");
+    out.push_str(&sections.join("\n\n"));
+    out.push('\n');
+    Ok(out)
+}
+
+/// Write the legacy file and return its checksum for the lockfile
+pub fn write_legacy_cursorrules(rules_dir: &Path, path: &Path) -> Result<String> {
+    let content = render_legacy_cursorrules(rules_dir)?;
+    std::fs::write(path, &content)
+        .map_err(|e| ApsError::io(e, format!("Failed to write {:?}", path)))?;
+    debug!("Wrote legacy cursor rules to {:?}", path);
+    Ok(compute_string_checksum(&content))
+}
+
+/// Whether the legacy file on disk still matches the locked checksum
+pub fn is_legacy_cursorrules_current(path: &Path, locked_checksum: Option<&str>) -> bool {
+    match (std::fs::read_to_string(path), locked_checksum) {
+        (Ok(content), Some(checksum)) => compute_string_checksum(&content) == checksum,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_legacy_cursorrules() {
+        let temp = TempDir::new().unwrap();
+        let rules = temp.path().join("rules");
+        std::fs::create_dir_all(&rules).unwrap();
+        std::fs::write(
+            rules.join("b-testing.mdc"),
+            "---\ndescription: Testing\nalwaysApply: true\n---\n\nWrite tests.\n",
+        )
+        .unwrap();
+        std::fs::write(rules.join("a-style.md"), "Use type hints.\n").unwrap();
+        std::fs::write(rules.join("notes.txt"), "ignored").unwrap();
+
+        let path = temp.path().join(LEGACY_CURSORRULES_FILENAME);
+        let checksum = write_legacy_cursorrules(&rules, &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.ends_with("Use type hints.\n\nWrite tests.\n"));
+        assert!(!content.contains("alwaysApply"));
+        assert!(is_legacy_cursorrules_current(&path, Some(&checksum)));
+
+        std::fs::write(&path, "edited").unwrap();
+        assert!(!is_legacy_cursorrules_current(&path, Some(&checksum)));
+    }
+}
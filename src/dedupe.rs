@@ -0,0 +1,257 @@
+//! Duplicate-content detection across composite fragments.
+//!
+//! Fragments are split into sections at markdown headings. A section whose
+//! text closely matches a section from an earlier fragment is reported (and
+//! removed with `aps sync --dedupe`), as is a heading that several fragments
+//! use for different content.
+
+use crate::compose::ComposedSource;
+use std::collections::HashSet;
+
+/// Similarity at or above which two sections count as duplicates
+const DUPLICATE_THRESHOLD: f64 = 0.8;
+
+/// Sections shorter than this many words are too small to compare reliably
+const MIN_SECTION_WORDS: usize = 5;
+
+/// A markdown section: an optional heading and everything up to the next one
+#[derive(Debug, Clone)]
+struct Section {
+    heading: Option<String>,
+    /// Original text, including the heading line
+    text: String,
+    /// Word bigrams of the body, for similarity
+    shingles: HashSet<(String, String)>,
+    words: usize,
+}
+
+impl Section {
+    fn new(heading: Option<String>, text: String) -> Self {
+        let body = match heading {
+            Some(_) => text.split_once('\n').map(|(_, b)| b).unwrap_or(""),
+            None => text.as_str(),
+        };
+        let words = normalized_words(body);
+        let shingles = words
+            .windows(2)
+            .map(|w| (w[0].clone(), w[1].clone()))
+            .collect();
+        Self {
+            heading,
+            text,
+            shingles,
+            words: words.len(),
+        }
+    }
+
+    fn name(&self) -> String {
+        match self.heading {
+            Some(ref heading) => format!("'{}'", heading),
+            None => "untitled section".to_string(),
+        }
+    }
+}
+
+/// Lowercased words with punctuation stripped
+fn normalized_words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|w| {
+            w.chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// Split markdown into sections at ATX headings outside code fences
+fn split_sections(content: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut text = String::new();
+    let mut in_fence = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+
+        let depth = line.chars().take_while(|c| *c == '#').count();
+        let is_heading = !in_fence && (1..=6).contains(&depth) && line[depth..].starts_with(' ');
+
+        if is_heading {
+            if !text.trim().is_empty() {
+                sections.push(Section::new(heading.take(), std::mem::take(&mut text)));
+            }
+            heading = Some(line[depth..].trim().to_string());
+            text.clear();
+        }
+        text.push_str(line);
+        text.push('\n');
+    }
+    if !text.trim().is_empty() {
+        sections.push(Section::new(heading, text));
+    }
+    sections
+}
+
+/// Jaccard similarity of two sections' word bigrams
+fn similarity(a: &Section, b: &Section) -> f64 {
+    let union = a.shingles.union(&b.shingles).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.shingles.intersection(&b.shingles).count() as f64 / union as f64
+}
+
+/// Fragment name for messages
+fn fragment_name(source: &ComposedSource) -> String {
+    source
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| source.path.display().to_string())
+}
+
+/// Find duplicated sections across fragments. With `remove`, near-duplicate
+/// sections are dropped from later fragments. Returns one message per finding.
+pub fn check_duplicates(sources: &mut [ComposedSource], remove: bool) -> Vec<String> {
+    let mut warnings = Vec::new();
+    // Sections kept from earlier fragments, with the fragment they came from
+    let mut seen: Vec<(Section, String)> = Vec::new();
+
+    for source in sources.iter_mut() {
+        let name = fragment_name(source);
+        let sections = split_sections(&source.content);
+        let mut kept: Vec<Section> = Vec::new();
+        let mut removed_any = false;
+
+        for section in sections {
+            let duplicate = (section.words >= MIN_SECTION_WORDS)
+                .then(|| {
+                    seen.iter()
+                        .map(|(earlier, from)| (earlier, from, similarity(&section, earlier)))
+                        .find(|(earlier, _, score)| {
+                            earlier.words >= MIN_SECTION_WORDS && *score >= DUPLICATE_THRESHOLD
+                        })
+                })
+                .flatten();
+
+            if let Some((earlier, from, score)) = duplicate {
+                if remove {
+                    warnings.push(format!(
+                        "removed {} from {} (duplicates {} in {})",
+                        section.name(),
+                        name,
+                        earlier.name(),
+                        from
+                    ));
+                    removed_any = true;
+                    continue;
+                }
+                warnings.push(format!(
+                    "{} in {} duplicates {} in {} ({:.0}% similar)",
+                    section.name(),
+                    name,
+                    earlier.name(),
+                    from,
+                    score * 100.0
+                ));
+            } else if let Some(ref heading) = section.heading {
+                let same_heading = seen.iter().find(|(earlier, _)| {
+                    earlier
+                        .heading
+                        .as_ref()
+                        .is_some_and(|h| h.eq_ignore_ascii_case(heading))
+                });
+                if let Some((_, from)) = same_heading {
+                    warnings.push(format!(
+                        "heading {} appears in both {} and {}",
+                        section.name(),
+                        from,
+                        name
+                    ));
+                }
+            }
+            kept.push(section);
+        }
+
+        if removed_any {
+            source.content = kept.iter().map(|s| s.text.as_str()).collect();
+        }
+        seen.extend(kept.into_iter().map(|s| (s, name.clone())));
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAFETY: &str = "## Safety\n\nNever run destructive commands such as `rm -rf` or `docker system prune` without asking first.\n";
+
+    #[test]
+    fn test_detects_near_duplicate_sections() {
+        let mut sources = vec![
+            ComposedSource::fragment(
+                "docker.md",
+                &format!("# Docker\n\nUse multi-stage builds.\n\n{}", SAFETY),
+            ),
+            ComposedSource::fragment(
+                "ci.md",
+                &format!(
+                    "# CI\n\nKeep pipelines fast.\n\n{}",
+                    SAFETY.replace("first", "first!")
+                ),
+            ),
+        ];
+
+        let warnings = check_duplicates(&mut sources, false);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("'Safety' in ci.md duplicates 'Safety' in docker.md"));
+        assert!(sources[1].content.contains("## Safety"));
+    }
+
+    #[test]
+    fn test_removes_duplicates_from_later_fragments() {
+        let mut sources = vec![
+            ComposedSource::fragment("docker.md", &format!("# Docker\n\n{}", SAFETY)),
+            ComposedSource::fragment(
+                "ci.md",
+                &format!("# CI\n\nKeep pipelines fast.\n\n{}", SAFETY),
+            ),
+        ];
+
+        let warnings = check_duplicates(&mut sources, true);
+        assert_eq!(
+            warnings,
+            vec!["removed 'Safety' from ci.md (duplicates 'Safety' in docker.md)"]
+        );
+        assert!(sources[0].content.contains("## Safety"));
+        assert_eq!(sources[1].content, "# CI\n\nKeep pipelines fast.\n\n");
+    }
+
+    #[test]
+    fn test_warns_on_repeated_headings_with_different_content() {
+        let mut sources = vec![
+            ComposedSource::fragment(
+                "python.md",
+                "## Testing\n\nRun pytest with coverage enabled always.\n",
+            ),
+            ComposedSource::fragment(
+                "go.md",
+                "## testing\n\nUse go test ./... with the race detector.\n",
+            ),
+        ];
+
+        let warnings = check_duplicates(&mut sources, true);
+        assert_eq!(
+            warnings,
+            vec!["heading 'testing' appears in both python.md and go.md"]
+        );
+        assert!(sources[1].content.contains("go test"));
+    }
+}
@@ -0,0 +1,273 @@
+//! Project stack detection.
+//!
+//! Languages are detected from marker files (manifests and lockfiles) in the
+//! project directory. Composite fragments use this for `when` conditions.
+//! `aps suggest` also looks at source file extensions, frameworks named in
+//! package manifests, and Docker and CI configuration.
+
+use crate::manifest::FragmentCondition;
+use std::collections::HashMap;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Marker files that indicate each language
+const LANGUAGE_MARKERS: &[(&str, &[&str])] = &[
+    (
+        "python",
+        &[
+            "pyproject.toml",
+            "requirements.txt",
+            "setup.py",
+            "setup.cfg",
+            "Pipfile",
+            "poetry.lock",
+            "uv.lock",
+        ],
+    ),
+    ("rust", &["Cargo.toml"]),
+    (
+        "javascript",
+        &[
+            "package.json",
+            "package-lock.json",
+            "yarn.lock",
+            "pnpm-lock.yaml",
+        ],
+    ),
+    ("typescript", &["tsconfig.json"]),
+    ("go", &["go.mod"]),
+    ("ruby", &["Gemfile", "Gemfile.lock"]),
+    ("java", &["pom.xml", "build.gradle", "build.gradle.kts"]),
+    ("php", &["composer.json"]),
+    ("elixir", &["mix.exs"]),
+];
+
+/// Source file extensions that indicate each language
+const LANGUAGE_EXTENSIONS: &[(&str, &[&str])] = &[
+    ("python", &["py"]),
+    ("rust", &["rs"]),
+    ("javascript", &["js", "jsx", "mjs", "cjs"]),
+    ("typescript", &["ts", "tsx"]),
+    ("go", &["go"]),
+    ("ruby", &["rb"]),
+    ("java", &["java"]),
+    ("php", &["php"]),
+    ("elixir", &["ex", "exs"]),
+];
+
+/// Source files of a language needed before it counts, so one stray script
+/// doesn't make a Python project a Ruby one too
+const MIN_SOURCE_FILES: usize = 3;
+
+/// How deep source files are looked for
+const MAX_SCAN_DEPTH: usize = 4;
+
+/// Directories holding dependencies or build output rather than sources
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "vendor", "dist", "build"];
+
+/// Frameworks, the package manifests naming them, and how they appear there
+const FRAMEWORK_MARKERS: &[(&str, &[&str], &str)] = &[
+    ("react", &["package.json"], "\"react\""),
+    ("nextjs", &["package.json"], "\"next\""),
+    ("vue", &["package.json"], "\"vue\""),
+    ("express", &["package.json"], "\"express\""),
+    ("django", &["pyproject.toml", "requirements.txt"], "django"),
+    (
+        "fastapi",
+        &["pyproject.toml", "requirements.txt"],
+        "fastapi",
+    ),
+    ("flask", &["pyproject.toml", "requirements.txt"], "flask"),
+    ("tokio", &["Cargo.toml"], "tokio"),
+    ("axum", &["Cargo.toml"], "axum"),
+    ("rails", &["Gemfile"], "rails"),
+    (
+        "spring",
+        &["pom.xml", "build.gradle", "build.gradle.kts"],
+        "spring",
+    ),
+];
+
+/// Tooling and the files or directories that indicate it
+const TOOL_MARKERS: &[(&str, &[&str])] = &[
+    (
+        "docker",
+        &[
+            "Dockerfile",
+            "docker-compose.yml",
+            "docker-compose.yaml",
+            "compose.yaml",
+        ],
+    ),
+    ("github-actions", &[".github/workflows"]),
+    ("gitlab-ci", &[".gitlab-ci.yml"]),
+    ("circleci", &[".circleci"]),
+];
+
+/// Names of the languages that can be detected
+pub fn known_languages() -> Vec<&'static str> {
+    LANGUAGE_MARKERS.iter().map(|(name, _)| *name).collect()
+}
+
+/// Whether `name` is a language that can be detected
+pub fn is_known_language(name: &str) -> bool {
+    LANGUAGE_MARKERS
+        .iter()
+        .any(|(lang, _)| lang.eq_ignore_ascii_case(name))
+}
+
+/// Languages whose marker files are present in `project_dir`
+pub fn detect_languages(project_dir: &Path) -> Vec<&'static str> {
+    LANGUAGE_MARKERS
+        .iter()
+        .filter(|(_, markers)| markers.iter().any(|m| project_dir.join(m).exists()))
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+/// Everything known about the project's stack: languages (from marker
+/// files and source file extensions), frameworks, then tooling
+pub fn detect_project(project_dir: &Path) -> Vec<&'static str> {
+    let mut signals = detect_languages(project_dir);
+    for language in languages_from_sources(project_dir) {
+        if !signals.contains(&language) {
+            signals.push(language);
+        }
+    }
+
+    for (framework, manifests, dependency) in FRAMEWORK_MARKERS {
+        let named = manifests.iter().any(|manifest| {
+            std::fs::read_to_string(project_dir.join(manifest))
+                .is_ok_and(|content| content.to_lowercase().contains(dependency))
+        });
+        if named {
+            signals.push(framework);
+        }
+    }
+    for (tool, markers) in TOOL_MARKERS {
+        if markers.iter().any(|m| project_dir.join(m).exists()) {
+            signals.push(tool);
+        }
+    }
+    signals
+}
+
+/// Languages with at least `MIN_SOURCE_FILES` source files in the project
+fn languages_from_sources(project_dir: &Path) -> Vec<&'static str> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let files = WalkDir::new(project_dir)
+        .max_depth(MAX_SCAN_DEPTH)
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            e.depth() == 0 || !(name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()))
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file());
+    for file in files {
+        let Some(extension) = file.path().extension().and_then(|e| e.to_str()) else {
+            continue;
+        };
+        if let Some((language, _)) = LANGUAGE_EXTENSIONS
+            .iter()
+            .find(|(_, extensions)| extensions.contains(&extension))
+        {
+            *counts.entry(language).or_default() += 1;
+        }
+    }
+
+    LANGUAGE_EXTENSIONS
+        .iter()
+        .map(|(language, _)| *language)
+        .filter(|language| counts.get(language).is_some_and(|n| *n >= MIN_SOURCE_FILES))
+        .collect()
+}
+
+/// Whether a fragment's `when` condition holds for the project
+pub fn condition_matches(condition: &FragmentCondition, project_dir: &Path) -> bool {
+    let exists = condition
+        .exists
+        .as_ref()
+        .is_none_or(|path| project_dir.join(path).exists());
+    let lang = condition.lang.as_ref().is_none_or(|lang| {
+        detect_languages(project_dir)
+            .iter()
+            .any(|detected| detected.eq_ignore_ascii_case(lang))
+    });
+    exists && lang
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_detect_languages() {
+        let dir = tempdir().unwrap();
+        assert!(detect_languages(dir.path()).is_empty());
+
+        std::fs::write(dir.path().join("uv.lock"), "").unwrap();
+        std::fs::write(dir.path().join("package.json"), "{}").unwrap();
+        assert_eq!(detect_languages(dir.path()), vec!["python", "javascript"]);
+    }
+
+    #[test]
+    fn test_detect_project() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("requirements.txt"),
+            "FastAPI==0.110\nuvicorn\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("Dockerfile"), "FROM python").unwrap();
+        std::fs::create_dir_all(dir.path().join(".github/workflows")).unwrap();
+        std::fs::create_dir_all(dir.path().join("web/src")).unwrap();
+        for name in ["app.ts", "api.ts", "main.tsx"] {
+            std::fs::write(dir.path().join("web/src").join(name), "").unwrap();
+        }
+        // Too few to count, or not the project's own sources
+        std::fs::write(dir.path().join("deploy.rb"), "").unwrap();
+        std::fs::create_dir_all(dir.path().join("node_modules/lib")).unwrap();
+        for name in ["a.js", "b.js", "c.js"] {
+            std::fs::write(dir.path().join("node_modules/lib").join(name), "").unwrap();
+        }
+
+        assert_eq!(
+            detect_project(dir.path()),
+            vec![
+                "python",
+                "typescript",
+                "fastapi",
+                "docker",
+                "github-actions"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_condition_matches() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("Dockerfile"), "FROM scratch").unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+
+        let condition = |exists: Option<&str>, lang: Option<&str>| FragmentCondition {
+            exists: exists.map(str::to_string),
+            lang: lang.map(str::to_string),
+        };
+
+        assert!(condition_matches(&condition(None, None), dir.path()));
+        assert!(condition_matches(
+            &condition(Some("Dockerfile"), Some("Rust")),
+            dir.path()
+        ));
+        assert!(!condition_matches(
+            &condition(Some("Dockerfile"), Some("python")),
+            dir.path()
+        ));
+        assert!(!condition_matches(
+            &condition(Some("docker-compose.yml"), None),
+            dir.path()
+        ));
+    }
+}
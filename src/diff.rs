@@ -0,0 +1,178 @@
+//! Unified diffs of what a sync would change (`aps sync --diff`).
+//!
+//! Diffs compare an existing destination with a staged install of the
+//! incoming content, file by file for directories. Long diffs on a terminal
+//! go through `$PAGER` (default `less -R`).
+
+use crate::error::{ApsError, Result};
+use console::style;
+use similar::TextDiff;
+use std::collections::BTreeSet;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use walkdir::WalkDir;
+
+/// Lines of context around each change
+const CONTEXT_LINES: usize = 3;
+
+/// Relative paths of the files under `root` (or `root` itself if a file)
+fn files_under(root: &Path) -> BTreeSet<PathBuf> {
+    if !root.is_dir() {
+        return if root.exists() {
+            BTreeSet::from([PathBuf::new()])
+        } else {
+            BTreeSet::new()
+        };
+    }
+    WalkDir::new(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| !e.path().is_dir())
+        .filter_map(|e| e.path().strip_prefix(root).ok().map(Path::to_path_buf))
+        .filter(|rel| !rel.components().any(|c| c.as_os_str() == ".git"))
+        .collect()
+}
+
+/// Unified diff of two versions of a text, labelled with `name`
+pub fn diff_text(name: &str, old: &str, new: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(CONTEXT_LINES)
+        .header(&format!("a/{}", name), &format!("b/{}", name))
+        .to_string()
+}
+
+/// Unified diff between the current content at `current` and the incoming
+/// content at `incoming` (files or directories). `name` labels the paths.
+pub fn diff_paths(name: &Path, current: &Path, incoming: &Path) -> Result<String> {
+    let mut out = String::new();
+    let paths: BTreeSet<PathBuf> = files_under(current)
+        .into_iter()
+        .chain(files_under(incoming))
+        .collect();
+
+    for rel in paths {
+        let under = |root: &Path| {
+            if rel.as_os_str().is_empty() {
+                root.to_path_buf()
+            } else {
+                root.join(&rel)
+            }
+        };
+        let label = under(name).to_string_lossy().replace('\\', "/");
+        let label = label.trim_start_matches("./");
+        let read = |root: &Path| -> Result<Option<Vec<u8>>> {
+            let path = under(root);
+            match std::fs::read(&path) {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(ApsError::io(e, format!("Failed to read {:?}", path))),
+            }
+        };
+        let (old, new) = (read(current)?, read(incoming)?);
+        if old == new {
+            continue;
+        }
+        match (
+            String::from_utf8(old.unwrap_or_default()),
+            String::from_utf8(new.unwrap_or_default()),
+        ) {
+            (Ok(old), Ok(new)) => out.push_str(&diff_text(label, &old, &new)),
+            _ => out.push_str(&format!("Binary files a/{0} and b/{0} differ\n", label)),
+        }
+    }
+    Ok(out)
+}
+
+/// Colour a unified diff for the terminal
+fn colorize(diff: &str) -> String {
+    diff.lines()
+        .map(|line| {
+            let styled = if line.starts_with("+++") || line.starts_with("---") {
+                style(line).bold()
+            } else if line.starts_with('+') {
+                style(line).green()
+            } else if line.starts_with('-') {
+                style(line).red()
+            } else if line.starts_with("@@") {
+                style(line).cyan()
+            } else {
+                style(line)
+            };
+            format!("{}\n", styled)
+        })
+        .collect()
+}
+
+/// Print a diff, through a pager when it doesn't fit on the terminal
+pub fn show_diff(diff: &str) {
+    let text = colorize(diff);
+    let term = console::Term::stdout();
+    let fits = term
+        .size_checked()
+        .is_none_or(|(rows, _)| text.lines().count() < rows as usize);
+    if !std::io::stdout().is_terminal() || fits || !page(&text) {
+        print!("{}", text);
+    }
+}
+
+/// Send text to the user's pager. Returns false if no pager could be run.
+fn page(text: &str) -> bool {
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "less -R".to_string());
+    let mut parts = pager.split_whitespace();
+    let Some(program) = parts.next() else {
+        return false;
+    };
+    let Ok(mut child) = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()
+    else {
+        return false;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user quitting the pager early closes the pipe; that's fine
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_diff_text() {
+        assert_eq!(diff_text("AGENTS.md", "same\n", "same\n"), "");
+        let diff = diff_text("AGENTS.md", "a\nb\n", "a\nc\n");
+        assert!(diff.starts_with("--- a/AGENTS.md\n+++ b/AGENTS.md\n@@"));
+        assert!(diff.contains("-b\n+c\n"));
+    }
+
+    #[test]
+    fn test_diff_paths_compares_directories() {
+        let dir = tempdir().unwrap();
+        let current = dir.path().join("current");
+        let incoming = dir.path().join("incoming");
+        std::fs::create_dir_all(&current).unwrap();
+        std::fs::create_dir_all(&incoming).unwrap();
+        std::fs::write(current.join("same.md"), "same\n").unwrap();
+        std::fs::write(incoming.join("same.md"), "same\n").unwrap();
+        std::fs::write(current.join("old.md"), "old\n").unwrap();
+        std::fs::write(incoming.join("new.md"), "new\n").unwrap();
+
+        let diff = diff_paths(Path::new("rules"), &current, &incoming).unwrap();
+        assert!(!diff.contains("same.md"));
+        assert!(diff.contains("--- a/rules/old.md\n+++ b/rules/old.md\n"));
+        assert!(diff.contains("-old\n"));
+        assert!(diff.contains("+new\n"));
+    }
+}
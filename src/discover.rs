@@ -389,35 +389,48 @@ impl dialoguer::theme::Theme for SkillSelectTheme {
 /// Present a multi-select TUI for choosing which skills to add.
 /// Returns the indices of selected skills.
 pub fn prompt_skill_selection(skills: &[DiscoveredSkill], defaults: &[bool]) -> Result<Vec<usize>> {
+    let items: Vec<(&str, Option<&str>)> = skills
+        .iter()
+        .map(|s| (s.name.as_str(), s.description.as_deref()))
+        .collect();
+    prompt_selection(
+        "Toggle skills (space to toggle, enter to confirm)",
+        &items,
+        defaults,
+    )
+}
+
+/// Present a multi-select TUI of names with descriptions, with `defaults`
+/// (items already installed) pre-checked. Returns the indices selected.
+pub fn prompt_selection(
+    prompt: &str,
+    items: &[(&str, Option<&str>)],
+    defaults: &[bool],
+) -> Result<Vec<usize>> {
     use console::Term;
     use dialoguer::MultiSelect;
 
-    let max_name_len = skills.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    let max_name_len = items.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
 
-    let items: Vec<String> = skills
+    let items: Vec<String> = items
         .iter()
         .zip(defaults.iter())
-        .map(|(s, &installed)| {
-            SkillSelectTheme::encode_item(
-                &s.name,
-                s.description.as_deref(),
-                installed,
-                max_name_len,
-            )
+        .map(|((name, description), &installed)| {
+            SkillSelectTheme::encode_item(name, *description, installed, max_name_len)
         })
         .collect();
 
     let theme = SkillSelectTheme::new();
 
     let selections = MultiSelect::with_theme(&theme)
-        .with_prompt("Toggle skills (space to toggle, enter to confirm)")
+        .with_prompt(prompt)
         .items(&items)
         .defaults(defaults)
         .interact_on(&Term::stderr())
         .map_err(|e| {
             ApsError::io(
                 std::io::Error::other(e.to_string()),
-                "Failed to display skill selection prompt",
+                "Failed to display selection prompt",
             )
         })?;
 
@@ -31,7 +31,7 @@ pub enum ApsError {
     #[error("Invalid asset kind: {kind}")]
     #[diagnostic(
         code(aps::manifest::invalid_kind),
-        help("Valid kinds are: cursor_rules, cursor_hooks, cursor_skills_root, agents_md, composite_agents_md, agent_skill")
+        help("Valid kinds are: cursor_rules, cursor_hooks, cursor_skills_root, agents_md, composite_agents_md, agent_skill, mcp_config, claude_settings, prompt_library, file, dir")
     )]
     InvalidAssetKind { kind: String },
 
@@ -61,12 +61,19 @@ pub enum ApsError {
     #[diagnostic(code(aps::cancelled))]
     Cancelled,
 
-    #[error("Non-interactive mode requires --yes flag for overwrites")]
+    #[error("No answer for prompt: {prompt}")]
     #[diagnostic(
-        code(aps::install::requires_yes),
-        help("Run with --yes to allow overwrites in non-interactive mode")
+        code(aps::prompt::required),
+        help("Prompts need a terminal; pass --yes to answer yes or --no to answer no to every prompt")
     )]
-    RequiresYesFlag,
+    PromptRequired { prompt: String },
+
+    #[error("--interactive needs a terminal to prompt on")]
+    #[diagnostic(
+        code(aps::sync::interactive_requires_terminal),
+        help("Run without --interactive, using --yes to allow overwrites")
+    )]
+    InteractiveRequiresTerminal,
 
     #[error("IO error: {message}")]
     #[diagnostic(code(aps::io))]
@@ -87,6 +94,20 @@ pub enum ApsError {
     )]
     LockfileNotFound,
 
+    #[error("Lockfile is out of sync with the manifest: {message}")]
+    #[diagnostic(
+        code(aps::lockfile::out_of_sync),
+        help("Run `aps sync` without --frozen to update aps.lock.yaml, then commit it")
+    )]
+    LockfileOutOfSync { message: String },
+
+    #[error("Could not decide which side to keep for: {ids}")]
+    #[diagnostic(
+        code(aps::lockfile::unresolved_conflict),
+        help("Neither side is newer. Run interactively to choose, or pass --ours or --theirs")
+    )]
+    UnresolvedLockConflict { ids: String },
+
     #[error("Skill '{skill_name}' is missing SKILL.md")]
     #[diagnostic(
         code(aps::skill::missing_skill_md),
@@ -94,6 +115,27 @@ pub enum ApsError {
     )]
     MissingSkillMd { skill_name: String },
 
+    #[error("Skill '{skill_name}' has an invalid SKILL.md: {message}")]
+    #[diagnostic(
+        code(aps::skill::invalid_skill_md),
+        help("SKILL.md needs frontmatter with a `name` matching its directory and a `description`; see https://agentskills.io/specification")
+    )]
+    InvalidSkill { skill_name: String, message: String },
+
+    #[error("Invalid Cursor rule {path:?}: {message}")]
+    #[diagnostic(
+        code(aps::cursor_rules::invalid_frontmatter),
+        help("Cursor silently ignores rules with frontmatter it can't read; use `description`, `globs` (comma-separated) and `alwaysApply: true|false`")
+    )]
+    InvalidCursorRule { path: PathBuf, message: String },
+
+    #[error("{file} links to '{link}', which won't exist where it is installed")]
+    #[diagnostic(
+        code(aps::links::broken),
+        help("Sync the linked file as well, or link to it by URL")
+    )]
+    BrokenLink { file: String, link: String },
+
     #[error("Git operation failed: {message}")]
     #[diagnostic(code(aps::git::error))]
     GitError { message: String },
@@ -119,10 +161,268 @@ pub enum ApsError {
     )]
     CatalogNotFound,
 
+    #[error("No catalog entry with ID '{id}'")]
+    #[diagnostic(
+        code(aps::catalog::entry_not_found),
+        help("Run `aps catalog search` to find entry IDs")
+    )]
+    CatalogEntryNotFound { id: String },
+
+    #[error("Catalog entry '{id}' has no version matching '{requirement}'")]
+    #[diagnostic(
+        code(aps::catalog::version_not_found),
+        help("Available versions: {available}")
+    )]
+    CatalogVersionNotFound {
+        id: String,
+        requirement: String,
+        available: String,
+    },
+
+    #[error("Catalog lint found {count} problem(s)")]
+    #[diagnostic(
+        code(aps::catalog::lint_failed),
+        help(
+            "Fix the entries listed above, or regenerate the catalog with `aps catalog generate`"
+        )
+    )]
+    CatalogLintFailed { count: usize },
+
+    #[error("Failed to publish catalog to {to}: {message}")]
+    #[diagnostic(
+        code(aps::catalog::publish_failed),
+        help("Check that you can push to or upload to the target with your own credentials; the local catalog was left unchanged")
+    )]
+    CatalogPublishError { to: String, message: String },
+
     #[error("Failed to read catalog: {message}")]
     #[diagnostic(code(aps::catalog::read_error))]
     CatalogReadError { message: String },
 
+    #[error("This manifest requires aps {required}, but this is aps {current}")]
+    #[diagnostic(
+        code(aps::manifest::requires_newer_aps),
+        help("Please upgrade aps (see https://github.com/westonplatter/aps#installation) and re-run the command")
+    )]
+    ApsVersionTooOld { required: String, current: String },
+
+    #[error("Invalid requires_aps version requirement '{value}': {message}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_requires_aps),
+        help("Use a semver requirement such as \">=0.5\" or \"^1.2\"")
+    )]
+    InvalidVersionRequirement { value: String, message: String },
+
+    #[error("Invalid max_age: {value}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_max_age),
+        help("Use a number followed by a unit: h (hours), d (days) or w (weeks), e.g. \"90d\"")
+    )]
+    InvalidMaxAge { value: String },
+
+    #[error("Invalid integrity for entry '{id}': {value}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_integrity),
+        help(
+            "Use a SHA-256 checksum as recorded in the lockfile, e.g. \"sha256:<64 hex digits>\""
+        )
+    )]
+    InvalidIntegrity { id: String, value: String },
+
+    #[error("post_install command `{command}` for '{id}' exited with {status}")]
+    #[diagnostic(
+        code(aps::post_install::failed),
+        help("The entry was installed and locked; fix the command and run it by hand or re-sync with --force")
+    )]
+    PostInstallFailed {
+        id: String,
+        command: String,
+        status: String,
+    },
+
+    #[error("Content of '{id}' does not match its pinned integrity")]
+    #[diagnostic(
+        code(aps::integrity::mismatch),
+        help("Expected {expected}, got {actual}. Review the upstream change, then update `integrity` in the manifest")
+    )]
+    IntegrityMismatch {
+        id: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("{count} entry(ies) exceeded max_age: {ids}")]
+    #[diagnostic(
+        code(aps::freshness::stale),
+        help("Review upstream changes and run `aps sync --upgrade` to refresh locked content")
+    )]
+    StaleEntries { count: usize, ids: String },
+
+    #[error("{count} installed file(s) differ from the lockfile")]
+    #[diagnostic(
+        code(aps::verify::drift),
+        help("Move intended changes into the entry's source; local edits are lost when the entry is next re-installed")
+    )]
+    VerifyFailed { count: usize },
+
+    #[error("{path} is not managed by aps")]
+    #[diagnostic(
+        code(aps::why::not_managed),
+        help("`aps status` lists every entry and its destination")
+    )]
+    NotManaged { path: String },
+
+    #[error("Status check failed: {message}")]
+    #[diagnostic(
+        code(aps::status::check_failed),
+        help("`aps status --check` exits with 2 for local drift, 3 when upgrades are available and 4 when the lockfile doesn't match the manifest")
+    )]
+    StatusCheckFailed { exit_code: i32, message: String },
+
+    #[error("Failed to read audit log: {message}")]
+    #[diagnostic(code(aps::audit::read_error))]
+    AuditLogError { message: String },
+
+    #[error("Registry error for {url}: {message}")]
+    #[diagnostic(
+        code(aps::registry::error),
+        help("Check the registry URL and your network connection, or use --offline with a cached index")
+    )]
+    RegistryError { url: String, message: String },
+
+    #[error("Entry '{id}' requires a 'dest' field")]
+    #[diagnostic(
+        code(aps::manifest::requires_dest),
+        help("The 'file' and 'dir' kinds have no default destination; set 'dest' explicitly")
+    )]
+    EntryRequiresDest { id: String },
+
+    #[error("Destination '{dest}' of entry '{id}' is outside the project")]
+    #[diagnostic(
+        code(aps::manifest::unsafe_dest),
+        help("Use a path inside the manifest directory or your home directory, or set 'allow_outside_project: true' on the entry")
+    )]
+    UnsafeDest { id: String, dest: String },
+
+    #[error("Destination '{dest}' of entry '{id}' is inside .git/")]
+    #[diagnostic(
+        code(aps::manifest::git_dir_dest),
+        help("Git manages everything under .git/ itself; install into the working tree instead")
+    )]
+    GitDirDest { id: String, dest: String },
+
+    #[error("Files don't match their schema:\n{problems}")]
+    #[diagnostic(
+        code(aps::manifest::schema_violation),
+        help("Print a schema with `aps schema <manifest|lockfile|catalog>`; unknown keys are often misspellings")
+    )]
+    SchemaViolation { problems: String },
+
+    #[error("Validation found {count} warning(s), more than max_warnings ({max})")]
+    #[diagnostic(
+        code(aps::validate::too_many_warnings),
+        help(
+            "Fix the new warnings, or list known-acceptable ones in the entry's `allow_warnings`"
+        )
+    )]
+    TooManyWarnings { count: usize, max: usize },
+
+    #[error("Entry '{id}' writes to '{dest}', inside '{other_dest}' which entry '{other}' installs as a whole")]
+    #[diagnostic(
+        code(aps::manifest::dest_collision),
+        help("Syncing '{other}' replaces everything at '{other_dest}'; give the entries destinations that don't overlap")
+    )]
+    DestCollision {
+        id: String,
+        dest: String,
+        other: String,
+        other_dest: String,
+    },
+
+    #[error("Invalid dest for entry '{id}': {message}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_dest),
+        help("'dest' is a path, or a list of distinct paths to install the same content to")
+    )]
+    InvalidDest { id: String, message: String },
+
+    #[error("Invalid targets for entry '{id}': {message}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_targets),
+        help("Valid targets are: agents_md, claude_md, copilot_instructions, gemini_md")
+    )]
+    InvalidTargets { id: String, message: String },
+
+    #[error("Invalid composite options for entry '{id}': {message}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_composite_options),
+        help("title, shift_headings, toc and local require composite_agents_md, and when.lang must be a detectable language")
+    )]
+    InvalidCompositeOptions { id: String, message: String },
+
+    #[error("Invalid settings target for entry '{id}': {message}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_settings_target),
+        help("Use target: project, local or managed on a claude_settings entry, without 'dest'")
+    )]
+    InvalidSettingsTarget { id: String, message: String },
+
+    #[error("Invalid merge strategy for entry '{id}': {message}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_merge_strategy),
+        help("Map dotted key paths (e.g. permissions.allow) to append, union, replace or error_on_conflict")
+    )]
+    InvalidMergeStrategy { id: String, message: String },
+
+    #[error("Entry '{id}' exceeds its size budget: {message}")]
+    #[diagnostic(
+        code(aps::compose::over_budget),
+        help("Trim or drop the largest fragments, raise the limit, or set `over_budget: warn`")
+    )]
+    BudgetExceeded { id: String, message: String },
+
+    #[error("Entry '{id}' sets 'mdc' but is not a cursor_rules entry")]
+    #[diagnostic(
+        code(aps::manifest::invalid_mdc),
+        help("Remove 'mdc', or change the entry kind to cursor_rules")
+    )]
+    InvalidMdcOptions { id: String },
+
+    #[error("Invalid transforms for entry '{id}': {message}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_transforms),
+        help("Supported transforms are strip_frontmatter, replace (from, to), prepend_file, append_file and script")
+    )]
+    InvalidTransforms { id: String, message: String },
+
+    #[error("Invalid map for entry '{id}': {message}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_map),
+        help("Each mapping needs 'from' (relative to the source) and 'to' (relative to the destination), e.g. {{from: README.md, to: AGENTS.md}}")
+    )]
+    InvalidMap { id: String, message: String },
+
+    #[error("Transform script {path} failed: {message}")]
+    #[diagnostic(
+        code(aps::transform::script_failed),
+        help("Scripts receive `content` and `path` and must return the new content as a string")
+    )]
+    TransformScriptError { path: PathBuf, message: String },
+
+    #[error("Invalid convert options for entry '{id}': {message}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_convert),
+        help("Set 'convert' on an agents_md entry, and use 'targets' to write other instruction files")
+    )]
+    InvalidConvertOptions { id: String, message: String },
+
+    #[error("Invalid legacy_cursorrules on entry '{id}': {message}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_legacy_cursorrules),
+        help("Set legacy_cursorrules on a single cursor_rules entry")
+    )]
+    InvalidLegacyCursorrules { id: String, message: String },
+
     #[error("Composite entry '{id}' requires 'sources' array")]
     #[diagnostic(
         code(aps::manifest::composite_requires_sources),
@@ -137,10 +437,105 @@ pub enum ApsError {
     )]
     EntryRequiresSource { id: String },
 
+    #[error("Unsupported source type '{source_type}'")]
+    #[diagnostic(
+        code(aps::source::unsupported_type),
+        help(
+            "This source type may require a newer version of aps; upgrade aps or remove the entry"
+        )
+    )]
+    UnsupportedSourceType { source_type: String },
+
+    #[error("{count} entry(ies) failed to sync")]
+    #[diagnostic(
+        code(aps::sync::entries_failed),
+        help("The remaining entries were synced; see the errors above for details")
+    )]
+    SyncFailed { count: usize },
+
+    #[error("Plan is out of date: {message}")]
+    #[diagnostic(
+        code(aps::plan::stale),
+        help("Run `aps plan` again and review the new plan before applying it")
+    )]
+    PlanStale { message: String },
+
+    #[error("Invalid plan at {path:?}: {message}")]
+    #[diagnostic(
+        code(aps::plan::invalid),
+        help("Plans are written by `aps plan --out <file>`")
+    )]
+    InvalidPlan { path: PathBuf, message: String },
+
+    #[error("Nothing to adopt for entry '{id}': {path:?} does not exist")]
+    #[diagnostic(
+        code(aps::adopt::missing_dest),
+        help("`aps adopt` records existing files; use `aps sync` to install new ones")
+    )]
+    NothingToAdopt { id: String, path: PathBuf },
+
     #[error("Failed to compose markdown files: {message}")]
     #[diagnostic(code(aps::compose::error))]
     ComposeError { message: String },
 
+    #[error("Failed to render template {path}: {message}")]
+    #[diagnostic(
+        code(aps::compose::template_error),
+        help("Check the fragment's {{{{placeholders}}}} against the entry's 'vars'")
+    )]
+    TemplateError { path: PathBuf, message: String },
+
+    #[error("Entry '{id}' sets 'vars' but is not a composite_agents_md entry")]
+    #[diagnostic(
+        code(aps::manifest::invalid_vars),
+        help("Remove 'vars', or compose the fragments with a composite_agents_md entry")
+    )]
+    InvalidVars { id: String },
+
+    #[error("Invalid MCP config fragment at {path}: {message}")]
+    #[diagnostic(
+        code(aps::mcp::invalid_fragment),
+        help("MCP config fragments must be JSON objects with an 'mcpServers' object")
+    )]
+    InvalidMcpConfig { path: PathBuf, message: String },
+
+    #[error("Invalid Claude settings fragment at {path}: {message}")]
+    #[diagnostic(
+        code(aps::claude_settings::invalid_fragment),
+        help("Settings fragments are YAML, JSON or JSONC mappings, e.g. 'allow: [\"Bash(git status)\"]' or 'model: sonnet'")
+    )]
+    InvalidClaudeSettings { path: PathBuf, message: String },
+
+    #[error("Settings composed by entry '{id}' don't match Claude's settings schema: {message}")]
+    #[diagnostic(
+        code(aps::claude_settings::invalid_settings),
+        help("Claude ignores keys it doesn't know; look for a misspelled key in the fragments, or see the composed settings with `aps validate --render {id}`")
+    )]
+    InvalidComposedSettings { id: String, message: String },
+
+    #[error("Merge conflict on '{key}' between {first} and {second}: {message}")]
+    #[diagnostic(
+        code(aps::merge::conflict),
+        help("The key uses the error_on_conflict merge strategy; make the fragments agree or choose another strategy")
+    )]
+    MergeConflict {
+        key: String,
+        message: String,
+        first: PathBuf,
+        second: PathBuf,
+    },
+
+    #[error("MCP server '{name}' is defined differently in {first} and {second}")]
+    #[diagnostic(
+        code(aps::mcp::server_conflict),
+        help("Remove or rename one of the conflicting server definitions")
+    )]
+    McpServerConflict {
+        name: String,
+        first: PathBuf,
+        second: PathBuf,
+    },
+
     #[error("Hooks directory should be named 'hooks': {path}")]
     #[diagnostic(code(aps::hooks::invalid_directory))]
     InvalidHooksDirectory { path: PathBuf },
@@ -172,6 +567,20 @@ pub enum ApsError {
     )]
     NoSkillsFound { location: String },
 
+    #[error("Skill(s) not found in {location}: {skills}")]
+    #[diagnostic(
+        code(aps::skill::not_found),
+        help("Check the names in 'skills' against the child directories of the source")
+    )]
+    SkillsNotFound { skills: String, location: PathBuf },
+
+    #[error("Invalid skills for entry '{id}': {message}")]
+    #[diagnostic(
+        code(aps::manifest::invalid_skills),
+        help("List the names of child skill directories, e.g. skills: [docker, terraform]")
+    )]
+    InvalidSkillSelection { id: String, message: String },
+
     #[error("No skills selected")]
     #[diagnostic(code(aps::discover::none_selected))]
     NoSkillsSelected,
@@ -188,4 +597,12 @@ impl ApsError {
             source: err,
         }
     }
+
+    /// Process exit code for this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ApsError::StatusCheckFailed { exit_code, .. } => *exit_code,
+            _ => 1,
+        }
+    }
 }
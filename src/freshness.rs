@@ -0,0 +1,200 @@
+//! Freshness policy for locked content (`max_age`).
+//!
+//! Each locked entry records `refreshed_at`, the last time its content was
+//! resolved from upstream. When an entry (or the manifest as a whole) sets
+//! `max_age`, `aps validate` and `aps status --check` fail once that
+//! timestamp falls outside the window, prompting a review of upstream changes.
+
+use crate::error::{ApsError, Result};
+use crate::lockfile::{LockedEntry, Lockfile};
+use crate::manifest::Manifest;
+use chrono::{DateTime, Duration, Utc};
+
+/// An entry whose locked content is older than its `max_age`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaleEntry {
+    pub id: String,
+    /// The policy that was exceeded, as written in the manifest
+    pub max_age: String,
+    /// When the entry was last refreshed (None if never recorded)
+    pub refreshed_at: Option<DateTime<Utc>>,
+}
+
+impl StaleEntry {
+    /// Human-readable description of how stale the entry is
+    pub fn describe(&self, now: DateTime<Utc>) -> String {
+        match self.refreshed_at {
+            Some(at) => format!(
+                "{} was last refreshed {} days ago (max_age: {})",
+                self.id,
+                (now - at).num_days(),
+                self.max_age
+            ),
+            None => format!(
+                "{} has no refresh timestamp (max_age: {})",
+                self.id, self.max_age
+            ),
+        }
+    }
+}
+
+/// Parse a `max_age` value such as `90d`, `12w` or `36h`
+pub fn parse_max_age(value: &str) -> Result<Duration> {
+    let invalid = || ApsError::InvalidMaxAge {
+        value: value.to_string(),
+    };
+
+    let trimmed = value.trim();
+    let unit_pos = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (amount, unit) = trimmed.split_at(unit_pos);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+
+    match unit {
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Current time formatted for `refreshed_at`
+pub fn now_timestamp() -> String {
+    Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// Whether installing `current` counts as refreshing from upstream.
+///
+/// Filesystem and composite sources are always read fresh, and `--upgrade`
+/// re-resolves git refs. A plain sync of a git entry reinstalls the locked
+/// commit, which is only a refresh if the commit actually changed.
+pub fn is_refresh(previous: Option<&LockedEntry>, current: &LockedEntry, upgrade: bool) -> bool {
+    upgrade
+        || current.commit.is_none()
+        || previous.and_then(|p| p.commit.as_ref()) != current.commit.as_ref()
+}
+
+/// Find manifest entries whose locked content exceeds their `max_age`.
+/// Entries without a policy or without a lockfile entry are ignored.
+pub fn find_stale_entries(
+    manifest: &Manifest,
+    lockfile: &Lockfile,
+    now: DateTime<Utc>,
+) -> Result<Vec<StaleEntry>> {
+    let mut stale = Vec::new();
+
+    for entry in &manifest.expanded_entries() {
+        let Some(max_age) = entry.max_age.as_ref().or(manifest.max_age.as_ref()) else {
+            continue;
+        };
+        let Some(locked) = lockfile.entries.get(&entry.id) else {
+            continue;
+        };
+
+        let window = parse_max_age(max_age)?;
+        let refreshed_at = locked
+            .refreshed_at
+            .as_deref()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&Utc));
+
+        let expired = match refreshed_at {
+            Some(at) => now - at > window,
+            None => true,
+        };
+        if expired {
+            stale.push(StaleEntry {
+                id: entry.id.clone(),
+                max_age: max_age.clone(),
+                refreshed_at,
+            });
+        }
+    }
+
+    Ok(stale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::Entry;
+
+    #[test]
+    fn test_parse_max_age() {
+        assert_eq!(parse_max_age("90d").unwrap(), Duration::days(90));
+        assert_eq!(parse_max_age("2w").unwrap(), Duration::weeks(2));
+        assert_eq!(parse_max_age("36h").unwrap(), Duration::hours(36));
+        assert!(parse_max_age("90").is_err());
+        assert!(parse_max_age("d").is_err());
+        assert!(parse_max_age("3mo").is_err());
+    }
+
+    fn locked(commit: Option<&str>, refreshed_at: Option<&str>) -> LockedEntry {
+        let mut entry = LockedEntry::new_composite(vec![], "./AGENTS.md", "sha256:x".into());
+        entry.commit = commit.map(str::to_string);
+        entry.refreshed_at = refreshed_at.map(str::to_string);
+        entry
+    }
+
+    #[test]
+    fn test_is_refresh() {
+        let old = locked(Some("abc"), None);
+        assert!(!is_refresh(Some(&old), &locked(Some("abc"), None), false));
+        assert!(is_refresh(Some(&old), &locked(Some("abc"), None), true));
+        assert!(is_refresh(Some(&old), &locked(Some("def"), None), false));
+        assert!(is_refresh(None, &locked(Some("abc"), None), false));
+        assert!(is_refresh(Some(&old), &locked(None, None), false));
+    }
+
+    #[test]
+    fn test_find_stale_entries() {
+        let now = DateTime::parse_from_rfc3339("2025-06-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let manifest = Manifest {
+            requires_aps: None,
+            entries: vec![
+                Entry {
+                    id: "fresh".into(),
+                    ..Default::default()
+                },
+                Entry {
+                    id: "old".into(),
+                    ..Default::default()
+                },
+                Entry {
+                    id: "strict".into(),
+                    max_age: Some("7d".into()),
+                    ..Default::default()
+                },
+                Entry {
+                    id: "unknown".into(),
+                    ..Default::default()
+                },
+                Entry {
+                    id: "unsynced".into(),
+                    ..Default::default()
+                },
+            ],
+            max_age: Some("30d".into()),
+            lockfile_format: None,
+            backup: None,
+            max_warnings: None,
+            catalogs: Vec::new(),
+        };
+
+        let mut lockfile = Lockfile::new();
+        lockfile.upsert("fresh".into(), locked(None, Some("2025-05-20T00:00:00Z")));
+        lockfile.upsert("old".into(), locked(None, Some("2025-03-01T00:00:00Z")));
+        lockfile.upsert("strict".into(), locked(None, Some("2025-05-20T00:00:00Z")));
+        lockfile.upsert("unknown".into(), locked(None, None));
+
+        let stale = find_stale_entries(&manifest, &lockfile, now).unwrap();
+        let ids: Vec<&str> = stale.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["old", "strict", "unknown"]);
+        assert_eq!(stale[1].max_age, "7d");
+        assert!(stale[0].describe(now).contains("92 days ago"));
+    }
+}
@@ -0,0 +1,60 @@
+//! YAML frontmatter helpers for markdown assets.
+
+use serde_yaml::Mapping;
+
+/// Split a document into its raw frontmatter and body.
+/// Returns None when the document has no `---` delimited frontmatter.
+pub fn split(content: &str) -> Option<(&str, &str)> {
+    let rest = content.strip_prefix("---")?;
+    let rest = rest
+        .strip_prefix('\n')
+        .or_else(|| rest.strip_prefix("\r\n"))?;
+
+    if let Some(body) = rest.strip_prefix("---") {
+        return Some(("", body.trim_start_matches(['\r', '\n'])));
+    }
+
+    let end = rest.find("\n---")?;
+    let body = &rest[end + 4..];
+    Some((&rest[..end], body.trim_start_matches(['\r', '\n'])))
+}
+
+/// Parse frontmatter as a YAML mapping, ignoring documents without valid frontmatter
+pub fn parse(content: &str) -> Option<Mapping> {
+    let (raw, _) = split(content)?;
+    serde_yaml::from_str(raw).ok()
+}
+
+/// Get a string field from parsed frontmatter
+pub fn get_str<'a>(frontmatter: &'a Mapping, key: &str) -> Option<&'a str> {
+    frontmatter
+        .get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_parse() {
+        let doc = "---\ntitle: Review\ndescription: \"Code review\"\n---\n\n# Body\n";
+        let (raw, body) = split(doc).unwrap();
+        assert_eq!(raw, "title: Review\ndescription: \"Code review\"");
+        assert_eq!(body, "# Body\n");
+
+        let fm = parse(doc).unwrap();
+        assert_eq!(get_str(&fm, "title"), Some("Review"));
+        assert_eq!(get_str(&fm, "description"), Some("Code review"));
+        assert_eq!(get_str(&fm, "missing"), None);
+    }
+
+    #[test]
+    fn test_no_frontmatter() {
+        assert!(split("# Title\n").is_none());
+        assert!(split("---not frontmatter").is_none());
+        assert!(parse("---\n: [unbalanced\n---\n").is_none());
+    }
+}
@@ -62,6 +62,15 @@ fn validate_hooks(hooks_dir: &Path, strict: bool) -> Result<Vec<String>> {
     Ok(warnings)
 }
 
+/// Scripts referenced by hook commands, relative to the tool's config
+/// directory (e.g. `hooks/check.sh` for `.claude/hooks/check.sh`)
+pub fn referenced_hook_scripts(hooks_section: &Value) -> Vec<PathBuf> {
+    let commands = collect_hook_commands(hooks_section);
+    let mut scripts: Vec<PathBuf> = collect_hook_script_paths(&commands).into_iter().collect();
+    scripts.sort();
+    scripts
+}
+
 fn hooks_root_dir(hooks_dir: &Path) -> PathBuf {
     match hooks_dir.file_name().and_then(|name| name.to_str()) {
         Some("hooks") | Some("scripts") => hooks_dir.parent().unwrap_or(hooks_dir).to_path_buf(),
@@ -136,7 +145,7 @@ fn extract_relative_path(token: &str) -> Option<String> {
         return None;
     }
 
-    let markers = [".cursor/", ".cursor\\"];
+    let markers = [".cursor/", ".cursor\\", ".claude/", ".claude\\"];
 
     for marker in markers {
         if let Some(position) = token.find(marker) {
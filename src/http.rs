@@ -0,0 +1,76 @@
+//! HTTP requests made by aps itself: fetching registry indexes and
+//! uploading published catalogs.
+//!
+//! Requests go through one `ureq` agent with a timeout, so every caller gets
+//! the same redirects, user agent and error messages. `file://` URLs are
+//! read from disk, which keeps local registries and tests off the network.
+
+use std::io::Read;
+use std::time::Duration;
+
+/// How long a request may take before it is abandoned
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout(TIMEOUT)
+        .user_agent(concat!("aps/", env!("CARGO_PKG_VERSION")))
+        .build()
+}
+
+/// Download the body at `url`. Errors are messages meant for the user.
+pub fn fetch(url: &str) -> std::result::Result<Vec<u8>, String> {
+    if url.starts_with("file://") {
+        let path = url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.to_file_path().ok())
+            .ok_or_else(|| format!("invalid file URL '{}'", url))?;
+        return std::fs::read(&path).map_err(|e| format!("failed to read {:?}: {}", path, e));
+    }
+
+    let response = agent().get(url).call().map_err(describe)?;
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .map_err(|e| format!("failed to read the response: {}", e))?;
+    Ok(body)
+}
+
+/// Upload `body` to `url` with an HTTP PUT, as presigned S3 URLs and most
+/// object stores accept
+pub fn upload(url: &str, body: &[u8], content_type: &str) -> std::result::Result<(), String> {
+    agent()
+        .put(url)
+        .set("Content-Type", content_type)
+        .send_bytes(body)
+        .map_err(describe)?;
+    Ok(())
+}
+
+/// Describe a failed request: the HTTP status, or why the server couldn't
+/// be reached
+fn describe(error: ureq::Error) -> String {
+    match error {
+        ureq::Error::Status(code, response) => {
+            format!("HTTP {} {}", code, response.status_text())
+        }
+        ureq::Error::Transport(transport) => transport.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_file_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aps.catalog.yaml");
+        std::fs::write(&path, "version: 1\n").unwrap();
+
+        let url = url::Url::from_file_path(&path).unwrap();
+        assert_eq!(fetch(url.as_str()).unwrap(), b"version: 1\n");
+        assert!(fetch(&format!("{}.missing", url)).is_err());
+    }
+}
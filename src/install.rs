@@ -1,16 +1,49 @@
-use crate::backup::{create_backup, has_conflict};
+use crate::backup::{create_backup, has_conflict, BackupMode};
+use crate::budget::check_budget;
 use crate::checksum::{compute_source_checksum, compute_string_checksum};
+use crate::claude_settings::{
+    collect_hook_scripts, compose_claude_settings, explain_permissions,
+    invalid_permission_patterns, managed_keys, permission_conflicts, permission_provenance,
+    read_existing_settings, write_settings_file, HookScript,
+};
 use crate::compose::{
-    compose_markdown, read_source_file, write_composed_file, ComposeOptions, ComposedSource,
+    compose_markdown, read_source_file, shift_headings, write_composed_file, ComposeOptions,
+    ComposedSource,
+};
+use crate::confirm::{confirm, PromptMode};
+use crate::convert::convert_to_markdown;
+use crate::cursorrules::{
+    is_legacy_cursorrules_current, write_legacy_cursorrules, LEGACY_CURSORRULES_FILENAME,
 };
+use crate::dedupe::check_duplicates;
+use crate::detect::condition_matches;
+use crate::diff::{diff_paths, show_diff};
 use crate::error::{ApsError, Result};
 use crate::hooks::validate_cursor_hooks;
-use crate::lockfile::{LockedEntry, Lockfile};
-use crate::manifest::{AssetKind, Entry};
+use crate::lockfile::{BackupReason, LockedBackup, LockedEntry, LockedFragment, Lockfile};
+use crate::managed::{
+    file_has_managed_region, load_base, region_content, save_base, write_managed_file,
+};
+use crate::manifest::{
+    check_dest_safety, entry_hash, AssetKind, Entry, FileMode, OverBudget, Source, SymlinkMode,
+    Transform,
+};
+use crate::mapping::stage_mapped;
+use crate::mcp::compose_mcp_config;
+use crate::mdc::install_mdc_rules;
+use crate::merge3::merge3;
+use crate::output::message;
+use crate::progress;
+use crate::prompts::write_prompt_index;
 use crate::sources::{clone_at_commit, get_remote_commit_sha, GitInfo, ResolvedSource};
-use dialoguer::Confirm;
-use std::io::IsTerminal;
+use crate::template::render_fragments;
+use crate::transform::{
+    apply_transforms, is_transformed_file, stage_transformed, transforms_fingerprint,
+};
+use crate::verify::installed_files;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use tempfile::TempDir;
 use tracing::{debug, info};
 use walkdir::WalkDir;
 
@@ -26,46 +59,174 @@ fn normalize_path(path: &Path) -> PathBuf {
     }
 }
 
+/// Checksum recorded for an entry: the source checksum, combined with any
+/// entry options that change the installed output so editing them re-syncs
+fn entry_checksum(entry: &Entry, manifest_dir: &Path, source_checksum: String) -> Result<String> {
+    let mut options = String::new();
+    if let Some(ref mdc) = entry.mdc {
+        options.push_str(&format!(
+            "\nmdc:{}",
+            serde_yaml::to_string(mdc).unwrap_or_default()
+        ));
+    }
+    if !entry.skills.is_empty() {
+        options.push_str(&format!("\nskills:{}", entry.skills.join(",")));
+    }
+    if !entry.transforms.is_empty() {
+        options.push_str(&format!(
+            "\ntransforms:{}",
+            transforms_fingerprint(&entry.transforms, manifest_dir)?
+        ));
+    }
+    if let Some(ref convert) = entry.convert {
+        options.push_str(&format!(
+            "\nconvert:{}",
+            serde_yaml::to_string(convert).unwrap_or_default()
+        ));
+    }
+
+    if let Some(mode) = entry.mode {
+        options.push_str(&format!("\nmode:{:04o}", mode.0));
+    }
+    if entry.readonly {
+        options.push_str("\nreadonly");
+    }
+    if !entry.map.is_empty() {
+        let map: Vec<String> = entry
+            .map
+            .iter()
+            .map(|m| format!("{}->{}", m.from, m.to))
+            .collect();
+        options.push_str(&format!("\nmap:{}", map.join(",")));
+    }
+    if entry.symlinks != SymlinkMode::default() {
+        options.push_str(&format!("\nsymlinks:{}", entry.symlinks.as_str()));
+    }
+
+    if options.is_empty() {
+        Ok(source_checksum)
+    } else {
+        Ok(compute_string_checksum(&format!(
+            "{}{}",
+            source_checksum, options
+        )))
+    }
+}
+
+/// Whether the entry is defined as it was when last synced, so a locked
+/// commit's installed output still matches it without resolving the source.
+/// Files read by transforms can change while the definition doesn't.
+fn definition_current(entry: &Entry, locked: Option<&LockedEntry>) -> bool {
+    let reads_files = entry.transforms.iter().any(|t| {
+        matches!(
+            t,
+            Transform::PrependFile(_) | Transform::AppendFile(_) | Transform::Script(_)
+        )
+    });
+    !reads_files
+        && locked.and_then(|l| l.definition_hash.as_deref()) == Some(entry_hash(entry).as_str())
+}
+
+/// Whether files generated alongside an entry's destination are still in place
+fn extras_current(entry: &Entry, manifest_dir: &Path, locked: Option<&LockedEntry>) -> bool {
+    !entry.legacy_cursorrules
+        || is_legacy_cursorrules_current(
+            &manifest_dir.join(LEGACY_CURSORRULES_FILENAME),
+            locked.and_then(|l| l.legacy_cursorrules_checksum.as_deref()),
+        )
+}
+
+/// In frozen mode, fail when content no longer matches the locked checksum
+fn check_frozen_checksum(
+    entry: &Entry,
+    lockfile: &Lockfile,
+    checksum: &str,
+    options: &InstallOptions,
+) -> Result<()> {
+    if options.frozen && !lockfile.checksum_matches(&entry.id, checksum) {
+        return Err(ApsError::LockfileOutOfSync {
+            message: format!("content of '{}' differs from its locked checksum", entry.id),
+        });
+    }
+    Ok(())
+}
+
+/// Fail closed when the content doesn't match the entry's pinned `integrity`
+fn check_integrity(entry: &Entry, checksum: &str) -> Result<()> {
+    match entry.integrity {
+        Some(ref expected) if expected != checksum => Err(ApsError::IntegrityMismatch {
+            id: entry.id.clone(),
+            expected: expected.clone(),
+            actual: checksum.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
 /// Options for the install operation
+#[derive(Default, Clone)]
 pub struct InstallOptions {
     pub dry_run: bool,
-    pub yes: bool,
+    /// How confirmation prompts are answered
+    pub prompts: PromptMode,
+    /// Where content is backed up before being overwritten
+    pub backup: BackupMode,
     pub strict: bool,
     /// When true, fetch latest versions from sources (ignore locked versions)
     /// When false (default), respect locked versions from the lockfile
     pub upgrade: bool,
+    /// Install only what the lockfile records; never resolve remote refs
+    pub frozen: bool,
+    /// Show a unified diff of changes to existing content on conflicts and
+    /// in dry runs
+    pub diff: bool,
+    /// Reinstall even when the lockfile says the destination is current
+    pub force: bool,
+    /// Remove links to files that were dropped from a symlinked source
+    pub prune: bool,
+    /// Three-way merge local edits to managed regions with upstream changes
+    pub merge: bool,
+    /// Drop sections of composite fragments that duplicate earlier fragments
+    pub dedupe: bool,
+    /// Print the provenance of each composed Claude permission rule
+    pub explain_permissions: bool,
+    /// Record diffs in the result instead of printing them (`aps plan`)
+    pub plan: bool,
+    /// Print composed content to stdout instead of installing it
+    /// (`aps validate --render`)
+    pub render: bool,
 }
 
 /// Handle conflict detection and resolution for a destination path.
-/// Returns Ok(true) if installation should proceed, Ok(false) if dry-run mode.
-/// Returns Err if user declines or non-interactive mode without --yes.
+/// Returns the backup made before overwriting, if any (none without a
+/// conflict or in dry-run mode).
+/// Returns Err if the overwrite is declined or can't be confirmed.
 fn handle_conflict(
+    entry_id: &str,
     dest_path: &Path,
     manifest_dir: &Path,
     options: &InstallOptions,
-) -> Result<bool> {
+) -> Result<Vec<PathBuf>> {
     if !has_conflict(dest_path) {
-        return Ok(true);
+        return Ok(Vec::new());
     }
 
     info!("Conflict detected at {:?}", dest_path);
+    progress::suspend();
 
     if options.dry_run {
-        println!("[dry-run] Would backup and overwrite: {:?}", dest_path);
-        return Ok(false);
-    }
-
-    let should_overwrite = if options.yes {
-        true
-    } else if std::io::stdin().is_terminal() {
-        Confirm::new()
-            .with_prompt(format!("Overwrite existing content at {:?}?", dest_path))
-            .default(false)
-            .interact()
-            .map_err(|_| ApsError::Cancelled)?
-    } else {
-        return Err(ApsError::RequiresYesFlag);
-    };
+        // Plans report conflicts in their own output
+        if !options.plan {
+            message!("[dry-run] Would backup and overwrite: {:?}", dest_path);
+        }
+        return Ok(Vec::new());
+    }
+
+    let should_overwrite = confirm(
+        options.prompts,
+        &format!("Overwrite existing content at {:?}?", dest_path),
+        false,
+    )?;
 
     if !should_overwrite {
         info!("User declined to overwrite {:?}", dest_path);
@@ -73,82 +234,226 @@ fn handle_conflict(
     }
 
     // Create backup
-    let backup_path = create_backup(manifest_dir, dest_path)?;
-    println!("Created backup at: {:?}", backup_path);
+    let backup_path = create_backup(manifest_dir, dest_path, entry_id, options.backup)?;
+    message!("Created backup at: {:?}", backup_path);
 
-    Ok(true)
+    Ok(vec![backup_path])
 }
 
 /// Handle conflict detection and resolution for a set of specific paths.
+/// Returns the backups made before overwriting.
 fn handle_partial_conflict(
+    entry_id: &str,
     dest_path: &Path,
     conflict_paths: &[PathBuf],
     manifest_dir: &Path,
     options: &InstallOptions,
-) -> Result<bool> {
+) -> Result<Vec<PathBuf>> {
     if conflict_paths.is_empty() {
-        return Ok(true);
+        return Ok(Vec::new());
     }
+    progress::suspend();
 
     if options.dry_run {
-        println!(
-            "[dry-run] Would overwrite {} item(s) under {:?}",
-            conflict_paths.len(),
-            dest_path
-        );
-        return Ok(false);
-    }
-
-    let should_overwrite = if options.yes {
-        true
-    } else if std::io::stdin().is_terminal() {
-        Confirm::new()
-            .with_prompt(format!(
-                "Overwrite {} existing item(s) under {:?}?",
+        if !options.plan {
+            message!(
+                "[dry-run] Would overwrite {} item(s) under {:?}",
                 conflict_paths.len(),
                 dest_path
-            ))
-            .default(false)
-            .interact()
-            .map_err(|_| ApsError::Cancelled)?
-    } else {
-        return Err(ApsError::RequiresYesFlag);
-    };
+            );
+        }
+        return Ok(Vec::new());
+    }
+
+    let should_overwrite = confirm(
+        options.prompts,
+        &format!(
+            "Overwrite {} existing item(s) under {:?}?",
+            conflict_paths.len(),
+            dest_path
+        ),
+        false,
+    )?;
 
     if !should_overwrite {
         info!("User declined to overwrite content under {:?}", dest_path);
         return Err(ApsError::Cancelled);
     }
 
+    let mut backups = Vec::new();
     for path in conflict_paths {
-        let backup_path = create_backup(manifest_dir, path)?;
-        println!("Created backup at: {:?}", backup_path);
+        let backup_path = create_backup(manifest_dir, path, entry_id, options.backup)?;
+        message!("Created backup at: {:?}", backup_path);
+        backups.push(backup_path);
     }
 
-    Ok(true)
+    Ok(backups)
+}
+
+/// Local edits to a managed region: the content aps last installed there
+/// and the region as it is now
+struct LocalEdits {
+    base: String,
+    local: String,
+}
+
+/// Find local edits to the managed region at `dest`. Returns `None` when the
+/// region is unchanged or no merge base was recorded for the entry.
+fn local_edits(manifest_dir: &Path, id: &str, dest: &Path) -> Option<LocalEdits> {
+    if !file_has_managed_region(dest) {
+        return None;
+    }
+    let base = load_base(manifest_dir, id)?;
+    let content = std::fs::read_to_string(dest).ok()?;
+    let local = region_content(&content)?.to_string();
+    (local != base).then_some(LocalEdits { base, local })
+}
+
+/// Items symlinked by an install, and the managed region it wrote
+type Installed = (Vec<String>, Option<ManagedWrite>);
+
+/// A managed region written by an install
+struct ManagedWrite {
+    /// Region content from upstream, the base for the next merge
+    upstream: String,
+    /// Conflicting hunks, when local edits were merged in
+    conflicts: Option<usize>,
+}
+
+/// Read back the managed region just written at `staged`, three-way merging
+/// `edits` into it when given
+fn merge_local_edits(staged: &Path, edits: Option<&LocalEdits>) -> Result<Option<ManagedWrite>> {
+    let Ok(content) = std::fs::read_to_string(staged) else {
+        return Ok(None);
+    };
+    let Some(upstream) = region_content(&content).map(str::to_string) else {
+        return Ok(None);
+    };
+    let conflicts = match edits {
+        Some(edits) => {
+            let merged = merge3(&edits.base, &edits.local, &upstream);
+            write_managed_file(staged, &merged.text, true)?;
+            Some(merged.conflicts)
+        }
+        None => None,
+    };
+    Ok(Some(ManagedWrite {
+        upstream,
+        conflicts,
+    }))
+}
+
+/// Record the merge base of a managed region after install, reporting how
+/// local edits were merged
+fn finish_managed_write(
+    manifest_dir: &Path,
+    id: &str,
+    dest: &Path,
+    managed: Option<ManagedWrite>,
+    warnings: &mut Vec<String>,
+) -> Result<()> {
+    let Some(managed) = managed else {
+        return Ok(());
+    };
+    save_base(manifest_dir, id, &managed.upstream)?;
+    progress::suspend();
+    match managed.conflicts {
+        Some(0) => message!("Merged local edits into {:?}", dest),
+        Some(conflicts) => {
+            let warning = format!(
+                "{} conflicting edit(s) merged into {:?}; resolve the <<<<<<< markers",
+                conflicts, dest
+            );
+            message!("Warning: {}", warning);
+            warnings.push(warning);
+        }
+        None => {}
+    }
+    Ok(())
 }
 
 /// Result of an install operation
 pub struct InstallResult {
     pub id: String,
-    #[allow(dead_code)]
     pub installed: bool,
     pub skipped_no_change: bool,
     pub locked_entry: Option<LockedEntry>,
     pub warnings: Vec<String>,
     pub dest_path: PathBuf,
     pub was_symlink: bool,
-    /// Whether a newer version is available (for git sources in locked mode)
-    pub upgrade_available: Option<UpgradeInfo>,
+    /// Newer versions available (for git sources in locked mode), or the
+    /// fragment commits bumped by an upgrade
+    pub upgrades: Vec<UpgradeInfo>,
+    /// Unified diff of the change (planned installs only)
+    pub diff: Option<String>,
+    /// Whether installing overwrites existing content, which is backed up first
+    pub conflict: bool,
 }
 
 /// Information about an available upgrade
 #[derive(Debug, Clone)]
 pub struct UpgradeInfo {
+    /// Composite fragment the upgrade applies to, if any
+    pub fragment: Option<String>,
     pub current_commit: String,
     pub available_commit: String,
 }
 
+/// Why an entry is copied even from a source that asks for symlinks.
+/// Converted content cannot be symlinked to the source, and mode or
+/// read-only overrides need copies of their own.
+pub fn copy_reason(entry: &Entry) -> Option<&'static str> {
+    if entry.mdc.is_some() {
+        Some("`mdc` converts the rules")
+    } else if entry.convert.is_some() {
+        Some("`convert` rewrites the content")
+    } else if !entry.transforms.is_empty() {
+        Some("`transforms` rewrite the content")
+    } else if !entry.map.is_empty() {
+        Some("`map` renames files")
+    } else if entry.mode.is_some() {
+        Some("`mode` sets permissions on the copies")
+    } else if entry.readonly {
+        Some("`readonly` marks the copies read-only")
+    } else {
+        None
+    }
+}
+
+/// Clone a git source at a locked commit
+fn resolve_locked_git(
+    source: &Source,
+    repo: &str,
+    commit: &str,
+    resolved_ref: &str,
+) -> Result<ResolvedSource> {
+    let resolved_git = clone_at_commit(repo, commit, resolved_ref)?;
+
+    // Build the path within the cloned repo
+    let path = source
+        .git_path()
+        .map(|p| p.to_string())
+        .unwrap_or_else(|| ".".to_string());
+    let source_path = if path == "." {
+        resolved_git.repo_path.clone()
+    } else {
+        resolved_git.repo_path.join(&path)
+    };
+
+    let git_info = GitInfo {
+        resolved_ref: resolved_git.resolved_ref.clone(),
+        commit_sha: resolved_git.commit_sha.clone(),
+        commit_info: resolved_git.commit_info.clone(),
+    };
+
+    Ok(ResolvedSource::git(
+        source_path,
+        repo.to_string(),
+        git_info,
+        resolved_git,
+    ))
+}
+
 /// Install a single entry
 pub fn install_entry(
     entry: &Entry,
@@ -157,6 +462,8 @@ pub fn install_entry(
     options: &InstallOptions,
 ) -> Result<InstallResult> {
     info!("Processing entry: {}", entry.id);
+    // Validation rejects these too; never write outside the project regardless
+    check_dest_safety(entry)?;
 
     // Get the source (required for non-composite entries)
     let source = entry
@@ -167,7 +474,7 @@ pub fn install_entry(
         })?;
 
     // For git sources, handle locked vs upgrade mode
-    let resolved = if let Some((repo, git_ref)) = source.git_info() {
+    let mut resolved = if let Some((repo, git_ref)) = source.git_info() {
         let dest_path = manifest_dir.join(entry.destination());
         let locked_entry = lockfile.entries.get(&entry.id);
 
@@ -181,7 +488,12 @@ pub fn install_entry(
             let locked_ref = locked.resolved_ref.as_deref().unwrap_or("unknown");
 
             // Check if there's a newer version available on the remote
-            let upgrade_available = match get_remote_commit_sha(repo, git_ref) {
+            let remote_sha = if options.frozen {
+                Ok(None)
+            } else {
+                get_remote_commit_sha(repo, git_ref)
+            };
+            let upgrade_available = match remote_sha {
                 Ok(Some(remote_sha)) if remote_sha != *locked_commit => {
                     debug!(
                         "Upgrade available for {}: {} -> {}",
@@ -190,6 +502,7 @@ pub fn install_entry(
                         &remote_sha[..8.min(remote_sha.len())]
                     );
                     Some(UpgradeInfo {
+                        fragment: None,
                         current_commit: locked_commit.clone(),
                         available_commit: remote_sha,
                     })
@@ -197,8 +510,13 @@ pub fn install_entry(
                 _ => None,
             };
 
-            // If destination exists and commit matches, we're up to date
-            if dest_path.exists() {
+            // If destination exists, commit matches and the entry wasn't
+            // edited, we're up to date
+            if !options.force
+                && dest_path.exists()
+                && definition_current(entry, Some(locked))
+                && extras_current(entry, manifest_dir, Some(locked))
+            {
                 info!(
                     "Entry {} is up to date (using locked commit {})",
                     entry.id,
@@ -213,7 +531,9 @@ pub fn install_entry(
                     warnings: Vec::new(),
                     dest_path: dest_path.clone(),
                     was_symlink,
-                    upgrade_available,
+                    upgrades: upgrade_available.into_iter().collect(),
+                    diff: None,
+                    conflict: false,
                 });
             }
 
@@ -223,32 +543,17 @@ pub fn install_entry(
                 entry.id,
                 &locked_commit[..8.min(locked_commit.len())]
             );
-            let resolved_git = clone_at_commit(repo, locked_commit, locked_ref)?;
-
-            // Build the path within the cloned repo
-            let path = source
-                .git_path()
-                .map(|p| p.to_string())
-                .unwrap_or_else(|| ".".to_string());
-            let source_path = if path == "." {
-                resolved_git.repo_path.clone()
-            } else {
-                resolved_git.repo_path.join(&path)
-            };
-
-            let git_info = GitInfo {
-                resolved_ref: resolved_git.resolved_ref.clone(),
-                commit_sha: resolved_git.commit_sha.clone(),
-            };
-
-            ResolvedSource::git(source_path, repo.to_string(), git_info, resolved_git)
+            resolve_locked_git(source, repo, locked_commit, locked_ref)?
         } else {
             // Upgrade mode or no locked commit: check remote and clone latest
             // Fast-path: skip if remote commit matches lockfile and dest exists
-            if dest_path.exists() {
+            if !options.force && dest_path.exists() {
                 debug!("Checking remote commit for {} ({})", repo, git_ref);
                 if let Ok(Some(remote_sha)) = get_remote_commit_sha(repo, git_ref) {
-                    if lockfile.commit_matches(&entry.id, &remote_sha) {
+                    if lockfile.commit_matches(&entry.id, &remote_sha)
+                        && definition_current(entry, lockfile.entries.get(&entry.id))
+                        && extras_current(entry, manifest_dir, lockfile.entries.get(&entry.id))
+                    {
                         info!(
                             "Entry {} is up to date (commit {} unchanged)",
                             entry.id,
@@ -267,7 +572,9 @@ pub fn install_entry(
                             warnings: Vec::new(),
                             dest_path: dest_path.clone(),
                             was_symlink,
-                            upgrade_available: None,
+                            upgrades: Vec::new(),
+                            diff: None,
+                            conflict: false,
                         });
                     }
                     debug!(
@@ -295,16 +602,50 @@ pub fn install_entry(
         });
     }
 
+    if copy_reason(entry).is_some() {
+        resolved.use_symlink = false;
+    }
+
+    // Copied installs already replace only the files they recorded; files
+    // dropped from a symlinked source leave dangling links until pruned
+    if options.prune && resolved.use_symlink {
+        let dest_path = manifest_dir.join(entry.destination());
+        let stale = stale_symlinks(&dest_path, &resolved.source_path);
+        if !stale.is_empty() {
+            progress::suspend();
+        }
+        if options.dry_run && !stale.is_empty() {
+            message!(
+                "[dry-run] Would remove {} stale link(s) under {:?}",
+                stale.len(),
+                dest_path
+            );
+        } else if !stale.is_empty() {
+            message!(
+                "Removed {} stale link(s) under {:?}",
+                stale.len(),
+                dest_path
+            );
+            remove_paths(&dest_path, stale.into_iter())?;
+        }
+    }
+
     // Compute checksum
-    let checksum = compute_source_checksum(&resolved.source_path)?;
+    let checksum = entry_checksum(
+        entry,
+        manifest_dir,
+        compute_source_checksum(&resolved.source_path)?,
+    )?;
     debug!("Source checksum: {}", checksum);
+    check_integrity(entry, &checksum)?;
+    check_frozen_checksum(entry, lockfile, &checksum, options)?;
 
     // Resolve destination path
     let dest_path = manifest_dir.join(entry.destination());
     debug!("Destination path: {:?}", dest_path);
 
     // Check if content is unchanged AND destination is valid (no-op)
-    if lockfile.checksum_matches(&entry.id, &checksum) {
+    if !options.force && lockfile.checksum_matches(&entry.id, &checksum) {
         // Even with matching checksum, verify destination exists and symlink targets are correct
         let dest_valid = if let Some(locked_entry) = lockfile.entries.get(&entry.id) {
             if locked_entry.is_symlink {
@@ -345,7 +686,7 @@ pub fn install_entry(
             false // No locked entry
         };
 
-        if dest_valid {
+        if dest_valid && extras_current(entry, manifest_dir, lockfile.entries.get(&entry.id)) {
             info!("Entry {} is up to date (checksum match)", entry.id);
             // Get was_symlink from lockfile if available
             let was_symlink = lockfile
@@ -361,7 +702,9 @@ pub fn install_entry(
                 warnings: Vec::new(),
                 dest_path: dest_path.clone(),
                 was_symlink,
-                upgrade_available: None,
+                upgrades: Vec::new(),
+                diff: None,
+                conflict: false,
             });
         } else {
             debug!(
@@ -371,23 +714,159 @@ pub fn install_entry(
         }
     }
 
+    // Renames and transforms run on staged copies so the source itself is
+    // never modified. Mapping first lets include and transforms see the
+    // installed names.
+    let _mapped = if entry.map.is_empty() {
+        None
+    } else {
+        let (guard, staged) = stage_mapped(&resolved.source_path, &entry.map)?;
+        resolved.source_path = staged;
+        Some(guard)
+    };
+    let _staging = if entry.transforms.is_empty() {
+        None
+    } else {
+        let (guard, staged) = stage_transformed(
+            &resolved.source_path,
+            &entry.transforms,
+            entry.symlinks,
+            manifest_dir,
+        )?;
+        resolved.source_path = staged;
+        Some(guard)
+    };
+
+    // Files recorded by the previous install are the only ones replaced, so
+    // files added to a destination directory by hand survive the reinstall
+    let recorded = lockfile
+        .entries
+        .get(&entry.id)
+        .filter(|l| !l.files.is_empty() && dest_path.is_dir())
+        .map(|l| &l.files);
+    let relative_dest = entry.destination();
+    // A directory other entries also install into is never cleared, and
+    // their files are left out of this entry's record
+    let shared = lockfile.entries.iter().any(|(id, locked)| {
+        id != &entry.id
+            && locked
+                .files
+                .keys()
+                .any(|key| manifest_dir.join(key).starts_with(&dest_path))
+    });
+    let unrecorded = match recorded {
+        Some(files) => {
+            let mut current = installed_files(manifest_dir, &relative_dest)?;
+            current.retain(|path, _| !files.contains_key(path));
+            current
+        }
+        None if shared && dest_path.is_dir() => installed_files(manifest_dir, &relative_dest)?,
+        None => BTreeMap::new(),
+    };
+
+    // Copied markdown goes into a managed region; edits made to it since the
+    // last install are merged with upstream under --merge
+    let writes_region = !resolved.use_symlink
+        && entry.mdc.is_none()
+        && (entry.kind == AssetKind::AgentsMd || entry.convert.is_some());
+    let edits = if writes_region {
+        local_edits(manifest_dir, &entry.id, &dest_path)
+    } else {
+        None
+    };
+
+    // Read-only files from the previous install are made writable again
+    // before being replaced, and marked again afterwards if still wanted
+    let was_readonly = previous_readonly(lockfile, &entry.id);
+
+    // Installs run against a staged copy of the destination, which replaces
+    // it only once everything has been written
+    let install_with = |staged: &Path, use_symlink: bool| -> Result<Installed> {
+        if entry.readonly || was_readonly {
+            set_readonly(staged, false)?;
+        }
+        if let Some(files) = recorded {
+            let paths = files.keys().filter_map(|key| {
+                let path = manifest_dir.join(key);
+                path.strip_prefix(&dest_path)
+                    .ok()
+                    .map(|rel| staged.join(rel))
+            });
+            remove_paths(staged, paths)?;
+        }
+
+        let symlinked_items = if let Some(ref mdc) = entry.mdc {
+            install_mdc_rules(&resolved.source_path, staged, &entry.include, mdc)?;
+            Vec::new()
+        } else if let Some(ref convert) = entry.convert {
+            let content = convert_to_markdown(&resolved.source_path, &entry.include, convert)?;
+            write_managed_file(staged, &content, entry.managed_region)?;
+            Vec::new()
+        } else {
+            install_asset(
+                entry,
+                &resolved.source_path,
+                staged,
+                use_symlink,
+                recorded.is_none() && !shared,
+            )?
+        };
+
+        if entry.kind == AssetKind::PromptLibrary {
+            write_prompt_index(staged)?;
+        }
+        if let Some(mode) = entry.mode {
+            apply_mode(staged, mode)?;
+        }
+        let managed = if writes_region {
+            merge_local_edits(staged, edits.as_ref().filter(|_| options.merge))?
+        } else {
+            None
+        };
+        if entry.readonly && !use_symlink {
+            set_readonly(staged, true)?;
+        }
+        Ok((symlinked_items, managed))
+    };
+    let install = |staged: &Path| install_with(staged, resolved.use_symlink);
+    let mut symlink_fallback = false;
+
     // Check for conflicts
     // For directory assets (CursorRules, CursorSkillsRoot) using symlinks, we use
     // file-level symlinks which can coexist with other files in the directory.
     // Only check for conflicts on single-file assets or when copying.
     let should_check_conflict = match entry.kind {
-        AssetKind::AgentsMd => true,          // Single file - always check
-        AssetKind::CompositeAgentsMd => true, // Composite file - always check
+        // Copied instructions only rewrite their managed region, if present
+        AssetKind::AgentsMd => resolved.use_symlink || !file_has_managed_region(&dest_path),
+        AssetKind::File => true, // Single file - always check
+        AssetKind::CompositeAgentsMd | AssetKind::McpConfig | AssetKind::ClaudeSettings => true, // Composite file - always check
         AssetKind::CursorRules
         | AssetKind::CursorHooks
         | AssetKind::CursorSkillsRoot
-        | AssetKind::AgentSkill => {
+        | AssetKind::AgentSkill
+        | AssetKind::PromptLibrary
+        | AssetKind::Dir => {
             // For directory assets with symlinks, we add files to the directory
             // without backing up existing content from other sources
             !resolved.use_symlink
         }
     };
+    // Without --merge, local edits to a managed region are overwritten like
+    // any other conflicting content
+    let should_check_conflict = should_check_conflict || (edits.is_some() && !options.merge);
+
+    // Show what would change before asking to overwrite anything
+    let conflict = should_check_conflict && has_conflict(&dest_path);
+    let diff = if options.plan {
+        Some(install_diff(&relative_dest, &dest_path, install)?)
+    } else {
+        if options.diff && (options.dry_run || conflict) {
+            print_install_diff(&relative_dest, &dest_path, install)?;
+        }
+        None
+    };
 
+    let mut backups = Vec::new();
     if should_check_conflict {
         if matches!(entry.kind, AssetKind::CursorHooks) {
             let mut conflicts = collect_hook_conflicts(&resolved.source_path, &dest_path)?;
@@ -415,23 +894,24 @@ pub fn install_entry(
             }
             conflicts.sort();
             conflicts.dedup();
-            let should_proceed =
-                handle_partial_conflict(&dest_path, &conflicts, manifest_dir, options)?;
-            if !should_proceed {
-                // dry-run mode, skip actual installation but continue
-            }
+            backups =
+                handle_partial_conflict(&entry.id, &dest_path, &conflicts, manifest_dir, options)?;
         } else {
-            let should_proceed = handle_conflict(&dest_path, manifest_dir, options)?;
-            if !should_proceed {
-                // dry-run mode, skip actual installation but continue
-            }
+            backups = handle_conflict(&entry.id, &dest_path, manifest_dir, options)?;
         }
     }
 
     // Validate skills if this is a skills root
     let mut warnings = Vec::new();
+    if !entry.skills.is_empty() {
+        select_skills(&resolved.source_path, &entry.skills)?;
+    }
     if entry.kind == AssetKind::CursorSkillsRoot {
-        warnings.extend(validate_skills_root(&resolved.source_path, options.strict)?);
+        warnings.extend(validate_skills_root(
+            &resolved.source_path,
+            &entry.skills,
+            options.strict,
+        )?);
     }
     if entry.kind == AssetKind::CursorHooks {
         warnings.extend(validate_cursor_hooks(
@@ -440,21 +920,32 @@ pub fn install_entry(
         )?);
     }
     for warning in &warnings {
-        println!("Warning: {}", warning);
+        progress::suspend();
+        message!("Warning: {}", warning);
     }
 
-    // Perform the install
-    let symlinked_items = if options.dry_run {
-        Vec::new()
+    let (symlinked_items, managed) = if options.dry_run {
+        (Vec::new(), None)
     } else {
-        install_asset(
-            &entry.kind,
-            &resolved.source_path,
-            &dest_path,
-            resolved.use_symlink,
-            &entry.include,
-        )?
+        progress::detail("installing");
+        match install_staged(&dest_path, install) {
+            Err(e) if resolved.use_symlink && is_symlink_unavailable(&e) => {
+                let warning = "symlinks are not available on this system (enable Developer \
+                               Mode on Windows); copied instead"
+                    .to_string();
+                progress::suspend();
+                message!("Warning: {}", warning);
+                warnings.push(warning);
+                symlink_fallback = true;
+                install_staged(&dest_path, |staged: &Path| install_with(staged, false))?
+            }
+            result => result?,
+        }
     };
+    if symlink_fallback {
+        resolved.use_symlink = false;
+    }
+    finish_managed_write(manifest_dir, &entry.id, &dest_path, managed, &mut warnings)?;
 
     if !options.dry_run && matches!(entry.kind, AssetKind::CursorHooks) {
         sync_hooks_config(
@@ -470,8 +961,40 @@ pub fn install_entry(
 
     // Create locked entry from resolved source
     // Store relative path in lockfile for portability across machines
-    let relative_dest = entry.destination();
-    let locked_entry = resolved.to_locked_entry(&relative_dest, checksum, symlinked_items);
+    let mut locked_entry = resolved.to_locked_entry(&relative_dest, checksum, symlinked_items);
+    locked_entry.symlink_fallback = symlink_fallback;
+    locked_entry.readonly = entry.readonly && !resolved.use_symlink;
+    if !options.dry_run && !resolved.use_symlink {
+        let mut files = installed_files(manifest_dir, &relative_dest)?;
+        // Hand-added files stay unrecorded unless the install overwrote them
+        files.retain(|path, checksum| unrecorded.get(path) != Some(checksum));
+        locked_entry.files = files;
+    }
+
+    if !options.dry_run && entry.legacy_cursorrules {
+        let legacy_path = manifest_dir.join(LEGACY_CURSORRULES_FILENAME);
+        // Back up a hand-written .cursorrules the first time aps takes it over
+        let previously_managed = lockfile
+            .entries
+            .get(&entry.id)
+            .is_some_and(|e| e.legacy_cursorrules_checksum.is_some());
+        if !previously_managed {
+            backups.extend(handle_conflict(
+                &entry.id,
+                &legacy_path,
+                manifest_dir,
+                options,
+            )?);
+        }
+        locked_entry.legacy_cursorrules_checksum =
+            Some(write_legacy_cursorrules(&dest_path, &legacy_path)?);
+    }
+    let reason = if edits.is_some() && !options.merge {
+        BackupReason::LocalEdits
+    } else {
+        BackupReason::Conflict
+    };
+    locked_entry.backup = LockedBackup::new(manifest_dir, &backups, reason);
 
     Ok(InstallResult {
         id: entry.id.clone(),
@@ -481,7 +1004,9 @@ pub fn install_entry(
         warnings,
         dest_path,
         was_symlink: resolved.use_symlink,
-        upgrade_available: None,
+        upgrades: Vec::new(),
+        diff,
+        conflict,
     })
 }
 
@@ -493,6 +1018,7 @@ pub fn install_composite_entry(
     options: &InstallOptions,
 ) -> Result<InstallResult> {
     info!("Processing composite entry: {}", entry.id);
+    check_dest_safety(entry)?;
 
     if entry.sources.is_empty() {
         return Err(ApsError::CompositeRequiresSources {
@@ -502,11 +1028,73 @@ pub fn install_composite_entry(
 
     // Resolve all sources and collect their content
     let mut composed_sources: Vec<ComposedSource> = Vec::new();
-    let mut all_checksums: Vec<String> = Vec::new();
+    let mut fragments: Vec<LockedFragment> = Vec::new();
+    let mut upgrades: Vec<UpgradeInfo> = Vec::new();
+    // Keeps cloned git fragments alive until the entry is installed
+    let mut resolved_sources: Vec<ResolvedSource> = Vec::new();
+    let previous = lockfile.entries.get(&entry.id);
+
+    // Fragments whose `when` condition doesn't match this project are left out
+    let ordered_sources: Vec<_> = entry
+        .ordered_sources()
+        .into_iter()
+        .filter(|s| {
+            let included = s
+                .when
+                .as_ref()
+                .is_none_or(|when| condition_matches(when, manifest_dir));
+            if !included {
+                debug!(
+                    "Skipping fragment {} (when: not matched)",
+                    s.source.display_path()
+                );
+            }
+            included
+        })
+        .collect();
+    if ordered_sources.is_empty() && entry.local.is_none() {
+        return Err(ApsError::ComposeError {
+            message: format!(
+                "no sources of '{}' match this project's `when` conditions",
+                entry.id
+            ),
+        });
+    }
 
-    for source in &entry.sources {
-        let adapter = source.to_adapter();
-        let resolved = adapter.resolve(manifest_dir)?;
+    for composite in &ordered_sources {
+        // Git fragments stay at their locked commits unless upgrading
+        let display = composite.source.display_path();
+        let locked_commit = previous
+            .and_then(|l| l.fragments.iter().find(|f| f.source == display))
+            .and_then(|f| f.commit.clone());
+        let resolved = match (composite.source.git_info(), locked_commit.as_ref()) {
+            (Some((repo, git_ref)), Some(commit)) if !options.upgrade => {
+                let remote_sha = if options.frozen {
+                    Ok(None)
+                } else {
+                    get_remote_commit_sha(repo, git_ref)
+                };
+                if let Ok(Some(remote_sha)) = remote_sha {
+                    if remote_sha != *commit {
+                        upgrades.push(UpgradeInfo {
+                            fragment: Some(display.clone()),
+                            current_commit: commit.clone(),
+                            available_commit: remote_sha,
+                        });
+                    }
+                }
+                resolve_locked_git(&composite.source, repo, commit, git_ref)?
+            }
+            (Some(_), None) if options.frozen => {
+                return Err(ApsError::LockfileOutOfSync {
+                    message: format!(
+                        "fragment {} of '{}' has no locked commit",
+                        display, entry.id
+                    ),
+                })
+            }
+            _ => composite.source.to_adapter().resolve(manifest_dir)?,
+        };
 
         if !resolved.source_path.exists() {
             return Err(ApsError::SourcePathNotFound {
@@ -515,86 +1103,480 @@ pub fn install_composite_entry(
         }
 
         // Read the source file
-        let composed_source = read_source_file(&resolved.source_path)?;
+        let mut composed_source = read_source_file(&resolved.source_path)?;
+        if is_transformed_file(&resolved.source_path) {
+            composed_source.content = apply_transforms(
+                &composed_source.content,
+                &entry.transforms,
+                manifest_dir,
+                Path::new(resolved.source_path.file_name().unwrap_or_default()),
+            )?;
+        }
+        composed_source.title = composite.title.clone();
+        if let Some(levels) = composite.shift_headings {
+            composed_source.content = shift_headings(&composed_source.content, levels);
+        }
         composed_sources.push(composed_source);
 
-        // Compute and collect checksum for this source
-        let source_checksum = compute_source_checksum(&resolved.source_path)?;
-        all_checksums.push(source_checksum);
+        // Lock each fragment's commit and checksum
+        let commit = resolved.git_info.as_ref().map(|g| g.commit_sha.clone());
+        if let (Some(old), Some(new), true) = (&locked_commit, &commit, options.upgrade) {
+            if old != new {
+                upgrades.push(UpgradeInfo {
+                    fragment: Some(display.clone()),
+                    current_commit: old.clone(),
+                    available_commit: new.clone(),
+                });
+            }
+        }
+        fragments.push(LockedFragment {
+            source: display,
+            commit,
+            checksum: compute_source_checksum(&resolved.source_path)?,
+        });
+        resolved_sources.push(resolved);
+    }
+
+    // The project's own fragment always comes last and is optional
+    if let Some(ref local) = entry.local {
+        let path = manifest_dir.join(local);
+        if path.is_file() {
+            composed_sources.push(read_source_file(&path)?);
+        } else {
+            debug!("Local fragment {:?} not found, skipping", path);
+        }
+    }
+
+    if !entry.vars.is_empty() {
+        render_fragments(&mut composed_sources, &entry.vars)?;
+    }
+
+    let mut warnings = if entry.kind == AssetKind::CompositeAgentsMd {
+        check_duplicates(&mut composed_sources, options.dedupe)
+    } else {
+        Vec::new()
+    };
+
+    // Compose all sources into a single file
+    let composed_content = match entry.kind {
+        AssetKind::McpConfig => compose_mcp_config(&composed_sources, &entry.merge)?,
+        AssetKind::ClaudeSettings => compose_claude_settings(&composed_sources, &entry.merge)?,
+        _ => {
+            let compose_options = ComposeOptions {
+                add_separators: false,
+                include_source_info: false,
+                toc: entry.toc,
+            };
+            compose_markdown(&composed_sources, &compose_options)?
+        }
+    };
+
+    if let Some(message) = check_budget(entry, &composed_content, &composed_sources) {
+        match entry.over_budget {
+            OverBudget::Error => {
+                return Err(ApsError::BudgetExceeded {
+                    id: entry.id.clone(),
+                    message,
+                })
+            }
+            OverBudget::Warn => warnings.push(message),
+        }
+    }
+
+    if entry.kind == AssetKind::ClaudeSettings {
+        let rules = permission_provenance(&composed_sources)?;
+        if options.explain_permissions && !rules.is_empty() {
+            progress::suspend();
+            message!("Permissions for '{}':", entry.id);
+            print!("{}", explain_permissions(&rules));
+        }
+        for source in &composed_sources {
+            warnings.extend(invalid_permission_patterns(source)?);
+        }
+        warnings.extend(permission_conflicts(&rules));
     }
 
-    // Compose all sources into one markdown string
-    let compose_options = ComposeOptions {
-        add_separators: false,
-        include_source_info: false,
+    // Scripts referenced by Claude hook commands are installed alongside the settings
+    let hook_scripts = if entry.kind == AssetKind::ClaudeSettings {
+        let (scripts, script_warnings) = collect_hook_scripts(&composed_sources, options.strict)?;
+        warnings.extend(script_warnings);
+        scripts
+    } else {
+        Vec::new()
     };
-    let composed_content = compose_markdown(&composed_sources, &compose_options)?;
 
-    // Compute checksum of the final composed content
-    let checksum = compute_string_checksum(&composed_content);
+    if options.render {
+        print!("{}", composed_content);
+        return Ok(InstallResult {
+            id: entry.id.clone(),
+            installed: false,
+            skipped_no_change: false,
+            locked_entry: None,
+            warnings,
+            dest_path: manifest_dir.join(entry.destination()),
+            was_symlink: false,
+            upgrades,
+            diff: None,
+            conflict: false,
+        });
+    }
+
+    // Compute checksum of the final composed content (and any hook scripts)
+    let mut checksum_input = composed_content.clone();
+    for script in &hook_scripts {
+        let content = std::fs::read_to_string(&script.source)
+            .map_err(|e| ApsError::io(e, format!("Failed to read {:?}", script.source)))?;
+        checksum_input.push_str(&format!("\n{}\n{}", script.path.display(), content));
+    }
+    if let Some(mode) = entry.mode {
+        checksum_input.push_str(&format!("\nmode:{:04o}", mode.0));
+    }
+    if entry.readonly {
+        checksum_input.push_str("\nreadonly");
+    }
+    let checksum = compute_string_checksum(&checksum_input);
     debug!("Composed content checksum: {}", checksum);
+    check_integrity(entry, &checksum)?;
+    check_frozen_checksum(entry, lockfile, &checksum, options)?;
 
     // Resolve destination path
     let dest_path = manifest_dir.join(entry.destination());
     debug!("Destination path: {:?}", dest_path);
 
     // Check if content is unchanged
-    if lockfile.checksum_matches(&entry.id, &checksum) && dest_path.exists() {
+    if !options.force && lockfile.checksum_matches(&entry.id, &checksum) && dest_path.exists() {
         info!(
             "Composite entry {} is up to date (checksum match)",
             entry.id
         );
+        // Fragment records still change when content doesn't (e.g. a bump
+        // that didn't touch the file, or a lockfile written before them)
+        let locked_entry = previous.filter(|l| l.fragments != fragments).map(|l| {
+            let mut locked = l.clone();
+            locked.fragments = fragments;
+            locked
+        });
         return Ok(InstallResult {
             id: entry.id.clone(),
             installed: false,
             skipped_no_change: true,
-            locked_entry: None,
-            warnings: Vec::new(),
+            locked_entry,
+            warnings: warnings.clone(),
             dest_path: dest_path.clone(),
             was_symlink: false,
-            upgrade_available: None,
+            upgrades,
+            diff: None,
+            conflict: false,
         });
     }
 
-    // Check for conflicts and handle backup if needed
-    handle_conflict(&dest_path, manifest_dir, options)?;
+    // Markdown output only rewrites its managed region, so an existing
+    // region means local edits around it are kept rather than backed up
+    // Likewise, settings are merged into an existing settings file
+    let managed = entry.kind == AssetKind::CompositeAgentsMd;
+    let merges_in_place = if managed {
+        file_has_managed_region(&dest_path)
+    } else {
+        entry.kind == AssetKind::ClaudeSettings && read_existing_settings(&dest_path).is_some()
+    };
+    let edits = if managed {
+        local_edits(manifest_dir, &entry.id, &dest_path)
+    } else {
+        None
+    };
+    let merges_in_place = merges_in_place && (edits.is_none() || options.merge);
+    let was_readonly = previous_readonly(lockfile, &entry.id);
+    let write = |staged: &Path| -> Result<Option<ManagedWrite>> {
+        if entry.readonly || was_readonly {
+            set_readonly(staged, false)?;
+        }
+        let written = if managed {
+            write_managed_file(staged, &composed_content, entry.managed_region)?;
+            merge_local_edits(staged, edits.as_ref().filter(|_| options.merge))?
+        } else {
+            if entry.kind == AssetKind::ClaudeSettings {
+                let previous_keys = previous.map(|l| l.managed_keys.as_slice()).unwrap_or(&[]);
+                write_settings_file(staged, &composed_content, previous_keys)?;
+            } else {
+                write_composed_file(&composed_content, staged)?;
+            }
+            None
+        };
+        if let Some(mode) = entry.mode {
+            apply_mode(staged, mode)?;
+        }
+        if entry.readonly {
+            set_readonly(staged, true)?;
+        }
+        Ok(written)
+    };
+    let conflict = !merges_in_place && has_conflict(&dest_path);
+    let diff = if options.plan {
+        Some(install_diff(&entry.destination(), &dest_path, write)?)
+    } else {
+        if options.diff && (options.dry_run || conflict) {
+            print_install_diff(&entry.destination(), &dest_path, write)?;
+        }
+        None
+    };
+    let backups = if merges_in_place {
+        Vec::new()
+    } else {
+        handle_conflict(&entry.id, &dest_path, manifest_dir, options)?
+    };
 
     // Write the composed file
     if !options.dry_run {
-        write_composed_file(&composed_content, &dest_path)?;
+        let written = install_staged(&dest_path, write)?;
+        finish_managed_write(manifest_dir, &entry.id, &dest_path, written, &mut warnings)?;
+        if entry.kind == AssetKind::ClaudeSettings {
+            let settings_dir = dest_path.parent().unwrap_or(manifest_dir);
+            install_hook_scripts(&hook_scripts, settings_dir)?;
+        }
         info!("Wrote composed file to {:?}", dest_path);
-    } else {
-        println!("[dry-run] Would write composed file to {:?}", dest_path);
+    } else if !options.plan {
+        progress::suspend();
+        message!("[dry-run] Would write composed file to {:?}", dest_path);
     }
 
     // Create locked entry with original source paths (preserving shell variables like $HOME)
     // Store relative path in lockfile for portability across machines
-    let source_paths: Vec<String> = entry.sources.iter().map(|s| s.display_path()).collect();
+    let source_paths: Vec<String> = ordered_sources
+        .iter()
+        .map(|s| s.source.display_path())
+        .chain(entry.local.iter().map(|local| format!("local:{}", local)))
+        .collect();
     let relative_dest = entry.destination();
 
-    let locked_entry =
+    let mut locked_entry =
         LockedEntry::new_composite(source_paths, &relative_dest.to_string_lossy(), checksum);
+    locked_entry.fragments = fragments;
+    if entry.kind == AssetKind::ClaudeSettings {
+        locked_entry.managed_keys = managed_keys(&composed_content);
+    }
+    locked_entry.readonly = entry.readonly;
+    locked_entry.backup = LockedBackup::new(manifest_dir, &backups, BackupReason::Conflict);
+    if !options.dry_run {
+        locked_entry.files = installed_files(manifest_dir, &relative_dest)?;
+    }
 
     Ok(InstallResult {
         id: entry.id.clone(),
         installed: !options.dry_run,
         skipped_no_change: false,
         locked_entry: Some(locked_entry),
-        warnings: Vec::new(),
+        warnings,
         dest_path,
         was_symlink: false,
-        upgrade_available: None,
+        upgrades: Vec::new(),
+        diff,
+        conflict,
     })
 }
 
-/// Install an asset based on its kind
+/// Symlinks under `dest` into `source` whose target has since been removed
+fn stale_symlinks(dest: &Path, source: &Path) -> Vec<PathBuf> {
+    if !dest.is_dir() {
+        return Vec::new();
+    }
+    WalkDir::new(dest)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path_is_symlink())
+        .filter(|e| {
+            std::fs::read_link(e.path())
+                .is_ok_and(|target| target.starts_with(source) && !target.exists())
+        })
+        .map(|e| e.into_path())
+        .collect()
+}
+
+/// Remove the files a previous install recorded under `root`, then any
+/// directories beneath it left empty. Files that weren't recorded are kept.
+pub fn remove_installed_files(
+    manifest_dir: &Path,
+    root: &Path,
+    files: &BTreeMap<String, String>,
+) -> Result<()> {
+    remove_paths(root, files.keys().map(|key| manifest_dir.join(key)))
+}
+
+/// Remove `paths` and any directories beneath `root` they leave empty
+fn remove_paths(root: &Path, paths: impl Iterator<Item = PathBuf>) -> Result<()> {
+    for path in paths {
+        if path.symlink_metadata().is_ok_and(|m| !m.is_dir()) {
+            std::fs::remove_file(&path)
+                .map_err(|e| ApsError::io(e, format!("Failed to remove file {:?}", path)))?;
+            debug!("Removed previously installed file {:?}", path);
+        }
+
+        let mut parent = path.parent();
+        while let Some(dir) = parent {
+            if dir == root || !dir.starts_with(root) || std::fs::remove_dir(dir).is_err() {
+                break;
+            }
+            parent = dir.parent();
+        }
+    }
+    Ok(())
+}
+
+/// A staging copy of `dest` in a temporary sibling directory (so renames
+/// stay on one filesystem), starting out with dest's current contents and
+/// symlinks kept as symlinks. `None` when dest can't be renamed, e.g. `.`.
+fn stage_destination(dest: &Path) -> Result<Option<(TempDir, PathBuf)>> {
+    let parent = match dest.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let Some(name) = dest.file_name() else {
+        return Ok(None);
+    };
+    std::fs::create_dir_all(parent)
+        .map_err(|e| ApsError::io(e, format!("Failed to create directory {:?}", parent)))?;
+
+    let staging = tempfile::Builder::new()
+        .prefix(".aps-staging-")
+        .tempdir_in(parent)
+        .map_err(|e| {
+            ApsError::io(
+                e,
+                format!("Failed to create staging directory in {:?}", parent),
+            )
+        })?;
+    let next_dir = staging.path().join("next");
+    std::fs::create_dir(&next_dir)
+        .map_err(|e| ApsError::io(e, format!("Failed to create directory {:?}", next_dir)))?;
+    let staged = next_dir.join(name);
+    if dest.symlink_metadata().is_ok() {
+        copy_preserving_links(dest, &staged)?;
+    }
+    Ok(Some((staging, staged)))
+}
+
+/// Run `install` against a staging copy of `dest`, then swap the result into
+/// place. Installs that merge into an existing destination behave the same
+/// as in place; if `install` fails, dest is left untouched.
+fn install_staged<T>(dest: &Path, install: impl FnOnce(&Path) -> Result<T>) -> Result<T> {
+    let dest = normalize_path(dest);
+    let Some((staging, staged)) = stage_destination(&dest)? else {
+        return install(&dest);
+    };
+
+    let output = install(&staged)?;
+
+    // The previous content is moved aside rather than deleted until the new
+    // content is in place; the staging directory removes it afterwards
+    let previous = staging.path().join("previous");
+    let existed = dest.symlink_metadata().is_ok();
+    if existed {
+        std::fs::rename(&dest, &previous)
+            .map_err(|e| ApsError::io(e, format!("Failed to move {:?} aside", dest)))?;
+    }
+    if staged.symlink_metadata().is_ok() {
+        if let Err(e) = std::fs::rename(&staged, &dest) {
+            if existed {
+                let _ = std::fs::rename(&previous, &dest);
+            }
+            return Err(ApsError::io(
+                e,
+                format!("Failed to move staged install to {:?}", dest),
+            ));
+        }
+    }
+    debug!("Swapped staged install into {:?}", dest);
+    Ok(output)
+}
+
+/// Unified diff of what running `install` would change at `dest`, labelled
+/// with the manifest-relative `name`
+fn install_diff<T>(
+    name: &Path,
+    dest: &Path,
+    install: impl FnOnce(&Path) -> Result<T>,
+) -> Result<String> {
+    let dest = normalize_path(dest);
+    let Some((_staging, staged)) = stage_destination(&dest)? else {
+        return Ok(String::new());
+    };
+    install(&staged)?;
+    diff_paths(name, &dest, &staged)
+}
+
+/// Print a unified diff of what running `install` would change at `dest`
+fn print_install_diff<T>(
+    name: &Path,
+    dest: &Path,
+    install: impl FnOnce(&Path) -> Result<T>,
+) -> Result<()> {
+    let diff = install_diff(name, dest, install)?;
+    progress::suspend();
+    if diff.is_empty() {
+        message!("No changes to {}", name.display());
+    } else {
+        show_diff(&diff);
+    }
+    Ok(())
+}
+
+/// Whether an install failed because this system doesn't allow creating
+/// symlinks (Windows without Developer Mode or elevated rights)
+fn is_symlink_unavailable(err: &ApsError) -> bool {
+    /// ERROR_PRIVILEGE_NOT_HELD
+    const PRIVILEGE_NOT_HELD: i32 = 1314;
+    cfg!(windows)
+        && matches!(err, ApsError::Io { source, .. } if source.raw_os_error() == Some(PRIVILEGE_NOT_HELD))
+}
+
+/// Install a symlink found while copying a directory, per `mode`. Returns
+/// true when the link was handled and its target must not be copied.
+pub fn copy_symlink(src: &Path, dst: &Path, mode: SymlinkMode) -> Result<bool> {
+    match mode {
+        SymlinkMode::Skip => Ok(true),
+        SymlinkMode::Preserve => {
+            copy_preserving_links(src, dst)?;
+            Ok(true)
+        }
+        SymlinkMode::Follow => Ok(!src.exists()),
+    }
+}
+
+/// Copy a file or directory tree, recreating symlinks instead of following them
+fn copy_preserving_links(src: &Path, dst: &Path) -> Result<()> {
+    let meta = src
+        .symlink_metadata()
+        .map_err(|e| ApsError::io(e, format!("Failed to read metadata for {:?}", src)))?;
+    if meta.file_type().is_symlink() {
+        let target = std::fs::read_link(src)
+            .map_err(|e| ApsError::io(e, format!("Failed to read symlink {:?}", src)))?;
+        return create_symlink(&target, dst);
+    }
+    if meta.is_dir() {
+        std::fs::create_dir_all(dst)
+            .map_err(|e| ApsError::io(e, format!("Failed to create directory {:?}", dst)))?;
+        for item in std::fs::read_dir(src)
+            .map_err(|e| ApsError::io(e, format!("Failed to read directory {:?}", src)))?
+        {
+            let item = item.map_err(|e| ApsError::io(e, "Failed to read directory entry"))?;
+            copy_preserving_links(&item.path(), &dst.join(item.file_name()))?;
+        }
+        return Ok(());
+    }
+    std::fs::copy(src, dst)
+        .map_err(|e| ApsError::io(e, format!("Failed to copy {:?} to {:?}", src, dst)))?;
+    Ok(())
+}
+
+/// Install an asset based on its kind. With `replace`, a copied directory
+/// destination is cleared first; otherwise files are copied into it.
 fn install_asset(
-    kind: &AssetKind,
+    entry: &Entry,
     source: &Path,
     dest: &Path,
     use_symlink: bool,
-    include: &[String],
+    replace: bool,
 ) -> Result<Vec<String>> {
+    let (kind, include, skills) = (&entry.kind, &entry.include, &entry.skills);
     // Track symlinked items for lockfile
     let mut symlinked_items = Vec::new();
 
@@ -607,12 +1589,17 @@ fn install_asset(
     }
 
     match kind {
-        AssetKind::AgentsMd => {
+        AssetKind::AgentsMd | AssetKind::File => {
             // Single file
             if use_symlink {
                 create_symlink(source, dest)?;
                 symlinked_items.push(source.to_string_lossy().to_string());
                 debug!("Symlinked file {:?} to {:?}", source, dest);
+            } else if *kind == AssetKind::AgentsMd {
+                let content = std::fs::read_to_string(source)
+                    .map_err(|e| ApsError::io(e, format!("Failed to read {:?}", source)))?;
+                write_managed_file(dest, &content, entry.managed_region)?;
+                debug!("Wrote {:?} to {:?}", source, dest);
             } else {
                 std::fs::copy(source, dest).map_err(|e| {
                     ApsError::io(e, format!("Failed to copy {:?} to {:?}", source, dest))
@@ -620,7 +1607,7 @@ fn install_asset(
                 debug!("Copied file {:?} to {:?}", source, dest);
             }
         }
-        AssetKind::CompositeAgentsMd => {
+        AssetKind::CompositeAgentsMd | AssetKind::McpConfig | AssetKind::ClaudeSettings => {
             // Composite entries are handled by install_composite_entry, not this function
             // This arm exists for exhaustive matching
             return Err(ApsError::ComposeError {
@@ -630,16 +1617,18 @@ fn install_asset(
         AssetKind::CursorRules
         | AssetKind::CursorHooks
         | AssetKind::CursorSkillsRoot
-        | AssetKind::AgentSkill => {
+        | AssetKind::AgentSkill
+        | AssetKind::PromptLibrary
+        | AssetKind::Dir => {
             if use_symlink {
-                if include.is_empty() {
+                if include.is_empty() && skills.is_empty() {
                     // Symlink individual files (not the directory itself)
                     // This allows multiple sources to contribute to the same dest
                     symlink_directory_files(source, dest, &mut symlinked_items)?;
                     debug!("Symlinked directory files from {:?} to {:?}", source, dest);
                 } else {
                     // Filter and symlink individual items
-                    let items = filter_by_prefix(source, include)?;
+                    let items = select_items(source, include, skills)?;
 
                     // Ensure dest directory exists for individual symlinks
                     if !dest.exists() {
@@ -666,7 +1655,7 @@ fn install_asset(
                 }
             } else {
                 // Copy behavior
-                if include.is_empty() {
+                if include.is_empty() && skills.is_empty() {
                     if matches!(kind, AssetKind::CursorHooks) {
                         if dest.exists() {
                             let meta = dest.symlink_metadata().map_err(|e| {
@@ -681,13 +1670,21 @@ fn install_asset(
                         std::fs::create_dir_all(dest).map_err(|e| {
                             ApsError::io(e, format!("Failed to create directory {:?}", dest))
                         })?;
-                        copy_directory_merge(source, dest)?;
+                        copy_directory_merge(source, dest, entry.symlinks)?;
                     } else {
-                        copy_directory(source, dest)?;
+                        if replace && dest.is_dir() {
+                            std::fs::remove_dir_all(dest).map_err(|e| {
+                                ApsError::io(
+                                    e,
+                                    format!("Failed to remove existing directory {:?}", dest),
+                                )
+                            })?;
+                        }
+                        copy_directory(source, dest, entry.symlinks)?;
                     }
                 } else {
                     // Filter and copy individual items
-                    let items = filter_by_prefix(source, include)?;
+                    let items = select_items(source, include, skills)?;
 
                     // Ensure dest exists
                     if matches!(kind, AssetKind::CursorHooks) {
@@ -705,7 +1702,7 @@ fn install_asset(
                             ApsError::io(e, format!("Failed to create directory {:?}", dest))
                         })?;
                     } else {
-                        if dest.exists() {
+                        if replace && dest.exists() {
                             std::fs::remove_dir_all(dest).map_err(|e| {
                                 ApsError::io(
                                     e,
@@ -731,9 +1728,9 @@ fn install_asset(
                         let item_dest = dest.join(item_name);
                         if item.is_dir() {
                             if matches!(kind, AssetKind::CursorHooks) {
-                                copy_directory_merge(&item, &item_dest)?;
+                                copy_directory_merge(&item, &item_dest, entry.symlinks)?;
                             } else {
-                                copy_directory(&item, &item_dest)?;
+                                copy_directory(&item, &item_dest, entry.symlinks)?;
                             }
                         } else {
                             if item_dest.exists() {
@@ -806,6 +1803,45 @@ fn symlink_directory_files(
     Ok(())
 }
 
+/// Items to install from a directory source: the named `skills` (if any),
+/// narrowed by the `include` prefixes (if any)
+fn select_items(source_dir: &Path, include: &[String], skills: &[String]) -> Result<Vec<PathBuf>> {
+    if skills.is_empty() {
+        return filter_by_prefix(source_dir, include);
+    }
+
+    let items = select_skills(source_dir, skills)?;
+    Ok(items
+        .into_iter()
+        .filter(|item| {
+            let name = item
+                .file_name()
+                .map(|n| n.to_string_lossy())
+                .unwrap_or_default();
+            include.is_empty() || include.iter().any(|p| name.starts_with(p.as_str()))
+        })
+        .collect())
+}
+
+/// Resolve skill names to child directories of a skills root, failing if
+/// any requested skill is missing
+fn select_skills(source_dir: &Path, skills: &[String]) -> Result<Vec<PathBuf>> {
+    let missing: Vec<&str> = skills
+        .iter()
+        .filter(|name| !source_dir.join(name).is_dir())
+        .map(String::as_str)
+        .collect();
+
+    if !missing.is_empty() {
+        return Err(ApsError::SkillsNotFound {
+            skills: missing.join(", "),
+            location: source_dir.to_path_buf(),
+        });
+    }
+
+    Ok(skills.iter().map(|name| source_dir.join(name)).collect())
+}
+
 /// Filter directory entries by prefix
 fn filter_by_prefix(source_dir: &Path, prefixes: &[String]) -> Result<Vec<PathBuf>> {
     let mut matches = Vec::new();
@@ -899,12 +1935,16 @@ fn create_symlink(source: &Path, dest: &Path) -> Result<()> {
     }
 
     if source.is_dir() {
-        std::os::windows::fs::symlink_dir(&source, &dest).map_err(|e| {
-            ApsError::io(
-                e,
-                format!("Failed to create symlink {:?} -> {:?}", dest, source),
-            )
-        })?;
+        // Junctions need no special privileges, unlike directory symlinks
+        if let Err(e) = std::os::windows::fs::symlink_dir(&source, &dest) {
+            create_junction(&source, &dest).map_err(|_| {
+                ApsError::io(
+                    e,
+                    format!("Failed to create symlink {:?} -> {:?}", dest, source),
+                )
+            })?;
+            debug!("Created junction {:?} -> {:?}", dest, source);
+        }
     } else {
         std::os::windows::fs::symlink_file(&source, &dest).map_err(|e| {
             ApsError::io(
@@ -917,8 +1957,27 @@ fn create_symlink(source: &Path, dest: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Validate a skills root directory - check each immediate child has SKILL.md
-fn validate_skills_root(source: &Path, strict: bool) -> Result<Vec<String>> {
+/// Link a directory with a junction (`mklink /J`), which works without
+/// Developer Mode. Junction targets must be absolute.
+#[cfg(windows)]
+fn create_junction(source: &Path, dest: &Path) -> std::io::Result<()> {
+    let source = std::path::absolute(source)?;
+    let status = std::process::Command::new("cmd")
+        .args(["/C", "mklink", "/J"])
+        .arg(dest)
+        .arg(&source)
+        .stdout(std::process::Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other("mklink /J failed"))
+    }
+}
+
+/// Validate a skills root directory - check each immediate child (or each
+/// selected skill) has SKILL.md
+fn validate_skills_root(source: &Path, skills: &[String], strict: bool) -> Result<Vec<String>> {
     let mut warnings = Vec::new();
 
     // Read immediate children (each is a skill)
@@ -934,6 +1993,9 @@ fn validate_skills_root(source: &Path, strict: bool) -> Result<Vec<String>> {
         }
 
         let skill_name = entry.file_name().to_string_lossy().to_string();
+        if !skills.is_empty() && !skills.contains(&skill_name) {
+            continue;
+        }
         let skill_md_path = skill_path.join("SKILL.md");
 
         // Check for SKILL.md (case-sensitive)
@@ -951,8 +2013,8 @@ fn validate_skills_root(source: &Path, strict: bool) -> Result<Vec<String>> {
     Ok(warnings)
 }
 
-/// Copy a directory recursively
-fn copy_directory(src: &Path, dst: &Path) -> Result<()> {
+/// Copy a directory recursively, merging into any existing destination
+fn copy_directory(src: &Path, dst: &Path, symlinks: SymlinkMode) -> Result<()> {
     // Normalize paths to handle trailing slashes
     let src = normalize_path(src);
     let dst = normalize_path(dst);
@@ -966,10 +2028,10 @@ fn copy_directory(src: &Path, dst: &Path) -> Result<()> {
         }
     }
 
-    if dst.exists() {
-        std::fs::remove_dir_all(&dst).map_err(|e| {
-            ApsError::io(e, format!("Failed to remove existing directory {:?}", dst))
-        })?;
+    // A file or symlink in the way is replaced rather than written through
+    if dst.symlink_metadata().is_ok_and(|m| !m.is_dir()) {
+        std::fs::remove_file(&dst)
+            .map_err(|e| ApsError::io(e, format!("Failed to remove file {:?}", dst)))?;
     }
 
     std::fs::create_dir_all(&dst)
@@ -982,11 +2044,24 @@ fn copy_directory(src: &Path, dst: &Path) -> Result<()> {
         let src_path = entry.path();
         let dst_path = dst.join(entry.file_name());
 
+        if entry.file_type().is_ok_and(|t| t.is_symlink())
+            && copy_symlink(&src_path, &dst_path, symlinks)?
+        {
+            continue;
+        }
         if src_path.is_dir() {
-            copy_directory(&src_path, &dst_path)?;
+            copy_directory(&src_path, &dst_path, symlinks)?;
         } else {
+            match dst_path.symlink_metadata() {
+                Ok(m) if m.is_symlink() => std::fs::remove_file(&dst_path)
+                    .map_err(|e| ApsError::io(e, format!("Failed to remove {:?}", dst_path)))?,
+                Ok(m) if m.is_dir() => std::fs::remove_dir_all(&dst_path)
+                    .map_err(|e| ApsError::io(e, format!("Failed to remove {:?}", dst_path)))?,
+                _ => {}
+            }
             std::fs::copy(&src_path, &dst_path)
                 .map_err(|e| ApsError::io(e, format!("Failed to copy {:?}", src_path)))?;
+            progress::file_copied();
         }
     }
 
@@ -998,7 +2073,7 @@ fn copy_directory(src: &Path, dst: &Path) -> Result<()> {
 ///
 /// Overwrites destination entries that conflict with source entries while
 /// preserving other destination content.
-fn copy_directory_merge(src: &Path, dst: &Path) -> Result<()> {
+fn copy_directory_merge(src: &Path, dst: &Path, symlinks: SymlinkMode) -> Result<()> {
     // Normalize paths to handle trailing slashes
     let src = normalize_path(src);
     let dst = normalize_path(dst);
@@ -1008,13 +2083,18 @@ fn copy_directory_merge(src: &Path, dst: &Path) -> Result<()> {
             .map_err(|e| ApsError::io(e, format!("Failed to create directory {:?}", dst)))?;
     }
 
-    for entry in WalkDir::new(&src).follow_links(true) {
-        let entry = entry.map_err(|e| {
-            ApsError::io(
-                std::io::Error::other(e),
-                "Failed to traverse source directory",
-            )
-        })?;
+    let walker = WalkDir::new(&src).follow_links(symlinks == SymlinkMode::Follow);
+    for entry in walker {
+        let entry = match entry {
+            // A followed link that doesn't resolve has no content to copy
+            Err(e) if e.path().is_some_and(|p| p.is_symlink() && !p.exists()) => continue,
+            entry => entry.map_err(|e| {
+                ApsError::io(
+                    std::io::Error::other(e),
+                    "Failed to traverse source directory",
+                )
+            })?,
+        };
         let path = entry.path();
         let rel = path.strip_prefix(&src).map_err(|e| {
             ApsError::io(
@@ -1027,6 +2107,9 @@ fn copy_directory_merge(src: &Path, dst: &Path) -> Result<()> {
         }
         let dest_path = dst.join(rel);
 
+        if entry.path_is_symlink() && copy_symlink(path, &dest_path, symlinks)? {
+            continue;
+        }
         if entry.file_type().is_dir() {
             if dest_path.exists() {
                 let meta = dest_path.symlink_metadata().map_err(|e| {
@@ -1065,6 +2148,7 @@ fn copy_directory_merge(src: &Path, dst: &Path) -> Result<()> {
             }
             std::fs::copy(path, &dest_path)
                 .map_err(|e| ApsError::io(e, format!("Failed to copy {:?}", path)))?;
+            progress::file_copied();
         }
     }
 
@@ -1072,6 +2156,96 @@ fn copy_directory_merge(src: &Path, dst: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Copy hook scripts into the settings directory and make shell scripts executable.
+fn install_hook_scripts(scripts: &[HookScript], settings_dir: &Path) -> Result<()> {
+    for script in scripts {
+        let target = settings_dir.join(&script.path);
+        let parent = target.parent().unwrap_or(settings_dir);
+        std::fs::create_dir_all(parent)
+            .map_err(|e| ApsError::io(e, format!("Failed to create directory: {:?}", parent)))?;
+        std::fs::copy(&script.source, &target)
+            .map_err(|e| ApsError::io(e, format!("Failed to copy {:?}", script.source)))?;
+        make_shell_scripts_executable(parent)?;
+        debug!("Installed hook script {:?}", target);
+    }
+    Ok(())
+}
+
+/// Set the permission bits of every regular file at `path` (a file or
+/// directory). Symlinks are left alone.
+fn apply_mode(path: &Path, mode: FileMode) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            std::fs::set_permissions(entry.path(), std::fs::Permissions::from_mode(mode.0))
+                .map_err(|e| {
+                    ApsError::io(
+                        e,
+                        format!("Failed to set permissions for {:?}", entry.path()),
+                    )
+                })?;
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        let _ = (path, mode);
+    }
+
+    Ok(())
+}
+
+/// Whether the previous install of an entry marked its files read-only
+fn previous_readonly(lockfile: &Lockfile, id: &str) -> bool {
+    lockfile.entries.get(id).is_some_and(|l| l.readonly)
+}
+
+/// Mark every regular file at `path` (a file or directory) read-only, or
+/// make it writable by its owner again. Symlinks are left alone.
+fn set_readonly(path: &Path, readonly: bool) -> Result<()> {
+    for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let mut permissions = entry
+            .metadata()
+            .map_err(|e| {
+                ApsError::io(
+                    std::io::Error::other(e),
+                    format!("Failed to read metadata for {:?}", entry.path()),
+                )
+            })?
+            .permissions();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = permissions.mode();
+            permissions.set_mode(if readonly {
+                mode & !0o222
+            } else {
+                mode | 0o200
+            });
+        }
+        #[cfg(windows)]
+        {
+            // Windows has a single read-only attribute rather than mode bits
+            #[allow(clippy::permissions_set_readonly_false)]
+            permissions.set_readonly(readonly);
+        }
+        std::fs::set_permissions(entry.path(), permissions).map_err(|e| {
+            ApsError::io(
+                e,
+                format!("Failed to set permissions for {:?}", entry.path()),
+            )
+        })?;
+    }
+    Ok(())
+}
+
 /// Make all .sh scripts under a directory executable (recursive).
 fn make_shell_scripts_executable(dir: &Path) -> Result<()> {
     if !dir.exists() {
@@ -0,0 +1,86 @@
+//! Relative links in markdown.
+//!
+//! Finds the targets of inline links and images (`[text](target)`) that
+//! point at other files rather than URLs or anchors, so validation can
+//! check that they exist. Links inside code spans and fenced code blocks are
+//! ignored.
+
+/// Relative link targets in `markdown`, without `#fragment` or `?query`,
+/// in document order
+pub fn relative_links(markdown: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    let mut in_fence = false;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut rest = line;
+        while let Some(position) = rest.find(['`', ']']) {
+            let marker = rest.as_bytes()[position];
+            rest = &rest[position + 1..];
+            if marker == b'`' {
+                in_code = !in_code;
+                continue;
+            }
+            if in_code || !rest.starts_with('(') {
+                continue;
+            }
+            let Some(end) = rest.find(')') else {
+                break;
+            };
+            if let Some(target) = relative_target(&rest[1..end]) {
+                links.push(target);
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+    links
+}
+
+/// The file a link target refers to, or `None` for URLs and anchors
+fn relative_target(target: &str) -> Option<String> {
+    // `[text](path "title")` and `[text](<path with spaces>)`
+    let target = target.split_whitespace().next()?;
+    let target = target.trim_start_matches('<').trim_end_matches('>');
+    if target.starts_with('#') || target.starts_with('/') || target.contains("://") {
+        return None;
+    }
+    if let Some((scheme, _)) = target.split_once(':') {
+        if scheme.chars().all(|c| c.is_ascii_alphabetic()) {
+            // mailto:, tel:, data: and the like
+            return None;
+        }
+    }
+    let path = target.split(['#', '?']).next().unwrap_or_default();
+    (!path.is_empty()).then(|| path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_links() {
+        let markdown = "\
+See [the guide](docs/guide.md#setup) and ![diagram](img/flow.png \"Flow\").
+Ignore [site](https://example.com), [anchor](#usage), [mail](mailto:a@b.c) and `[code](x.md)`.
+
+```
+[fenced](fenced.md)
+```
+[last](../shared/README.md)
+";
+        assert_eq!(
+            relative_links(markdown),
+            vec!["docs/guide.md", "img/flow.png", "../shared/README.md"]
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Resolving merge conflicts in the lockfile.
+//!
+//! When two branches both re-lock entries, git leaves conflict markers in the
+//! lockfile. Each side of the conflict is read as a complete lockfile; entries
+//! that differ are resolved to the side that was refreshed from upstream most
+//! recently, falling back to the newer locked commit.
+
+use crate::error::{ApsError, Result};
+use crate::lockfile::{LockedEntry, Lockfile};
+use chrono::{DateTime, Utc};
+
+/// One side of a conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+/// Both sides of a conflicted lockfile
+pub struct ConflictSides {
+    pub ours: Lockfile,
+    pub theirs: Lockfile,
+    /// Labels git wrote after the markers (e.g. `HEAD`, a branch name)
+    pub ours_label: String,
+    pub theirs_label: String,
+}
+
+impl ConflictSides {
+    pub fn label(&self, side: Side) -> &str {
+        match side {
+            Side::Ours => &self.ours_label,
+            Side::Theirs => &self.theirs_label,
+        }
+    }
+}
+
+/// How a differing entry was resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolution {
+    pub id: String,
+    pub side: Side,
+    /// Whether the kept side was the more recently refreshed one
+    pub newer: bool,
+}
+
+/// Split conflicted lockfile content into its two sides. Returns `None` when
+/// the content has no conflict markers.
+pub fn split_conflict(content: &str) -> Result<Option<ConflictSides>> {
+    enum Section {
+        Both,
+        Ours,
+        Base,
+        Theirs,
+    }
+
+    let malformed = |line: usize| ApsError::LockfileReadError {
+        message: format!("unbalanced conflict marker on line {}", line),
+    };
+    let mut ours = String::new();
+    let mut theirs = String::new();
+    let mut labels: (Option<String>, Option<String>) = (None, None);
+    let mut section = Section::Both;
+    let mut found = false;
+
+    for (number, line) in content.lines().enumerate() {
+        let number = number + 1;
+        if let Some(label) = line.strip_prefix("<<<<<<<") {
+            if !matches!(section, Section::Both) {
+                return Err(malformed(number));
+            }
+            labels.0.get_or_insert_with(|| label.trim().to_string());
+            section = Section::Ours;
+            found = true;
+            continue;
+        }
+        if line.starts_with("|||||||") {
+            if !matches!(section, Section::Ours) {
+                return Err(malformed(number));
+            }
+            section = Section::Base;
+            continue;
+        }
+        if line == "=======" {
+            if !matches!(section, Section::Ours | Section::Base) {
+                return Err(malformed(number));
+            }
+            section = Section::Theirs;
+            continue;
+        }
+        if let Some(label) = line.strip_prefix(">>>>>>>") {
+            if !matches!(section, Section::Theirs) {
+                return Err(malformed(number));
+            }
+            labels.1.get_or_insert_with(|| label.trim().to_string());
+            section = Section::Both;
+            continue;
+        }
+
+        match section {
+            Section::Both => {
+                ours.push_str(line);
+                ours.push('\n');
+                theirs.push_str(line);
+                theirs.push('\n');
+            }
+            Section::Ours => {
+                ours.push_str(line);
+                ours.push('\n');
+            }
+            Section::Theirs => {
+                theirs.push_str(line);
+                theirs.push('\n');
+            }
+            Section::Base => {}
+        }
+    }
+
+    if !matches!(section, Section::Both) {
+        return Err(malformed(content.lines().count()));
+    }
+    if !found {
+        return Ok(None);
+    }
+
+    let label = |label: Option<String>, fallback: &str| {
+        label
+            .filter(|l| !l.is_empty())
+            .unwrap_or_else(|| fallback.to_string())
+    };
+    Ok(Some(ConflictSides {
+        ours: Lockfile::parse(&ours)?,
+        theirs: Lockfile::parse(&theirs)?,
+        ours_label: label(labels.0, "ours"),
+        theirs_label: label(labels.1, "theirs"),
+    }))
+}
+
+fn parse_time(value: Option<&str>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value?)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// The side whose entry is newer: refreshed from upstream more recently, or
+/// failing that, locked to a newer commit. `None` when neither is newer.
+pub fn newer_side(ours: &LockedEntry, theirs: &LockedEntry) -> Option<Side> {
+    let refreshed = |e: &LockedEntry| parse_time(e.refreshed_at.as_deref());
+    let committed = |e: &LockedEntry| parse_time(e.commit_info.as_ref().map(|c| c.date.as_str()));
+    let pairs = [
+        (refreshed(ours), refreshed(theirs)),
+        (committed(ours), committed(theirs)),
+    ];
+    for pair in pairs {
+        if let (Some(a), Some(b)) = pair {
+            if a != b {
+                return Some(if a > b { Side::Ours } else { Side::Theirs });
+            }
+        }
+    }
+    None
+}
+
+/// Merge both sides into one lockfile. Entries present on only one side are
+/// kept; `choose` picks the side for entries that differ, given the newer one.
+pub fn resolve_conflict(
+    sides: &ConflictSides,
+    mut choose: impl FnMut(&str, Option<Side>) -> Result<Side>,
+) -> Result<(Lockfile, Vec<Resolution>)> {
+    let mut merged = sides.ours.clone();
+    merged.version = merged.version.max(sides.theirs.version);
+    let mut resolutions = Vec::new();
+
+    for (id, theirs) in &sides.theirs.entries {
+        let Some(ours) = sides.ours.entries.get(id) else {
+            merged.upsert(id.clone(), theirs.clone());
+            continue;
+        };
+        if ours == theirs {
+            continue;
+        }
+        let newer = newer_side(ours, theirs);
+        let side = choose(id, newer)?;
+        if side == Side::Theirs {
+            merged.upsert(id.clone(), theirs.clone());
+        }
+        resolutions.push(Resolution {
+            id: id.clone(),
+            side,
+            newer: newer == Some(side),
+        });
+    }
+
+    Ok((merged, resolutions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFLICTED: &str = r#"version: 1
+aps_version: 0.1.12
+entries:
+  agents:
+    source: https://example.com/repo.git:AGENTS.md
+    dest: ./AGENTS.md
+<<<<<<< HEAD
+    commit: aaa
+    checksum: sha256:aaa
+    refreshed_at: 2026-03-01T00:00:00Z
+=======
+    commit: bbb
+    checksum: sha256:bbb
+    refreshed_at: 2026-04-01T00:00:00Z
+>>>>>>> feature
+  rules:
+    source: ./rules
+    dest: ./.cursor/rules
+    checksum: sha256:rules
+"#;
+
+    #[test]
+    fn test_split_conflict() {
+        assert!(split_conflict("version: 1\nentries: {}\n")
+            .unwrap()
+            .is_none());
+
+        let sides = split_conflict(CONFLICTED).unwrap().unwrap();
+        assert_eq!(sides.ours_label, "HEAD");
+        assert_eq!(sides.theirs_label, "feature");
+        assert_eq!(sides.ours.entries["agents"].commit.as_deref(), Some("aaa"));
+        assert_eq!(
+            sides.theirs.entries["agents"].commit.as_deref(),
+            Some("bbb")
+        );
+        assert_eq!(sides.theirs.entries.len(), 2);
+
+        assert!(split_conflict("<<<<<<< HEAD\nversion: 1\n").is_err());
+    }
+
+    #[test]
+    fn test_resolve_conflict_keeps_newer_entry() {
+        let sides = split_conflict(CONFLICTED).unwrap().unwrap();
+        let (merged, resolutions) =
+            resolve_conflict(&sides, |_, newer| Ok(newer.unwrap_or(Side::Ours))).unwrap();
+
+        assert_eq!(merged.entries["agents"].commit.as_deref(), Some("bbb"));
+        assert!(merged.entries.contains_key("rules"));
+        assert_eq!(
+            resolutions,
+            vec![Resolution {
+                id: "agents".to_string(),
+                side: Side::Theirs,
+                newer: true,
+            }]
+        );
+    }
+}
@@ -1,6 +1,9 @@
+use crate::claude_settings::ManagedKey;
 use crate::error::{ApsError, Result};
+use crate::verify::lock_key;
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::path::{Path, PathBuf};
 use tracing::{debug, info};
@@ -8,9 +11,38 @@ use tracing::{debug, info};
 /// Default lockfile filename
 pub const LOCKFILE_NAME: &str = "aps.lock.yaml";
 
+/// Lockfile filename when `lockfile_format: json` is set
+pub const JSON_LOCKFILE_NAME: &str = "aps.lock.json";
+
 /// Legacy lockfile filename (for backward compatibility)
 const LEGACY_LOCKFILE_NAME: &str = "aps.manifest.lock";
 
+/// On-disk format of the lockfile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockfileFormat {
+    Yaml,
+    Json,
+}
+
+impl LockfileFormat {
+    /// Lockfile filename for this format
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            LockfileFormat::Yaml => LOCKFILE_NAME,
+            LockfileFormat::Json => JSON_LOCKFILE_NAME,
+        }
+    }
+
+    /// Format implied by a lockfile path's extension
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => LockfileFormat::Json,
+            _ => LockfileFormat::Yaml,
+        }
+    }
+}
+
 /// Source types for locked entries - supports both simple strings and composite structures
 #[derive(Debug, Clone, PartialEq)]
 pub enum LockedSource {
@@ -18,6 +50,8 @@ pub enum LockedSource {
     Simple(String),
     /// Composite source (multiple files merged into one)
     Composite(Vec<String>),
+    /// Source written by a newer aps, kept verbatim so it survives a rewrite
+    Other(serde_yaml::Value),
 }
 
 impl LockedSource {
@@ -32,7 +66,6 @@ impl LockedSource {
     }
 
     /// Check if this is a composite source
-    #[allow(dead_code)]
     pub fn is_composite(&self) -> bool {
         matches!(self, LockedSource::Composite(_))
     }
@@ -52,6 +85,7 @@ impl fmt::Display for LockedSource {
                 }
                 write!(f, "]")
             }
+            LockedSource::Other(_) => write!(f, "(unsupported source)"),
         }
     }
 }
@@ -69,6 +103,7 @@ impl Serialize for LockedSource {
                 map.serialize_entry("composite", sources)?;
                 map.end()
             }
+            LockedSource::Other(value) => value.serialize(serializer),
         }
     }
 }
@@ -129,20 +164,20 @@ impl<'de> Deserialize<'de> for LockedSource {
             where
                 M: MapAccess<'de>,
             {
-                let mut composite: Option<Vec<String>> = None;
-
-                while let Some(key) = map.next_key::<String>()? {
-                    if key == "composite" {
-                        composite = Some(map.next_value()?);
-                    } else {
-                        // Skip unknown keys
-                        let _: serde::de::IgnoredAny = map.next_value()?;
-                    }
+                let mut fields = serde_yaml::Mapping::new();
+                while let Some((key, value)) =
+                    map.next_entry::<serde_yaml::Value, serde_yaml::Value>()?
+                {
+                    fields.insert(key, value);
                 }
 
-                match composite {
-                    Some(sources) => Ok(LockedSource::Composite(sources)),
-                    None => Err(serde::de::Error::missing_field("composite")),
+                // Any other map form comes from a newer aps; keep it rather than
+                // failing to load the whole lockfile
+                match fields.remove("composite") {
+                    Some(sources) => serde_yaml::from_value(sources)
+                        .map(LockedSource::Composite)
+                        .map_err(serde::de::Error::custom),
+                    None => Ok(LockedSource::Other(serde_yaml::Value::Mapping(fields))),
                 }
             }
         }
@@ -162,17 +197,97 @@ pub struct Lockfile {
     #[serde(default)]
     pub aps_version: String,
 
-    /// Locked entries by ID
+    /// Hash of the manifest as of the last full sync
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest_hash: Option<String>,
+
+    /// Locked entries by ID, kept sorted so saves are stable
     #[serde(default)]
-    pub entries: HashMap<String, LockedEntry>,
+    pub entries: BTreeMap<String, LockedEntry>,
 }
 
 fn default_version() -> u32 {
     1
 }
 
+/// Metadata of a locked git commit
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct CommitInfo {
+    /// Committer date (RFC 3339)
+    pub date: String,
+    pub author: String,
+    /// First line of the commit message
+    pub subject: String,
+}
+
+impl fmt::Display for CommitInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The day of an ISO 8601 date; anything else is shown as is
+        let day = self.date.get(..10).unwrap_or(&self.date);
+        write!(f, "{}: {}", day, self.subject)
+    }
+}
+
+/// A source of a composite entry, locked individually
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LockedFragment {
+    /// Source as listed under the entry's `composite` sources
+    pub source: String,
+    /// Git commit SHA (git sources only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Checksum of the source file
+    pub checksum: String,
+}
+
+/// Why content was backed up before an install replaced it
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupReason {
+    /// Content aps didn't install was in the way
+    Conflict,
+    /// The managed region had local edits (synced without `--merge`)
+    LocalEdits,
+}
+
+impl fmt::Display for BackupReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackupReason::Conflict => write!(f, "overwrote existing content"),
+            BackupReason::LocalEdits => write!(f, "overwrote local edits"),
+        }
+    }
+}
+
+/// Backups made the last time installing an entry overwrote content
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LockedBackup {
+    /// Backup paths, relative to the manifest directory
+    pub paths: Vec<String>,
+    pub reason: BackupReason,
+}
+
+impl LockedBackup {
+    /// Record `backups` made under `manifest_dir`, if there are any
+    pub fn new(manifest_dir: &Path, backups: &[PathBuf], reason: BackupReason) -> Option<Self> {
+        if backups.is_empty() {
+            return None;
+        }
+        let paths = backups
+            .iter()
+            .map(|path| {
+                path.strip_prefix(manifest_dir)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        Some(Self { paths, reason })
+    }
+}
+
 /// A locked entry with installation metadata
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct LockedEntry {
     /// Source description (simple string or composite structure)
     pub source: LockedSource,
@@ -188,6 +303,10 @@ pub struct LockedEntry {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub commit: Option<String>,
 
+    /// Date, author and subject of the locked commit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_info: Option<CommitInfo>,
+
     /// Content checksum
     pub checksum: String,
 
@@ -195,6 +314,15 @@ pub struct LockedEntry {
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub is_symlink: bool,
 
+    /// Installed as a copy because this system couldn't create the
+    /// symlinks the manifest asks for (Windows without Developer Mode)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub symlink_fallback: bool,
+
+    /// Installed files were marked read-only (`readonly: true`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub readonly: bool,
+
     /// Target path for symlinks (the source the symlink points to)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub target_path: Option<String>,
@@ -202,6 +330,42 @@ pub struct LockedEntry {
     /// List of symlinked items (for filtered symlinks)
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub symlinked_items: Vec<String>,
+
+    /// Checksum of the generated legacy `.cursorrules` file, if exported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub legacy_cursorrules_checksum: Option<String>,
+
+    /// When the content was last resolved from upstream (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refreshed_at: Option<String>,
+
+    /// When the content was last written to the destination (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed_at: Option<String>,
+
+    /// Hash of the manifest entry as of the last sync, to find entries
+    /// edited since
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition_hash: Option<String>,
+
+    /// Per-source commits and checksums (composite entries only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fragments: Vec<LockedFragment>,
+
+    /// Settings keys and list items written by a `claude_settings` entry,
+    /// so the ones dropped from its fragments are removed on the next sync
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub managed_keys: Vec<ManagedKey>,
+
+    /// Checksums of the files written, keyed by path relative to the manifest
+    /// directory (copied installs only)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub files: BTreeMap<String, String>,
+
+    /// Where content was backed up the last time installing this entry
+    /// overwrote it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup: Option<LockedBackup>,
 }
 
 impl LockedEntry {
@@ -219,10 +383,21 @@ impl LockedEntry {
             dest: dest.to_string(),
             resolved_ref: None,
             commit: None,
+            commit_info: None,
             checksum,
             is_symlink,
+            symlink_fallback: false,
+            readonly: false,
             target_path,
             symlinked_items,
+            legacy_cursorrules_checksum: None,
+            refreshed_at: None,
+            installed_at: None,
+            definition_hash: None,
+            fragments: Vec::new(),
+            managed_keys: Vec::new(),
+            files: BTreeMap::new(),
+            backup: None,
         }
     }
 
@@ -240,10 +415,21 @@ impl LockedEntry {
             dest: dest.to_string(),
             resolved_ref: Some(resolved_ref),
             commit: Some(commit),
+            commit_info: None,
             checksum,
             is_symlink: false,
+            symlink_fallback: false,
+            readonly: false,
             target_path: None,
             symlinked_items: Vec::new(),
+            legacy_cursorrules_checksum: None,
+            refreshed_at: None,
+            installed_at: None,
+            definition_hash: None,
+            fragments: Vec::new(),
+            managed_keys: Vec::new(),
+            files: BTreeMap::new(),
+            backup: None,
         }
     }
 
@@ -254,10 +440,21 @@ impl LockedEntry {
             dest: dest.to_string(),
             resolved_ref: None,
             commit: None,
+            commit_info: None,
             checksum,
             is_symlink: false,
+            symlink_fallback: false,
+            readonly: false,
             target_path: None,
             symlinked_items: Vec::new(),
+            legacy_cursorrules_checksum: None,
+            refreshed_at: None,
+            installed_at: None,
+            definition_hash: None,
+            fragments: Vec::new(),
+            managed_keys: Vec::new(),
+            files: BTreeMap::new(),
+            backup: None,
         }
     }
 }
@@ -268,16 +465,34 @@ impl Lockfile {
         Self {
             version: default_version(),
             aps_version: env!("CARGO_PKG_VERSION").to_string(),
-            entries: HashMap::new(),
+            manifest_hash: None,
+            entries: BTreeMap::new(),
         }
     }
 
     /// Get the lockfile path relative to the manifest
-    pub fn path_for_manifest(manifest_path: &Path) -> PathBuf {
-        manifest_path
-            .parent()
-            .map(|p| p.join(LOCKFILE_NAME))
-            .unwrap_or_else(|| PathBuf::from(LOCKFILE_NAME))
+    ///
+    /// Without a configured format, an existing `aps.lock.json` is used when
+    /// there is no `aps.lock.yaml`.
+    pub fn path_for_manifest(manifest_path: &Path, format: Option<LockfileFormat>) -> PathBuf {
+        let dir = manifest_path.parent().unwrap_or(Path::new(""));
+        let format = format.unwrap_or_else(|| {
+            if !dir.join(LOCKFILE_NAME).exists() && dir.join(JSON_LOCKFILE_NAME).exists() {
+                LockfileFormat::Json
+            } else {
+                LockfileFormat::Yaml
+            }
+        });
+        dir.join(format.file_name())
+    }
+
+    /// The lockfile in the other format next to `path`
+    fn sibling_path(path: &Path) -> PathBuf {
+        let other = match LockfileFormat::from_path(path) {
+            LockfileFormat::Yaml => LockfileFormat::Json,
+            LockfileFormat::Json => LockfileFormat::Yaml,
+        };
+        path.with_file_name(other.file_name())
     }
 
     /// Load a lockfile from disk
@@ -289,15 +504,26 @@ impl Lockfile {
             let content = std::fs::read_to_string(path)
                 .map_err(|e| ApsError::io(e, format!("Failed to read lockfile at {:?}", path)))?;
 
-            let lockfile: Lockfile =
-                serde_yaml::from_str(&content).map_err(|e| ApsError::LockfileReadError {
-                    message: e.to_string(),
-                })?;
+            let lockfile = Self::parse(&content)?;
 
             debug!("Loaded lockfile with {} entries", lockfile.entries.len());
             return Ok(lockfile);
         }
 
+        // After switching `lockfile_format`, read the existing lockfile until
+        // the next save converts it
+        let sibling_path = Self::sibling_path(path);
+        if sibling_path.exists() {
+            info!(
+                "Loading {:?} (will be converted to {:?} on next save)",
+                sibling_path, path
+            );
+            let content = std::fs::read_to_string(&sibling_path).map_err(|e| {
+                ApsError::io(e, format!("Failed to read lockfile at {:?}", sibling_path))
+            })?;
+            return Self::parse(&content);
+        }
+
         // Fall back to legacy filename for backward compatibility
         let legacy_path = path
             .parent()
@@ -314,10 +540,7 @@ impl Lockfile {
                 ApsError::io(e, format!("Failed to read lockfile at {:?}", legacy_path))
             })?;
 
-            let lockfile: Lockfile =
-                serde_yaml::from_str(&content).map_err(|e| ApsError::LockfileReadError {
-                    message: e.to_string(),
-                })?;
+            let lockfile = Self::parse(&content)?;
 
             debug!(
                 "Loaded legacy lockfile with {} entries",
@@ -329,13 +552,32 @@ impl Lockfile {
         Err(ApsError::LockfileNotFound)
     }
 
-    /// Save the lockfile to disk
+    /// Parse lockfile content, detecting JSON or YAML
+    pub fn parse(content: &str) -> Result<Self> {
+        if content.trim_start().starts_with('{') {
+            return serde_json::from_str(content).map_err(|e| ApsError::LockfileReadError {
+                message: e.to_string(),
+            });
+        }
+        serde_yaml::from_str(content).map_err(|e| ApsError::LockfileReadError {
+            message: e.to_string(),
+        })
+    }
+
+    /// Save the lockfile to disk, as JSON when `path` ends in `.json`
     ///
     /// Automatically migrates from legacy filename if it exists.
     /// Always stamps the current aps version before writing.
     pub fn save(&mut self, path: &Path) -> Result<()> {
         self.aps_version = env!("CARGO_PKG_VERSION").to_string();
-        let content = serde_yaml::to_string(self).map_err(|e| ApsError::LockfileReadError {
+        self.normalize();
+        let content = match LockfileFormat::from_path(path) {
+            LockfileFormat::Json => serde_json::to_string_pretty(self)
+                .map(|json| json + "\n")
+                .map_err(|e| e.to_string()),
+            LockfileFormat::Yaml => serde_yaml::to_string(self).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| ApsError::LockfileReadError {
             message: format!("Failed to serialize lockfile: {}", e),
         })?;
 
@@ -344,6 +586,15 @@ impl Lockfile {
 
         info!("Saved lockfile to {:?}", path);
 
+        // A lockfile left over in the other format would shadow this one
+        let sibling_path = Self::sibling_path(path);
+        if sibling_path.exists() {
+            match std::fs::remove_file(&sibling_path) {
+                Ok(_) => info!("Converted lockfile: removed {:?}", sibling_path),
+                Err(e) => debug!("Could not remove {:?}: {}", sibling_path, e),
+            }
+        }
+
         // Automatic migration: Remove legacy lockfile if it exists
         let legacy_path = path
             .parent()
@@ -370,6 +621,28 @@ impl Lockfile {
         Ok(())
     }
 
+    /// Put lists and timestamps in canonical form, so re-saving an unchanged
+    /// lockfile produces identical output
+    fn normalize(&mut self) {
+        for entry in self.entries.values_mut() {
+            if let LockedSource::Composite(ref mut sources) = entry.source {
+                sources.sort();
+            }
+            entry.fragments.sort_by(|a, b| a.source.cmp(&b.source));
+            entry.symlinked_items.sort();
+            for timestamp in [&mut entry.refreshed_at, &mut entry.installed_at]
+                .into_iter()
+                .flatten()
+            {
+                if let Ok(at) = DateTime::parse_from_rfc3339(timestamp) {
+                    *timestamp = at
+                        .with_timezone(&Utc)
+                        .to_rfc3339_opts(SecondsFormat::Secs, true);
+                }
+            }
+        }
+    }
+
     /// Update or insert an entry
     pub fn upsert(&mut self, id: String, entry: LockedEntry) {
         self.entries.insert(id, entry);
@@ -392,6 +665,36 @@ impl Lockfile {
             .unwrap_or(false)
     }
 
+    /// Entries whose destination contains `path` (relative to the manifest
+    /// directory), innermost destination first
+    pub fn owners_of(&self, path: &str) -> Vec<(&str, &LockedEntry, Ownership)> {
+        let mut owners: Vec<(&str, &LockedEntry, Ownership)> = self
+            .entries
+            .iter()
+            .filter_map(|(id, entry)| {
+                let dest = lock_key(Path::new(&entry.dest));
+                let inside = path == dest
+                    || path
+                        .strip_prefix(dest.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'));
+                if !inside {
+                    return None;
+                }
+                let ownership = if entry.files.contains_key(path) {
+                    Ownership::File
+                } else if entry.is_symlink || entry.files.is_empty() || path == dest {
+                    Ownership::Dest
+                } else {
+                    Ownership::Unrecorded
+                };
+                Some((id.as_str(), entry, ownership))
+            })
+            .collect();
+        owners
+            .sort_by_key(|(_, entry, _)| std::cmp::Reverse(lock_key(Path::new(&entry.dest)).len()));
+        owners
+    }
+
     /// Retain only entries with IDs in the given set, removing stale entries.
     /// Returns the list of IDs that were removed.
     pub fn retain_entries(&mut self, ids_to_keep: &[&str]) -> Vec<String> {
@@ -412,6 +715,88 @@ impl Lockfile {
     }
 }
 
+/// How an entry accounts for a path inside its destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ownership {
+    /// Installed by the entry and recorded in its file list
+    File,
+    /// The destination itself, or inside a symlinked destination
+    Dest,
+    /// Inside a copied destination but not installed by the entry
+    Unrecorded,
+}
+
+/// How a locked entry differs between two lockfiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A locked field whose value changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// An entry that was added, removed or changed between two lockfiles
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockChange {
+    pub id: String,
+    pub kind: LockChangeKind,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+}
+
+/// The fields compared by `diff_lockfiles`
+fn diff_fields(entry: &LockedEntry) -> [(&'static str, Option<String>); 5] {
+    [
+        ("source", Some(entry.source.to_string())),
+        ("dest", Some(entry.dest.clone())),
+        ("ref", entry.resolved_ref.clone()),
+        ("commit", entry.commit.clone()),
+        ("checksum", Some(entry.checksum.clone())),
+    ]
+}
+
+/// Compare two lockfiles entry by entry, sorted by ID
+pub fn diff_lockfiles(old: &Lockfile, new: &Lockfile) -> Vec<LockChange> {
+    let mut ids: Vec<&String> = old.entries.keys().chain(new.entries.keys()).collect();
+    ids.sort();
+    ids.dedup();
+
+    ids.into_iter()
+        .filter_map(|id| {
+            let (kind, fields) = match (old.entries.get(id), new.entries.get(id)) {
+                (None, Some(_)) => (LockChangeKind::Added, Vec::new()),
+                (Some(_), None) => (LockChangeKind::Removed, Vec::new()),
+                (Some(before), Some(after)) => {
+                    let fields: Vec<FieldChange> = diff_fields(before)
+                        .into_iter()
+                        .zip(diff_fields(after))
+                        .filter(|((_, old), (_, new))| old != new)
+                        .map(|((field, old), (_, new))| FieldChange { field, old, new })
+                        .collect();
+                    if fields.is_empty() {
+                        return None;
+                    }
+                    (LockChangeKind::Changed, fields)
+                }
+                (None, None) => return None,
+            };
+            Some(LockChange {
+                id: id.clone(),
+                kind,
+                fields,
+            })
+        })
+        .collect()
+}
+
 /// Display status information from the lockfile
 pub fn display_status(lockfile: &Lockfile) {
     if !lockfile.aps_version.is_empty() {
@@ -433,16 +818,43 @@ pub fn display_status(lockfile: &Lockfile) {
             LockedSource::Composite(sources) => {
                 println!("Source:       composite");
                 for s in sources {
-                    println!("              - {}", s);
+                    let commit = entry
+                        .fragments
+                        .iter()
+                        .find(|f| &f.source == s)
+                        .and_then(|f| f.commit.as_deref());
+                    match commit {
+                        Some(sha) => {
+                            println!("              - {} @ {}", s, &sha[..8.min(sha.len())])
+                        }
+                        None => println!("              - {}", s),
+                    }
                 }
             }
+            LockedSource::Other(_) => println!("Source:       {}", entry.source),
         }
         println!("Destination:  {}", entry.dest);
         if let Some(ref resolved_ref) = entry.resolved_ref {
             println!("Ref:          {}", resolved_ref);
         }
         if let Some(ref commit) = entry.commit {
-            println!("Commit:       {}", commit);
+            match entry.commit_info {
+                Some(ref info) => println!("Commit:       {} ({})", commit, info),
+                None => println!("Commit:       {}", commit),
+            }
+        }
+        if entry.symlink_fallback {
+            println!("Type:         copy (symlinks unavailable)");
+        }
+        if entry.readonly {
+            println!("Read-only:    yes");
+        }
+        if let Some(ref backup) = entry.backup {
+            println!(
+                "Last backup:  {} ({})",
+                backup.paths.join(", "),
+                backup.reason
+            );
         }
         if entry.is_symlink {
             println!("Type:         symlink");
@@ -454,6 +866,12 @@ pub fn display_status(lockfile: &Lockfile) {
             }
         }
         println!("Checksum:     {}", entry.checksum);
+        if let Some(ref installed_at) = entry.installed_at {
+            println!("Installed:    {}", installed_at);
+        }
+        if let Some(ref refreshed_at) = entry.refreshed_at {
+            println!("Refreshed:    {}", refreshed_at);
+        }
         println!("{}", "-".repeat(80));
     }
 }
@@ -462,6 +880,22 @@ pub fn display_status(lockfile: &Lockfile) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_commit_info_display() {
+        let info = |date: &str| CommitInfo {
+            date: date.to_string(),
+            author: "Ada".to_string(),
+            subject: "Add rules".to_string(),
+        };
+        assert_eq!(
+            info("2024-05-01T12:00:00+02:00").to_string(),
+            "2024-05-01: Add rules"
+        );
+        assert_eq!(info("yesterday").to_string(), "yesterday: Add rules");
+        // A multi-byte character straddling byte 10 doesn't panic
+        assert_eq!(info("2024-05-0é").to_string(), "2024-05-0é: Add rules");
+    }
+
     #[test]
     fn test_retain_entries_removes_stale() {
         let mut lockfile = Lockfile::new();
@@ -568,4 +1002,163 @@ mod tests {
         assert!(removed.is_empty());
         assert_eq!(lockfile.entries.len(), 2);
     }
+
+    #[test]
+    fn test_diff_lockfiles() {
+        let git = |commit: &str, checksum: &str| {
+            LockedEntry::new_git(
+                "https://github.com/acme/prompts.git",
+                "./AGENTS.md",
+                "main".to_string(),
+                commit.to_string(),
+                checksum.to_string(),
+            )
+        };
+        let mut old = Lockfile::new();
+        old.upsert("same".to_string(), git("aaa", "sha256:1"));
+        old.upsert("bumped".to_string(), git("aaa", "sha256:1"));
+        old.upsert("gone".to_string(), git("aaa", "sha256:1"));
+        let mut new = Lockfile::new();
+        new.upsert("same".to_string(), git("aaa", "sha256:1"));
+        new.upsert("bumped".to_string(), git("bbb", "sha256:2"));
+        new.upsert("added".to_string(), git("aaa", "sha256:1"));
+
+        let changes = diff_lockfiles(&old, &new);
+        let summary: Vec<_> = changes.iter().map(|c| (c.id.as_str(), c.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("added", LockChangeKind::Added),
+                ("bumped", LockChangeKind::Changed),
+                ("gone", LockChangeKind::Removed),
+            ]
+        );
+        let fields: Vec<_> = changes[1].fields.iter().map(|f| f.field).collect();
+        assert_eq!(fields, vec!["commit", "checksum"]);
+        assert_eq!(changes[1].fields[0].new.as_deref(), Some("bbb"));
+    }
+
+    #[test]
+    fn test_unknown_locked_source_is_preserved() {
+        let yaml = r#"
+version: 1
+entries:
+  remote:
+    source:
+      s3: prompts/AGENTS.md
+    dest: ./AGENTS.md
+    checksum: sha256:abc
+"#;
+        let lockfile: Lockfile = serde_yaml::from_str(yaml).unwrap();
+        let entry = &lockfile.entries["remote"];
+        assert!(matches!(entry.source, LockedSource::Other(_)));
+
+        let written = serde_yaml::to_string(&lockfile).unwrap();
+        assert!(written.contains("s3: prompts/AGENTS.md"));
+    }
+
+    #[test]
+    fn test_json_lockfile_round_trip_and_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = dir.path().join("aps.yaml");
+        assert_eq!(
+            Lockfile::path_for_manifest(&manifest_path, None),
+            dir.path().join(LOCKFILE_NAME)
+        );
+
+        let json_path = Lockfile::path_for_manifest(&manifest_path, Some(LockfileFormat::Json));
+        let mut lockfile = Lockfile::new();
+        lockfile.upsert(
+            "composite".to_string(),
+            LockedEntry::new_composite(
+                vec!["a.md".to_string()],
+                "./AGENTS.md",
+                "sha256:x".to_string(),
+            ),
+        );
+        lockfile.save(&json_path).unwrap();
+
+        let content = std::fs::read_to_string(&json_path).unwrap();
+        assert!(content.starts_with('{'));
+        assert_eq!(Lockfile::parse(&content).unwrap().entries.len(), 1);
+        // An existing JSON lockfile is picked up without configuration
+        assert_eq!(Lockfile::path_for_manifest(&manifest_path, None), json_path);
+
+        // Switching back to YAML reads the JSON file and replaces it on save
+        let yaml_path = Lockfile::path_for_manifest(&manifest_path, Some(LockfileFormat::Yaml));
+        let mut loaded = Lockfile::load(&yaml_path).unwrap();
+        loaded.save(&yaml_path).unwrap();
+        assert!(!json_path.exists());
+        assert!(std::fs::read_to_string(&yaml_path)
+            .unwrap()
+            .contains("composite:"));
+    }
+
+    #[test]
+    fn test_save_is_canonical() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKFILE_NAME);
+        let mut lockfile = Lockfile::new();
+        let mut composite = LockedEntry::new_composite(
+            vec!["b.md".to_string(), "a.md".to_string()],
+            "./AGENTS.md",
+            "sha256:x".to_string(),
+        );
+        composite.refreshed_at = Some("2026-01-02T03:04:05.678+02:00".to_string());
+        lockfile.upsert("zeta".to_string(), composite);
+        lockfile.upsert(
+            "alpha".to_string(),
+            LockedEntry::new_composite(vec![], "./CLAUDE.md", "sha256:y".to_string()),
+        );
+        lockfile.save(&path).unwrap();
+        let first = std::fs::read_to_string(&path).unwrap();
+
+        assert!(first.find("alpha:").unwrap() < first.find("zeta:").unwrap());
+        assert!(first.find("- a.md").unwrap() < first.find("- b.md").unwrap());
+        assert!(first.contains("refreshed_at: 2026-01-02T01:04:05Z"));
+
+        Lockfile::load(&path).unwrap().save(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), first);
+    }
+
+    #[test]
+    fn test_owners_of() {
+        let mut lockfile = Lockfile::new();
+        let mut rules = LockedEntry::new_filesystem(
+            "source",
+            "./.cursor/rules",
+            "sha256:x".to_string(),
+            false,
+            None,
+            vec![],
+        );
+        rules.files.insert(
+            ".cursor/rules/python.mdc".to_string(),
+            "sha256:p".to_string(),
+        );
+        lockfile.upsert("rules".to_string(), rules);
+        lockfile.upsert(
+            "skills".to_string(),
+            LockedEntry::new_filesystem(
+                "skills",
+                "./.claude/skills",
+                "sha256:y".to_string(),
+                true,
+                None,
+                vec![],
+            ),
+        );
+
+        let owners = lockfile.owners_of(".cursor/rules/python.mdc");
+        assert_eq!(owners.len(), 1);
+        assert_eq!((owners[0].0, owners[0].2), ("rules", Ownership::File));
+
+        let owners = lockfile.owners_of(".cursor/rules/mine.mdc");
+        assert_eq!(owners[0].2, Ownership::Unrecorded);
+
+        let owners = lockfile.owners_of(".claude/skills/review/SKILL.md");
+        assert_eq!((owners[0].0, owners[0].2), ("skills", Ownership::Dest));
+
+        assert!(lockfile.owners_of(".cursor/rules-old/a.mdc").is_empty());
+    }
 }
@@ -0,0 +1,148 @@
+//! Log output.
+//!
+//! Logs go to the console (only warnings unless `--verbose`), and with
+//! `--log-file` also to a file at debug level, so a CI run keeps a full
+//! record without cluttering its console output. `--log-format json` writes
+//! one JSON object per line instead of text, for log collectors.
+
+use crate::cli::OutputFormat;
+use crate::error::{ApsError, Result};
+use crate::output;
+use serde_json::{Map, Value};
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{self, FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Install the global subscriber for this run
+pub fn init(verbose: bool, log_file: Option<&Path>, format: OutputFormat) -> Result<()> {
+    let console_level = if verbose {
+        LevelFilter::DEBUG
+    } else {
+        LevelFilter::WARN
+    };
+    // With a log file, the file gets the requested format and the console
+    // stays readable
+    let console_format = if log_file.is_some() {
+        OutputFormat::Text
+    } else {
+        format
+    };
+    let mut layers = vec![layer(output::log_writer, console_format, true)
+        .with_filter(console_level)
+        .boxed()];
+
+    if let Some(path) = log_file {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| ApsError::io(e, format!("Failed to open log file {:?}", path)))?;
+        layers.push(
+            layer(Mutex::new(file), format, false)
+                .with_filter(LevelFilter::DEBUG)
+                .boxed(),
+        );
+    }
+
+    let subscriber = tracing_subscriber::registry().with(layers);
+    tracing::subscriber::set_global_default(subscriber).expect("Failed to set tracing subscriber");
+    Ok(())
+}
+
+fn layer<W>(writer: W, format: OutputFormat, ansi: bool) -> BoxedLayer
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi)
+        .with_target(false);
+    match format {
+        OutputFormat::Text => layer.boxed(),
+        OutputFormat::Json => layer.event_format(JsonFormat).boxed(),
+    }
+}
+
+/// One JSON object per event: timestamp, level, target and the event's
+/// fields (`message` for the formatted message)
+struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> std::fmt::Result {
+        writeln!(writer, "{}", json_record(event))
+    }
+}
+
+fn json_record(event: &Event<'_>) -> Value {
+    let metadata = event.metadata();
+    let mut record = Map::new();
+    record.insert(
+        "timestamp".to_string(),
+        Value::String(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+    );
+    record.insert(
+        "level".to_string(),
+        Value::String(metadata.level().to_string()),
+    );
+    record.insert(
+        "target".to_string(),
+        Value::String(metadata.target().to_string()),
+    );
+    let mut fields = JsonFields(Map::new());
+    event.record(&mut fields);
+    record.extend(fields.0);
+    Value::Object(record)
+}
+
+struct JsonFields(Map<String, Value>);
+
+impl JsonFields {
+    fn insert(&mut self, field: &Field, value: Value) {
+        self.0.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for JsonFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::String(value.to_string()));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::Bool(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.insert(field, Value::String(format!("{:?}", value)));
+    }
+}
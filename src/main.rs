@@ -1,62 +1,126 @@
+mod audit;
 mod backup;
+mod budget;
 mod catalog;
+mod catalog_lint;
+mod catalog_publish;
 mod checksum;
+mod claude_settings;
 mod cli;
 mod commands;
 mod compose;
+mod confirm;
+mod convert;
+mod cursorrules;
+mod dedupe;
+mod detect;
+mod diff;
 mod discover;
 mod error;
+mod freshness;
+mod frontmatter;
 mod github_url;
 mod hooks;
+mod http;
 mod install;
+mod links;
+mod lock_resolve;
 mod lockfile;
+mod logging;
+mod managed;
 mod manifest;
+mod mapping;
+mod mcp;
+mod mdc;
+mod merge;
+mod merge3;
 mod orphan;
+mod output;
+mod plan;
+mod post_install;
+mod progress;
+mod prompts;
+mod registry;
+mod report;
+mod schema;
+mod search;
+mod skill_spec;
 mod sources;
+mod status;
+mod suggest;
 mod sync_output;
+mod template;
+mod transform;
+mod tree;
+mod usage;
+mod validate;
+mod verify;
 
 use clap::Parser;
-use cli::{CatalogCommands, Cli, Commands};
+use cli::{BackupCommands, CatalogCommands, Cli, Commands, LockCommands};
 use commands::{
-    cmd_add, cmd_catalog_generate, cmd_init, cmd_list, cmd_status, cmd_sync, cmd_validate,
+    cmd_add, cmd_adopt, cmd_apply, cmd_backup_list, cmd_catalog_generate, cmd_catalog_info,
+    cmd_catalog_lint, cmd_catalog_list, cmd_catalog_publish, cmd_catalog_search, cmd_catalog_stats,
+    cmd_catalog_update, cmd_explain, cmd_init, cmd_list, cmd_lock_diff, cmd_lock_resolve, cmd_log,
+    cmd_plan, cmd_schema, cmd_status, cmd_suggest, cmd_sync, cmd_tree, cmd_validate, cmd_verify,
+    cmd_why,
 };
-use miette::Result;
-use tracing::Level;
-use tracing_subscriber::FmtSubscriber;
 
-fn main() -> Result<()> {
+fn main() {
     // Parse CLI arguments
     let cli = Cli::parse();
 
-    // Set up logging based on --verbose flag
-    let log_level = if cli.verbose {
-        Level::DEBUG
-    } else {
-        Level::WARN
-    };
-
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(log_level)
-        .with_target(false)
-        .with_thread_ids(false)
-        .with_thread_names(false)
-        .finish();
-
-    tracing::subscriber::set_global_default(subscriber).expect("Failed to set tracing subscriber");
+    // Set up logging based on --verbose, --log-file and --log-format
+    if let Err(err) = logging::init(cli.verbose, cli.log_file.as_deref(), cli.log_format) {
+        exit_with(err);
+    }
 
     // Execute the appropriate command
     let result = match cli.command {
         Commands::Init(args) => cmd_init(args),
         Commands::Add(args) => cmd_add(args),
         Commands::Sync(args) => cmd_sync(args),
+        Commands::Plan(args) => cmd_plan(args),
+        Commands::Apply(args) => cmd_apply(args),
+        Commands::Adopt(args) => cmd_adopt(args),
         Commands::Validate(args) => cmd_validate(args),
+        Commands::Schema(args) => cmd_schema(args),
         Commands::Status(args) => cmd_status(args),
+        Commands::Verify(args) => cmd_verify(args),
+        Commands::Why(args) => cmd_why(args),
+        Commands::Explain(args) => cmd_explain(args),
+        Commands::Tree(args) => cmd_tree(args),
+        Commands::Lock(args) => match args.command {
+            LockCommands::Diff(diff_args) => cmd_lock_diff(diff_args),
+            LockCommands::Resolve(resolve_args) => cmd_lock_resolve(resolve_args),
+        },
+        Commands::Backup(args) => match args.command {
+            BackupCommands::List(list_args) => cmd_backup_list(list_args),
+        },
         Commands::List(args) => cmd_list(args),
         Commands::Catalog(args) => match args.command {
             CatalogCommands::Generate(gen_args) => cmd_catalog_generate(gen_args),
+            CatalogCommands::List(list_args) => cmd_catalog_list(list_args),
+            CatalogCommands::Search(search_args) => cmd_catalog_search(search_args),
+            CatalogCommands::Update(update_args) => cmd_catalog_update(update_args),
+            CatalogCommands::Info(info_args) => cmd_catalog_info(info_args),
+            CatalogCommands::Lint(lint_args) => cmd_catalog_lint(lint_args),
+            CatalogCommands::Publish(publish_args) => cmd_catalog_publish(publish_args),
+            CatalogCommands::Stats(stats_args) => cmd_catalog_stats(stats_args),
         },
+        Commands::Suggest(args) => cmd_suggest(args),
+        Commands::Log(args) => cmd_log(args),
     };
 
     // Convert our error type to miette for nice display
-    result.map_err(|e| e.into())
+    if let Err(err) = result {
+        exit_with(err);
+    }
+}
+
+/// Print the error and exit with its exit code
+fn exit_with(err: error::ApsError) -> ! {
+    let exit_code = err.exit_code();
+    eprintln!("Error: {:?}", miette::Report::new(err));
+    std::process::exit(exit_code);
 }
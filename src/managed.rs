@@ -0,0 +1,170 @@
+//! Managed-region markers for instruction files.
+//!
+//! Entries with `managed_region: true` wrap the content they write to
+//! AGENTS.md-style files in `<!-- aps:begin -->` / `<!-- aps:end -->`
+//! markers. When the destination already contains a managed region, only
+//! that region is rewritten, so human-edited content above or below it
+//! survives sync. Other files are written whole, as they always were.
+//!
+//! The region content last installed is kept under `.aps/base/` as the base
+//! for three-way merging local edits made inside the region.
+
+use crate::audit::APS_DIR;
+use crate::error::{ApsError, Result};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Marker opening the region owned by aps
+pub const BEGIN_MARKER: &str = "<!-- aps:begin -->";
+
+/// Marker closing the region owned by aps
+pub const END_MARKER: &str = "<!-- aps:end -->";
+
+/// Byte range of the managed region, from the start of the begin marker to
+/// the end of the end marker
+fn find_region(content: &str) -> Option<(usize, usize)> {
+    let start = content.find(BEGIN_MARKER)?;
+    let end = content[start..].find(END_MARKER)? + start + END_MARKER.len();
+    Some((start, end))
+}
+
+/// Whether `content` contains a complete managed region
+pub fn has_managed_region(content: &str) -> bool {
+    find_region(content).is_some()
+}
+
+/// The managed region of `content`, markers included
+pub fn managed_region(content: &str) -> Option<&str> {
+    find_region(content).map(|(start, end)| &content[start..end])
+}
+
+/// The content inside the managed region of `content`, without markers
+pub fn region_content(content: &str) -> Option<&str> {
+    let (start, end) = find_region(content)?;
+    let inner = &content[start + BEGIN_MARKER.len()..end - END_MARKER.len()];
+    Some(inner.strip_prefix('\n').unwrap_or(inner))
+}
+
+/// Directory within the aps state directory holding merge bases
+const BASE_DIR: &str = "base";
+
+/// Where the merge base of an entry's managed region is kept
+pub fn base_path(manifest_dir: &Path, id: &str) -> PathBuf {
+    manifest_dir
+        .join(APS_DIR)
+        .join(BASE_DIR)
+        .join(format!("{}.md", id))
+}
+
+/// The managed region content last installed for an entry, if recorded
+pub fn load_base(manifest_dir: &Path, id: &str) -> Option<String> {
+    std::fs::read_to_string(base_path(manifest_dir, id)).ok()
+}
+
+/// Record the managed region content installed for an entry
+pub fn save_base(manifest_dir: &Path, id: &str, content: &str) -> Result<()> {
+    let path = base_path(manifest_dir, id);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| ApsError::io(e, format!("Failed to create directory {:?}", parent)))?;
+    }
+    std::fs::write(&path, content)
+        .map_err(|e| ApsError::io(e, format!("Failed to write {:?}", path)))
+}
+
+/// Whether the file at `path` is a regular file with a managed region
+pub fn file_has_managed_region(path: &Path) -> bool {
+    let is_file = path
+        .symlink_metadata()
+        .map(|m| m.file_type().is_file())
+        .unwrap_or(false);
+    is_file
+        && std::fs::read_to_string(path)
+            .map(|c| has_managed_region(&c))
+            .unwrap_or(false)
+}
+
+/// Place `managed` inside the managed region of `existing`, keeping everything
+/// outside the markers. Without an existing region the result is just the
+/// wrapped content.
+pub fn merge_managed_region(existing: Option<&str>, managed: &str) -> String {
+    let region = format!("{}\n{}\n{}", BEGIN_MARKER, managed.trim_end(), END_MARKER);
+
+    match existing.and_then(|e| find_region(e).map(|r| (e, r))) {
+        Some((existing, (start, end))) => {
+            format!("{}{}{}", &existing[..start], region, &existing[end..])
+        }
+        None => format!("{}\n", region),
+    }
+}
+
+/// Write `managed` to `dest`, rewriting only the managed region if the file
+/// already has one. Otherwise the content is wrapped in a new region when
+/// `wrap` asks for one, or written as the whole file.
+pub fn write_managed_file(dest: &Path, managed: &str, wrap: bool) -> Result<()> {
+    let meta = dest.symlink_metadata().ok();
+    let existing = match meta {
+        Some(ref m) if m.file_type().is_file() => std::fs::read_to_string(dest).ok(),
+        _ => None,
+    };
+
+    // Never write through a symlink left by a previous symlinked install
+    if meta.is_some_and(|m| m.file_type().is_symlink()) {
+        std::fs::remove_file(dest)
+            .map_err(|e| ApsError::io(e, format!("Failed to remove symlink {:?}", dest)))?;
+    }
+
+    if let Some(parent) = dest.parent() {
+        if !parent.exists() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                ApsError::io(e, format!("Failed to create directory: {:?}", parent))
+            })?;
+        }
+    }
+
+    let content = if existing.as_deref().is_some_and(has_managed_region) {
+        debug!("Updating managed region in {:?}", dest);
+        merge_managed_region(existing.as_deref(), managed)
+    } else if wrap {
+        merge_managed_region(None, managed)
+    } else {
+        managed.to_string()
+    };
+    std::fs::write(dest, content)
+        .map_err(|e| ApsError::io(e, format!("Failed to write {:?}", dest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_wraps_new_content() {
+        assert_eq!(
+            merge_managed_region(None, "# Shared\n"),
+            "<!-- aps:begin -->\n# Shared\n<!-- aps:end -->\n"
+        );
+        // Existing content without markers is replaced
+        assert_eq!(
+            merge_managed_region(Some("old"), "new"),
+            "<!-- aps:begin -->\nnew\n<!-- aps:end -->\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_preserves_content_outside_region() {
+        let existing =
+            "# Local notes\n\n<!-- aps:begin -->\nold\n<!-- aps:end -->\n\n## Team section\n";
+        assert_eq!(
+            merge_managed_region(Some(existing), "new\n"),
+            "# Local notes\n\n<!-- aps:begin -->\nnew\n<!-- aps:end -->\n\n## Team section\n"
+        );
+    }
+
+    #[test]
+    fn test_unterminated_region_is_not_managed() {
+        assert!(!has_managed_region("<!-- aps:begin -->\nno end"));
+        assert!(!has_managed_region("<!-- aps:end -->\n<!-- aps:begin -->"));
+        assert!(has_managed_region("<!-- aps:begin --><!-- aps:end -->"));
+    }
+}
@@ -1,8 +1,17 @@
+use crate::backup::BackupMode;
+use crate::checksum::compute_string_checksum;
+use crate::claude_settings;
+use crate::detect;
 use crate::error::{ApsError, Result};
-use crate::sources::{FilesystemSource, GitSource, SourceAdapter};
-use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::path::{Path, PathBuf};
+use crate::freshness::parse_max_age;
+use crate::lockfile::LockfileFormat;
+use crate::mapping;
+use crate::mcp::MCP_SERVERS_KEY;
+use crate::sources::{FilesystemSource, GitSource, SourceAdapter, UnsupportedSource};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path, PathBuf};
 use tracing::{debug, info};
 
 /// Default manifest filename
@@ -11,21 +20,75 @@ pub const DEFAULT_MANIFEST_NAME: &str = "aps.yaml";
 /// The main manifest structure
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Manifest {
+    /// Minimum aps version needed to sync this manifest (e.g. ">=0.5")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires_aps: Option<String>,
+
     /// List of entries to sync
     #[serde(default)]
     pub entries: Vec<Entry>,
+
+    /// Default maximum age of locked content for all entries (e.g. "90d")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<String>,
+
+    /// Format to write the lockfile in (defaults to whichever lockfile exists, else YAML)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lockfile_format: Option<LockfileFormat>,
+
+    /// Where content is backed up before it is overwritten or deleted
+    /// (defaults to `.aps-backups/`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup: Option<BackupMode>,
+
+    /// Most warnings `aps validate` accepts before failing, not counting
+    /// warnings an entry allows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_warnings: Option<usize>,
+
+    /// Catalogs read by `aps catalog` commands when none is given: paths
+    /// relative to the manifest, or git or HTTP(S) URLs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub catalogs: Vec<String>,
+}
+
+impl Manifest {
+    /// All entries with `targets` fanned out, as they are synced and locked
+    pub fn expanded_entries(&self) -> Vec<Entry> {
+        self.entries
+            .iter()
+            .flat_map(Entry::expand_targets)
+            .collect()
+    }
 }
 
 impl Default for Manifest {
     fn default() -> Self {
         Self {
+            requires_aps: None,
             entries: vec![Entry::example()],
+            max_age: None,
+            lockfile_format: None,
+            backup: None,
+            max_warnings: None,
+            catalogs: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Manifest {
+    /// A manifest of `entries` with every other setting left unset
+    pub fn with_entries(entries: Vec<Entry>) -> Self {
+        Self {
+            entries,
+            ..Self::default()
         }
     }
 }
 
 /// A single entry in the manifest
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Entry {
     /// Unique identifier for this entry
     pub id: String,
@@ -37,17 +100,139 @@ pub struct Entry {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<Source>,
 
-    /// Multiple sources to compose (for composite_agents_md kind)
+    /// Catalog version the entry was added at, for reference
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    /// Multiple sources to compose (for composite_agents_md, mcp_config and claude_settings kinds)
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub sources: Vec<Source>,
+    pub sources: Vec<CompositeSource>,
+
+    /// Project-owned fragment appended after all sources (composite_agents_md
+    /// only). Read on every sync but never written by aps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local: Option<String>,
+
+    /// Maximum estimated tokens in the composed output (composite kinds only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<usize>,
+
+    /// Maximum size in bytes of the composed output (composite kinds only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<usize>,
+
+    /// Whether exceeding `max_tokens`/`max_bytes` fails the entry or warns
+    #[serde(default, skip_serializing_if = "OverBudget::is_default")]
+    pub over_budget: OverBudget,
+
+    /// Merge strategy per dotted key path (claude_settings and mcp_config only)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub merge: BTreeMap<String, MergeStrategy>,
 
-    /// Optional destination override
+    /// Settings file to write (claude_settings only)
+    #[serde(default, skip_serializing_if = "SettingsTarget::is_default")]
+    pub target: SettingsTarget,
+
+    /// Generate a table of contents from fragment titles (composite_agents_md only)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub toc: bool,
+
+    /// Optional destination override, or a list of destinations to install
+    /// the same content to (each synced and locked as `<id>:<dest>`)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub dest: Option<String>,
+    pub dest: Option<Dest>,
 
     /// Optional list of prefixes to filter which files/folders to sync
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub include: Vec<String>,
+
+    /// Names of child skills to install from a skills root
+    /// (cursor_skills_root and agent_skill only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skills: Vec<String>,
+
+    /// Values rendered into composite fragments as `{{name}}` (composite_agents_md only)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vars: BTreeMap<String, serde_yaml::Value>,
+
+    /// Maximum age of locked content before validation fails (e.g. "90d").
+    /// Overrides the manifest-level `max_age`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<String>,
+
+    /// Expected content checksum (`sha256:...`, as recorded in the lockfile).
+    /// Installs fail if the resolved content doesn't match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
+
+    /// Permission bits for installed files, overriding those of the source
+    /// (copies only; ignored on Windows)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<FileMode>,
+
+    /// Mark installed files read-only to discourage local edits (copies only)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub readonly: bool,
+
+    /// Wrap copied markdown in `aps:begin`/`aps:end` markers so content
+    /// added around them survives sync (agents_md and composite_agents_md)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub managed_region: bool,
+
+    /// Allow destinations outside the manifest directory and the home
+    /// directory, such as absolute system paths
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_outside_project: bool,
+
+    /// How symlinks inside a copied source directory are installed
+    #[serde(default, skip_serializing_if = "SymlinkMode::is_default")]
+    pub symlinks: SymlinkMode,
+
+    /// Whether sync adds the destination to `.gitignore`, removes it, or
+    /// leaves `.gitignore` alone
+    #[serde(default, skip_serializing_if = "GitignoreMode::is_default")]
+    pub gitignore: GitignoreMode,
+
+    /// Shell commands run from the manifest directory after the entry is
+    /// installed (requires `aps sync --allow-hooks`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_install: Vec<String>,
+
+    /// Convert markdown rules into Cursor `.mdc` rules (cursor_rules only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mdc: Option<MdcOptions>,
+
+    /// Source files installed under another name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub map: Vec<FileMapping>,
+
+    /// Transforms applied to markdown content between resolve and install
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "serde_yaml::with::singleton_map_recursive"
+    )]
+    pub transforms: Vec<Transform>,
+
+    /// Flatten a source in another tool's format into this instruction file
+    /// (agents_md only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub convert: Option<ConvertOptions>,
+
+    /// Also write installed rules to a single legacy `.cursorrules` file
+    /// (cursor_rules only)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub legacy_cursorrules: bool,
+
+    /// Install the same instructions to several tool-specific destinations.
+    /// Each target is synced and locked as `<id>:<target>`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<InstructionTarget>,
+
+    /// Kinds of `aps validate` warning known to be acceptable for this
+    /// entry: they are neither shown nor escalated by `--strict`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_warnings: Vec<WarningKind>,
 }
 
 impl Entry {
@@ -61,32 +246,183 @@ impl Entry {
                 symlink: true,
                 path: Some("AGENTS.md".to_string()),
             }),
-            sources: Vec::new(),
-            dest: None,
-            include: Vec::new(),
+            ..Default::default()
         }
     }
 
     /// Check if this is a composite entry (uses multiple sources)
     pub fn is_composite(&self) -> bool {
-        self.kind == AssetKind::CompositeAgentsMd && !self.sources.is_empty()
+        self.kind.is_composite() && !self.sources.is_empty()
+    }
+
+    /// Source type used by this entry that this version of aps cannot handle
+    pub fn unsupported_source_type(&self) -> Option<&str> {
+        self.source
+            .iter()
+            .chain(self.sources.iter().map(|s| &s.source))
+            .find_map(Source::unsupported_type)
+    }
+
+    /// Composite sources in composition order: by `order` (unset counts as
+    /// 0), keeping manifest order for ties
+    pub fn ordered_sources(&self) -> Vec<&CompositeSource> {
+        let mut sources: Vec<&CompositeSource> = self.sources.iter().collect();
+        sources.sort_by_key(|s| s.order.unwrap_or(0));
+        sources
     }
 
-    /// Get the destination path for this entry (with shell variable expansion)
+    /// Expand `targets` and `dest` lists into one entry per destination, each
+    /// with its own ID. Entries with a single destination expand to themselves.
+    pub fn expand_targets(&self) -> Vec<Entry> {
+        if let Some(Dest::Many(dests)) = &self.dest {
+            return dests
+                .iter()
+                .map(|dest| Entry {
+                    id: format!(
+                        "{}:{}",
+                        self.id,
+                        normalize_dest(Path::new(dest)).to_string_lossy()
+                    ),
+                    dest: Some(Dest::One(dest.clone())),
+                    ..self.clone()
+                })
+                .collect();
+        }
+        if self.targets.is_empty() {
+            return vec![self.clone()];
+        }
+
+        self.targets
+            .iter()
+            .map(|target| Entry {
+                id: format!("{}:{}", self.id, target.as_str()),
+                dest: Some(target.default_dest().into()),
+                targets: Vec::new(),
+                ..self.clone()
+            })
+            .collect()
+    }
+
+    /// Get the destination path for this entry (with shell variable expansion).
+    /// For a `dest` list this is the first destination.
     pub fn destination(&self) -> PathBuf {
-        if let Some(ref dest) = self.dest {
-            let expanded = shellexpand::full(dest)
-                .map(|s| s.into_owned())
-                .unwrap_or_else(|_| dest.clone());
-            PathBuf::from(expanded)
+        if let Some(dest) = self.dest.as_ref().and_then(|d| d.paths().first().copied()) {
+            expand_dest(dest)
+        } else if self.kind == AssetKind::ClaudeSettings {
+            self.target.path()
         } else {
             self.kind.default_dest()
         }
     }
+
+    /// The `.gitignore` pattern matching this entry's destination, anchored
+    /// at the manifest directory
+    pub fn gitignore_pattern(&self) -> String {
+        format!("/{}", normalize_dest(&self.destination()).to_string_lossy())
+    }
+}
+
+/// A `dest` value with shell variables expanded
+fn expand_dest(dest: &str) -> PathBuf {
+    let expanded = shellexpand::full(dest)
+        .map(|s| s.into_owned())
+        .unwrap_or_else(|_| dest.to_string());
+    PathBuf::from(expanded)
+}
+
+/// Whether a destination would be written outside the manifest directory:
+/// an absolute path, or one whose `..` components climb above it
+pub fn escapes_project(dest: &Path) -> bool {
+    let mut depth = 0usize;
+    for component in dest.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return true,
+            },
+            Component::RootDir | Component::Prefix(_) => return true,
+        }
+    }
+    false
+}
+
+/// Whether a destination is `.git` or lies inside it, once `..` components
+/// are resolved
+fn inside_git_dir(dest: &Path) -> bool {
+    let mut components: Vec<&std::ffi::OsStr> = Vec::new();
+    for component in dest.components() {
+        match component {
+            Component::Normal(name) => components.push(name),
+            Component::ParentDir => {
+                components.pop();
+            }
+            _ => {}
+        }
+    }
+    components.iter().any(|name| *name == ".git")
+}
+
+/// Whether an absolute destination lies in the user's home directory, as
+/// `~/...` destinations do
+fn inside_home(dest: &Path) -> bool {
+    if !dest.is_absolute() {
+        return false;
+    }
+    let mut resolved = PathBuf::new();
+    for component in dest.components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+    dirs::home_dir().is_some_and(|home| home.parent().is_some() && resolved.starts_with(home))
+}
+
+/// Fail if any of an entry's destinations, including those derived from
+/// `targets` or a settings `target`, escape the manifest directory or point
+/// into `.git/`. Destinations in the home directory are allowed; others
+/// outside the project need `allow_outside_project`.
+pub fn check_dest_safety(entry: &Entry) -> Result<()> {
+    let mut paths: Vec<(String, PathBuf)> = match entry.dest {
+        Some(ref dest) => dest
+            .paths()
+            .into_iter()
+            .map(|path| (path.to_string(), expand_dest(path)))
+            .collect(),
+        None => {
+            let derived = entry.destination();
+            vec![(derived.display().to_string(), derived)]
+        }
+    };
+    paths.extend(entry.targets.iter().map(|target| {
+        let dest = target.default_dest();
+        (dest.to_string(), PathBuf::from(dest))
+    }));
+
+    for (path, expanded) in paths {
+        if escapes_project(&expanded) && !inside_home(&expanded) && !entry.allow_outside_project {
+            return Err(ApsError::UnsafeDest {
+                id: entry.id.clone(),
+                dest: path,
+            });
+        }
+        if inside_git_dir(&expanded) {
+            return Err(ApsError::GitDirDest {
+                id: entry.id.clone(),
+                dest: path,
+            });
+        }
+    }
+    Ok(())
 }
 
 /// Asset kinds supported by APS
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum AssetKind {
     /// Cursor rules directory
@@ -96,11 +432,22 @@ pub enum AssetKind {
     /// Cursor skills root directory
     CursorSkillsRoot,
     /// AGENTS.md file
+    #[default]
     AgentsMd,
     /// Agent skill directory (per agentskills.io spec)
     AgentSkill,
     /// Composite AGENTS.md - merge multiple markdown files into one
     CompositeAgentsMd,
+    /// MCP server config - merge `mcpServers` JSON fragments into .mcp.json
+    McpConfig,
+    /// Claude Code settings - merge permission and settings fragments into .claude/settings.json
+    ClaudeSettings,
+    /// Folder of reusable prompt templates with a generated INDEX.md
+    PromptLibrary,
+    /// Arbitrary single file with no tool-specific behavior (dest required)
+    File,
+    /// Arbitrary directory with no tool-specific behavior (dest required)
+    Dir,
 }
 
 impl AssetKind {
@@ -113,9 +460,59 @@ impl AssetKind {
             AssetKind::AgentsMd => PathBuf::from("AGENTS.md"),
             AssetKind::AgentSkill => PathBuf::from(".claude/skills"),
             AssetKind::CompositeAgentsMd => PathBuf::from("AGENTS.md"),
+            AssetKind::McpConfig => PathBuf::from(".mcp.json"),
+            AssetKind::ClaudeSettings => PathBuf::from(".claude/settings.json"),
+            AssetKind::PromptLibrary => PathBuf::from("prompts"),
+            // Generic kinds have no conventional location; validation requires `dest`
+            AssetKind::File | AssetKind::Dir => PathBuf::new(),
         }
     }
 
+    /// Whether entries of this kind install their destination as a whole,
+    /// replacing anything already there, rather than adding items to a
+    /// directory that other entries may share
+    pub fn owns_dest(&self) -> bool {
+        matches!(
+            self,
+            AssetKind::AgentsMd
+                | AssetKind::CompositeAgentsMd
+                | AssetKind::McpConfig
+                | AssetKind::ClaudeSettings
+                | AssetKind::File
+                | AssetKind::Dir
+        )
+    }
+
+    /// Whether entries of this kind must set an explicit `dest`
+    pub fn requires_dest(&self) -> bool {
+        matches!(self, AssetKind::File | AssetKind::Dir)
+    }
+
+    /// Kind name as written in manifests
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetKind::CursorRules => "cursor_rules",
+            AssetKind::CursorHooks => "cursor_hooks",
+            AssetKind::CursorSkillsRoot => "cursor_skills_root",
+            AssetKind::AgentsMd => "agents_md",
+            AssetKind::AgentSkill => "agent_skill",
+            AssetKind::CompositeAgentsMd => "composite_agents_md",
+            AssetKind::McpConfig => "mcp_config",
+            AssetKind::ClaudeSettings => "claude_settings",
+            AssetKind::PromptLibrary => "prompt_library",
+            AssetKind::File => "file",
+            AssetKind::Dir => "dir",
+        }
+    }
+
+    /// Whether this kind composes multiple `sources` into a single file
+    pub fn is_composite(&self) -> bool {
+        matches!(
+            self,
+            AssetKind::CompositeAgentsMd | AssetKind::McpConfig | AssetKind::ClaudeSettings
+        )
+    }
+
     /// Check if this is a valid kind string (for future use)
     #[allow(dead_code)]
     pub fn from_str(s: &str) -> Result<Self> {
@@ -126,6 +523,11 @@ impl AssetKind {
             "agents_md" => Ok(AssetKind::AgentsMd),
             "agent_skill" => Ok(AssetKind::AgentSkill),
             "composite_agents_md" => Ok(AssetKind::CompositeAgentsMd),
+            "mcp_config" => Ok(AssetKind::McpConfig),
+            "claude_settings" => Ok(AssetKind::ClaudeSettings),
+            "prompt_library" => Ok(AssetKind::PromptLibrary),
+            "file" => Ok(AssetKind::File),
+            "dir" => Ok(AssetKind::Dir),
             _ => Err(ApsError::InvalidAssetKind {
                 kind: s.to_string(),
             }),
@@ -133,9 +535,427 @@ impl AssetKind {
     }
 }
 
+/// Frontmatter generated when converting markdown rules to `.mdc`.
+/// Unset fields fall back to the rule's own frontmatter.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct MdcOptions {
+    /// Rule description (defaults to the rule's first heading)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// File globs the rule applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub globs: Option<Vec<String>>,
+
+    /// Whether the rule is always included in context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub always_apply: Option<bool>,
+}
+
+/// Where an entry is installed: one path, or a list of paths
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Dest {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Dest {
+    /// The destination paths, in manifest order
+    pub fn paths(&self) -> Vec<&str> {
+        match self {
+            Dest::One(path) => vec![path.as_str()],
+            Dest::Many(paths) => paths.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+impl From<String> for Dest {
+    fn from(path: String) -> Self {
+        Dest::One(path)
+    }
+}
+
+impl From<&str> for Dest {
+    fn from(path: &str) -> Self {
+        Dest::One(path.to_string())
+    }
+}
+
+/// A file or directory of a source installed under another path
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FileMapping {
+    /// Path relative to the source
+    pub from: String,
+    /// Path relative to the destination
+    pub to: String,
+}
+
+/// A content transform applied to markdown files before install
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    /// Remove `---` delimited YAML frontmatter
+    StripFrontmatter,
+    /// Replace every occurrence of `from` with `to`
+    Replace { from: String, to: String },
+    /// Insert a file (relative to the manifest) at the top
+    PrependFile(String),
+    /// Add a file (relative to the manifest) at the bottom
+    AppendFile(String),
+    /// Run a sandboxed Rhai script (relative to the manifest) that returns
+    /// the new content
+    Script(String),
+}
+
+/// Conversion of a source in another tool's format into markdown instructions
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ConvertOptions {
+    /// Format of the source
+    pub from: ConvertFormat,
+
+    /// Demote headings in the converted output by this many levels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shift_headings: Option<u8>,
+}
+
+/// Source formats that `convert` understands
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConvertFormat {
+    /// Cursor rules (`.mdc`/`.md` files with frontmatter)
+    CursorRules,
+    /// Plain markdown files, such as AGENTS.md or CLAUDE.md
+    Markdown,
+}
+
+/// What to do when a composed output exceeds its size budget
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OverBudget {
+    /// Fail the entry
+    #[default]
+    Error,
+    /// Write the output and report a warning
+    Warn,
+}
+
+impl OverBudget {
+    fn is_default(&self) -> bool {
+        *self == OverBudget::default()
+    }
+}
+
+/// How symlinks found inside a copied source directory are installed
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkMode {
+    /// Copy what the link points to; links that don't resolve are skipped
+    #[default]
+    Follow,
+    /// Recreate the link with the same target
+    Preserve,
+    /// Leave links out of the install
+    Skip,
+}
+
+impl SymlinkMode {
+    fn is_default(&self) -> bool {
+        *self == SymlinkMode::default()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymlinkMode::Follow => "follow",
+            SymlinkMode::Preserve => "preserve",
+            SymlinkMode::Skip => "skip",
+        }
+    }
+}
+
+/// How sync manages an entry's destination in `.gitignore`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GitignoreMode {
+    /// Ignore the installed files; they are regenerated by `aps sync`
+    Add,
+    /// Make sure the installed files are not ignored, so they get committed
+    Remove,
+    /// Don't touch `.gitignore`
+    #[default]
+    Leave,
+}
+
+impl GitignoreMode {
+    fn is_default(&self) -> bool {
+        *self == GitignoreMode::default()
+    }
+}
+
+/// Kinds of warning `aps validate` reports for an entry
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    /// The entry has no `source`
+    MissingSource,
+    /// The source can't be resolved or reached
+    SourceUnreachable,
+    /// The source path doesn't exist
+    SourceNotFound,
+    /// A skill directory has no SKILL.md
+    MissingSkillMd,
+    /// A SKILL.md breaks the Agent Skills specification
+    InvalidSkill,
+    /// A Cursor rule has invalid frontmatter
+    InvalidCursorRule,
+    /// A Cursor hooks config is invalid
+    InvalidCursorHooks,
+    /// A relative link won't resolve where the file is installed
+    BrokenLink,
+    /// A Claude settings fragment has an invalid permission rule
+    InvalidPermission,
+    /// Composed Claude settings are invalid
+    InvalidSettings,
+}
+
+/// POSIX permission bits, written in octal (`mode: "0755"`; an unquoted
+/// `755` reads the same)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMode(pub u32);
+
+impl<'de> Deserialize<'de> for FileMode {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let digits = match serde_yaml::Value::deserialize(deserializer)? {
+            serde_yaml::Value::String(s) => s,
+            serde_yaml::Value::Number(n) => n.to_string(),
+            other => {
+                return Err(serde::de::Error::custom(format!(
+                    "invalid mode {:?}: expected octal permissions such as \"0755\"",
+                    other
+                )))
+            }
+        };
+        u32::from_str_radix(digits.trim_start_matches("0o"), 8)
+            .ok()
+            .filter(|mode| *mode <= 0o7777)
+            .map(FileMode)
+            .ok_or_else(|| {
+                serde::de::Error::custom(format!(
+                    "invalid mode '{}': expected octal permissions such as \"0755\"",
+                    digits
+                ))
+            })
+    }
+}
+
+impl Serialize for FileMode {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{:04o}", self.0))
+    }
+}
+
+/// How fragments are combined for a key of a JSON composition kind
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// Concatenate lists, keeping duplicates; combine object keys
+    Append,
+    /// Concatenate lists, dropping duplicates; combine object keys
+    Union,
+    /// The later fragment's value replaces the earlier one
+    Replace,
+    /// Fail when fragments set the key (or an object member) to different values
+    #[serde(alias = "error-on-conflict")]
+    ErrorOnConflict,
+}
+
+/// Which Claude settings file a `claude_settings` entry writes
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingsTarget {
+    /// `.claude/settings.json`, shared with the team
+    #[default]
+    Project,
+    /// `.claude/settings.local.json`, kept out of version control
+    Local,
+    /// The OS-wide managed settings file, enforced for every project
+    Managed,
+}
+
+impl SettingsTarget {
+    fn is_default(&self) -> bool {
+        *self == SettingsTarget::default()
+    }
+
+    /// Settings file written for this target
+    pub fn path(&self) -> PathBuf {
+        match self {
+            SettingsTarget::Project => PathBuf::from(".claude/settings.json"),
+            SettingsTarget::Local => PathBuf::from(".claude/settings.local.json"),
+            SettingsTarget::Managed => claude_settings::managed_settings_path(),
+        }
+    }
+}
+
+/// Tool-specific instruction files an entry can fan out to via `targets`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum InstructionTarget {
+    /// AGENTS.md (Codex, Jules, and other agents)
+    AgentsMd,
+    /// CLAUDE.md (Claude)
+    ClaudeMd,
+    /// .github/copilot-instructions.md (GitHub Copilot)
+    CopilotInstructions,
+    /// GEMINI.md (Gemini CLI)
+    GeminiMd,
+}
+
+impl InstructionTarget {
+    /// Name used in the manifest and in fanned-out entry IDs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InstructionTarget::AgentsMd => "agents_md",
+            InstructionTarget::ClaudeMd => "claude_md",
+            InstructionTarget::CopilotInstructions => "copilot_instructions",
+            InstructionTarget::GeminiMd => "gemini_md",
+        }
+    }
+
+    /// Where the tool expects its instructions file
+    pub fn default_dest(&self) -> &'static str {
+        match self {
+            InstructionTarget::AgentsMd => "AGENTS.md",
+            InstructionTarget::ClaudeMd => "CLAUDE.md",
+            InstructionTarget::CopilotInstructions => ".github/copilot-instructions.md",
+            InstructionTarget::GeminiMd => "GEMINI.md",
+        }
+    }
+}
+
+/// Keys in a composite source that describe how it is composed rather than
+/// where it comes from
+const COMPOSITE_SOURCE_KEYS: &[&str] = &["order", "title", "shift_headings", "when"];
+
+/// A source in a composite entry along with how it is composed
+#[derive(Debug, Clone)]
+pub struct CompositeSource {
+    /// Where the fragment comes from
+    pub source: Source,
+    /// Position in the composed file (lower first)
+    pub order: Option<i64>,
+    /// Heading inserted above the fragment
+    pub title: Option<String>,
+    /// Number of levels to demote the fragment's headings by
+    pub shift_headings: Option<u8>,
+    /// Include the fragment only when the project matches
+    pub when: Option<FragmentCondition>,
+}
+
+/// Project conditions for including a composite fragment. All set fields
+/// must match.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FragmentCondition {
+    /// Path (relative to the manifest) that must exist
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exists: Option<String>,
+
+    /// Language that must be detected in the project (e.g. "python")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+}
+
+impl From<Source> for CompositeSource {
+    fn from(source: Source) -> Self {
+        Self {
+            source,
+            order: None,
+            title: None,
+            shift_headings: None,
+            when: None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CompositeSource {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut raw = serde_yaml::Value::deserialize(deserializer)?;
+        let order = take_key(&mut raw, "order")?;
+        let title = take_key(&mut raw, "title")?;
+        let shift_headings = take_key(&mut raw, "shift_headings")?;
+        let when = take_key(&mut raw, "when")?;
+
+        Ok(Self {
+            source: <Source as Deserialize>::deserialize(raw).map_err(serde::de::Error::custom)?,
+            order,
+            title,
+            shift_headings,
+            when,
+        })
+    }
+}
+
+/// Remove `key` from a YAML mapping and deserialize it, treating null as unset
+fn take_key<T, E>(raw: &mut serde_yaml::Value, key: &str) -> std::result::Result<Option<T>, E>
+where
+    T: serde::de::DeserializeOwned,
+    E: serde::de::Error,
+{
+    raw.as_mapping_mut()
+        .and_then(|m| m.remove(key))
+        .filter(|v| !v.is_null())
+        .map(serde_yaml::from_value)
+        .transpose()
+        .map_err(|e| E::custom(format!("invalid {}: {}", key, e)))
+}
+
+impl Serialize for CompositeSource {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::Error;
+
+        let mut raw = serde_yaml::to_value(&self.source).map_err(S::Error::custom)?;
+        if let Some(map) = raw.as_mapping_mut() {
+            for key in COMPOSITE_SOURCE_KEYS {
+                map.remove(*key);
+            }
+            if let Some(order) = self.order {
+                map.insert("order".into(), order.into());
+            }
+            if let Some(ref title) = self.title {
+                map.insert("title".into(), title.as_str().into());
+            }
+            if let Some(levels) = self.shift_headings {
+                map.insert("shift_headings".into(), levels.into());
+            }
+            if let Some(ref when) = self.when {
+                let when = serde_yaml::to_value(when).map_err(S::Error::custom)?;
+                map.insert("when".into(), when);
+            }
+        }
+        raw.serialize(serializer)
+    }
+}
+
+/// Source `type` values understood by this version of aps
+const SUPPORTED_SOURCE_TYPES: &[&str] = &["git", "filesystem"];
+
 /// Source types for syncing assets
 #[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(tag = "type", rename_all = "lowercase")]
+#[serde(remote = "Self", tag = "type", rename_all = "lowercase")]
 pub enum Source {
     /// Git repository source
     Git {
@@ -163,6 +983,43 @@ pub enum Source {
         #[serde(default)]
         path: Option<String>,
     },
+    /// Source type from a newer aps. Kept verbatim so the manifest still loads
+    /// and round-trips; only entries using it fail.
+    #[serde(skip)]
+    Unsupported {
+        source_type: String,
+        raw: serde_yaml::Value,
+    },
+}
+
+impl<'de> Deserialize<'de> for Source {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = serde_yaml::Value::deserialize(deserializer)?;
+        match raw.get("type").and_then(|t| t.as_str()) {
+            Some(source_type) if !SUPPORTED_SOURCE_TYPES.contains(&source_type) => {
+                Ok(Source::Unsupported {
+                    source_type: source_type.to_string(),
+                    raw,
+                })
+            }
+            _ => Source::deserialize(raw).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl Serialize for Source {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Source::Unsupported { raw, .. } => raw.serialize(serializer),
+            _ => Source::serialize(self, serializer),
+        }
+    }
 }
 
 fn default_ref() -> String {
@@ -197,6 +1054,9 @@ impl Source {
                 symlink,
                 path,
             } => Box::new(FilesystemSource::new(root.clone(), *symlink, path.clone())),
+            Source::Unsupported { source_type, .. } => {
+                Box::new(UnsupportedSource::new(source_type.clone()))
+            }
         }
     }
 
@@ -204,7 +1064,7 @@ impl Source {
     pub fn git_info(&self) -> Option<(&str, &str)> {
         match self {
             Source::Git { repo, r#ref, .. } => Some((repo.as_str(), r#ref.as_str())),
-            Source::Filesystem { .. } => None,
+            Source::Filesystem { .. } | Source::Unsupported { .. } => None,
         }
     }
 
@@ -212,7 +1072,7 @@ impl Source {
     pub fn git_path(&self) -> Option<&str> {
         match self {
             Source::Git { path, .. } => path.as_deref(),
-            Source::Filesystem { .. } => None,
+            Source::Filesystem { .. } | Source::Unsupported { .. } => None,
         }
     }
 
@@ -234,6 +1094,15 @@ impl Source {
                     root.clone()
                 }
             }
+            Source::Unsupported { source_type, .. } => format!("{}:<unsupported>", source_type),
+        }
+    }
+
+    /// Get the source type if this version of aps cannot handle it
+    pub fn unsupported_type(&self) -> Option<&str> {
+        match self {
+            Source::Unsupported { source_type, .. } => Some(source_type),
+            _ => None,
         }
     }
 }
@@ -290,6 +1159,14 @@ pub fn load_manifest(path: &Path) -> Result<Manifest> {
     let content = std::fs::read_to_string(path)
         .map_err(|e| ApsError::io(e, format!("Failed to read manifest at {:?}", path)))?;
 
+    // Check the version requirement before the full parse, so a manifest
+    // using newer features fails with an upgrade hint rather than a parse error
+    if let Ok(header) = serde_yaml::from_str::<ManifestHeader>(&content) {
+        if let Some(ref requirement) = header.requires_aps {
+            check_requires_aps(requirement)?;
+        }
+    }
+
     let manifest: Manifest =
         serde_yaml::from_str(&content).map_err(|e| ApsError::ManifestParseError {
             message: e.to_string(),
@@ -298,9 +1175,51 @@ pub fn load_manifest(path: &Path) -> Result<Manifest> {
     Ok(manifest)
 }
 
+/// Hash of a manifest's parsed content, recorded in the lockfile to detect
+/// edits since the last sync. Formatting and comments don't affect it.
+pub fn manifest_hash(manifest: &Manifest) -> String {
+    compute_string_checksum(&serde_json::to_string(manifest).unwrap_or_default())
+}
+
+/// Hash of one entry's parsed definition, recorded with its lock record to
+/// find entries edited since the last sync
+pub fn entry_hash(entry: &Entry) -> String {
+    compute_string_checksum(&serde_json::to_string(entry).unwrap_or_default())
+}
+
+/// Top-level manifest fields that must be read before anything else
+#[derive(Deserialize)]
+struct ManifestHeader {
+    #[serde(default)]
+    requires_aps: Option<String>,
+}
+
+/// Ensure this aps binary satisfies a `requires_aps` version requirement
+pub fn check_requires_aps(requirement: &str) -> Result<()> {
+    let req = VersionReq::parse(requirement).map_err(|e| ApsError::InvalidVersionRequirement {
+        value: requirement.to_string(),
+        message: e.to_string(),
+    })?;
+    let current =
+        Version::parse(env!("CARGO_PKG_VERSION")).expect("package version is valid semver");
+
+    if !req.matches(&current) {
+        return Err(ApsError::ApsVersionTooOld {
+            required: requirement.to_string(),
+            current: current.to_string(),
+        });
+    }
+    Ok(())
+}
+
 /// Validate a manifest for schema correctness
 pub fn validate_manifest(manifest: &Manifest) -> Result<()> {
     let mut seen_ids = HashSet::new();
+    let mut legacy_cursorrules_entry: Option<&str> = None;
+
+    if let Some(ref max_age) = manifest.max_age {
+        parse_max_age(max_age)?;
+    }
 
     for entry in &manifest.entries {
         // Check for duplicate IDs
@@ -310,8 +1229,180 @@ pub fn validate_manifest(manifest: &Manifest) -> Result<()> {
             });
         }
 
+        if let Some(ref integrity) = entry.integrity {
+            let digest = integrity.strip_prefix("sha256:").unwrap_or_default();
+            if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ApsError::InvalidIntegrity {
+                    id: entry.id.clone(),
+                    value: integrity.clone(),
+                });
+            }
+        }
+
+        if let Some(ref max_age) = entry.max_age {
+            parse_max_age(max_age)?;
+        }
+
+        if !entry.targets.is_empty() {
+            validate_targets(entry)?;
+        }
+
+        if !entry.skills.is_empty() {
+            validate_skills(entry)?;
+        }
+
+        if !entry.vars.is_empty() && entry.kind != AssetKind::CompositeAgentsMd {
+            return Err(ApsError::InvalidVars {
+                id: entry.id.clone(),
+            });
+        }
+
+        if entry.kind != AssetKind::CompositeAgentsMd
+            && (entry.toc
+                || entry.local.is_some()
+                || entry
+                    .sources
+                    .iter()
+                    .any(|s| s.title.is_some() || s.shift_headings.is_some()))
+        {
+            return Err(ApsError::InvalidCompositeOptions {
+                id: entry.id.clone(),
+                message: "'title', 'shift_headings', 'toc' and 'local' are only supported for composite_agents_md".to_string(),
+            });
+        }
+
+        let has_budget = entry.max_tokens.is_some()
+            || entry.max_bytes.is_some()
+            || !entry.over_budget.is_default();
+        if has_budget && !entry.kind.is_composite() {
+            return Err(ApsError::InvalidCompositeOptions {
+                id: entry.id.clone(),
+                message: "'max_tokens', 'max_bytes' and 'over_budget' are only supported for composite entries".to_string(),
+            });
+        }
+
+        if !entry.merge.is_empty() {
+            if !matches!(entry.kind, AssetKind::ClaudeSettings | AssetKind::McpConfig) {
+                return Err(ApsError::InvalidMergeStrategy {
+                    id: entry.id.clone(),
+                    message: "'merge' is only supported for claude_settings and mcp_config"
+                        .to_string(),
+                });
+            }
+            for key in entry.merge.keys() {
+                if key.is_empty() || key.split('.').any(str::is_empty) {
+                    return Err(ApsError::InvalidMergeStrategy {
+                        id: entry.id.clone(),
+                        message: format!("'{}' is not a valid key path", key),
+                    });
+                }
+                if entry.kind == AssetKind::McpConfig
+                    && key.split('.').next() != Some(MCP_SERVERS_KEY)
+                {
+                    return Err(ApsError::InvalidMergeStrategy {
+                        id: entry.id.clone(),
+                        message: format!("'{}' must start with '{}'", key, MCP_SERVERS_KEY),
+                    });
+                }
+            }
+        }
+
+        if !entry.target.is_default() {
+            if entry.kind != AssetKind::ClaudeSettings {
+                return Err(ApsError::InvalidSettingsTarget {
+                    id: entry.id.clone(),
+                    message: "'target' is only supported for claude_settings".to_string(),
+                });
+            }
+            if entry.dest.is_some() {
+                return Err(ApsError::InvalidSettingsTarget {
+                    id: entry.id.clone(),
+                    message: "'target' and 'dest' cannot both be set".to_string(),
+                });
+            }
+        }
+
+        if let Some(ref local) = entry.local {
+            if normalize_dest(Path::new(local)) == normalize_dest(&entry.destination()) {
+                return Err(ApsError::InvalidCompositeOptions {
+                    id: entry.id.clone(),
+                    message: "'local' must not point at the entry's own destination".to_string(),
+                });
+            }
+        }
+
+        for source in &entry.sources {
+            if let Some(lang) = source.when.as_ref().and_then(|w| w.lang.as_deref()) {
+                if !detect::is_known_language(lang) {
+                    return Err(ApsError::InvalidCompositeOptions {
+                        id: entry.id.clone(),
+                        message: format!(
+                            "unknown language '{}' in 'when' (known: {})",
+                            lang,
+                            detect::known_languages().join(", ")
+                        ),
+                    });
+                }
+            }
+        }
+
+        if entry
+            .transforms
+            .iter()
+            .any(|t| matches!(t, Transform::Replace { from, .. } if from.is_empty()))
+        {
+            return Err(ApsError::InvalidTransforms {
+                id: entry.id.clone(),
+                message: "'replace' needs a non-empty 'from'".to_string(),
+            });
+        }
+
+        if !entry.map.is_empty() {
+            validate_map(entry)?;
+        }
+
+        if entry.convert.is_some() && entry.kind != AssetKind::AgentsMd {
+            return Err(ApsError::InvalidConvertOptions {
+                id: entry.id.clone(),
+                message: "'convert' is only supported for agents_md".to_string(),
+            });
+        }
+
+        if entry.mdc.is_some() && entry.kind != AssetKind::CursorRules {
+            return Err(ApsError::InvalidMdcOptions {
+                id: entry.id.clone(),
+            });
+        }
+
+        if entry.legacy_cursorrules {
+            if entry.kind != AssetKind::CursorRules {
+                return Err(ApsError::InvalidLegacyCursorrules {
+                    id: entry.id.clone(),
+                    message: "only cursor_rules entries can export .cursorrules".to_string(),
+                });
+            }
+            if let Some(other) = legacy_cursorrules_entry {
+                return Err(ApsError::InvalidLegacyCursorrules {
+                    id: entry.id.clone(),
+                    message: format!("'{}' already exports .cursorrules", other),
+                });
+            }
+            legacy_cursorrules_entry = Some(&entry.id);
+        }
+
+        if entry.kind.requires_dest() && entry.dest.is_none() {
+            return Err(ApsError::EntryRequiresDest {
+                id: entry.id.clone(),
+            });
+        }
+
+        if let Some(Dest::Many(dests)) = &entry.dest {
+            validate_dest_list(entry, dests)?;
+        }
+        check_dest_safety(entry)?;
+
         // Validate source configuration based on kind
-        if entry.kind == AssetKind::CompositeAgentsMd {
+        if entry.kind.is_composite() {
             // Composite entries require sources array
             if entry.sources.is_empty() {
                 return Err(ApsError::CompositeRequiresSources {
@@ -328,10 +1419,125 @@ pub fn validate_manifest(manifest: &Manifest) -> Result<()> {
         }
     }
 
+    check_dest_collisions(manifest)?;
+
     info!("Manifest validation passed");
     Ok(())
 }
 
+/// Check that an entry's `targets` make sense for its kind
+fn validate_targets(entry: &Entry) -> Result<()> {
+    let invalid = |message: &str| ApsError::InvalidTargets {
+        id: entry.id.clone(),
+        message: message.to_string(),
+    };
+
+    if !matches!(
+        entry.kind,
+        AssetKind::AgentsMd | AssetKind::CompositeAgentsMd
+    ) {
+        return Err(invalid(
+            "targets are only supported for agents_md and composite_agents_md",
+        ));
+    }
+    if entry.dest.is_some() {
+        return Err(invalid("set either 'dest' or 'targets', not both"));
+    }
+
+    let mut seen = HashSet::new();
+    for target in &entry.targets {
+        if !seen.insert(target) {
+            return Err(invalid(&format!("duplicate target '{}'", target.as_str())));
+        }
+    }
+    Ok(())
+}
+
+/// Check that a `dest` list names distinct destinations
+fn validate_dest_list(entry: &Entry, dests: &[String]) -> Result<()> {
+    let invalid = |message: String| ApsError::InvalidDest {
+        id: entry.id.clone(),
+        message,
+    };
+
+    if dests.is_empty() {
+        return Err(invalid("'dest' list is empty".to_string()));
+    }
+    if entry.kind.is_composite() || entry.kind == AssetKind::ClaudeSettings {
+        return Err(invalid(format!(
+            "'dest' lists are not supported for {} entries",
+            entry.kind.as_str()
+        )));
+    }
+    let mut seen = HashSet::new();
+    for dest in dests {
+        if !seen.insert(normalize_dest(Path::new(dest))) {
+            return Err(invalid(format!("'{}' is listed more than once", dest)));
+        }
+    }
+    Ok(())
+}
+
+/// Check that an entry's `map` renames plain relative paths to distinct ones
+fn validate_map(entry: &Entry) -> Result<()> {
+    let invalid = |message: String| ApsError::InvalidMap {
+        id: entry.id.clone(),
+        message,
+    };
+
+    if entry.kind.is_composite() {
+        return Err(invalid(
+            "'map' is not supported for composite entries".to_string(),
+        ));
+    }
+    let mut targets = HashSet::new();
+    for mapping in &entry.map {
+        for path in [&mapping.from, &mapping.to] {
+            if !mapping::is_plain_relative(path) {
+                return Err(invalid(format!(
+                    "'{}' must be a relative path inside the source",
+                    path
+                )));
+            }
+        }
+        if !targets.insert(&mapping.to) {
+            return Err(invalid(format!(
+                "more than one mapping to '{}'",
+                mapping.to
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check that an entry's `skills` selection names plain child directories
+fn validate_skills(entry: &Entry) -> Result<()> {
+    let invalid = |message: String| ApsError::InvalidSkillSelection {
+        id: entry.id.clone(),
+        message,
+    };
+
+    if !matches!(
+        entry.kind,
+        AssetKind::CursorSkillsRoot | AssetKind::AgentSkill
+    ) {
+        return Err(invalid(
+            "skills are only supported for cursor_skills_root and agent_skill".to_string(),
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    for name in &entry.skills {
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(invalid(format!("'{}' is not a skill directory name", name)));
+        }
+        if !seen.insert(name) {
+            return Err(invalid(format!("duplicate skill '{}'", name)));
+        }
+    }
+    Ok(())
+}
+
 /// Normalize a destination path by stripping `./` prefix and trailing slashes
 /// so that `./.claude/skills/foo/` and `.claude/skills/foo` compare equal.
 fn normalize_dest(path: &Path) -> PathBuf {
@@ -341,27 +1547,62 @@ fn normalize_dest(path: &Path) -> PathBuf {
     PathBuf::from(s)
 }
 
+/// Fail if an entry writes inside the destination of another entry that
+/// installs it as a whole: the file can't be written, or the next sync of
+/// the other entry wipes it out. Entries sharing the exact same destination
+/// are left to `detect_overlapping_destinations`.
+fn check_dest_collisions(manifest: &Manifest) -> Result<()> {
+    let entries = manifest.expanded_entries();
+    let dests: Vec<PathBuf> = entries
+        .iter()
+        .map(|e| normalize_dest(&e.destination()))
+        .collect();
+    for (outer, outer_dest) in entries.iter().zip(&dests) {
+        if !outer.kind.owns_dest() || outer_dest.as_os_str().is_empty() {
+            continue;
+        }
+        for (inner, inner_dest) in entries.iter().zip(&dests) {
+            if inner_dest != outer_dest && inner_dest.starts_with(outer_dest) {
+                return Err(ApsError::DestCollision {
+                    id: inner.id.clone(),
+                    dest: inner_dest.display().to_string(),
+                    other: outer.id.clone(),
+                    other_dest: outer_dest.display().to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Detect entries that write to overlapping destination paths.
 /// Returns a list of human-readable warning strings.
 pub fn detect_overlapping_destinations(manifest: &Manifest) -> Vec<String> {
     let mut warnings = Vec::new();
 
     // Build a map of effective destination paths to entry IDs.
-    // An entry with `include` filters produces sub-paths like `dest/included_item`.
-    // An entry without `include` writes to `dest` directly.
+    // An entry with `skills` or `include` filters produces sub-paths like
+    // `dest/included_item`. An entry without either writes to `dest` directly.
     let mut dest_to_entries: std::collections::BTreeMap<PathBuf, Vec<&str>> =
         std::collections::BTreeMap::new();
 
-    for entry in &manifest.entries {
+    let entries = manifest.expanded_entries();
+    for entry in &entries {
         let base_dest = normalize_dest(&entry.destination());
 
-        if entry.include.is_empty() {
+        let selected = if entry.skills.is_empty() {
+            &entry.include
+        } else {
+            &entry.skills
+        };
+
+        if selected.is_empty() {
             dest_to_entries
                 .entry(base_dest)
                 .or_default()
                 .push(&entry.id);
         } else {
-            for inc in &entry.include {
+            for inc in selected {
                 let effective = normalize_dest(&base_dest.join(inc));
                 dest_to_entries
                     .entry(effective)
@@ -406,9 +1647,7 @@ mod tests {
                 symlink: true,
                 path: None,
             }),
-            sources: Vec::new(),
-            dest: None,
-            include: Vec::new(),
+            ..Default::default()
         };
 
         assert_eq!(entry.destination(), PathBuf::from("AGENTS.md"));
@@ -424,9 +1663,8 @@ mod tests {
                 symlink: true,
                 path: None,
             }),
-            sources: Vec::new(),
-            dest: Some("custom/path/AGENTS.md".to_string()),
-            include: Vec::new(),
+            dest: Some("custom/path/AGENTS.md".into()),
+            ..Default::default()
         };
 
         assert_eq!(entry.destination(), PathBuf::from("custom/path/AGENTS.md"));
@@ -444,9 +1682,8 @@ mod tests {
                 symlink: true,
                 path: None,
             }),
-            sources: Vec::new(),
-            dest: Some("$TEST_DEST_VAR/AGENTS.md".to_string()),
-            include: Vec::new(),
+            dest: Some("$TEST_DEST_VAR/AGENTS.md".into()),
+            ..Default::default()
         };
 
         assert_eq!(entry.destination(), PathBuf::from("/custom/dest/AGENTS.md"));
@@ -464,9 +1701,8 @@ mod tests {
                 symlink: true,
                 path: None,
             }),
-            sources: Vec::new(),
-            dest: Some("~/agents/AGENTS.md".to_string()),
-            include: Vec::new(),
+            dest: Some("~/agents/AGENTS.md".into()),
+            ..Default::default()
         };
 
         let result = entry.destination();
@@ -486,15 +1722,16 @@ mod tests {
                     root: ".".to_string(),
                     symlink: false,
                     path: Some("agents.python.md".to_string()),
-                },
+                }
+                .into(),
                 Source::Filesystem {
                     root: ".".to_string(),
                     symlink: false,
                     path: Some("agents.pandas.md".to_string()),
-                },
+                }
+                .into(),
             ],
-            dest: None,
-            include: Vec::new(),
+            ..Default::default()
         };
 
         assert!(entry.is_composite());
@@ -514,32 +1751,35 @@ mod tests {
                     root: "$HOME/agents".to_string(),
                     symlink: false,
                     path: Some("AGENT.python.md".to_string()),
-                },
+                }
+                .into(),
                 // Remote git source (e.g., Apache Airflow's AGENTS.md)
                 Source::Git {
                     repo: "https://github.com/apache/airflow.git".to_string(),
                     r#ref: "main".to_string(),
                     shallow: true,
                     path: Some("AGENTS.md".to_string()),
-                },
+                }
+                .into(),
                 // Another filesystem source
                 Source::Filesystem {
                     root: ".".to_string(),
                     symlink: false,
                     path: Some("agents.dockerfile.md".to_string()),
-                },
+                }
+                .into(),
             ],
-            dest: Some("./AGENTS.md".to_string()),
-            include: Vec::new(),
+            dest: Some("./AGENTS.md".into()),
+            ..Default::default()
         };
 
         assert!(entry.is_composite());
         assert_eq!(entry.sources.len(), 3);
 
         // Verify source types
-        assert!(matches!(entry.sources[0], Source::Filesystem { .. }));
-        assert!(matches!(entry.sources[1], Source::Git { .. }));
-        assert!(matches!(entry.sources[2], Source::Filesystem { .. }));
+        assert!(matches!(entry.sources[0].source, Source::Filesystem { .. }));
+        assert!(matches!(entry.sources[1].source, Source::Git { .. }));
+        assert!(matches!(entry.sources[2].source, Source::Filesystem { .. }));
     }
 
     #[test]
@@ -547,6 +1787,7 @@ mod tests {
         // Simulates the user's case: one entry uses include filter that targets
         // the same dest as a standalone entry
         let manifest = Manifest {
+            requires_aps: None,
             entries: vec![
                 Entry {
                     id: "anthropic-skills".to_string(),
@@ -557,9 +1798,9 @@ mod tests {
                         shallow: true,
                         path: Some("skills".to_string()),
                     }),
-                    sources: Vec::new(),
-                    dest: Some(".claude/skills/".to_string()),
+                    dest: Some(".claude/skills/".into()),
                     include: vec!["skill-creator".to_string()],
+                    ..Default::default()
                 },
                 Entry {
                     id: "skill-creator".to_string(),
@@ -570,11 +1811,15 @@ mod tests {
                         shallow: true,
                         path: Some("skills/skill-creator".to_string()),
                     }),
-                    sources: Vec::new(),
-                    dest: Some(".claude/skills/skill-creator/".to_string()),
-                    include: Vec::new(),
+                    dest: Some(".claude/skills/skill-creator/".into()),
+                    ..Default::default()
                 },
             ],
+            max_age: None,
+            lockfile_format: None,
+            backup: None,
+            max_warnings: None,
+            catalogs: Vec::new(),
         };
 
         let warnings = detect_overlapping_destinations(&manifest);
@@ -586,6 +1831,7 @@ mod tests {
     #[test]
     fn test_no_overlap_different_destinations() {
         let manifest = Manifest {
+            requires_aps: None,
             entries: vec![
                 Entry {
                     id: "skill-a".to_string(),
@@ -595,9 +1841,8 @@ mod tests {
                         symlink: true,
                         path: None,
                     }),
-                    sources: Vec::new(),
-                    dest: Some(".claude/skills/a/".to_string()),
-                    include: Vec::new(),
+                    dest: Some(".claude/skills/a/".into()),
+                    ..Default::default()
                 },
                 Entry {
                     id: "skill-b".to_string(),
@@ -607,14 +1852,534 @@ mod tests {
                         symlink: true,
                         path: None,
                     }),
-                    sources: Vec::new(),
-                    dest: Some(".claude/skills/b/".to_string()),
-                    include: Vec::new(),
+                    dest: Some(".claude/skills/b/".into()),
+                    ..Default::default()
                 },
             ],
+            max_age: None,
+            lockfile_format: None,
+            backup: None,
+            max_warnings: None,
+            catalogs: Vec::new(),
         };
 
         let warnings = detect_overlapping_destinations(&manifest);
         assert!(warnings.is_empty());
     }
+
+    #[test]
+    fn test_generic_kinds_require_dest() {
+        let mut entry = Entry {
+            id: "editorconfig".to_string(),
+            kind: AssetKind::File,
+            source: Some(Source::Filesystem {
+                root: ".".to_string(),
+                symlink: false,
+                path: Some(".editorconfig".to_string()),
+            }),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            validate_manifest(&Manifest::with_entries(vec![entry.clone()])),
+            Err(ApsError::EntryRequiresDest { .. })
+        ));
+
+        entry.dest = Some(".editorconfig".into());
+        assert!(validate_manifest(&Manifest::with_entries(vec![entry.clone()])).is_ok());
+    }
+
+    #[test]
+    fn test_unsupported_source_type_round_trips() {
+        let yaml = r#"
+entries:
+  - id: remote
+    kind: agents_md
+    source:
+      type: s3
+      bucket: prompts
+  - id: local
+    kind: agents_md
+    source:
+      type: filesystem
+      root: .
+"#;
+        let manifest: Manifest = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(manifest.entries[0].unsupported_source_type(), Some("s3"));
+        assert_eq!(manifest.entries[1].unsupported_source_type(), None);
+
+        let written = serde_yaml::to_string(&manifest).unwrap();
+        assert!(written.contains("bucket: prompts"));
+        let reparsed: Manifest = serde_yaml::from_str(&written).unwrap();
+        assert_eq!(reparsed.entries[0].unsupported_source_type(), Some("s3"));
+    }
+
+    #[test]
+    fn test_invalid_known_source_still_errors() {
+        let yaml = "entries:\n  - id: bad\n    kind: agents_md\n    source:\n      type: git\n";
+        assert!(serde_yaml::from_str::<Manifest>(yaml).is_err());
+    }
+
+    #[test]
+    fn test_check_requires_aps() {
+        assert!(check_requires_aps(">=0.1").is_ok());
+        assert!(check_requires_aps(&format!("={}", env!("CARGO_PKG_VERSION"))).is_ok());
+        assert!(matches!(
+            check_requires_aps(">=99.0"),
+            Err(ApsError::ApsVersionTooOld { .. })
+        ));
+        assert!(matches!(
+            check_requires_aps("newest"),
+            Err(ApsError::InvalidVersionRequirement { .. })
+        ));
+    }
+
+    #[test]
+    fn test_expand_targets() {
+        let mut entry = Entry {
+            id: "instructions".to_string(),
+            kind: AssetKind::AgentsMd,
+            source: Some(Source::Filesystem {
+                root: ".".to_string(),
+                symlink: false,
+                path: Some("INSTRUCTIONS.md".to_string()),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(entry.expand_targets().len(), 1);
+        assert_eq!(entry.expand_targets()[0].id, "instructions");
+
+        entry.targets = vec![
+            InstructionTarget::AgentsMd,
+            InstructionTarget::ClaudeMd,
+            InstructionTarget::CopilotInstructions,
+        ];
+        let expanded = entry.expand_targets();
+        let ids: Vec<&str> = expanded.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "instructions:agents_md",
+                "instructions:claude_md",
+                "instructions:copilot_instructions"
+            ]
+        );
+        assert_eq!(
+            expanded[2].destination(),
+            PathBuf::from(".github/copilot-instructions.md")
+        );
+        assert!(expanded.iter().all(|e| e.targets.is_empty()));
+    }
+
+    #[test]
+    fn test_escapes_project() {
+        for inside in ["AGENTS.md", "./.cursor/rules/", "docs/../AGENTS.md"] {
+            assert!(!escapes_project(Path::new(inside)), "{}", inside);
+        }
+        for outside in ["../AGENTS.md", "docs/../../x", "/etc/passwd"] {
+            assert!(escapes_project(Path::new(outside)), "{}", outside);
+        }
+
+        let entry = Entry {
+            id: "agents".to_string(),
+            kind: AssetKind::AgentsMd,
+            dest: Some(Dest::Many(vec![
+                "AGENTS.md".to_string(),
+                "../AGENTS.md".to_string(),
+            ])),
+            ..Default::default()
+        };
+        assert!(matches!(
+            check_dest_safety(&entry),
+            Err(ApsError::UnsafeDest { dest, .. }) if dest == "../AGENTS.md"
+        ));
+
+        let outside = |dest: &str, allow_outside_project: bool| Entry {
+            id: "rules".to_string(),
+            kind: AssetKind::CursorRules,
+            dest: Some(Dest::One(dest.to_string())),
+            allow_outside_project,
+            ..Default::default()
+        };
+        assert!(check_dest_safety(&outside("~/.cursor/rules", false)).is_ok());
+        assert!(check_dest_safety(&outside("~/../../etc/rules", false)).is_err());
+        assert!(check_dest_safety(&outside("/etc/rules", false)).is_err());
+        assert!(check_dest_safety(&outside("/etc/rules", true)).is_ok());
+        assert!(check_dest_safety(&outside("/etc/.git/rules", true)).is_err());
+    }
+
+    #[test]
+    fn test_managed_settings_target_needs_opt_in() {
+        let mut entry = Entry {
+            id: "settings".to_string(),
+            kind: AssetKind::ClaudeSettings,
+            target: SettingsTarget::Managed,
+            ..Default::default()
+        };
+        assert!(matches!(
+            check_dest_safety(&entry),
+            Err(ApsError::UnsafeDest { dest, .. })
+                if Path::new(&dest) == claude_settings::managed_settings_path()
+        ));
+
+        entry.allow_outside_project = true;
+        assert!(check_dest_safety(&entry).is_ok());
+    }
+
+    #[test]
+    fn test_git_dir_dest() {
+        for inside in [".git/hooks/pre-commit", "./.git", "vendor/lib/.git/config"] {
+            assert!(inside_git_dir(Path::new(inside)), "{}", inside);
+        }
+        for outside in [
+            ".github/copilot-instructions.md",
+            ".gitignore",
+            ".git/../AGENTS.md",
+        ] {
+            assert!(!inside_git_dir(Path::new(outside)), "{}", outside);
+        }
+    }
+
+    #[test]
+    fn test_dest_collisions() {
+        let yaml = r#"
+entries:
+  - id: config
+    kind: dir
+    source:
+      type: filesystem
+      root: shared
+    dest: ./config/
+  - id: editorconfig
+    kind: file
+    source:
+      type: filesystem
+      root: shared
+      path: .editorconfig
+    dest: config/.editorconfig
+"#;
+        let manifest: Manifest = serde_yaml::from_str(yaml).unwrap();
+        assert!(matches!(
+            validate_manifest(&manifest),
+            Err(ApsError::DestCollision { id, other, .. }) if id == "editorconfig" && other == "config"
+        ));
+
+        // Directories that entries add items to can be shared
+        let yaml = r#"
+entries:
+  - id: skills
+    kind: agent_skill
+    source:
+      type: filesystem
+      root: skills
+  - id: pdf
+    kind: agent_skill
+    source:
+      type: filesystem
+      root: vendor/pdf
+    dest: .claude/skills/pdf
+"#;
+        let manifest: Manifest = serde_yaml::from_str(yaml).unwrap();
+        assert!(validate_manifest(&manifest).is_ok());
+    }
+
+    #[test]
+    fn test_dest_list_expands_per_destination() {
+        let yaml = r#"
+entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: .
+      path: rules
+    dest: [./.cursor/rules/, .windsurf/rules]
+"#;
+        let manifest: Manifest = serde_yaml::from_str(yaml).unwrap();
+        validate_manifest(&manifest).unwrap();
+        let expanded = manifest.expanded_entries();
+        let ids: Vec<&str> = expanded.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["rules:.cursor/rules", "rules:.windsurf/rules"]);
+        assert_eq!(expanded[1].destination(), PathBuf::from(".windsurf/rules"));
+
+        let mut entry = manifest.entries[0].clone();
+        entry.dest = Some(Dest::Many(vec![
+            ".cursor/rules".to_string(),
+            "./.cursor/rules/".to_string(),
+        ]));
+        let manifest = Manifest {
+            entries: vec![entry],
+            ..Default::default()
+        };
+        assert!(matches!(
+            validate_manifest(&manifest),
+            Err(ApsError::InvalidDest { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_targets() {
+        let entry = Entry {
+            id: "instructions".to_string(),
+            kind: AssetKind::AgentsMd,
+            source: Some(Source::Filesystem {
+                root: ".".to_string(),
+                symlink: false,
+                path: None,
+            }),
+            targets: vec![InstructionTarget::ClaudeMd],
+            ..Default::default()
+        };
+        assert!(validate_manifest(&Manifest::with_entries(vec![entry.clone()])).is_ok());
+
+        for invalid in [
+            Entry {
+                dest: Some("./OTHER.md".into()),
+                ..entry.clone()
+            },
+            Entry {
+                kind: AssetKind::CursorRules,
+                ..entry.clone()
+            },
+            Entry {
+                targets: vec![InstructionTarget::ClaudeMd, InstructionTarget::ClaudeMd],
+                ..entry.clone()
+            },
+        ] {
+            assert!(matches!(
+                validate_manifest(&Manifest::with_entries(vec![invalid.clone()])),
+                Err(ApsError::InvalidTargets { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_validate_skills() {
+        let entry = Entry {
+            id: "skills".to_string(),
+            kind: AssetKind::AgentSkill,
+            source: Some(Source::Filesystem {
+                root: ".".to_string(),
+                symlink: false,
+                path: None,
+            }),
+            skills: vec!["docker".to_string(), "terraform".to_string()],
+            ..Default::default()
+        };
+        assert!(validate_manifest(&Manifest::with_entries(vec![entry.clone()])).is_ok());
+
+        for invalid in [
+            Entry {
+                kind: AssetKind::CursorRules,
+                ..entry.clone()
+            },
+            Entry {
+                skills: vec!["docker".to_string(), "docker".to_string()],
+                ..entry.clone()
+            },
+            Entry {
+                skills: vec!["../secrets".to_string()],
+                ..entry.clone()
+            },
+        ] {
+            assert!(matches!(
+                validate_manifest(&Manifest::with_entries(vec![invalid.clone()])),
+                Err(ApsError::InvalidSkillSelection { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_settings_target() {
+        let entry = Entry {
+            id: "settings".to_string(),
+            kind: AssetKind::ClaudeSettings,
+            sources: vec![Source::Filesystem {
+                root: ".".to_string(),
+                symlink: false,
+                path: Some("base.yaml".to_string()),
+            }
+            .into()],
+            target: SettingsTarget::Local,
+            ..Default::default()
+        };
+        assert_eq!(
+            entry.destination(),
+            PathBuf::from(".claude/settings.local.json")
+        );
+        assert!(validate_manifest(&Manifest::with_entries(vec![entry.clone()])).is_ok());
+
+        for invalid in [
+            Entry {
+                dest: Some("./settings.json".into()),
+                ..entry.clone()
+            },
+            Entry {
+                kind: AssetKind::McpConfig,
+                ..entry.clone()
+            },
+        ] {
+            assert!(matches!(
+                validate_manifest(&Manifest::with_entries(vec![invalid.clone()])),
+                Err(ApsError::InvalidSettingsTarget { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_merge_strategies() {
+        let yaml = r#"
+entries:
+  - id: settings
+    kind: claude_settings
+    merge:
+      permissions.allow: replace
+      model: error-on-conflict
+    sources:
+      - type: filesystem
+        root: .
+        path: base.yaml
+"#;
+        let manifest: Manifest = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            manifest.entries[0].merge.get("model"),
+            Some(&MergeStrategy::ErrorOnConflict)
+        );
+        assert!(validate_manifest(&manifest).is_ok());
+
+        for invalid in [
+            yaml.replace("claude_settings", "composite_agents_md"),
+            yaml.replace("kind: claude_settings", "kind: mcp_config"),
+            yaml.replace("model:", "model..name:"),
+        ] {
+            let manifest: Manifest = serde_yaml::from_str(&invalid).unwrap();
+            assert!(matches!(
+                validate_manifest(&manifest),
+                Err(ApsError::InvalidMergeStrategy { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_file_mode() {
+        for (yaml, mode) in [("mode: \"0755\"", 0o755), ("mode: 644", 0o644)] {
+            let entry: Entry =
+                serde_yaml::from_str(&format!("id: x\nkind: dir\n{}", yaml)).unwrap();
+            assert_eq!(entry.mode, Some(FileMode(mode)));
+        }
+        assert_eq!(
+            serde_yaml::to_string(&FileMode(0o755)).unwrap().trim(),
+            "'0755'"
+        );
+        assert!(serde_yaml::from_str::<Entry>("id: x\nkind: dir\nmode: \"0789\"").is_err());
+        assert!(serde_yaml::from_str::<Entry>("id: x\nkind: dir\nmode: \"77777\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_transforms() {
+        let yaml = r#"
+entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: .
+      path: rules
+    transforms:
+      - strip_frontmatter
+      - replace: { from: Acme, to: Contoso }
+      - prepend_file: header.md
+      - append_file: footer.md
+      - script: fix-links.rhai
+"#;
+        let manifest: Manifest = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            manifest.entries[0].transforms,
+            vec![
+                Transform::StripFrontmatter,
+                Transform::Replace {
+                    from: "Acme".to_string(),
+                    to: "Contoso".to_string()
+                },
+                Transform::PrependFile("header.md".to_string()),
+                Transform::AppendFile("footer.md".to_string()),
+                Transform::Script("fix-links.rhai".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_composite_source_metadata() {
+        let yaml = r#"
+id: agents
+kind: composite_agents_md
+toc: true
+sources:
+  - type: filesystem
+    root: .
+    path: python.md
+    order: 20
+    title: Python
+  - type: filesystem
+    root: .
+    path: intro.md
+    order: 10
+    shift_headings: 1
+  - type: filesystem
+    root: .
+    path: docker.md
+    when:
+      exists: Dockerfile
+"#;
+        let entry: Entry = serde_yaml::from_str(yaml).unwrap();
+        assert!(entry.toc);
+        assert_eq!(entry.sources[0].title.as_deref(), Some("Python"));
+        assert_eq!(entry.sources[1].order, Some(10));
+        assert_eq!(entry.sources[1].shift_headings, Some(1));
+
+        let order: Vec<String> = entry
+            .ordered_sources()
+            .iter()
+            .map(|s| s.source.display_path())
+            .collect();
+        assert_eq!(order, vec!["./docker.md", "./intro.md", "./python.md"]);
+
+        // Metadata round-trips alongside the source fields
+        let written = serde_yaml::to_string(&entry).unwrap();
+        let reparsed: Entry = serde_yaml::from_str(&written).unwrap();
+        assert_eq!(reparsed.sources[0].order, Some(20));
+        assert_eq!(reparsed.sources[0].title.as_deref(), Some("Python"));
+        assert_eq!(reparsed.sources[1].shift_headings, Some(1));
+        assert_eq!(
+            reparsed.sources[2].when.as_ref().unwrap().exists.as_deref(),
+            Some("Dockerfile")
+        );
+
+        // Typos in conditions are rejected rather than ignored
+        let typo = yaml.replace("exists: Dockerfile", "exist: Dockerfile");
+        assert!(serde_yaml::from_str::<Entry>(&typo).is_err());
+        assert!(matches!(
+            reparsed.sources[0].source,
+            Source::Filesystem { .. }
+        ));
+    }
+
+    #[test]
+    fn test_asset_kind_names_round_trip() {
+        for name in [
+            "cursor_rules",
+            "agents_md",
+            "mcp_config",
+            "claude_settings",
+            "prompt_library",
+            "dir",
+        ] {
+            let kind: AssetKind = serde_yaml::from_str(name).unwrap();
+            assert_eq!(kind.as_str(), name);
+            assert_eq!(serde_yaml::to_string(&kind).unwrap().trim(), name);
+        }
+
+        let err = serde_yaml::from_str::<AssetKind>("unknown_kind").unwrap_err();
+        assert!(err.to_string().contains("unknown variant `unknown_kind`"));
+    }
 }
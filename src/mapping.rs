@@ -0,0 +1,141 @@
+//! Renaming source files on install (`map`).
+//!
+//! Entries can list `from`/`to` pairs so upstream files are installed under
+//! the names this project uses (`README.md` as `AGENTS.md`) without forking
+//! the source. Mappings run on a staged copy of the source before `include`
+//! is applied, so include prefixes match the mapped names.
+
+use crate::error::{ApsError, Result};
+use crate::manifest::FileMapping;
+use std::path::{Component, Path, PathBuf};
+use tempfile::TempDir;
+use tracing::debug;
+use walkdir::WalkDir;
+
+/// The path `relative` is installed at: the first mapping whose `from` is
+/// the path itself or one of its parent directories renames that part
+pub fn mapped_path(relative: &Path, map: &[FileMapping]) -> PathBuf {
+    for mapping in map {
+        if let Ok(rest) = relative.strip_prefix(&mapping.from) {
+            // Joining an empty rest would add a trailing separator
+            if rest.as_os_str().is_empty() {
+                return PathBuf::from(&mapping.to);
+            }
+            return Path::new(&mapping.to).join(rest);
+        }
+    }
+    relative.to_path_buf()
+}
+
+/// Whether `path` is a plain relative path that stays inside its root
+pub fn is_plain_relative(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// Copy `source` to a temporary directory with `map` applied. The guard
+/// must be kept alive while the staged copy is in use.
+pub fn stage_mapped(source: &Path, map: &[FileMapping]) -> Result<(TempDir, PathBuf)> {
+    // Validation rejects these too; a mapped path must never leave the staging copy
+    if let Some(mapping) = map.iter().find(|m| !is_plain_relative(&m.to)) {
+        return Err(ApsError::io(
+            std::io::Error::other("mapped path leaves the destination"),
+            format!("Refusing to map to {:?}", mapping.to),
+        ));
+    }
+    let staging =
+        TempDir::new().map_err(|e| ApsError::io(e, "Failed to create staging directory"))?;
+    let name = source.file_name().unwrap_or_else(|| "source".as_ref());
+
+    // A single-file source is renamed as a whole
+    if source.is_file() {
+        let staged = staging.path().join(mapped_path(Path::new(name), map));
+        if let Some(parent) = staged.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ApsError::io(e, format!("Failed to create {:?}", parent)))?;
+        }
+        std::fs::copy(source, &staged)
+            .map_err(|e| ApsError::io(e, format!("Failed to copy {:?}", source)))?;
+        return Ok((staging, staged));
+    }
+
+    let staged = staging.path().join(name);
+    std::fs::create_dir_all(&staged)
+        .map_err(|e| ApsError::io(e, format!("Failed to create {:?}", staged)))?;
+
+    for entry in WalkDir::new(source).follow_links(true) {
+        let entry = entry
+            .map_err(|e| ApsError::io(std::io::Error::other(e), "Failed to traverse source"))?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(source).unwrap_or(entry.path());
+        let mapped = mapped_path(relative, map);
+        if mapped != relative {
+            debug!("Mapping {:?} to {:?}", relative, mapped);
+        }
+        let target = staged.join(&mapped);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ApsError::io(e, format!("Failed to create {:?}", parent)))?;
+        }
+        std::fs::copy(entry.path(), &target)
+            .map_err(|e| ApsError::io(e, format!("Failed to copy {:?}", entry.path())))?;
+    }
+
+    Ok((staging, staged))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn mapping(from: &str, to: &str) -> FileMapping {
+        FileMapping {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_mapped_path() {
+        let map = vec![
+            mapping("README.md", "AGENTS.md"),
+            mapping("rules", "cursor"),
+        ];
+        assert_eq!(
+            mapped_path(Path::new("README.md"), &map),
+            Path::new("AGENTS.md")
+        );
+        assert_eq!(
+            mapped_path(Path::new("rules/style.md"), &map),
+            Path::new("cursor/style.md")
+        );
+        // Prefixes only match whole path components
+        assert_eq!(
+            mapped_path(Path::new("rules-extra/a.md"), &map),
+            Path::new("rules-extra/a.md")
+        );
+    }
+
+    #[test]
+    fn test_stage_mapped_renames_files() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("skills");
+        std::fs::create_dir_all(source.join("review")).unwrap();
+        std::fs::write(source.join("review/README.md"), "# Review").unwrap();
+        std::fs::write(source.join("notes.txt"), "notes").unwrap();
+
+        let map = vec![mapping("review/README.md", "review/SKILL.md")];
+        let (_guard, staged) = stage_mapped(&source, &map).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(staged.join("review/SKILL.md")).unwrap(),
+            "# Review"
+        );
+        assert!(!staged.join("review/README.md").exists());
+        assert!(staged.join("notes.txt").exists());
+    }
+}
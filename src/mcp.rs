@@ -0,0 +1,214 @@
+//! MCP config composition for the `mcp_config` kind.
+//!
+//! Each source is a JSON fragment with an `mcpServers` object. Fragments are
+//! merged into a single `.mcp.json`: servers with the same name and identical
+//! definitions are deduplicated, while conflicting definitions are an error
+//! unless the entry sets a `merge` strategy for them.
+
+use crate::compose::ComposedSource;
+use crate::error::{ApsError, Result};
+use crate::manifest::MergeStrategy;
+use crate::merge::StrategyMerge;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use tracing::debug;
+
+/// Top-level key holding server definitions
+pub const MCP_SERVERS_KEY: &str = "mcpServers";
+
+/// Merge `mcpServers` from each fragment into a single pretty-printed document.
+/// Keys listed in `strategies` are merged with their strategy instead.
+pub fn compose_mcp_config(
+    sources: &[ComposedSource],
+    strategies: &BTreeMap<String, MergeStrategy>,
+) -> Result<String> {
+    if sources.is_empty() {
+        return Err(ApsError::ComposeError {
+            message: "No sources provided for composition".to_string(),
+        });
+    }
+
+    let mut servers: Map<String, Value> = Map::new();
+    // Which fragment first defined each server, for conflict messages
+    let mut origins: HashMap<String, PathBuf> = HashMap::new();
+
+    let mut strategy_merge = StrategyMerge::new(strategies);
+
+    for source in sources {
+        let mut fragment = Map::new();
+        fragment.insert(
+            MCP_SERVERS_KEY.to_string(),
+            Value::Object(parse_fragment(source)?),
+        );
+        strategy_merge.take(&mut fragment, &source.path)?;
+        let remaining = match fragment.remove(MCP_SERVERS_KEY) {
+            Some(Value::Object(servers)) => servers,
+            _ => Map::new(),
+        };
+        for (name, definition) in remaining {
+            match servers.get(&name) {
+                Some(existing) if existing == &definition => {
+                    debug!("Deduplicated MCP server '{}' from {:?}", name, source.path);
+                }
+                Some(_) => {
+                    return Err(ApsError::McpServerConflict {
+                        first: origins.remove(&name).unwrap_or_default(),
+                        name,
+                        second: source.path.clone(),
+                    });
+                }
+                None => {
+                    origins.insert(name.clone(), source.path.clone());
+                    servers.insert(name, definition);
+                }
+            }
+        }
+    }
+
+    let mut root = Map::new();
+    root.insert(MCP_SERVERS_KEY.to_string(), Value::Object(servers));
+    strategy_merge.apply(&mut root);
+
+    let mut content =
+        serde_json::to_string_pretty(&Value::Object(root)).map_err(|e| ApsError::ComposeError {
+            message: format!("Failed to serialize MCP config: {}", e),
+        })?;
+    content.push('\n');
+    Ok(content)
+}
+
+/// Parse a fragment and return its server definitions
+fn parse_fragment(source: &ComposedSource) -> Result<Map<String, Value>> {
+    let invalid = |message: String| ApsError::InvalidMcpConfig {
+        path: source.path.clone(),
+        message,
+    };
+
+    let value: Value = serde_json::from_str(&source.content).map_err(|e| invalid(e.to_string()))?;
+    let Value::Object(mut root) = value else {
+        return Err(invalid("expected a JSON object".to_string()));
+    };
+
+    match root.remove(MCP_SERVERS_KEY) {
+        Some(Value::Object(servers)) => Ok(servers),
+        Some(_) => Err(invalid(format!("'{}' must be an object", MCP_SERVERS_KEY))),
+        None => Err(invalid(format!("missing '{}' object", MCP_SERVERS_KEY))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compose_merges_servers() {
+        let sources = vec![
+            ComposedSource::fragment(
+                "a.json",
+                r#"{"mcpServers": {"github": {"command": "gh-mcp"}}}"#,
+            ),
+            ComposedSource::fragment(
+                "b.json",
+                r#"{"mcpServers": {"linear": {"url": "https://mcp.linear.app"}}}"#,
+            ),
+        ];
+
+        let result: Value =
+            serde_json::from_str(&compose_mcp_config(&sources, &BTreeMap::new()).unwrap()).unwrap();
+        let servers = result[MCP_SERVERS_KEY].as_object().unwrap();
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers["github"]["command"], "gh-mcp");
+        assert_eq!(servers["linear"]["url"], "https://mcp.linear.app");
+    }
+
+    #[test]
+    fn test_compose_dedupes_identical_servers() {
+        let sources = vec![
+            ComposedSource::fragment(
+                "a.json",
+                r#"{"mcpServers": {"github": {"command": "gh-mcp"}}}"#,
+            ),
+            ComposedSource::fragment(
+                "b.json",
+                r#"{"mcpServers": {"github": {"command": "gh-mcp"}}}"#,
+            ),
+        ];
+
+        let result: Value =
+            serde_json::from_str(&compose_mcp_config(&sources, &BTreeMap::new()).unwrap()).unwrap();
+        assert_eq!(result[MCP_SERVERS_KEY].as_object().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_compose_rejects_conflicting_servers() {
+        let sources = vec![
+            ComposedSource::fragment(
+                "a.json",
+                r#"{"mcpServers": {"github": {"command": "gh-mcp"}}}"#,
+            ),
+            ComposedSource::fragment(
+                "b.json",
+                r#"{"mcpServers": {"github": {"command": "other"}}}"#,
+            ),
+        ];
+
+        let err = compose_mcp_config(&sources, &BTreeMap::new()).unwrap_err();
+        match err {
+            ApsError::McpServerConflict {
+                name,
+                first,
+                second,
+            } => {
+                assert_eq!(name, "github");
+                assert_eq!(first, PathBuf::from("a.json"));
+                assert_eq!(second, PathBuf::from("b.json"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_compose_rejects_invalid_fragments() {
+        for content in [
+            "not json",
+            "[]",
+            r#"{"servers": {}}"#,
+            r#"{"mcpServers": []}"#,
+        ] {
+            let err = compose_mcp_config(
+                &[ComposedSource::fragment("bad.json", content)],
+                &BTreeMap::new(),
+            )
+            .unwrap_err();
+            assert!(
+                matches!(err, ApsError::InvalidMcpConfig { .. }),
+                "{}",
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn test_compose_with_union_strategy_lets_later_fragments_win() {
+        let sources = vec![
+            ComposedSource::fragment(
+                "a.json",
+                r#"{"mcpServers": {"github": {"command": "gh-mcp"}, "linear": {"url": "https://mcp.linear.app"}}}"#,
+            ),
+            ComposedSource::fragment(
+                "b.json",
+                r#"{"mcpServers": {"github": {"command": "gh-mcp", "args": ["--readonly"]}}}"#,
+            ),
+        ];
+        let strategies = BTreeMap::from([(MCP_SERVERS_KEY.to_string(), MergeStrategy::Union)]);
+
+        let result: Value =
+            serde_json::from_str(&compose_mcp_config(&sources, &strategies).unwrap()).unwrap();
+        assert_eq!(result["mcpServers"]["github"]["args"][0], "--readonly");
+        assert_eq!(
+            result["mcpServers"]["linear"]["url"],
+            "https://mcp.linear.app"
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Conversion of plain markdown rules into Cursor `.mdc` rules.
+//!
+//! Upstream rule repositories can stay tool-agnostic markdown; entries that set
+//! `mdc` get each `.md` rule installed as `<name>.mdc` with generated
+//! `description`, `globs` and `alwaysApply` frontmatter.
+
+use crate::error::{ApsError, Result};
+use crate::frontmatter;
+use crate::manifest::MdcOptions;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Extension for Cursor rule files
+pub const MDC_EXTENSION: &str = "mdc";
+
+/// Frontmatter keys Cursor reads from a rule
+const MDC_KEYS: &[&str] = &["description", "globs", "alwaysApply"];
+
+/// Destination filename for a rule source (`.md` rules become `.mdc`)
+pub fn mdc_file_name(name: &str) -> String {
+    match name.strip_suffix(".md") {
+        Some(stem) => format!("{}.{}", stem, MDC_EXTENSION),
+        None => name.to_string(),
+    }
+}
+
+/// Convert a markdown rule into `.mdc` content.
+///
+/// Entry options take precedence over the rule's own frontmatter. Without
+/// either, the description falls back to the first heading or line.
+pub fn convert_to_mdc(content: &str, options: &MdcOptions) -> String {
+    let existing = frontmatter::parse(content);
+    let body = frontmatter::split(content).map_or(content, |(_, body)| body);
+    let existing_str = |key: &str| {
+        existing
+            .as_ref()
+            .and_then(|fm| frontmatter::get_str(fm, key))
+            .map(str::to_string)
+    };
+
+    let description = options
+        .description
+        .clone()
+        .or_else(|| existing_str("description"))
+        .or_else(|| first_line_summary(body))
+        .unwrap_or_default();
+
+    let globs = match &options.globs {
+        Some(globs) => globs.join(","),
+        None => existing_str("globs").unwrap_or_default(),
+    };
+
+    let always_apply = options.always_apply.unwrap_or_else(|| {
+        existing
+            .as_ref()
+            .and_then(|fm| fm.get("alwaysApply"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    });
+
+    // Cursor expects globs as a bare comma-separated list, so the frontmatter
+    // is written by hand rather than through a YAML serializer
+    format!(
+        "---\ndescription: {}\nglobs: {}\nalwaysApply: {}\n---\n\n{}",
+        description.replace('\n', " "),
+        globs,
+        always_apply,
+        body.trim_start()
+    )
+}
+
+/// Install rule files from `source` into `dest`, converting `.md` rules to `.mdc`.
+/// Other files are copied unchanged. Returns the installed paths.
+pub fn install_mdc_rules(
+    source: &Path,
+    dest: &Path,
+    include: &[String],
+    options: &MdcOptions,
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dest)
+        .map_err(|e| ApsError::io(e, format!("Failed to create directory {:?}", dest)))?;
+
+    let mut installed = Vec::new();
+    for file in rule_files(source, include)? {
+        let name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let target = dest.join(mdc_file_name(&name));
+
+        // Replace symlinks left by a previous non-converting sync
+        if target
+            .symlink_metadata()
+            .is_ok_and(|m| m.file_type().is_symlink())
+        {
+            std::fs::remove_file(&target)
+                .map_err(|e| ApsError::io(e, format!("Failed to remove {:?}", target)))?;
+        }
+
+        if name.ends_with(".md") {
+            let content = std::fs::read_to_string(&file)
+                .map_err(|e| ApsError::io(e, format!("Failed to read rule {:?}", file)))?;
+            std::fs::write(&target, convert_to_mdc(&content, options))
+                .map_err(|e| ApsError::io(e, format!("Failed to write {:?}", target)))?;
+            debug!("Converted {:?} to {:?}", file, target);
+        } else {
+            std::fs::copy(&file, &target)
+                .map_err(|e| ApsError::io(e, format!("Failed to copy {:?}", file)))?;
+        }
+        installed.push(target);
+    }
+
+    Ok(installed)
+}
+
+/// Rule files in a source, which may be a single file or a flat directory
+pub fn rule_files(source: &Path, include: &[String]) -> Result<Vec<PathBuf>> {
+    if source.is_file() {
+        return Ok(vec![source.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(source)
+        .map_err(|e| ApsError::io(e, format!("Failed to read directory {:?}", source)))?
+    {
+        let entry = entry.map_err(|e| ApsError::io(e, "Failed to read directory entry"))?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_file() && (include.is_empty() || include.iter().any(|p| name.starts_with(p))) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Problems with an `.mdc` rule's frontmatter that would make Cursor ignore
+/// or misapply the rule. Rules without frontmatter have none.
+pub fn mdc_problems(content: &str) -> Vec<String> {
+    let Some((raw, _)) = frontmatter::split(content) else {
+        return Vec::new();
+    };
+    let mut problems = Vec::new();
+
+    // Cursor writes globs as a bare comma-separated list (`globs: *.ts,*.tsx`),
+    // which isn't valid YAML on its own, so that line is read separately
+    let mut globs = None;
+    let mut yaml = String::new();
+    for line in raw.lines() {
+        match line.strip_prefix("globs:") {
+            Some(value) => globs = Some(value.trim()),
+            None => {
+                yaml.push_str(line);
+                yaml.push('\n');
+            }
+        }
+    }
+
+    let fields = match serde_yaml::from_str::<serde_yaml::Value>(&yaml) {
+        Ok(serde_yaml::Value::Mapping(fields)) => fields,
+        Ok(serde_yaml::Value::Null) => serde_yaml::Mapping::new(),
+        Ok(_) => return vec!["frontmatter is not a mapping".to_string()],
+        Err(e) => return vec![format!("frontmatter is not valid YAML: {}", e)],
+    };
+    for key in fields.keys() {
+        let key = key.as_str().unwrap_or_default();
+        if !MDC_KEYS.contains(&key) {
+            problems.push(format!(
+                "unknown frontmatter key `{}` (Cursor reads {})",
+                key,
+                MDC_KEYS.join(", ")
+            ));
+        }
+    }
+    if fields
+        .get("description")
+        .is_some_and(|d| !d.is_string() && !d.is_null())
+    {
+        problems.push("`description` must be a string".to_string());
+    }
+    if fields
+        .get("alwaysApply")
+        .is_some_and(|a| !a.is_bool() && !a.is_null())
+    {
+        problems.push("`alwaysApply` must be true or false".to_string());
+    }
+    for glob in globs.map(glob_list).unwrap_or_default() {
+        if let Err(e) = globset::Glob::new(&glob) {
+            problems.push(format!("invalid glob '{}': {}", glob, e.kind()));
+        }
+    }
+    problems
+}
+
+/// Globs of a `globs:` value: a comma-separated list, quoted or not, or a
+/// YAML sequence
+fn glob_list(value: &str) -> Vec<String> {
+    let items: Vec<String> = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        Some(inner) => inner.split(',').map(str::to_string).collect(),
+        None => value
+            .trim_matches(|c| c == '"' || c == '\'')
+            .split(',')
+            .map(str::to_string)
+            .collect(),
+    };
+    items
+        .iter()
+        .map(|glob| {
+            glob.trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string()
+        })
+        .filter(|glob| !glob.is_empty())
+        .collect()
+}
+
+/// First heading (or first non-empty line) of a markdown body
+fn first_line_summary(body: &str) -> Option<String> {
+    body.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(|line| line.trim_start_matches('#').trim().to_string())
+        .filter(|line| !line.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mdc_file_name() {
+        assert_eq!(mdc_file_name("python.md"), "python.mdc");
+        assert_eq!(mdc_file_name("python.mdc"), "python.mdc");
+        assert_eq!(mdc_file_name("notes.txt"), "notes.txt");
+    }
+
+    #[test]
+    fn test_mdc_problems() {
+        let valid = "---\ndescription: Python style\nglobs: **/*.py,src/**/*.pyi\nalwaysApply: false\n---\nBody\n";
+        assert!(mdc_problems(valid).is_empty());
+        assert!(mdc_problems("---\nglobs: [\"*.ts\", \"*.tsx\"]\n---\n").is_empty());
+        assert!(mdc_problems("# No frontmatter\n").is_empty());
+
+        let problems = mdc_problems("---\ndescription: Style\nglob: *.py\n---\n");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("frontmatter is not valid YAML"));
+
+        let invalid = "---\nalways_apply: true\nalwaysApply: \"true\"\nglobs: src/[a-z.py\n---\n";
+        assert_eq!(
+            mdc_problems(invalid),
+            vec![
+                "unknown frontmatter key `always_apply` (Cursor reads description, globs, alwaysApply)",
+                "`alwaysApply` must be true or false",
+                "invalid glob 'src/[a-z.py': unclosed character class; missing ']'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_convert_plain_markdown() {
+        let options = MdcOptions {
+            globs: Some(vec!["**/*.py".into(), "**/*.pyi".into()]),
+            ..Default::default()
+        };
+        let mdc = convert_to_mdc("# Python Style\n\nUse type hints.\n", &options);
+        assert_eq!(
+            mdc,
+            "---\ndescription: Python Style\nglobs: **/*.py,**/*.pyi\nalwaysApply: false\n---\n\n# Python Style\n\nUse type hints.\n"
+        );
+    }
+
+    #[test]
+    fn test_convert_options_override_frontmatter() {
+        let source = "---\ndescription: From file\nalwaysApply: true\n---\nBody\n";
+
+        let mdc = convert_to_mdc(source, &MdcOptions::default());
+        assert!(mdc.contains("description: From file\n"));
+        assert!(mdc.contains("alwaysApply: true\n"));
+        assert!(mdc.ends_with("---\n\nBody\n"));
+
+        let options = MdcOptions {
+            description: Some("From entry".into()),
+            always_apply: Some(false),
+            ..Default::default()
+        };
+        let mdc = convert_to_mdc(source, &options);
+        assert!(mdc.contains("description: From entry\n"));
+        assert!(mdc.contains("alwaysApply: false\n"));
+    }
+}
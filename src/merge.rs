@@ -0,0 +1,237 @@
+//! Per-key merge strategies for JSON composition kinds.
+//!
+//! Entries can override how fragments are combined for specific keys, written
+//! as dotted paths into the output document (`permissions.allow`, `env`,
+//! `mcpServers.github`). Configured keys are taken out of each fragment before
+//! the kind's default merge runs, merged with their strategy, and written back
+//! into the composed output.
+
+use crate::error::{ApsError, Result};
+use crate::manifest::MergeStrategy;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Combine `incoming` into `current` with `strategy`. Returns an error message
+/// when `error_on_conflict` finds differing values.
+pub fn merge_value(
+    current: &mut Value,
+    incoming: Value,
+    strategy: MergeStrategy,
+) -> std::result::Result<(), String> {
+    match (strategy, current, incoming) {
+        (MergeStrategy::Replace, current, incoming) => *current = incoming,
+        (MergeStrategy::Append, Value::Array(current), Value::Array(incoming)) => {
+            current.extend(incoming)
+        }
+        (MergeStrategy::Union, Value::Array(current), Value::Array(incoming)) => {
+            for item in incoming {
+                if !current.contains(&item) {
+                    current.push(item);
+                }
+            }
+        }
+        (
+            MergeStrategy::Append | MergeStrategy::Union,
+            Value::Object(current),
+            Value::Object(incoming),
+        ) => current.extend(incoming),
+        (MergeStrategy::ErrorOnConflict, Value::Object(current), Value::Object(incoming)) => {
+            for (key, value) in incoming {
+                match current.get(&key) {
+                    Some(existing) if existing != &value => {
+                        return Err(format!("'{}' is set to different values", key))
+                    }
+                    Some(_) => {}
+                    None => {
+                        current.insert(key, value);
+                    }
+                }
+            }
+        }
+        (MergeStrategy::ErrorOnConflict, current, incoming) => {
+            if *current != incoming {
+                return Err("set to different values".to_string());
+            }
+        }
+        // Mismatched shapes can't be combined; the later fragment wins
+        (_, current, incoming) => *current = incoming,
+    }
+    Ok(())
+}
+
+/// Remove the value at a dotted `path` from `map`
+fn take_path(map: &mut Map<String, Value>, path: &str) -> Option<Value> {
+    match path.split_once('.') {
+        Some((head, rest)) => take_path(map.get_mut(head)?.as_object_mut()?, rest),
+        None => map.remove(path),
+    }
+}
+
+/// Set the value at a dotted `path` in `map`, creating objects along the way
+fn insert_path(map: &mut Map<String, Value>, path: &str, value: Value) {
+    match path.split_once('.') {
+        Some((head, rest)) => {
+            let child = map.entry(head).or_insert_with(|| Value::Object(Map::new()));
+            if !child.is_object() {
+                *child = Value::Object(Map::new());
+            }
+            if let Value::Object(child) = child {
+                insert_path(child, rest, value);
+            }
+        }
+        None => {
+            map.insert(path.to_string(), value);
+        }
+    }
+}
+
+/// Accumulates the keys an entry merges with an explicit strategy
+pub struct StrategyMerge<'a> {
+    strategies: &'a BTreeMap<String, MergeStrategy>,
+    /// Merged value and the fragment that first set it, per configured key
+    merged: BTreeMap<&'a str, (Value, PathBuf)>,
+}
+
+impl<'a> StrategyMerge<'a> {
+    pub fn new(strategies: &'a BTreeMap<String, MergeStrategy>) -> Self {
+        Self {
+            strategies,
+            merged: BTreeMap::new(),
+        }
+    }
+
+    /// Take the configured keys out of a fragment and merge them
+    pub fn take(&mut self, fragment: &mut Map<String, Value>, path: &Path) -> Result<()> {
+        // Deeper keys first, so `permissions.allow` is taken before `permissions`
+        let mut keys: Vec<(&'a String, &'a MergeStrategy)> = self.strategies.iter().collect();
+        keys.sort_by_key(|(key, _)| std::cmp::Reverse(key.matches('.').count()));
+
+        for (key, strategy) in keys {
+            let Some(value) = take_path(fragment, key) else {
+                continue;
+            };
+            match self.merged.get_mut(key.as_str()) {
+                Some((current, first)) => {
+                    merge_value(current, value, *strategy).map_err(|message| {
+                        ApsError::MergeConflict {
+                            key: key.clone(),
+                            message,
+                            first: first.clone(),
+                            second: path.to_path_buf(),
+                        }
+                    })?
+                }
+                None => {
+                    self.merged.insert(key, (value, path.to_path_buf()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the merged keys into the composed output
+    pub fn apply(self, output: &mut Map<String, Value>) {
+        let mut merged: Vec<_> = self.merged.into_iter().collect();
+        merged.sort_by_key(|(key, _)| key.matches('.').count());
+        for (key, (value, _)) in merged {
+            insert_path(output, key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn merge(strategy: MergeStrategy, a: Value, b: Value) -> std::result::Result<Value, String> {
+        let mut current = a;
+        merge_value(&mut current, b, strategy)?;
+        Ok(current)
+    }
+
+    #[test]
+    fn test_merge_value_strategies() {
+        assert_eq!(
+            merge(MergeStrategy::Append, json!(["a", "b"]), json!(["b", "c"])).unwrap(),
+            json!(["a", "b", "b", "c"])
+        );
+        assert_eq!(
+            merge(MergeStrategy::Union, json!(["a", "b"]), json!(["b", "c"])).unwrap(),
+            json!(["a", "b", "c"])
+        );
+        assert_eq!(
+            merge(
+                MergeStrategy::Union,
+                json!({"a": 1, "b": 1}),
+                json!({"b": 2})
+            )
+            .unwrap(),
+            json!({"a": 1, "b": 2})
+        );
+        assert_eq!(
+            merge(MergeStrategy::Replace, json!(["a"]), json!(["b"])).unwrap(),
+            json!(["b"])
+        );
+        assert_eq!(
+            merge(
+                MergeStrategy::ErrorOnConflict,
+                json!({"a": 1}),
+                json!({"a": 1, "b": 2})
+            )
+            .unwrap(),
+            json!({"a": 1, "b": 2})
+        );
+        assert!(merge(
+            MergeStrategy::ErrorOnConflict,
+            json!({"a": 1}),
+            json!({"a": 2})
+        )
+        .is_err());
+        assert!(merge(
+            MergeStrategy::ErrorOnConflict,
+            json!("opus"),
+            json!("sonnet")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_strategy_merge_takes_and_applies_paths() {
+        let strategies = BTreeMap::from([
+            ("permissions.allow".to_string(), MergeStrategy::Replace),
+            ("model".to_string(), MergeStrategy::ErrorOnConflict),
+        ]);
+        let mut merge = StrategyMerge::new(&strategies);
+
+        let mut first =
+            json!({"permissions": {"allow": ["Read"], "deny": ["Bash"]}, "model": "opus"});
+        let mut second = json!({"permissions": {"allow": ["Edit"]}, "model": "opus"});
+        merge
+            .take(first.as_object_mut().unwrap(), Path::new("a.yaml"))
+            .unwrap();
+        merge
+            .take(second.as_object_mut().unwrap(), Path::new("b.yaml"))
+            .unwrap();
+        assert_eq!(first, json!({"permissions": {"deny": ["Bash"]}}));
+
+        let mut output = first.as_object().unwrap().clone();
+        merge.apply(&mut output);
+        assert_eq!(
+            Value::Object(output),
+            json!({"permissions": {"allow": ["Edit"], "deny": ["Bash"]}, "model": "opus"})
+        );
+
+        let mut merge = StrategyMerge::new(&strategies);
+        let mut first = json!({"model": "opus"});
+        let mut second = json!({"model": "sonnet"});
+        merge
+            .take(first.as_object_mut().unwrap(), Path::new("a.yaml"))
+            .unwrap();
+        let err = merge
+            .take(second.as_object_mut().unwrap(), Path::new("b.yaml"))
+            .unwrap_err();
+        assert!(matches!(err, ApsError::MergeConflict { ref key, .. } if key == "model"));
+    }
+}
@@ -0,0 +1,139 @@
+//! Three-way merge of managed markdown regions (`aps sync --merge`).
+//!
+//! When the managed region of an installed file was edited locally and
+//! upstream changed too, the edits are merged diff3-style: both sides are
+//! diffed line by line against the content aps last installed (the base),
+//! changes made on only one side are taken, and overlapping changes that
+//! differ are written between conflict markers.
+
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+/// Label on the local side of a conflict
+const LOCAL_LABEL: &str = "local";
+
+/// Label on the upstream side of a conflict
+const UPSTREAM_LABEL: &str = "upstream";
+
+/// Result of a three-way merge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merged {
+    pub text: String,
+    /// Number of conflicting hunks written with markers
+    pub conflicts: usize,
+}
+
+/// For each base line, the line of `other` it is unchanged in, if any
+fn matches(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let mut matched = vec![None; base.len()];
+    for op in capture_diff_slices(Algorithm::Myers, base, other) {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            for offset in 0..len {
+                matched[old_index + offset] = Some(new_index + offset);
+            }
+        }
+    }
+    matched
+}
+
+/// Append lines to `out`, ending the last one with a newline
+fn push_lines(out: &mut String, lines: &[&str]) {
+    for line in lines {
+        out.push_str(line);
+    }
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Merge the `local` and `upstream` edits of `base`
+pub fn merge3(base: &str, local: &str, upstream: &str) -> Merged {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let local: Vec<&str> = local.split_inclusive('\n').collect();
+    let upstream: Vec<&str> = upstream.split_inclusive('\n').collect();
+    let in_local = matches(&base, &local);
+    let in_upstream = matches(&base, &upstream);
+
+    let mut merged = Merged {
+        text: String::new(),
+        conflicts: 0,
+    };
+    let (mut o, mut a, mut b) = (0, 0, 0);
+    loop {
+        // Lines unchanged on both sides
+        let mut stable = 0;
+        while o + stable < base.len()
+            && in_local[o + stable] == Some(a + stable)
+            && in_upstream[o + stable] == Some(b + stable)
+        {
+            stable += 1;
+        }
+        if stable > 0 {
+            push_lines(&mut merged.text, &base[o..o + stable]);
+            (o, a, b) = (o + stable, a + stable, b + stable);
+            continue;
+        }
+        if o == base.len() && a == local.len() && b == upstream.len() {
+            break;
+        }
+
+        // The changed hunk runs up to the next base line both sides kept
+        let next = (o..base.len()).find_map(|i| Some((i, in_local[i]?, in_upstream[i]?)));
+        let (o_end, a_end, b_end) = next.unwrap_or((base.len(), local.len(), upstream.len()));
+        let (base_hunk, local_hunk, upstream_hunk) =
+            (&base[o..o_end], &local[a..a_end], &upstream[b..b_end]);
+
+        if local_hunk == base_hunk || local_hunk == upstream_hunk {
+            push_lines(&mut merged.text, upstream_hunk);
+        } else if upstream_hunk == base_hunk {
+            push_lines(&mut merged.text, local_hunk);
+        } else {
+            merged.conflicts += 1;
+            merged.text.push_str(&format!("<<<<<<< {}\n", LOCAL_LABEL));
+            push_lines(&mut merged.text, local_hunk);
+            merged.text.push_str("=======\n");
+            push_lines(&mut merged.text, upstream_hunk);
+            merged
+                .text
+                .push_str(&format!(">>>>>>> {}\n", UPSTREAM_LABEL));
+        }
+        (o, a, b) = (o_end, a_end, b_end);
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge3_combines_separate_edits() {
+        let base = "# Rules\n\none\ntwo\nthree\nfour\n";
+        let local = "# Rules\n\none (local)\ntwo\nthree\nfour\n";
+        let upstream = "# Rules\n\none\ntwo\nthree\nfour\nfive\n";
+        assert_eq!(
+            merge3(base, local, upstream),
+            Merged {
+                text: "# Rules\n\none (local)\ntwo\nthree\nfour\nfive\n".to_string(),
+                conflicts: 0,
+            }
+        );
+
+        // Identical edits on both sides aren't a conflict
+        assert_eq!(merge3(base, upstream, upstream).text, upstream);
+    }
+
+    #[test]
+    fn test_merge3_marks_conflicts() {
+        let merged = merge3("a\nb\nc\n", "a\nlocal\nc\n", "a\nupstream\nc\n");
+        assert_eq!(merged.conflicts, 1);
+        assert_eq!(
+            merged.text,
+            "a\n<<<<<<< local\nlocal\n=======\nupstream\n>>>>>>> upstream\nc\n"
+        );
+    }
+}
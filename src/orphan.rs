@@ -1,11 +1,13 @@
-use crate::backup::create_backup;
+use crate::backup::{create_backup, BackupMode};
+use crate::claude_settings::is_managed_settings_path;
+use crate::confirm::{confirm, PromptMode};
 use crate::error::{ApsError, Result};
-use crate::install::InstallOptions;
+use crate::install::{remove_installed_files, InstallOptions};
 use crate::lockfile::Lockfile;
 use crate::manifest::Entry;
+use crate::output::message;
 use console::{style, Style};
-use dialoguer::Confirm;
-use std::io::IsTerminal;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
@@ -14,6 +16,8 @@ pub struct OrphanedPath {
     pub entry_id: String,
     pub old_dest: PathBuf,
     pub new_dest: PathBuf,
+    /// Files the previous install recorded under `old_dest`
+    pub files: BTreeMap<String, String>,
 }
 
 /// Detect orphaned paths by comparing lockfile destinations with current manifest destinations
@@ -42,6 +46,15 @@ pub fn detect_orphaned_paths(
 
             // Check if destinations are different
             if old_normalized != new_normalized {
+                if is_managed_settings_path(&old_dest) {
+                    message!(
+                        "Keeping {:?} (entry '{}' no longer writes it): aps never deletes the managed settings file",
+                        old_dest,
+                        entry.id
+                    );
+                    continue;
+                }
+
                 // Check if old path still exists
                 if old_dest.exists() || old_dest.symlink_metadata().is_ok() {
                     // Check if paths overlap (don't delete new dest!)
@@ -62,6 +75,7 @@ pub fn detect_orphaned_paths(
                         entry_id: entry.id.clone(),
                         old_dest,
                         new_dest,
+                        files: locked_entry.files.clone(),
                     });
                 } else {
                     debug!(
@@ -192,45 +206,42 @@ pub fn prompt_and_cleanup_orphans(
     }
 
     // Print orphan list with highlighted diffs
-    println!();
-    println!(
+    message!();
+    message!(
         "Detected {} orphaned path(s) from destination changes:",
         orphans.len()
     );
     for orphan in orphans {
         let (old_formatted, new_formatted) = format_path_diff(&orphan.old_dest, &orphan.new_dest);
-        println!(
+        message!(
             "  {} {}",
             style("─").dim(),
             style(&orphan.entry_id).cyan().bold()
         );
-        println!("      {} {}", style("was:").red(), old_formatted);
-        println!("      {} {}", style("now:").green(), new_formatted);
+        message!("      {} {}", style("was:").red(), old_formatted);
+        message!("      {} {}", style("now:").green(), new_formatted);
     }
-    println!();
+    message!();
 
     // Handle dry-run mode
     if options.dry_run {
-        println!("[dry-run] Would delete {} orphaned path(s)", orphans.len());
+        message!("[dry-run] Would delete {} orphaned path(s)", orphans.len());
         return Ok(0);
     }
 
     // Determine whether to proceed with deletion
-    let should_delete = if options.yes {
-        true
-    } else if std::io::stdin().is_terminal() {
-        // Interactive prompt
-        Confirm::new()
-            .with_prompt(format!("Delete {} orphaned path(s)?", orphans.len()))
-            .default(false)
-            .interact()
-            .map_err(|_| ApsError::Cancelled)?
-    } else {
-        // Non-interactive without --yes flag
-        println!("Warning: Cannot delete orphaned paths without confirmation.");
-        println!("Run with --yes to auto-delete, or run interactively to confirm.");
+    // Without a terminal, orphans are kept rather than failing a sync whose
+    // entries are already installed
+    if options.prompts == PromptMode::Ask && !options.prompts.attended() {
+        message!("Warning: Cannot delete orphaned paths without confirmation.");
+        message!("Run with --yes to auto-delete, or run interactively to confirm.");
         return Ok(0);
-    };
+    }
+    let should_delete = confirm(
+        options.prompts,
+        &format!("Delete {} orphaned path(s)?", orphans.len()),
+        false,
+    )?;
 
     if !should_delete {
         info!("User declined to delete orphaned paths");
@@ -240,13 +251,13 @@ pub fn prompt_and_cleanup_orphans(
     // Delete orphans
     let mut deleted_count = 0;
     for orphan in orphans {
-        match delete_orphan(orphan, manifest_dir) {
+        match delete_orphan(orphan, manifest_dir, options.backup) {
             Ok(()) => {
                 deleted_count += 1;
-                println!("Deleted orphaned path: {:?}", orphan.old_dest);
+                message!("Deleted orphaned path: {:?}", orphan.old_dest);
             }
             Err(e) => {
-                println!("Warning: Failed to delete {:?}: {}", orphan.old_dest, e);
+                message!("Warning: Failed to delete {:?}: {}", orphan.old_dest, e);
             }
         }
     }
@@ -255,9 +266,25 @@ pub fn prompt_and_cleanup_orphans(
 }
 
 /// Delete a single orphaned path
-fn delete_orphan(orphan: &OrphanedPath, manifest_dir: &Path) -> Result<()> {
-    let path = &orphan.old_dest;
+fn delete_orphan(orphan: &OrphanedPath, manifest_dir: &Path, backup: BackupMode) -> Result<()> {
+    delete_installed(
+        &orphan.entry_id,
+        backup,
+        &orphan.old_dest,
+        &orphan.files,
+        manifest_dir,
+    )
+}
 
+/// Delete an installed destination, backing up any non-symlink content first.
+/// With a recorded file list, only those files are removed from a directory.
+fn delete_installed(
+    entry_id: &str,
+    backup: BackupMode,
+    path: &Path,
+    files: &BTreeMap<String, String>,
+    manifest_dir: &Path,
+) -> Result<()> {
     // Check if it's a symlink
     let is_symlink = path
         .symlink_metadata()
@@ -271,23 +298,32 @@ fn delete_orphan(orphan: &OrphanedPath, manifest_dir: &Path) -> Result<()> {
         debug!("Removed symlink at {:?}", path);
     } else if path.is_file() {
         // Regular file - backup first
-        let backup_path = create_backup(manifest_dir, path)?;
-        println!("  Backed up to: {:?}", backup_path);
+        let backup_path = create_backup(manifest_dir, path, entry_id, backup)?;
+        message!("  Backed up to: {:?}", backup_path);
 
         std::fs::remove_file(path)
             .map_err(|e| ApsError::io(e, format!("Failed to remove file {:?}", path)))?;
         debug!("Removed file at {:?}", path);
     } else if path.is_dir() {
-        // Check if directory contains only symlinks (aps-managed)
-        if is_aps_managed_directory(path) {
+        if !files.is_empty() {
+            // Only the recorded files are removed; anything added by hand stays
+            let backup_path = create_backup(manifest_dir, path, entry_id, backup)?;
+            message!("  Backed up to: {:?}", backup_path);
+
+            remove_installed_files(manifest_dir, path, files)?;
+            if std::fs::remove_dir(path).is_err() {
+                debug!("Kept {:?}: it still holds files aps didn't install", path);
+            }
+        } else if is_aps_managed_directory(path) {
+            // Directory contains only symlinks (aps-managed)
             // Safe to delete without backup
             std::fs::remove_dir_all(path)
                 .map_err(|e| ApsError::io(e, format!("Failed to remove directory {:?}", path)))?;
             debug!("Removed aps-managed directory at {:?}", path);
         } else {
             // Directory with non-symlink content - backup first
-            let backup_path = create_backup(manifest_dir, path)?;
-            println!("  Backed up to: {:?}", backup_path);
+            let backup_path = create_backup(manifest_dir, path, entry_id, backup)?;
+            message!("  Backed up to: {:?}", backup_path);
 
             std::fs::remove_dir_all(path)
                 .map_err(|e| ApsError::io(e, format!("Failed to remove directory {:?}", path)))?;
@@ -298,6 +334,94 @@ fn delete_orphan(orphan: &OrphanedPath, manifest_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Destination of a locked entry that is no longer in the manifest
+pub struct StaleDestination {
+    pub entry_id: String,
+    pub dest: PathBuf,
+    /// Files the install recorded under `dest`
+    pub files: BTreeMap<String, String>,
+}
+
+/// Find lockfile records without a manifest entry whose destination still
+/// exists. Destinations overlapping a current entry's are left alone, since
+/// that content is still installed (e.g. an entry renamed in place).
+pub fn detect_stale_destinations(
+    entries: &[Entry],
+    lockfile: &Lockfile,
+    manifest_dir: &Path,
+) -> Vec<StaleDestination> {
+    let mut stale: Vec<StaleDestination> = lockfile
+        .entries
+        .iter()
+        .filter(|(id, _)| !entries.iter().any(|e| &e.id == *id))
+        .map(|(id, locked)| StaleDestination {
+            entry_id: id.clone(),
+            dest: manifest_dir.join(&locked.dest),
+            files: locked.files.clone(),
+        })
+        .filter(|stale| stale.dest.symlink_metadata().is_ok())
+        .filter(|stale| {
+            let managed = is_managed_settings_path(&stale.dest);
+            if managed {
+                message!(
+                    "Keeping {:?} (entry '{}' was removed from the manifest): aps never deletes the managed settings file",
+                    stale.dest,
+                    stale.entry_id
+                );
+            }
+            !managed
+        })
+        .filter(|stale| {
+            !entries
+                .iter()
+                .any(|e| paths_overlap(&stale.dest, &manifest_dir.join(e.destination())))
+        })
+        .collect();
+    stale.sort_by(|a, b| a.entry_id.cmp(&b.entry_id));
+    stale
+}
+
+/// Delete the destinations of entries removed from the manifest. Returns the
+/// number of destinations deleted.
+pub fn remove_stale_destinations(
+    stale: &[StaleDestination],
+    dry_run: bool,
+    manifest_dir: &Path,
+    backup: BackupMode,
+) -> Result<usize> {
+    let mut deleted_count = 0;
+    for entry in stale {
+        if dry_run {
+            message!(
+                "[dry-run] Would delete {:?} (entry '{}' was removed from the manifest)",
+                entry.dest,
+                entry.entry_id
+            );
+            continue;
+        }
+        match delete_installed(
+            &entry.entry_id,
+            backup,
+            &entry.dest,
+            &entry.files,
+            manifest_dir,
+        ) {
+            Ok(()) => {
+                deleted_count += 1;
+                message!(
+                    "Deleted {:?} (entry '{}' was removed from the manifest)",
+                    entry.dest,
+                    entry.entry_id
+                );
+            }
+            Err(e) => {
+                message!("Warning: Failed to delete {:?}: {}", entry.dest, e);
+            }
+        }
+    }
+    Ok(deleted_count)
+}
+
 /// Check if a directory contains only symlinks (indicating it was created by aps)
 fn is_aps_managed_directory(dir_path: &Path) -> bool {
     match std::fs::read_dir(dir_path) {
@@ -0,0 +1,43 @@
+//! Where progress messages go.
+//!
+//! Commands report what they do as they go: backups made, orphans deleted,
+//! `.gitignore` updated. When stdout carries a machine-readable result
+//! instead (`aps sync --format json`), those messages move to stderr so the
+//! result can be piped as is.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Send messages (and log output) to stderr for the rest of the command
+pub fn messages_to_stderr() {
+    TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Whether messages currently go to stderr
+pub fn to_stderr() -> bool {
+    TO_STDERR.load(Ordering::Relaxed)
+}
+
+/// Writer for log output, following messages
+pub fn log_writer() -> Box<dyn Write> {
+    if to_stderr() {
+        Box::new(std::io::stderr())
+    } else {
+        Box::new(std::io::stdout())
+    }
+}
+
+/// `println!` for progress messages: stdout, or stderr once stdout carries a
+/// machine-readable result
+macro_rules! message {
+    ($($arg:tt)*) => {
+        if $crate::output::to_stderr() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+pub(crate) use message;
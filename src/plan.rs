@@ -0,0 +1,182 @@
+//! Reviewable sync plans (`aps plan` / `aps apply`).
+//!
+//! A plan records what a sync would do without writing anything: each
+//! entry's action, the commit and checksum it would be locked to, whether it
+//! overwrites existing content, and a diff of the change. Applying the plan
+//! installs exactly those locked versions, and refuses to run if the
+//! manifest, the lockfile or the planned content changed in the meantime.
+
+use crate::checksum::compute_string_checksum;
+use crate::error::{ApsError, Result};
+use crate::lockfile::LockedEntry;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Version of the plan file format
+pub const PLAN_VERSION: u32 = 1;
+
+/// What applying a plan does to an entry
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlannedAction {
+    Install,
+    Unchanged,
+}
+
+/// One entry of a plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedEntry {
+    pub id: String,
+    pub action: PlannedAction,
+    pub dest: String,
+    /// Commit the entry is installed from (git sources)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Whether installing overwrites existing content (backed up first)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub conflict: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// The lockfile record the entry will have once applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked: Option<LockedEntry>,
+}
+
+/// Everything a sync would do, as computed by `aps plan`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Plan {
+    pub version: u32,
+    pub aps_version: String,
+    /// Hash of the manifest the plan was computed from
+    pub manifest_hash: String,
+    /// Checksum of the lockfile the plan was computed against, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lockfile_checksum: Option<String>,
+    /// Entry IDs the plan was limited to with `--only`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only: Vec<String>,
+    #[serde(default)]
+    pub upgrade: bool,
+    pub entries: Vec<PlannedEntry>,
+}
+
+impl Plan {
+    /// Read a plan written by `aps plan --out`
+    pub fn load(path: &Path) -> Result<Self> {
+        let invalid = |message: String| ApsError::InvalidPlan {
+            path: path.to_path_buf(),
+            message,
+        };
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ApsError::io(e, format!("Failed to read plan at {:?}", path)))?;
+        let plan: Plan = serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+        if plan.version > PLAN_VERSION {
+            return Err(invalid(format!(
+                "plan format version {} is newer than this aps supports ({})",
+                plan.version, PLAN_VERSION
+            )));
+        }
+        Ok(plan)
+    }
+
+    /// Write the plan as JSON
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| ApsError::InvalidPlan {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })? + "\n";
+        std::fs::write(path, content)
+            .map_err(|e| ApsError::io(e, format!("Failed to write plan to {:?}", path)))
+    }
+
+    /// Fail unless the manifest and lockfile are still the ones the plan was
+    /// computed from
+    pub fn check_current(
+        &self,
+        manifest_hash: &str,
+        lockfile_checksum: Option<&str>,
+    ) -> Result<()> {
+        if self.manifest_hash != manifest_hash {
+            return Err(ApsError::PlanStale {
+                message: "the manifest changed after the plan was made".to_string(),
+            });
+        }
+        if self.lockfile_checksum.as_deref() != lockfile_checksum {
+            return Err(ApsError::PlanStale {
+                message: "the lockfile changed after the plan was made".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Checksum of the lockfile at `path`, or `None` if there is none
+pub fn lockfile_checksum(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|content| compute_string_checksum(&content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan() -> Plan {
+        Plan {
+            version: PLAN_VERSION,
+            aps_version: "0.1.12".to_string(),
+            manifest_hash: "sha256:manifest".to_string(),
+            lockfile_checksum: Some("sha256:lock".to_string()),
+            only: Vec::new(),
+            upgrade: false,
+            entries: vec![PlannedEntry {
+                id: "rules".to_string(),
+                action: PlannedAction::Install,
+                dest: "./rules".to_string(),
+                commit: None,
+                conflict: true,
+                diff: Some("--- a/rules/a.md\n+++ b/rules/a.md\n".to_string()),
+                warnings: Vec::new(),
+                locked: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_plan_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plan.json");
+        plan().save(&path).unwrap();
+
+        let loaded = Plan::load(&path).unwrap();
+        assert_eq!(loaded.entries[0].action, PlannedAction::Install);
+        assert!(loaded.entries[0].conflict);
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("\"action\": \"install\""));
+
+        std::fs::write(&path, "{\"version\": 99}").unwrap();
+        assert!(matches!(
+            Plan::load(&path),
+            Err(ApsError::InvalidPlan { .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_current() {
+        let plan = plan();
+        assert!(plan
+            .check_current("sha256:manifest", Some("sha256:lock"))
+            .is_ok());
+        assert!(matches!(
+            plan.check_current("sha256:other", Some("sha256:lock")),
+            Err(ApsError::PlanStale { .. })
+        ));
+        assert!(matches!(
+            plan.check_current("sha256:manifest", None),
+            Err(ApsError::PlanStale { .. })
+        ));
+    }
+}
@@ -0,0 +1,52 @@
+//! Commands run after an entry is installed (`post_install`).
+//!
+//! Entries can list shell commands to run from the manifest directory once
+//! their content has been (re)installed, e.g. to regenerate docs that embed
+//! it. Running arbitrary commands from a manifest is opt-in: without
+//! `aps sync --allow-hooks` they are reported and skipped.
+
+use crate::error::{ApsError, Result};
+use crate::output::{self, message};
+use console::style;
+use std::path::Path;
+use std::process::Command;
+
+/// Shell command line for `command` on this platform
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+/// Run an entry's post-install commands in order, stopping at the first
+/// that fails
+pub fn run_post_install(id: &str, commands: &[String], manifest_dir: &Path) -> Result<()> {
+    for command in commands {
+        message!("{} {} ({})", style("Running").cyan(), command, id);
+        let mut shell = shell(command);
+        // Keep stdout for the machine-readable result
+        if output::to_stderr() {
+            shell.stdout(std::io::stderr());
+        }
+        let status = shell
+            .current_dir(manifest_dir)
+            .status()
+            .map_err(|e| ApsError::io(e, format!("Failed to run post_install `{}`", command)))?;
+        if !status.success() {
+            return Err(ApsError::PostInstallFailed {
+                id: id.to_string(),
+                command: command.clone(),
+                status: status
+                    .code()
+                    .map_or_else(|| "a signal".to_string(), |code| code.to_string()),
+            });
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! Live progress line for `aps sync`.
+//!
+//! While an entry syncs, a single status line on stderr shows which entry
+//! is being processed and what it is doing (cloning, copying). The line is
+//! an `indicatif` progress bar, only drawn when stderr is a terminal, and
+//! anything that prints during the sync calls [`suspend`] first so output
+//! never lands on the line.
+
+use console::style;
+use indicatif::{MultiProgress, ProgressBar, ProgressState, ProgressStyle};
+use std::sync::{Mutex, OnceLock};
+
+/// The bar of the entry being synced
+static BAR: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+fn enabled() -> bool {
+    console::user_attended_stderr()
+}
+
+fn multi() -> &'static MultiProgress {
+    static MULTI: OnceLock<MultiProgress> = OnceLock::new();
+    MULTI.get_or_init(MultiProgress::new)
+}
+
+/// `[2/5] rules: cloning <url> (12 files)`; the bar's position counts the
+/// files copied
+fn line_style() -> ProgressStyle {
+    ProgressStyle::with_template("{prefix}{msg}{files}")
+        .expect("valid progress template")
+        .with_key(
+            "files",
+            |state: &ProgressState, w: &mut dyn std::fmt::Write| {
+                if state.pos() > 0 {
+                    let _ = write!(w, " ({} files)", state.pos());
+                }
+            },
+        )
+}
+
+fn with_bar(f: impl FnOnce(&ProgressBar)) {
+    if let Ok(guard) = BAR.lock() {
+        if let Some(bar) = guard.as_ref() {
+            f(bar);
+        }
+    }
+}
+
+/// Show the line for the `position`th of `total` entries
+pub fn start(position: usize, total: usize, id: &str) {
+    if !enabled() {
+        return;
+    }
+    let bar = multi().add(
+        ProgressBar::new_spinner()
+            .with_style(line_style())
+            .with_prefix(format!(
+                "{} {}",
+                style(format!("[{}/{}]", position, total)).dim(),
+                id
+            )),
+    );
+    bar.tick();
+    if let Ok(mut guard) = BAR.lock() {
+        if let Some(previous) = guard.replace(bar) {
+            previous.finish_and_clear();
+            multi().remove(&previous);
+        }
+    }
+}
+
+/// Describe what the current entry is doing
+pub fn detail(detail: impl Into<String>) {
+    let detail = detail.into();
+    with_bar(|bar| {
+        bar.set_position(0);
+        bar.set_message(format!(": {}", detail));
+    });
+}
+
+/// Count a file copied for the current entry
+pub fn file_copied() {
+    with_bar(|bar| bar.inc(1));
+}
+
+/// Clear the line so other output can be printed. The next update
+/// draws it again.
+pub fn suspend() {
+    with_bar(|_| {
+        let _ = multi().clear();
+    });
+}
+
+/// Clear the line once the entry is done
+pub fn finish() {
+    if let Ok(mut guard) = BAR.lock() {
+        if let Some(bar) = guard.take() {
+            bar.finish_and_clear();
+            multi().remove(&bar);
+        }
+    }
+}
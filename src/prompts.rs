@@ -0,0 +1,173 @@
+//! Prompt library support for the `prompt_library` kind.
+//!
+//! A prompt library is a folder of reusable prompt templates. After the files
+//! are installed, an `INDEX.md` is generated in the destination listing every
+//! prompt with the title and description from its frontmatter.
+
+use crate::error::{ApsError, Result};
+use crate::frontmatter;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+use walkdir::WalkDir;
+
+/// Filename of the generated index within the library destination
+pub const PROMPT_INDEX_FILENAME: &str = "INDEX.md";
+
+/// A prompt discovered in the library
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptInfo {
+    /// Path relative to the library root, with `/` separators
+    pub path: String,
+    pub title: String,
+    pub description: Option<String>,
+}
+
+/// Whether a file is a prompt template (markdown, excluding the generated index)
+pub fn is_prompt_file(path: &Path) -> bool {
+    let is_markdown = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md"));
+    let is_index = path
+        .file_name()
+        .is_some_and(|name| name == PROMPT_INDEX_FILENAME);
+    is_markdown && !is_index
+}
+
+/// Read the title and description of a prompt.
+/// The title falls back to the first heading, then to the file stem.
+pub fn read_prompt_info(root: &Path, file: &Path) -> Result<PromptInfo> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| ApsError::io(e, format!("Failed to read prompt {:?}", file)))?;
+    let fm = frontmatter::parse(&content);
+    let body = frontmatter::split(&content).map_or(content.as_str(), |(_, body)| body);
+
+    let title = fm
+        .as_ref()
+        .and_then(|fm| frontmatter::get_str(fm, "title"))
+        .map(str::to_string)
+        .or_else(|| {
+            body.lines()
+                .find_map(|line| line.strip_prefix("# "))
+                .map(|heading| heading.trim().to_string())
+        })
+        .unwrap_or_else(|| {
+            file.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+
+    let description = fm
+        .as_ref()
+        .and_then(|fm| frontmatter::get_str(fm, "description"))
+        .map(str::to_string);
+
+    let path = file
+        .strip_prefix(root)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .replace('\\', "/");
+
+    Ok(PromptInfo {
+        path,
+        title,
+        description,
+    })
+}
+
+/// Collect all prompts under a library directory, sorted by path
+pub fn collect_prompts(root: &Path) -> Result<Vec<PromptInfo>> {
+    let mut files: Vec<PathBuf> = WalkDir::new(root)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_prompt_file(e.path()))
+        .map(|e| e.into_path())
+        .collect();
+    files.sort();
+
+    files
+        .iter()
+        .map(|file| read_prompt_info(root, file))
+        .collect()
+}
+
+/// Render the index document for a set of prompts
+pub fn render_index(prompts: &[PromptInfo]) -> String {
+    let mut out = String::from("# Prompt Library\n\n");
+    out.push_str("<!-- Generated by aps. Do not edit; changes are overwritten on sync. -->\n\n");
+
+    if prompts.is_empty() {
+        out.push_str("_No prompts installed._\n");
+        return out;
+    }
+
+    for prompt in prompts {
+        match &prompt.description {
+            Some(desc) => out.push_str(&format!(
+                "- [{}]({}) — {}\n",
+                prompt.title, prompt.path, desc
+            )),
+            None => out.push_str(&format!("- [{}]({})\n", prompt.title, prompt.path)),
+        }
+    }
+    out
+}
+
+/// Generate `INDEX.md` in an installed library directory
+pub fn write_prompt_index(dest: &Path) -> Result<PathBuf> {
+    let index_path = dest.join(PROMPT_INDEX_FILENAME);
+    let content = render_index(&collect_prompts(dest)?);
+
+    // Replace rather than write through a symlinked index from the source
+    if index_path.symlink_metadata().is_ok() {
+        std::fs::remove_file(&index_path)
+            .map_err(|e| ApsError::io(e, format!("Failed to remove {:?}", index_path)))?;
+    }
+    std::fs::write(&index_path, content)
+        .map_err(|e| ApsError::io(e, format!("Failed to write {:?}", index_path)))?;
+
+    debug!("Wrote prompt index to {:?}", index_path);
+    Ok(index_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_collect_and_render_index() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("review")).unwrap();
+        std::fs::write(
+            root.join("review/pr.md"),
+            "---\ntitle: PR Review\ndescription: Review a pull request\n---\nReview {{diff}}\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("commit.md"), "# Commit Message\n\nWrite one.\n").unwrap();
+        std::fs::write(root.join("notes.txt"), "not a prompt").unwrap();
+        std::fs::write(root.join(PROMPT_INDEX_FILENAME), "stale").unwrap();
+
+        let prompts = collect_prompts(root).unwrap();
+        assert_eq!(
+            prompts,
+            vec![
+                PromptInfo {
+                    path: "commit.md".into(),
+                    title: "Commit Message".into(),
+                    description: None,
+                },
+                PromptInfo {
+                    path: "review/pr.md".into(),
+                    title: "PR Review".into(),
+                    description: Some("Review a pull request".into()),
+                },
+            ]
+        );
+
+        let index = render_index(&prompts);
+        assert!(index.contains("- [Commit Message](commit.md)\n"));
+        assert!(index.contains("- [PR Review](review/pr.md) — Review a pull request\n"));
+    }
+}
@@ -0,0 +1,299 @@
+//! Remote catalog registry with an offline index cache.
+//!
+//! A registry is an HTTP(S) URL serving a catalog document (the same format
+//! as `aps.catalog.yaml`), or a git repository with `aps.catalog.yaml` at its
+//! root. The last successfully fetched index is cached per user so searches
+//! keep working offline, and callers are told how old the data is whenever
+//! it may be out of date.
+
+use crate::catalog::{Catalog, CATALOG_FILENAME};
+use crate::checksum::compute_string_checksum;
+use crate::error::{ApsError, Result};
+use crate::http;
+use crate::sources::clone_and_resolve;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Environment variable overriding the cache directory (mainly for tests)
+pub const CACHE_DIR_ENV: &str = "APS_CACHE_DIR";
+
+/// How long a cached index is used before refreshing
+pub const DEFAULT_INDEX_TTL_HOURS: i64 = 24;
+
+/// When to contact the registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshMode {
+    /// Refresh only when the cached index is older than the TTL
+    Auto,
+    /// Always refresh, falling back to the cache if the registry is unreachable
+    Force,
+    /// Never contact the registry
+    Offline,
+}
+
+/// Cached registry index on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedIndex {
+    url: String,
+    fetched_at: DateTime<Utc>,
+    catalog: Catalog,
+}
+
+/// A registry index along with how fresh it is
+#[derive(Debug)]
+pub struct RegistryIndex {
+    pub catalog: Catalog,
+    pub fetched_at: DateTime<Utc>,
+    /// Set when a refresh was attempted and failed, so cached data was used
+    pub refresh_error: Option<String>,
+}
+
+impl RegistryIndex {
+    /// Whether the index is older than the refresh window
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        now - self.fetched_at > Duration::hours(DEFAULT_INDEX_TTL_HOURS)
+    }
+
+    /// Describe the index age when it may be out of date, for display on stderr
+    pub fn staleness_notice(&self, now: DateTime<Utc>) -> Option<String> {
+        if self.refresh_error.is_none() && !self.is_stale(now) {
+            return None;
+        }
+
+        let age = format_age(now - self.fetched_at);
+        Some(match &self.refresh_error {
+            Some(err) => format!(
+                "Registry unreachable ({}); using cached index from {} ago",
+                err, age
+            ),
+            None => format!("Using cached registry index from {} ago (offline)", age),
+        })
+    }
+}
+
+/// Whether a catalog location is a URL rather than a local path
+pub fn is_remote(location: &str) -> bool {
+    location.contains("://") || location.starts_with("git@")
+}
+
+/// Whether a registry URL names a git repository rather than a document
+pub fn is_git_url(url: &str) -> bool {
+    url.starts_with("git@")
+        || url.starts_with("ssh://")
+        || url.starts_with("git://")
+        || url.trim_end_matches('/').ends_with(".git")
+}
+
+/// Load a registry index, fetching or reusing the cache according to `mode`
+pub fn load_index(url: &str, mode: RefreshMode) -> Result<RegistryIndex> {
+    let cache_path = cache_path_for(url)?;
+    let cached = read_cache(&cache_path);
+    let now = Utc::now();
+
+    let cache_fresh = cached
+        .as_ref()
+        .is_some_and(|c| now - c.fetched_at <= Duration::hours(DEFAULT_INDEX_TTL_HOURS));
+
+    let should_fetch = match mode {
+        RefreshMode::Offline => false,
+        RefreshMode::Force => true,
+        RefreshMode::Auto => !cache_fresh,
+    };
+
+    if should_fetch {
+        match update_index(url) {
+            Ok(index) => return Ok(index),
+            Err(e) => {
+                let Some(cached) = cached else {
+                    return Err(e);
+                };
+                warn!("Falling back to cached registry index: {}", e);
+                return Ok(RegistryIndex {
+                    catalog: cached.catalog,
+                    fetched_at: cached.fetched_at,
+                    refresh_error: Some(e.to_string()),
+                });
+            }
+        }
+    }
+
+    match cached {
+        Some(cached) => Ok(RegistryIndex {
+            catalog: cached.catalog,
+            fetched_at: cached.fetched_at,
+            refresh_error: None,
+        }),
+        None => Err(ApsError::RegistryError {
+            url: url.to_string(),
+            message: "no cached index available offline".to_string(),
+        }),
+    }
+}
+
+/// Fetch a registry index now and cache it, without falling back to the
+/// cache when the registry can't be reached
+pub fn update_index(url: &str) -> Result<RegistryIndex> {
+    let index = CachedIndex {
+        url: url.to_string(),
+        fetched_at: Utc::now(),
+        catalog: fetch_catalog(url)?,
+    };
+    if let Err(e) = write_cache(&cache_path_for(url)?, &index) {
+        warn!("Failed to cache registry index: {}", e);
+    }
+    Ok(RegistryIndex {
+        catalog: index.catalog,
+        fetched_at: index.fetched_at,
+        refresh_error: None,
+    })
+}
+
+/// Per-user directory for aps caches
+pub fn cache_root() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os(CACHE_DIR_ENV) {
+        return Ok(PathBuf::from(dir));
+    }
+    dirs::cache_dir()
+        .map(|d| d.join("aps"))
+        .ok_or_else(|| ApsError::RegistryError {
+            url: String::new(),
+            message: "could not determine a cache directory".to_string(),
+        })
+}
+
+/// Cache file for a registry URL, keyed by a hash of the URL
+fn cache_path_for(url: &str) -> Result<PathBuf> {
+    let checksum = compute_string_checksum(url);
+    let key = checksum.strip_prefix("sha256:").unwrap_or(&checksum);
+    Ok(cache_root()?
+        .join("registry")
+        .join(format!("{}.yaml", &key[..16])))
+}
+
+fn read_cache(path: &Path) -> Option<CachedIndex> {
+    let content = std::fs::read_to_string(path).ok()?;
+    match serde_yaml::from_str(&content) {
+        Ok(index) => Some(index),
+        Err(e) => {
+            debug!("Ignoring unreadable registry cache {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+fn write_cache(path: &Path, index: &CachedIndex) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| ApsError::io(e, format!("Failed to create directory {:?}", parent)))?;
+    }
+    let content = serde_yaml::to_string(index).map_err(|e| ApsError::CatalogReadError {
+        message: format!("Failed to serialize registry index: {}", e),
+    })?;
+    std::fs::write(path, content)
+        .map_err(|e| ApsError::io(e, format!("Failed to write registry cache {:?}", path)))?;
+    debug!("Cached registry index at {:?}", path);
+    Ok(())
+}
+
+/// Download and parse the registry catalog
+fn fetch_catalog(url: &str) -> Result<Catalog> {
+    if is_git_url(url) {
+        return fetch_git_catalog(url);
+    }
+    info!("Fetching registry index from {}", url);
+
+    let body = http::fetch(url).map_err(|message| ApsError::RegistryError {
+        url: url.to_string(),
+        message,
+    })?;
+
+    // YAML is a superset of JSON, so either format is accepted
+    serde_yaml::from_slice(&body).map_err(|e| ApsError::RegistryError {
+        url: url.to_string(),
+        message: format!("invalid catalog: {}", e),
+    })
+}
+
+/// Clone a catalog repository and parse the catalog at its root
+fn fetch_git_catalog(url: &str) -> Result<Catalog> {
+    info!("Fetching registry index from git repository {}", url);
+    let registry_error = |message: String| ApsError::RegistryError {
+        url: url.to_string(),
+        message,
+    };
+
+    let cloned = clone_and_resolve(url, "auto", true).map_err(|e| registry_error(e.to_string()))?;
+    let path = cloned.repo_path.join(CATALOG_FILENAME);
+    let content = std::fs::read_to_string(&path)
+        .map_err(|_| registry_error(format!("no {} at the repository root", CATALOG_FILENAME)))?;
+    serde_yaml::from_str(&content).map_err(|e| registry_error(format!("invalid catalog: {}", e)))
+}
+
+/// Format a duration as a coarse human-readable age
+fn format_age(age: Duration) -> String {
+    if age.num_days() > 0 {
+        format!("{} day(s)", age.num_days())
+    } else if age.num_hours() > 0 {
+        format!("{} hour(s)", age.num_hours())
+    } else {
+        format!("{} minute(s)", age.num_minutes().max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(age_hours: i64, refresh_error: Option<&str>) -> (RegistryIndex, DateTime<Utc>) {
+        let now = Utc::now();
+        (
+            RegistryIndex {
+                catalog: Catalog::new(),
+                fetched_at: now - Duration::hours(age_hours),
+                refresh_error: refresh_error.map(str::to_string),
+            },
+            now,
+        )
+    }
+
+    #[test]
+    fn test_staleness_notice() {
+        let (fresh, now) = index(1, None);
+        assert!(!fresh.is_stale(now));
+        assert!(fresh.staleness_notice(now).is_none());
+
+        let (old, now) = index(72, None);
+        assert!(old.is_stale(now));
+        assert!(old
+            .staleness_notice(now)
+            .unwrap()
+            .contains("from 3 day(s) ago"));
+
+        let (failed, now) = index(2, Some("timeout"));
+        assert!(failed
+            .staleness_notice(now)
+            .unwrap()
+            .contains("Registry unreachable (timeout)"));
+    }
+
+    #[test]
+    fn test_remote_locations() {
+        assert!(is_remote("https://example.com/catalog.yaml"));
+        assert!(is_remote("git@github.com:acme/prompt-catalog.git"));
+        assert!(!is_remote("catalogs/team.yaml"));
+
+        assert!(is_git_url("https://github.com/acme/prompt-catalog.git"));
+        assert!(is_git_url("git@github.com:acme/prompt-catalog.git"));
+        assert!(is_git_url("ssh://git@example.com/catalog"));
+        assert!(!is_git_url("https://example.com/aps.catalog.yaml"));
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::seconds(5)), "1 minute(s)");
+        assert_eq!(format_age(Duration::minutes(90)), "1 hour(s)");
+        assert_eq!(format_age(Duration::days(4)), "4 day(s)");
+    }
+}
@@ -0,0 +1,267 @@
+//! Validation reports for CI (`aps validate --report`).
+//!
+//! JUnit XML shows each entry as a test case in GitLab and most CI test
+//! UIs; SARIF turns warnings and errors into code scanning annotations on
+//! the manifest in GitHub.
+
+use crate::cli::ReportFormat;
+use crate::error::{ApsError, Result};
+use crate::validate::EntryValidation;
+use serde_json::json;
+use std::path::Path;
+
+/// Write the report for `results` to `path`
+pub fn write_report(
+    path: &Path,
+    format: ReportFormat,
+    manifest_path: &Path,
+    results: &[EntryValidation],
+    manifest_warnings: &[String],
+) -> Result<()> {
+    let manifest_display = display_path(manifest_path);
+    let content = match format {
+        ReportFormat::Junit => junit(&manifest_display, results, manifest_warnings),
+        ReportFormat::Sarif => {
+            let manifest_text = std::fs::read_to_string(manifest_path).unwrap_or_default();
+            sarif(
+                &manifest_display,
+                &manifest_text,
+                results,
+                manifest_warnings,
+            )
+        }
+    };
+    std::fs::write(path, content)
+        .map_err(|e| ApsError::io(e, format!("Failed to write report to {:?}", path)))
+}
+
+/// The format for a report at `path`: SARIF for `.sarif` files, JUnit
+/// otherwise
+pub fn format_for_path(path: &Path) -> ReportFormat {
+    let name = path.to_string_lossy();
+    if name.ends_with(".sarif") || name.ends_with(".sarif.json") {
+        ReportFormat::Sarif
+    } else {
+        ReportFormat::Junit
+    }
+}
+
+/// The manifest path relative to the working directory, as CI tools expect
+fn display_path(path: &Path) -> String {
+    let relative = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf());
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+fn junit(manifest: &str, results: &[EntryValidation], manifest_warnings: &[String]) -> String {
+    let failures = results.iter().filter(|r| r.error.is_some()).count();
+    let time: f64 = results.iter().map(|r| r.duration.as_secs_f64()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"aps validate\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        results.len(),
+        failures,
+        time
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\" time=\"{:.3}\">\n",
+        escape_xml(manifest),
+        results.len(),
+        failures,
+        time
+    ));
+    if !manifest_warnings.is_empty() {
+        xml.push_str(&format!(
+            "    <system-out>{}</system-out>\n",
+            escape_xml(&warning_lines(manifest_warnings))
+        ));
+    }
+    for result in results {
+        xml.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\">\n",
+            result.source_type,
+            escape_xml(&result.id),
+            result.duration.as_secs_f64()
+        ));
+        if let Some(ref error) = result.error {
+            let message = error.to_string();
+            xml.push_str(&format!(
+                "      <failure message=\"{}\" type=\"error\">{}</failure>\n",
+                escape_xml(&message),
+                escape_xml(&message)
+            ));
+        }
+        if !result.warnings.is_empty() {
+            xml.push_str(&format!(
+                "      <system-out>{}</system-out>\n",
+                escape_xml(&warning_lines(&result.warnings))
+            ));
+        }
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn warning_lines(warnings: &[String]) -> String {
+    warnings
+        .iter()
+        .map(|w| format!("Warning: {}", w))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn sarif(
+    manifest: &str,
+    manifest_text: &str,
+    results: &[EntryValidation],
+    manifest_warnings: &[String],
+) -> String {
+    let result = |level: &str, message: String, line: Option<usize>| {
+        let mut location = json!({ "artifactLocation": { "uri": manifest } });
+        if let Some(line) = line {
+            location["region"] = json!({ "startLine": line });
+        }
+        json!({
+            "ruleId": format!("aps/{}", level),
+            "level": level,
+            "message": { "text": message },
+            "locations": [{ "physicalLocation": location }],
+        })
+    };
+
+    let mut sarif_results: Vec<_> = manifest_warnings
+        .iter()
+        .map(|w| result("warning", w.clone(), None))
+        .collect();
+    for entry in results {
+        let line = entry_line(manifest_text, &entry.id);
+        for warning in &entry.warnings {
+            sarif_results.push(result(
+                "warning",
+                format!("{}: {}", entry.id, warning),
+                line,
+            ));
+        }
+        if let Some(ref error) = entry.error {
+            sarif_results.push(result("error", format!("{}: {}", entry.id, error), line));
+        }
+    }
+
+    let rule = |level: &str, description: &str| {
+        json!({
+            "id": format!("aps/{}", level),
+            "shortDescription": { "text": description },
+        })
+    };
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "aps",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/westonplatter/agentic-prompt-sync",
+                    "rules": [
+                        rule("warning", "Manifest validation warning"),
+                        rule("error", "Manifest validation error"),
+                    ],
+                },
+            },
+            "results": sarif_results,
+        }],
+    });
+    serde_json::to_string_pretty(&log).unwrap_or_default() + "\n"
+}
+
+/// 1-based line of the manifest declaring entry `id`
+fn entry_line(manifest_text: &str, id: &str) -> Option<usize> {
+    manifest_text
+        .lines()
+        .position(|line| {
+            let line = line.trim_start().trim_start_matches("- ").trim();
+            line.strip_prefix("id:")
+                .map(|value| value.trim().trim_matches(|c| c == '"' || c == '\''))
+                == Some(id)
+        })
+        .map(|index| index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn results() -> Vec<EntryValidation> {
+        vec![
+            EntryValidation {
+                id: "rules".to_string(),
+                source_type: "filesystem",
+                source: "source/rules".to_string(),
+                warnings: vec!["Skill 'a' is missing <SKILL.md>".to_string()],
+                allowed_warnings: 0,
+                error: None,
+                duration: Duration::from_millis(12),
+            },
+            EntryValidation {
+                id: "agents".to_string(),
+                source_type: "filesystem",
+                source: "source/AGENTS.md".to_string(),
+                warnings: Vec::new(),
+                allowed_warnings: 0,
+                error: Some(ApsError::EntryRequiresSource {
+                    id: "agents".to_string(),
+                }),
+                duration: Duration::from_millis(3),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_junit() {
+        let xml = junit("aps.yaml", &results(), &[]);
+        assert!(xml.contains("tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("<testcase classname=\"filesystem\" name=\"rules\" time=\"0.012\">"));
+        assert!(xml.contains("Warning: Skill &apos;a&apos; is missing &lt;SKILL.md&gt;"));
+        assert!(xml.contains("<failure message="));
+    }
+
+    #[test]
+    fn test_sarif() {
+        let manifest = "entries:\n  - id: rules\n    kind: cursor_rules\n  - id: \"agents\"\n";
+        let sarif: serde_json::Value =
+            serde_json::from_str(&sarif("aps.yaml", manifest, &results(), &[])).unwrap();
+        let results = &sarif["runs"][0]["results"];
+        assert_eq!(results[0]["level"], "warning");
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"]["region"]["startLine"],
+            2
+        );
+        assert_eq!(results[1]["level"], "error");
+        assert_eq!(
+            results[1]["locations"][0]["physicalLocation"]["region"]["startLine"],
+            4
+        );
+    }
+
+    #[test]
+    fn test_format_for_path() {
+        assert_eq!(
+            format_for_path(Path::new("out/aps.sarif")),
+            ReportFormat::Sarif
+        );
+        assert_eq!(format_for_path(Path::new("junit.xml")), ReportFormat::Junit);
+    }
+}
@@ -0,0 +1,143 @@
+//! JSON Schemas for the files aps reads.
+//!
+//! The schemas for the manifest, lockfile and catalog ship inside the
+//! binary: `aps validate` checks files against them, catching keys that
+//! serde would silently ignore (a misspelled `dest`, say), and `aps schema`
+//! prints them for editors that complete and check YAML against a schema.
+
+use crate::cli::SchemaKind;
+use crate::error::{ApsError, Result};
+use serde_json::Value;
+use std::path::Path;
+
+/// The schema for `kind`, as JSON text
+pub fn schema_text(kind: SchemaKind) -> &'static str {
+    match kind {
+        SchemaKind::Manifest => include_str!("../schemas/aps.schema.json"),
+        SchemaKind::Lockfile => include_str!("../schemas/aps.lock.schema.json"),
+        SchemaKind::Catalog => include_str!("../schemas/aps.catalog.schema.json"),
+        SchemaKind::ClaudeSettings => include_str!("../schemas/claude-settings.schema.json"),
+    }
+}
+
+/// Where the YAML or JSON file at `path` doesn't match the schema for
+/// `kind`, one `location: problem` line each
+pub fn file_problems(kind: SchemaKind, path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| ApsError::io(e, format!("Failed to read {:?}", path)))?;
+    // JSON is YAML, so one parser covers both lockfile formats
+    let document: Value = match serde_yaml::from_str(&content) {
+        Ok(document) => document,
+        Err(e) => return Ok(vec![format!("not valid YAML or JSON: {}", e)]),
+    };
+    Ok(problems(kind, &document))
+}
+
+/// Where `document` doesn't match the schema for `kind`
+pub fn problems(kind: SchemaKind, document: &Value) -> Vec<String> {
+    let schema: Value =
+        serde_json::from_str(schema_text(kind)).expect("embedded schema is valid JSON");
+    let validator = jsonschema::validator_for(&schema).expect("embedded schema is valid");
+    validator
+        .iter_errors(document)
+        .map(|error| {
+            let location = location(&error.instance_path.to_string());
+            if location.is_empty() {
+                error.to_string()
+            } else {
+                format!("{}: {}", location, error)
+            }
+        })
+        .collect()
+}
+
+/// `entries[0].source` for the JSON pointer `/entries/0/source`
+fn location(pointer: &str) -> String {
+    let mut location = String::new();
+    for segment in pointer.split('/').skip(1) {
+        let segment = segment.replace("~1", "/").replace("~0", "~");
+        if segment.chars().all(|c| c.is_ascii_digit()) && !segment.is_empty() {
+            location.push_str(&format!("[{}]", segment));
+        } else {
+            if !location.is_empty() {
+                location.push('.');
+            }
+            location.push_str(&segment);
+        }
+    }
+    location
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(text: &str) -> Value {
+        serde_yaml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_manifest_schema() {
+        let manifest = yaml(
+            r#"
+entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: git
+      repo: https://github.com/org/prompts.git
+      path: AGENTS.md
+    dest: ./AGENTS.md
+  - id: settings
+    kind: claude_settings
+    sources:
+      - type: filesystem
+        root: ../shared
+        path: base.yaml
+        order: 1
+      - type: s3
+        bucket: prompts
+"#,
+        );
+        assert!(problems(SchemaKind::Manifest, &manifest).is_empty());
+
+        let manifest = yaml(
+            r#"
+entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      roots: ../shared
+    destination: ./AGENTS.md
+"#,
+        );
+        let problems = problems(SchemaKind::Manifest, &manifest);
+        assert_eq!(problems.len(), 3, "{:?}", problems);
+        assert!(problems
+            .iter()
+            .any(|p| p.starts_with("entries[0]: ") && p.contains("'destination' was unexpected")));
+        assert!(problems
+            .iter()
+            .any(|p| p.starts_with("entries[0].source: ") && p.contains("\"root\"")));
+    }
+
+    #[test]
+    fn test_lockfile_and_catalog_schemas() {
+        let lockfile = yaml(include_str!("../aps.lock.yaml"));
+        assert!(problems(SchemaKind::Lockfile, &lockfile).is_empty());
+
+        let catalog = yaml("version: 1\nentries:\n  - id: a\n    name: a\n    kind: agent_skill\n");
+        assert_eq!(
+            problems(SchemaKind::Catalog, &catalog),
+            vec!["entries[0]: \"destination\" is a required property"]
+        );
+    }
+
+    #[test]
+    fn test_location() {
+        assert_eq!(location(""), "");
+        assert_eq!(location("/entries/0/source"), "entries[0].source");
+        assert_eq!(location("/entries/a~1b"), "entries.a/b");
+    }
+}
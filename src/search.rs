@@ -0,0 +1,600 @@
+//! Keyword search over catalog entries.
+//!
+//! Scoring is BM25F, as described in `docs/catalog-search-spec.md`: term
+//! frequencies are weighted by field and normalized by field length, then
+//! saturated, so rare terms outrank common ones, matches in the name count
+//! for more than matches in the description, and a long description can't
+//! win by repeating a word. Query terms missing from the catalog fall back
+//! to close spellings, so `kubernets` still finds Kubernetes entries.
+//!
+//! Queries can also hold `"quoted phrases"`, whose words must appear in a
+//! row in one field, and `field:value` filters (`tag:`, `kind:`, `id:`,
+//! `name:`) that every hit must pass.
+
+use crate::catalog::{Catalog, CatalogEntry};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Words that carry no signal for matching
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "is", "it", "of", "on",
+    "or", "the", "to", "with",
+];
+
+/// Searchable fields and their weights
+const FIELD_WEIGHTS: &[(Field, f64)] = &[
+    (Field::Name, 3.0),
+    (Field::Id, 2.0),
+    (Field::Tags, 2.0),
+    (Field::Kind, 1.5),
+    (Field::Description, 1.0),
+];
+
+/// BM25 term frequency saturation
+const K1: f64 = 1.2;
+
+/// BM25 field length normalization (0 ignores length, 1 fully normalizes)
+const B: f64 = 0.75;
+
+/// How much a misspelled query term counts next to an exact match
+const FUZZY_WEIGHT: f64 = 0.7;
+
+/// How much adoption raises a match's score, per doubling of the number of
+/// manifests keeping the entry. Adoption orders close matches but can't
+/// make up for missing query terms.
+const ADOPTION_WEIGHT: f64 = 0.15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Field {
+    Name,
+    Id,
+    Tags,
+    Kind,
+    Description,
+}
+
+/// Entry fields a query can filter on with `field:value`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Filter {
+    /// One of the entry's tags, exactly
+    Tag,
+    /// The manifest name of the entry's kind, e.g. `cursor_rules`
+    Kind,
+    /// Part of the entry's id
+    Id,
+    /// Part of the entry's name
+    Name,
+}
+
+impl Filter {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "tag" => Some(Self::Tag),
+            "kind" => Some(Self::Kind),
+            "id" => Some(Self::Id),
+            "name" => Some(Self::Name),
+            _ => None,
+        }
+    }
+
+    fn matches(self, entry: &CatalogEntry, value: &str) -> bool {
+        let value = value.to_lowercase();
+        match self {
+            Self::Tag => entry.tags.iter().any(|tag| tag.to_lowercase() == value),
+            Self::Kind => entry.kind.as_str() == value,
+            Self::Id => entry.id.to_lowercase().contains(&value),
+            Self::Name => entry.name.to_lowercase().contains(&value),
+        }
+    }
+}
+
+/// A parsed search query
+#[derive(Debug, Default, PartialEq)]
+struct Query {
+    /// Free-text terms to score
+    terms: Vec<String>,
+    /// Quoted phrases, as terms that must appear in a row in one field
+    phrases: Vec<Vec<String>>,
+    /// `field:value` filters every hit must pass
+    filters: Vec<(Filter, String)>,
+}
+
+impl Query {
+    /// Parse free text, `"quoted phrases"` and `field:value` filters (the
+    /// value may be quoted). Words with an unknown field prefix are free
+    /// text, so ids like `rules:fastapi` still search as usual.
+    fn parse(query: &str) -> Self {
+        let mut parsed = Self::default();
+        for (word, quoted) in split_words(query) {
+            if quoted {
+                let phrase = tokenize(&word);
+                if phrase.len() > 1 {
+                    parsed.phrases.push(phrase);
+                } else {
+                    parsed.terms.extend(phrase);
+                }
+                continue;
+            }
+            let filter = word
+                .split_once(':')
+                .and_then(|(field, value)| Some((Filter::parse(field)?, value)))
+                .filter(|(_, value)| !value.is_empty());
+            match filter {
+                Some((filter, value)) => parsed.filters.push((filter, value.to_string())),
+                None => parsed.terms.extend(tokenize(&word)),
+            }
+        }
+        parsed
+    }
+
+    fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.phrases.is_empty() && self.filters.is_empty()
+    }
+
+    /// Terms that count towards the score, including the phrases' words
+    fn scored_terms(&self) -> impl Iterator<Item = &String> {
+        self.terms.iter().chain(self.phrases.iter().flatten())
+    }
+
+    /// Whether the entry passes every filter and contains every phrase
+    fn admits(&self, entry: &CatalogEntry) -> bool {
+        self.filters
+            .iter()
+            .all(|(filter, value)| filter.matches(entry, value))
+            && self.phrases.iter().all(|phrase| {
+                FIELD_WEIGHTS.iter().any(|(field, _)| {
+                    tokenize(&field_text(entry, *field))
+                        .windows(phrase.len())
+                        .any(|window| window == phrase.as_slice())
+                })
+            })
+    }
+}
+
+/// A catalog entry matched by a search, with its relevance score
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub entry: CatalogEntry,
+
+    /// Relevance score (higher is better)
+    pub score: f64,
+}
+
+/// Pre-tokenized index over a catalog
+pub struct CatalogSearch<'a> {
+    entries: &'a [CatalogEntry],
+    /// Per-entry term counts for each field
+    fields: Vec<HashMap<Field, HashMap<String, usize>>>,
+    /// Average number of terms in each field
+    avg_len: HashMap<Field, f64>,
+    /// Number of entries containing each term in any field
+    doc_freq: HashMap<String, usize>,
+    /// Number of manifests keeping each entry, by entry id
+    adoption: HashMap<String, usize>,
+}
+
+impl<'a> CatalogSearch<'a> {
+    /// Build a search index for the given catalog
+    pub fn new(catalog: &'a Catalog) -> Self {
+        let entries = catalog.entries.as_slice();
+        let mut fields = Vec::with_capacity(entries.len());
+        let mut doc_freq: HashMap<String, usize> = HashMap::new();
+
+        for entry in entries {
+            let mut per_field = HashMap::new();
+            let mut seen: Vec<String> = Vec::new();
+
+            for (field, _) in FIELD_WEIGHTS {
+                let text = field_text(entry, *field);
+                let mut counts: HashMap<String, usize> = HashMap::new();
+                for term in tokenize(&text) {
+                    if !seen.contains(&term) {
+                        seen.push(term.clone());
+                    }
+                    *counts.entry(term).or_default() += 1;
+                }
+                per_field.insert(*field, counts);
+            }
+
+            for term in seen {
+                *doc_freq.entry(term).or_default() += 1;
+            }
+            fields.push(per_field);
+        }
+
+        let avg_len = FIELD_WEIGHTS
+            .iter()
+            .map(|(field, _)| {
+                let total: usize = fields.iter().map(|f| field_len(&f[field])).sum();
+                (*field, total as f64 / entries.len().max(1) as f64)
+            })
+            .collect();
+
+        Self {
+            entries,
+            fields,
+            avg_len,
+            doc_freq,
+            adoption: HashMap::new(),
+        }
+    }
+
+    /// Rank entries kept in more manifests higher (see [`crate::usage`])
+    pub fn with_adoption(mut self, adoption: HashMap<String, usize>) -> Self {
+        self.adoption = adoption;
+        self
+    }
+
+    /// Index terms a query term matches, with how much each counts: the
+    /// term itself, or else the terms within a few typos of it
+    fn expand(&self, term: &str) -> Vec<(&str, f64)> {
+        if let Some((indexed, _)) = self.doc_freq.get_key_value(term) {
+            return vec![(indexed.as_str(), 1.0)];
+        }
+        let max_distance = max_typos(term);
+        if max_distance == 0 {
+            return Vec::new();
+        }
+        let mut matches: Vec<(&str, f64)> = self
+            .doc_freq
+            .keys()
+            .filter(|indexed| strsim::levenshtein(term, indexed) <= max_distance)
+            .map(|indexed| (indexed.as_str(), FUZZY_WEIGHT))
+            .collect();
+        matches.sort_by(|a, b| a.0.cmp(b.0));
+        matches
+    }
+
+    /// Search the catalog, returning matches ordered by descending score.
+    /// Ties are broken by entry id so results are stable across runs.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let query = Query::parse(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let terms: Vec<(&str, f64)> = query
+            .scored_terms()
+            .flat_map(|term| self.expand(term))
+            .collect();
+        // Terms that match nothing leave nothing to find, but a query of
+        // filters alone lists every entry passing them
+        let filter_only = query.scored_terms().next().is_none();
+        if terms.is_empty() && !filter_only {
+            return Vec::new();
+        }
+
+        let total = self.entries.len() as f64;
+        let mut hits: Vec<SearchHit> = self
+            .entries
+            .iter()
+            .zip(&self.fields)
+            .filter(|(entry, _)| query.admits(entry))
+            .filter_map(|(entry, per_field)| {
+                let mut score = 0.0;
+                for (term, term_weight) in &terms {
+                    let tf = self.weighted_tf(per_field, term);
+                    if tf == 0.0 {
+                        continue;
+                    }
+                    let df = self.doc_freq[*term] as f64;
+                    // BM25's idf, kept positive for terms in most entries
+                    let idf = (1.0 + (total - df + 0.5) / (df + 0.5)).ln();
+                    score += term_weight * idf * tf * (K1 + 1.0) / (tf + K1);
+                }
+                let kept = self.adoption.get(&entry.id).copied().unwrap_or(0);
+                score *= 1.0 + ADOPTION_WEIGHT * (1.0 + kept as f64).log2();
+                (score > 0.0 || filter_only).then(|| SearchHit {
+                    entry: entry.clone(),
+                    score: (score * 1000.0).round() / 1000.0,
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.entry.id.cmp(&b.entry.id))
+        });
+        hits
+    }
+
+    /// Term frequency summed over fields, each scaled by its weight and
+    /// normalized by how long the field is compared to the average
+    fn weighted_tf(&self, per_field: &HashMap<Field, HashMap<String, usize>>, term: &str) -> f64 {
+        FIELD_WEIGHTS
+            .iter()
+            .map(|(field, weight)| {
+                let counts = &per_field[field];
+                let tf = counts.get(term).copied().unwrap_or(0) as f64;
+                if tf == 0.0 {
+                    return 0.0;
+                }
+                let avg_len = self.avg_len[field].max(1.0);
+                let norm = 1.0 - B + B * field_len(counts) as f64 / avg_len;
+                weight * tf / norm
+            })
+            .sum()
+    }
+}
+
+/// Number of terms in a field
+fn field_len(counts: &HashMap<String, usize>) -> usize {
+    counts.values().sum()
+}
+
+/// Typos tolerated in a query term: none for short terms, where one edit
+/// already reaches unrelated words
+fn max_typos(term: &str) -> usize {
+    match term.chars().count() {
+        0..=4 => 0,
+        5..=8 => 1,
+        _ => 2,
+    }
+}
+
+fn field_text(entry: &CatalogEntry, field: Field) -> String {
+    match field {
+        Field::Name => entry.name.clone(),
+        Field::Id => entry.id.clone(),
+        Field::Tags => entry.tags.join(" "),
+        // Manifest name, e.g. "cursor_rules"
+        Field::Kind => entry.kind.as_str().to_string(),
+        Field::Description => entry.short_description.clone().unwrap_or_default(),
+    }
+}
+
+/// Split a query on whitespace outside double quotes, dropping the quotes.
+/// Each word is flagged if it is a quoted phrase as a whole.
+fn split_words(query: &str) -> Vec<(String, bool)> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    for c in query.chars() {
+        if c == '"' {
+            if word.is_empty() && !in_quotes {
+                quoted = true;
+            }
+            in_quotes = !in_quotes;
+        } else if c.is_whitespace() && !in_quotes {
+            if !word.is_empty() {
+                words.push((std::mem::take(&mut word), quoted));
+            }
+            quoted = false;
+        } else {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        words.push((word, quoted));
+    }
+    words
+}
+
+/// Lowercase, split on non-alphanumeric characters and drop stop words
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty() && !STOP_WORDS.contains(t))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::AssetKind;
+
+    fn entry(id: &str, name: &str, kind: AssetKind, desc: Option<&str>) -> CatalogEntry {
+        CatalogEntry {
+            id: id.to_string(),
+            name: name.to_string(),
+            kind,
+            destination: format!("./{}", name),
+            short_description: desc.map(str::to_string),
+            tags: Vec::new(),
+            versions: Vec::new(),
+            source: None,
+            catalog: None,
+        }
+    }
+
+    fn sample_catalog() -> Catalog {
+        Catalog {
+            version: 1,
+            release: None,
+            publish: None,
+            entries: vec![
+                entry(
+                    "rules:fastapi-auth.mdc",
+                    "fastapi-auth.mdc",
+                    AssetKind::CursorRules,
+                    Some("JWT and OAuth2 patterns for FastAPI"),
+                ),
+                entry(
+                    "rules:python-style.mdc",
+                    "python-style.mdc",
+                    AssetKind::CursorRules,
+                    Some("Python style guide for APIs"),
+                ),
+                entry(
+                    "skills:pdf",
+                    "pdf",
+                    AssetKind::AgentSkill,
+                    Some("Extract text from PDF documents"),
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("Add JWT auth to the FastAPI-app"),
+            vec!["add", "jwt", "auth", "fastapi", "app"]
+        );
+        assert_eq!(tokenize("cursor_rules"), vec!["cursor", "rules"]);
+        assert!(tokenize("the a to").is_empty());
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query =
+            Query::parse(r#"tag:rust "integration test" kind:cursor_rules fast name:"a b""#);
+        assert_eq!(query.terms, vec!["fast"]);
+        assert_eq!(query.phrases, vec![vec!["integration", "test"]]);
+        assert_eq!(
+            query.filters,
+            vec![
+                (Filter::Tag, "rust".to_string()),
+                (Filter::Kind, "cursor_rules".to_string()),
+                (Filter::Name, "a b".to_string()),
+            ]
+        );
+
+        // Unknown fields are plain text; one-word phrases are plain terms
+        let query = Query::parse(r#"rules:fastapi "jwt""#);
+        assert_eq!(query.terms, vec!["rules", "fastapi", "jwt"]);
+        assert!(query.filters.is_empty() && query.phrases.is_empty());
+    }
+
+    #[test]
+    fn test_search_filters_and_phrases() {
+        let mut catalog = sample_catalog();
+        catalog.entries[0].tags = vec!["python".to_string(), "security".to_string()];
+        let search = CatalogSearch::new(&catalog);
+        let ids = |query: &str| {
+            search
+                .search(query)
+                .into_iter()
+                .map(|hit| hit.entry.id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids("python tag:security"), vec!["rules:fastapi-auth.mdc"]);
+        assert_eq!(ids("kind:agent_skill"), vec!["skills:pdf"]);
+        assert_eq!(
+            ids("kind:cursor_rules"),
+            vec!["rules:fastapi-auth.mdc", "rules:python-style.mdc"]
+        );
+        assert_eq!(ids(r#""style guide""#), vec!["rules:python-style.mdc"]);
+        // Both words appear, but not in a row
+        assert!(ids(r#""guide style""#).is_empty());
+        assert!(ids("pdf kind:cursor_rules").is_empty());
+    }
+
+    #[test]
+    fn test_search_ranks_name_matches_first() {
+        let catalog = sample_catalog();
+        let search = CatalogSearch::new(&catalog);
+
+        let hits = search.search("fastapi");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].entry.id, "rules:fastapi-auth.mdc");
+
+        let hits = search.search("python api");
+        assert_eq!(hits[0].entry.id, "rules:python-style.mdc");
+    }
+
+    #[test]
+    fn test_search_boosts_adopted_entries() {
+        let catalog = sample_catalog();
+        let adoption = HashMap::from([
+            ("rules:python-style.mdc".to_string(), 3),
+            ("skills:pdf".to_string(), 50),
+        ]);
+        let search = CatalogSearch::new(&catalog).with_adoption(adoption);
+
+        // Both rules match equally; the adopted one comes first
+        let ids: Vec<_> = search
+            .search("rules")
+            .into_iter()
+            .map(|h| h.entry.id)
+            .collect();
+        assert_eq!(
+            ids,
+            vec!["rules:python-style.mdc", "rules:fastapi-auth.mdc"]
+        );
+
+        // Adoption alone doesn't make an entry match
+        let hits = search.search("fastapi");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].entry.id, "rules:fastapi-auth.mdc");
+    }
+
+    #[test]
+    fn test_search_no_matches() {
+        let catalog = sample_catalog();
+        let search = CatalogSearch::new(&catalog);
+
+        assert!(search.search("kubernetes").is_empty());
+        assert!(search.search("the").is_empty());
+        // Too short to correct
+        assert!(search.search("pdx").is_empty());
+    }
+
+    #[test]
+    fn test_search_tolerates_typos() {
+        let mut catalog = sample_catalog();
+        catalog.entries.push(entry(
+            "rules:kubernetes.mdc",
+            "kubernetes.mdc",
+            AssetKind::CursorRules,
+            Some("Kubernetes manifests and Helm charts"),
+        ));
+        let search = CatalogSearch::new(&catalog);
+
+        let hits = search.search("kubernets");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].entry.id, "rules:kubernetes.mdc");
+
+        // An exact match outranks the same match through a typo
+        let exact = search.search("kubernetes");
+        assert!(exact[0].score > hits[0].score);
+    }
+
+    #[test]
+    fn test_search_long_descriptions_do_not_dominate() {
+        let catalog = Catalog {
+            version: 1,
+            release: None,
+            publish: None,
+            entries: vec![
+                entry(
+                    "rules:containers.mdc",
+                    "containers.mdc",
+                    AssetKind::CursorRules,
+                    Some("Small Docker images"),
+                ),
+                entry(
+                    "rules:platform.mdc",
+                    "platform.mdc",
+                    AssetKind::CursorRules,
+                    Some(
+                        "Conventions for services: logging, metrics, tracing, configuration, \
+                         secrets, deployment with Docker, health checks and rollbacks",
+                    ),
+                ),
+                entry("rules:go.mdc", "go.mdc", AssetKind::CursorRules, None),
+            ],
+        };
+        let search = CatalogSearch::new(&catalog);
+
+        // One mention each, but in a shorter description
+        let hits = search.search("docker");
+        assert_eq!(hits[0].entry.id, "rules:containers.mdc");
+        assert_eq!(hits.len(), 2);
+    }
+
+    #[test]
+    fn test_search_matches_kind() {
+        let catalog = sample_catalog();
+        let search = CatalogSearch::new(&catalog);
+
+        let hits = search.search("skill");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].entry.id, "skills:pdf");
+    }
+}
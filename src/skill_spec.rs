@@ -0,0 +1,166 @@
+//! SKILL.md checks against the Agent Skills specification.
+//!
+//! A skill is a directory whose SKILL.md starts with YAML frontmatter naming
+//! it and describing when to use it. Agents skip skills whose frontmatter
+//! they can't read, so `aps validate` reports the problems that would make
+//! a synced skill silently disappear.
+
+use crate::frontmatter;
+use crate::links::relative_links;
+use std::path::Path;
+
+/// Longest `name` the specification allows
+const MAX_NAME_LENGTH: usize = 64;
+/// Longest `description` the specification allows
+const MAX_DESCRIPTION_LENGTH: usize = 1024;
+/// Longest `compatibility` the specification allows
+const MAX_COMPATIBILITY_LENGTH: usize = 500;
+/// Frontmatter fields defined by the specification
+const KNOWN_FIELDS: &[&str] = &[
+    "name",
+    "description",
+    "license",
+    "compatibility",
+    "metadata",
+    "allowed-tools",
+];
+
+/// Problems with the SKILL.md of the skill in `skill_dir`
+pub fn skill_problems(skill_dir: &Path, content: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let Some((raw, body)) = frontmatter::split(content) else {
+        return vec!["SKILL.md has no YAML frontmatter".to_string()];
+    };
+    let fields: serde_yaml::Mapping = match serde_yaml::from_str(raw) {
+        Ok(serde_yaml::Value::Mapping(fields)) => fields,
+        Ok(serde_yaml::Value::Null) => serde_yaml::Mapping::new(),
+        Ok(_) => return vec!["SKILL.md frontmatter is not a mapping".to_string()],
+        Err(e) => return vec![format!("SKILL.md frontmatter is not valid YAML: {}", e)],
+    };
+
+    let dir_name = skill_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match frontmatter::get_str(&fields, "name") {
+        None => problems.push("SKILL.md frontmatter is missing `name`".to_string()),
+        Some(name) => {
+            problems.extend(name_problems(name));
+            if name != dir_name {
+                problems.push(format!(
+                    "`name` '{}' does not match the skill directory '{}'",
+                    name, dir_name
+                ));
+            }
+        }
+    }
+    match frontmatter::get_str(&fields, "description") {
+        None => problems.push("SKILL.md frontmatter is missing `description`".to_string()),
+        Some(description) if description.chars().count() > MAX_DESCRIPTION_LENGTH => {
+            problems.push(format!(
+                "`description` is longer than {} characters",
+                MAX_DESCRIPTION_LENGTH
+            ))
+        }
+        Some(_) => {}
+    }
+    if frontmatter::get_str(&fields, "compatibility")
+        .is_some_and(|c| c.chars().count() > MAX_COMPATIBILITY_LENGTH)
+    {
+        problems.push(format!(
+            "`compatibility` is longer than {} characters",
+            MAX_COMPATIBILITY_LENGTH
+        ));
+    }
+    if fields
+        .get("metadata")
+        .is_some_and(|metadata| !metadata.is_mapping())
+    {
+        problems.push("`metadata` must be a mapping".to_string());
+    }
+    for key in fields.keys() {
+        let key = key.as_str().unwrap_or_default();
+        if !KNOWN_FIELDS.contains(&key) {
+            problems.push(format!("unknown frontmatter field `{}`", key));
+        }
+    }
+
+    for link in relative_links(body) {
+        if !skill_dir.join(&link).exists() {
+            problems.push(format!("references missing file '{}'", link));
+        }
+    }
+
+    problems
+}
+
+/// Naming rules: lowercase letters, digits and single hyphens, not at
+/// either end
+fn name_problems(name: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    if name.chars().count() > MAX_NAME_LENGTH {
+        problems.push(format!(
+            "`name` is longer than {} characters",
+            MAX_NAME_LENGTH
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        problems.push(format!(
+            "`name` '{}' may only contain lowercase letters, digits and hyphens",
+            name
+        ));
+    }
+    if name.starts_with('-') || name.ends_with('-') || name.contains("--") {
+        problems.push(format!(
+            "`name` '{}' must not start or end with a hyphen or contain consecutive hyphens",
+            name
+        ));
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_skill() {
+        let dir = tempfile::tempdir().unwrap();
+        let skill = dir.path().join("pdf-tools");
+        std::fs::create_dir_all(skill.join("scripts")).unwrap();
+        std::fs::write(skill.join("scripts/extract.py"), "").unwrap();
+
+        let content = "---\nname: pdf-tools\ndescription: Extract text from PDFs.\nmetadata:\n  author: me\n---\nRun [the script](scripts/extract.py).\n";
+        assert!(skill_problems(&skill, content).is_empty());
+    }
+
+    #[test]
+    fn test_skill_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let skill = dir.path().join("pdf-tools");
+        std::fs::create_dir_all(&skill).unwrap();
+
+        assert_eq!(
+            skill_problems(&skill, "# PDF tools\n"),
+            vec!["SKILL.md has no YAML frontmatter"]
+        );
+
+        let content =
+            "---\nname: PDF--Tools\nauthor: me\n---\nSee [reference](references/REFERENCE.md).\n";
+        assert_eq!(
+            skill_problems(&skill, content),
+            vec![
+                "`name` 'PDF--Tools' may only contain lowercase letters, digits and hyphens",
+                "`name` 'PDF--Tools' must not start or end with a hyphen or contain consecutive hyphens",
+                "`name` 'PDF--Tools' does not match the skill directory 'pdf-tools'",
+                "SKILL.md frontmatter is missing `description`",
+                "unknown frontmatter field `author`",
+                "references missing file 'references/REFERENCE.md'",
+            ]
+        );
+    }
+}
@@ -2,6 +2,8 @@
 
 use super::{expand_path, GitInfo, ResolvedSource, SourceAdapter};
 use crate::error::{ApsError, Result};
+use crate::lockfile::CommitInfo;
+use crate::progress;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use tempfile::TempDir;
@@ -66,6 +68,7 @@ impl SourceAdapter for GitSource {
         let git_info = GitInfo {
             resolved_ref: resolved_git.resolved_ref.clone(),
             commit_sha: resolved_git.commit_sha.clone(),
+            commit_info: resolved_git.commit_info.clone(),
         };
 
         Ok(ResolvedSource::git(
@@ -87,12 +90,15 @@ pub struct ResolvedGitSource {
     pub resolved_ref: String,
     /// Commit SHA at the resolved ref
     pub commit_sha: String,
+    /// Date, author and subject of the commit, if git could report them
+    pub commit_info: Option<CommitInfo>,
 }
 
 /// Clone a git repository and resolve the ref using the git CLI.
 /// This inherits the user's existing git configuration (SSH, credentials, etc.)
 pub fn clone_and_resolve(url: &str, git_ref: &str, shallow: bool) -> Result<ResolvedGitSource> {
     info!("Cloning git repository: {}", url);
+    progress::detail(format!("cloning {}", url));
 
     // Create temp directory for the clone
     let temp_dir = TempDir::new()
@@ -119,11 +125,13 @@ pub fn clone_and_resolve(url: &str, git_ref: &str, shallow: bool) -> Result<Reso
         &commit_sha[..8.min(commit_sha.len())]
     );
 
+    let commit_info = get_commit_info(&repo_path);
     Ok(ResolvedGitSource {
         _temp_dir: temp_dir,
         repo_path,
         resolved_ref,
         commit_sha,
+        commit_info,
     })
 }
 
@@ -204,6 +212,57 @@ fn get_head_commit(repo_path: &Path) -> Result<String> {
     Ok(sha)
 }
 
+/// Date, author and subject of the HEAD commit. Best effort: the lockfile
+/// just omits them if git can't report them.
+fn get_commit_info(repo_path: &Path) -> Option<CommitInfo> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("log")
+        .arg("-1")
+        .arg("--format=%cI%x00%an%x00%s")
+        .arg("HEAD")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        debug!(
+            "Could not read commit info: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.trim_end().splitn(3, '\0');
+    Some(CommitInfo {
+        date: fields.next()?.to_string(),
+        author: fields.next()?.to_string(),
+        subject: fields.next()?.to_string(),
+    })
+}
+
+/// Read a file as committed at `rev`, relative to `dir` in its working tree
+pub fn show_file_at_revision(dir: &Path, rev: &str, file: &str) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .arg("show")
+        .arg(format!("{}:./{}", rev, file))
+        .output()
+        .map_err(|e| ApsError::GitError {
+            message: format!("Failed to execute git show: {}", e),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ApsError::GitError {
+            message: format!("Failed to read {} at {}: {}", file, rev, stderr.trim()),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
 /// Clone a git repository at a specific commit SHA.
 /// This is used when respecting locked versions from the lockfile.
 pub fn clone_at_commit(
@@ -216,6 +275,7 @@ pub fn clone_at_commit(
         url,
         &commit_sha[..8.min(commit_sha.len())]
     );
+    progress::detail(format!("cloning {}", url));
 
     // Create temp directory for the clone
     let temp_dir = TempDir::new()
@@ -273,11 +333,13 @@ pub fn clone_at_commit(
         resolved_ref
     );
 
+    let commit_info = get_commit_info(&repo_path);
     Ok(ResolvedGitSource {
         _temp_dir: temp_dir,
         repo_path,
         resolved_ref: resolved_ref.to_string(),
         commit_sha: commit_sha.to_string(),
+        commit_info,
     })
 }
 
@@ -325,3 +387,65 @@ pub fn get_remote_commit_sha(url: &str, git_ref: &str) -> Result<Option<String>>
     // No matching ref found
     Ok(None)
 }
+
+/// Check that a remote repository is reachable and has `git_ref` (a branch
+/// or tag; "auto" tries main then master) without cloning it. Returns the
+/// ref that was found.
+pub fn check_remote_ref(url: &str, git_ref: &str) -> Result<String> {
+    debug!("Listing remote refs of {}", url);
+    progress::detail(format!("checking {}", url));
+
+    let output = Command::new("git")
+        .arg("ls-remote")
+        .arg("--heads")
+        .arg("--tags")
+        .arg(url)
+        .output()
+        .map_err(|e| ApsError::GitError {
+            message: format!("Failed to execute git ls-remote: {}", e),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ApsError::GitError {
+            message: format!("Cannot reach {}: {}", url, stderr.trim()),
+        });
+    }
+
+    let refs_to_try = if git_ref == "auto" {
+        vec!["main", "master"]
+    } else {
+        vec![git_ref]
+    };
+    find_ref(&String::from_utf8_lossy(&output.stdout), &refs_to_try)
+        .map(str::to_string)
+        .ok_or_else(|| ApsError::GitRefNotFound {
+            refs: refs_to_try.iter().map(|r| r.to_string()).collect(),
+        })
+}
+
+/// The first of `refs` that `git ls-remote` output lists as a branch or tag
+fn find_ref<'a>(listing: &str, refs: &[&'a str]) -> Option<&'a str> {
+    let names: Vec<&str> = listing
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .collect();
+    refs.iter().copied().find(|name| {
+        names.iter().any(|listed| {
+            *listed == format!("refs/heads/{}", name) || *listed == format!("refs/tags/{}", name)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_ref() {
+        let listing = "1111\trefs/heads/master\n2222\trefs/tags/v1.0\n3333\trefs/tags/v1.0^{}\n";
+        assert_eq!(find_ref(listing, &["main", "master"]), Some("master"));
+        assert_eq!(find_ref(listing, &["v1.0"]), Some("v1.0"));
+        assert_eq!(find_ref(listing, &["v2.0"]), None);
+    }
+}
@@ -5,12 +5,17 @@
 
 mod filesystem;
 mod git;
+mod unsupported;
 
 pub use filesystem::FilesystemSource;
-pub use git::{clone_and_resolve, clone_at_commit, get_remote_commit_sha, GitSource};
+pub use git::{
+    check_remote_ref, clone_and_resolve, clone_at_commit, get_remote_commit_sha,
+    show_file_at_revision, GitSource, ResolvedGitSource,
+};
+pub use unsupported::UnsupportedSource;
 
 use crate::error::Result;
-use crate::lockfile::LockedEntry;
+use crate::lockfile::{CommitInfo, LockedEntry};
 use std::path::{Path, PathBuf};
 
 /// Result of resolving a source - contains the path to content and metadata
@@ -78,13 +83,15 @@ impl ResolvedSource {
         symlinked_items: Vec<String>,
     ) -> LockedEntry {
         if let Some(ref git_info) = self.git_info {
-            LockedEntry::new_git(
+            let mut entry = LockedEntry::new_git(
                 &self.source_display,
                 &dest_path.to_string_lossy(),
                 git_info.resolved_ref.clone(),
                 git_info.commit_sha.clone(),
                 checksum,
-            )
+            );
+            entry.commit_info = git_info.commit_info.clone();
+            entry
         } else {
             // For filesystem sources, preserve shell variables in paths
             let (target_path, transformed_items) = if self.use_symlink {
@@ -128,6 +135,8 @@ pub struct GitInfo {
     pub resolved_ref: String,
     /// Commit SHA at the resolved ref
     pub commit_sha: String,
+    /// Date, author and subject of the commit
+    pub commit_info: Option<CommitInfo>,
 }
 
 /// Trait for source adapters that can resolve and provide content
@@ -451,6 +460,11 @@ mod tests {
         let git_info = GitInfo {
             resolved_ref: "main".to_string(),
             commit_sha: "abc123def456".to_string(),
+            commit_info: Some(CommitInfo {
+                date: "2024-05-01T09:30:00+00:00".to_string(),
+                author: "Jane Doe".to_string(),
+                subject: "tighten bash permissions".to_string(),
+            }),
         };
 
         let resolved = ResolvedSource::git(
@@ -472,5 +486,9 @@ mod tests {
         assert!(!locked.is_symlink);
         assert_eq!(locked.resolved_ref, Some("main".to_string()));
         assert_eq!(locked.commit, Some("abc123def456".to_string()));
+        assert_eq!(
+            locked.commit_info.unwrap().to_string(),
+            "2024-05-01: tighten bash permissions"
+        );
     }
 }
@@ -0,0 +1,44 @@
+//! Placeholder adapter for source types this version of aps does not know.
+
+use super::{ResolvedSource, SourceAdapter};
+use crate::error::{ApsError, Result};
+use std::path::Path;
+
+/// Source adapter for a `type` introduced by a newer aps. Resolving always
+/// fails, so only the entries that use it are affected.
+#[derive(Debug, Clone)]
+pub struct UnsupportedSource {
+    /// The `type` value from the manifest
+    pub source_type: String,
+}
+
+impl UnsupportedSource {
+    /// Create a new UnsupportedSource
+    pub fn new(source_type: String) -> Self {
+        Self { source_type }
+    }
+}
+
+impl SourceAdapter for UnsupportedSource {
+    fn source_type(&self) -> &'static str {
+        "unsupported"
+    }
+
+    fn display_name(&self) -> String {
+        format!("{}:<unsupported>", self.source_type)
+    }
+
+    fn path(&self) -> &str {
+        "."
+    }
+
+    fn supports_symlink(&self) -> bool {
+        false
+    }
+
+    fn resolve(&self, _manifest_dir: &Path) -> Result<ResolvedSource> {
+        Err(ApsError::UnsupportedSourceType {
+            source_type: self.source_type.clone(),
+        })
+    }
+}
@@ -0,0 +1,367 @@
+//! Divergence reported by `aps status`.
+//!
+//! Each locked entry can diverge in two directions: its installed files no
+//! longer match the lockfile (modified locally), or its git source has moved
+//! past the locked commit (upgrade available). The report covers both, and
+//! `aps status --format json|yaml` prints it as is so CI and editor
+//! integrations don't have to parse the text view.
+
+use crate::error::{ApsError, Result};
+use crate::lockfile::{LockedEntry, LockedSource, Lockfile};
+use crate::manifest::{manifest_hash, Manifest};
+use crate::sources::get_remote_commit_sha;
+use crate::verify::verify_entry;
+use console::style;
+use serde::Serialize;
+use std::path::Path;
+
+/// `aps status --check` exit code when installed files differ from the lockfile
+pub const EXIT_DRIFT: i32 = 2;
+/// `aps status --check` exit code when git sources have newer commits
+pub const EXIT_UPGRADES: i32 = 3;
+/// `aps status --check` exit code when the lockfile doesn't match the manifest
+pub const EXIT_MISMATCH: i32 = 4;
+
+/// Whether an entry's installed files still match the lockfile
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftState {
+    Clean,
+    Modified,
+    /// No per-file checksums were recorded (installed by an older aps)
+    Unknown,
+}
+
+/// Whether an entry's git source has moved past the locked commit
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UpstreamState {
+    Current,
+    UpgradeAvailable,
+    /// Remotes weren't contacted (`--offline`) or couldn't be reached
+    Unchecked,
+}
+
+/// A newer upstream commit than the one locked
+#[derive(Debug, Clone, Serialize)]
+pub struct AvailableUpgrade {
+    /// Composite fragment the upgrade applies to, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fragment: Option<String>,
+    pub current_commit: String,
+    pub available_commit: String,
+}
+
+/// A file that differs from the lockfile
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftedFile {
+    pub path: String,
+    /// `modified`, `missing` or `extra`
+    pub change: &'static str,
+}
+
+/// One locked entry as reported by `aps status`
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryStatus {
+    pub id: String,
+    pub source: LockedSource,
+    pub dest: String,
+    pub resolved_ref: Option<String>,
+    pub commit: Option<String>,
+    pub checksum: String,
+    pub installed_at: Option<String>,
+    pub refreshed_at: Option<String>,
+    pub drift: DriftState,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub drifted_files: Vec<DriftedFile>,
+    /// Only reported for entries with git sources
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<UpstreamState>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub upgrades: Vec<AvailableUpgrade>,
+}
+
+/// Everything `aps status` knows about the project
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusReport {
+    pub aps_version: String,
+    pub entries: Vec<EntryStatus>,
+    /// Ways the lockfile doesn't match the manifest
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mismatches: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl StatusReport {
+    /// Build the report for `lockfile`, checking installed files relative to
+    /// `manifest_dir`, and the manifest's git sources unless `offline`
+    pub fn build(
+        manifest: &Manifest,
+        lockfile: &Lockfile,
+        manifest_dir: &Path,
+        warnings: Vec<String>,
+        offline: bool,
+    ) -> Result<Self> {
+        let mut entries = Vec::new();
+        for (id, locked) in &lockfile.entries {
+            let (upstream, upgrades) = match upstream_state(manifest, id, locked, offline) {
+                Some((state, upgrades)) => (Some(state), upgrades),
+                None => (None, Vec::new()),
+            };
+            let (drift, drifted_files) = match verify_entry(lockfile, id, manifest_dir)? {
+                None => (DriftState::Unknown, Vec::new()),
+                Some(drifts) if drifts.is_empty() => (DriftState::Clean, Vec::new()),
+                Some(drifts) => (
+                    DriftState::Modified,
+                    drifts
+                        .into_iter()
+                        .map(|d| DriftedFile {
+                            path: d.path,
+                            change: d.kind.label(),
+                        })
+                        .collect(),
+                ),
+            };
+            entries.push(EntryStatus {
+                id: id.clone(),
+                source: locked.source.clone(),
+                dest: locked.dest.clone(),
+                resolved_ref: locked.resolved_ref.clone(),
+                commit: locked.commit.clone(),
+                checksum: locked.checksum.clone(),
+                installed_at: locked.installed_at.clone(),
+                refreshed_at: locked.refreshed_at.clone(),
+                drift,
+                drifted_files,
+                upstream,
+                upgrades,
+            });
+        }
+
+        Ok(StatusReport {
+            aps_version: lockfile.aps_version.clone(),
+            entries,
+            mismatches: mismatches(manifest, lockfile),
+            warnings,
+        })
+    }
+
+    /// Fail with the exit code of the most fundamental divergence: a lockfile
+    /// out of sync with the manifest, then local drift, then upgrades
+    pub fn check(&self) -> Result<()> {
+        let failed = |exit_code, message| Err(ApsError::StatusCheckFailed { exit_code, message });
+
+        if !self.mismatches.is_empty() {
+            return failed(EXIT_MISMATCH, self.mismatches.join("; "));
+        }
+        let drifted: Vec<&str> = self
+            .entries
+            .iter()
+            .filter(|e| e.drift == DriftState::Modified)
+            .map(|e| e.id.as_str())
+            .collect();
+        if !drifted.is_empty() {
+            return failed(
+                EXIT_DRIFT,
+                format!("modified locally: {}", drifted.join(", ")),
+            );
+        }
+        let upgradable: Vec<&str> = self
+            .entries
+            .iter()
+            .filter(|e| !e.upgrades.is_empty())
+            .map(|e| e.id.as_str())
+            .collect();
+        if !upgradable.is_empty() {
+            return failed(
+                EXIT_UPGRADES,
+                format!("upgrades available: {}", upgradable.join(", ")),
+            );
+        }
+        Ok(())
+    }
+
+    /// Print a line for every entry that diverged, in either direction
+    pub fn print_divergence(&self) {
+        println!();
+        let mut diverged = false;
+        for entry in &self.entries {
+            if entry.drift == DriftState::Modified {
+                diverged = true;
+                let files: Vec<&str> = entry
+                    .drifted_files
+                    .iter()
+                    .map(|f| f.path.as_str())
+                    .collect();
+                println!(
+                    "  {} {} ({})",
+                    style("[MODIFIED LOCALLY]").yellow(),
+                    entry.id,
+                    files.join(", ")
+                );
+            }
+            for upgrade in &entry.upgrades {
+                diverged = true;
+                let target = match upgrade.fragment {
+                    Some(ref fragment) => format!("{} fragment {}", entry.id, fragment),
+                    None => entry.id.clone(),
+                };
+                println!(
+                    "  {} {} ({} -> {})",
+                    style("[UPGRADE AVAILABLE]").cyan(),
+                    target,
+                    short(&upgrade.current_commit),
+                    short(&upgrade.available_commit)
+                );
+            }
+        }
+
+        if !diverged {
+            println!("Installed files match the lockfile and no upgrades are available.");
+        } else if self.entries.iter().any(|e| !e.upgrades.is_empty()) {
+            println!();
+            println!("Run `aps sync --upgrade` to update to the latest versions.");
+        }
+        if self
+            .entries
+            .iter()
+            .any(|e| e.upstream == Some(UpstreamState::Unchecked))
+        {
+            println!(
+                "{}",
+                style("Upstream sources were not checked for upgrades.").dim()
+            );
+        }
+    }
+}
+
+/// Entries locked but no longer in the manifest, or in the manifest but not
+/// yet locked, and manifest edits since the last full sync
+fn mismatches(manifest: &Manifest, lockfile: &Lockfile) -> Vec<String> {
+    let mut mismatches = Vec::new();
+    for entry in &manifest.entries {
+        if !lockfile.entries.contains_key(&entry.id) {
+            mismatches.push(format!("'{}' is not in the lockfile", entry.id));
+        }
+    }
+    for id in lockfile.entries.keys() {
+        if !manifest.entries.iter().any(|e| e.id == *id) {
+            mismatches.push(format!("'{}' is no longer in the manifest", id));
+        }
+    }
+    if mismatches.is_empty()
+        && lockfile
+            .manifest_hash
+            .as_ref()
+            .is_some_and(|hash| *hash != manifest_hash(manifest))
+    {
+        mismatches.push("the manifest changed since the last sync".to_string());
+    }
+    mismatches
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..8.min(commit.len())]
+}
+
+/// Compare the locked commits of an entry's git sources with their remotes.
+/// `None` for entries without git sources or no longer in the manifest.
+fn upstream_state(
+    manifest: &Manifest,
+    id: &str,
+    locked: &LockedEntry,
+    offline: bool,
+) -> Option<(UpstreamState, Vec<AvailableUpgrade>)> {
+    let entry = manifest.entries.iter().find(|e| e.id == id)?;
+
+    // (fragment, repo, ref, locked commit) for every git source
+    let mut sources = Vec::new();
+    if let Some((repo, git_ref)) = entry.source.as_ref().and_then(|s| s.git_info()) {
+        sources.push((None, repo, git_ref, locked.commit.clone()?));
+    }
+    for composite in &entry.sources {
+        let Some((repo, git_ref)) = composite.source.git_info() else {
+            continue;
+        };
+        let display = composite.source.display_path();
+        let commit = locked
+            .fragments
+            .iter()
+            .find(|f| f.source == display)
+            .and_then(|f| f.commit.clone());
+        if let Some(commit) = commit {
+            sources.push((Some(display), repo, git_ref, commit));
+        }
+    }
+    if sources.is_empty() {
+        return None;
+    }
+    if offline {
+        return Some((UpstreamState::Unchecked, Vec::new()));
+    }
+
+    let mut upgrades = Vec::new();
+    for (fragment, repo, git_ref, commit) in sources {
+        match get_remote_commit_sha(repo, git_ref) {
+            Ok(Some(remote)) if remote != commit => upgrades.push(AvailableUpgrade {
+                fragment,
+                current_commit: commit,
+                available_commit: remote,
+            }),
+            Ok(Some(_)) => {}
+            _ => return Some((UpstreamState::Unchecked, upgrades)),
+        }
+    }
+    let state = if upgrades.is_empty() {
+        UpstreamState::Current
+    } else {
+        UpstreamState::UpgradeAvailable
+    };
+    Some((state, upgrades))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::LockedEntry;
+
+    #[test]
+    fn test_mismatches() {
+        let manifest: Manifest = serde_yaml::from_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+"#,
+        )
+        .unwrap();
+
+        let mut lockfile = Lockfile::new();
+        assert_eq!(
+            mismatches(&manifest, &lockfile),
+            vec!["'agents' is not in the lockfile"]
+        );
+
+        let locked = |dest: &str| {
+            LockedEntry::new_filesystem("source", dest, "sha256:x".to_string(), false, None, vec![])
+        };
+        lockfile.upsert("agents".to_string(), locked("./AGENTS.md"));
+        lockfile.manifest_hash = Some(manifest_hash(&manifest));
+        assert!(mismatches(&manifest, &lockfile).is_empty());
+
+        lockfile.manifest_hash = Some("sha256:older".to_string());
+        assert_eq!(
+            mismatches(&manifest, &lockfile),
+            vec!["the manifest changed since the last sync"]
+        );
+
+        lockfile.upsert("removed".to_string(), locked("./OLD.md"));
+        assert_eq!(
+            mismatches(&manifest, &lockfile),
+            vec!["'removed' is no longer in the manifest"]
+        );
+    }
+}
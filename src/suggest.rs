@@ -0,0 +1,156 @@
+//! Catalog suggestions for a project (`aps suggest`).
+//!
+//! Suggestions are catalog search results blended with what the project
+//! uses: each detected language, framework or tool is searched for as well,
+//! and its matches add to an entry's score. Without a query, those signals
+//! alone pick a starter set for the project.
+
+use crate::catalog::{Catalog, CatalogEntry};
+use crate::search::CatalogSearch;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// How much a project signal match counts next to a query match
+const SIGNAL_WEIGHT: f64 = 0.5;
+
+/// A suggested catalog entry, with its score and the project signals it
+/// matched
+#[derive(Debug, Clone, Serialize)]
+pub struct Suggestion {
+    #[serde(flatten)]
+    pub entry: CatalogEntry,
+
+    /// Relevance score (higher is better)
+    pub score: f64,
+
+    /// Detected languages, frameworks and tools the entry matched
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub signals: Vec<String>,
+}
+
+/// Suggest entries for `query`, ranked up by the project `signals` and by
+/// `adoption`. With no query, every entry matching a signal is suggested.
+pub fn suggest(
+    catalog: &Catalog,
+    query: Option<&str>,
+    signals: &[&str],
+    adoption: HashMap<String, usize>,
+) -> Vec<Suggestion> {
+    let search = CatalogSearch::new(catalog).with_adoption(adoption);
+    let mut suggestions: Vec<Suggestion> = query
+        .map(|query| search.search(query))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|hit| Suggestion {
+            entry: hit.entry,
+            score: hit.score,
+            signals: Vec::new(),
+        })
+        .collect();
+
+    for signal in signals {
+        for hit in search.search(signal) {
+            let score = hit.score * SIGNAL_WEIGHT;
+            match suggestions.iter_mut().find(|s| s.entry.id == hit.entry.id) {
+                Some(suggestion) => {
+                    suggestion.score += score;
+                    suggestion.signals.push(signal.to_string());
+                }
+                // With a query, signals only rank its matches
+                None if query.is_none() => suggestions.push(Suggestion {
+                    entry: hit.entry,
+                    score,
+                    signals: vec![signal.to_string()],
+                }),
+                None => {}
+            }
+        }
+    }
+
+    for suggestion in &mut suggestions {
+        suggestion.score = (suggestion.score * 1000.0).round() / 1000.0;
+    }
+    suggestions.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.entry.id.cmp(&b.entry.id))
+    });
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::AssetKind;
+
+    fn catalog() -> Catalog {
+        let entry = |id: &str, description: &str| CatalogEntry {
+            id: id.to_string(),
+            name: id.to_string(),
+            kind: AssetKind::CursorRules,
+            destination: format!("./.cursor/rules/{}", id),
+            short_description: Some(description.to_string()),
+            tags: Vec::new(),
+            versions: Vec::new(),
+            source: None,
+            catalog: None,
+        };
+        Catalog {
+            version: 1,
+            release: None,
+            publish: None,
+            entries: vec![
+                entry("django-testing.mdc", "Testing Django views"),
+                entry("fastapi-testing.mdc", "Testing FastAPI endpoints"),
+                entry("dockerfile.mdc", "Small Docker images"),
+                entry("go-style.mdc", "Go style guide"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_signals_rank_query_matches() {
+        let catalog = catalog();
+        let ids = |suggestions: Vec<Suggestion>| {
+            suggestions
+                .into_iter()
+                .map(|s| (s.entry.id, s.signals))
+                .collect::<Vec<_>>()
+        };
+
+        // Equal matches for the query, so the project breaks the tie
+        assert_eq!(
+            ids(suggest(
+                &catalog,
+                Some("testing"),
+                &["fastapi"],
+                HashMap::new()
+            )),
+            vec![
+                (
+                    "fastapi-testing.mdc".to_string(),
+                    vec!["fastapi".to_string()]
+                ),
+                ("django-testing.mdc".to_string(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_starter_set_without_query() {
+        let catalog = catalog();
+        let ids: Vec<_> = suggest(
+            &catalog,
+            None,
+            &["python", "fastapi", "docker"],
+            HashMap::new(),
+        )
+        .into_iter()
+        .map(|s| s.entry.id)
+        .collect();
+        assert_eq!(ids, vec!["fastapi-testing.mdc", "dockerfile.mdc"]);
+
+        assert!(suggest(&catalog, None, &[], HashMap::new()).is_empty());
+    }
+}
@@ -1,8 +1,11 @@
 use console::{style, Style};
+use serde::{Serialize, Serializer};
 use std::path::Path;
+use std::time::Duration;
 
 /// Status of a sync operation for display purposes
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SyncStatus {
     /// Entry was synced (symlinked)
     Synced,
@@ -12,20 +15,64 @@ pub enum SyncStatus {
     Current,
     /// Entry is current but has an upgrade available
     Upgradable,
-    /// Entry had warnings during sync
-    Warning,
-    /// Entry failed to sync (reserved for future use)
-    #[allow(dead_code)]
+    /// Entry failed to sync
     Error,
 }
 
+impl SyncStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            SyncStatus::Synced => "synced",
+            SyncStatus::Copied => "copied",
+            SyncStatus::Current => "current",
+            SyncStatus::Upgradable => "upgrade available",
+            SyncStatus::Error => "error",
+        }
+    }
+}
+
+/// A newer commit than the locked one, for an entry or one of its fragments
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncUpgrade {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fragment: Option<String>,
+    pub current: String,
+    pub available: String,
+}
+
 /// Display item for sync output
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct SyncDisplayItem {
     pub id: String,
+    #[serde(rename = "dest")]
     pub dest_path: String,
+    #[serde(rename = "action")]
     pub status: SyncStatus,
-    pub message: Option<String>,
+    /// Locked version (commit, or checksum for other sources) before the sync
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_version: Option<String>,
+    /// Locked version after the sync
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub upgrades: Vec<SyncUpgrade>,
+    #[serde(
+        rename = "duration_ms",
+        serialize_with = "serialize_millis",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub duration: Option<Duration>,
+    pub warnings: Vec<String>,
+}
+
+fn serialize_millis<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serializer.serialize_u64(duration.as_millis() as u64),
+        None => serializer.serialize_none(),
+    }
 }
 
 impl SyncDisplayItem {
@@ -34,14 +81,71 @@ impl SyncDisplayItem {
             id,
             dest_path,
             status,
-            message: None,
+            previous_version: None,
+            version: None,
+            upgrades: Vec::new(),
+            duration: None,
+            warnings: Vec::new(),
         }
     }
 
-    pub fn with_message(mut self, message: String) -> Self {
-        self.message = Some(message);
+    pub fn with_versions(mut self, previous: Option<String>, current: Option<String>) -> Self {
+        self.previous_version = previous;
+        self.version = current;
+        self
+    }
+
+    pub fn with_upgrades(mut self, upgrades: Vec<SyncUpgrade>) -> Self {
+        self.upgrades = upgrades;
         self
     }
+
+    pub fn with_duration(mut self, duration: Option<Duration>) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn with_warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// `old → new` for the version column, shortened
+    fn version_display(&self) -> String {
+        match self.upgrades.as_slice() {
+            [] => {}
+            [upgrade] if upgrade.fragment.is_none() => {
+                return format!(
+                    "{} → {}",
+                    short_version(&upgrade.current),
+                    short_version(&upgrade.available)
+                )
+            }
+            upgrades => return format!("{} fragments", upgrades.len()),
+        }
+        match (&self.previous_version, &self.version) {
+            (Some(old), Some(new)) if old != new => {
+                format!("{} → {}", short_version(old), short_version(new))
+            }
+            (_, Some(version)) | (Some(version), None) => short_version(version).to_string(),
+            (None, None) => "-".to_string(),
+        }
+    }
+}
+
+/// Everything a sync did, as printed by `aps sync --format json`
+#[derive(Debug, Serialize)]
+pub struct SyncReport<'a> {
+    pub dry_run: bool,
+    pub entries: &'a [SyncDisplayItem],
+    pub orphans_removed: usize,
+    pub warnings: &'a [String],
+}
+
+/// A commit or `sha256:` checksum shortened to 8 characters
+fn short_version(version: &str) -> &str {
+    let hex = version.strip_prefix("sha256:").unwrap_or(version);
+    &hex[..8.min(hex.len())]
 }
 
 /// Format a destination path for display, making it relative and concise
@@ -63,7 +167,8 @@ fn format_dest_path(dest_path: &str, manifest_dir: &Path) -> String {
     }
 }
 
-/// Print all sync results in the new styled format
+/// Print all sync results as a table: entry, destination, action, version,
+/// time and warnings
 pub fn print_sync_results(
     items: &[SyncDisplayItem],
     manifest_path: &Path,
@@ -102,6 +207,10 @@ pub fn print_sync_results(
         println!();
     }
 
+    if items.is_empty() {
+        return;
+    }
+
     // Styles
     let green = Style::new().green();
     let dim = Style::new().dim();
@@ -109,58 +218,88 @@ pub fn print_sync_results(
     let orange = Style::new().color256(208); // Orange color for upgradable
     let red = Style::new().red();
 
-    // Calculate column widths for alignment
-    let max_id_len = items.iter().map(|i| i.id.len()).max().unwrap_or(0);
-    let max_dest_len = items
+    let rows: Vec<[String; 5]> = items
         .iter()
-        .map(|i| format_dest_path(&i.dest_path, manifest_dir).len())
-        .max()
-        .unwrap_or(0);
-
-    // Print each entry
-    for item in items {
-        let (badge, badge_style, status_text, status_style): (&str, &Style, &str, &Style) =
-            match item.status {
-                SyncStatus::Synced => ("✓", &green, "[synced]", &green),
-                SyncStatus::Copied => ("✓", &green, "[copied]", &green),
-                SyncStatus::Current => ("·", &dim, "[current]", &dim),
-                SyncStatus::Upgradable => ("↑", &orange, "[upgrade available]", &orange),
-                SyncStatus::Warning => ("!", &yellow, "[warning]", &yellow),
-                SyncStatus::Error => ("✗", &red, "[error]", &red),
-            };
-
-        let dest_display = format_dest_path(&item.dest_path, manifest_dir);
-
-        // Format: "  ✓ entry-id         → ./dest/path     [synced]"
+        .map(|item| {
+            [
+                item.id.clone(),
+                format_dest_path(&item.dest_path, manifest_dir),
+                item.status.label().to_string(),
+                item.version_display(),
+                item.duration
+                    .map_or_else(|| "-".to_string(), format_duration),
+            ]
+        })
+        .collect();
+    let headers = ["Entry", "Destination", "Action", "Version", "Time"];
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .chain([headers[column].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let any_warnings = items.iter().any(|i| !i.warnings.is_empty());
+
+    let mut header = format!(
+        "    {:<w0$}  {:<w1$}  {:<w2$}  {:<w3$}  {:>w4$}",
+        headers[0],
+        headers[1],
+        headers[2],
+        headers[3],
+        headers[4],
+        w0 = widths[0],
+        w1 = widths[1],
+        w2 = widths[2],
+        w3 = widths[3],
+        w4 = widths[4],
+    );
+    if any_warnings {
+        header.push_str("  Warnings");
+    }
+    println!("{}", dim.apply_to(header));
+
+    // Format: "  ✓ entry-id  ./dest/path  copied  3f2a1b9c → 8d7e6f5a  0.02s"
+    for (item, row) in items.iter().zip(&rows) {
+        let (badge, badge_style, status_style): (&str, &Style, &Style) = match item.status {
+            SyncStatus::Error => ("✗", &red, &red),
+            _ if !item.warnings.is_empty() => ("!", &yellow, &yellow),
+            SyncStatus::Synced | SyncStatus::Copied => ("✓", &green, &green),
+            SyncStatus::Current => ("·", &dim, &dim),
+            SyncStatus::Upgradable => ("↑", &orange, &orange),
+        };
         let id_style = match item.status {
             SyncStatus::Current => Style::new().dim(),
             SyncStatus::Upgradable => Style::new().color256(208),
-            SyncStatus::Warning => Style::new().yellow(),
             SyncStatus::Error => Style::new().red(),
+            _ if !item.warnings.is_empty() => Style::new().yellow(),
             _ => Style::new().white(),
         };
 
-        println!(
-            "  {} {:<width_id$} {} {:<width_dest$} {}",
+        let mut line = format!(
+            "  {} {}  {}  {}  {:<w3$}  {}",
             badge_style.apply_to(badge),
-            id_style.apply_to(&item.id),
-            dim.apply_to("→"),
-            dim.apply_to(&dest_display),
-            status_style.apply_to(status_text),
-            width_id = max_id_len,
-            width_dest = max_dest_len,
+            id_style.apply_to(format!("{:<w$}", row[0], w = widths[0])),
+            dim.apply_to(format!("{:<w$}", row[1], w = widths[1])),
+            status_style.apply_to(format!("{:<w$}", row[2], w = widths[2])),
+            row[3],
+            dim.apply_to(format!("{:>w$}", row[4], w = widths[4])),
+            w3 = widths[3],
         );
-
-        // Print message if present (for warnings/errors/upgrades)
-        if let Some(ref msg) = item.message {
-            let msg_style = match item.status {
-                SyncStatus::Upgradable => &orange,
-                SyncStatus::Warning => &yellow,
-                SyncStatus::Error => &red,
-                _ => &dim,
+        if !item.warnings.is_empty() {
+            let warning_style = if item.status == SyncStatus::Error {
+                &red
+            } else {
+                &yellow
             };
-            println!("      {}", msg_style.apply_to(msg));
+            line.push_str(&format!(
+                "  {}",
+                warning_style.apply_to(item.warnings.join("; "))
+            ));
         }
+        println!("{}", line);
     }
 
     println!();
@@ -264,6 +403,28 @@ pub fn print_sync_summary(
     }
 }
 
+/// Print how long each entry took to sync, slowest first
+pub fn print_sync_timings(timings: &[(String, Duration)]) {
+    let dim = Style::new().dim();
+    let mut sorted: Vec<&(String, Duration)> = timings.iter().collect();
+    sorted.sort_by_key(|(_, duration)| std::cmp::Reverse(*duration));
+    let total: Duration = timings.iter().map(|(_, d)| *d).sum();
+
+    println!("\n{}", style("Timings").bold());
+    for (id, duration) in sorted {
+        println!("  {:>8}  {}", format_duration(*duration), id);
+    }
+    println!(
+        "  {}  {}",
+        dim.apply_to(format!("{:>8}", format_duration(total))),
+        dim.apply_to("total")
+    );
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}s", duration.as_secs_f64())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -284,14 +445,33 @@ mod tests {
     }
 
     #[test]
-    fn test_sync_display_item_with_message() {
+    fn test_sync_display_item_with_warnings() {
         let item = SyncDisplayItem::new(
             "test-entry".to_string(),
             "/path/to/dest".to_string(),
-            SyncStatus::Warning,
+            SyncStatus::Copied,
         )
-        .with_message("Missing SKILL.md".to_string());
+        .with_warnings(vec!["Missing SKILL.md".to_string()]);
 
-        assert_eq!(item.message, Some("Missing SKILL.md".to_string()));
+        assert_eq!(item.warnings, vec!["Missing SKILL.md".to_string()]);
+    }
+
+    #[test]
+    fn test_version_display() {
+        let item = |previous: Option<&str>, current: Option<&str>| {
+            SyncDisplayItem::new("id".to_string(), "dest".to_string(), SyncStatus::Copied)
+                .with_versions(previous.map(String::from), current.map(String::from))
+                .version_display()
+        };
+        assert_eq!(
+            item(Some("0123456789abcdef"), Some("fedcba9876543210")),
+            "01234567 → fedcba98"
+        );
+        assert_eq!(item(None, Some("sha256:0123456789abcdef")), "01234567");
+        assert_eq!(
+            item(Some("sha256:0123456789ab"), Some("sha256:0123456789ab")),
+            "01234567"
+        );
+        assert_eq!(item(None, None), "-");
     }
 }
@@ -0,0 +1,76 @@
+//! Template rendering for composite fragments (`vars`).
+//!
+//! When a `composite_agents_md` entry sets `vars`, each fragment is rendered
+//! as a Handlebars template before composition, so shared fragments can say
+//! "run {{test_command}}" and each project fills in its own values.
+
+use crate::compose::ComposedSource;
+use crate::error::{ApsError, Result};
+use handlebars::Handlebars;
+use std::collections::BTreeMap;
+
+/// Render each fragment with the entry's `vars`
+pub fn render_fragments(
+    sources: &mut [ComposedSource],
+    vars: &BTreeMap<String, serde_yaml::Value>,
+) -> Result<()> {
+    let mut engine = Handlebars::new();
+    // Fail on undefined variables rather than silently rendering nothing
+    engine.set_strict_mode(true);
+    // Output is markdown, not HTML
+    engine.register_escape_fn(handlebars::no_escape);
+
+    for source in sources.iter_mut() {
+        source.content =
+            engine
+                .render_template(&source.content, vars)
+                .map_err(|e| ApsError::TemplateError {
+                    path: source.path.clone(),
+                    message: e.to_string(),
+                })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn vars(yaml: &str) -> BTreeMap<String, serde_yaml::Value> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_render_fragments_substitutes_vars() {
+        let mut sources = vec![ComposedSource::fragment("testing.md", 
+            "# {{project}}\n\nRun `{{test_command}}` before pushing.\n{{#if strict}}No warnings allowed.{{/if}}\n",
+        )];
+        render_fragments(
+            &mut sources,
+            &vars("project: widgets\ntest_command: cargo test && echo '<ok>'\nstrict: true\n"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            sources[0].content,
+            "# widgets\n\nRun `cargo test && echo '<ok>'` before pushing.\nNo warnings allowed.\n"
+        );
+    }
+
+    #[test]
+    fn test_render_fragments_rejects_undefined_vars() {
+        let mut sources = vec![ComposedSource::fragment(
+            "testing.md",
+            "Run {{test_comand}}",
+        )];
+        let err = render_fragments(&mut sources, &vars("test_command: make test\n")).unwrap_err();
+        match err {
+            ApsError::TemplateError { path, message } => {
+                assert_eq!(path, PathBuf::from("testing.md"));
+                assert!(message.contains("test_comand"), "{}", message);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Content transforms applied between resolving a source and installing it.
+//!
+//! Entries can list `transforms` to adapt upstream assets without forking
+//! them. Transforms run in order on every markdown file (`.md`, `.mdc`,
+//! `.markdown`) in the source; other files are installed unchanged.
+//!
+//! `script` transforms run Rhai code in a sandbox: no module imports (and so
+//! no filesystem or network access), no `eval`, and bounded operations.
+
+use crate::error::{ApsError, Result};
+use crate::frontmatter;
+use crate::install::copy_symlink;
+use crate::manifest::{SymlinkMode, Transform};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, Scope};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use tracing::debug;
+use walkdir::WalkDir;
+
+/// File extensions that transforms apply to
+const TRANSFORMED_EXTENSIONS: &[&str] = &["md", "mdc", "markdown"];
+
+/// Whether transforms apply to the file at `path`
+pub fn is_transformed_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| TRANSFORMED_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// Read a file referenced by `prepend_file`/`append_file`
+fn read_transform_file(path: &str, manifest_dir: &Path) -> Result<String> {
+    let full = manifest_dir.join(path);
+    std::fs::read_to_string(&full)
+        .map_err(|e| ApsError::io(e, format!("Failed to read transform file {:?}", full)))
+}
+
+/// Upper bound on operations a script may run, so runaway loops fail
+const SCRIPT_MAX_OPERATIONS: u64 = 5_000_000;
+
+/// Engine for transform scripts, with every escape hatch closed
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_operations(SCRIPT_MAX_OPERATIONS);
+    engine.set_max_call_levels(64);
+    engine.set_max_expr_depths(64, 64);
+    engine.set_max_string_size(16 * 1024 * 1024);
+    engine.on_print(|_| {});
+    engine.on_debug(|text, _, _| debug!("script: {}", text));
+    engine
+}
+
+/// Run a Rhai script with `content` and `path` in scope and return its
+/// string result
+pub fn run_script(script: &Path, content: &str, file: &Path) -> Result<String> {
+    let failed = |message: String| ApsError::TransformScriptError {
+        path: script.to_path_buf(),
+        message,
+    };
+    let code = std::fs::read_to_string(script)
+        .map_err(|e| ApsError::io(e, format!("Failed to read transform script {:?}", script)))?;
+
+    let mut scope = Scope::new();
+    scope.push("content", content.to_string());
+    scope.push("path", file.to_string_lossy().to_string());
+
+    let result: Dynamic = sandboxed_engine()
+        .eval_with_scope(&mut scope, &code)
+        .map_err(|e| failed(e.to_string()))?;
+    result
+        .into_string()
+        .map_err(|kind| failed(format!("script returned {} instead of a string", kind)))
+}
+
+/// Apply `transforms` in order to one document at `file`
+pub fn apply_transforms(
+    content: &str,
+    transforms: &[Transform],
+    manifest_dir: &Path,
+    file: &Path,
+) -> Result<String> {
+    let mut content = content.to_string();
+    for transform in transforms {
+        content = match transform {
+            Transform::StripFrontmatter => frontmatter::split(&content)
+                .map(|(_, body)| body.to_string())
+                .unwrap_or(content),
+            Transform::Replace { from, to } => content.replace(from.as_str(), to),
+            Transform::PrependFile(path) => {
+                let header = read_transform_file(path, manifest_dir)?;
+                format!("{}\n{}", header.trim_end(), content)
+            }
+            Transform::AppendFile(path) => {
+                let footer = read_transform_file(path, manifest_dir)?;
+                format!("{}\n\n{}", content.trim_end(), footer)
+            }
+            Transform::Script(path) => run_script(&manifest_dir.join(path), &content, file)?,
+        };
+    }
+    Ok(content)
+}
+
+/// Content that transforms depend on besides the source itself, for checksums
+pub fn transforms_fingerprint(transforms: &[Transform], manifest_dir: &Path) -> Result<String> {
+    let mut fingerprint = serde_yaml::to_string(transforms).unwrap_or_default();
+    for transform in transforms {
+        if let Transform::PrependFile(path)
+        | Transform::AppendFile(path)
+        | Transform::Script(path) = transform
+        {
+            fingerprint.push_str(&read_transform_file(path, manifest_dir)?);
+        }
+    }
+    Ok(fingerprint)
+}
+
+/// Copy `source` into a temporary directory with transforms applied,
+/// handling symlinks inside it per `symlinks` as installs do.
+/// Returns the directory guard and the path of the staged copy.
+pub fn stage_transformed(
+    source: &Path,
+    transforms: &[Transform],
+    symlinks: SymlinkMode,
+    manifest_dir: &Path,
+) -> Result<(TempDir, PathBuf)> {
+    let staging =
+        TempDir::new().map_err(|e| ApsError::io(e, "Failed to create staging directory"))?;
+    let name = source.file_name().unwrap_or_else(|| "source".as_ref());
+    let staged = staging.path().join(name);
+
+    for entry in WalkDir::new(source).follow_links(symlinks == SymlinkMode::Follow) {
+        let entry = match entry {
+            // A followed link that doesn't resolve has no content to stage
+            Err(e) if e.path().is_some_and(|p| p.is_symlink() && !p.exists()) => continue,
+            entry => entry
+                .map_err(|e| ApsError::io(std::io::Error::other(e), "Failed to traverse source"))?,
+        };
+        let relative = entry.path().strip_prefix(source).unwrap_or(entry.path());
+        let target = if relative.as_os_str().is_empty() {
+            staged.clone()
+        } else {
+            staged.join(relative)
+        };
+
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target)
+                .map_err(|e| ApsError::io(e, format!("Failed to create {:?}", target)))?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ApsError::io(e, format!("Failed to create {:?}", parent)))?;
+        }
+        if entry.path_is_symlink() && copy_symlink(entry.path(), &target, symlinks)? {
+            continue;
+        }
+
+        if is_transformed_file(entry.path()) {
+            let content = std::fs::read_to_string(entry.path())
+                .map_err(|e| ApsError::io(e, format!("Failed to read {:?}", entry.path())))?;
+            let transformed = apply_transforms(&content, transforms, manifest_dir, relative)?;
+            std::fs::write(&target, transformed)
+                .map_err(|e| ApsError::io(e, format!("Failed to write {:?}", target)))?;
+            // Rewritten files keep the source's permissions, like copies do
+            if let Ok(meta) = entry.metadata() {
+                std::fs::set_permissions(&target, meta.permissions()).map_err(|e| {
+                    ApsError::io(e, format!("Failed to set permissions on {:?}", target))
+                })?;
+            }
+            debug!("Transformed {:?}", entry.path());
+        } else {
+            std::fs::copy(entry.path(), &target)
+                .map_err(|e| ApsError::io(e, format!("Failed to copy {:?}", entry.path())))?;
+        }
+    }
+
+    Ok((staging, staged))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_apply_transforms_in_order() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("header.md"), "<!-- Managed by aps -->\n").unwrap();
+        std::fs::write(dir.path().join("footer.md"), "See also: wiki\n").unwrap();
+
+        let transforms = vec![
+            Transform::StripFrontmatter,
+            Transform::Replace {
+                from: "Acme".to_string(),
+                to: "Contoso".to_string(),
+            },
+            Transform::PrependFile("header.md".to_string()),
+            Transform::AppendFile("footer.md".to_string()),
+        ];
+        let result = apply_transforms(
+            "---\ndescription: x\n---\n# Acme rules\n\nFollow Acme style.\n",
+            &transforms,
+            dir.path(),
+            Path::new("AGENTS.md"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            result,
+            "<!-- Managed by aps -->\n# Contoso rules\n\nFollow Contoso style.\n\nSee also: wiki\n"
+        );
+    }
+
+    #[test]
+    fn test_transforms_fingerprint_tracks_files() {
+        let dir = tempdir().unwrap();
+        let transforms = vec![Transform::PrependFile("header.md".to_string())];
+        assert!(transforms_fingerprint(&transforms, dir.path()).is_err());
+
+        std::fs::write(dir.path().join("header.md"), "v1\n").unwrap();
+        let before = transforms_fingerprint(&transforms, dir.path()).unwrap();
+        std::fs::write(dir.path().join("header.md"), "v2\n").unwrap();
+        assert_ne!(
+            before,
+            transforms_fingerprint(&transforms, dir.path()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_stage_transformed_leaves_other_files() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("skill");
+        std::fs::create_dir_all(source.join("scripts")).unwrap();
+        std::fs::write(source.join("SKILL.md"), "Use Acme.\n").unwrap();
+        std::fs::write(source.join("scripts/run.sh"), "echo Acme\n").unwrap();
+
+        let transforms = vec![Transform::Replace {
+            from: "Acme".to_string(),
+            to: "Contoso".to_string(),
+        }];
+        let (_guard, staged) =
+            stage_transformed(&source, &transforms, SymlinkMode::Follow, dir.path()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(staged.join("SKILL.md")).unwrap(),
+            "Use Contoso.\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(staged.join("scripts/run.sh")).unwrap(),
+            "echo Acme\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stage_transformed_honours_symlinks() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("rules");
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::write(source.join("style.md"), "Use Acme.\n").unwrap();
+        std::os::unix::fs::symlink("style.md", source.join("alias.md")).unwrap();
+
+        let transforms = vec![Transform::Replace {
+            from: "Acme".to_string(),
+            to: "Contoso".to_string(),
+        }];
+        let stage = |symlinks| stage_transformed(&source, &transforms, symlinks, dir.path());
+
+        let (_guard, staged) = stage(SymlinkMode::Preserve).unwrap();
+        assert_eq!(
+            std::fs::read_link(staged.join("alias.md")).unwrap(),
+            Path::new("style.md")
+        );
+        assert_eq!(
+            std::fs::read_to_string(staged.join("alias.md")).unwrap(),
+            "Use Contoso.\n"
+        );
+
+        let (_guard, staged) = stage(SymlinkMode::Skip).unwrap();
+        assert!(staged.join("style.md").exists());
+        assert!(staged.join("alias.md").symlink_metadata().is_err());
+
+        let (_guard, staged) = stage(SymlinkMode::Follow).unwrap();
+        assert!(!staged.join("alias.md").is_symlink());
+        assert_eq!(
+            std::fs::read_to_string(staged.join("alias.md")).unwrap(),
+            "Use Contoso.\n"
+        );
+    }
+
+    #[test]
+    fn test_script_transform() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("links.rhai"),
+            r#"let out = content; out.replace("](./", "](https://example.com/"); path + "\n" + out"#,
+        )
+        .unwrap();
+
+        let transforms = vec![Transform::Script("links.rhai".to_string())];
+        let result = apply_transforms(
+            "See [docs](./docs.md).",
+            &transforms,
+            dir.path(),
+            Path::new("rules/a.md"),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            "rules/a.md\nSee [docs](https://example.com/docs.md)."
+        );
+    }
+
+    #[test]
+    fn test_script_sandbox() {
+        let dir = tempdir().unwrap();
+        for (name, code) in [
+            ("import.rhai", r#"import "secrets" as s; content"#),
+            ("eval.rhai", r#"eval("content")"#),
+            ("loop.rhai", "loop {}"),
+            ("number.rhai", "42"),
+        ] {
+            std::fs::write(dir.path().join(name), code).unwrap();
+            let err = run_script(&dir.path().join(name), "x", Path::new("a.md")).unwrap_err();
+            assert!(
+                matches!(err, ApsError::TransformScriptError { .. }),
+                "{}",
+                name
+            );
+        }
+    }
+}
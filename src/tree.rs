@@ -0,0 +1,74 @@
+//! Text rendering for `aps tree`.
+//!
+//! Paths are grouped into directories and drawn with box-drawing guides,
+//! the way `tree(1)` does, with an optional note after each file.
+
+use std::collections::BTreeMap;
+
+#[derive(Default)]
+struct Node {
+    note: String,
+    children: BTreeMap<String, Node>,
+}
+
+/// Render `/`-separated paths with their notes as tree lines, directories
+/// first within each level
+pub fn render_tree(paths: &[(String, String)]) -> Vec<String> {
+    let mut root = Node::default();
+    for (path, note) in paths {
+        let mut node = &mut root;
+        for part in path.split('/').filter(|p| !p.is_empty()) {
+            node = node.children.entry(part.to_string()).or_default();
+        }
+        node.note = note.clone();
+    }
+
+    let mut lines = Vec::new();
+    render_children(&root, "", &mut lines);
+    lines
+}
+
+fn render_children(node: &Node, prefix: &str, lines: &mut Vec<String>) {
+    let mut children: Vec<(&String, &Node)> = node.children.iter().collect();
+    children.sort_by_key(|(name, child)| (child.children.is_empty(), name.as_str()));
+
+    for (i, (name, child)) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let branch = if last { "└── " } else { "├── " };
+        let mut line = format!("{}{}{}", prefix, branch, name);
+        if !child.children.is_empty() {
+            line.push('/');
+        }
+        if !child.note.is_empty() {
+            line.push_str("  ");
+            line.push_str(&child.note);
+        }
+        lines.push(line);
+
+        let guide = if last { "    " } else { "│   " };
+        render_children(child, &format!("{}{}", prefix, guide), lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_tree() {
+        let paths = vec![
+            ("python.mdc".to_string(), String::new()),
+            ("go/errors.mdc".to_string(), "[modified]".to_string()),
+            ("go/style.mdc".to_string(), String::new()),
+        ];
+        assert_eq!(
+            render_tree(&paths),
+            vec![
+                "├── go/",
+                "│   ├── errors.mdc  [modified]",
+                "│   └── style.mdc",
+                "└── python.mdc",
+            ]
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Local, opt-in stats of which catalog entries get adopted.
+//!
+//! With `APS_TRACK_USAGE=1`, adding a catalog entry to a manifest records
+//! it in `usage.yaml` in the aps cache directory, along with the manifest
+//! it went to. Each sync of a manifest forgets the entries it no longer
+//! lists, so an entry's adoption is the number of manifests that kept it.
+//! Nothing leaves the machine. Catalog search ranks adopted entries higher.
+
+use crate::error::{ApsError, Result};
+use crate::manifest::Manifest;
+use crate::registry::cache_root;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Environment variable that turns usage tracking on
+pub const TRACK_USAGE_ENV: &str = "APS_TRACK_USAGE";
+
+/// Usage stats filename within the aps cache directory
+pub const USAGE_FILENAME: &str = "usage.yaml";
+
+/// Whether the user opted in to usage tracking
+pub fn tracking_enabled() -> bool {
+    std::env::var(TRACK_USAGE_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Adoption of catalog entries, keyed by catalog entry id
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UsageStats {
+    #[serde(default)]
+    pub entries: BTreeMap<String, EntryUsage>,
+}
+
+/// How often one catalog entry was added, and where it is still used
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EntryUsage {
+    /// Times the entry was added to a manifest
+    pub added: u32,
+
+    /// Manifests still listing the entry, with its id in each
+    #[serde(default)]
+    pub manifests: BTreeMap<String, String>,
+}
+
+impl EntryUsage {
+    /// Number of manifests that kept the entry
+    pub fn kept(&self) -> usize {
+        self.manifests.len()
+    }
+}
+
+impl UsageStats {
+    /// Path of the usage stats file
+    pub fn path() -> Result<PathBuf> {
+        Ok(cache_root()?.join(USAGE_FILENAME))
+    }
+
+    /// Load the stats, or empty stats if none were recorded yet
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ApsError::io(e, format!("Failed to read usage stats {:?}", path)))?;
+        serde_yaml::from_str(&content).map_err(|e| ApsError::InvalidInput {
+            message: format!("Failed to parse usage stats {:?}: {}", path, e),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ApsError::io(e, format!("Failed to create directory {:?}", parent)))?;
+        }
+        let content = serde_yaml::to_string(self).map_err(|e| ApsError::InvalidInput {
+            message: format!("Failed to serialize usage stats: {}", e),
+        })?;
+        std::fs::write(path, content)
+            .map_err(|e| ApsError::io(e, format!("Failed to write usage stats {:?}", path)))
+    }
+
+    /// Record that catalog entry `catalog_id` was added to the manifest at
+    /// `manifest_path` as `entry_id`
+    pub fn record_added(&mut self, catalog_id: &str, manifest_path: &Path, entry_id: &str) {
+        let usage = self.entries.entry(catalog_id.to_string()).or_default();
+        usage.added += 1;
+        usage
+            .manifests
+            .insert(manifest_key(manifest_path), entry_id.to_string());
+    }
+
+    /// Forget the catalog entries `manifest` no longer lists
+    pub fn record_manifest(&mut self, manifest_path: &Path, manifest: &Manifest) {
+        let key = manifest_key(manifest_path);
+        for usage in self.entries.values_mut() {
+            if usage
+                .manifests
+                .get(&key)
+                .is_some_and(|id| !manifest.entries.iter().any(|e| &e.id == id))
+            {
+                usage.manifests.remove(&key);
+            }
+        }
+    }
+
+    /// Number of manifests keeping each catalog entry, for ranking
+    pub fn adoption(&self) -> HashMap<String, usize> {
+        self.entries
+            .iter()
+            .filter(|(_, usage)| usage.kept() > 0)
+            .map(|(id, usage)| (id.clone(), usage.kept()))
+            .collect()
+    }
+}
+
+/// Adoption counts to rank catalog entries by, when tracking is on
+pub fn load_adoption() -> HashMap<String, usize> {
+    if !tracking_enabled() {
+        return HashMap::new();
+    }
+    match UsageStats::path().and_then(|path| UsageStats::load(&path)) {
+        Ok(stats) => stats.adoption(),
+        Err(e) => {
+            warn!("Ignoring usage stats: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Update the stats with `f`, when tracking is on. Failures only warn, so
+/// tracking never gets in the way of the command.
+pub fn track(f: impl FnOnce(&mut UsageStats)) {
+    if !tracking_enabled() {
+        return;
+    }
+    let result = UsageStats::path().and_then(|path| {
+        let mut stats = UsageStats::load(&path)?;
+        let before = stats.clone();
+        f(&mut stats);
+        if stats != before {
+            stats.save(&path)?;
+            debug!("Updated usage stats at {:?}", path);
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        warn!("Failed to update usage stats: {}", e);
+    }
+}
+
+/// Manifests are told apart by their absolute path
+fn manifest_key(manifest_path: &Path) -> String {
+    std::fs::canonicalize(manifest_path)
+        .unwrap_or_else(|_| manifest_path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::Entry;
+
+    #[test]
+    fn test_adoption_counts_kept_manifests() {
+        let mut stats = UsageStats::default();
+        stats.record_added("rules:auth.mdc", Path::new("/a/aps.yaml"), "auth");
+        stats.record_added("rules:auth.mdc", Path::new("/b/aps.yaml"), "auth");
+        stats.record_added("skills:pdf", Path::new("/a/aps.yaml"), "pdf");
+
+        // /a dropped the rule but kept the skill
+        let manifest = Manifest {
+            entries: vec![Entry {
+                id: "pdf".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        stats.record_manifest(Path::new("/a/aps.yaml"), &manifest);
+
+        assert_eq!(stats.entries["rules:auth.mdc"].added, 2);
+        let adoption = stats.adoption();
+        assert_eq!(adoption["rules:auth.mdc"], 1);
+        assert_eq!(adoption["skills:pdf"], 1);
+    }
+}
@@ -0,0 +1,647 @@
+//! Per-entry checks for `aps validate`.
+//!
+//! Each entry is validated on its own and produces an [`EntryValidation`]:
+//! the warnings found and, under `--strict`, the error that failed it. The
+//! command prints the results and can write them as a JUnit or SARIF report
+//! (see [`crate::report`]).
+//!
+//! Git sources are checked with `git ls-remote` by default: the remote must
+//! be reachable and have the ref, but nothing is cloned, so paths inside the
+//! repository aren't checked. `--deep` clones every git source and checks
+//! it like a local one.
+//!
+//! Entries are validated concurrently on a bounded number of threads (most
+//! of the time goes to waiting on git remotes); results are still reported
+//! in manifest order.
+
+use crate::claude_settings::{compose_claude_settings, invalid_permission_patterns};
+use crate::cli::SchemaKind;
+use crate::compose::{read_source_file, ComposedSource};
+use crate::detect::condition_matches;
+use crate::error::{ApsError, Result};
+use crate::hooks::validate_cursor_hooks;
+use crate::links::relative_links;
+use crate::manifest::{AssetKind, Entry, Source, WarningKind};
+use crate::mdc::{mdc_problems, rule_files, MDC_EXTENSION};
+use crate::schema;
+use crate::skill_spec::skill_problems;
+use crate::sources::check_remote_ref;
+use console::style;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+/// Entries validated at once unless `--jobs` says otherwise
+pub const DEFAULT_JOBS: usize = 8;
+
+/// How thoroughly `aps validate` checks entries
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidateOptions {
+    /// Fail entries on the first problem instead of warning
+    pub strict: bool,
+    /// Clone git sources and check their contents
+    pub deep: bool,
+    /// Check relative links in installed markdown (clones git sources)
+    pub check_links: bool,
+}
+
+/// Outcome of validating one entry
+#[derive(Debug)]
+pub struct EntryValidation {
+    pub id: String,
+    /// `git`, `filesystem`, `composite`, or `none` for entries without a
+    /// source
+    pub source_type: &'static str,
+    /// What was checked, e.g. `repo @ main` or `composite, 3 sources`
+    pub source: String,
+    pub warnings: Vec<String>,
+    /// Warnings left out because the entry's `allow_warnings` lists them
+    pub allowed_warnings: usize,
+    /// Why the entry failed; only set with `--strict`
+    pub error: Option<ApsError>,
+    pub duration: Duration,
+}
+
+impl EntryValidation {
+    /// Print the entry's result line, followed by its warnings and error
+    pub fn print(&self) {
+        let badge = if self.error.is_some() {
+            style("[FAILED]").red()
+        } else if !self.warnings.is_empty() {
+            style("[WARN]").yellow()
+        } else {
+            style("[OK]").green()
+        };
+        println!("  {} {} ({})", badge, self.id, self.source);
+        for warning in &self.warnings {
+            println!("       Warning: {}", warning);
+        }
+        if let Some(ref error) = self.error {
+            println!("       Error: {}", error);
+        }
+        if self.allowed_warnings > 0 {
+            println!(
+                "       {}",
+                style(format!(
+                    "{} allowed warning(s) hidden",
+                    self.allowed_warnings
+                ))
+                .dim()
+            );
+        }
+    }
+}
+
+/// Warnings found validating one entry. Under `--strict` a warning fails
+/// the entry instead, unless the entry's `allow_warnings` lists its kind;
+/// allowed warnings are only counted.
+struct Warnings<'a> {
+    strict: bool,
+    allowed: &'a [WarningKind],
+    list: Vec<String>,
+    allowed_count: usize,
+}
+
+impl<'a> Warnings<'a> {
+    fn new(strict: bool, allowed: &'a [WarningKind]) -> Self {
+        Self {
+            strict,
+            allowed,
+            list: Vec::new(),
+            allowed_count: 0,
+        }
+    }
+
+    /// Record `warning`, or fail with `error` under `--strict`
+    fn warn(&mut self, kind: WarningKind, warning: String, error: ApsError) -> Result<()> {
+        if self.allowed.contains(&kind) {
+            self.allowed_count += 1;
+            return Ok(());
+        }
+        if self.strict {
+            return Err(error);
+        }
+        self.list.push(warning);
+        Ok(())
+    }
+
+    /// Whether a problem of `kind` fails the entry
+    fn fails(&self, kind: WarningKind) -> bool {
+        self.strict && !self.allowed.contains(&kind)
+    }
+
+    /// Record warnings of `kind` from a check that handled `--strict` itself
+    fn extend(&mut self, kind: WarningKind, warnings: Vec<String>) {
+        if self.allowed.contains(&kind) {
+            self.allowed_count += warnings.len();
+        } else {
+            self.list.extend(warnings);
+        }
+    }
+}
+
+/// Validate `entries` on up to `jobs` threads. `report` is called with each
+/// result in manifest order, as soon as it and every earlier one are done.
+pub fn validate_entries(
+    entries: &[Entry],
+    base_dir: &Path,
+    options: ValidateOptions,
+    jobs: usize,
+    mut report: impl FnMut(usize, &EntryValidation),
+) -> Vec<EntryValidation> {
+    let next = AtomicUsize::new(0);
+    let mut done: Vec<Option<EntryValidation>> = entries.iter().map(|_| None).collect();
+
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..jobs.clamp(1, entries.len().max(1)) {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(entry) = entries.get(index) else {
+                    break;
+                };
+                let result = validate_entry(entry, base_dir, options);
+                if sender.send((index, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        // Hold results back until every earlier entry has been reported
+        let mut reported = 0;
+        for (index, result) in receiver {
+            done[index] = Some(result);
+            while let Some(Some(result)) = done.get(reported) {
+                report(reported, result);
+                reported += 1;
+            }
+        }
+    });
+
+    done.into_iter().flatten().collect()
+}
+
+/// Validate one entry's sources relative to `base_dir`. With `strict`, the
+/// first problem fails the entry instead of being a warning.
+pub fn validate_entry(entry: &Entry, base_dir: &Path, options: ValidateOptions) -> EntryValidation {
+    let started = Instant::now();
+    let mut warnings = Warnings::new(options.strict, &entry.allow_warnings);
+
+    let mut source_type = "composite";
+    let (source, outcome) = if entry.is_composite() {
+        (
+            format!("composite, {} sources", entry.sources.len()),
+            check_composite(entry, base_dir, options, &mut warnings),
+        )
+    } else {
+        match entry.source {
+            Some(ref source) => {
+                let adapter = source.to_adapter();
+                source_type = adapter.source_type();
+                let display_name = adapter.display_name();
+                match check_source(entry, base_dir, options, &mut warnings) {
+                    Ok(Some(resolved_ref)) => {
+                        (format!("{} @ {}", display_name, resolved_ref), Ok(()))
+                    }
+                    Ok(None) => (display_name, Ok(())),
+                    Err(e) => (display_name, Err(e)),
+                }
+            }
+            None => {
+                source_type = "none";
+                let outcome = warnings.warn(
+                    WarningKind::MissingSource,
+                    format!("Entry '{}' has no source configured", entry.id),
+                    ApsError::EntryRequiresSource {
+                        id: entry.id.clone(),
+                    },
+                );
+                ("no source".to_string(), outcome)
+            }
+        }
+    };
+
+    EntryValidation {
+        id: entry.id.clone(),
+        source_type,
+        source,
+        warnings: warnings.list,
+        allowed_warnings: warnings.allowed_count,
+        error: outcome.err(),
+        duration: started.elapsed(),
+    }
+}
+
+/// Check every fragment of a composite entry
+fn check_composite(
+    entry: &Entry,
+    base_dir: &Path,
+    options: ValidateOptions,
+    warnings: &mut Warnings,
+) -> Result<()> {
+    // Settings fragments to compose; `None` once one of them couldn't be read
+    let mut settings_fragments: Option<Vec<ComposedSource>> = Some(Vec::new());
+    for composite in &entry.sources {
+        if !options.deep
+            && !options.check_links
+            && check_git_remote(&composite.source, warnings)?.is_some()
+        {
+            settings_fragments = None;
+            continue;
+        }
+        let resolved = match composite.source.to_adapter().resolve(base_dir) {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                warnings.warn(
+                    WarningKind::SourceUnreachable,
+                    format!("Source validation failed: {}", e),
+                    e,
+                )?;
+                settings_fragments = None;
+                continue;
+            }
+        };
+        if !resolved.source_path.exists() {
+            settings_fragments = None;
+            warnings.warn(
+                WarningKind::SourceNotFound,
+                format!("Source path not found: {:?}", resolved.source_path),
+                ApsError::SourcePathNotFound {
+                    path: resolved.source_path.clone(),
+                },
+            )?;
+        } else if entry.kind == AssetKind::ClaudeSettings {
+            let fragment = read_source_file(&resolved.source_path)?;
+            match invalid_permission_patterns(&fragment) {
+                Ok(invalid) => {
+                    for message in invalid {
+                        warnings.warn(
+                            WarningKind::InvalidPermission,
+                            message.clone(),
+                            ApsError::InvalidClaudeSettings {
+                                path: resolved.source_path.clone(),
+                                message,
+                            },
+                        )?;
+                    }
+                }
+                Err(e) => warnings.warn(WarningKind::InvalidSettings, e.to_string(), e)?,
+            }
+            let included = composite
+                .when
+                .as_ref()
+                .is_none_or(|when| condition_matches(when, base_dir));
+            if let Some(fragments) = settings_fragments.as_mut().filter(|_| included) {
+                fragments.push(fragment);
+            }
+        }
+        if options.check_links && resolved.source_path.is_file() {
+            // Fragments are composed into the entry's destination file
+            validate_links(
+                &resolved.source_path,
+                &base_dir.join(entry.destination()),
+                warnings,
+            )?;
+        }
+    }
+    if let Some(fragments) = settings_fragments.filter(|f| !f.is_empty()) {
+        check_composed_settings(entry, &fragments, warnings)?;
+    }
+    Ok(())
+}
+
+/// Check the settings a `claude_settings` entry composes against Claude's
+/// settings schema, since Claude silently ignores keys it doesn't know
+fn check_composed_settings(
+    entry: &Entry,
+    fragments: &[ComposedSource],
+    warnings: &mut Warnings,
+) -> Result<()> {
+    let composed = match compose_claude_settings(fragments, &entry.merge) {
+        Ok(composed) => composed,
+        Err(e) => {
+            return warnings.warn(
+                WarningKind::InvalidSettings,
+                format!("Settings fragments don't compose: {}", e),
+                e,
+            )
+        }
+    };
+    let settings: serde_json::Value = serde_json::from_str(&composed).unwrap_or_default();
+    for problem in schema::problems(SchemaKind::ClaudeSettings, &settings) {
+        warnings.warn(
+            WarningKind::InvalidSettings,
+            format!("Composed settings: {}", problem),
+            ApsError::InvalidComposedSettings {
+                id: entry.id.clone(),
+                message: problem,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Check a single-source entry, returning the ref its git source resolved to
+fn check_source(
+    entry: &Entry,
+    base_dir: &Path,
+    options: ValidateOptions,
+    warnings: &mut Warnings,
+) -> Result<Option<String>> {
+    let Some(ref source) = entry.source else {
+        return Ok(None);
+    };
+    if !options.deep && !options.check_links {
+        if let Some(checked) = check_git_remote(source, warnings)? {
+            return Ok(checked);
+        }
+    }
+    let resolved = match source.to_adapter().resolve(base_dir) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            warnings.warn(
+                WarningKind::SourceUnreachable,
+                format!("Source validation failed: {}", e),
+                e,
+            )?;
+            return Ok(None);
+        }
+    };
+    if !resolved.source_path.exists() {
+        warnings.warn(
+            WarningKind::SourceNotFound,
+            format!("Source path not found: {:?}", resolved.source_path),
+            ApsError::SourcePathNotFound {
+                path: resolved.source_path.clone(),
+            },
+        )?;
+    } else {
+        if entry.kind == AssetKind::CursorSkillsRoot {
+            validate_skills(&resolved.source_path, &entry.id, warnings)?;
+        }
+        if entry.kind == AssetKind::AgentSkill && resolved.source_path.is_dir() {
+            validate_skill(&resolved.source_path, &entry.id, warnings)?;
+        }
+        if entry.kind == AssetKind::CursorRules {
+            validate_rules(&resolved.source_path, entry, warnings)?;
+        }
+        if entry.kind == AssetKind::CursorHooks {
+            let hook_warnings = validate_cursor_hooks(
+                &resolved.source_path,
+                warnings.fails(WarningKind::InvalidCursorHooks),
+            )?;
+            warnings.extend(WarningKind::InvalidCursorHooks, hook_warnings);
+        }
+        if options.check_links {
+            let mut installed_at = base_dir.join(entry.destination());
+            if resolved.source_path.is_file() && installed_at.extension().is_none() {
+                // A single file installed into a destination directory
+                installed_at =
+                    installed_at.join(resolved.source_path.file_name().unwrap_or_default());
+            }
+            validate_links(&resolved.source_path, &installed_at, warnings)?;
+        }
+    }
+    Ok(resolved.git_info.map(|git_info| git_info.resolved_ref))
+}
+
+/// For git sources, check the remote and ref with `git ls-remote` instead of
+/// cloning. `None` for other sources; otherwise the ref found, if any.
+fn check_git_remote(source: &Source, warnings: &mut Warnings) -> Result<Option<Option<String>>> {
+    let Some((repo, git_ref)) = source.git_info() else {
+        return Ok(None);
+    };
+    match check_remote_ref(repo, git_ref) {
+        Ok(found) => Ok(Some(Some(found))),
+        Err(e) => {
+            warnings.warn(
+                WarningKind::SourceUnreachable,
+                format!("Source validation failed: {}", e),
+                e,
+            )?;
+            Ok(Some(None))
+        }
+    }
+}
+
+/// Check relative links in the markdown of `source`, installed at
+/// `installed_at`: each target must either be installed along with it or
+/// already exist in the project next to the installed copy
+fn validate_links(source: &Path, installed_at: &Path, warnings: &mut Warnings) -> Result<()> {
+    // (file, path relative to the source root)
+    let files: Vec<(PathBuf, PathBuf)> = if source.is_file() {
+        let name = PathBuf::from(source.file_name().unwrap_or_default());
+        vec![(source.to_path_buf(), name)]
+    } else {
+        WalkDir::new(source)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| {
+                let relative = e.path().strip_prefix(source).ok()?.to_path_buf();
+                Some((e.into_path(), relative))
+            })
+            .collect()
+    };
+
+    for (file, relative) in files {
+        let is_markdown = file
+            .extension()
+            .is_some_and(|ext| ext == "md" || ext == "mdc" || ext == "markdown");
+        let Some(content) = is_markdown
+            .then(|| std::fs::read_to_string(&file).ok())
+            .flatten()
+        else {
+            continue;
+        };
+
+        let (installed_file, source_dir) = if source.is_file() {
+            (installed_at.to_path_buf(), PathBuf::new())
+        } else {
+            (
+                installed_at.join(&relative),
+                relative.parent().map(Path::to_path_buf).unwrap_or_default(),
+            )
+        };
+        let installed_dir = installed_file.parent().unwrap_or(installed_at);
+
+        for link in relative_links(&content) {
+            let installed_with_it = source.is_dir()
+                && normalize(&source_dir.join(&link)).is_some_and(|t| source.join(t).exists());
+            if installed_with_it || installed_dir.join(&link).exists() {
+                continue;
+            }
+            let file_name = relative.to_string_lossy().to_string();
+            warnings.warn(
+                WarningKind::BrokenLink,
+                format!(
+                    "{} links to '{}', which won't exist where it is installed",
+                    file_name, link
+                ),
+                ApsError::BrokenLink {
+                    file: file_name.clone(),
+                    link,
+                },
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Resolve `.` and `..` in a relative path, or `None` if it climbs out
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(parts.iter().collect())
+}
+
+/// Check the frontmatter of every `.mdc` rule an entry installs
+fn validate_rules(source: &Path, entry: &Entry, warnings: &mut Warnings) -> Result<()> {
+    for file in rule_files(source, &entry.include)? {
+        if file.extension().is_none_or(|ext| ext != MDC_EXTENSION) {
+            continue;
+        }
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| ApsError::io(e, format!("Failed to read rule {:?}", file)))?;
+        let name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        for problem in mdc_problems(&content) {
+            warnings.warn(
+                WarningKind::InvalidCursorRule,
+                format!("Rule '{}': {}", name, problem),
+                ApsError::InvalidCursorRule {
+                    path: file.clone(),
+                    message: problem,
+                },
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Check that every skill in a skills directory has a SKILL.md that
+/// follows the Agent Skills specification
+fn validate_skills(source: &Path, entry_id: &str, warnings: &mut Warnings) -> Result<()> {
+    let mut skill_paths = Vec::new();
+    for dir_entry in std::fs::read_dir(source)
+        .map_err(|e| ApsError::io(e, format!("Failed to read skills directory {:?}", source)))?
+    {
+        let dir_entry = dir_entry.map_err(|e| ApsError::io(e, "Failed to read directory entry"))?;
+        if dir_entry.path().is_dir() {
+            skill_paths.push(dir_entry.path());
+        }
+    }
+    skill_paths.sort();
+
+    for skill_path in skill_paths {
+        validate_skill(&skill_path, entry_id, warnings)?;
+    }
+    Ok(())
+}
+
+/// Check one skill directory's SKILL.md
+fn validate_skill(skill_path: &Path, entry_id: &str, warnings: &mut Warnings) -> Result<()> {
+    let skill_name = skill_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let skill_md = skill_path.join("SKILL.md");
+    if !skill_md.exists() {
+        return warnings.warn(
+            WarningKind::MissingSkillMd,
+            format!(
+                "Skill '{}' in entry '{}' is missing SKILL.md",
+                skill_name, entry_id
+            ),
+            ApsError::MissingSkillMd { skill_name },
+        );
+    }
+
+    let content = std::fs::read_to_string(&skill_md)
+        .map_err(|e| ApsError::io(e, format!("Failed to read {:?}", skill_md)))?;
+    for problem in skill_problems(skill_path, &content) {
+        warnings.warn(
+            WarningKind::InvalidSkill,
+            format!("Skill '{}': {}", skill_name, problem),
+            ApsError::InvalidSkill {
+                skill_name: skill_name.clone(),
+                message: problem,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        std::fs::create_dir_all(source.join("docs")).unwrap();
+        std::fs::write(
+            source.join("AGENTS.md"),
+            "See [setup](docs/setup.md), [style](STYLE.md) and [api](../api.md).\n",
+        )
+        .unwrap();
+        std::fs::write(source.join("docs/setup.md"), "[back](../AGENTS.md)\n").unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("STYLE.md"), "").unwrap();
+
+        // Only AGENTS.md is installed: docs/setup.md is left behind
+        let mut warnings = Warnings::new(false, &[]);
+        validate_links(
+            &source.join("AGENTS.md"),
+            &project.join("AGENTS.md"),
+            &mut warnings,
+        )
+        .unwrap();
+        assert_eq!(
+            warnings.list,
+            vec![
+                "AGENTS.md links to 'docs/setup.md', which won't exist where it is installed",
+                "AGENTS.md links to '../api.md', which won't exist where it is installed",
+            ]
+        );
+
+        // The whole directory is installed: only the link out of it breaks
+        let mut warnings = Warnings::new(false, &[]);
+        validate_links(&source, &project, &mut warnings).unwrap();
+        assert_eq!(
+            warnings.list,
+            vec!["AGENTS.md links to '../api.md', which won't exist where it is installed"]
+        );
+
+        assert!(matches!(
+            validate_links(&source, &project, &mut Warnings::new(true, &[])),
+            Err(ApsError::BrokenLink { .. })
+        ));
+
+        // Allowed warnings are counted, even under --strict
+        let mut warnings = Warnings::new(true, &[WarningKind::BrokenLink]);
+        validate_links(&source, &project, &mut warnings).unwrap();
+        assert!(warnings.list.is_empty());
+        assert_eq!(warnings.allowed_count, 1);
+    }
+}
@@ -0,0 +1,239 @@
+//! Tamper detection for installed files.
+//!
+//! Copied installs record every file they write, with its checksum, in the
+//! lockfile. `aps verify` re-hashes the destinations and reports files that
+//! were modified, deleted, or added since. Files with a managed region are
+//! hashed on that region only, so edits outside it don't count as tampering.
+
+use crate::checksum::{compute_checksum, compute_string_checksum};
+use crate::error::Result;
+use crate::lockfile::Lockfile;
+use crate::managed::managed_region;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// How an installed file differs from the lockfile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftKind {
+    Modified,
+    Missing,
+    Extra,
+}
+
+impl DriftKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DriftKind::Modified => "modified",
+            DriftKind::Missing => "missing",
+            DriftKind::Extra => "extra",
+        }
+    }
+}
+
+/// A file that no longer matches what was installed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    /// Path relative to the manifest directory
+    pub path: String,
+    pub kind: DriftKind,
+}
+
+/// Checksum of a single installed file
+fn file_checksum(path: &Path) -> Result<String> {
+    if let Ok(content) = std::fs::read_to_string(path) {
+        if let Some(region) = managed_region(&content) {
+            return Ok(compute_string_checksum(region));
+        }
+    }
+    compute_checksum(path)
+}
+
+/// Lockfile key for a path: `/`-separated, without `./` components
+pub fn lock_key(path: &Path) -> String {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Checksums of the regular files at `dest` (a file or directory relative to
+/// `manifest_dir`). Symlinks are skipped; their content belongs to the source.
+pub fn installed_files(manifest_dir: &Path, dest: &Path) -> Result<BTreeMap<String, String>> {
+    let root = manifest_dir.join(dest);
+    let mut files = BTreeMap::new();
+    let Ok(meta) = root.symlink_metadata() else {
+        return Ok(files);
+    };
+
+    if meta.is_file() {
+        files.insert(lock_key(dest), file_checksum(&root)?);
+    } else if meta.is_dir() {
+        for item in WalkDir::new(&root).into_iter().filter_map(|e| e.ok()) {
+            let relative = item.path().strip_prefix(&root).unwrap_or(item.path());
+            if !item.file_type().is_file() || relative.components().any(|c| c.as_os_str() == ".git")
+            {
+                continue;
+            }
+            files.insert(lock_key(&dest.join(relative)), file_checksum(item.path())?);
+        }
+    }
+    Ok(files)
+}
+
+/// Compare the installed files of the locked entry `id` against the
+/// lockfile. Returns `None` when the entry has no recorded files to compare
+/// against. Files other entries installed into the same directory are not
+/// extra.
+pub fn verify_entry(
+    lockfile: &Lockfile,
+    id: &str,
+    manifest_dir: &Path,
+) -> Result<Option<Vec<Drift>>> {
+    let locked = &lockfile.entries[id];
+    let dest = Path::new(&locked.dest);
+
+    // Symlinked content lives in the source; only the link itself can go missing
+    if locked.is_symlink || locked.files.is_empty() {
+        if manifest_dir.join(dest).symlink_metadata().is_err() {
+            return Ok(Some(vec![Drift {
+                path: lock_key(dest),
+                kind: DriftKind::Missing,
+            }]));
+        }
+        return Ok(locked.is_symlink.then(Vec::new));
+    }
+
+    let current = installed_files(manifest_dir, dest)?;
+    let mut drift: Vec<Drift> = locked
+        .files
+        .iter()
+        .filter_map(|(path, checksum)| {
+            let kind = match current.get(path) {
+                None => DriftKind::Missing,
+                Some(actual) if actual != checksum => DriftKind::Modified,
+                Some(_) => return None,
+            };
+            Some(Drift {
+                path: path.clone(),
+                kind,
+            })
+        })
+        .collect();
+    drift.extend(
+        current
+            .keys()
+            .filter(|path| {
+                !lockfile
+                    .entries
+                    .values()
+                    .any(|other| other.files.contains_key(*path))
+            })
+            .map(|path| Drift {
+                path: path.clone(),
+                kind: DriftKind::Extra,
+            }),
+    );
+    drift.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(Some(drift))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::LockedEntry;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_installed_files_hashes_directory_contents() {
+        let dir = tempdir().unwrap();
+        let rules = dir.path().join(".cursor/rules");
+        std::fs::create_dir_all(rules.join("nested")).unwrap();
+        std::fs::write(rules.join("a.mdc"), "a").unwrap();
+        std::fs::write(rules.join("nested/b.mdc"), "b").unwrap();
+
+        let files = installed_files(dir.path(), Path::new("./.cursor/rules")).unwrap();
+        let keys: Vec<_> = files.keys().map(String::as_str).collect();
+        assert_eq!(
+            keys,
+            vec![".cursor/rules/a.mdc", ".cursor/rules/nested/b.mdc"]
+        );
+        assert_eq!(files[".cursor/rules/a.mdc"], compute_string_checksum("a"));
+    }
+
+    #[test]
+    fn test_managed_files_hash_only_the_region() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("AGENTS.md");
+        std::fs::write(&path, "<!-- aps:begin -->\nshared\n<!-- aps:end -->\n").unwrap();
+        let before = installed_files(dir.path(), Path::new("AGENTS.md")).unwrap();
+
+        std::fs::write(
+            &path,
+            "# Local\n\n<!-- aps:begin -->\nshared\n<!-- aps:end -->\n",
+        )
+        .unwrap();
+        assert_eq!(
+            installed_files(dir.path(), Path::new("AGENTS.md")).unwrap(),
+            before
+        );
+    }
+
+    #[test]
+    fn test_verify_entry_reports_drift() {
+        let dir = tempdir().unwrap();
+        let rules = dir.path().join("rules");
+        std::fs::create_dir_all(&rules).unwrap();
+        std::fs::write(rules.join("keep.md"), "keep").unwrap();
+        std::fs::write(rules.join("edit.md"), "edit").unwrap();
+        std::fs::write(rules.join("gone.md"), "gone").unwrap();
+
+        let mut locked = LockedEntry::new_composite(vec![], "./rules", "sha256:x".to_string());
+        locked.files = installed_files(dir.path(), Path::new("./rules")).unwrap();
+        let mut lockfile = Lockfile::new();
+        lockfile.upsert("rules".to_string(), locked);
+        assert_eq!(
+            verify_entry(&lockfile, "rules", dir.path()).unwrap(),
+            Some(vec![])
+        );
+
+        std::fs::write(rules.join("edit.md"), "edited").unwrap();
+        std::fs::remove_file(rules.join("gone.md")).unwrap();
+        std::fs::write(rules.join("new.md"), "new").unwrap();
+
+        // Files another entry installed alongside are not extra
+        let mut other = LockedEntry::new_composite(vec![], "./rules", "sha256:y".to_string());
+        other.files = BTreeMap::from([("rules/theirs.md".to_string(), "sha256:t".to_string())]);
+        lockfile.upsert("theirs".to_string(), other);
+        std::fs::write(rules.join("theirs.md"), "theirs").unwrap();
+
+        let drift = verify_entry(&lockfile, "rules", dir.path())
+            .unwrap()
+            .unwrap();
+        let found: Vec<_> = drift.iter().map(|d| (d.path.as_str(), d.kind)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("rules/edit.md", DriftKind::Modified),
+                ("rules/gone.md", DriftKind::Missing),
+                ("rules/new.md", DriftKind::Extra),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_entry_without_file_list() {
+        let dir = tempdir().unwrap();
+        let locked = LockedEntry::new_composite(vec![], "AGENTS.md", "sha256:x".to_string());
+        let mut lockfile = Lockfile::new();
+        lockfile.upsert("agents".to_string(), locked);
+        let drift = verify_entry(&lockfile, "agents", dir.path())
+            .unwrap()
+            .unwrap();
+        assert_eq!(drift[0].kind, DriftKind::Missing);
+
+        std::fs::write(dir.path().join("AGENTS.md"), "x").unwrap();
+        assert_eq!(verify_entry(&lockfile, "agents", dir.path()).unwrap(), None);
+    }
+}
@@ -162,202 +162,4841 @@ fn sync_with_invalid_entry_id_fails() {
         .stderr(predicate::str::contains("Entry not found"));
 }
 
+#[test]
+fn sync_prunes_files_removed_upstream_and_keeps_hand_added_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source/rules");
+    source_dir.child("a.mdc").write_str("# A\n").unwrap();
+    source_dir.child("b.mdc").write_str("# B\n").unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: {}
+      symlink: false
+"#,
+        source_dir.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    let rules = temp.child(".cursor/rules");
+    rules.child("local.mdc").write_str("# Mine\n").unwrap();
+    std::fs::remove_file(source_dir.child("a.mdc").path()).unwrap();
+    source_dir.child("b.mdc").write_str("# B v2\n").unwrap();
+
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+
+    rules.child("a.mdc").assert(predicate::path::missing());
+    rules.child("b.mdc").assert("# B v2\n");
+    rules.child("local.mdc").assert("# Mine\n");
+
+    let lock = std::fs::read_to_string(temp.child("aps.lock.yaml").path()).unwrap();
+    assert!(lock.contains(".cursor/rules/b.mdc"));
+    assert!(!lock.contains(".cursor/rules/a.mdc"));
+    assert!(!lock.contains("local.mdc"));
+}
+
+#[test]
+fn sync_entries_sharing_a_dest_keep_each_others_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("team/a.md").write_str("# A\n").unwrap();
+    temp.child("mine/b.md").write_str("# B\n").unwrap();
+    let entry = |id: &str, root: &str| {
+        format!(
+            "  - id: {id}\n    kind: cursor_rules\n    source:\n      type: filesystem\n      root: {root}\n      symlink: false\n    dest: ./.cursor/rules\n"
+        )
+    };
+    temp.child("aps.yaml")
+        .write_str(&format!("entries:\n{}", entry("team", "team")))
+        .unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+
+    // A second entry installing into the same directory leaves the first's files
+    temp.child("aps.yaml")
+        .write_str(&format!(
+            "entries:\n{}{}",
+            entry("team", "team"),
+            entry("mine", "mine")
+        ))
+        .unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    let rules = temp.child(".cursor/rules");
+    rules.child("a.md").assert("# A\n");
+    rules.child("b.md").assert("# B\n");
+    aps().arg("verify").current_dir(&temp).assert().success();
+
+    // Updating one entry replaces only its own files
+    std::fs::remove_file(temp.child("mine/b.md").path()).unwrap();
+    temp.child("mine/c.md").write_str("# C\n").unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    rules.child("a.md").assert("# A\n");
+    rules.child("b.md").assert(predicate::path::missing());
+    rules.child("c.md").assert("# C\n");
+    aps().arg("verify").current_dir(&temp).assert().success();
+}
+
+#[test]
+fn sync_new_entries_sharing_a_dest_install_together() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("team/a.md").write_str("# A\n").unwrap();
+    temp.child("mine/b.md").write_str("# B\n").unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: team
+    kind: cursor_rules
+    source: { type: filesystem, root: team, symlink: false }
+    dest: ./.cursor/rules
+  - id: mine
+    kind: cursor_rules
+    source: { type: filesystem, root: mine, symlink: false }
+    dest: ./.cursor/rules
+"#,
+        )
+        .unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+
+    temp.child(".cursor/rules/a.md").assert("# A\n");
+    temp.child(".cursor/rules/b.md").assert("# B\n");
+    aps().arg("verify").current_dir(&temp).assert().success();
+}
+
+#[test]
+fn sync_frozen_fails_when_manifest_and_lockfile_differ() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("AGENTS.md")
+        .write_str("# Agents\n")
+        .unwrap();
+
+    let entry = |id: &str| {
+        format!(
+            r#"  - id: {}
+    kind: agents_md
+    dest: {}.md
+    source:
+      type: filesystem
+      root: {}
+      path: AGENTS.md
+      symlink: false
+"#,
+            id,
+            id,
+            source_dir.path().display()
+        )
+    };
+    temp.child("aps.yaml")
+        .write_str(&format!("entries:\n{}", entry("agents")))
+        .unwrap();
+
+    aps()
+        .args(["sync", "--frozen"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No lockfile found"));
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    let lock = std::fs::read_to_string(temp.child("aps.lock.yaml").path()).unwrap();
+
+    std::fs::remove_file(temp.child("agents.md").path()).unwrap();
+    aps()
+        .args(["sync", "--frozen"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("agents.md").assert(predicate::path::exists());
+
+    source_dir
+        .child("AGENTS.md")
+        .write_str("# Changed\n")
+        .unwrap();
+    aps()
+        .args(["sync", "--frozen"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("differs"));
+
+    source_dir
+        .child("AGENTS.md")
+        .write_str("# Agents\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(&format!("entries:\n{}{}", entry("agents"), entry("extra")))
+        .unwrap();
+    aps()
+        .args(["sync", "--locked"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'extra' is not in the lockfile"));
+
+    temp.child("aps.lock.yaml").assert(lock.as_str());
+
+    aps()
+        .args(["sync", "--frozen", "--upgrade"])
+        .current_dir(&temp)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn sync_prune_deletes_files_of_removed_entries() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("AGENTS.md")
+        .write_str("# Agents\n")
+        .unwrap();
+
+    let entry = |id: &str| {
+        format!(
+            r#"  - id: {}
+    kind: agents_md
+    dest: {}.md
+    source:
+      type: filesystem
+      root: {}
+      path: AGENTS.md
+      symlink: false
+"#,
+            id,
+            id,
+            source_dir.path().display()
+        )
+    };
+    temp.child("aps.yaml")
+        .write_str(&format!("entries:\n{}{}", entry("keep"), entry("old")))
+        .unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+
+    // Removing an entry drops its record even when syncing with --only
+    temp.child("aps.yaml")
+        .write_str(&format!("entries:\n{}", entry("keep")))
+        .unwrap();
+    aps()
+        .args(["sync", "--only", "keep"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("aps.lock.yaml")
+        .assert(predicate::str::contains("old.md").not());
+    temp.child("old.md").assert(predicate::path::exists());
+
+    // Restore the record, then prune it for real
+    temp.child("aps.yaml")
+        .write_str(&format!("entries:\n{}{}", entry("keep"), entry("old")))
+        .unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("aps.yaml")
+        .write_str(&format!("entries:\n{}", entry("keep")))
+        .unwrap();
+
+    aps()
+        .args(["sync", "--prune", "--dry-run"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would delete"));
+    temp.child("old.md").assert(predicate::path::exists());
+
+    aps()
+        .args(["sync", "--yes", "--prune"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("entry 'old' was removed"));
+    temp.child("old.md").assert(predicate::path::missing());
+    temp.child("keep.md").assert(predicate::path::exists());
+    assert!(temp.child(".aps-backups").path().is_dir());
+}
+
+#[test]
+fn sync_writes_json_lockfile_when_configured() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Shared\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"lockfile_format: json
+entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    temp.child("aps.lock.json")
+        .assert(predicate::str::starts_with("{"));
+    temp.child("aps.lock.yaml")
+        .assert(predicate::path::missing());
+
+    aps()
+        .arg("status")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("agents"));
+}
+
+#[test]
+fn resync_without_changes_leaves_lockfile_untouched() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Shared\n")
+        .unwrap();
+    temp.child("source/rules/a.mdc").write_str("a").unwrap();
+    temp.child("source/rules/b.mdc").write_str("b").unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: source
+      path: rules
+    dest: ./.cursor/rules
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    let first = std::fs::read_to_string(temp.child("aps.lock.yaml").path()).unwrap();
+    assert!(first.find("agents:").unwrap() < first.find("rules:").unwrap());
+
+    for _ in 0..3 {
+        aps().arg("sync").current_dir(&temp).assert().success();
+        temp.child("aps.lock.yaml").assert(first.as_str());
+    }
+}
+
+#[test]
+fn sync_fails_closed_on_integrity_mismatch() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Shared\n")
+        .unwrap();
+    let manifest = r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+      symlink: false
+    dest: ./AGENTS.md
+"#;
+    temp.child("aps.yaml").write_str(manifest).unwrap();
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    let lockfile = std::fs::read_to_string(temp.child("aps.lock.yaml").path()).unwrap();
+    let checksum = lockfile
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("checksum: "))
+        .unwrap()
+        .to_string();
+    temp.child("aps.yaml")
+        .write_str(&format!("{}    integrity: {}\n", manifest, checksum))
+        .unwrap();
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    // Upstream content changes without the pin being updated
+    temp.child("source/AGENTS.md")
+        .write_str("# Tampered\n")
+        .unwrap();
+    aps()
+        .arg("sync")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pinned integrity"));
+    temp.child("AGENTS.md")
+        .assert(predicate::str::contains("# Shared"));
+
+    temp.child("aps.yaml")
+        .write_str(&format!("{}    integrity: sha256:nope\n", manifest))
+        .unwrap();
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid integrity"));
+}
+
+#[test]
+#[cfg(unix)]
+fn failed_install_leaves_previous_content_in_place() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/a.mdc").write_str("a v1").unwrap();
+    temp.child("source/b.mdc").write_str("b v1").unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: source
+      symlink: false
+    dest: ./.cursor/rules
+"#,
+        )
+        .unwrap();
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    // A dangling symlink makes the copy fail partway through
+    temp.child("source/a.mdc").write_str("a v2").unwrap();
+    std::os::unix::fs::symlink("missing.mdc", temp.child("source/broken.mdc").path()).unwrap();
+    aps().arg("sync").current_dir(&temp).assert().failure();
+
+    temp.child(".cursor/rules/a.mdc").assert("a v1");
+    temp.child(".cursor/rules/b.mdc").assert("b v1");
+    temp.child(".cursor/rules/broken.mdc")
+        .assert(predicate::path::missing());
+    let leftovers: Vec<_> = std::fs::read_dir(temp.child(".cursor").path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(leftovers, vec!["rules"]);
+}
+
+#[test]
+fn sync_dry_run_diff_shows_incoming_changes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Shared\n")
+        .unwrap();
+    temp.child("AGENTS.md").write_str("# Local\n").unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+      symlink: false
+    dest: ./AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["sync", "--dry-run", "--diff"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--- a/AGENTS.md"))
+        .stdout(predicate::str::contains("-# Local"))
+        .stdout(predicate::str::contains("+# Shared"));
+
+    temp.child("AGENTS.md").assert("# Local\n");
+    let leftovers = std::fs::read_dir(temp.path())
+        .unwrap()
+        .filter(|e| {
+            e.as_ref()
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with(".aps-staging")
+        })
+        .count();
+    assert_eq!(leftovers, 0);
+}
+
+// ============================================================================
+// Validate Command Tests
+// ============================================================================
+
+#[test]
+fn validate_fails_without_manifest() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Manifest not found"));
+}
+
+#[test]
+fn validate_empty_manifest_succeeds() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("valid"));
+}
+
+#[test]
+fn validate_invalid_yaml_fails() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("aps.yaml")
+        .write_str("this is not: valid: yaml: [")
+        .unwrap();
+
+    aps().arg("validate").current_dir(&temp).assert().failure();
+}
+
+#[test]
+fn validate_rejects_invalid_max_age() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("aps.yaml")
+        .write_str("max_age: soon\nentries: []\n")
+        .unwrap();
+
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid max_age"));
+}
+
+// ============================================================================
+// Status Command Tests
+// ============================================================================
+
+#[test]
+fn status_fails_without_manifest() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    aps()
+        .arg("status")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Manifest not found"));
+}
+
+#[test]
+fn status_fails_without_lockfile() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+
+    aps()
+        .arg("status")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("lockfile"));
+}
+
+#[test]
+fn status_works_after_sync() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+
+    // First sync to create lockfile
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    // Then status should work
+    aps().arg("status").current_dir(&temp).assert().success();
+}
+
+#[test]
+fn status_check_fails_when_max_age_exceeded() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("AGENTS.md")
+        .write_str("# Test Agents\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"max_age: 30d
+entries:
+  - id: test-agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: {}
+      path: AGENTS.md
+      symlink: false
+"#,
+        source_dir.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    temp.child("aps.lock.yaml")
+        .assert(predicate::str::contains("refreshed_at:"));
+
+    aps()
+        .args(["status", "--check"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+
+    // Backdate the refresh timestamp past the window
+    let lock_path = temp.child("aps.lock.yaml");
+    let lock = std::fs::read_to_string(lock_path.path()).unwrap();
+    let backdated: String = lock
+        .lines()
+        .map(|line| {
+            if line.trim_start().starts_with("refreshed_at:") {
+                "    refreshed_at: 2020-01-01T00:00:00Z".to_string()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    std::fs::write(lock_path.path(), backdated).unwrap();
+
+    aps()
+        .args(["status", "--check"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exceeded max_age"));
+
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("[STALE] test-agents"));
+}
+
+#[test]
+fn status_warns_when_manifest_changed_since_sync() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Shared\n")
+        .unwrap();
+    let manifest = r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#;
+    temp.child("aps.yaml").write_str(manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    temp.child("aps.lock.yaml")
+        .assert(predicate::str::contains("manifest_hash: sha256:"));
+
+    // Comments and formatting don't count as changes
+    temp.child("aps.yaml")
+        .write_str(&format!("# Shared prompts\n{}", manifest))
+        .unwrap();
+    aps()
+        .arg("status")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Warning").not());
+
+    temp.child("aps.yaml")
+        .write_str(&manifest.replace("./AGENTS.md", "./CLAUDE.md"))
+        .unwrap();
+    aps()
+        .arg("status")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("manifest has changed"));
+
+    let lockfile = std::fs::read_to_string(temp.child("aps.lock.yaml").path()).unwrap();
+    let lockfile = lockfile
+        .lines()
+        .map(|l| {
+            if l.starts_with("aps_version:") {
+                "aps_version: 99.0.0"
+            } else {
+                l
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    temp.child("aps.lock.yaml").write_str(&lockfile).unwrap();
+    aps()
+        .arg("status")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("written by aps 99.0.0"));
+}
+
+// ============================================================================
+// Verify Command Tests
+// ============================================================================
+
+#[test]
+fn verify_reports_modified_missing_and_extra_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source/rules");
+    source_dir.child("a.mdc").write_str("# A\n").unwrap();
+    source_dir.child("b.mdc").write_str("# B\n").unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: {}
+      symlink: false
+"#,
+        source_dir.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    temp.child("aps.lock.yaml")
+        .assert(predicate::str::contains(".cursor/rules/a.mdc: sha256:"));
+
+    aps()
+        .arg("verify")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("✓ rules"));
+
+    let rules = temp.child(".cursor/rules");
+    rules.child("a.mdc").write_str("# A (edited)\n").unwrap();
+    std::fs::remove_file(rules.child("b.mdc").path()).unwrap();
+    rules.child("c.mdc").write_str("# C\n").unwrap();
+
+    aps()
+        .arg("verify")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("modified  .cursor/rules/a.mdc"))
+        .stdout(predicate::str::contains("missing   .cursor/rules/b.mdc"))
+        .stdout(predicate::str::contains("extra     .cursor/rules/c.mdc"))
+        .stderr(predicate::str::contains("3 installed file(s) differ"));
+}
+
+// ============================================================================
+// Lock Command Tests
+// ============================================================================
+
+#[test]
+fn lock_diff_reports_changed_entries() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("AGENTS.md")
+        .write_str("# Agents\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: {}
+      path: AGENTS.md
+      symlink: false
+"#,
+        source_dir.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=aps", "-c", "user.email=aps@example.com"])
+            .args(args)
+            .current_dir(temp.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["add", "aps.lock.yaml"]);
+    git(&["commit", "-q", "-m", "lock"]);
+
+    aps()
+        .args(["lock", "diff"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No entries changed."));
+
+    std::fs::copy(
+        temp.child("aps.lock.yaml").path(),
+        temp.child("old.lock.yaml").path(),
+    )
+    .unwrap();
+    source_dir
+        .child("AGENTS.md")
+        .write_str("# Changed\n")
+        .unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+
+    aps()
+        .args(["lock", "diff"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("~ agents"))
+        .stdout(predicate::str::contains("checksum  sha256:"))
+        .stdout(predicate::str::contains("0 added, 0 removed, 1 changed"));
+
+    let output = aps()
+        .args([
+            "lock",
+            "diff",
+            "--against",
+            "old.lock.yaml",
+            "--format",
+            "json",
+        ])
+        .current_dir(&temp)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let changes: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(changes[0]["id"], "agents");
+    assert_eq!(changes[0]["kind"], "changed");
+    assert_eq!(changes[0]["fields"][0]["field"], "checksum");
+}
+
+#[test]
+fn lock_resolve_keeps_newer_side_of_conflicts() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+    temp.child("aps.lock.yaml")
+        .write_str(
+            r#"version: 1
+aps_version: 0.1.12
+entries:
+  agents:
+    source: https://example.com/repo.git:AGENTS.md
+    dest: ./AGENTS.md
+<<<<<<< HEAD
+    commit: aaa
+    checksum: sha256:aaa
+    refreshed_at: 2026-03-01T00:00:00Z
+=======
+    commit: bbb
+    checksum: sha256:bbb
+    refreshed_at: 2026-04-01T00:00:00Z
+>>>>>>> feature
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["lock", "resolve"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("kept theirs (feature), newer"));
+
+    let lockfile = temp.child("aps.lock.yaml");
+    lockfile.assert(predicate::str::contains("commit: bbb"));
+    lockfile.assert(predicate::str::contains("<<<<<<<").not());
+
+    aps()
+        .args(["lock", "resolve"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No conflict markers"));
+}
+
+// ============================================================================
+// Catalog Command Tests
+// ============================================================================
+
+#[test]
+fn catalog_generate_fails_without_manifest() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    aps()
+        .args(["catalog", "generate"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Manifest not found"));
+}
+
+#[test]
+fn catalog_generate_from_assets_directory() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let assets = temp.child("assets");
+    assets
+        .child("skills/pdf/SKILL.md")
+        .write_str("---\nname: pdf\ndescription: Extract text from PDF files\n---\n")
+        .unwrap();
+    assets
+        .child("rules/fastapi.mdc")
+        .write_str("---\ndescription: JWT patterns for FastAPI\n---\n")
+        .unwrap();
+
+    // No manifest needed
+    aps()
+        .args(["catalog", "generate", "--from", "assets"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Generated catalog with 2 entries"));
+    temp.child("aps.catalog.yaml")
+        .assert(predicate::str::contains("id: skills/pdf"));
+
+    aps()
+        .args(["catalog", "search", "jwt", "--catalog", "aps.catalog.yaml"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rules/fastapi.mdc"));
+}
+
+#[test]
+fn suggest_add_appends_catalog_entries() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let assets = temp.child("assets");
+    assets
+        .child("skills/pdf/SKILL.md")
+        .write_str("---\nname: pdf\ndescription: Extract text from PDF files\n---\n")
+        .unwrap();
+    assets
+        .child("rules/fastapi.mdc")
+        .write_str("---\ndescription: JWT patterns for FastAPI\n---\n")
+        .unwrap();
+    aps()
+        .args(["catalog", "generate", "--from", "assets"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+
+    aps()
+        .args([
+            "suggest",
+            "fastapi",
+            "--no-detect",
+            "--catalog",
+            "aps.catalog.yaml",
+            "--add",
+            "missing",
+        ])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No catalog entry with ID 'missing'",
+        ));
+    temp.child("aps.yaml").assert(predicate::path::missing());
+
+    aps()
+        .args([
+            "suggest",
+            "fastapi",
+            "--no-detect",
+            "--catalog",
+            "aps.catalog.yaml",
+            "--add",
+            "skills/pdf,rules/fastapi.mdc",
+        ])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added 2 entries: pdf, fastapi"));
+
+    // The top suggestion is already in the manifest
+    aps()
+        .args([
+            "suggest",
+            "jwt",
+            "--no-detect",
+            "--catalog",
+            "aps.catalog.yaml",
+            "--add-top",
+            "1",
+        ])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Skipped 1 already-existing: fastapi",
+        ));
+
+    let manifest = std::fs::read_to_string(temp.child("aps.yaml").path()).unwrap();
+    assert!(manifest.contains("kind: agent_skill"));
+    assert!(manifest.contains("- fastapi.mdc"));
+
+    aps()
+        .args(["validate"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+}
+
+#[test]
+fn catalog_stats_tracks_added_and_kept_entries() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let cache = temp.child("cache");
+    temp.child("assets/skills/pdf/SKILL.md")
+        .write_str("---\nname: pdf\ndescription: Extract text from PDF files\n---\n")
+        .unwrap();
+    temp.child("assets/rules/fastapi.mdc")
+        .write_str("---\ndescription: JWT patterns for FastAPI\n---\n")
+        .unwrap();
+    aps()
+        .args(["catalog", "generate", "--from", "assets"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    let tracked = || {
+        let mut cmd = aps();
+        cmd.env("APS_CACHE_DIR", cache.path())
+            .env("APS_TRACK_USAGE", "1")
+            .current_dir(&temp);
+        cmd
+    };
+
+    // Nothing is recorded without opting in
+    aps()
+        .env("APS_CACHE_DIR", cache.path())
+        .args([
+            "suggest",
+            "pdf",
+            "--no-detect",
+            "--catalog",
+            "aps.catalog.yaml",
+            "--add",
+            "skills/pdf",
+        ])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    aps()
+        .env("APS_CACHE_DIR", cache.path())
+        .args(["catalog", "stats"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Usage tracking is off"))
+        .stdout(predicate::str::contains("No catalog entries added yet"));
+
+    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+    tracked()
+        .args([
+            "suggest",
+            "pdf",
+            "--no-detect",
+            "--catalog",
+            "aps.catalog.yaml",
+            "--add",
+            "skills/pdf,rules/fastapi.mdc",
+        ])
+        .assert()
+        .success();
+    tracked()
+        .args(["catalog", "stats", "--format", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""id": "rules/fastapi.mdc",
+    "kept": 1"#,
+        ));
+
+    // Dropping the entries from the manifest is noticed on the next sync
+    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+    tracked().arg("sync").assert().success();
+    tracked()
+        .args(["catalog", "stats"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Usage tracking is off").not())
+        .stdout(predicate::str::is_match(r"skills/pdf\s+0\s+1").unwrap());
+}
+
+#[test]
+fn catalog_generate_creates_catalog_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+
+    aps()
+        .args(["catalog", "generate"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+
+    temp.child("aps.catalog.yaml")
+        .assert(predicate::path::exists());
+}
+
+/// Write a small catalog with three entries for list/search tests
+fn write_sample_catalog(temp: &assert_fs::TempDir) {
+    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+    temp.child("aps.catalog.yaml")
+        .write_str(
+            r#"version: 1
+entries:
+  - id: rules:fastapi-auth.mdc
+    name: fastapi-auth.mdc
+    kind: cursor_rules
+    destination: ./.cursor/rules/fastapi-auth.mdc
+    short_description: JWT patterns for FastAPI
+    tags: [python, security]
+  - id: rules:python-style.mdc
+    name: python-style.mdc
+    kind: cursor_rules
+    destination: ./.cursor/rules/python-style.mdc
+    short_description: Python style guide
+  - id: skills:pdf
+    name: pdf
+    kind: agent_skill
+    destination: ./.claude/skills/pdf
+"#,
+        )
+        .unwrap();
+}
+
+#[test]
+fn catalog_search_field_and_phrase_queries() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    write_sample_catalog(&temp);
+
+    aps()
+        .args(["catalog", "search", "python tag:security"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rules:fastapi-auth.mdc"))
+        .stdout(predicate::str::contains("rules:python-style.mdc").not());
+
+    aps()
+        .args(["catalog", "search", "kind:cursor_rules", "--format", "json"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"total\": 2"));
+
+    aps()
+        .args(["catalog", "search", "\"style guide\" kind:cursor_rules"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rules:python-style.mdc"))
+        .stdout(predicate::str::contains("rules:fastapi-auth.mdc").not());
+
+    aps()
+        .args(["catalog", "search", "\"guide style\""])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rules:python-style.mdc").not());
+}
+
+#[test]
+fn catalog_versions_info_and_add() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+    temp.child("aps.catalog.yaml")
+        .write_str(
+            r#"version: 1
+entries:
+  - id: skills/pdf
+    name: pdf
+    kind: agent_skill
+    destination: ./.claude/skills/pdf
+    source:
+      type: git
+      repo: https://github.com/acme/assets.git
+      ref: main
+      path: skills/pdf
+    versions:
+      - version: 1.0.0
+        ref: v1.0.0
+        changelog: First release
+      - version: 1.1.0
+        ref: v1.1.0
+        changelog: Adds OCR
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["catalog", "info", "skills/pdf"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("git: acme/assets @ main"))
+        .stdout(predicate::str::contains("1.1.0 @ v1.1.0  Adds OCR"));
+
+    aps()
+        .args(["catalog", "info", "nope"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No catalog entry with ID 'nope'"));
+
+    aps()
+        .args(["suggest", "pdf", "--no-detect", "--add", "skills/pdf@2"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Available versions: 1.1.0, 1.0.0"));
+
+    // Without a terminal, the newest matching version is taken
+    aps()
+        .args(["add", "skills/pdf@^1.0", "--no-sync"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added entry 'pdf' at 1.1.0"));
+    let manifest = std::fs::read_to_string(temp.child("aps.yaml").path()).unwrap();
+    assert!(manifest.contains("version: 1.1.0"));
+    assert!(manifest.contains("ref: v1.1.0"));
+}
+
+#[test]
+fn catalog_lint_reports_unhealthy_entries() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let repo = temp.child("assets");
+    repo.create_dir_all().unwrap();
+    create_git_repo_with_agents_md(repo.path(), "# Agents\n");
+    temp.child("rules/python.mdc")
+        .write_str("# Python\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str("catalogs:\n  - team.catalog.yaml\n  - extra.catalog.yaml\nentries: []\n")
+        .unwrap();
+    temp.child("team.catalog.yaml")
+        .write_str(&format!(
+            r#"version: 1
+entries:
+  - id: agents
+    name: AGENTS.md
+    kind: agents_md
+    destination: ./AGENTS.md
+    short_description: Team agent guidance
+    source: {{ type: git, repo: "{repo}", ref: main, path: AGENTS.md }}
+    versions:
+      - {{ version: 1.0.0, ref: v1.0.0 }}
+  - id: rules/python.mdc
+    name: python.mdc
+    kind: cursor_rules
+    destination: ./.cursor/rules/python.mdc
+    source: {{ type: filesystem, root: ., path: rules/python.mdc }}
+"#,
+            repo = repo.path().display()
+        ))
+        .unwrap();
+    temp.child("extra.catalog.yaml")
+        .write_str(
+            r#"version: 1
+entries:
+  - id: agents
+    name: AGENTS.md
+    kind: agents_md
+    destination: ./AGENTS.md
+    short_description: Other agent guidance
+    source: { type: filesystem, root: ., path: missing/AGENTS.md }
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["catalog", "lint"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stdout(
+            predicate::str::contains("[FAILED] agents: ").and(predicate::str::contains("v1.0.0")),
+        )
+        .stdout(predicate::str::contains(
+            "[WARN] rules/python.mdc: No description",
+        ))
+        .stdout(predicate::str::contains(
+            "team.catalog.yaml, which takes precedence",
+        ))
+        .stdout(predicate::str::contains(
+            "Path 'missing/AGENTS.md' does not exist",
+        ))
+        .stderr(predicate::str::contains("Catalog lint found 3 problem(s)"));
+
+    // A healthy catalog passes, but --strict fails on the missing description
+    aps()
+        .args([
+            "catalog",
+            "lint",
+            "--deep",
+            "--catalog",
+            "team.catalog.yaml",
+            "--format",
+            "json",
+        ])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("\"kind\": \"dead_source\""));
+    git(repo.path()).args(["tag", "v1.0.0"]).output().unwrap();
+    aps()
+        .args([
+            "catalog",
+            "lint",
+            "--deep",
+            "--catalog",
+            "team.catalog.yaml",
+        ])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 warning(s)"));
+    aps()
+        .args([
+            "catalog",
+            "lint",
+            "--catalog",
+            "team.catalog.yaml",
+            "--strict",
+        ])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Catalog lint found 1 problem(s)"));
+}
+
+#[test]
+fn catalog_publish_bumps_release_and_pushes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    write_sample_catalog(&temp);
+    let seed = temp.child("seed");
+    seed.create_dir_all().unwrap();
+    create_git_repo_with_agents_md(seed.path(), "# Agents\n");
+    git(temp.path())
+        .args(["clone", "--bare", "seed", "remote.git"])
+        .output()
+        .unwrap();
+    let remote = temp.child("remote.git");
+    let publish = || {
+        let mut cmd = aps();
+        cmd.args(["catalog", "publish", "--to"])
+            .arg(remote.path())
+            .env("GIT_AUTHOR_NAME", "Test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "Test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .current_dir(&temp);
+        cmd
+    };
+
+    publish()
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[WARN] skills:pdf: No description",
+        ))
+        .stdout(predicate::str::contains("as release 1.0.0"));
+    temp.child("aps.catalog.yaml")
+        .assert(predicate::str::contains("release").not());
+
+    publish()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Published catalog release 1.0.0"));
+    publish()
+        .args(["--bump", "minor"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Published catalog release 1.1.0"));
+    temp.child("aps.catalog.yaml")
+        .assert(predicate::str::contains("release: 1.1.0"));
+
+    let output = git(remote.path())
+        .args(["show", "main:aps.catalog.yaml"])
+        .output()
+        .unwrap();
+    let published = String::from_utf8_lossy(&output.stdout);
+    assert!(published.contains("release: 1.1.0"));
+    assert!(published.contains("rules:fastapi-auth.mdc"));
+    let output = git(remote.path())
+        .args(["log", "-1", "--format=%s", "main"])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "Publish catalog 1.1.0"
+    );
+}
+
+#[test]
+fn catalog_publish_requires_a_target() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    write_sample_catalog(&temp);
+
+    aps()
+        .args(["catalog", "publish"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("set `publish.to` in the catalog"));
+}
+
+#[test]
+fn catalog_list_fails_without_catalog() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+
+    aps()
+        .args(["catalog", "list"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Catalog not found"));
+}
+
+#[test]
+fn catalog_list_json_paginates_with_total() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    write_sample_catalog(&temp);
+
+    aps()
+        .args([
+            "catalog", "list", "--format", "json", "--limit", "1", "--offset", "1",
+        ])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"total\": 3"))
+        .stdout(predicate::str::contains("\"offset\": 1"))
+        .stdout(predicate::str::contains("\"limit\": 1"))
+        .stdout(predicate::str::contains("rules:python-style.mdc"))
+        .stdout(predicate::str::contains("rules:fastapi-auth.mdc").not());
+}
+
+#[test]
+fn catalog_list_text_shows_page_footer() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    write_sample_catalog(&temp);
+
+    aps()
+        .args(["catalog", "list", "--limit", "2"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Showing 1-2 of 3"));
+}
+
+#[test]
+fn catalog_search_json_returns_scored_matches() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    write_sample_catalog(&temp);
+
+    aps()
+        .args(["catalog", "search", "fastapi", "--format", "json"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"total\": 1"))
+        .stdout(predicate::str::contains("\"score\""))
+        .stdout(predicate::str::contains("rules:fastapi-auth.mdc"));
+}
+
+#[test]
+fn suggest_uses_project_signals() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    write_sample_catalog(&temp);
+
+    // Nothing detected and no query
+    aps()
+        .arg("suggest")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing to suggest from"));
+
+    temp.child("requirements.txt")
+        .write_str("fastapi\n")
+        .unwrap();
+    aps()
+        .arg("suggest")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Detected: python, fastapi"))
+        .stdout(predicate::str::contains("rules:fastapi-auth.mdc"))
+        .stdout(predicate::str::contains("matches python, fastapi"))
+        .stdout(predicate::str::contains("skills:pdf").not());
+
+    aps()
+        .args(["suggest", "style", "--no-detect", "--format", "json"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"total\": 1"))
+        .stdout(predicate::str::contains("rules:python-style.mdc"));
+
+    // Picking needs a terminal
+    aps()
+        .args(["suggest", "fastapi", "--pick"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Pick suggestions to add"));
+}
+
+#[test]
+fn catalog_search_registry_uses_cache_when_unreachable() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    write_sample_catalog(&temp);
+    let cache = temp.child("cache");
+    let url = format!("file://{}", temp.child("aps.catalog.yaml").path().display());
+
+    // First search fetches and caches the index
+    aps()
+        .args(["catalog", "search", "fastapi", "--registry", &url])
+        .env("APS_CACHE_DIR", cache.path())
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rules:fastapi-auth.mdc"))
+        .stderr(predicate::str::contains("Warning").not());
+
+    // Registry goes away: a forced refresh falls back to the cached index
+    std::fs::remove_file(temp.child("aps.catalog.yaml").path()).unwrap();
+    aps()
+        .args([
+            "catalog",
+            "search",
+            "fastapi",
+            "--registry",
+            &url,
+            "--refresh",
+        ])
+        .env("APS_CACHE_DIR", cache.path())
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rules:fastapi-auth.mdc"))
+        .stderr(predicate::str::contains("using cached index"));
+}
+
+#[test]
+fn catalog_list_registry_offline_without_cache_fails() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    aps()
+        .args([
+            "catalog",
+            "list",
+            "--registry",
+            "https://registry.invalid/catalog.yaml",
+            "--offline",
+        ])
+        .env("APS_CACHE_DIR", temp.child("cache").path())
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no cached index"));
+}
+
+#[test]
+fn catalog_from_git_repository() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let cache = temp.child("cache");
+    let repo = temp.child("prompt-catalog.git");
+    repo.create_dir_all().unwrap();
+    create_git_repo_with_agents_md(repo.path(), "# Agents\n");
+    repo.child("aps.catalog.yaml")
+        .write_str(
+            r#"entries:
+  - id: rules:go-style.mdc
+    name: go-style.mdc
+    kind: cursor_rules
+    destination: ./.cursor/rules/go-style.mdc
+  - id: rules:python-style.mdc
+    name: python-style.mdc
+    kind: cursor_rules
+    destination: ./.cursor/rules/python-style.mdc
+    short_description: Org-wide Python style
+"#,
+        )
+        .unwrap();
+    git(repo.path()).args(["add", "."]).output().unwrap();
+    git(repo.path())
+        .args(["commit", "-m", "Add catalog"])
+        .output()
+        .unwrap();
+    let url = format!("file://{}", repo.path().display());
+
+    aps()
+        .args(["catalog", "search", "go", "--catalog", &url])
+        .env("APS_CACHE_DIR", cache.path())
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rules:go-style.mdc"));
+
+    // Catalogs listed in the manifest are merged, earlier ones first
+    write_sample_catalog(&temp);
+    temp.child("aps.yaml")
+        .write_str(&format!(
+            "catalogs:\n  - aps.catalog.yaml\n  - {}\nentries: []\n",
+            url
+        ))
+        .unwrap();
+    aps()
+        .args(["catalog", "list"])
+        .env("APS_CACHE_DIR", cache.path())
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(4 entries)"))
+        .stdout(predicate::str::contains("rules:go-style.mdc"))
+        .stdout(predicate::str::contains(format!("from {}", url)))
+        .stdout(predicate::str::contains("Org-wide Python style").not());
+
+    aps()
+        .args(["catalog", "update"])
+        .env("APS_CACHE_DIR", cache.path())
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updated"))
+        .stdout(predicate::str::contains("(2 entries)"));
+
+    // Updating fails rather than reusing the cache
+    std::fs::remove_dir_all(repo.path()).unwrap();
+    aps()
+        .args(["catalog", "update"])
+        .env("APS_CACHE_DIR", cache.path())
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("aps::registry::error"));
+}
+
 // ============================================================================
-// Validate Command Tests
+// Audit Log Tests
+// ============================================================================
+
+#[test]
+fn log_empty_without_records() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+
+    aps()
+        .arg("log")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No audit records found"));
+}
+
+#[test]
+fn sync_appends_audit_record() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let source_dir = temp.child("source");
+    source_dir.create_dir_all().unwrap();
+    source_dir
+        .child("AGENTS.md")
+        .write_str("# Test Agents\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: test-agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: {}
+      path: AGENTS.md
+      symlink: false
+    dest: ./AGENTS.md
+"#,
+        source_dir.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    aps()
+        .args(["sync", "--dry-run"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    // Nothing changed, so nothing is recorded
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    temp.child(".aps/audit.log")
+        .assert(predicate::str::contains("\"command\":\"sync\""));
+
+    aps()
+        .args(["log", "--format", "json"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"id\": \"test-agents\""))
+        .stdout(predicate::str::contains("\"after\": \"sha256:"));
+
+    // Dry runs and no-op syncs are not recorded
+    let log = std::fs::read_to_string(temp.child(".aps/audit.log").path()).unwrap();
+    assert_eq!(log.lines().count(), 1);
+}
+
+// ============================================================================
+// Filesystem Source Tests
 // ============================================================================
 
 #[test]
-fn validate_fails_without_manifest() {
+fn sync_filesystem_source_copies_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    // Create source file
+    let source_dir = temp.child("source");
+    source_dir.create_dir_all().unwrap();
+    source_dir
+        .child("AGENTS.md")
+        .write_str("# Test Agents\n")
+        .unwrap();
+
+    // Create manifest pointing to local file
+    let manifest = format!(
+        r#"entries:
+  - id: test-agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: {}
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        source_dir.path().display()
+    );
+
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    // Verify file was copied
+    temp.child("AGENTS.md")
+        .assert(predicate::str::contains("# Test Agents"));
+}
+
+#[test]
+fn sync_file_and_dir_kinds_copy_to_dest() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let source_dir = temp.child("source");
+    source_dir
+        .child(".editorconfig")
+        .write_str("root = true\n")
+        .unwrap();
+    source_dir
+        .child("docs/prompting.md")
+        .write_str("# Prompting\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: editorconfig
+    kind: file
+    source:
+      type: filesystem
+      root: {root}
+      path: .editorconfig
+      symlink: false
+    dest: ./.editorconfig
+  - id: shared-docs
+    kind: dir
+    source:
+      type: filesystem
+      root: {root}
+      path: docs
+      symlink: false
+    dest: ./docs/shared
+"#,
+        root = source_dir.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    temp.child(".editorconfig")
+        .assert(predicate::str::contains("root = true"));
+    temp.child("docs/shared/prompting.md")
+        .assert(predicate::str::contains("# Prompting"));
+}
+
+#[cfg(unix)]
+#[test]
+fn sync_preserves_executable_bits_and_applies_mode_override() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let script = temp.child("source/hooks/check.sh");
+    script.write_str("#!/bin/sh\n").unwrap();
+    std::fs::set_permissions(script.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+    temp.child("source/hooks/README.md")
+        .write_str("# Hooks\n")
+        .unwrap();
+    let readme = temp.child("source/hooks/README.md");
+    std::fs::set_permissions(readme.path(), std::fs::Permissions::from_mode(0o640)).unwrap();
+
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: hooks
+    kind: dir
+    source:
+      type: filesystem
+      root: source
+      path: hooks
+      symlink: false
+    dest: ./tools/hooks
+    transforms:
+      - replace: { from: Hooks, to: Scripts }
+  - id: private
+    kind: dir
+    source:
+      type: filesystem
+      root: source
+      path: hooks
+    dest: ./tools/private
+    mode: "0600"
+"#,
+        )
+        .unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    let mode = |path: &str| {
+        std::fs::metadata(temp.child(path).path())
+            .unwrap()
+            .permissions()
+            .mode()
+            & 0o7777
+    };
+    assert_eq!(mode("tools/hooks/check.sh"), 0o755);
+    temp.child("tools/hooks/README.md").assert("# Scripts\n");
+    // Transformed files keep the source's permissions too
+    assert_eq!(mode("tools/hooks/README.md"), 0o640);
+    assert_eq!(mode("tools/private/check.sh"), 0o600);
+    assert_eq!(mode("tools/private/README.md"), 0o600);
+    assert!(!temp
+        .child("tools/private/check.sh")
+        .path()
+        .symlink_metadata()
+        .unwrap()
+        .is_symlink());
+}
+
+#[cfg(unix)]
+#[test]
+fn sync_copies_internal_symlinks_per_symlinks_option() {
+    use std::os::unix::fs::symlink;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let docs = temp.child("source/docs");
+    docs.child("real.md").write_str("real").unwrap();
+    docs.child("other.md").write_str("other").unwrap();
+    symlink("real.md", docs.child("link.md").path()).unwrap();
+    symlink("missing.md", docs.child("dangling.md").path()).unwrap();
+
+    let entry = |id: &str, symlinks: &str| {
+        format!(
+            r#"  - id: {id}
+    kind: dir
+    source:
+      type: filesystem
+      root: source
+      path: docs
+      symlink: false
+    dest: ./{id}
+    symlinks: {symlinks}
+"#
+        )
+    };
+    temp.child("aps.yaml")
+        .write_str(&format!(
+            "entries:\n{}{}{}",
+            entry("followed", "follow"),
+            entry("preserved", "preserve"),
+            entry("skipped", "skip")
+        ))
+        .unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    let is_link = |path: &str| {
+        temp.child(path)
+            .path()
+            .symlink_metadata()
+            .map(|m| m.is_symlink())
+            .ok()
+    };
+    assert_eq!(is_link("followed/link.md"), Some(false));
+    temp.child("followed/link.md").assert("real");
+    assert_eq!(is_link("followed/dangling.md"), None);
+
+    assert_eq!(is_link("preserved/link.md"), Some(true));
+    assert_eq!(
+        std::fs::read_link(temp.child("preserved/link.md").path()).unwrap(),
+        std::path::Path::new("real.md")
+    );
+    assert_eq!(is_link("preserved/dangling.md"), Some(true));
+
+    assert_eq!(is_link("skipped/link.md"), None);
+    assert_eq!(is_link("skipped/dangling.md"), None);
+    temp.child("skipped/real.md").assert("real");
+
+    // Retargeting a link changes the source checksum, so it re-syncs
+    std::fs::remove_file(docs.child("link.md").path()).unwrap();
+    symlink("other.md", docs.child("link.md").path()).unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("followed/link.md").assert("other");
+    assert_eq!(
+        std::fs::read_link(temp.child("preserved/link.md").path()).unwrap(),
+        std::path::Path::new("other.md")
+    );
+}
+
+#[test]
+fn sync_requires_newer_aps_fails_before_parsing_entries() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"requires_aps: ">=99.0"
+entries:
+  - id: future
+    kind: some_future_kind
+    source:
+      type: filesystem
+      root: .
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .arg("sync")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("requires aps >=99.0"))
+        .stderr(predicate::str::contains("upgrade aps"));
+
+    temp.child("aps.lock.yaml")
+        .assert(predicate::path::missing());
+}
+
+#[test]
+fn sync_unsupported_source_type_fails_only_that_entry() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let source_dir = temp.child("source");
+    source_dir
+        .child("AGENTS.md")
+        .write_str("# Test Agents\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: from-s3
+    kind: cursor_rules
+    source:
+      type: s3
+      bucket: team-prompts
+  - id: test-agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: {}
+      path: AGENTS.md
+      symlink: false
+    dest: ./AGENTS.md
+"#,
+        source_dir.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps()
+        .arg("sync")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("Unsupported source type 's3'"))
+        .stderr(predicate::str::contains("1 entry(ies) failed to sync"));
+
+    temp.child("AGENTS.md")
+        .assert(predicate::str::contains("# Test Agents"));
+    temp.child("aps.lock.yaml")
+        .assert(predicate::str::contains("test-agents"));
+}
+
+#[test]
+fn sync_prompt_library_generates_index() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let library = temp.child("library");
+    library
+        .child("review.md")
+        .write_str("---\ntitle: Code Review\ndescription: Review a diff\n---\nReview this.\n")
+        .unwrap();
+    library
+        .child("writing/commit.md")
+        .write_str("# Commit Message\n\nSummarize the change.\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: team-prompts
+    kind: prompt_library
+    source:
+      type: filesystem
+      root: {}
+      symlink: false
+"#,
+        library.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    temp.child("prompts/writing/commit.md")
+        .assert(predicate::path::exists());
+    temp.child("prompts/INDEX.md")
+        .assert(predicate::str::contains(
+            "- [Code Review](review.md) — Review a diff",
+        ))
+        .assert(predicate::str::contains(
+            "- [Commit Message](writing/commit.md)",
+        ));
+}
+
+#[test]
+fn sync_targets_fan_out_to_each_tool() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let source_dir = temp.child("source");
+    source_dir
+        .child("INSTRUCTIONS.md")
+        .write_str("# Shared Instructions\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: instructions
+    kind: agents_md
+    source:
+      type: filesystem
+      root: {}
+      path: INSTRUCTIONS.md
+      symlink: false
+    targets: [agents_md, claude_md, copilot_instructions]
+"#,
+        source_dir.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    for dest in ["AGENTS.md", "CLAUDE.md", ".github/copilot-instructions.md"] {
+        temp.child(dest)
+            .assert(predicate::str::contains("# Shared Instructions"));
+    }
+    temp.child("aps.lock.yaml")
+        .assert(predicate::str::contains("instructions:agents_md"))
+        .assert(predicate::str::contains("instructions:claude_md"))
+        .assert(predicate::str::contains(
+            "instructions:copilot_instructions",
+        ));
+}
+
+#[test]
+fn sync_dest_list_installs_to_each_destination() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/rules/style.mdc")
+        .write_str("# Style\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: source
+      path: rules
+      symlink: false
+    dest: [.cursor/rules, .windsurf/rules]
+"#,
+        )
+        .unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    for dest in [".cursor/rules", ".windsurf/rules"] {
+        temp.child(format!("{}/style.mdc", dest))
+            .assert("# Style\n");
+    }
+    temp.child("aps.lock.yaml")
+        .assert(predicate::str::contains("rules:.cursor/rules"))
+        .assert(predicate::str::contains("rules:.windsurf/rules"));
+
+    // `--only` selects the entry and syncs every destination
+    std::fs::remove_dir_all(temp.child(".windsurf").path()).unwrap();
+    aps()
+        .args(["sync", "--only", "rules"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child(".windsurf/rules/style.mdc").assert("# Style\n");
+}
+
+#[test]
+fn sync_convert_cursor_rules_to_instruction_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let rules = temp.child("source/rules");
+    rules
+        .child("python.mdc")
+        .write_str("---\ndescription: Python style\nglobs: \"**/*.py\"\n---\n\nUse type hints.\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: rules
+    kind: agents_md
+    source:
+      type: filesystem
+      root: {}
+      path: rules
+    convert:
+      from: cursor_rules
+    targets: [agents_md, claude_md]
+"#,
+        temp.child("source").path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    for dest in ["AGENTS.md", "CLAUDE.md"] {
+        temp.child(dest)
+            .assert(predicate::str::contains("## Python style"))
+            .assert(predicate::str::contains("_Applies to `**/*.py`._"))
+            .assert(predicate::str::contains("description:").not());
+    }
+}
+
+#[test]
+fn sync_applies_transforms_to_a_staged_copy() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let source = temp.child("source/AGENTS.md");
+    source
+        .write_str("---\nowner: acme\n---\n# Acme Guidelines\n\nAsk in #acme-dev.\n")
+        .unwrap();
+    temp.child("header.md")
+        .write_str("<!-- Adapted for Contoso -->\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: guidelines
+    kind: agents_md
+    source:
+      type: filesystem
+      root: {}
+      path: AGENTS.md
+    transforms:
+      - strip_frontmatter
+      - replace: {{ from: acme, to: contoso }}
+      - replace: {{ from: Acme, to: Contoso }}
+      - prepend_file: header.md
+"#,
+        temp.child("source").path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    temp.child("AGENTS.md")
+        .assert(predicate::str::contains(
+            "<!-- Adapted for Contoso -->\n# Contoso Guidelines",
+        ))
+        .assert(predicate::str::contains("#contoso-dev"))
+        .assert(predicate::str::contains("owner:").not());
+    // The upstream file is untouched and not symlinked
+    source.assert(predicate::str::contains("# Acme Guidelines"));
+    assert!(!temp
+        .child("AGENTS.md")
+        .path()
+        .symlink_metadata()
+        .unwrap()
+        .file_type()
+        .is_symlink());
+}
+
+#[test]
+fn sync_cursor_rules_converts_markdown_to_mdc() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let rules = temp.child("rules");
+    rules
+        .child("python-style.md")
+        .write_str("# Python Style\n\nUse type hints.\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: {}
+    mdc:
+      globs: ["**/*.py"]
+      always_apply: false
+"#,
+        rules.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    temp.child(".cursor/rules/python-style.md")
+        .assert(predicate::path::missing());
+    temp.child(".cursor/rules/python-style.mdc")
+        .assert(predicate::str::starts_with(
+            "---\ndescription: Python Style\nglobs: **/*.py\nalwaysApply: false\n---\n",
+        ))
+        .assert(predicate::str::contains("Use type hints."));
+}
+
+#[test]
+fn sync_legacy_cursorrules_concatenates_rules() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let rules = temp.child("rules");
+    rules
+        .child("a-style.mdc")
+        .write_str("---\ndescription: Style\n---\nUse type hints.\n")
+        .unwrap();
+    rules
+        .child("b-testing.mdc")
+        .write_str("Write tests first.\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: {}
+    legacy_cursorrules: true
+"#,
+        rules.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    temp.child(".cursorrules")
+        .assert(predicate::str::contains(
+            "Use type hints.\n\nWrite tests first.\n",
+        ))
+        .assert(predicate::str::contains("description: Style").not());
+    temp.child("aps.lock.yaml").assert(predicate::str::contains(
+        "legacy_cursorrules_checksum: sha256:",
+    ));
+
+    // A deleted legacy file is regenerated even though the rules are unchanged
+    std::fs::remove_file(temp.child(".cursorrules").path()).unwrap();
+    aps().arg("sync").current_dir(&temp).assert().success();
+    temp.child(".cursorrules")
+        .assert(predicate::str::contains("Write tests first."));
+}
+
+#[test]
+fn sync_skills_installs_only_selected_skills() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let skills = temp.child("skills");
+    for name in ["docker", "docker-compose", "terraform", "kubernetes"] {
+        skills
+            .child(format!("{}/SKILL.md", name))
+            .write_str(&format!("# {}\n", name))
+            .unwrap();
+    }
+
+    let manifest = format!(
+        r#"entries:
+  - id: skills
+    kind: agent_skill
+    source:
+      type: filesystem
+      root: {}
+      symlink: false
+    skills: [docker, terraform]
+"#,
+        skills.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    temp.child(".claude/skills/docker/SKILL.md")
+        .assert(predicate::path::exists());
+    temp.child(".claude/skills/terraform/SKILL.md")
+        .assert(predicate::path::exists());
+    temp.child(".claude/skills/docker-compose")
+        .assert(predicate::path::missing());
+    temp.child(".claude/skills/kubernetes")
+        .assert(predicate::path::missing());
+}
+
+#[test]
+fn sync_skills_fails_when_selected_skill_missing() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let skills = temp.child("skills");
+    skills
+        .child("docker/SKILL.md")
+        .write_str("# docker\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: skills
+    kind: cursor_skills_root
+    source:
+      type: filesystem
+      root: {}
+    skills: [docker, terraform]
+"#,
+        skills.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps()
+        .arg("sync")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("terraform"));
+    temp.child(".cursor/skills/docker")
+        .assert(predicate::path::missing());
+}
+
+#[test]
+fn sync_composite_renders_vars_into_fragments() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let partials = temp.child("partials");
+    partials
+        .child("testing.md")
+        .write_str("## Testing for {{project}}\n\nRun `{{test_command}}` before pushing.\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: agents
+    kind: composite_agents_md
+    vars:
+      project: widgets
+      test_command: cargo test
+    sources:
+      - type: filesystem
+        root: {}
+        path: testing.md
+"#,
+        partials.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    temp.child("AGENTS.md")
+        .assert(predicate::str::contains("## Testing for widgets"))
+        .assert(predicate::str::contains("Run `cargo test` before pushing."));
+
+    // Changing a var re-renders even though the fragment is unchanged
+    let manifest = manifest.replace("cargo test", "cargo nextest run");
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("AGENTS.md").assert(predicate::str::contains(
+        "Run `cargo nextest run` before pushing.",
+    ));
+}
+
+#[test]
+fn sync_agents_md_preserves_content_outside_managed_region() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let shared = temp.child("shared");
+    shared
+        .child("AGENTS.md")
+        .write_str("# Shared v1\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: agents
+    kind: agents_md
+    managed_region: true
+    source:
+      type: filesystem
+      root: {}
+      symlink: false
+      path: AGENTS.md
+"#,
+        shared.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    temp.child("AGENTS.md")
+        .assert("<!-- aps:begin -->\n# Shared v1\n<!-- aps:end -->\n");
+
+    // Local edits outside the markers survive an upstream change, without a prompt
+    let edited = std::fs::read_to_string(temp.child("AGENTS.md").path()).unwrap();
+    temp.child("AGENTS.md")
+        .write_str(&format!("# Local notes\n\n{}\n## Repo quirks\n", edited))
+        .unwrap();
+    shared
+        .child("AGENTS.md")
+        .write_str("# Shared v2\n")
+        .unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    temp.child("AGENTS.md").assert(
+        "# Local notes\n\n<!-- aps:begin -->\n# Shared v2\n<!-- aps:end -->\n\n## Repo quirks\n",
+    );
+}
+
+#[test]
+fn sync_agents_md_writes_markers_only_when_opted_in() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("shared/AGENTS.md")
+        .write_str("# Shared v1\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source: { type: filesystem, root: shared, symlink: false, path: AGENTS.md }
+"#,
+        )
+        .unwrap();
+
+    // Without managed_region the file is copied as is
+    aps().arg("sync").current_dir(&temp).assert().success();
+    temp.child("AGENTS.md").assert("# Shared v1\n");
+
+    // A region the user added by hand is still the only part rewritten
+    temp.child("AGENTS.md")
+        .write_str("# Local notes\n\n<!-- aps:begin -->\n# Shared v1\n<!-- aps:end -->\n")
+        .unwrap();
+    temp.child("shared/AGENTS.md")
+        .write_str("# Shared v2\n")
+        .unwrap();
+    aps().arg("sync").current_dir(&temp).assert().success();
+    temp.child("AGENTS.md")
+        .assert("# Local notes\n\n<!-- aps:begin -->\n# Shared v2\n<!-- aps:end -->\n");
+}
+
+#[test]
+fn sync_merge_combines_local_and_upstream_edits_to_managed_region() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let shared = temp.child("shared");
+    shared
+        .child("AGENTS.md")
+        .write_str("# Shared\n\nOne.\n\nTwo.\n\nThree.\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: agents
+    kind: agents_md
+    managed_region: true
+    source:
+      type: filesystem
+      root: {}
+      symlink: false
+      path: AGENTS.md
+"#,
+        shared.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    // Edit inside the managed region, then change upstream elsewhere
+    let installed = std::fs::read_to_string(temp.child("AGENTS.md").path()).unwrap();
+    temp.child("AGENTS.md")
+        .write_str(&installed.replace("One.", "One, with a local note."))
+        .unwrap();
+    shared
+        .child("AGENTS.md")
+        .write_str("# Shared\n\nOne.\n\nTwo.\n\nThree.\n\nFour.\n")
+        .unwrap();
+
+    // Local edits are a conflict without --merge
+    aps()
+        .arg("sync")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--yes"));
+
+    aps()
+        .args(["sync", "--merge"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Merged local edits"));
+    temp.child("AGENTS.md").assert(
+        "<!-- aps:begin -->\n# Shared\n\nOne, with a local note.\n\nTwo.\n\nThree.\n\nFour.\n<!-- aps:end -->\n",
+    );
+
+    // Overlapping edits are written with conflict markers
+    let merged = std::fs::read_to_string(temp.child("AGENTS.md").path()).unwrap();
+    temp.child("AGENTS.md")
+        .write_str(&merged.replace("Two.", "Two, locally."))
+        .unwrap();
+    shared
+        .child("AGENTS.md")
+        .write_str("# Shared\n\nOne.\n\nTwo, upstream.\n\nThree.\n\nFour.\n")
+        .unwrap();
+
+    aps()
+        .args(["sync", "--merge"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 conflicting edit(s)"));
+    temp.child("AGENTS.md").assert(predicate::str::contains(
+        "<<<<<<< local\nTwo, locally.\n=======\nTwo, upstream.\n>>>>>>> upstream\n",
+    ));
+}
+
+#[test]
+fn sync_force_rewrites_up_to_date_entries() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let shared = temp.child("shared");
+    shared.child("AGENTS.md").write_str("# Shared\n").unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: {}
+      symlink: false
+      path: AGENTS.md
+"#,
+        shared.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    temp.child("AGENTS.md").write_str("mangled\n").unwrap();
+
+    // The lockfile still says the entry is current
+    aps().arg("sync").current_dir(&temp).assert().success();
+    temp.child("AGENTS.md").assert("mangled\n");
+
+    aps()
+        .args(["sync", "--force", "--only", "agents", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("AGENTS.md").assert("# Shared\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn sync_prune_removes_links_to_files_dropped_upstream() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let rules = temp.child("source/rules");
+    rules.child("keep.mdc").write_str("keep").unwrap();
+    rules.child("gone.mdc").write_str("gone").unwrap();
+
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: source
+      path: rules
+    dest: ./.cursor/rules
+"#,
+        )
+        .unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    let gone = temp.child(".cursor/rules/gone.mdc");
+    assert!(gone.path().symlink_metadata().unwrap().is_symlink());
+
+    // Without --prune the link to the removed file is left dangling
+    std::fs::remove_file(rules.child("gone.mdc").path()).unwrap();
+    aps().arg("sync").current_dir(&temp).assert().success();
+    assert!(gone.path().symlink_metadata().is_ok());
+
+    aps()
+        .args(["sync", "--prune"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 1 stale link(s)"));
+    assert!(gone.path().symlink_metadata().is_err());
+    temp.child(".cursor/rules/keep.mdc").assert("keep");
+}
+
+#[cfg(unix)]
+#[test]
+fn sync_runs_post_install_only_with_allow_hooks() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("shared/AGENTS.md")
+        .write_str("# Shared\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: shared
+      symlink: false
+      path: AGENTS.md
+    post_install: ["echo ran >> hook.log"]
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--allow-hooks"));
+    temp.child("hook.log").assert(predicate::path::missing());
+
+    // Up-to-date entries don't rerun their commands
+    temp.child("shared/AGENTS.md")
+        .write_str("# Shared v2\n")
+        .unwrap();
+    aps()
+        .args(["sync", "--yes", "--allow-hooks"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    aps()
+        .args(["sync", "--yes", "--allow-hooks"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("hook.log").assert("ran\n");
+}
+
+#[test]
+fn sync_map_renames_files_before_include() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/kit/README.md")
+        .write_str("# Agent notes\n")
+        .unwrap();
+    temp.child("source/kit/docs/setup.md")
+        .write_str("# Setup\n")
+        .unwrap();
+    temp.child("source/kit/CHANGELOG.md")
+        .write_str("# Changes\n")
+        .unwrap();
+
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: kit
+    kind: dir
+    source:
+      type: filesystem
+      root: source
+      path: kit
+    dest: ./kit
+    map:
+      - { from: README.md, to: AGENTS.md }
+      - { from: docs, to: guides }
+    include:
+      - AGENTS.md
+      - guides
+"#,
+        )
+        .unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    temp.child("kit/AGENTS.md").assert("# Agent notes\n");
+    temp.child("kit/guides/setup.md").assert("# Setup\n");
+    temp.child("kit/README.md")
+        .assert(predicate::path::missing());
+    temp.child("kit/CHANGELOG.md")
+        .assert(predicate::path::missing());
+
+    // Mapping two paths to one name is rejected
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: kit
+    kind: dir
+    source:
+      type: filesystem
+      root: source
+      path: kit
+    dest: ./kit
+    map:
+      - { from: README.md, to: AGENTS.md }
+      - { from: CHANGELOG.md, to: AGENTS.md }
+"#,
+        )
+        .unwrap();
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "more than one mapping to 'AGENTS.md'",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn sync_readonly_marks_files_and_restores_them_around_syncs() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/rules/style.md")
+        .write_str("# Style\n")
+        .unwrap();
+    let manifest = |readonly: bool| {
+        format!(
+            r#"entries:
+  - id: rules
+    kind: dir
+    source:
+      type: filesystem
+      root: source
+      path: rules
+    dest: ./rules
+    readonly: {}
+"#,
+            readonly
+        )
+    };
+    let writable = |path: &str| {
+        std::fs::metadata(temp.child(path).path())
+            .unwrap()
+            .permissions()
+            .mode()
+            & 0o222
+            != 0
+    };
+    temp.child("aps.yaml").write_str(&manifest(true)).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    assert!(!writable("rules/style.md"));
+    temp.child("aps.lock.yaml")
+        .assert(predicate::str::contains("readonly: true"));
+
+    // Upstream changes still install over the read-only copy
+    temp.child("source/rules/style.md")
+        .write_str("# Style v2\n")
+        .unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("rules/style.md").assert("# Style v2\n");
+    assert!(!writable("rules/style.md"));
+
+    // Turning it off makes the files writable again
+    temp.child("aps.yaml").write_str(&manifest(false)).unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    assert!(writable("rules/style.md"));
+}
+
+#[test]
+fn sync_timings_lists_each_entry() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Agents\n")
+        .unwrap();
+    temp.child("source/rules/style.md")
+        .write_str("# Style\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+  - id: rules
+    kind: dir
+    source:
+      type: filesystem
+      root: source
+      path: rules
+    dest: ./rules
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["sync", "--timings"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Timings"))
+        .stdout(predicate::str::is_match(r"\d+\.\d{2}s  agents").unwrap())
+        .stdout(predicate::str::is_match(r"\d+\.\d{2}s  rules").unwrap())
+        .stdout(predicate::str::contains("total"));
+
+    aps()
+        .arg("sync")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Timings").not());
+}
+
+#[test]
+fn plan_and_apply_install_exactly_the_reviewed_changes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/rules/style.md")
+        .write_str("# Style\n")
+        .unwrap();
+    let manifest = r#"entries:
+  - id: rules
+    kind: dir
+    source:
+      type: filesystem
+      root: source
+      path: rules
+      symlink: false
+    dest: ./rules
+"#;
+    temp.child("aps.yaml").write_str(manifest).unwrap();
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    temp.child("source/rules/style.md")
+        .write_str("# Style v2\n")
+        .unwrap();
+    aps()
+        .args(["plan", "--out", "plan.json"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("rules → ./rules (install)"))
+        .stdout(predicate::str::contains("+# Style v2"))
+        .stdout(predicate::str::contains("1 to install, 0 unchanged"));
+    // Planning writes nothing
+    temp.child("rules/style.md").assert("# Style\n");
+    temp.child("plan.json")
+        .assert(predicate::str::contains("\"action\": \"install\""));
+
+    aps()
+        .args(["apply", "plan.json"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("rules/style.md").assert("# Style v2\n");
+
+    // The lockfile changed, so the plan no longer applies
+    aps()
+        .args(["apply", "plan.json"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("lockfile changed"));
+
+    // Content that changed after planning isn't installed
+    temp.child("source/rules/style.md")
+        .write_str("# Style v3\n")
+        .unwrap();
+    aps()
+        .args(["plan", "--out", "plan.json"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("source/rules/style.md")
+        .write_str("# Style v4\n")
+        .unwrap();
+    aps()
+        .args(["apply", "plan.json"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Plan is out of date"));
+    temp.child("rules/style.md").assert("# Style v2\n");
+}
+
+#[test]
+fn dest_outside_the_project_is_rejected() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let project = temp.child("project");
+    project
+        .child("source/AGENTS.md")
+        .write_str("# Agents\n")
+        .unwrap();
+    project
+        .child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    dest: ../AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .arg("validate")
+        .current_dir(&project)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is outside the project"));
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&project)
+        .assert()
+        .failure();
+    temp.child("AGENTS.md").assert(predicate::path::missing());
+}
+
+#[test]
+fn sync_manages_gitignore_per_entry() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Agents\n")
+        .unwrap();
+    temp.child("source/rules/a.md").write_str("# A\n").unwrap();
+    temp.child(".gitignore")
+        .write_str("target/\n.cursor/rules/\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    dest: ./AGENTS.md
+    gitignore: add
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: source
+      path: rules
+    dest: ./.cursor/rules/
+    gitignore: remove
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added /AGENTS.md to .gitignore"))
+        .stdout(predicate::str::contains(
+            "Removed .cursor/rules/ from .gitignore",
+        ));
+    let expected = "target/\n\n# APS (Agentic Prompt Sync)\n/AGENTS.md\n";
+    temp.child(".gitignore").assert(expected);
+
+    // Running again leaves .gitignore as it is
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".gitignore").not());
+    temp.child(".gitignore").assert(expected);
+}
+
+#[test]
+fn adopt_keeps_existing_files_until_the_source_changes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Upstream\n")
+        .unwrap();
+    temp.child("AGENTS.md")
+        .write_str("# Hand-maintained\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+      symlink: false
+    dest: ./AGENTS.md
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: source
+      path: rules
+    dest: ./.cursor/rules/
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["adopt", "rules"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing to adopt"));
+
+    aps()
+        .args(["adopt", "agents"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Adopted agents"))
+        .stdout(predicate::str::contains("differs from the source"));
+    temp.child("AGENTS.md").assert("# Hand-maintained\n");
+    temp.child("aps.lock.yaml")
+        .assert(predicate::str::contains("agents:"));
+
+    // Nothing changed upstream, so sync leaves the adopted file alone
+    aps()
+        .args(["sync", "--yes", "--only", "agents"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("AGENTS.md").assert("# Hand-maintained\n");
+
+    temp.child("source/AGENTS.md")
+        .write_str("# Upstream v2\n")
+        .unwrap();
+    aps()
+        .args(["sync", "--yes", "--only", "agents"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("AGENTS.md")
+        .assert(predicate::str::contains("# Upstream v2"));
+}
+
+#[test]
+fn sync_interactive_requires_a_terminal() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Agents\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["sync", "--interactive"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--interactive needs a terminal"));
+    aps()
+        .args(["sync", "--interactive", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    temp.child("AGENTS.md").assert(predicate::path::missing());
+}
+
+#[test]
+fn sync_prompt_flags_answer_overwrite_prompts() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Upstream\n")
+        .unwrap();
+    temp.child("AGENTS.md").write_str("# Local\n").unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    // Without a terminal nobody can answer, with --fail-on-prompt nobody may
+    for flags in [&["sync"][..], &["sync", "--fail-on-prompt"]] {
+        aps()
+            .args(flags)
+            .current_dir(&temp)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("No answer for prompt"))
+            .stderr(predicate::str::contains("Overwrite existing content"));
+    }
+    aps()
+        .args(["sync", "--no"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cancelled"));
+    temp.child("AGENTS.md").assert("# Local\n");
+
+    aps()
+        .args(["sync", "--no", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("AGENTS.md")
+        .assert(predicate::str::contains("# Upstream"));
+}
+
+#[test]
+fn backup_list_shows_the_entry_behind_each_backup() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Upstream\n")
+        .unwrap();
+    temp.child("AGENTS.md").write_str("# Local\n").unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["backup", "list"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No backups found"));
+
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+
+    aps()
+        .args(["backup", "list"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("8 B"))
+        .stdout(predicate::str::contains("agents"))
+        .stdout(predicate::str::contains("AGENTS.md-"));
+    aps()
+        .args(["backup", "list", "./AGENTS.md", "--format", "json"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"entry\": \"agents\""))
+        .stdout(predicate::str::contains("\"dest\": \"AGENTS.md\""));
+    aps()
+        .args(["backup", "list", ".cursor"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No backups found"));
+}
+
+#[test]
+fn status_shows_the_last_backup_of_an_entry() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Upstream\n")
+        .unwrap();
+    temp.child("AGENTS.md").write_str("# Local\n").unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+      symlink: false
+    dest: ./AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    temp.child("aps.lock.yaml")
+        .assert(predicate::str::contains("reason: conflict"));
+
+    // A later sync that backs nothing up keeps the record
+    temp.child("source/AGENTS.md")
+        .write_str("# Upstream v2\n")
+        .unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+
+    aps()
+        .arg("status")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Last backup:  .aps-backups/AGENTS.md-",
+        ))
+        .stdout(predicate::str::contains("(overwrote existing content)"));
+}
+
+#[test]
+fn sync_backs_up_to_git_refs() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    git(temp.path())
+        .args(["init", "--initial-branch=main"])
+        .output()
+        .expect("Failed to init git repo");
+    temp.child("source/AGENTS.md")
+        .write_str("# Upstream\n")
+        .unwrap();
+    temp.child("AGENTS.md").write_str("# Local\n").unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"backup: git
+entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("refs/aps/backups/AGENTS.md-"));
+    temp.child(".aps-backups")
+        .assert(predicate::path::missing());
+
+    let refs = git(temp.path())
+        .args(["for-each-ref", "--format=%(refname)", "refs/aps/backups/"])
+        .output()
+        .unwrap();
+    let reference = String::from_utf8(refs.stdout).unwrap().trim().to_string();
+    let backed_up = git(temp.path())
+        .args(["show", &format!("{}:AGENTS.md", reference)])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(backed_up.stdout).unwrap(), "# Local\n");
+
+    aps()
+        .args(["backup", "list"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("agents"))
+        .stdout(predicate::str::contains(reference.as_str()));
+}
+
+#[test]
+fn status_format_json_reports_drift() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Upstream\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+      symlink: false
+    dest: ./AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    let status = |format: &str| {
+        aps()
+            .args(["status", "--format", format])
+            .current_dir(&temp)
+            .output()
+            .unwrap()
+    };
+
+    let report: serde_json::Value = serde_json::from_slice(&status("json").stdout).unwrap();
+    let entry = &report["entries"][0];
+    assert_eq!(entry["id"], "agents");
+    assert_eq!(entry["dest"], "./AGENTS.md");
+    assert_eq!(entry["drift"], "clean");
+    assert!(entry["checksum"].as_str().unwrap().starts_with("sha256:"));
+    assert!(entry["installed_at"].is_string());
+
+    temp.child("AGENTS.md").write_str("# Edited\n").unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&status("json").stdout).unwrap();
+    assert_eq!(report["entries"][0]["drift"], "modified");
+    assert_eq!(
+        report["entries"][0]["drifted_files"][0]["change"],
+        "modified"
+    );
+
+    let yaml = String::from_utf8(status("yaml").stdout).unwrap();
+    assert!(yaml.contains("drift: modified"));
+}
+
+#[test]
+fn why_names_the_entry_that_installed_a_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/rules/python.mdc")
+        .write_str("# Python\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: source
+      path: rules
+      symlink: false
+"#,
+        )
+        .unwrap();
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    // Paths resolve from the working directory
+    aps()
+        .args(["why", "python.mdc", "--manifest", "../../aps.yaml"])
+        .current_dir(temp.child(".cursor/rules").path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            ".cursor/rules/python.mdc is installed by rules",
+        ))
+        .stdout(predicate::str::contains("Kind:         cursor_rules"))
+        .stdout(predicate::str::contains("Checksum:     sha256:"));
+
+    temp.child(".cursor/rules/mine.mdc")
+        .write_str("# Mine\n")
+        .unwrap();
+    aps()
+        .args(["why", ".cursor/rules/mine.mdc"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "was not installed by aps, but is inside the destination of rules",
+        ));
+
+    aps()
+        .args(["why", "README.md"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("README.md is not managed by aps"));
+}
+
+#[test]
+fn explain_shows_resolution_without_installing() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/rules/python.md")
+        .write_str("# Python\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: source
+      path: rules
+    include:
+      - python
+    readonly: true
+  - id: agents
+    kind: agents_md
+    source:
+      type: git
+      repo: https://example.com/prompts.git
+      ref: auto
+      path: AGENTS.md
+    dest: ./docs/AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["explain", "rules"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Source type:  filesystem"))
+        .stdout(predicate::str::contains(
+            "Destination:  .cursor/rules (default for cursor_rules)",
+        ))
+        .stdout(predicate::str::contains("Include:      files under python"))
+        .stdout(predicate::str::contains(
+            "Install:      copy, because `readonly` marks the copies read-only",
+        ));
+
+    aps()
+        .args(["explain", "agents"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Ref:          auto (main, then master)",
+        ))
+        .stdout(predicate::str::contains(
+            "Destination:  ./docs/AGENTS.md (from `dest`)",
+        ))
+        .stdout(predicate::str::contains("not locked yet"));
+
+    temp.child(".cursor").assert(predicate::path::missing());
+    temp.child("aps.lock.yaml")
+        .assert(predicate::path::missing());
+
+    aps()
+        .args(["explain", "missing"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Entry not found: missing"));
+}
+
+#[test]
+fn tree_groups_managed_files_by_entry() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/rules/python.md")
+        .write_str("# Python\n")
+        .unwrap();
+    temp.child("source/rules/go/style.md")
+        .write_str("# Go\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: source
+      path: rules
+      symlink: false
+  - id: agents
+    kind: composite_agents_md
+    sources:
+      - type: filesystem
+        root: source
+        path: rules/python.md
+"#,
+        )
+        .unwrap();
+    aps().arg("sync").current_dir(&temp).assert().success();
+    temp.child(".cursor/rules/python.md")
+        .write_str("# Edited\n")
+        .unwrap();
+
+    aps()
+        .arg("tree")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("agents AGENTS.md [composite]"))
+        .stdout(predicate::str::contains(
+            "rules .cursor/rules\n├── go/\n│   └── style.md\n└── python.md  [modified]\n",
+        ))
+        .stdout(predicate::str::contains(
+            "2 entries, 3 files, 1 differ from the lockfile",
+        ));
+}
+
+#[test]
+fn sync_prints_results_table_and_json() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Upstream\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+      symlink: false
+    dest: ./AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .arg("sync")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Entry"))
+        .stdout(predicate::str::contains("Version"))
+        .stdout(predicate::str::contains("Time"))
+        .stdout(predicate::str::contains("copied"));
+
+    let sync_json = || {
+        let output = aps()
+            .args(["sync", "--format", "json", "--yes"])
+            .current_dir(&temp)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    let report = sync_json();
+    let entry = &report["entries"][0];
+    assert_eq!(entry["id"], "agents");
+    assert_eq!(entry["dest"], "./AGENTS.md");
+    assert_eq!(entry["action"], "current");
+    let version = entry["version"].as_str().unwrap().to_string();
+    assert!(version.starts_with("sha256:"));
+    assert_eq!(entry["previous_version"], version.as_str());
+    assert!(entry["duration_ms"].is_u64());
+
+    temp.child("source/AGENTS.md")
+        .write_str("# Changed upstream\n")
+        .unwrap();
+    let report = sync_json();
+    let entry = &report["entries"][0];
+    assert_eq!(entry["action"], "copied");
+    assert_eq!(entry["previous_version"], version.as_str());
+    assert_ne!(entry["version"], version.as_str());
+}
+
+#[test]
+fn log_file_receives_json_logs() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Upstream\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    // Debug logs go to the file only; the console keeps its usual output
+    aps()
+        .args(["sync", "--log-file", "aps.log", "--log-format", "json"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Syncing from"))
+        .stdout(predicate::str::contains("\"level\"").not())
+        .stderr(predicate::str::contains("\"level\"").not());
+
+    let log = std::fs::read_to_string(temp.child("aps.log").path()).unwrap();
+    let records: Vec<serde_json::Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(records.iter().any(|r| r["level"] == "DEBUG"));
+    assert!(records.iter().all(|r| r["timestamp"].is_string()));
+    assert!(records
+        .iter()
+        .any(|r| r["message"].as_str().unwrap().contains("Found manifest")));
+}
+
+#[test]
+fn validate_writes_junit_and_sarif_reports() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Upstream\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    dest: ./AGENTS.md
+  - id: missing
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: MISSING.md
+    dest: ./docs/AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .args(["validate", "--report", "junit.xml"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+    let junit = std::fs::read_to_string(temp.child("junit.xml").path()).unwrap();
+    assert!(junit.contains("tests=\"2\" failures=\"0\""));
+    assert!(junit.contains("name=\"agents\""));
+    assert!(junit.contains("Warning: Source path not found"));
+
+    // Under --strict the report is still written, with the failure in it
+    aps()
+        .args(["validate", "--strict", "--report", "aps.sarif"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("[FAILED] missing"));
+    let sarif: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(temp.child("aps.sarif").path()).unwrap())
+            .unwrap();
+    let result = &sarif["runs"][0]["results"][0];
+    assert_eq!(result["level"], "error");
+    assert_eq!(
+        result["locations"][0]["physicalLocation"]["region"]["startLine"],
+        9
+    );
+}
+
+#[test]
+fn validate_reports_parallel_results_in_manifest_order() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let mut manifest = String::from("entries:\n");
+    let ids: Vec<String> = (0..6).map(|i| format!("entry-{}", i)).collect();
+    for id in &ids {
+        temp.child(format!("source/{}.md", id))
+            .write_str("# Rules\n")
+            .unwrap();
+        manifest.push_str(&format!(
+            "  - id: {id}\n    kind: agents_md\n    source:\n      type: filesystem\n      root: source\n      path: {id}.md\n    dest: ./{id}/AGENTS.md\n"
+        ));
+    }
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    let output = aps()
+        .args(["validate", "--jobs", "3"])
+        .current_dir(&temp)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let positions: Vec<usize> = ids
+        .iter()
+        .map(|id| stdout.find(&format!("[OK] {} ", id)).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn validate_checks_skill_frontmatter() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("skills/docker/SKILL.md")
+        .write_str(
+            "---\nname: docker\ndescription: Build images.\n---\nSee [the guide](guide.md).\n",
+        )
+        .unwrap();
+    temp.child("skills/docker/guide.md")
+        .write_str("# Guide\n")
+        .unwrap();
+    temp.child("skills/terraform/SKILL.md")
+        .write_str("---\nname: Terraform\n---\nSee [modules](references/modules.md).\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: skills
+    kind: cursor_skills_root
+    source:
+      type: filesystem
+      root: skills
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Skill 'terraform': SKILL.md frontmatter is missing `description`",
+        ))
+        .stdout(predicate::str::contains(
+            "Skill 'terraform': references missing file 'references/modules.md'",
+        ))
+        .stdout(predicate::str::contains("Skill 'docker'").not());
+
+    aps()
+        .args(["validate", "--strict"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Skill 'terraform' has an invalid SKILL.md",
+        ));
+}
+
+#[test]
+fn validate_checks_cursor_rule_frontmatter() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("rules/python.mdc")
+        .write_str("---\ndescription: Python style\nglobs: **/*.py\nalwaysApply: false\n---\nUse type hints.\n")
+        .unwrap();
+    temp.child("rules/go.mdc")
+        .write_str(
+            "---\ndescription: Go style\nglobs: **/*.{go\nalways_apply: true\n---\nRun gofmt.\n",
+        )
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: rules
+    kind: cursor_rules
+    source:
+      type: filesystem
+      root: rules
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Rule 'go.mdc': unknown frontmatter key `always_apply`",
+        ))
+        .stdout(predicate::str::contains(
+            "Rule 'go.mdc': invalid glob '**/*.{go'",
+        ))
+        .stdout(predicate::str::contains("Rule 'python.mdc'").not());
+
+    aps()
+        .args(["validate", "--strict"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid Cursor rule"));
+}
+
+#[test]
+fn validate_check_links_reports_links_left_behind() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Agents\n\nFollow [the setup guide](docs/setup.md).\n")
+        .unwrap();
+    temp.child("source/docs/setup.md")
+        .write_str("# Setup\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("docs/setup.md").not());
+
+    aps()
+        .args(["validate", "--check-links"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "AGENTS.md links to 'docs/setup.md', which won't exist where it is installed",
+        ));
+
+    // Once the project has the file, the link resolves
+    temp.child("docs/setup.md").write_str("# Setup\n").unwrap();
+    aps()
+        .args(["validate", "--check-links", "--strict"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+}
+
+#[test]
+fn schema_prints_and_validate_checks_against_it() {
+    let output = aps().args(["schema", "manifest"]).output().unwrap();
+    assert!(output.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["title"], "aps manifest (aps.yaml)");
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("source/AGENTS.md")
+        .write_str("# Rules\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: source
+      path: AGENTS.md
+    destination: ./docs/AGENTS.md
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "aps.yaml: entries[0]: Additional properties are not allowed ('destination' was unexpected)",
+        ));
+    aps()
+        .args(["validate", "--strict"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Files don't match their schema"));
+}
+
+#[test]
+fn validate_only_and_changed_select_entries() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let mut manifest = String::from("entries:\n");
+    for id in ["first", "second"] {
+        temp.child(format!("source/{}.md", id))
+            .write_str("# Rules\n")
+            .unwrap();
+        manifest.push_str(&format!(
+            "  - id: {id}\n    kind: agents_md\n    source:\n      type: filesystem\n      root: source\n      path: {id}.md\n    dest: ./{id}/AGENTS.md\n"
+        ));
+    }
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps()
+        .args(["validate", "--only", "second"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[OK] second "))
+        .stdout(predicate::str::contains("[OK] first ").not());
+    aps()
+        .args(["validate", "--only", "missing"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Entry not found: missing"));
+
+    // Without a lockfile, every entry counts as changed
+    aps()
+        .args(["validate", "--changed"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[OK] first "))
+        .stdout(predicate::str::contains("[OK] second "));
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+    aps()
+        .args(["validate", "--changed"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "No entries changed since the last sync.",
+        ));
+
+    temp.child("aps.yaml")
+        .write_str(&manifest.replace("./first/AGENTS.md", "./moved/AGENTS.md"))
+        .unwrap();
+    aps()
+        .args(["validate", "--changed"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[OK] first "))
+        .stdout(predicate::str::contains("[OK] second ").not());
+}
+
+#[test]
+fn sync_composite_orders_and_titles_fragments() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let partials = temp.child("partials");
+    partials
+        .child("python.md")
+        .write_str("Use type hints.\n\n# Linting\n\nRun ruff.\n")
+        .unwrap();
+    partials
+        .child("intro.md")
+        .write_str("Read CONTRIBUTING.md first.\n")
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: agents
+    kind: composite_agents_md
+    toc: true
+    sources:
+      - type: filesystem
+        root: {root}
+        path: python.md
+        order: 2
+        title: Python
+        shift_headings: 2
+      - type: filesystem
+        root: {root}
+        path: intro.md
+        order: 1
+        title: Getting Started
+"#,
+        root = partials.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    let content = std::fs::read_to_string(temp.child("AGENTS.md").path()).unwrap();
+    assert!(content
+        .contains("## Contents\n\n- [Getting Started](#getting-started)\n- [Python](#python)\n"));
+    let intro = content
+        .find("## Getting Started\n\nRead CONTRIBUTING.md")
+        .unwrap();
+    let python = content.find("## Python\n\nUse type hints.").unwrap();
+    assert!(intro < python);
+    assert!(content.contains("\n### Linting\n"));
+}
+
+#[test]
+fn sync_composite_includes_fragments_matching_when() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let partials = temp.child("partials");
+    partials
+        .child("base.md")
+        .write_str("Base rules.\n")
+        .unwrap();
+    partials
+        .child("docker.md")
+        .write_str("Docker rules.\n")
+        .unwrap();
+    partials
+        .child("python.md")
+        .write_str("Python rules.\n")
+        .unwrap();
+    partials.child("go.md").write_str("Go rules.\n").unwrap();
+
+    let project = temp.child("project");
+    project
+        .child("Dockerfile")
+        .write_str("FROM python")
+        .unwrap();
+    project.child("pyproject.toml").write_str("").unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: agents
+    kind: composite_agents_md
+    sources:
+      - type: filesystem
+        root: {root}
+        path: base.md
+      - type: filesystem
+        root: {root}
+        path: docker.md
+        when:
+          exists: Dockerfile
+      - type: filesystem
+        root: {root}
+        path: python.md
+        when:
+          lang: python
+      - type: filesystem
+        root: {root}
+        path: go.md
+        when:
+          lang: go
+"#,
+        root = partials.path().display()
+    );
+    project.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&project).assert().success();
+
+    project
+        .child("AGENTS.md")
+        .assert(predicate::str::contains("Base rules."))
+        .assert(predicate::str::contains("Docker rules."))
+        .assert(predicate::str::contains("Python rules."))
+        .assert(predicate::str::contains("Go rules.").not());
+}
+
+#[test]
+fn sync_composite_appends_local_fragment() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let partials = temp.child("partials");
+    partials.child("org.md").write_str("Org rules.\n").unwrap();
+
+    let project = temp.child("project");
+    let manifest = format!(
+        r#"entries:
+  - id: agents
+    kind: composite_agents_md
+    local: docs/agents-local.md
+    sources:
+      - type: filesystem
+        root: {}
+        path: org.md
+"#,
+        partials.path().display()
+    );
+    project.child("aps.yaml").write_str(&manifest).unwrap();
+
+    // A missing local fragment is fine
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&project)
+        .assert()
+        .success();
+    project
+        .child("AGENTS.md")
+        .assert(predicate::str::contains("Org rules."));
+
+    project
+        .child("docs/agents-local.md")
+        .write_str("Repo-specific rules.\n")
+        .unwrap();
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&project)
+        .assert()
+        .success();
+
+    let content = std::fs::read_to_string(project.child("AGENTS.md").path()).unwrap();
+    let org = content.find("Org rules.").unwrap();
+    let local = content.find("Repo-specific rules.").unwrap();
+    assert!(org < local);
+    project
+        .child("docs/agents-local.md")
+        .assert("Repo-specific rules.\n");
+}
+
+#[test]
+fn sync_composite_warns_and_dedupes_duplicate_sections() {
     let temp = assert_fs::TempDir::new().unwrap();
 
+    let safety = "## Safety\n\nNever run destructive commands like rm -rf without asking first.\n";
+    let partials = temp.child("partials");
+    partials
+        .child("docker.md")
+        .write_str(&format!(
+            "# Docker\n\nUse multi-stage builds.\n\n{}",
+            safety
+        ))
+        .unwrap();
+    partials
+        .child("ci.md")
+        .write_str(&format!("# CI\n\nKeep pipelines fast.\n\n{}", safety))
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: agents
+    kind: composite_agents_md
+    sources:
+      - type: filesystem
+        root: {root}
+        path: docker.md
+      - type: filesystem
+        root: {root}
+        path: ci.md
+"#,
+        root = partials.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
     aps()
-        .arg("validate")
+        .arg("sync")
         .current_dir(&temp)
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("Manifest not found"));
+        .success()
+        .stdout(predicate::str::contains("duplicates 'Safety' in docker.md"));
+    let content = std::fs::read_to_string(temp.child("AGENTS.md").path()).unwrap();
+    assert_eq!(content.matches("## Safety").count(), 2);
+
+    aps()
+        .args(["sync", "--yes", "--dedupe"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("removed 'Safety' from ci.md"));
+    let content = std::fs::read_to_string(temp.child("AGENTS.md").path()).unwrap();
+    assert_eq!(content.matches("## Safety").count(), 1);
+    assert!(content.contains("Keep pipelines fast."));
 }
 
 #[test]
-fn validate_empty_manifest_succeeds() {
+fn sync_composite_enforces_size_budget() {
     let temp = assert_fs::TempDir::new().unwrap();
 
-    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+    let partials = temp.child("partials");
+    partials.child("short.md").write_str("Be brief.\n").unwrap();
+    partials
+        .child("long.md")
+        .write_str(&"Lots of detail. ".repeat(100))
+        .unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: agents
+    kind: composite_agents_md
+    max_tokens: 200
+    sources:
+      - type: filesystem
+        root: {root}
+        path: short.md
+      - type: filesystem
+        root: {root}
+        path: long.md
+"#,
+        root = partials.path().display()
+    );
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
 
     aps()
-        .arg("validate")
+        .arg("sync")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("exceeds its size budget"))
+        .stderr(predicate::str::contains("long.md"));
+    temp.child("AGENTS.md").assert(predicate::path::missing());
+
+    let manifest = manifest.replace("max_tokens: 200", "max_tokens: 200\n    over_budget: warn");
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+    aps()
+        .arg("sync")
         .current_dir(&temp)
         .assert()
         .success()
-        .stdout(predicate::str::contains("valid"));
+        .stdout(predicate::str::contains("max_tokens: 200"));
+    temp.child("AGENTS.md").assert(predicate::path::exists());
 }
 
 #[test]
-fn validate_invalid_yaml_fails() {
+fn validate_file_kind_requires_dest() {
     let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: editorconfig
+    kind: file
+    source:
+      type: filesystem
+      root: .
+      path: .editorconfig
+"#,
+        )
+        .unwrap();
+
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("requires a 'dest' field"));
+}
 
+#[test]
+fn validate_rejects_git_dir_and_colliding_dests() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("shared/pre-commit")
+        .write_str("#!/bin/sh\n")
+        .unwrap();
     temp.child("aps.yaml")
-        .write_str("this is not: valid: yaml: [")
+        .write_str(
+            r#"entries:
+  - id: hook
+    kind: file
+    source:
+      type: filesystem
+      root: shared
+      path: pre-commit
+    dest: .git/hooks/pre-commit
+"#,
+        )
         .unwrap();
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is inside .git/"));
 
-    aps().arg("validate").current_dir(&temp).assert().failure();
+    temp.child("aps.yaml")
+        .write_str(
+            r#"entries:
+  - id: config
+    kind: dir
+    source:
+      type: filesystem
+      root: shared
+    dest: config
+  - id: hook
+    kind: file
+    source:
+      type: filesystem
+      root: shared
+      path: pre-commit
+    dest: config/hooks/pre-commit
+"#,
+        )
+        .unwrap();
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Entry 'hook' writes to"));
+}
+
+#[test]
+fn sync_with_symlink_creates_symlink() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    // Create source file
+    let source_dir = temp.child("source");
+    source_dir.create_dir_all().unwrap();
+    source_dir
+        .child("AGENTS.md")
+        .write_str("# Test Agents\n")
+        .unwrap();
+
+    // Create manifest with symlink enabled
+    let manifest = format!(
+        r#"entries:
+  - id: test-agents
+    kind: agents_md
+    source:
+      type: filesystem
+      root: {}
+      path: AGENTS.md
+      symlink: true
+    dest: ./AGENTS.md
+"#,
+        source_dir.path().display()
+    );
+
+    temp.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    // Verify symlink was created
+    let dest_path = temp.child("AGENTS.md");
+    dest_path.assert(predicate::path::exists());
+
+    // Check it's actually a symlink (on Unix)
+    #[cfg(unix)]
+    {
+        let metadata = std::fs::symlink_metadata(dest_path.path()).unwrap();
+        assert!(metadata.file_type().is_symlink());
+    }
 }
 
 // ============================================================================
-// Status Command Tests
+// MCP Config Tests
 // ============================================================================
 
+fn mcp_manifest(root: &std::path::Path) -> String {
+    format!(
+        r#"entries:
+  - id: mcp
+    kind: mcp_config
+    sources:
+      - type: filesystem
+        root: {root}
+        path: a.json
+      - type: filesystem
+        root: {root}
+        path: b.json
+"#,
+        root = root.display()
+    )
+}
+
 #[test]
-fn status_fails_without_manifest() {
+fn sync_mcp_config_merges_fragments() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("a.json")
+        .write_str(r#"{"mcpServers": {"github": {"command": "gh-mcp"}}}"#)
+        .unwrap();
+    source_dir
+        .child("b.json")
+        .write_str(
+            r#"{"mcpServers": {"github": {"command": "gh-mcp"}, "linear": {"url": "https://mcp.linear.app"}}}"#,
+        )
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(&mcp_manifest(source_dir.path()))
+        .unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    temp.child(".mcp.json")
+        .assert(predicate::str::contains("\"github\""))
+        .assert(predicate::str::contains("\"linear\""));
+}
+
+#[test]
+fn sync_mcp_config_fails_on_conflicting_servers() {
     let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("a.json")
+        .write_str(r#"{"mcpServers": {"github": {"command": "gh-mcp"}}}"#)
+        .unwrap();
+    source_dir
+        .child("b.json")
+        .write_str(r#"{"mcpServers": {"github": {"command": "other"}}}"#)
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(&mcp_manifest(source_dir.path()))
+        .unwrap();
 
     aps()
-        .arg("status")
+        .arg("sync")
         .current_dir(&temp)
         .assert()
         .failure()
-        .stderr(predicate::str::contains("Manifest not found"));
+        .stderr(predicate::str::contains("MCP server 'github'"));
+
+    temp.child(".mcp.json").assert(predicate::path::missing());
 }
 
 #[test]
-fn status_fails_without_lockfile() {
+fn sync_claude_settings_merges_permissions() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("base.yaml")
+        .write_str("allow: [\"Bash(git status)\"]\nask: [\"Bash(git push:*)\"]\nmodel: sonnet\n")
+        .unwrap();
+    source_dir
+        .child("team.yaml")
+        .write_str("permissions:\n  allow: [\"Bash(git status)\", Read]\n  deny: [\"Bash(rm:*)\"]\nenv:\n  CI: \"1\"\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(&format!(
+            r#"entries:
+  - id: settings
+    kind: claude_settings
+    sources:
+      - type: filesystem
+        root: {root}
+        path: base.yaml
+      - type: filesystem
+        root: {root}
+        path: team.yaml
+"#,
+            root = source_dir.path().display()
+        ))
+        .unwrap();
+
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    let settings: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(temp.child(".claude/settings.json").path()).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        settings["permissions"]["allow"],
+        serde_json::json!(["Bash(git status)", "Read"])
+    );
+    assert_eq!(
+        settings["permissions"]["ask"],
+        serde_json::json!(["Bash(git push:*)"])
+    );
+    assert_eq!(
+        settings["permissions"]["deny"],
+        serde_json::json!(["Bash(rm:*)"])
+    );
+    assert_eq!(settings["model"], "sonnet");
+    assert_eq!(settings["env"]["CI"], "1");
+}
+
+#[test]
+fn validate_render_prints_composed_entry() {
     let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("intro.md")
+        .write_str("Read CONTRIBUTING.md first.\n")
+        .unwrap();
+    source_dir
+        .child("base.yaml")
+        .write_str("allow: [Read]\nmodel: sonnet\n")
+        .unwrap();
+    source_dir
+        .child("team.yaml")
+        .write_str("allow: [\"Bash(git status)\"]\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(&format!(
+            r#"entries:
+  - id: agents
+    kind: composite_agents_md
+    sources:
+      - type: filesystem
+        root: {root}
+        path: intro.md
+        title: Getting Started
+  - id: settings
+    kind: claude_settings
+    sources:
+      - type: filesystem
+        root: {root}
+        path: base.yaml
+      - type: filesystem
+        root: {root}
+        path: team.yaml
+  - id: plain
+    kind: agents_md
+    source:
+      type: filesystem
+      root: {root}
+      path: intro.md
+"#,
+            root = source_dir.path().display()
+        ))
+        .unwrap();
+
+    aps()
+        .args(["validate", "--render", "agents"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "## Getting Started\n\nRead CONTRIBUTING.md first.\n",
+        ))
+        .stdout(predicate::str::contains("Validating manifest").not());
 
-    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+    let output = aps()
+        .args(["validate", "--render", "settings"])
+        .current_dir(&temp)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let settings: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        settings["permissions"]["allow"],
+        serde_json::json!(["Read", "Bash(git status)"])
+    );
+    assert_eq!(settings["model"], "sonnet");
 
     aps()
-        .arg("status")
+        .args(["validate", "--render", "plain"])
         .current_dir(&temp)
         .assert()
         .failure()
-        .stderr(predicate::str::contains("lockfile"));
+        .stderr(predicate::str::contains("not a composite entry"));
+
+    // Nothing is installed
+    temp.child("AGENTS.md").assert(predicate::path::missing());
+    temp.child(".claude").assert(predicate::path::missing());
 }
 
 #[test]
-fn status_works_after_sync() {
+fn sync_claude_settings_preserves_unmanaged_keys() {
     let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("base.yaml")
+        .write_str("allow: [Read]\nmodel: opus\n")
+        .unwrap();
+    temp.child(".claude/settings.json")
+        .write_str(
+            r#"{"statusLine": {"type": "command", "command": "status.sh"}, "model": "haiku"}"#,
+        )
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(&format!(
+            r#"entries:
+  - id: settings
+    kind: claude_settings
+    sources:
+      - type: filesystem
+        root: {}
+        path: base.yaml
+"#,
+            source_dir.path().display()
+        ))
+        .unwrap();
 
-    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
-
-    // First sync to create lockfile
     aps().arg("sync").current_dir(&temp).assert().success();
 
-    // Then status should work
-    aps().arg("status").current_dir(&temp).assert().success();
+    let settings: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(temp.child(".claude/settings.json").path()).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(settings["statusLine"]["command"], "status.sh");
+    assert_eq!(settings["model"], "opus");
+    assert_eq!(
+        settings["permissions"]["allow"],
+        serde_json::json!(["Read"])
+    );
 }
 
-// ============================================================================
-// Catalog Command Tests
-// ============================================================================
-
 #[test]
-fn catalog_generate_fails_without_manifest() {
+fn sync_claude_settings_removes_keys_dropped_from_fragments() {
     let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("base.yaml")
+        .write_str("allow: [Read, \"Bash(ls:*)\"]\nmodel: opus\n")
+        .unwrap();
+    temp.child(".claude/settings.json")
+        .write_str(r#"{"theme": "dark", "permissions": {"allow": ["Local"]}}"#)
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(&format!(
+            r#"entries:
+  - id: settings
+    kind: claude_settings
+    sources:
+      - type: filesystem
+        root: {}
+        path: base.yaml
+"#,
+            source_dir.path().display()
+        ))
+        .unwrap();
+    let settings = || -> serde_json::Value {
+        serde_json::from_str(
+            &std::fs::read_to_string(temp.child(".claude/settings.json").path()).unwrap(),
+        )
+        .unwrap()
+    };
 
-    aps()
-        .args(["catalog", "generate"])
-        .current_dir(&temp)
-        .assert()
-        .failure()
-        .stderr(predicate::str::contains("Manifest not found"));
+    aps().arg("sync").current_dir(&temp).assert().success();
+    assert_eq!(
+        settings()["permissions"]["allow"],
+        serde_json::json!(["Local", "Read", "Bash(ls:*)"])
+    );
+    assert_eq!(settings()["model"], "opus");
+
+    source_dir
+        .child("base.yaml")
+        .write_str("allow: [Read]\n")
+        .unwrap();
+    aps().arg("sync").current_dir(&temp).assert().success();
+
+    assert_eq!(
+        settings(),
+        serde_json::json!({"theme": "dark", "permissions": {"allow": ["Local", "Read"]}})
+    );
 }
 
 #[test]
-fn catalog_generate_creates_catalog_file() {
+fn sync_claude_settings_installs_hook_scripts() {
     let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("hooks.yaml")
+        .write_str(
+            "hooks:\n  PreToolUse:\n    - matcher: Bash\n      hooks:\n        - type: command\n          command: .claude/hooks/check-bash.sh\n",
+        )
+        .unwrap();
+    source_dir
+        .child("hooks/check-bash.sh")
+        .write_str("#!/bin/sh\nexit 0\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(&format!(
+            r#"entries:
+  - id: settings
+    kind: claude_settings
+    sources:
+      - type: filesystem
+        root: {}
+        path: hooks.yaml
+"#,
+            source_dir.path().display()
+        ))
+        .unwrap();
 
-    temp.child("aps.yaml").write_str("entries: []\n").unwrap();
+    aps().arg("sync").current_dir(&temp).assert().success();
 
-    aps()
-        .args(["catalog", "generate"])
-        .current_dir(&temp)
-        .assert()
-        .success();
+    temp.child(".claude/settings.json")
+        .assert(predicate::str::contains("\"PreToolUse\""))
+        .assert(predicate::str::contains(".claude/hooks/check-bash.sh"));
+    temp.child(".claude/hooks/check-bash.sh")
+        .assert(predicate::str::contains("exit 0"));
 
-    temp.child("aps.catalog.yaml")
-        .assert(predicate::path::exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(temp.child(".claude/hooks/check-bash.sh").path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_ne!(mode & 0o111, 0);
+    }
 }
 
-// ============================================================================
-// Filesystem Source Tests
-// ============================================================================
-
 #[test]
-fn sync_filesystem_source_copies_file() {
+fn sync_explain_permissions_reports_provenance() {
     let temp = assert_fs::TempDir::new().unwrap();
-
-    // Create source file
     let source_dir = temp.child("source");
-    source_dir.create_dir_all().unwrap();
     source_dir
-        .child("AGENTS.md")
-        .write_str("# Test Agents\n")
+        .child("dev.yaml")
+        .write_str("allow: [Read, \"Bash(rm -rf build)\"]\n")
         .unwrap();
-
-    // Create manifest pointing to local file
-    let manifest = format!(
-        r#"entries:
-  - id: test-agents
-    kind: agents_md
-    source:
-      type: filesystem
-      root: {}
-      path: AGENTS.md
-    dest: ./AGENTS.md
+    source_dir
+        .child("security.yaml")
+        .write_str("deny: [\"Bash(rm:*)\"]\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(&format!(
+            r#"entries:
+  - id: settings
+    kind: claude_settings
+    sources:
+      - type: filesystem
+        root: {root}
+        path: dev.yaml
+      - type: filesystem
+        root: {root}
+        path: security.yaml
 "#,
-        source_dir.path().display()
-    );
-
-    temp.child("aps.yaml").write_str(&manifest).unwrap();
-
-    aps().arg("sync").current_dir(&temp).assert().success();
+            root = source_dir.path().display()
+        ))
+        .unwrap();
 
-    // Verify file was copied
-    temp.child("AGENTS.md")
-        .assert(predicate::str::contains("# Test Agents"));
+    aps()
+        .args(["sync", "--explain-permissions"])
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Permissions for 'settings':"))
+        .stdout(predicate::str::contains("Bash(rm:*)          security.yaml"))
+        .stdout(predicate::str::contains(
+            "'Bash(rm -rf build)' in allow (dev.yaml) is blocked by deny 'Bash(rm:*)' (security.yaml)",
+        ));
 }
 
 #[test]
-fn sync_with_symlink_creates_symlink() {
+fn sync_claude_settings_error_on_conflict_strategy() {
     let temp = assert_fs::TempDir::new().unwrap();
-
-    // Create source file
     let source_dir = temp.child("source");
-    source_dir.create_dir_all().unwrap();
     source_dir
-        .child("AGENTS.md")
-        .write_str("# Test Agents\n")
+        .child("base.yaml")
+        .write_str("model: opus\n")
         .unwrap();
-
-    // Create manifest with symlink enabled
-    let manifest = format!(
-        r#"entries:
-  - id: test-agents
-    kind: agents_md
-    source:
-      type: filesystem
-      root: {}
-      path: AGENTS.md
-      symlink: true
-    dest: ./AGENTS.md
+    source_dir
+        .child("team.yaml")
+        .write_str("model: sonnet\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(&format!(
+            r#"entries:
+  - id: settings
+    kind: claude_settings
+    merge:
+      model: error_on_conflict
+    sources:
+      - type: filesystem
+        root: {root}
+        path: base.yaml
+      - type: filesystem
+        root: {root}
+        path: team.yaml
 "#,
-        source_dir.path().display()
-    );
-
-    temp.child("aps.yaml").write_str(&manifest).unwrap();
-
-    aps().arg("sync").current_dir(&temp).assert().success();
+            root = source_dir.path().display()
+        ))
+        .unwrap();
 
-    // Verify symlink was created
-    let dest_path = temp.child("AGENTS.md");
-    dest_path.assert(predicate::path::exists());
+    aps()
+        .arg("sync")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Merge conflict on 'model'"));
 
-    // Check it's actually a symlink (on Unix)
-    #[cfg(unix)]
-    {
-        let metadata = std::fs::symlink_metadata(dest_path.path()).unwrap();
-        assert!(metadata.file_type().is_symlink());
-    }
+    temp.child(".claude/settings.json")
+        .assert(predicate::path::missing());
 }
 
 // ============================================================================
@@ -445,6 +5084,164 @@ fn sync_cursor_hooks_copies_directory_and_sets_exec() {
     }
 }
 
+#[test]
+fn validate_flags_malformed_permission_patterns() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("base.yaml")
+        .write_str("allow: [Read, \"Bash(git push*\"]\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(&format!(
+            r#"entries:
+  - id: settings
+    kind: claude_settings
+    sources:
+      - type: filesystem
+        root: {}
+        path: base.yaml
+"#,
+            source_dir.path().display()
+        ))
+        .unwrap();
+
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "invalid permission 'Bash(git push*' in allow of base.yaml: missing closing ')'",
+        ));
+
+    aps()
+        .args(["validate", "--strict"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Bash(git push*"));
+}
+
+#[test]
+fn validate_checks_composed_claude_settings() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_dir = temp.child("source");
+    source_dir
+        .child("base.yaml")
+        .write_str("allow: [Read]\nmodle: sonnet\n")
+        .unwrap();
+    source_dir
+        .child("team.yaml")
+        .write_str("permissions:\n  defaultMode: yolo\ncleanupPeriodDays: 30\n")
+        .unwrap();
+    temp.child("aps.yaml")
+        .write_str(&format!(
+            r#"entries:
+  - id: settings
+    kind: claude_settings
+    sources:
+      - type: filesystem
+        root: {root}
+        path: base.yaml
+      - type: filesystem
+        root: {root}
+        path: team.yaml
+"#,
+            root = source_dir.path().display()
+        ))
+        .unwrap();
+
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Composed settings: Additional properties are not allowed ('modle' was unexpected)",
+        ))
+        .stdout(predicate::str::contains(
+            "Composed settings: permissions.defaultMode: \"yolo\" is not one of",
+        ));
+
+    aps()
+        .args(["validate", "--strict"])
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "aps::claude_settings::invalid_settings",
+        ));
+}
+
+#[test]
+fn validate_allow_warnings_and_max_warnings() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let skills = temp.child("source/skills");
+    skills.child("draft/notes.md").write_str("wip\n").unwrap();
+    skills.child("other/notes.md").write_str("wip\n").unwrap();
+    let manifest = |allow: &str, max: &str| {
+        format!(
+            r#"{max}
+entries:
+  - id: drafts
+    kind: agent_skill
+    source:
+      type: filesystem
+      root: {root}
+      path: draft
+{allow}
+  - id: others
+    kind: agent_skill
+    source:
+      type: filesystem
+      root: {root}
+      path: other
+    allow_warnings: [missing_skill_md]
+"#,
+            root = skills.path().display()
+        )
+    };
+
+    // Only the warning that isn't allowed shows, and fails --strict
+    temp.child("aps.yaml").write_str(&manifest("", "")).unwrap();
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skill 'draft'"))
+        .stdout(predicate::str::contains("Skill 'other'").not())
+        .stdout(predicate::str::contains("1 allowed warning(s) hidden"))
+        .stdout(predicate::str::contains("valid with 1 warning(s)"));
+    aps()
+        .args(["validate", "--strict"])
+        .current_dir(&temp)
+        .assert()
+        .failure();
+
+    // Allowing it too makes --strict pass
+    temp.child("aps.yaml")
+        .write_str(&manifest("    allow_warnings: [missing_skill_md]", ""))
+        .unwrap();
+    aps()
+        .args(["validate", "--strict"])
+        .current_dir(&temp)
+        .assert()
+        .success();
+
+    // More warnings than max_warnings fail
+    temp.child("aps.yaml")
+        .write_str(&manifest("", "max_warnings: 0"))
+        .unwrap();
+    aps()
+        .arg("validate")
+        .current_dir(&temp)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("aps::validate::too_many_warnings"));
+}
+
 #[test]
 fn validate_cursor_hooks_strict_rejects_missing_config() {
     let temp = assert_fs::TempDir::new().unwrap();
@@ -739,6 +5536,297 @@ fn sync_without_upgrade_respects_locked_commit() {
         .assert(predicate::str::contains("Version 2").not());
 }
 
+#[test]
+fn sync_reinstalls_locked_entry_when_its_options_change() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_repo = temp.child("source-repo");
+    source_repo.create_dir_all().unwrap();
+    create_git_repo_with_agents_md(source_repo.path(), "---\nowner: acme\n---\n# Version 1\n");
+
+    let project = temp.child("project");
+    project.create_dir_all().unwrap();
+    let manifest = format!(
+        r#"entries:
+  - id: test-agents
+    kind: agents_md
+    source:
+      type: git
+      repo: {}
+      ref: main
+      shallow: false
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        source_repo.path().display()
+    );
+    project.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&project).assert().success();
+    project
+        .child("AGENTS.md")
+        .assert(predicate::str::contains("owner: acme"));
+
+    // A new upstream commit stays out; the edited transforms apply to the
+    // locked commit
+    update_agents_md_in_repo(source_repo.path(), "# Version 2\n");
+    project
+        .child("aps.yaml")
+        .write_str(&format!(
+            "{}    transforms:\n      - strip_frontmatter\n",
+            manifest
+        ))
+        .unwrap();
+
+    aps()
+        .args(["sync", "--yes"])
+        .current_dir(&project)
+        .assert()
+        .success();
+    project
+        .child("AGENTS.md")
+        .assert(predicate::str::contains("owner: acme").not())
+        .assert(predicate::str::contains("# Version 1"));
+
+    // Once applied, the entry is left alone until upgraded
+    let output = aps()
+        .args(["sync", "--format", "json"])
+        .current_dir(&project)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["entries"][0]["action"], "upgradable");
+}
+
+#[test]
+fn status_shows_locked_commit_date_and_subject() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_repo = temp.child("source-repo");
+    source_repo.create_dir_all().unwrap();
+    create_git_repo_with_agents_md(source_repo.path(), "# Version 1\n");
+    std::fs::write(source_repo.path().join("AGENTS.md"), "# Version 2\n").unwrap();
+    git(source_repo.path())
+        .args(["commit", "--no-gpg-sign", "-am", "tighten bash permissions"])
+        .output()
+        .unwrap();
+
+    let project = temp.child("project");
+    project.create_dir_all().unwrap();
+    let manifest = format!(
+        r#"entries:
+  - id: test-agents
+    kind: agents_md
+    source:
+      type: git
+      repo: {}
+      ref: main
+      shallow: false
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        source_repo.path().display()
+    );
+    project.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&project).assert().success();
+    project
+        .child("aps.lock.yaml")
+        .assert(predicate::str::contains(
+            "subject: tighten bash permissions",
+        ))
+        .assert(predicate::str::contains("author: Test User"));
+
+    aps()
+        .arg("status")
+        .current_dir(&project)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r"Commit: +[0-9a-f]{40} \(\d{4}-\d{2}-\d{2}: tighten bash permissions\)",
+            )
+            .unwrap(),
+        );
+}
+
+#[test]
+fn status_separates_local_edits_from_upgrades() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_repo = temp.child("source-repo");
+    source_repo.create_dir_all().unwrap();
+    create_git_repo_with_agents_md(source_repo.path(), "# Version 1\n");
+
+    let project = temp.child("project");
+    project.create_dir_all().unwrap();
+    let manifest = format!(
+        r#"entries:
+  - id: test-agents
+    kind: agents_md
+    source:
+      type: git
+      repo: {}
+      ref: main
+      shallow: false
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        source_repo.path().display()
+    );
+    project.child("aps.yaml").write_str(&manifest).unwrap();
+    aps().arg("sync").current_dir(&project).assert().success();
+
+    aps()
+        .arg("status")
+        .current_dir(&project)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Installed files match the lockfile and no upgrades are available.",
+        ));
+
+    update_agents_md_in_repo(source_repo.path(), "# Version 2\n");
+    project.child("AGENTS.md").write_str("# Edited\n").unwrap();
+
+    aps()
+        .arg("status")
+        .current_dir(&project)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[MODIFIED LOCALLY] test-agents (AGENTS.md)",
+        ))
+        .stdout(predicate::str::contains(
+            "[UPGRADE AVAILABLE] test-agents (",
+        ));
+
+    aps()
+        .args(["status", "--offline"])
+        .current_dir(&project)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[UPGRADE AVAILABLE]").not())
+        .stdout(predicate::str::contains(
+            "Upstream sources were not checked for upgrades.",
+        ));
+}
+
+#[test]
+fn status_check_exit_codes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_repo = temp.child("source-repo");
+    source_repo.create_dir_all().unwrap();
+    create_git_repo_with_agents_md(source_repo.path(), "# Version 1\n");
+
+    let project = temp.child("project");
+    project.create_dir_all().unwrap();
+    let manifest = format!(
+        r#"entries:
+  - id: test-agents
+    kind: agents_md
+    source:
+      type: git
+      repo: {}
+      ref: main
+      shallow: false
+      path: AGENTS.md
+    dest: ./AGENTS.md
+"#,
+        source_repo.path().display()
+    );
+    project.child("aps.yaml").write_str(&manifest).unwrap();
+
+    let check = || {
+        aps()
+            .args(["status", "--check"])
+            .current_dir(&project)
+            .assert()
+    };
+
+    check()
+        .code(4)
+        .stderr(predicate::str::contains("no lockfile"));
+
+    aps().arg("sync").current_dir(&project).assert().success();
+    check().success();
+
+    update_agents_md_in_repo(source_repo.path(), "# Version 2\n");
+    check()
+        .code(3)
+        .stderr(predicate::str::contains("upgrades available: test-agents"));
+
+    project.child("AGENTS.md").write_str("# Edited\n").unwrap();
+    check()
+        .code(2)
+        .stderr(predicate::str::contains("modified locally: test-agents"));
+
+    project
+        .child("aps.yaml")
+        .write_str(&manifest.replace("test-agents", "renamed"))
+        .unwrap();
+    check()
+        .code(4)
+        .stderr(predicate::str::contains("'renamed' is not in the lockfile"));
+}
+
+#[test]
+fn validate_checks_git_refs_without_cloning_unless_deep() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source_repo = temp.child("source-repo");
+    source_repo.create_dir_all().unwrap();
+    create_git_repo_with_agents_md(source_repo.path(), "# Version 1\n");
+
+    let project = temp.child("project");
+    project.create_dir_all().unwrap();
+    let manifest = |git_ref: &str, path: &str| {
+        format!(
+            r#"entries:
+  - id: test-agents
+    kind: agents_md
+    source:
+      type: git
+      repo: {}
+      ref: {}
+      path: {}
+    dest: ./AGENTS.md
+"#,
+            source_repo.path().display(),
+            git_ref,
+            path
+        )
+    };
+
+    // A missing path is only found by cloning
+    project
+        .child("aps.yaml")
+        .write_str(&manifest("main", "MISSING.md"))
+        .unwrap();
+    aps()
+        .args(["validate", "--strict"])
+        .current_dir(&project)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[OK] test-agents"))
+        .stdout(predicate::str::contains("run with --deep"));
+    aps()
+        .args(["validate", "--strict", "--deep"])
+        .current_dir(&project)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("[FAILED] test-agents"));
+
+    // A missing ref is found either way
+    project
+        .child("aps.yaml")
+        .write_str(&manifest("no-such-branch", "AGENTS.md"))
+        .unwrap();
+    aps()
+        .arg("validate")
+        .current_dir(&project)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Git ref not found"));
+}
+
 #[test]
 fn sync_with_upgrade_fetches_latest_version() {
     let temp = assert_fs::TempDir::new().unwrap();
@@ -841,6 +5929,64 @@ fn sync_shows_upgrade_available_status() {
         );
 }
 
+#[test]
+fn sync_keeps_composite_git_fragments_at_locked_commits() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let source_repo = temp.child("source-repo");
+    source_repo.create_dir_all().unwrap();
+    create_git_repo_with_agents_md(source_repo.path(), "# Shared v1\n");
+
+    let project = temp.child("project");
+    project.create_dir_all().unwrap();
+    project.child("local.md").write_str("# Local\n").unwrap();
+
+    let manifest = format!(
+        r#"entries:
+  - id: composite
+    kind: composite_agents_md
+    sources:
+      - type: git
+        repo: {}
+        ref: main
+        shallow: false
+        path: AGENTS.md
+      - type: filesystem
+        root: .
+        path: local.md
+    dest: ./AGENTS.md
+"#,
+        source_repo.path().display()
+    );
+    project.child("aps.yaml").write_str(&manifest).unwrap();
+
+    aps().arg("sync").current_dir(&project).assert().success();
+    project
+        .child("aps.lock.yaml")
+        .assert(predicate::str::contains("fragments:"));
+
+    // A new upstream commit is reported but not installed
+    update_agents_md_in_repo(source_repo.path(), "# Shared v2\n");
+    aps()
+        .arg("sync")
+        .current_dir(&project)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("upgrade available"));
+    project
+        .child("AGENTS.md")
+        .assert(predicate::str::contains("Shared v1"));
+
+    aps()
+        .args(["sync", "--upgrade", "--yes"])
+        .current_dir(&project)
+        .assert()
+        .success();
+    project
+        .child("AGENTS.md")
+        .assert(predicate::str::contains("Shared v2"));
+}
+
 // ============================================================================
 // Composite Agents MD Tests (Live Git Sources)
 // ============================================================================
@@ -969,13 +6115,16 @@ fn sync_composite_agents_md_respects_locked_version() {
         .unwrap()
         .to_string();
 
-    // Second sync should show [current] (no changes)
+    // Second sync should report the entry as current (no changes)
     aps()
         .arg("sync")
         .current_dir(&temp)
         .assert()
         .success()
-        .stdout(predicate::str::contains("[current]"));
+        .stdout(
+            predicate::str::is_match(r"(?m)^\s+\S+ composite-test\s+AGENTS\.md\s+current\s")
+                .unwrap(),
+        );
 
     // Verify checksum hasn't changed
     let lockfile_content_after =
@@ -1562,6 +6711,24 @@ fn add_local_path_discovers_skills_with_all() {
     manifest.assert(predicate::str::contains("symlink: true"));
 }
 
+#[test]
+fn add_local_dir_without_a_terminal_needs_all() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source = temp.child("skills-source");
+    source.create_dir_all().unwrap();
+    create_skills_dir(source.path());
+    let project = temp.child("project");
+    project.create_dir_all().unwrap();
+
+    aps()
+        .args(["add", &source.path().display().to_string(), "--no-sync"])
+        .current_dir(&project)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --all to add every skill"));
+    project.child("aps.yaml").assert(predicate::path::missing());
+}
+
 #[test]
 fn add_local_single_skill_with_skill_md() {
     let temp = assert_fs::TempDir::new().unwrap();